@@ -22,8 +22,12 @@ fn main() -> anyhow::Result<()> {
         writeln!(out, "#[cfg(test)]")?;
         writeln!(out, "#[allow(non_snake_case)]")?;
         if *strategy == "Winch" {
-            // We only test Winch on x86_64, for now.
-            writeln!(out, "{}", "#[cfg(all(target_arch = \"x86_64\"))]")?;
+            // We only test Winch on x86_64 and aarch64, for now.
+            writeln!(
+                out,
+                "{}",
+                "#[cfg(any(target_arch = \"x86_64\", target_arch = \"aarch64\"))]"
+            )?;
         }
         writeln!(out, "mod {} {{", strategy)?;
 
@@ -214,6 +218,14 @@ fn ignore(testsuite: &str, testname: &str, strategy: &str) -> bool {
             "table_copy",
             "table_set",
             "table_get",
+            "load",
+            "store",
+            "address",
+            "conversions",
+            "block",
+            "loop",
+            "if",
+            "br_table",
         ]
         .contains(&testname);
 
@@ -228,8 +240,31 @@ fn ignore(testsuite: &str, testname: &str, strategy: &str) -> bool {
             }
         }
         if testsuite == "spec_testsuite" {
-            // The official table init and table copy tests are now supported.
-            return !["table_init", "table_copy"].contains(&testname);
+            // SIMD is supported on x64, but the tests that assert for
+            // traps on out of bounds memory accesses are ignored on
+            // Windows, like above.
+            if testname.starts_with("simd_") {
+                let simd_assert_trap = testname == "simd_address"
+                    || testname.starts_with("simd_load")
+                    || testname.starts_with("simd_store");
+                return env::var("CARGO_CFG_TARGET_ARCH").unwrap() != "x86_64"
+                    || (simd_assert_trap
+                        && env::var("CARGO_CFG_TARGET_OS").unwrap().as_str() == "windows");
+            }
+            // The official table init, table copy, address and conversions
+            // tests are now supported, as well as the control flow tests
+            // which exercise multi-value blocks.
+            return ![
+                "table_init",
+                "table_copy",
+                "address",
+                "conversions",
+                "block",
+                "loop",
+                "if",
+                "br_table",
+            ]
+            .contains(&testname);
         }
 
         if testsuite != "winch" {
@@ -196,6 +196,7 @@
 #include <wasmtime/trap.h>
 #include <wasmtime/val.h>
 #include <wasmtime/async.h>
+#include <wasmtime/component.h>
 
 /**
  * \brief Wasmtime version string.
@@ -0,0 +1,677 @@
+/**
+ * \file wasmtime/component.h
+ *
+ * APIs for interacting with components in Wasmtime
+ *
+ * This header mirrors the `wasmtime::component` module of the Rust API and
+ * covers compiling components, defining host functions in a
+ * #wasmtime_component_linker_t, instantiating components and calling their
+ * exported functions with #wasmtime_component_val_t values.
+ *
+ * These APIs are only available when the C API is built with the
+ * `component-model` Cargo feature enabled.
+ *
+ * Headers with typed wrappers around these APIs for the imports and exports
+ * of a WIT world can be generated with the `generate_c_header` function of
+ * the `wasmtime-wit-bindgen` crate.
+ */
+
+#ifndef WASMTIME_COMPONENT_H
+#define WASMTIME_COMPONENT_H
+
+#include <wasm.h>
+#include <wasmtime/error.h>
+#include <wasmtime/store.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/**
+ * \typedef wasmtime_component_t
+ * \brief Convenience alias for #wasmtime_component
+ *
+ * \struct wasmtime_component
+ * \brief A compiled Wasmtime component.
+ *
+ * This type represents a compiled WebAssembly component which is ready to be
+ * instantiated. It is safe to use a component across multiple threads
+ * simultaneously.
+ */
+typedef struct wasmtime_component wasmtime_component_t;
+
+/**
+ * \brief Compiles a WebAssembly component binary into a #wasmtime_component_t
+ *
+ * On success the returned #wasmtime_error_t is `NULL` and the `ret` pointer is
+ * filled in with a #wasmtime_component_t. On failure the #wasmtime_error_t is
+ * non-`NULL` and the `ret` pointer is unmodified.
+ *
+ * This function does not take ownership of any of its arguments, but the
+ * returned error and component are owned by the caller.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_new(
+    wasm_engine_t *engine,
+    const uint8_t *wasm,
+    size_t wasm_len,
+    wasmtime_component_t **ret
+);
+
+/**
+ * \brief Deletes a component.
+ */
+WASM_API_EXTERN void wasmtime_component_delete(wasmtime_component_t *c);
+
+/**
+ * \brief Creates a shallow clone of the specified component, increasing the
+ * internal reference count.
+ */
+WASM_API_EXTERN wasmtime_component_t *wasmtime_component_clone(wasmtime_component_t *c);
+
+/**
+ * \brief Serializes the compiled component artifact.
+ *
+ * This is the same as #wasmtime_module_serialize except for components.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_serialize(
+    const wasmtime_component_t *component,
+    wasm_byte_vec_t *ret
+);
+
+/**
+ * \brief Build a component from serialized data.
+ *
+ * This is the same as #wasmtime_module_deserialize except for components, and
+ * the same safety caveats apply: only trusted artifacts produced by
+ * #wasmtime_component_serialize may be passed to this function.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_deserialize(
+    wasm_engine_t *engine,
+    const uint8_t *bytes,
+    size_t bytes_len,
+    wasmtime_component_t **ret
+);
+
+/// \brief Discriminant used in #wasmtime_component_val_t::kind
+typedef uint8_t wasmtime_component_valkind_t;
+
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `bool`
+#define WASMTIME_COMPONENT_BOOL 0
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `s8`
+#define WASMTIME_COMPONENT_S8 1
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `u8`
+#define WASMTIME_COMPONENT_U8 2
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `s16`
+#define WASMTIME_COMPONENT_S16 3
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `u16`
+#define WASMTIME_COMPONENT_U16 4
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `s32`
+#define WASMTIME_COMPONENT_S32 5
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `u32`
+#define WASMTIME_COMPONENT_U32 6
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `s64`
+#define WASMTIME_COMPONENT_S64 7
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `u64`
+#define WASMTIME_COMPONENT_U64 8
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `float32`
+#define WASMTIME_COMPONENT_FLOAT32 9
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `float64`
+#define WASMTIME_COMPONENT_FLOAT64 10
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `char`
+#define WASMTIME_COMPONENT_CHAR 11
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `string`
+#define WASMTIME_COMPONENT_STRING 12
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `list`
+#define WASMTIME_COMPONENT_LIST 13
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `record`
+#define WASMTIME_COMPONENT_RECORD 14
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `tuple`
+#define WASMTIME_COMPONENT_TUPLE 15
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `variant`
+#define WASMTIME_COMPONENT_VARIANT 16
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is an `enum`
+#define WASMTIME_COMPONENT_ENUM 17
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is an `option`
+#define WASMTIME_COMPONENT_OPTION 18
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `result`
+#define WASMTIME_COMPONENT_RESULT 19
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is a `flags`
+#define WASMTIME_COMPONENT_FLAGS 20
+/// \brief Value of #wasmtime_component_valkind_t meaning that
+/// #wasmtime_component_val_t is an `own` or `borrow` resource handle
+#define WASMTIME_COMPONENT_RESOURCE 21
+
+/// \brief Forward declaration, see #wasmtime_component_val
+typedef struct wasmtime_component_val wasmtime_component_val_t;
+/// \brief Forward declaration, see #wasmtime_component_val_record_field
+typedef struct wasmtime_component_val_record_field wasmtime_component_val_record_field_t;
+
+/**
+ * \typedef wasmtime_component_resource_any_t
+ * \brief Convenience alias for #wasmtime_component_resource_any
+ *
+ * \struct wasmtime_component_resource_any
+ * \brief An owned handle to a resource of any type, either `own` or `borrow`.
+ *
+ * Resources owned by the host must be disposed of with
+ * #wasmtime_component_resource_any_drop once they're no longer needed.
+ */
+typedef struct wasmtime_component_resource_any wasmtime_component_resource_any_t;
+
+/**
+ * \brief A vector of #wasmtime_component_val_t, used for `list` and `tuple`
+ * values.
+ *
+ * Vectors must be created with one of the `wasmtime_component_val_vec_new*`
+ * functions as they're deallocated by Wasmtime.
+ */
+typedef struct wasmtime_component_val_vec {
+  /// Number of values in `data`.
+  size_t size;
+  /// Pointer to the values.
+  wasmtime_component_val_t *data;
+} wasmtime_component_val_vec_t;
+
+/**
+ * \brief A vector of #wasmtime_component_val_record_field_t, used for
+ * `record` values.
+ *
+ * Fields must be listed in the same order as the record's type declares them.
+ */
+typedef struct wasmtime_component_val_record {
+  /// Number of fields in `data`.
+  size_t size;
+  /// Pointer to the fields.
+  wasmtime_component_val_record_field_t *data;
+} wasmtime_component_val_record_t;
+
+/**
+ * \brief A vector of names, used for `flags` values to list the flags which
+ * are set.
+ */
+typedef struct wasmtime_component_val_flags {
+  /// Number of flags in `data`.
+  size_t size;
+  /// Pointer to the names of the flags.
+  wasm_name_t *data;
+} wasmtime_component_val_flags_t;
+
+/// \brief Representation of a `variant` value.
+typedef struct wasmtime_component_val_variant {
+  /// The name of the case of this variant.
+  wasm_name_t discriminant;
+  /// The payload of this case, or `NULL` if the case has no payload.
+  wasmtime_component_val_t *val;
+} wasmtime_component_val_variant_t;
+
+/// \brief Representation of a `result` value.
+typedef struct wasmtime_component_val_result {
+  /// Whether this is the `ok` case of the result, otherwise it's `err`.
+  bool is_ok;
+  /// The payload of this case, or `NULL` if the case has no payload.
+  wasmtime_component_val_t *val;
+} wasmtime_component_val_result_t;
+
+/**
+ * \typedef wasmtime_component_valunion_t
+ * \brief Convenience alias for #wasmtime_component_valunion
+ *
+ * \union wasmtime_component_valunion
+ * \brief Container for different kinds of component values
+ *
+ * The active field of this union is determined by
+ * #wasmtime_component_val_t::kind.
+ */
+typedef union wasmtime_component_valunion {
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_BOOL
+  bool boolean;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S8
+  int8_t s8;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U8
+  uint8_t u8;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S16
+  int16_t s16;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U16
+  uint16_t u16;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S32
+  int32_t s32;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U32
+  uint32_t u32;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_S64
+  int64_t s64;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_U64
+  uint64_t u64;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_FLOAT32
+  float float32;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_FLOAT64
+  double float64;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_CHAR,
+  /// which must be a valid unicode scalar value.
+  uint32_t character;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_STRING,
+  /// which must be valid utf-8.
+  wasm_name_t string;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_LIST
+  wasmtime_component_val_vec_t list;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_RECORD
+  wasmtime_component_val_record_t record;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_TUPLE
+  wasmtime_component_val_vec_t tuple;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_VARIANT
+  wasmtime_component_val_variant_t variant;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_ENUM,
+  /// the name of the enum's case.
+  wasm_name_t enumeration;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_OPTION,
+  /// `NULL` for `none`.
+  wasmtime_component_val_t *option;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_RESULT
+  wasmtime_component_val_result_t result;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_FLAGS
+  wasmtime_component_val_flags_t flags;
+  /// Field used if #wasmtime_component_val_t::kind is #WASMTIME_COMPONENT_RESOURCE
+  wasmtime_component_resource_any_t *resource;
+} wasmtime_component_valunion_t;
+
+/**
+ * \typedef wasmtime_component_val_t
+ * \brief Convenience alias for #wasmtime_component_val
+ *
+ * \struct wasmtime_component_val
+ * \brief Container for a component model value.
+ *
+ * Component values own all of the memory they point to, such as strings,
+ * lists and boxed payloads of options, variants and results. This memory is
+ * managed by Wasmtime, so it must be allocated with functions such as
+ * #wasm_name_new, #wasmtime_component_val_vec_new or
+ * #wasmtime_component_val_new, and values are released with
+ * #wasmtime_component_val_delete.
+ *
+ * Compound values are converted to their Rust representation using the type
+ * that Wasmtime expects at the location they're passed to, so for example the
+ * fields of a record must be listed in the same order as its type.
+ */
+struct wasmtime_component_val {
+  /// Discriminant of which field of #of is valid.
+  wasmtime_component_valkind_t kind;
+  /// Container for the component value.
+  wasmtime_component_valunion_t of;
+};
+
+/// \brief A field of a `record` value.
+struct wasmtime_component_val_record_field {
+  /// The name of the field.
+  wasm_name_t name;
+  /// The value of the field.
+  wasmtime_component_val_t val;
+};
+
+/**
+ * \brief Allocates a new #wasmtime_component_val_t on the heap, initialized
+ * to `false`.
+ *
+ * This is used to create the payloads of options, variants and results. The
+ * returned value is owned by the containing value once it's stored there, or
+ * it must otherwise be released with #wasmtime_component_val_free.
+ */
+WASM_API_EXTERN wasmtime_component_val_t *wasmtime_component_val_new(void);
+
+/**
+ * \brief Deallocates a heap-allocated value created with
+ * #wasmtime_component_val_new, including everything it owns.
+ */
+WASM_API_EXTERN void wasmtime_component_val_free(wasmtime_component_val_t *val);
+
+/**
+ * \brief Deallocates everything owned by the value provided, but not the value
+ * itself.
+ *
+ * This is used to release results of #wasmtime_component_func_call.
+ */
+WASM_API_EXTERN void wasmtime_component_val_delete(wasmtime_component_val_t *val);
+
+/**
+ * \brief Performs a deep copy of the `src` value into `dst`.
+ */
+WASM_API_EXTERN void wasmtime_component_val_copy(
+    wasmtime_component_val_t *dst,
+    const wasmtime_component_val_t *src
+);
+
+/// \brief Creates an empty vector of values.
+WASM_API_EXTERN void wasmtime_component_val_vec_new_empty(wasmtime_component_val_vec_t *out);
+/// \brief Creates a vector of `size` values, all initialized to `false`.
+WASM_API_EXTERN void wasmtime_component_val_vec_new_uninitialized(
+    wasmtime_component_val_vec_t *out, size_t size);
+/// \brief Creates a vector of values, taking ownership of the `size` values in
+/// `ptr`.
+WASM_API_EXTERN void wasmtime_component_val_vec_new(
+    wasmtime_component_val_vec_t *out, size_t size,
+    const wasmtime_component_val_t *ptr);
+/// \brief Performs a deep copy of a vector of values.
+WASM_API_EXTERN void wasmtime_component_val_vec_copy(
+    wasmtime_component_val_vec_t *out, const wasmtime_component_val_vec_t *src);
+/// \brief Deallocates a vector of values along with the values within.
+WASM_API_EXTERN void wasmtime_component_val_vec_delete(wasmtime_component_val_vec_t *vec);
+
+/// \brief Creates an empty vector of record fields.
+WASM_API_EXTERN void wasmtime_component_val_record_new_empty(
+    wasmtime_component_val_record_t *out);
+/// \brief Creates a vector of `size` record fields with empty names and values
+/// initialized to `false`.
+WASM_API_EXTERN void wasmtime_component_val_record_new_uninitialized(
+    wasmtime_component_val_record_t *out, size_t size);
+/// \brief Creates a vector of record fields, taking ownership of the `size`
+/// fields in `ptr`.
+WASM_API_EXTERN void wasmtime_component_val_record_new(
+    wasmtime_component_val_record_t *out, size_t size,
+    const wasmtime_component_val_record_field_t *ptr);
+/// \brief Performs a deep copy of a vector of record fields.
+WASM_API_EXTERN void wasmtime_component_val_record_copy(
+    wasmtime_component_val_record_t *out,
+    const wasmtime_component_val_record_t *src);
+/// \brief Deallocates a vector of record fields along with the fields within.
+WASM_API_EXTERN void wasmtime_component_val_record_delete(
+    wasmtime_component_val_record_t *vec);
+
+/// \brief Creates an empty vector of flag names.
+WASM_API_EXTERN void wasmtime_component_val_flags_new_empty(
+    wasmtime_component_val_flags_t *out);
+/// \brief Creates a vector of `size` empty flag names.
+WASM_API_EXTERN void wasmtime_component_val_flags_new_uninitialized(
+    wasmtime_component_val_flags_t *out, size_t size);
+/// \brief Creates a vector of flag names, taking ownership of the `size` names
+/// in `ptr`.
+WASM_API_EXTERN void wasmtime_component_val_flags_new(
+    wasmtime_component_val_flags_t *out, size_t size, const wasm_name_t *ptr);
+/// \brief Performs a deep copy of a vector of flag names.
+WASM_API_EXTERN void wasmtime_component_val_flags_copy(
+    wasmtime_component_val_flags_t *out,
+    const wasmtime_component_val_flags_t *src);
+/// \brief Deallocates a vector of flag names.
+WASM_API_EXTERN void wasmtime_component_val_flags_delete(
+    wasmtime_component_val_flags_t *vec);
+
+/**
+ * \brief Deletes a resource handle.
+ *
+ * Note that this doesn't dispose of the resource itself, see
+ * #wasmtime_component_resource_any_drop for that.
+ */
+WASM_API_EXTERN void wasmtime_component_resource_any_delete(
+    wasmtime_component_resource_any_t *resource);
+
+/**
+ * \brief Creates a new handle referring to the same resource.
+ */
+WASM_API_EXTERN wasmtime_component_resource_any_t *wasmtime_component_resource_any_clone(
+    const wasmtime_component_resource_any_t *resource);
+
+/**
+ * \brief Returns whether this is an `own` handle, as opposed to a `borrow`.
+ */
+WASM_API_EXTERN bool wasmtime_component_resource_any_owned(
+    const wasmtime_component_resource_any_t *resource);
+
+/**
+ * \brief Disposes of the resource within `store`, running its destructor if
+ * it has one.
+ *
+ * Resources received as results from a component or as parameters to a host
+ * function must be dropped with this function once they're no longer needed.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_resource_any_drop(
+    wasmtime_context_t *store,
+    const wasmtime_component_resource_any_t *resource);
+
+/**
+ * \brief Representation of an instantiated component in Wasmtime.
+ *
+ * Like #wasmtime_instance_t this is a handle owned by a #wasmtime_store_t and
+ * has no destructor associated with it.
+ */
+typedef struct wasmtime_component_instance {
+  /// Internal identifier of what store this belongs to, never zero.
+  uint64_t store_id;
+  /// Internal index within the store.
+  size_t index;
+} wasmtime_component_instance_t;
+
+/**
+ * \brief Representation of a function exported by a component instance.
+ *
+ * Like #wasmtime_func_t this is a handle owned by a #wasmtime_store_t and
+ * has no destructor associated with it.
+ */
+typedef struct wasmtime_component_func {
+  /// Internal identifier of what store this belongs to, never zero.
+  uint64_t store_id;
+  /// Internal index within the store.
+  size_t index;
+} wasmtime_component_func_t;
+
+/**
+ * \brief Looks up a function exported from the root of a component instance.
+ *
+ * \param store the store that owns `instance`
+ * \param instance the instance to look up `name` in
+ * \param name the name of the export
+ * \param name_len the byte length of `name`
+ * \param func where to store the function, if found
+ *
+ * \return `true` if the function was found and `false` otherwise.
+ */
+WASM_API_EXTERN bool wasmtime_component_instance_get_func(
+    wasmtime_context_t *store,
+    const wasmtime_component_instance_t *instance,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_func_t *func
+);
+
+/**
+ * \brief Looks up a function exported from an instance which is itself
+ * exported from a component instance, such as a function of an exported
+ * interface.
+ *
+ * \return `true` if the function was found and `false` otherwise.
+ */
+WASM_API_EXTERN bool wasmtime_component_instance_get_instance_func(
+    wasmtime_context_t *store,
+    const wasmtime_component_instance_t *instance,
+    const char *instance_name,
+    size_t instance_name_len,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_func_t *func
+);
+
+/**
+ * \brief Calls a function exported by a component.
+ *
+ * \param store the store that owns `func`
+ * \param func the function to call
+ * \param args the arguments to the function
+ * \param nargs the number of arguments provided
+ * \param results where to write the results of the function
+ * \param nresults the number of results expected
+ * \param trap where to store a trap, if one happens
+ *
+ * The number of arguments and results must match the function's type. The
+ * `args` remain owned by the caller. On success the `results` are initialized
+ * and owned by the caller, who must release each one with
+ * #wasmtime_component_val_delete.
+ *
+ * The function's post-return cleanup is run automatically after the results
+ * have been copied out, so the function may be called again immediately.
+ *
+ * Like #wasmtime_func_call this returns a #wasmtime_error_t for programmer
+ * errors such as type mismatches, and stores a #wasm_trap_t in `trap` if the
+ * component traps.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_func_call(
+    wasmtime_context_t *store,
+    const wasmtime_component_func_t *func,
+    const wasmtime_component_val_t *args,
+    size_t nargs,
+    wasmtime_component_val_t *results,
+    size_t nresults,
+    wasm_trap_t **trap
+);
+
+/**
+ * \typedef wasmtime_component_linker_t
+ * \brief Convenience alias for #wasmtime_component_linker
+ *
+ * \struct wasmtime_component_linker
+ * \brief Object used to conveniently link together and instantiate components.
+ *
+ * This mirrors `wasmtime::component::Linker` in the Rust API.
+ */
+typedef struct wasmtime_component_linker wasmtime_component_linker_t;
+
+/**
+ * \typedef wasmtime_component_linker_instance_t
+ * \brief Convenience alias for #wasmtime_component_linker_instance
+ *
+ * \struct wasmtime_component_linker_instance
+ * \brief A location within a #wasmtime_component_linker_t to define items in.
+ *
+ * This mutably borrows the linker it was created from, so the linker must
+ * not be used again until this is deleted with
+ * #wasmtime_component_linker_instance_delete.
+ */
+typedef struct wasmtime_component_linker_instance wasmtime_component_linker_instance_t;
+
+/**
+ * \brief Creates a new linker for components within the given engine.
+ */
+WASM_API_EXTERN wasmtime_component_linker_t *wasmtime_component_linker_new(
+    const wasm_engine_t *engine);
+
+/**
+ * \brief Deletes a linker.
+ */
+WASM_API_EXTERN void wasmtime_component_linker_delete(wasmtime_component_linker_t *linker);
+
+/**
+ * \brief Returns the root instance of the linker, used to define top-level
+ * imports of a component.
+ *
+ * The returned value must be deleted with
+ * #wasmtime_component_linker_instance_delete before `linker` is used again.
+ */
+WASM_API_EXTERN wasmtime_component_linker_instance_t *wasmtime_component_linker_root(
+    wasmtime_component_linker_t *linker);
+
+/**
+ * \brief Instantiates `component` with the items defined in `linker`.
+ *
+ * \param linker the linker to instantiate with
+ * \param store the store to instantiate within
+ * \param component the component to instantiate
+ * \param instance where to store the new instance on success
+ * \param trap where to store a trap, if one happens during instantiation
+ *
+ * \return an error if instantiation failed for a reason other than a trap,
+ * `NULL` otherwise.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instantiate(
+    const wasmtime_component_linker_t *linker,
+    wasmtime_context_t *store,
+    const wasmtime_component_t *component,
+    wasmtime_component_instance_t *instance,
+    wasm_trap_t **trap
+);
+
+/**
+ * \brief Deletes a #wasmtime_component_linker_instance_t, releasing the
+ * borrow on its linker.
+ */
+WASM_API_EXTERN void wasmtime_component_linker_instance_delete(
+    wasmtime_component_linker_instance_t *linker_instance);
+
+/**
+ * \brief Defines a nested instance named `name` within `linker_instance`.
+ *
+ * On success `ret` is filled in with the new instance which mutably borrows
+ * `linker_instance` until it's deleted with
+ * #wasmtime_component_linker_instance_delete.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instance_add_instance(
+    wasmtime_component_linker_instance_t *linker_instance,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_linker_instance_t **ret
+);
+
+/**
+ * \brief Callback signature for #wasmtime_component_linker_instance_add_func.
+ *
+ * \param env user-provided argument passed to
+ * #wasmtime_component_linker_instance_add_func
+ * \param store the store the function is being called within
+ * \param args the arguments provided to this function invocation, which
+ * remain owned by Wasmtime
+ * \param nargs how many arguments are provided
+ * \param results where to write the results of this function
+ * \param nresults how many results must be produced
+ *
+ * The `results` are initialized to `false` and may be overwritten without
+ * being deleted first. Values written there are taken over by Wasmtime.
+ *
+ * This callback can optionally return a #wasm_trap_t indicating that a trap
+ * should be raised in WebAssembly. It's expected that in this case the caller
+ * relinquishes ownership of the trap and it is passed back to the engine.
+ */
+typedef wasm_trap_t *(*wasmtime_component_func_callback_t)(
+    void *env,
+    wasmtime_context_t *store,
+    const wasmtime_component_val_t *args,
+    size_t nargs,
+    wasmtime_component_val_t *results,
+    size_t nresults);
+
+/**
+ * \brief Defines a host function named `name` within `linker_instance`.
+ *
+ * The type of the function is taken from the import of the same name in
+ * `component`, so an error is returned if `component` doesn't import a
+ * function at this location.
+ */
+WASM_API_EXTERN wasmtime_error_t *wasmtime_component_linker_instance_add_func(
+    wasmtime_component_linker_instance_t *linker_instance,
+    const wasmtime_component_t *component,
+    const char *name,
+    size_t name_len,
+    wasmtime_component_func_callback_t callback,
+    void *env,
+    void (*finalizer)(void*)
+);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif
+
+#endif // WASMTIME_COMPONENT_H
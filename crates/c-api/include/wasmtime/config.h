@@ -213,6 +213,16 @@ WASMTIME_CONFIG_PROP(void, wasm_multi_memory, bool)
  */
 WASMTIME_CONFIG_PROP(void, wasm_memory64, bool)
 
+/**
+ * \brief Configures whether the WebAssembly component model proposal is
+ * enabled, which is required to compile components with
+ * #wasmtime_component_new.
+ *
+ * This setting is `false` by default, and is only available when the C API is
+ * built with the `component-model` Cargo feature enabled.
+ */
+WASMTIME_CONFIG_PROP(void, wasm_component_model, bool)
+
 /**
  * \brief Configures how JIT code will be compiled.
  *
@@ -48,6 +48,8 @@ enum wasmtime_trap_code_enum {
   WASMTIME_TRAP_CODE_INTERRUPT,
   /// Execution has run out of the configured fuel amount.
   WASMTIME_TRAP_CODE_OUT_OF_FUEL,
+  /// A degenerate component adapter, which always traps, was called.
+  WASMTIME_TRAP_CODE_ALWAYS_TRAP_ADAPTER,
 };
 
 /**
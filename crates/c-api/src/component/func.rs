@@ -0,0 +1,115 @@
+use super::wasmtime_component_val_t;
+use crate::func::{error_from_panic, store_err};
+use crate::{wasm_trap_t, wasmtime_error_t, CStoreContextMut};
+use anyhow::{bail, Result};
+use std::ffi::c_void;
+use std::mem::MaybeUninit;
+use std::panic::{self, AssertUnwindSafe};
+use wasmtime::component::{Func, Type, Val};
+
+pub type wasmtime_component_func_callback_t = extern "C" fn(
+    *mut c_void,
+    CStoreContextMut<'_>,
+    *const wasmtime_component_val_t,
+    usize,
+    *mut wasmtime_component_val_t,
+    usize,
+) -> Option<Box<wasm_trap_t>>;
+
+pub(crate) unsafe fn c_callback_to_rust_fn(
+    callback: wasmtime_component_func_callback_t,
+    data: *mut c_void,
+    finalizer: Option<extern "C" fn(*mut std::ffi::c_void)>,
+    result_tys: Box<[Type]>,
+) -> impl Fn(CStoreContextMut<'_>, &[Val], &mut [Val]) -> Result<()> {
+    let foreign = crate::ForeignData { data, finalizer };
+    move |store, params, results| {
+        let _ = &foreign; // move entire foreign into this closure
+
+        let params = params
+            .iter()
+            .map(wasmtime_component_val_t::from_val)
+            .collect::<Vec<_>>();
+        let mut out_results = (0..results.len())
+            .map(|_| wasmtime_component_val_t::default())
+            .collect::<Vec<_>>();
+
+        let out = callback(
+            foreign.data,
+            store,
+            params.as_ptr(),
+            params.len(),
+            out_results.as_mut_ptr(),
+            out_results.len(),
+        );
+        if let Some(trap) = out {
+            return Err(trap.error);
+        }
+
+        for ((slot, result), ty) in results.iter_mut().zip(&out_results).zip(result_tys.iter()) {
+            *slot = result.to_val(ty)?;
+        }
+        Ok(())
+    }
+}
+
+fn call(
+    mut store: CStoreContextMut<'_>,
+    func: &Func,
+    args: &[wasmtime_component_val_t],
+    nresults: usize,
+) -> Result<Vec<Val>> {
+    let param_tys = func.params(&store);
+    if args.len() != param_tys.len() {
+        bail!(
+            "expected {} argument(s), got {}",
+            param_tys.len(),
+            args.len()
+        );
+    }
+    let result_tys = func.results(&store);
+    if nresults != result_tys.len() {
+        bail!("expected {} result(s), got {}", result_tys.len(), nresults);
+    }
+    let params = args
+        .iter()
+        .zip(param_tys.iter())
+        .map(|(arg, ty)| arg.to_val(ty))
+        .collect::<Result<Vec<_>>>()?;
+    let mut results = vec![Val::Bool(false); nresults];
+    func.call(&mut store, &params, &mut results)?;
+    func.post_return(&mut store)?;
+    Ok(results)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_func_call(
+    store: CStoreContextMut<'_>,
+    func: &Func,
+    args: *const wasmtime_component_val_t,
+    nargs: usize,
+    results: *mut MaybeUninit<wasmtime_component_val_t>,
+    nresults: usize,
+    trap_ret: &mut *mut wasm_trap_t,
+) -> Option<Box<wasmtime_error_t>> {
+    let args = crate::slice_from_raw_parts(args, nargs);
+
+    // Like `wasmtime_func_call` panics are caught here and turned into traps
+    // to insulate callers from bugs on the Rust side of things.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| call(store, func, args, nresults)));
+    match result {
+        Ok(Ok(vals)) => {
+            let results = crate::slice_from_raw_parts_mut(results, nresults);
+            for (slot, val) in results.iter_mut().zip(vals.iter()) {
+                crate::initialize(slot, wasmtime_component_val_t::from_val(val));
+            }
+            None
+        }
+        Ok(Err(err)) => store_err(err, trap_ret),
+        Err(panic) => {
+            let err = error_from_panic(panic);
+            *trap_ret = Box::into_raw(Box::new(wasm_trap_t::new(err)));
+            None
+        }
+    }
+}
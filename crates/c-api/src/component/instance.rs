@@ -0,0 +1,54 @@
+use crate::CStoreContextMut;
+use std::str;
+use wasmtime::component::{Func, Instance};
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_instance_get_func(
+    store: CStoreContextMut<'_>,
+    instance: &Instance,
+    name: *const u8,
+    name_len: usize,
+    func: &mut Func,
+) -> bool {
+    let name = crate::slice_from_raw_parts(name, name_len);
+    let name = match str::from_utf8(name) {
+        Ok(name) => name,
+        Err(_) => return false,
+    };
+    match instance.get_func(store, name) {
+        Some(f) => {
+            *func = f;
+            true
+        }
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_instance_get_instance_func(
+    mut store: CStoreContextMut<'_>,
+    instance: &Instance,
+    instance_name: *const u8,
+    instance_name_len: usize,
+    name: *const u8,
+    name_len: usize,
+    func: &mut Func,
+) -> bool {
+    let instance_name = crate::slice_from_raw_parts(instance_name, instance_name_len);
+    let name = crate::slice_from_raw_parts(name, name_len);
+    let (instance_name, name) = match (str::from_utf8(instance_name), str::from_utf8(name)) {
+        (Ok(instance_name), Ok(name)) => (instance_name, name),
+        _ => return false,
+    };
+    let mut exports = instance.exports(&mut store);
+    match exports
+        .instance(instance_name)
+        .and_then(|mut i| i.func(name))
+    {
+        Some(f) => {
+            *func = f;
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,126 @@
+use super::{c_callback_to_rust_fn, wasmtime_component_func_callback_t, wasmtime_component_t};
+use crate::func::store_err;
+use crate::linker::to_str;
+use crate::{bad_utf8, handle_result, wasm_engine_t, wasm_trap_t, wasmtime_error_t};
+use crate::{CStoreContextMut, StoreData};
+use anyhow::{bail, Result};
+use std::ffi::c_void;
+use std::str;
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, Instance, Linker, LinkerInstance, Type};
+
+#[repr(C)]
+pub struct wasmtime_component_linker_t {
+    pub(crate) linker: Linker<StoreData>,
+}
+
+wasmtime_c_api_macros::declare_own!(wasmtime_component_linker_t);
+
+/// A definition site within a `wasmtime_component_linker_t`.
+///
+/// The path of instance names leading to this instance is tracked alongside
+/// the `LinkerInstance` to find the types of host functions defined here.
+pub struct wasmtime_component_linker_instance_t<'a> {
+    linker_instance: LinkerInstance<'a, StoreData>,
+    path: Vec<String>,
+}
+
+wasmtime_c_api_macros::declare_own!(wasmtime_component_linker_instance_t);
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_new(
+    engine: &wasm_engine_t,
+) -> Box<wasmtime_component_linker_t> {
+    Box::new(wasmtime_component_linker_t {
+        linker: Linker::new(&engine.engine),
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_root(
+    linker: &mut wasmtime_component_linker_t,
+) -> Box<wasmtime_component_linker_instance_t<'_>> {
+    Box::new(wasmtime_component_linker_instance_t {
+        linker_instance: linker.linker.root(),
+        path: Vec::new(),
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_linker_instantiate(
+    linker: &wasmtime_component_linker_t,
+    store: CStoreContextMut<'_>,
+    component: &wasmtime_component_t,
+    instance_ptr: &mut Instance,
+    trap_ptr: &mut *mut wasm_trap_t,
+) -> Option<Box<wasmtime_error_t>> {
+    match linker.linker.instantiate(store, &component.component) {
+        Ok(instance) => {
+            *instance_ptr = instance;
+            None
+        }
+        Err(e) => store_err(e, trap_ptr),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_linker_instance_add_instance<'a>(
+    linker_instance: &'a mut wasmtime_component_linker_instance_t<'_>,
+    name: *const u8,
+    name_len: usize,
+    instance_ptr: &mut *mut wasmtime_component_linker_instance_t<'a>,
+) -> Option<Box<wasmtime_error_t>> {
+    let name = to_str!(name, name_len);
+    let mut path = linker_instance.path.clone();
+    path.push(name.to_string());
+    handle_result(linker_instance.linker_instance.instance(name), |i| {
+        *instance_ptr = Box::into_raw(Box::new(wasmtime_component_linker_instance_t {
+            linker_instance: i,
+            path,
+        }))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_linker_instance_add_func(
+    linker_instance: &mut wasmtime_component_linker_instance_t<'_>,
+    component: &wasmtime_component_t,
+    name: *const u8,
+    name_len: usize,
+    callback: wasmtime_component_func_callback_t,
+    data: *mut c_void,
+    finalizer: Option<extern "C" fn(*mut std::ffi::c_void)>,
+) -> Option<Box<wasmtime_error_t>> {
+    let name = to_str!(name, name_len);
+    let result = import_func_results(&component.component, &linker_instance.path, name).and_then(
+        |result_tys| {
+            let cb = c_callback_to_rust_fn(callback, data, finalizer, result_tys);
+            linker_instance
+                .linker_instance
+                .func_new(&component.component, name, cb)
+        },
+    );
+    handle_result(result, |()| ())
+}
+
+/// Returns the result types of the function imported by `component` at
+/// `path` followed by `name`.
+fn import_func_results(component: &Component, path: &[String], name: &str) -> Result<Box<[Type]>> {
+    let ty = component.component_type();
+    let mut names = path.iter().map(|s| s.as_str()).chain([name]);
+    let Some(first) = names.next() else {
+        bail!("import path is empty");
+    };
+    let mut item = ty.get_import(first);
+    for name in names {
+        item = match item {
+            Some(ComponentItem::ComponentInstance(i)) => i.get_export(name),
+            _ => None,
+        };
+    }
+    match item {
+        Some(ComponentItem::ComponentFunc(f)) => Ok(f.results().collect()),
+        Some(_) => bail!("import `{name}` has the wrong type (expected a function)"),
+        None => bail!("import `{name}` not found"),
+    }
+}
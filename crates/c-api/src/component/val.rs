@@ -0,0 +1,337 @@
+use crate::vec::declare_vecs;
+use crate::{handle_result, wasm_name_t, wasmtime_error_t, CStoreContextMut};
+use anyhow::{anyhow, bail, Result};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::{mem, ptr, slice, str};
+use wasmtime::component::{ResourceAny, Type, Val};
+
+#[repr(C, u8)]
+#[derive(Clone)]
+pub enum wasmtime_component_val_t {
+    Bool(bool),
+    S8(i8),
+    U8(u8),
+    S16(i16),
+    U16(u16),
+    S32(i32),
+    U32(u32),
+    S64(i64),
+    U64(u64),
+    Float32(f32),
+    Float64(f64),
+    Char(u32),
+    String(wasm_name_t),
+    List(wasmtime_component_val_vec_t),
+    Record(wasmtime_component_val_record_t),
+    Tuple(wasmtime_component_val_vec_t),
+    Variant(wasmtime_component_val_variant_t),
+    Enum(wasm_name_t),
+    Option(Option<Box<wasmtime_component_val_t>>),
+    Result(wasmtime_component_val_result_t),
+    Flags(wasmtime_component_val_flags_t),
+    Resource(Box<wasmtime_component_resource_any_t>),
+}
+
+impl Default for wasmtime_component_val_t {
+    fn default() -> wasmtime_component_val_t {
+        wasmtime_component_val_t::Bool(false)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Default)]
+pub struct wasmtime_component_val_record_field_t {
+    pub name: wasm_name_t,
+    pub val: wasmtime_component_val_t,
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct wasmtime_component_val_variant_t {
+    pub discriminant: wasm_name_t,
+    pub val: Option<Box<wasmtime_component_val_t>>,
+}
+
+#[repr(C)]
+#[derive(Clone)]
+pub struct wasmtime_component_val_result_t {
+    pub is_ok: bool,
+    pub val: Option<Box<wasmtime_component_val_t>>,
+}
+
+#[derive(Clone)]
+pub struct wasmtime_component_resource_any_t {
+    pub(crate) resource: ResourceAny,
+}
+
+declare_vecs! {
+    (
+        name: wasmtime_component_val_vec_t,
+        ty: wasmtime_component_val_t,
+        new: wasmtime_component_val_vec_new,
+        empty: wasmtime_component_val_vec_new_empty,
+        uninit: wasmtime_component_val_vec_new_uninitialized,
+        copy: wasmtime_component_val_vec_copy,
+        delete: wasmtime_component_val_vec_delete,
+    )
+    (
+        name: wasmtime_component_val_record_t,
+        ty: wasmtime_component_val_record_field_t,
+        new: wasmtime_component_val_record_new,
+        empty: wasmtime_component_val_record_new_empty,
+        uninit: wasmtime_component_val_record_new_uninitialized,
+        copy: wasmtime_component_val_record_copy,
+        delete: wasmtime_component_val_record_delete,
+    )
+    (
+        name: wasmtime_component_val_flags_t,
+        ty: wasm_name_t,
+        new: wasmtime_component_val_flags_new,
+        empty: wasmtime_component_val_flags_new_empty,
+        uninit: wasmtime_component_val_flags_new_uninitialized,
+        copy: wasmtime_component_val_flags_copy,
+        delete: wasmtime_component_val_flags_delete,
+    )
+}
+
+fn name(s: &str) -> wasm_name_t {
+    wasm_name_t::from_name(s.to_string())
+}
+
+fn payload_to_c(val: Option<&Val>) -> Option<Box<wasmtime_component_val_t>> {
+    val.map(|v| Box::new(wasmtime_component_val_t::from_val(v)))
+}
+
+fn payload_to_val(
+    val: &Option<Box<wasmtime_component_val_t>>,
+    ty: Option<Type>,
+) -> Result<Option<Val>> {
+    match (val, ty) {
+        (Some(val), Some(ty)) => Ok(Some(val.to_val(&ty)?)),
+        (None, None) => Ok(None),
+        (Some(_), None) => bail!("expected no payload but one was provided"),
+        (None, Some(_)) => bail!("expected a payload but none was provided"),
+    }
+}
+
+impl wasmtime_component_val_t {
+    pub(crate) fn from_val(val: &Val) -> wasmtime_component_val_t {
+        match val {
+            Val::Bool(b) => wasmtime_component_val_t::Bool(*b),
+            Val::S8(i) => wasmtime_component_val_t::S8(*i),
+            Val::U8(i) => wasmtime_component_val_t::U8(*i),
+            Val::S16(i) => wasmtime_component_val_t::S16(*i),
+            Val::U16(i) => wasmtime_component_val_t::U16(*i),
+            Val::S32(i) => wasmtime_component_val_t::S32(*i),
+            Val::U32(i) => wasmtime_component_val_t::U32(*i),
+            Val::S64(i) => wasmtime_component_val_t::S64(*i),
+            Val::U64(i) => wasmtime_component_val_t::U64(*i),
+            Val::Float32(f) => wasmtime_component_val_t::Float32(*f),
+            Val::Float64(f) => wasmtime_component_val_t::Float64(*f),
+            Val::Char(c) => wasmtime_component_val_t::Char(*c as u32),
+            Val::String(s) => wasmtime_component_val_t::String(name(s)),
+            Val::List(list) => wasmtime_component_val_t::List(
+                list.iter()
+                    .map(wasmtime_component_val_t::from_val)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            Val::Record(record) => wasmtime_component_val_t::Record(
+                record
+                    .fields()
+                    .map(|(n, v)| wasmtime_component_val_record_field_t {
+                        name: name(n),
+                        val: wasmtime_component_val_t::from_val(v),
+                    })
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            Val::Tuple(tuple) => wasmtime_component_val_t::Tuple(
+                tuple
+                    .values()
+                    .iter()
+                    .map(wasmtime_component_val_t::from_val)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            Val::Variant(variant) => {
+                wasmtime_component_val_t::Variant(wasmtime_component_val_variant_t {
+                    discriminant: name(variant.discriminant()),
+                    val: payload_to_c(variant.payload()),
+                })
+            }
+            Val::Enum(e) => wasmtime_component_val_t::Enum(name(e.discriminant())),
+            Val::Option(option) => wasmtime_component_val_t::Option(payload_to_c(option.value())),
+            Val::Result(result) => {
+                let (is_ok, val) = match result.value() {
+                    Ok(val) => (true, val),
+                    Err(val) => (false, val),
+                };
+                wasmtime_component_val_t::Result(wasmtime_component_val_result_t {
+                    is_ok,
+                    val: payload_to_c(val),
+                })
+            }
+            Val::Flags(flags) => {
+                wasmtime_component_val_t::Flags(flags.flags().map(name).collect::<Vec<_>>().into())
+            }
+            Val::Resource(resource) => {
+                wasmtime_component_val_t::Resource(Box::new(wasmtime_component_resource_any_t {
+                    resource: *resource,
+                }))
+            }
+        }
+    }
+
+    /// Converts this C value into a `Val`, using `ty` to construct compound
+    /// values.
+    ///
+    /// Primitive values are converted as-is and are typechecked later on when
+    /// they're passed to or returned from a function.
+    pub(crate) fn to_val(&self, ty: &Type) -> Result<Val> {
+        Ok(match (self, ty) {
+            (wasmtime_component_val_t::Bool(b), _) => Val::Bool(*b),
+            (wasmtime_component_val_t::S8(i), _) => Val::S8(*i),
+            (wasmtime_component_val_t::U8(i), _) => Val::U8(*i),
+            (wasmtime_component_val_t::S16(i), _) => Val::S16(*i),
+            (wasmtime_component_val_t::U16(i), _) => Val::U16(*i),
+            (wasmtime_component_val_t::S32(i), _) => Val::S32(*i),
+            (wasmtime_component_val_t::U32(i), _) => Val::U32(*i),
+            (wasmtime_component_val_t::S64(i), _) => Val::S64(*i),
+            (wasmtime_component_val_t::U64(i), _) => Val::U64(*i),
+            (wasmtime_component_val_t::Float32(f), _) => Val::Float32(*f),
+            (wasmtime_component_val_t::Float64(f), _) => Val::Float64(*f),
+            (wasmtime_component_val_t::Char(c), _) => Val::Char(
+                char::from_u32(*c).ok_or_else(|| anyhow!("invalid unicode scalar value {c:#x}"))?,
+            ),
+            (wasmtime_component_val_t::String(s), _) => {
+                Val::String(str::from_utf8(s.as_slice())?.into())
+            }
+            (wasmtime_component_val_t::Resource(r), _) => Val::Resource(r.resource),
+            (wasmtime_component_val_t::List(list), Type::List(ty)) => {
+                let elem = ty.ty();
+                ty.new_val(
+                    list.as_slice()
+                        .iter()
+                        .map(|v| v.to_val(&elem))
+                        .collect::<Result<_>>()?,
+                )?
+            }
+            (wasmtime_component_val_t::Record(record), Type::Record(ty)) => {
+                let fields = record.as_slice();
+                if fields.len() != ty.fields().len() {
+                    bail!(
+                        "expected {} record field(s); got {}",
+                        ty.fields().len(),
+                        fields.len()
+                    );
+                }
+                let values = fields
+                    .iter()
+                    .zip(ty.fields())
+                    .map(|(field, ty)| {
+                        Ok((
+                            str::from_utf8(field.name.as_slice())?,
+                            field.val.to_val(&ty.ty)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                ty.new_val(values)?
+            }
+            (wasmtime_component_val_t::Tuple(tuple), Type::Tuple(ty)) => {
+                let values = tuple.as_slice();
+                if values.len() != ty.types().len() {
+                    bail!(
+                        "expected {} tuple element(s); got {}",
+                        ty.types().len(),
+                        values.len()
+                    );
+                }
+                ty.new_val(
+                    values
+                        .iter()
+                        .zip(ty.types())
+                        .map(|(v, ty)| v.to_val(&ty))
+                        .collect::<Result<_>>()?,
+                )?
+            }
+            (wasmtime_component_val_t::Variant(variant), Type::Variant(ty)) => {
+                let discriminant = str::from_utf8(variant.discriminant.as_slice())?;
+                let case = ty
+                    .cases()
+                    .find(|case| case.name == discriminant)
+                    .ok_or_else(|| anyhow!("unknown variant case `{discriminant}`"))?;
+                ty.new_val(discriminant, payload_to_val(&variant.val, case.ty)?)?
+            }
+            (wasmtime_component_val_t::Enum(discriminant), Type::Enum(ty)) => {
+                ty.new_val(str::from_utf8(discriminant.as_slice())?)?
+            }
+            (wasmtime_component_val_t::Option(val), Type::Option(ty)) => {
+                ty.new_val(payload_to_val(val, Some(ty.ty()))?)?
+            }
+            (wasmtime_component_val_t::Result(result), Type::Result(ty)) => {
+                ty.new_val(if result.is_ok {
+                    Ok(payload_to_val(&result.val, ty.ok())?)
+                } else {
+                    Err(payload_to_val(&result.val, ty.err())?)
+                })?
+            }
+            (wasmtime_component_val_t::Flags(flags), Type::Flags(ty)) => {
+                let names = flags
+                    .as_slice()
+                    .iter()
+                    .map(|name| str::from_utf8(name.as_slice()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                ty.new_val(&names)?
+            }
+            (_, ty) => bail!("value does not match the expected type {ty:?}"),
+        })
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_val_new() -> Box<wasmtime_component_val_t> {
+    Box::new(wasmtime_component_val_t::default())
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_val_free(_val: Option<Box<wasmtime_component_val_t>>) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasmtime_component_val_delete(
+    val: &mut ManuallyDrop<wasmtime_component_val_t>,
+) {
+    ManuallyDrop::drop(val)
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_val_copy(
+    dst: &mut MaybeUninit<wasmtime_component_val_t>,
+    src: &wasmtime_component_val_t,
+) {
+    crate::initialize(dst, src.clone())
+}
+
+wasmtime_c_api_macros::declare_own!(wasmtime_component_resource_any_t);
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_resource_any_clone(
+    resource: &wasmtime_component_resource_any_t,
+) -> Box<wasmtime_component_resource_any_t> {
+    Box::new(resource.clone())
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_resource_any_owned(
+    resource: &wasmtime_component_resource_any_t,
+) -> bool {
+    resource.resource.owned()
+}
+
+#[no_mangle]
+pub extern "C" fn wasmtime_component_resource_any_drop(
+    store: CStoreContextMut<'_>,
+    resource: &wasmtime_component_resource_any_t,
+) -> Option<Box<wasmtime_error_t>> {
+    handle_result(resource.resource.resource_drop(store), |()| ())
+}
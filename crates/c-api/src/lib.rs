@@ -54,6 +54,11 @@ mod r#async;
 #[cfg(feature = "async")]
 pub use crate::r#async::*;
 
+#[cfg(feature = "component-model")]
+mod component;
+#[cfg(feature = "component-model")]
+pub use crate::component::*;
+
 #[cfg(feature = "wasi")]
 mod wasi;
 #[cfg(feature = "wasi")]
@@ -134,7 +134,7 @@ pub extern "C" fn wasmtime_trap_code(raw: &wasm_trap_t, code: &mut u8) -> bool {
         Trap::UnreachableCodeReached => 9,
         Trap::Interrupt => 10,
         Trap::OutOfFuel => 11,
-        Trap::AlwaysTrapAdapter => unreachable!("component model not supported"),
+        Trap::AlwaysTrapAdapter => 12,
         _ => unreachable!(),
     };
     true
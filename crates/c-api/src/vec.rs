@@ -85,6 +85,15 @@ macro_rules! declare_vecs {
             }
         }
 
+        impl$(<$lt>)? Default for $name $(<$lt>)? {
+            fn default() -> Self {
+                $name {
+                    size: 0,
+                    data: ptr::null_mut(),
+                }
+            }
+        }
+
         impl$(<$lt>)? From<Vec<$elem_ty>> for $name $(<$lt>)? {
             fn from(vec: Vec<$elem_ty>) -> Self {
                 let mut vec = vec.into_boxed_slice();
@@ -139,6 +148,8 @@ macro_rules! declare_vecs {
     )*};
 }
 
+pub(crate) use declare_vecs;
+
 declare_vecs! {
     (
         name: wasm_byte_vec_t,
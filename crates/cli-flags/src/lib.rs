@@ -75,10 +75,12 @@ wasmtime_option_group! {
 
 wasmtime_option_group! {
     pub struct CodegenOptions {
-        /// Either `cranelift` or `winch`.
+        /// Either `cranelift`, `winch` or `tiered`.
         ///
         /// Currently only `cranelift` and `winch` are supported, but not all
-        /// builds of Wasmtime have both built in.
+        /// builds of Wasmtime have both built in. `tiered` compiles with
+        /// `winch` first and recompiles with `cranelift` in the background,
+        /// which requires both.
         pub compiler: Option<wasmtime::Strategy>,
         /// Enable Cranelift's internal debug verifier (expensive)
         pub cranelift_debug_verifier: Option<bool>,
@@ -248,6 +250,34 @@ wasmtime_option_group! {
         pub inherit_network: Option<bool>,
         /// Indicates whether `wasi:sockets/ip-name-lookup` is enabled or not.
         pub allow_ip_name_lookup: Option<bool>,
+        /// Restrict outgoing wasi-http requests to this authority.
+        ///
+        /// This can be specified multiple times and takes the form
+        /// `HOST[:PORT]`, where a missing port allows any port and a host
+        /// starting with `*.` allows all of its subdomains. All authorities
+        /// are allowed if this isn't specified.
+        pub http_allow: Vec<String>,
+        /// Maximum number of outgoing wasi-http requests per store.
+        pub http_max_requests: Option<u64>,
+        /// Maximum number of body bytes sent and received through outgoing
+        /// wasi-http requests per store.
+        pub http_max_bytes: Option<u64>,
+        /// Maximum number of idle connections kept open to each authority
+        /// for outgoing wasi-http requests, where 0 disables reusing
+        /// connections.
+        pub http_max_idle_connections: Option<usize>,
+        /// How long idle connections for outgoing wasi-http requests are kept
+        /// open.
+        pub http_idle_timeout: Option<Duration>,
+        /// Whether HTTP/2 is offered for outgoing wasi-http requests over
+        /// TLS.
+        pub http2: Option<bool>,
+        /// Run with WASI preview2 in deterministic mode.
+        ///
+        /// Clocks are virtual and only advance when read, randomness is
+        /// seeded, network access is disabled, and NaN canonicalization and
+        /// deterministic relaxed-simd lowerings are enabled in the engine.
+        pub deterministic: Option<bool>,
 
     }
 
@@ -405,7 +435,7 @@ impl CommonOptions {
             _ => err,
         }
         match_feature! {
-            ["cranelift" : self.wasm.nan_canonicalization]
+            ["cranelift" : self.wasm.nan_canonicalization.or(self.wasi.deterministic)]
             enable => config.cranelift_nan_canonicalization(enable),
             true => err,
         }
@@ -506,7 +536,11 @@ impl CommonOptions {
             config.max_wasm_stack(max);
         }
 
-        if let Some(enable) = self.wasm.relaxed_simd_deterministic {
+        if let Some(enable) = self
+            .wasm
+            .relaxed_simd_deterministic
+            .or(self.wasi.deterministic)
+        {
             config.relaxed_simd_deterministic(enable);
         }
         match_feature! {
@@ -361,11 +361,12 @@ impl WasmtimeOptionValue for wasmtime::OptLevel {
 }
 
 impl WasmtimeOptionValue for wasmtime::Strategy {
-    const VAL_HELP: &'static str = "=winch|cranelift";
+    const VAL_HELP: &'static str = "=winch|cranelift|tiered";
     fn parse(val: Option<&str>) -> Result<Self> {
         match String::parse(val)?.as_str() {
             "cranelift" => Ok(wasmtime::Strategy::Cranelift),
             "winch" => Ok(wasmtime::Strategy::Winch),
+            "tiered" => Ok(wasmtime::Strategy::Tiered),
             other => bail!(
                 "unknown optimization level `{}`, only 0,1,2,s accepted",
                 other
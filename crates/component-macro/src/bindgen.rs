@@ -109,6 +109,7 @@ impl Parse for Config {
                         opts.only_interfaces = true;
                     }
                     Opt::With(val) => opts.with.extend(val),
+                    Opt::Forward(val) => opts.forward.extend(val),
                 }
             }
         } else {
@@ -180,6 +181,7 @@ mod kw {
     syn::custom_keyword!(ownership);
     syn::custom_keyword!(interfaces);
     syn::custom_keyword!(with);
+    syn::custom_keyword!(forward);
     syn::custom_keyword!(except_imports);
     syn::custom_keyword!(only_imports);
 }
@@ -194,6 +196,7 @@ enum Opt {
     Ownership(Ownership),
     Interfaces(syn::LitStr),
     With(HashMap<String, String>),
+    Forward(HashMap<String, String>),
 }
 
 impl Parse for Opt {
@@ -311,6 +314,14 @@ impl Parse for Opt {
             let fields: Punctuated<(String, String), Token![,]> =
                 contents.parse_terminated(with_field_parse, Token![,])?;
             Ok(Opt::With(HashMap::from_iter(fields.into_iter())))
+        } else if l.peek(kw::forward) {
+            input.parse::<kw::forward>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            let _lbrace = braced!(contents in input);
+            let fields: Punctuated<(String, String), Token![,]> =
+                contents.parse_terminated(forward_field_parse, Token![,])?;
+            Ok(Opt::Forward(HashMap::from_iter(fields.into_iter())))
         } else {
             Err(l.error())
         }
@@ -327,6 +338,13 @@ fn trappable_error_field_parse(input: ParseStream<'_>) -> Result<TrappableError>
     })
 }
 
+fn forward_field_parse(input: ParseStream<'_>) -> Result<(String, String)> {
+    let interface = input.parse::<syn::LitStr>()?.value();
+    input.parse::<Token![:]>()?;
+    let name = input.parse::<syn::Ident>()?;
+    Ok((interface, name.to_string()))
+}
+
 fn with_field_parse(input: ParseStream<'_>) -> Result<(String, String)> {
     let interface = input.parse::<syn::LitStr>()?.value();
     input.parse::<Token![:]>()?;
@@ -148,3 +148,75 @@ mod trappable_errors {
     #[allow(dead_code)]
     type MyX = u32;
 }
+
+mod forward {
+    const _: () = {
+        wasmtime::component::bindgen!({
+            inline: "
+                package demo:pkg;
+
+                interface types {
+                    record r {
+                        a: string,
+                        b: u32,
+                    }
+                }
+
+                interface a {
+                    use types.{r};
+
+                    type bytes = list<u8>;
+
+                    variant v {
+                        x(string),
+                        y,
+                    }
+
+                    f1: func(a: u32, b: string, c: list<string>) -> string;
+                    f2: func(a: r, b: v, c: bytes);
+                    f3: func(a: option<string>, b: tuple<string, u8, r>) -> option<r>;
+                    f4: func(a: result<string, list<u8>>, b: result<_, u32>) -> result<r, v>;
+                }
+
+                world foo {
+                    import a;
+                    export a;
+                }
+            ",
+            forward: {
+                "demo:pkg/a": Forward,
+            },
+        });
+
+        fn _assert_host<T>() -> impl demo::pkg::a::Host {
+            let _: fn(&exports::demo::pkg::a::A) = |_| ();
+            let _: fn(demo::pkg::a::V) -> exports::demo::pkg::a::V = |v| v;
+            None::<demo::pkg::a::Forward<T>>.unwrap()
+        }
+    };
+
+    const _: () = {
+        wasmtime::component::bindgen!({
+            inline: "
+                package demo:pkg;
+
+                interface a {
+                    f: func(a: string) -> string;
+                }
+
+                world foo {
+                    import a;
+                    export a;
+                }
+            ",
+            async: true,
+            forward: {
+                "demo:pkg/a": Forward,
+            },
+        });
+
+        fn _assert_host<T: Send>() -> impl demo::pkg::a::Host {
+            None::<demo::pkg::a::Forward<T>>.unwrap()
+        }
+    };
+}
@@ -51,6 +51,9 @@ macro_rules! foreach_builtin_function {
             out_of_gas(vmctx: vmctx);
             /// Invoked when we reach a new epoch.
             new_epoch(vmctx: vmctx) -> i64;
+            /// Invoked when a function compiled by a baseline compiler has
+            /// been called often enough to be recompiled.
+            tier_up(vmctx: vmctx, func: i32);
             /// Invoked before malloc returns.
             check_malloc(vmctx: vmctx, addr: i32, len: i32) -> i32;
             /// Invoked before the free returns.
@@ -37,6 +37,9 @@ use std::ops::Index;
 #[derive(Default)]
 #[allow(missing_docs)]
 pub struct ComponentDfg {
+    /// Same as `Component::ty`, filled in once inlining has finished.
+    pub ty: Option<TypeComponentIndex>,
+
     /// Same as `Component::import_types`
     pub import_types: PrimaryMap<ImportIndex, (String, TypeDef)>,
 
@@ -75,6 +78,16 @@ pub struct ComponentDfg {
     /// considered "roots" in dataflow.
     pub instances: PrimaryMap<InstanceId, Instance>,
 
+    /// Metadata about all component `start` functions which are invoked.
+    ///
+    /// Like `instances` this is not deduplicated as invoking a `start` function
+    /// is side-effectful.
+    pub starts: PrimaryMap<StartId, Start>,
+
+    /// Number of component-level values produced by `start` functions during
+    /// the inlining phase (this is not edited after creation).
+    pub num_runtime_values: u32,
+
     /// Number of component instances that were created during the inlining
     /// phase (this is not edited after creation).
     pub num_runtime_component_instances: u32,
@@ -121,8 +134,9 @@ pub struct ComponentDfg {
 
     /// An ordered list of side effects induced by instantiating this component.
     ///
-    /// Currently all side effects are either instantiating core wasm modules or
-    /// declaring a resource. These side effects affect the dataflow processing
+    /// Currently all side effects are either instantiating core wasm modules,
+    /// declaring a resource, or invoking a component `start` function. These
+    /// side effects affect the dataflow processing
     /// of this component by idnicating what order operations should be
     /// performed during instantiation.
     pub side_effects: Vec<SideEffect>,
@@ -145,6 +159,12 @@ pub enum SideEffect {
     /// destructors. Destructors are loaded from core wasm instances (or
     /// lowerings) which are produced by prior side-effectful operations.
     Resource(DefinedResourceIndex),
+
+    /// A component `start` function is invoked.
+    ///
+    /// This runs arbitrary code and may produce values that are used later
+    /// on, so it's ordered with respect to all other side effects.
+    Start(StartId),
 }
 
 macro_rules! id {
@@ -163,6 +183,7 @@ id! {
     pub struct AdapterId(u32);
     pub struct PostReturnId(u32);
     pub struct AdapterModuleId(u32);
+    pub struct StartId(u32);
 }
 
 /// Same as `info::InstantiateModule`
@@ -187,6 +208,17 @@ pub enum Export {
     ModuleImport(RuntimeImportIndex),
     Instance(IndexMap<String, Export>),
     Type(TypeDef),
+    Value(ValueDef),
+}
+
+/// Same as `info::Start`
+#[allow(missing_docs)]
+pub struct Start {
+    pub ty: TypeFuncIndex,
+    pub func: CoreDef,
+    pub options: CanonicalOptions,
+    pub args: Vec<ValueDef>,
+    pub results: u32,
 }
 
 /// Same as `info::CoreDef`, except has an extra `Adapter` variant.
@@ -382,6 +414,8 @@ impl ComponentDfg {
                 num_runtime_post_returns: linearize.runtime_post_return.len() as u32,
                 num_runtime_reallocs: linearize.runtime_reallocs.len() as u32,
                 num_runtime_instances: linearize.runtime_instances.len() as u32,
+                num_runtime_values: self.num_runtime_values,
+                ty: self.ty.unwrap(),
                 imports: self.imports,
                 import_types: self.import_types,
                 num_runtime_component_instances: self.num_runtime_component_instances,
@@ -432,6 +466,9 @@ impl LinearizeDfg<'_> {
             SideEffect::Resource(i) => {
                 self.resource(*i, &self.dfg.resources[*i]);
             }
+            SideEffect::Start(i) => {
+                self.start(&self.dfg.starts[*i]);
+            }
         }
     }
 
@@ -475,6 +512,19 @@ impl LinearizeDfg<'_> {
             }));
     }
 
+    fn start(&mut self, start: &Start) {
+        let func = self.core_def(&start.func);
+        let options = self.options(&start.options);
+        self.initializers
+            .push(GlobalInitializer::Start(info::Start {
+                ty: start.ty,
+                func,
+                options,
+                args: start.args.clone(),
+                results: start.results,
+            }));
+    }
+
     fn export(&mut self, export: &Export) -> info::Export {
         match export {
             Export::LiftedFunction { ty, func, options } => {
@@ -494,6 +544,7 @@ impl LinearizeDfg<'_> {
                     .collect(),
             ),
             Export::Type(def) => info::Export::Type(*def),
+            Export::Value(def) => info::Export::Value(*def),
         }
     }
 
@@ -69,8 +69,11 @@ pub struct ComponentTranslation {
 ///
 /// NB: Lots of the component model is not yet implemented in the runtime so
 /// this is going to undergo a lot of churn.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Component {
+    /// The type of this component, in terms of its imports and exports.
+    pub ty: TypeComponentIndex,
+
     /// A list of typed values that this component imports.
     ///
     /// Note that each name is given an `ImportIndex` here for the next map to
@@ -145,6 +148,10 @@ pub struct Component {
     /// Same as `num_runtime_reallocs`, but for post-return functions.
     pub num_runtime_post_returns: u32,
 
+    /// The number of component-level values (maximum `RuntimeValueIndex`)
+    /// produced by `start` functions when instantiating this component.
+    pub num_runtime_values: u32,
+
     /// WebAssembly type signature of all trampolines.
     pub trampolines: PrimaryMap<TrampolineIndex, SignatureIndex>,
 
@@ -254,6 +261,12 @@ pub enum GlobalInitializer {
     ///
     /// Contains information about the destructor, for example.
     Resource(Resource),
+
+    /// A component `start` function is invoked.
+    ///
+    /// The results of the function are appended, in order, to the runtime
+    /// value index space.
+    Start(Start),
 }
 
 /// Metadata for extraction of a memory of what's being extracted and where it's
@@ -284,6 +297,35 @@ pub struct ExtractPostReturn {
     pub def: CoreDef,
 }
 
+/// Description of a component `start` function which is invoked as part of
+/// instantiation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Start {
+    /// The component function type of the function being invoked.
+    pub ty: TypeFuncIndex,
+    /// The core wasm function that was lifted to create the start function.
+    pub func: CoreDef,
+    /// The canonical ABI options used when lifting `func`.
+    pub options: CanonicalOptions,
+    /// The values that are passed as arguments to the function.
+    pub args: Vec<ValueDef>,
+    /// The number of results that the function produces.
+    ///
+    /// Each result is assigned the next `RuntimeValueIndex` in order.
+    pub results: u32,
+}
+
+/// Definition of a component-level value and where it comes from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub enum ValueDef {
+    /// The value was imported from the host.
+    ///
+    /// It's guaranteed that this `RuntimeImportIndex` points to a value.
+    Import(RuntimeImportIndex),
+    /// The value was produced by a `start` function during instantiation.
+    Start(RuntimeValueIndex),
+}
+
 /// Different methods of instantiating a core wasm module.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum InstantiateModule {
@@ -409,6 +451,8 @@ pub enum Export {
     /// An exported type from a component or instance, currently only
     /// informational.
     Type(TypeDef),
+    /// An exported component-level value.
+    Value(ValueDef),
 }
 
 /// Canonical ABI options associated with a lifted or lowered function.
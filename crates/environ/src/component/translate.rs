@@ -207,6 +207,9 @@ enum LocalInitializer<'data> {
 
     // export section
     Export(ComponentItem),
+
+    // start section
+    Start(ComponentFuncIndex, Box<[ValueIndex]>, u32),
 }
 
 /// The "closure environment" of components themselves.
@@ -610,9 +613,20 @@ impl<'a, 'data> Translator<'a, 'data> {
                 }
             }
 
+            // The start section invokes a component function with values from
+            // the value index space as arguments. Results of the function are
+            // pushed onto the value index space in turn.
             Payload::ComponentStartSection { start, range } => {
                 self.validator.component_start_section(&start, &range)?;
-                unimplemented!("component start section");
+                let func = ComponentFuncIndex::from_u32(start.func_index);
+                let args = start
+                    .arguments
+                    .iter()
+                    .map(|i| ValueIndex::from_u32(*i))
+                    .collect();
+                self.result
+                    .initializers
+                    .push(LocalInitializer::Start(func, args, start.results));
             }
 
             // Aliases of instance exports (either core or component) will be
@@ -771,7 +785,8 @@ impl<'a, 'data> Translator<'a, 'data> {
                 ComponentItem::Component(index)
             }
             wasmparser::ComponentExternalKind::Value => {
-                unimplemented!("component values");
+                let index = ValueIndex::from_u32(index);
+                ComponentItem::Value(index)
             }
             wasmparser::ComponentExternalKind::Type => {
                 let types = self.validator.types(0).unwrap();
@@ -76,6 +76,7 @@ pub(super) fn run(
     // item since we don't know the precise structure of the host import.
     let mut args = HashMap::with_capacity(result.exports.len());
     let mut path = Vec::new();
+    let mut component_ty = TypeComponent::default();
     types.resources_mut().set_current_instance(index);
     let types_ref = result.types_ref();
     for init in result.initializers.iter() {
@@ -107,6 +108,7 @@ pub(super) fn run(
         // With resources all taken care of it's now possible to convert this
         // into Wasmtime's type system.
         let ty = types.convert_component_entity_type(types_ref, ty)?;
+        component_ty.imports.insert(name.as_str().to_string(), ty);
 
         // Imports of types that aren't resources are not required to be
         // specified by the host since it's just for type information within
@@ -133,6 +135,16 @@ pub(super) fn run(
     let exports = inliner.run(types, &mut frames)?;
     assert!(frames.is_empty());
 
+    // The resources of the root component are still registered at this point,
+    // so the type of the root component itself can be calculated from the
+    // validator's view of its exports.
+    for name in result.exports.keys() {
+        let ty = types_ref.component_entity_type_of_export(name).unwrap();
+        let ty = types.convert_component_entity_type(types_ref, ty)?;
+        component_ty.exports.insert(name.to_string(), ty);
+    }
+    inliner.result.ty = Some(types.add_component_type(component_ty));
+
     let mut export_map = Default::default();
     for (name, def) in exports {
         inliner.record_export(name, def, types, &mut export_map)?;
@@ -212,6 +224,7 @@ struct InlinerFrame<'a> {
     module_instances: PrimaryMap<ModuleInstanceIndex, ModuleInstanceDef<'a>>,
     component_instances: PrimaryMap<ComponentInstanceIndex, ComponentInstanceDef<'a>>,
     components: PrimaryMap<ComponentIndex, ComponentDef<'a>>,
+    values: PrimaryMap<ValueIndex, ComponentValueDef<'a>>,
 
     /// The type of instance produced by completing the instantiation of this
     /// frame.
@@ -266,6 +279,7 @@ enum ComponentItemDef<'a> {
     Instance(ComponentInstanceDef<'a>),
     Func(ComponentFuncDef<'a>),
     Module(ModuleDef<'a>),
+    Value(ComponentValueDef<'a>),
     Type(TypeDef),
 }
 
@@ -335,6 +349,16 @@ enum ComponentInstanceDef<'a> {
     Items(IndexMap<&'a str, ComponentItemDef<'a>>),
 }
 
+#[derive(Clone)]
+enum ComponentValueDef<'a> {
+    /// A host-imported value.
+    Import(ImportPath<'a>),
+
+    /// A value produced as the result of a `start` function, stored at runtime
+    /// at the specified index.
+    Start(RuntimeValueIndex),
+}
+
 #[derive(Clone)]
 struct ComponentDef<'a> {
     index: StaticComponentIndex,
@@ -395,15 +419,17 @@ impl<'a> Inliner<'a> {
                         .collect::<Result<_>>()?;
                     let instance_ty = frame.instance_ty;
                     let (_, snapshot) = frames.pop().unwrap();
-                    *types.resources_mut() = snapshot;
                     match frames.last_mut() {
                         Some((parent, _)) => {
+                            *types.resources_mut() = snapshot;
                             parent.finish_instantiate(
                                 ComponentInstanceDef::Items(exports),
                                 instance_ty.unwrap(),
                                 types,
                             );
                         }
+                        // Note that the resources of the root component are
+                        // left in place to calculate its type afterwards.
                         None => break Ok(exports),
                     }
                 }
@@ -883,16 +909,64 @@ impl<'a> Inliner<'a> {
                         .component_instances
                         .push(frame.component_instances[*i].clone());
                 }
+                ComponentItem::Value(i) => {
+                    frame.values.push(frame.values[*i].clone());
+                }
 
                 // Type index spaces aren't maintained during this inlining pass
                 // so ignore this.
                 ComponentItem::Type(_) => {}
             },
+
+            // A `start` function invokes a component function with arguments
+            // from the value index space. This is recorded as a side effect
+            // to preserve ordering with respect to other instantiations and the
+            // results of the function are allocated fresh runtime indices.
+            //
+            // Note that only lifted functions are supported here. Invoking an
+            // imported host function as a start function would require the
+            // host to call itself which isn't implemented.
+            Start(func, args, results) => {
+                let (ty, func, options) = match &frame.component_funcs[*func] {
+                    ComponentFuncDef::Lifted { ty, func, options } => {
+                        (*ty, func.clone(), options.clone())
+                    }
+                    ComponentFuncDef::Import(_) => {
+                        bail!("component start function is an imported function which is not implemented")
+                    }
+                };
+                let options = self.canonical_options(options);
+                let args = args
+                    .iter()
+                    .map(|i| self.value_def(&frame.values[*i]))
+                    .collect();
+                let start = self.result.starts.push(dfg::Start {
+                    ty,
+                    func,
+                    options,
+                    args,
+                    results: *results,
+                });
+                self.result.side_effects.push(dfg::SideEffect::Start(start));
+                for _ in 0..*results {
+                    let index = RuntimeValueIndex::from_u32(self.result.num_runtime_values);
+                    self.result.num_runtime_values += 1;
+                    frame.values.push(ComponentValueDef::Start(index));
+                }
+            }
         }
 
         Ok(None)
     }
 
+    /// Translates the definition of a value into its runtime representation.
+    fn value_def(&mut self, def: &ComponentValueDef<'a>) -> ValueDef {
+        match def {
+            ComponentValueDef::Import(path) => ValueDef::Import(self.runtime_import(path)),
+            ComponentValueDef::Start(index) => ValueDef::Start(*index),
+        }
+    }
+
     /// "Commits" a path of an import to an actual index which is something that
     /// will be calculated at runtime.
     ///
@@ -1092,6 +1166,8 @@ impl<'a> Inliner<'a> {
             }
 
             ComponentItemDef::Type(def) => dfg::Export::Type(def),
+
+            ComponentItemDef::Value(def) => dfg::Export::Value(self.value_def(&def)),
         };
 
         map.insert(name.to_string(), export);
@@ -1129,6 +1205,7 @@ impl<'a> InlinerFrame<'a> {
             module_instances: Default::default(),
             components: Default::default(),
             modules: Default::default(),
+            values: Default::default(),
         }
     }
 
@@ -1144,6 +1221,7 @@ impl<'a> InlinerFrame<'a> {
                 ComponentItemDef::Instance(self.component_instances[i].clone())
             }
             ComponentItem::Module(i) => ComponentItemDef::Module(self.modules[i].clone()),
+            ComponentItem::Value(i) => ComponentItemDef::Value(self.values[i].clone()),
             ComponentItem::Type(t) => {
                 let types_ref = self.translation.types_ref();
                 ComponentItemDef::Type(types.convert_type(types_ref, t)?)
@@ -1167,6 +1245,9 @@ impl<'a> InlinerFrame<'a> {
             ComponentItemDef::Instance(i) => {
                 self.component_instances.push(i);
             }
+            ComponentItemDef::Value(i) => {
+                self.values.push(i);
+            }
 
             // In short, type definitions aren't tracked here.
             //
@@ -1268,6 +1349,7 @@ impl<'a> ComponentItemDef<'a> {
                 ComponentItemDef::Instance(ComponentInstanceDef::Import(path, ty))
             }
             TypeDef::ComponentFunc(_ty) => ComponentItemDef::Func(ComponentFuncDef::Import(path)),
+            TypeDef::Value(_ty) => ComponentItemDef::Value(ComponentValueDef::Import(path)),
             // FIXME(#4283) should commit one way or another to how this
             // should be treated.
             TypeDef::Component(_ty) => bail!("root-level component imports are not supported"),
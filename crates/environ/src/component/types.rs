@@ -69,6 +69,9 @@ indices! {
     /// Index within a component's component function index space.
     pub struct ComponentFuncIndex(u32);
 
+    /// Index within a component's value index space.
+    pub struct ValueIndex(u32);
+
     // ========================================================================
     // These indices are used to lookup type information within a `TypeTables`
     // structure. These represent generally deduplicated type information across
@@ -186,6 +189,13 @@ indices! {
     /// component model.
     pub struct RuntimeImportIndex(u32);
 
+    /// Index that represents a component-level value produced at runtime by a
+    /// component's `start` function.
+    ///
+    /// Values are assigned indices in the order that the `start` functions
+    /// producing them are executed during instantiation.
+    pub struct RuntimeValueIndex(u32);
+
     /// Index that represents a lowered host function and is used to represent
     /// host function lowerings with options and such.
     ///
@@ -230,6 +240,7 @@ pub enum ComponentItem {
     Module(ModuleIndex),
     Component(ComponentIndex),
     ComponentInstance(ComponentInstanceIndex),
+    Value(ValueIndex),
     Type(wasmparser::types::TypeId),
 }
 
@@ -485,7 +496,7 @@ impl ComponentTypesBuilder {
                 types::Type::Resource(_) => TypeDef::Resource(self.resource_id(types, created)),
                 _ => bail!("unsupported type export"),
             },
-            types::ComponentEntityType::Value(_) => bail!("values not supported"),
+            types::ComponentEntityType::Value(ty) => TypeDef::Value(self.valtype(types, &ty)?),
         })
     }
 
@@ -777,6 +788,13 @@ impl ComponentTypesBuilder {
         self.resources.convert(id, &mut self.component_types)
     }
 
+    /// Adds a new component type to this type information.
+    ///
+    /// Note that component types are not interned.
+    pub fn add_component_type(&mut self, ty: TypeComponent) -> TypeComponentIndex {
+        self.component_types.components.push(ty)
+    }
+
     /// Interns a new function type within this type information.
     pub fn add_func_type(&mut self, ty: TypeFunc) -> TypeFuncIndex {
         intern(&mut self.functions, &mut self.component_types.functions, ty)
@@ -939,6 +957,8 @@ pub enum TypeDef {
     ComponentFunc(TypeFuncIndex),
     /// An interface type.
     Interface(InterfaceType),
+    /// A component-level value of the specified interface type.
+    Value(InterfaceType),
     /// A core wasm module and its type.
     Module(TypeModuleIndex),
     /// A core wasm function using only core wasm types.
@@ -54,6 +54,10 @@ pub struct Tunables {
 
     /// Whether or not Wasm functions can be tail-called or not.
     pub tail_callable: bool,
+
+    /// Whether or not functions count how often they're called so that hot
+    /// code can be recompiled with an optimizing compiler.
+    pub tier_up_counters: bool,
 }
 
 impl Default for Tunables {
@@ -112,6 +116,7 @@ impl Default for Tunables {
             debug_adapter_modules: false,
             relaxed_simd_deterministic: false,
             tail_callable: false,
+            tier_up_counters: false,
         }
     }
 }
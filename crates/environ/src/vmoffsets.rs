@@ -12,6 +12,7 @@
 //      store: *mut dyn Store,
 //      builtins: *mut VMBuiltinFunctionsArray,
 //      signature_ids: *const VMSharedSignatureIndex,
+//      tier_up_counters: *mut u32,
 //      imported_functions: [VMFunctionImport; module.num_imported_functions],
 //      imported_tables: [VMTableImport; module.num_imported_tables],
 //      imported_memories: [VMMemoryImport; module.num_imported_memories],
@@ -81,6 +82,7 @@ pub struct VMOffsets<P> {
     store: u32,
     builtin_functions: u32,
     signature_ids: u32,
+    tier_up_counters: u32,
     imported_functions: u32,
     imported_tables: u32,
     imported_memories: u32,
@@ -355,6 +357,7 @@ impl<P: PtrSize> VMOffsets<P> {
             imported_memories: "imported memories",
             imported_tables: "imported tables",
             imported_functions: "imported functions",
+            tier_up_counters: "tier-up counters",
             signature_ids: "module types",
             builtin_functions: "jit builtin functions state",
             store: "jit store state",
@@ -388,6 +391,7 @@ impl<P: PtrSize> From<VMOffsetsFields<P>> for VMOffsets<P> {
             store: 0,
             builtin_functions: 0,
             signature_ids: 0,
+            tier_up_counters: 0,
             imported_functions: 0,
             imported_tables: 0,
             imported_memories: 0,
@@ -439,6 +443,7 @@ impl<P: PtrSize> From<VMOffsetsFields<P>> for VMOffsets<P> {
             size(store) = ret.ptr.size() * 2,
             size(builtin_functions) = ret.pointer_size(),
             size(signature_ids) = ret.ptr.size(),
+            size(tier_up_counters) = ret.ptr.size(),
             size(imported_functions)
                 = cmul(ret.num_imported_functions, ret.size_of_vmfunction_import()),
             size(imported_tables)
@@ -649,6 +654,12 @@ impl<P: PtrSize> VMOffsets<P> {
         self.signature_ids
     }
 
+    /// The offset of the `tier_up_counters` array pointer.
+    #[inline]
+    pub fn vmctx_tier_up_counters(&self) -> u32 {
+        self.tier_up_counters
+    }
+
     /// The offset of the `tables` array.
     #[inline]
     pub fn vmctx_imported_functions_begin(&self) -> u32 {
@@ -28,7 +28,7 @@ const INVALID_PTR: usize = 0xdead_dead_beef_beef_u64 as usize;
 mod libcalls;
 mod resources;
 
-pub use self::resources::{CallContexts, ResourceTable, ResourceTables};
+pub use self::resources::{CallContexts, ResourceHandle, ResourceTable, ResourceTables};
 
 /// Runtime representation of a component instance and all state necessary for
 /// the instance itself.
@@ -568,6 +568,15 @@ impl ComponentInstance {
         &mut self.component_resource_tables
     }
 
+    /// Same as `component_resource_tables` but only provides shared access,
+    /// for example for introspection.
+    #[inline]
+    pub fn component_resource_tables_ref(
+        &self,
+    ) -> &PrimaryMap<TypeResourceTableIndex, ResourceTable> {
+        &self.component_resource_tables
+    }
+
     /// Returns the destructor and instance flags for the specified resource
     /// table type.
     ///
@@ -108,6 +108,21 @@ struct CallContext {
     borrow_count: u32,
 }
 
+/// Information about a single live handle within a `ResourceTable`, as
+/// returned by `ResourceTable::handles`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResourceHandle {
+    /// The index of this handle within its table, as seen by wasm.
+    pub idx: u32,
+    /// The representation of the resource this handle points to.
+    pub rep: u32,
+    /// Whether this is an `own` handle or a `borrow` handle.
+    pub own: bool,
+    /// For `own` handles the number of times it's currently lent out as a
+    /// `borrow`, and always zero for `borrow` handles.
+    pub lend_count: u32,
+}
+
 #[derive(Copy, Clone)]
 struct Lender {
     ty: Option<TypeResourceTableIndex>,
@@ -282,6 +297,32 @@ impl ResourceTable {
         self.next as usize
     }
 
+    /// Returns an iterator over all live handles within this table, in order
+    /// of their index.
+    ///
+    /// This is intended for introspection and debugging and doesn't otherwise
+    /// modify the state of the table.
+    pub fn handles(&self) -> impl Iterator<Item = ResourceHandle> + '_ {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            let idx = u32::try_from(i).unwrap();
+            match *slot {
+                Slot::Free { .. } => None,
+                Slot::Own { rep, lend_count } => Some(ResourceHandle {
+                    idx,
+                    rep,
+                    own: true,
+                    lend_count,
+                }),
+                Slot::Borrow { rep, .. } => Some(ResourceHandle {
+                    idx,
+                    rep,
+                    own: false,
+                    lend_count: 0,
+                }),
+            }
+        })
+    }
+
     fn insert(&mut self, new: Slot) -> u32 {
         let next = self.next();
         if next == self.slots.len() {
@@ -203,6 +203,16 @@ impl MemoryImage {
         }
     }
 
+    /// Creates a standalone image from `data` which, when mapped, will be
+    /// placed `offset` bytes into linear memory.
+    ///
+    /// Both `offset` and the length of `data` must be multiples of the host
+    /// page size. Returns `Ok(None)` if this platform has no way to represent
+    /// the image as a copy-on-write mapping.
+    pub fn from_data(offset: u64, data: &[u8]) -> Result<Option<MemoryImage>> {
+        MemoryImage::new(crate::page_size() as u32, offset, data, None)
+    }
+
     unsafe fn map_at(&self, base: *mut u8) -> Result<()> {
         cfg_if::cfg_if! {
             if #[cfg(all(unix, not(miri)))] {
@@ -432,7 +442,6 @@ impl MemoryImageSlot {
         }
     }
 
-    #[cfg(feature = "pooling-allocator")]
     pub(crate) fn dummy() -> MemoryImageSlot {
         MemoryImageSlot {
             // This pointer isn't ever actually used so its value doesn't
@@ -7,9 +7,9 @@ use crate::externref::VMExternRefActivationsTable;
 use crate::memory::{Memory, RuntimeMemoryCreator};
 use crate::table::{Table, TableElement, TableElementType};
 use crate::vmcontext::{
-    VMBuiltinFunctionsArray, VMContext, VMFuncRef, VMFunctionImport, VMGlobalDefinition,
-    VMGlobalImport, VMMemoryDefinition, VMMemoryImport, VMOpaqueContext, VMRuntimeLimits,
-    VMTableDefinition, VMTableImport,
+    VMArrayCallFunction, VMBuiltinFunctionsArray, VMContext, VMFuncRef, VMFunctionImport,
+    VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition, VMMemoryImport, VMOpaqueContext,
+    VMRuntimeLimits, VMTableDefinition, VMTableImport,
 };
 use crate::{
     ExportFunction, ExportGlobal, ExportMemory, ExportTable, Imports, ModuleRuntimeInfo,
@@ -27,10 +27,11 @@ use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::{mem, ptr};
 use wasmtime_environ::{
-    packed_option::ReservedValue, DataIndex, DefinedGlobalIndex, DefinedMemoryIndex,
-    DefinedTableIndex, ElemIndex, EntityIndex, EntityRef, EntitySet, FuncIndex, GlobalIndex,
-    GlobalInit, HostPtr, MemoryIndex, MemoryPlan, Module, PrimaryMap, SignatureIndex, TableIndex,
-    TableInitialValue, Trap, VMOffsets, WasmHeapType, WasmRefType, WasmType, VMCONTEXT_MAGIC,
+    packed_option::ReservedValue, DataIndex, DefinedFuncIndex, DefinedGlobalIndex,
+    DefinedMemoryIndex, DefinedTableIndex, ElemIndex, EntityIndex, EntityRef, EntitySet, FuncIndex,
+    GlobalIndex, GlobalInit, HostPtr, MemoryIndex, MemoryPlan, Module, PrimaryMap, SignatureIndex,
+    TableIndex, TableInitialValue, Trap, VMOffsets, WasmHeapType, WasmRefType, WasmType,
+    VMCONTEXT_MAGIC,
 };
 #[cfg(feature = "wmemcheck")]
 use wasmtime_wmemcheck::Wmemcheck;
@@ -777,6 +778,63 @@ impl Instance {
         }
     }
 
+    /// Invoked by compiled code once the function `index` has been called
+    /// often enough to be recompiled.
+    pub(crate) fn tier_up(&mut self, index: DefinedFuncIndex) {
+        self.runtime_info.function_hot(index)
+    }
+
+    /// Switches this instance over to the code described by `runtime_info`,
+    /// which must have been compiled from the same module as the code that
+    /// this instance currently runs.
+    ///
+    /// The code pointers of imported functions are passed through `redirect`,
+    /// which maps the address of code in the previous tier to the address of
+    /// its equivalent in the new one, and all escaped functions' `VMFuncRef`s
+    /// are reconstructed.
+    ///
+    /// # Unsafety
+    ///
+    /// None of this instance's code may be executing, and `redirect` must map
+    /// every address of code which isn't compatible with the new code.
+    unsafe fn switch_code(
+        &mut self,
+        runtime_info: Arc<dyn ModuleRuntimeInfo>,
+        redirect: &dyn Fn(usize) -> usize,
+    ) {
+        assert_eq!(
+            self.offsets().size_of_vmctx(),
+            runtime_info.offsets().size_of_vmctx()
+        );
+        self.runtime_info = runtime_info;
+
+        let offsets = *self.offsets();
+        let signatures = self.runtime_info.signature_ids().as_ptr();
+        *self.vmctx_plus_offset_mut(offsets.vmctx_signature_ids_array()) = signatures;
+        *self.vmctx_plus_offset_mut(offsets.vmctx_tier_up_counters()) =
+            self.runtime_info.tier_up_counters();
+
+        for i in 0..self.module().num_imported_funcs {
+            let index = FuncIndex::new(i);
+            let import: *mut VMFunctionImport =
+                self.vmctx_plus_offset_mut(offsets.vmctx_vmfunction_import(index));
+            let import = &mut *import;
+            import.wasm_call =
+                NonNull::new(redirect(import.wasm_call.as_ptr() as usize) as *mut _).unwrap();
+            import.native_call =
+                NonNull::new(redirect(import.native_call.as_ptr() as usize) as *mut _).unwrap();
+            import.array_call =
+                mem::transmute::<usize, VMArrayCallFunction>(redirect(import.array_call as usize));
+        }
+
+        let module = self.module().clone();
+        for (index, func) in module.functions.iter() {
+            if func.is_escaping() {
+                self.get_func_ref(index);
+            }
+        }
+    }
+
     /// The `table.init` operation: initializes a portion of a table with a
     /// passive element.
     ///
@@ -1123,6 +1181,10 @@ impl Instance {
         let signatures = self.runtime_info.signature_ids();
         *self.vmctx_plus_offset_mut(offsets.vmctx_signature_ids_array()) = signatures.as_ptr();
 
+        // Initialize the call counters used by tiered compilation
+        *self.vmctx_plus_offset_mut(offsets.vmctx_tier_up_counters()) =
+            self.runtime_info.tier_up_counters();
+
         // Initialize the built-in functions
         *self.vmctx_plus_offset_mut(offsets.vmctx_builtin_functions()) =
             &VMBuiltinFunctionsArray::INIT;
@@ -1313,6 +1375,21 @@ impl InstanceHandle {
         self.instance().module()
     }
 
+    /// Switches this instance over to the code described by `runtime_info`,
+    /// redirecting the code pointers of its imports with `redirect`.
+    ///
+    /// # Unsafety
+    ///
+    /// None of this instance's code may be executing, and `redirect` must map
+    /// every address of code which isn't compatible with the new code.
+    pub unsafe fn switch_code(
+        &mut self,
+        runtime_info: Arc<dyn ModuleRuntimeInfo>,
+        redirect: &dyn Fn(usize) -> usize,
+    ) {
+        self.instance_mut().switch_code(runtime_info, redirect)
+    }
+
     /// Lookup a function by index.
     pub fn get_exported_func(&mut self, export: FuncIndex) -> ExportFunction {
         self.instance_mut().get_exported_func(export)
@@ -1357,6 +1434,11 @@ impl InstanceHandle {
         self.instance().host_state()
     }
 
+    /// Get a memory defined locally within this module.
+    pub fn get_defined_memory(&mut self, index: DefinedMemoryIndex) -> *mut Memory {
+        self.instance_mut().get_defined_memory(index)
+    }
+
     /// Get a table defined locally within this module.
     pub fn get_defined_table(&mut self, index: DefinedTableIndex) -> *mut Table {
         self.instance_mut().get_defined_table(index)
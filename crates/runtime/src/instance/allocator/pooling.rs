@@ -88,6 +88,7 @@ use memory_pool::MemoryPool;
 use std::{
     mem,
     sync::atomic::{AtomicU64, Ordering},
+    sync::Arc,
 };
 use table_pool::TablePool;
 use wasmtime_environ::{
@@ -257,7 +258,7 @@ pub struct PoolingInstanceAllocator {
     live_core_instances: AtomicU64,
     live_component_instances: AtomicU64,
 
-    memories: MemoryPool,
+    memories: Arc<MemoryPool>,
     tables: TablePool,
 
     #[cfg(all(feature = "async", unix, not(miri)))]
@@ -291,7 +292,7 @@ impl PoolingInstanceAllocator {
             limits: config.limits,
             live_component_instances: AtomicU64::new(0),
             live_core_instances: AtomicU64::new(0),
-            memories: MemoryPool::new(config, tunables)?,
+            memories: Arc::new(MemoryPool::new(config, tunables)?),
             tables: TablePool::new(config)?,
             #[cfg(all(feature = "async", unix, not(miri)))]
             stacks: StackPool::new(config)?,
@@ -426,7 +427,8 @@ unsafe impl InstanceAllocatorImpl for PoolingInstanceAllocator {
                 | ExtractMemory(_)
                 | ExtractRealloc(_)
                 | ExtractPostReturn(_)
-                | Resource(_) => {}
+                | Resource(_)
+                | Start(_) => {}
             }
         }
 
@@ -62,6 +62,7 @@ use super::{
     index_allocator::{MemoryInModule, ModuleAffinityIndexAllocator, SlotId},
     MemoryAllocationIndex,
 };
+use crate::memory::ReleaseStaticMemory;
 use crate::mpk::{self, ProtectionKey, ProtectionMask};
 use crate::{
     CompiledModuleId, InstanceAllocationRequest, InstanceLimits, Memory, MemoryImageSlot, Mmap,
@@ -70,7 +71,7 @@ use crate::{
 use anyhow::{anyhow, bail, Context, Result};
 use libc::c_void;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use wasmtime_environ::{
     DefinedMemoryIndex, MemoryPlan, MemoryStyle, Module, Tunables, WASM_PAGE_SIZE,
 };
@@ -284,6 +285,13 @@ impl MemoryPool {
                 }
                 MemoryStyle::Dynamic { .. } => {}
             }
+            if plan.memory.shared && self.stripes.len() > 1 {
+                bail!(
+                    "memory index {} is a shared memory which is not supported \
+                     when memory protection keys are in use",
+                    i.as_u32(),
+                );
+            }
             if plan.memory.minimum > u64::try_from(max_memory_pages).unwrap() {
                 bail!(
                     "memory index {} has a minimum page size of {} which exceeds the limit of {}",
@@ -303,7 +311,7 @@ impl MemoryPool {
 
     /// Allocate a single memory for the given instance allocation request.
     pub fn allocate(
-        &self,
+        self: &Arc<Self>,
         request: &mut InstanceAllocationRequest,
         memory_plan: &MemoryPlan,
         memory_index: DefinedMemoryIndex,
@@ -369,6 +377,21 @@ impl MemoryPool {
             // else to come in and map something.
             slot.instantiate(initial_size as usize, image, memory_plan)?;
 
+            // Shared memories can be kept alive by other threads after the
+            // instance that allocated them is deallocated, so rather than
+            // reclaiming their slot in `deallocate` it's reclaimed when the
+            // last reference to the memory is dropped. The pool is kept alive
+            // until then through the reference held here.
+            let release = if memory_plan.memory.shared {
+                let pool = self.clone();
+                let release: ReleaseStaticMemory = Box::new(move |image| unsafe {
+                    pool.release(allocation_index, image);
+                });
+                Some(release)
+            } else {
+                None
+            };
+
             Memory::new_static(
                 memory_plan,
                 base_ptr,
@@ -376,6 +399,7 @@ impl MemoryPool {
                 slot,
                 self.layout.slot_bytes,
                 unsafe { &mut *request.store.get().unwrap() },
+                release,
             )
         })() {
             Ok(memory) => Ok((allocation_index, memory)),
@@ -395,9 +419,23 @@ impl MemoryPool {
     /// The memory must have been previously allocated from this pool and
     /// assigned the given index, must currently be in an allocated state, and
     /// must never be used again.
-    pub unsafe fn deallocate(&self, allocation_index: MemoryAllocationIndex, memory: Memory) {
-        let mut image = memory.unwrap_static_image();
+    pub unsafe fn deallocate(&self, allocation_index: MemoryAllocationIndex, mut memory: Memory) {
+        // Shared memories return their slot to the pool themselves once the
+        // last reference to them is dropped, which may well be after this
+        // point; see `allocate`.
+        if memory.as_shared_memory().is_some() {
+            return;
+        }
+        self.release(allocation_index, memory.unwrap_static_image());
+    }
 
+    /// Reset the image slot of a memory and make its index available for
+    /// allocation again.
+    ///
+    /// # Safety
+    ///
+    /// Same as `deallocate`, and `image` must be the image slot of that memory.
+    unsafe fn release(&self, allocation_index: MemoryAllocationIndex, mut image: MemoryImageSlot) {
         // Reset the image slot. If there is any error clearing the
         // image, just drop it here, and let the drop handler for the
         // slot unmap in a way that retains the address space
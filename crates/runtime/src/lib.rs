@@ -211,6 +211,15 @@ pub trait ModuleRuntimeInfo: Send + Sync + 'static {
 
     /// Offset information for the current host.
     fn offsets(&self) -> &VMOffsets<HostPtr>;
+
+    /// Returns the per-function call counters, indexed by `DefinedFuncIndex`,
+    /// which compiled code decrements when `Tunables::tier_up_counters` is
+    /// enabled, or null if the code doesn't count calls.
+    fn tier_up_counters(&self) -> *mut u32;
+
+    /// Invoked through the `tier_up` builtin once the call counter of the
+    /// function `index` reaches zero.
+    fn function_hot(&self, index: DefinedFuncIndex);
 }
 
 /// Returns the host OS page size, in bytes.
@@ -66,7 +66,7 @@ use std::mem;
 use std::ptr::{self, NonNull};
 use std::time::{Duration, Instant};
 use wasmtime_environ::{
-    DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, TableIndex, Trap,
+    DataIndex, DefinedFuncIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, TableIndex, Trap,
 };
 #[cfg(feature = "wmemcheck")]
 use wasmtime_wmemcheck::AccessError::{
@@ -495,6 +495,11 @@ unsafe fn new_epoch(instance: &mut Instance) -> Result<u64> {
     (*instance.store()).new_epoch()
 }
 
+// Hook for when a function has been called often enough to be recompiled.
+fn tier_up(instance: &mut Instance, func: u32) {
+    instance.tier_up(DefinedFuncIndex::from_u32(func))
+}
+
 cfg_if! {
     if #[cfg(feature = "wmemcheck")] {
         // Hook for validating malloc using wmemcheck_state.
@@ -381,8 +381,16 @@ struct StaticMemory {
     /// The image management, if any, for this memory. Owned here and
     /// returned to the pooling allocator when termination occurs.
     memory_image: MemoryImageSlot,
+
+    /// For shared memories, which may outlive the instance that allocated
+    /// them, the callback which returns this memory's slot to the pooling
+    /// allocator once the memory is dropped.
+    release: Option<ReleaseStaticMemory>,
 }
 
+/// A callback used to return the slot of a static memory to its pool.
+pub type ReleaseStaticMemory = Box<dyn FnOnce(MemoryImageSlot) + Send + Sync>;
+
 impl StaticMemory {
     fn new(
         base_ptr: *mut u8,
@@ -391,6 +399,7 @@ impl StaticMemory {
         maximum_size: Option<usize>,
         memory_image: MemoryImageSlot,
         memory_and_guard_size: usize,
+        release: Option<ReleaseStaticMemory>,
     ) -> Result<Self> {
         if base_capacity < initial_size {
             bail!(
@@ -413,10 +422,22 @@ impl StaticMemory {
             size: initial_size,
             memory_image,
             memory_and_guard_size,
+            release,
         })
     }
 }
 
+impl Drop for StaticMemory {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release(std::mem::replace(
+                &mut self.memory_image,
+                MemoryImageSlot::dummy(),
+            ));
+        }
+    }
+}
+
 impl RuntimeLinearMemory for StaticMemory {
     fn byte_size(&self) -> usize {
         self.size
@@ -696,6 +717,12 @@ impl Memory {
     }
 
     /// Create a new static (immovable) memory instance for the specified plan.
+    ///
+    /// Shared memories may be kept alive by other threads after the instance
+    /// that allocated them is gone, so for those the owner of `base_ptr`
+    /// provides a `release` callback which is invoked with `memory_image` once
+    /// the last reference to the memory is dropped. The callback is not
+    /// invoked if this function returns an error.
     pub fn new_static(
         plan: &MemoryPlan,
         base_ptr: *mut u8,
@@ -703,7 +730,12 @@ impl Memory {
         memory_image: MemoryImageSlot,
         memory_and_guard_size: usize,
         store: &mut dyn Store,
+        release: Option<ReleaseStaticMemory>,
     ) -> Result<Self> {
+        assert_eq!(plan.memory.shared, release.is_some());
+        if plan.memory.shared && !matches!(plan.style, MemoryStyle::Static { .. }) {
+            bail!("shared memory can only be built from a static memory allocation")
+        }
         let (minimum, maximum) = Self::limit_new(plan, Some(store))?;
         let pooled_memory = StaticMemory::new(
             base_ptr,
@@ -712,14 +744,14 @@ impl Memory {
             maximum,
             memory_image,
             memory_and_guard_size,
+            release,
         )?;
         let allocation = Box::new(pooled_memory);
         let allocation: Box<dyn RuntimeLinearMemory> = if plan.memory.shared {
-            // FIXME: since the pooling allocator owns the memory allocation
-            // (which is torn down with the instance), the current shared memory
-            // implementation will cause problems; see
-            // https://github.com/bytecodealliance/wasmtime/issues/4244.
-            todo!("using shared memory with the pooling allocator is a work in progress");
+            // Note that this can't fail given the checks above, which is
+            // important as otherwise `release` would run and the caller would
+            // also clean up after the failed allocation.
+            Box::new(SharedMemory::wrap(plan, allocation, plan.memory)?)
         } else {
             allocation
         };
@@ -837,7 +869,7 @@ impl Memory {
     /// Returns whether or not this memory needs initialization. It
     /// may not if it already has initial content thanks to a CoW
     /// mechanism.
-    pub(crate) fn needs_init(&self) -> bool {
+    pub fn needs_init(&self) -> bool {
         self.0.needs_init()
     }
 
@@ -0,0 +1,57 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Files from the read-only image are visible, and can be modified as
+    // they're copied into the writable layer.
+    assert_eq!(fs::read_to_string("/etc/motd")?, "hello from the image");
+    OpenOptions::new()
+        .append(true)
+        .open("/etc/motd")?
+        .write_all(b" and the tenant")?;
+    assert_eq!(
+        fs::read_to_string("/etc/motd")?,
+        "hello from the image and the tenant"
+    );
+
+    fs::write("/data/new.txt", "new")?;
+    fs::rename("/data/new.txt", "/data/renamed.txt")?;
+    assert_eq!(fs::read_to_string("/data/renamed.txt")?, "new");
+    fs::remove_file("/etc/passwd")?;
+
+    // Writes which don't fit within the writable layer's limit fail without
+    // affecting the host.
+    let big = File::create("/data/big.bin")?;
+    assert!(big.set_len(1 << 40).is_err());
+    assert!(big.set_len(u64::MAX).is_err());
+    assert!((&big).write_all(&[0; 8192]).is_err());
+    assert_eq!(big.metadata()?.len(), 0);
+    drop(big);
+    fs::remove_file("/data/big.bin")?;
+
+    assert_eq!(
+        ["/data", "/etc"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>(),
+        fs::read_dir("/")?
+            .map(|r| r.map(|d| d.path()))
+            .collect::<Result<_, _>>()?
+    );
+    assert_eq!(
+        ["/etc/motd"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>(),
+        fs::read_dir("/etc")?
+            .map(|r| r.map(|d| d.path()))
+            .collect::<Result<_, _>>()?
+    );
+
+    Ok(())
+}
@@ -71,20 +71,30 @@ impl<T: WasiHttpView> outgoing_handler::Host for T {
             format!("{}:{port}", req.authority)
         };
 
+        let mut headers = req.headers;
+        if let Some(policy) = self.outgoing_policy() {
+            if let Err(e) = policy.check_request(&authority, &mut headers) {
+                return Ok(Err(e));
+            }
+        }
+
         let mut builder = hyper::Request::builder()
             .method(method)
             .uri(format!("{scheme}{authority}{}", req.path_with_query))
             .header(hyper::header::HOST, &authority);
 
-        for (k, v) in req.headers.iter() {
+        for (k, v) in headers.iter() {
             builder = builder.header(k, v);
         }
 
-        let body = req.body.unwrap_or_else(|| {
+        let mut body = req.body.unwrap_or_else(|| {
             Empty::<Bytes>::new()
                 .map_err(|_| anyhow::anyhow!("empty error"))
                 .boxed()
         });
+        if let Some(policy) = self.outgoing_policy() {
+            body = policy.limit_body(body);
+        }
 
         let request = builder.body(body).map_err(types::http_protocol_error)?;
 
@@ -2,7 +2,10 @@ pub use crate::types::{WasiHttpCtx, WasiHttpView};
 
 pub mod body;
 pub mod http_impl;
+pub mod policy;
+pub mod pool;
 pub mod proxy;
+pub mod record;
 pub mod types;
 pub mod types_impl;
 
@@ -0,0 +1,332 @@
+//! Restrictions on the outgoing requests a guest is allowed to make.
+//!
+//! An [`OutgoingPolicy`] is installed on a store by keeping a
+//! [`PolicyState`] for it in the store's data and returning it from
+//! [`WasiHttpView::outgoing_policy`]. It's enforced for every request made
+//! through `wasi:http/outgoing-handler`, before it reaches
+//! [`WasiHttpView::send_request`].
+//!
+//! [`WasiHttpView::outgoing_policy`]: crate::WasiHttpView::outgoing_policy
+//! [`WasiHttpView::send_request`]: crate::WasiHttpView::send_request
+
+use crate::bindings::http::types::Error;
+use crate::types::FieldMap;
+use anyhow::{anyhow, bail, Result};
+use bytes::Bytes;
+use http_body::{Body, Frame};
+use http_body_util::{combinators::BoxBody, BodyExt};
+use hyper::header::{self, HeaderName, HeaderValue};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Headers which only make sense for a single connection, or which are
+/// already set by the implementation, and which guests therefore can't set.
+fn hop_by_hop_headers() -> Vec<HeaderName> {
+    vec![
+        header::CONNECTION,
+        header::HOST,
+        header::PROXY_AUTHENTICATE,
+        header::PROXY_AUTHORIZATION,
+        header::TE,
+        header::TRAILER,
+        header::TRANSFER_ENCODING,
+        header::UPGRADE,
+        HeaderName::from_static("keep-alive"),
+    ]
+}
+
+/// Restrictions applied to the outgoing requests made by a guest.
+///
+/// A new policy allows requests to any authority and only strips
+/// hop-by-hop headers, such as `Connection`, from them.
+#[derive(Clone)]
+pub struct OutgoingPolicy {
+    allowed: Option<Vec<AllowedAuthority>>,
+    forbidden_headers: Vec<HeaderName>,
+    set_headers: Vec<(HeaderName, HeaderValue)>,
+    max_requests: Option<u64>,
+    max_bytes: Option<u64>,
+}
+
+#[derive(Clone)]
+struct AllowedAuthority {
+    /// The lowercase host, or the suffix including the leading `.` if
+    /// subdomains are matched.
+    host: String,
+    subdomains: bool,
+    port: Option<u16>,
+}
+
+impl OutgoingPolicy {
+    /// Creates a policy which allows all requests.
+    pub fn new() -> Self {
+        OutgoingPolicy {
+            allowed: None,
+            forbidden_headers: hop_by_hop_headers(),
+            set_headers: Vec::new(),
+            max_requests: None,
+            max_bytes: None,
+        }
+    }
+
+    /// Allows requests to the authorities matching `pattern`.
+    ///
+    /// Once this has been called, requests are only allowed to authorities
+    /// which match one of the patterns. Patterns take the form
+    /// `HOST[:PORT]`, where a missing port matches any port and a host
+    /// starting with `*.` matches all of its subdomains, for example
+    /// `*.example.com:443`.
+    pub fn allow(&mut self, pattern: &str) -> Result<&mut Self> {
+        let (host, port) = split_authority(pattern);
+        let port = match port {
+            Some(port) => Some(
+                port.parse()
+                    .map_err(|_| anyhow!("invalid port in `{pattern}`"))?,
+            ),
+            None => None,
+        };
+        let (host, subdomains) = match host.strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') => (suffix, true),
+            _ => (host, false),
+        };
+        if host.is_empty() || host.contains('*') {
+            bail!("invalid host in `{pattern}`");
+        }
+        self.allowed
+            .get_or_insert_with(Vec::new)
+            .push(AllowedAuthority {
+                host: host.to_ascii_lowercase(),
+                subdomains,
+                port,
+            });
+        Ok(self)
+    }
+
+    /// Removes the header `name` from all requests.
+    pub fn forbid_header(&mut self, name: HeaderName) -> &mut Self {
+        self.forbidden_headers.push(name);
+        self
+    }
+
+    /// Sets the header `name` to `value` on all requests, replacing any
+    /// value set by the guest.
+    pub fn set_header(&mut self, name: HeaderName, value: HeaderValue) -> &mut Self {
+        self.set_headers.push((name, value));
+        self
+    }
+
+    /// Limits the number of requests which can be made.
+    pub fn max_requests(&mut self, max: u64) -> &mut Self {
+        self.max_requests = Some(max);
+        self
+    }
+
+    /// Limits the number of body bytes which can be sent and received,
+    /// counting both request and response bodies.
+    ///
+    /// Bodies which exceed this limit fail with an error.
+    pub fn max_bytes(&mut self, max: u64) -> &mut Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    /// Returns whether requests can be made to `authority`, which is of the
+    /// form `HOST:PORT`.
+    pub fn is_allowed(&self, authority: &str) -> bool {
+        let allowed = match &self.allowed {
+            Some(allowed) => allowed,
+            None => return true,
+        };
+        let (host, port) = split_authority(authority);
+        let host = host.to_ascii_lowercase();
+        let port = port.and_then(|p| p.parse::<u16>().ok());
+        allowed.iter().any(|a| {
+            let host_matches = if a.subdomains {
+                host.ends_with(&a.host)
+            } else {
+                host == a.host
+            };
+            host_matches && (a.port.is_none() || a.port == port)
+        })
+    }
+}
+
+impl Default for OutgoingPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits an authority into its host and port, taking care of IPv6
+/// addresses in brackets.
+fn split_authority(authority: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = authority.strip_prefix('[') {
+        if let Some((host, rest)) = rest.split_once(']') {
+            return (host, rest.strip_prefix(':'));
+        }
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    }
+}
+
+/// An [`OutgoingPolicy`] along with the usage of its quotas by one store.
+pub struct PolicyState {
+    policy: Arc<OutgoingPolicy>,
+    requests: u64,
+    bytes: Arc<AtomicU64>,
+}
+
+impl PolicyState {
+    /// Starts tracking the usage of `policy`'s quotas, which are all unused.
+    pub fn new(policy: Arc<OutgoingPolicy>) -> Self {
+        PolicyState {
+            policy,
+            requests: 0,
+            bytes: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Checks whether a request to `authority` is allowed, counting it
+    /// against the request quota and rewriting its `headers` if so.
+    pub(crate) fn check_request(
+        &mut self,
+        authority: &str,
+        headers: &mut FieldMap,
+    ) -> Result<(), Error> {
+        if !self.policy.is_allowed(authority) {
+            return Err(Error::UnexpectedError(format!(
+                "outgoing requests to `{authority}` are not allowed"
+            )));
+        }
+        if let Some(max) = self.policy.max_requests {
+            if self.requests >= max {
+                return Err(Error::UnexpectedError(format!(
+                    "outgoing request quota of {max} exceeded"
+                )));
+            }
+        }
+        self.requests += 1;
+
+        for name in self.policy.forbidden_headers.iter() {
+            headers.remove(name);
+        }
+        for (name, value) in self.policy.set_headers.iter() {
+            headers.insert(name.clone(), value.clone());
+        }
+        Ok(())
+    }
+
+    /// Wraps `body` so its bytes are counted against the byte quota.
+    pub(crate) fn limit_body(
+        &self,
+        body: BoxBody<Bytes, anyhow::Error>,
+    ) -> BoxBody<Bytes, anyhow::Error> {
+        match self.policy.max_bytes {
+            Some(max) => LimitedBody {
+                body,
+                used: self.bytes.clone(),
+                max,
+            }
+            .boxed(),
+            None => body,
+        }
+    }
+}
+
+struct LimitedBody {
+    body: BoxBody<Bytes, anyhow::Error>,
+    used: Arc<AtomicU64>,
+    max: u64,
+}
+
+impl Body for LimitedBody {
+    type Data = Bytes;
+    type Error = anyhow::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, anyhow::Error>>> {
+        let frame = match Pin::new(&mut self.body).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => frame,
+            other => return other,
+        };
+        if let Some(data) = frame.data_ref() {
+            let len = data.len() as u64;
+            let used = self.used.fetch_add(len, Ordering::Relaxed) + len;
+            if used > self.max {
+                return Poll::Ready(Some(Err(anyhow!(Error::ProtocolError(format!(
+                    "outgoing HTTP byte quota of {} exceeded",
+                    self.max
+                ))))));
+            }
+        }
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allow_list() -> Result<()> {
+        let mut policy = OutgoingPolicy::new();
+        assert!(policy.is_allowed("anything.test:80"));
+
+        policy
+            .allow("example.com:443")?
+            .allow("*.internal")?
+            .allow("[::1]")?;
+        assert!(policy.is_allowed("example.com:443"));
+        assert!(policy.is_allowed("EXAMPLE.com:443"));
+        assert!(!policy.is_allowed("example.com:80"));
+        assert!(!policy.is_allowed("www.example.com:443"));
+        assert!(policy.is_allowed("api.internal:8080"));
+        assert!(!policy.is_allowed("internal:8080"));
+        assert!(policy.is_allowed("[::1]:3000"));
+
+        assert!(OutgoingPolicy::new().allow("example.com:http").is_err());
+        assert!(OutgoingPolicy::new().allow("*example.com").is_err());
+        assert!(OutgoingPolicy::new().allow(":80").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn quotas_and_headers() {
+        let mut policy = OutgoingPolicy::new();
+        policy
+            .max_requests(1)
+            .forbid_header(HeaderName::from_static("x-secret"))
+            .set_header(header::USER_AGENT, HeaderValue::from_static("wasmtime"));
+        let mut state = PolicyState::new(Arc::new(policy));
+
+        let mut headers = FieldMap::new();
+        headers.insert("x-secret", HeaderValue::from_static("hunter2"));
+        headers.insert(header::HOST, HeaderValue::from_static("elsewhere"));
+        headers.insert(header::USER_AGENT, HeaderValue::from_static("guest"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+        state.check_request("example.com:80", &mut headers).unwrap();
+        assert!(headers.get("x-secret").is_none());
+        assert!(headers.get(header::HOST).is_none());
+        assert_eq!(headers[header::USER_AGENT], "wasmtime");
+        assert_eq!(headers[header::ACCEPT], "*/*");
+
+        let err = state
+            .check_request("example.com:80", &mut FieldMap::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::UnexpectedError(_)));
+    }
+}
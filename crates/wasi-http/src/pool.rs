@@ -0,0 +1,470 @@
+//! Reuse of connections between outgoing requests.
+//!
+//! Requests sent with [`default_send_request`] go through the
+//! [`ConnectionPool`] returned by [`WasiHttpView::connection_pool`], which
+//! keeps connections open once a response has been received so that later
+//! requests to the same authority don't need to connect again. On TLS
+//! connections HTTP/2 is offered with ALPN, and if the server accepts it a
+//! single connection is shared by all requests to that authority.
+//!
+//! A pooled connection may have been closed by the server while it was idle.
+//! If that's noticed before the request is handed to the connection then the
+//! request is sent over a new connection instead. Requests which fail after
+//! being handed over aren't retried, as their body may already have been
+//! consumed.
+//!
+//! A pool is usually kept in the data of a store, and can be shared between
+//! stores, for example by all the stores created to handle incoming
+//! requests, by cloning it.
+//!
+//! [`default_send_request`]: crate::types::default_send_request
+//! [`WasiHttpView::connection_pool`]: crate::WasiHttpView::connection_pool
+
+use crate::body::{HyperIncomingBody, HyperOutgoingBody};
+use crate::types::{hyper_protocol_error, invalid_url, timeout_error};
+use anyhow::anyhow;
+use http_body_util::BodyExt;
+use hyper::client::conn::{http1, http2};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// Configuration of a [`ConnectionPool`].
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    max_idle_per_authority: usize,
+    idle_timeout: Duration,
+    http2: bool,
+    root_certificates: Vec<Vec<u8>>,
+}
+
+impl PoolConfig {
+    /// Creates the default configuration, which keeps up to 8 idle
+    /// connections to each authority open for 90 seconds and offers HTTP/2
+    /// on TLS connections.
+    pub fn new() -> Self {
+        PoolConfig {
+            max_idle_per_authority: 8,
+            idle_timeout: Duration::from_secs(90),
+            http2: true,
+            root_certificates: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of idle connections kept open to each
+    /// authority.
+    ///
+    /// Connections are closed once they're idle and the limit is reached.
+    /// Setting this to zero disables the reuse of connections.
+    pub fn max_idle_per_authority(&mut self, max: usize) -> &mut Self {
+        self.max_idle_per_authority = max;
+        self
+    }
+
+    /// Sets how long a connection can be idle before it's closed.
+    pub fn idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Sets whether HTTP/2 is offered with ALPN on TLS connections.
+    ///
+    /// Plaintext connections always use HTTP/1.1.
+    pub fn http2(&mut self, enable: bool) -> &mut Self {
+        self.http2 = enable;
+        self
+    }
+
+    /// Trusts the DER-encoded certificate `der` as a root for TLS
+    /// connections, in addition to the Mozilla root certificates which are
+    /// always trusted.
+    pub fn add_root_certificate(&mut self, der: impl Into<Vec<u8>>) -> &mut Self {
+        self.root_certificates.push(der.into());
+        self
+    }
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pool of connections used to send outgoing requests.
+///
+/// Clones of a pool share the same connections.
+#[derive(Clone, Default)]
+pub struct ConnectionPool {
+    inner: Arc<PoolInner>,
+}
+
+#[derive(Default)]
+struct PoolInner {
+    config: PoolConfig,
+    idle: Mutex<HashMap<PoolKey, Vec<Idle>>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PoolKey {
+    use_tls: bool,
+    authority: String,
+}
+
+struct Idle {
+    sender: Sender,
+    since: Instant,
+}
+
+enum Sender {
+    Http1(http1::SendRequest<HyperOutgoingBody>),
+    Http2(http2::SendRequest<HyperOutgoingBody>),
+}
+
+impl Sender {
+    fn is_ready(&self) -> bool {
+        match self {
+            Sender::Http1(sender) => sender.is_ready(),
+            Sender::Http2(sender) => sender.is_ready(),
+        }
+    }
+
+    async fn ready(&mut self) -> hyper::Result<()> {
+        match self {
+            Sender::Http1(sender) => sender.ready().await,
+            Sender::Http2(sender) => sender.ready().await,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        match self {
+            Sender::Http1(sender) => sender.is_closed(),
+            Sender::Http2(sender) => sender.is_closed(),
+        }
+    }
+
+    async fn send_request(
+        &mut self,
+        request: hyper::Request<HyperOutgoingBody>,
+    ) -> hyper::Result<hyper::Response<hyper::body::Incoming>> {
+        match self {
+            Sender::Http1(sender) => sender.send_request(request).await,
+            Sender::Http2(sender) => sender.send_request(request).await,
+        }
+    }
+}
+
+impl ConnectionPool {
+    /// Creates an empty pool.
+    pub fn new(config: PoolConfig) -> Self {
+        ConnectionPool {
+            inner: Arc::new(PoolInner {
+                config,
+                idle: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Returns the number of idle connections in the pool.
+    ///
+    /// HTTP/2 connections are counted as idle even while they have requests
+    /// in flight, as they can still be used for more requests.
+    pub fn idle_connections(&self) -> usize {
+        let mut idle = self.inner.idle.lock().unwrap();
+        self.prune(&mut idle);
+        idle.values().map(|conns| conns.len()).sum()
+    }
+
+    /// Closes all of the idle connections in the pool.
+    pub fn clear(&self) {
+        self.inner.idle.lock().unwrap().clear();
+    }
+
+    /// Sends `request` to `authority` over a pooled connection, or over a
+    /// new one if none is available.
+    ///
+    /// The timeouts are the same as those of [`OutgoingRequest`], where
+    /// `connect_timeout` only applies if a new connection is made.
+    ///
+    /// [`OutgoingRequest`]: crate::types::OutgoingRequest
+    pub(crate) async fn send_request(
+        &self,
+        use_tls: bool,
+        authority: &str,
+        request: hyper::Request<HyperOutgoingBody>,
+        connect_timeout: Duration,
+        first_byte_timeout: Duration,
+    ) -> anyhow::Result<hyper::Response<HyperIncomingBody>> {
+        let key = PoolKey {
+            use_tls,
+            authority: authority.to_string(),
+        };
+        let mut sender = match self.checkout(&key) {
+            Some(mut sender) => match sender.ready().await {
+                Ok(()) => sender,
+                // The connection was closed while it was idle, and the
+                // request hasn't been sent yet, so try once more on a new
+                // connection.
+                Err(e) => {
+                    tracing::debug!("pooled connection to {authority} failed, reconnecting: {e}");
+                    self.connect(&key, connect_timeout).await?
+                }
+            },
+            None => self.connect(&key, connect_timeout).await?,
+        };
+
+        let resp = timeout(first_byte_timeout, sender.send_request(request))
+            .await
+            .map_err(|_| timeout_error("first byte"))?
+            .map_err(hyper_protocol_error)?;
+
+        // An HTTP/1.1 connection can only be reused once the response body
+        // has been read, at which point it becomes ready again.
+        if let Sender::Http1(mut sender) = sender {
+            let pool = self.clone();
+            tokio::task::spawn(async move {
+                if sender.ready().await.is_ok() {
+                    pool.insert(key, Sender::Http1(sender));
+                }
+            });
+        }
+
+        Ok(resp.map(|body| body.map_err(|e| anyhow!(e)).boxed()))
+    }
+
+    /// Opens a new connection to `key`.
+    async fn connect(&self, key: &PoolKey, connect_timeout: Duration) -> anyhow::Result<Sender> {
+        let sender = timeout(
+            connect_timeout,
+            connect(key.use_tls, &key.authority, &self.inner.config),
+        )
+        .await
+        .map_err(|_| timeout_error("connection"))??;
+        // HTTP/2 connections are shared by concurrent requests, so they're
+        // made available as soon as they're established.
+        if let Sender::Http2(sender) = &sender {
+            self.insert(key.clone(), Sender::Http2(sender.clone()));
+        }
+        Ok(sender)
+    }
+
+    /// Takes a connection to `key` out of the pool, or clones it if it's an
+    /// HTTP/2 connection.
+    fn checkout(&self, key: &PoolKey) -> Option<Sender> {
+        let mut idle = self.inner.idle.lock().unwrap();
+        self.prune(&mut idle);
+        let conns = idle.get_mut(key)?;
+        let i = conns.iter().position(|conn| conn.sender.is_ready())?;
+        let sender = match &conns[i].sender {
+            Sender::Http2(sender) => {
+                conns[i].since = Instant::now();
+                Sender::Http2(sender.clone())
+            }
+            Sender::Http1(_) => conns.swap_remove(i).sender,
+        };
+        if conns.is_empty() {
+            idle.remove(key);
+        }
+        Some(sender)
+    }
+
+    /// Returns a connection to the pool, closing it if the pool is full.
+    fn insert(&self, key: PoolKey, sender: Sender) {
+        let max = self.inner.config.max_idle_per_authority;
+        if max == 0 {
+            return;
+        }
+        let mut idle = self.inner.idle.lock().unwrap();
+        self.prune(&mut idle);
+        let conns = idle.entry(key).or_default();
+        if conns.len() < max {
+            conns.push(Idle {
+                sender,
+                since: Instant::now(),
+            });
+        }
+    }
+
+    /// Drops the connections which have been closed or have been idle for
+    /// too long.
+    fn prune(&self, idle: &mut HashMap<PoolKey, Vec<Idle>>) {
+        let idle_timeout = self.inner.config.idle_timeout;
+        idle.retain(|_, conns| {
+            conns.retain(|conn| !conn.sender.is_closed() && conn.since.elapsed() < idle_timeout);
+            !conns.is_empty()
+        });
+    }
+}
+
+/// Opens a new connection to `authority`, negotiating HTTP/2 if it's enabled
+/// in `config` and the connection uses TLS.
+async fn connect(use_tls: bool, authority: &str, config: &PoolConfig) -> anyhow::Result<Sender> {
+    let tcp_stream = TcpStream::connect(authority).await.map_err(invalid_url)?;
+
+    if !use_tls {
+        return handshake_http1(tcp_stream).await;
+    }
+
+    #[cfg(any(target_arch = "riscv64", target_arch = "s390x"))]
+    {
+        let _ = config;
+        anyhow::bail!(crate::bindings::http::types::Error::UnexpectedError(
+            "unsupported architecture for SSL".to_string(),
+        ));
+    }
+
+    #[cfg(not(any(target_arch = "riscv64", target_arch = "s390x")))]
+    {
+        use tokio_rustls::rustls::OwnedTrustAnchor;
+
+        // derived from https://github.com/tokio-rs/tls/blob/master/tokio-rustls/examples/client/src/main.rs
+        let mut root_cert_store = rustls::RootCertStore::empty();
+        root_cert_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        for der in config.root_certificates.iter() {
+            root_cert_store
+                .add(&rustls::Certificate(der.clone()))
+                .map_err(|e| anyhow!("invalid root certificate: {e:?}"))?;
+        }
+        let http2 = config.http2;
+        let mut config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(root_cert_store)
+            .with_no_client_auth();
+        if http2 {
+            config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        }
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+        let mut parts = authority.split(":");
+        let host = parts.next().unwrap_or(authority);
+        let domain = rustls::ServerName::try_from(host)?;
+        let stream = connector
+            .connect(domain, tcp_stream)
+            .await
+            .map_err(|e| crate::bindings::http::types::Error::ProtocolError(e.to_string()))?;
+
+        if stream.get_ref().1.alpn_protocol() == Some(&b"h2"[..]) {
+            let (sender, conn) = http2::handshake(TokioExecutor, stream).await?;
+            spawn_connection(conn);
+            Ok(Sender::Http2(sender))
+        } else {
+            handshake_http1(stream).await
+        }
+    }
+}
+
+async fn handshake_http1<T>(io: T) -> anyhow::Result<Sender>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sender, conn) = http1::handshake(io).await?;
+    spawn_connection(conn);
+    Ok(Sender::Http1(sender))
+}
+
+/// Drives a connection in the background until it's closed, which happens
+/// once all of its senders have been dropped.
+fn spawn_connection(conn: impl Future<Output = hyper::Result<()>> + Send + 'static) {
+    tokio::task::spawn(async move {
+        if let Err(e) = conn.await {
+            tracing::debug!("outgoing connection failed: {e}");
+        }
+    });
+}
+
+#[derive(Clone)]
+/// An Executor that uses the tokio runtime.
+struct TokioExecutor;
+
+impl<F> hyper::rt::Executor<F> for TokioExecutor
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    fn execute(&self, fut: F) {
+        tokio::task::spawn(fut);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    fn key(authority: &str) -> PoolKey {
+        PoolKey {
+            use_tls: false,
+            authority: authority.to_string(),
+        }
+    }
+
+    /// Creates an HTTP/1.1 connection over an in-memory stream, returning the
+    /// server's end of the stream, which keeps the connection open.
+    async fn connection() -> anyhow::Result<(Sender, DuplexStream)> {
+        let (client, server) = tokio::io::duplex(1024);
+        Ok((handshake_http1(client).await?, server))
+    }
+
+    #[tokio::test]
+    async fn max_idle() -> anyhow::Result<()> {
+        let mut config = PoolConfig::new();
+        config.max_idle_per_authority(2);
+        let pool = ConnectionPool::new(config);
+        let mut servers = Vec::new();
+        for _ in 0..3 {
+            let (sender, server) = connection().await?;
+            pool.insert(key("a.test:80"), sender);
+            servers.push(server);
+        }
+        assert_eq!(pool.idle_connections(), 2);
+
+        // The limit applies to each authority separately.
+        let (sender, server) = connection().await?;
+        pool.insert(key("b.test:80"), sender);
+        servers.push(server);
+        assert_eq!(pool.idle_connections(), 3);
+
+        let mut config = PoolConfig::new();
+        config.max_idle_per_authority(0);
+        let pool = ConnectionPool::new(config);
+        let (sender, _server) = connection().await?;
+        pool.insert(key("a.test:80"), sender);
+        assert_eq!(pool.idle_connections(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn idle_timeout() -> anyhow::Result<()> {
+        let mut config = PoolConfig::new();
+        config.idle_timeout(Duration::from_millis(100));
+        let pool = ConnectionPool::new(config);
+        let (sender, _server) = connection().await?;
+        pool.insert(key("a.test:80"), sender);
+        assert_eq!(pool.idle_connections(), 1);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(pool.idle_connections(), 0);
+        assert!(pool.checkout(&key("a.test:80")).is_none());
+
+        // Connections which are closed are evicted before they time out.
+        let (sender, server) = connection().await?;
+        pool.insert(key("a.test:80"), sender);
+        assert_eq!(pool.idle_connections(), 1);
+        drop(server);
+        timeout(Duration::from_secs(10), async {
+            while pool.idle_connections() != 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await?;
+        Ok(())
+    }
+}
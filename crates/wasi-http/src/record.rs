@@ -0,0 +1,178 @@
+//! Recording and replaying of outgoing requests.
+//!
+//! Embedders which record a guest with a
+//! [`Recorder`](wasmtime_wasi::preview2::record::Recorder), or replay it with
+//! a [`Replayer`](wasmtime_wasi::preview2::record::Replayer), override
+//! [`WasiHttpView::send_request`] to call [`record_send_request`] or
+//! [`replay_send_request`] so that the responses the guest receives are part
+//! of the trace.
+//!
+//! The body of a response is read completely before the response is handed
+//! to the guest, so that it can be recorded. Trailers aren't recorded.
+//! Replayed requests never touch the network, and their bodies are
+//! discarded.
+//!
+//! [`WasiHttpView::send_request`]: crate::WasiHttpView::send_request
+
+use crate::bindings::http::types::Error;
+use crate::types::{timeout_error, IncomingResponseInternal, OutgoingRequest};
+use crate::{types::HostFutureIncomingResponse, WasiHttpView};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use std::time::Duration;
+use tokio::time::timeout;
+use wasmtime::component::Resource;
+use wasmtime_wasi::preview2::{
+    self,
+    record::{HttpError, HttpResponse, Recorder, Replayer},
+    AbortOnDropJoinHandle,
+};
+
+/// Sends `request` like [`default_send_request`] and records the response
+/// with `recorder`.
+///
+/// [`default_send_request`]: crate::types::default_send_request
+pub fn record_send_request(
+    view: &mut dyn WasiHttpView,
+    OutgoingRequest {
+        use_tls,
+        authority,
+        request,
+        connect_timeout,
+        first_byte_timeout,
+        between_bytes_timeout,
+    }: OutgoingRequest,
+    recorder: &Recorder,
+) -> wasmtime::Result<Resource<HostFutureIncomingResponse>> {
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+    let pool = view.connection_pool();
+    let recorder = recorder.clone();
+    let handle = preview2::spawn(async move {
+        let result = async {
+            let resp = pool
+                .send_request(
+                    use_tls,
+                    &authority,
+                    request,
+                    connect_timeout,
+                    first_byte_timeout,
+                )
+                .await?;
+            let (parts, mut body) = resp.into_parts();
+            let mut data = Vec::new();
+            while let Some(frame) = timeout(between_bytes_timeout, body.frame())
+                .await
+                .map_err(|_| timeout_error("between bytes"))?
+            {
+                let frame = frame.map_err(|e| Error::ProtocolError(e.to_string()))?;
+                if let Ok(bytes) = frame.into_data() {
+                    data.extend_from_slice(&bytes);
+                }
+            }
+            let headers = parts
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str().to_string(), value.as_bytes().to_vec()))
+                .collect();
+            anyhow::Ok(HttpResponse {
+                status: parts.status.as_u16(),
+                headers,
+                body: data,
+            })
+        }
+        .await;
+
+        let recorded = match &result {
+            Ok(response) => Some(Ok(response.clone())),
+            Err(e) => e.downcast_ref::<Error>().map(|e| Err(to_http_error(e))),
+        };
+        // Errors which aren't `wasi:http` errors trap, which ends the guest,
+        // so there's nothing to replay afterwards.
+        if let Some(recorded) = recorded {
+            recorder.record_http(&method, &uri, &recorded);
+        }
+
+        // The response has been read completely, so there's no work left for
+        // it to keep alive.
+        incoming_response(result?, None, between_bytes_timeout)
+    });
+
+    Ok(view.table().push(HostFutureIncomingResponse::new(handle))?)
+}
+
+/// Responds to `request` with the response recorded for it in the trace read
+/// by `replayer`, without sending it.
+///
+/// # Errors
+///
+/// Fails, trapping the guest, if no response to such a request was recorded.
+pub fn replay_send_request(
+    view: &mut dyn WasiHttpView,
+    OutgoingRequest {
+        request,
+        between_bytes_timeout,
+        ..
+    }: OutgoingRequest,
+    replayer: &Replayer,
+) -> wasmtime::Result<Resource<HostFutureIncomingResponse>> {
+    let result = replayer.replay_http(request.method().as_str(), &request.uri().to_string())?;
+
+    // The guest may still be writing the body of the request, so it's read to
+    // the end to keep the guest from waiting on it.
+    let mut body = request.into_body();
+    let handle = preview2::spawn(async move {
+        let worker = preview2::spawn(async move {
+            while let Some(frame) = body.frame().await {
+                frame?;
+            }
+            anyhow::Ok(())
+        });
+        match result {
+            Ok(response) => incoming_response(response, Some(worker), between_bytes_timeout),
+            Err(e) => Err(from_http_error(e).into()),
+        }
+    });
+
+    Ok(view.table().push(HostFutureIncomingResponse::new(handle))?)
+}
+
+fn incoming_response(
+    response: HttpResponse,
+    worker: Option<AbortOnDropJoinHandle<anyhow::Result<()>>>,
+    between_bytes_timeout: Duration,
+) -> anyhow::Result<IncomingResponseInternal> {
+    let mut builder = hyper::Response::builder().status(response.status);
+    for (name, value) in response.headers {
+        builder = builder.header(name, value);
+    }
+    let body = Full::new(Bytes::from(response.body))
+        .map_err(|_| anyhow::anyhow!("full error"))
+        .boxed();
+    let resp = builder
+        .body(body)
+        .map_err(|e| Error::ProtocolError(e.to_string()))?;
+    Ok(IncomingResponseInternal {
+        resp,
+        worker,
+        between_bytes_timeout,
+    })
+}
+
+fn to_http_error(e: &Error) -> HttpError {
+    match e {
+        Error::InvalidUrl(message) => HttpError::InvalidUrl(message.clone()),
+        Error::TimeoutError(message) => HttpError::Timeout(message.clone()),
+        Error::ProtocolError(message) => HttpError::Protocol(message.clone()),
+        Error::UnexpectedError(message) => HttpError::Unexpected(message.clone()),
+    }
+}
+
+fn from_http_error(e: HttpError) -> Error {
+    match e {
+        HttpError::InvalidUrl(message) => Error::InvalidUrl(message),
+        HttpError::Timeout(message) => Error::TimeoutError(message),
+        HttpError::Protocol(message) => Error::ProtocolError(message),
+        HttpError::Unexpected(message) => Error::UnexpectedError(message),
+    }
+}
@@ -4,13 +4,12 @@
 use crate::{
     bindings::http::types::{self, Method, Scheme},
     body::{HostIncomingBodyBuilder, HyperIncomingBody, HyperOutgoingBody},
+    policy::PolicyState,
+    pool::ConnectionPool,
 };
-use anyhow::Context;
 use http_body_util::BodyExt;
 use std::any::Any;
 use std::time::Duration;
-use tokio::net::TcpStream;
-use tokio::time::timeout;
 use wasmtime::component::Resource;
 use wasmtime_wasi::preview2::{self, AbortOnDropJoinHandle, Subscribe, Table};
 
@@ -56,6 +55,25 @@ pub trait WasiHttpView: Send {
         Ok(id)
     }
 
+    /// Returns the state of the policy which outgoing requests are checked
+    /// against, or `None` if they aren't restricted.
+    ///
+    /// The state tracks the usage of the policy's quotas, so it should be kept
+    /// for as long as the store.
+    fn outgoing_policy(&mut self) -> Option<&mut PolicyState> {
+        None
+    }
+
+    /// Returns the pool of connections which [`default_send_request`] sends
+    /// outgoing requests through.
+    ///
+    /// By default a new pool is returned each time, so every request is sent
+    /// over a new connection. Returning the same pool, which can be shared
+    /// between stores, lets requests reuse connections.
+    fn connection_pool(&mut self) -> ConnectionPool {
+        ConnectionPool::default()
+    }
+
     fn send_request(
         &mut self,
         request: OutgoingRequest,
@@ -78,86 +96,23 @@ pub fn default_send_request(
         between_bytes_timeout,
     }: OutgoingRequest,
 ) -> wasmtime::Result<Resource<HostFutureIncomingResponse>> {
+    let pool = view.connection_pool();
     let handle = preview2::spawn(async move {
-        let tcp_stream = TcpStream::connect(authority.clone())
-            .await
-            .map_err(invalid_url)?;
-
-        let (mut sender, worker) = if use_tls {
-            #[cfg(any(target_arch = "riscv64", target_arch = "s390x"))]
-            {
-                anyhow::bail!(crate::bindings::http::types::Error::UnexpectedError(
-                    "unsupported architecture for SSL".to_string(),
-                ));
-            }
-
-            #[cfg(not(any(target_arch = "riscv64", target_arch = "s390x")))]
-            {
-                use tokio_rustls::rustls::OwnedTrustAnchor;
-
-                // derived from https://github.com/tokio-rs/tls/blob/master/tokio-rustls/examples/client/src/main.rs
-                let mut root_cert_store = rustls::RootCertStore::empty();
-                root_cert_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(
-                    |ta| {
-                        OwnedTrustAnchor::from_subject_spki_name_constraints(
-                            ta.subject,
-                            ta.spki,
-                            ta.name_constraints,
-                        )
-                    },
-                ));
-                let config = rustls::ClientConfig::builder()
-                    .with_safe_defaults()
-                    .with_root_certificates(root_cert_store)
-                    .with_no_client_auth();
-                let connector = tokio_rustls::TlsConnector::from(std::sync::Arc::new(config));
-                let mut parts = authority.split(":");
-                let host = parts.next().unwrap_or(&authority);
-                let domain = rustls::ServerName::try_from(host)?;
-                let stream = connector.connect(domain, tcp_stream).await.map_err(|e| {
-                    crate::bindings::http::types::Error::ProtocolError(e.to_string())
-                })?;
-
-                let (sender, conn) = timeout(
-                    connect_timeout,
-                    hyper::client::conn::http1::handshake(stream),
-                )
-                .await
-                .map_err(|_| timeout_error("connection"))??;
-
-                let worker = preview2::spawn(async move {
-                    conn.await.context("hyper connection failed")?;
-                    Ok::<_, anyhow::Error>(())
-                });
-
-                (sender, worker)
-            }
-        } else {
-            let (sender, conn) = timeout(
+        let resp = pool
+            .send_request(
+                use_tls,
+                &authority,
+                request,
                 connect_timeout,
-                // TODO: we should plumb the builder through the http context, and use it here
-                hyper::client::conn::http1::handshake(tcp_stream),
+                first_byte_timeout,
             )
-            .await
-            .map_err(|_| timeout_error("connection"))??;
-
-            let worker = preview2::spawn(async move {
-                conn.await.context("hyper connection failed")?;
-                Ok::<_, anyhow::Error>(())
-            });
-
-            (sender, worker)
-        };
-
-        let resp = timeout(first_byte_timeout, sender.send_request(request))
-            .await
-            .map_err(|_| timeout_error("first byte"))?
-            .map_err(hyper_protocol_error)?
-            .map(|body| body.map_err(|e| anyhow::anyhow!(e)).boxed());
+            .await?;
 
+        // The connection is driven in the background by the pool, so there's
+        // no work left for the response to keep alive.
         Ok(IncomingResponseInternal {
             resp,
-            worker,
+            worker: None,
             between_bytes_timeout,
         })
     });
@@ -185,7 +140,7 @@ pub fn hyper_protocol_error(e: hyper::Error) -> anyhow::Error {
     ))
 }
 
-fn invalid_url(e: std::io::Error) -> anyhow::Error {
+pub(crate) fn invalid_url(e: std::io::Error) -> anyhow::Error {
     // TODO: DNS errors show up as a Custom io error, what subset of errors should we consider for
     // InvalidUrl here?
     anyhow::anyhow!(crate::bindings::http::types::Error::InvalidUrl(
@@ -216,7 +171,7 @@ pub struct HostIncomingResponse {
     pub status: u16,
     pub headers: FieldMap,
     pub body: Option<HostIncomingBodyBuilder>,
-    pub worker: AbortOnDropJoinHandle<anyhow::Result<()>>,
+    pub worker: Option<AbortOnDropJoinHandle<anyhow::Result<()>>>,
 }
 
 pub struct HostOutgoingResponse {
@@ -267,7 +222,9 @@ pub enum HostFields {
 
 pub struct IncomingResponseInternal {
     pub resp: hyper::Response<HyperIncomingBody>,
-    pub worker: AbortOnDropJoinHandle<anyhow::Result<()>>,
+    /// A task which needs to keep running for as long as the response is
+    /// alive, such as the one driving its connection, if there's any.
+    pub worker: Option<AbortOnDropJoinHandle<anyhow::Result<()>>>,
     pub between_bytes_timeout: std::time::Duration,
 }
 
@@ -534,7 +534,10 @@ impl<T: WasiHttpView> crate::bindings::http::types::HostFutureIncomingResponse f
                 Ok(resp) => resp,
             };
 
-        let (parts, body) = resp.resp.into_parts();
+        let (parts, mut body) = resp.resp.into_parts();
+        if let Some(policy) = self.outgoing_policy() {
+            body = policy.limit_body(body);
+        }
 
         let resp = self.table().push(HostIncomingResponse {
             status: parts.status.as_u16(),
@@ -1,21 +1,41 @@
 use super::*;
 use test_programs_artifacts::*;
 use wasmtime_wasi::preview2::command::Command;
+use wasmtime_wasi_http::policy::{OutgoingPolicy, PolicyState};
 
 foreach_http!(assert_test_exists);
 
 async fn run(path: &str, server: &Server) -> Result<()> {
+    run_with_policy(path, server, None).await
+}
+
+async fn run_with_policy(
+    path: &str,
+    server: &Server,
+    policy: Option<OutgoingPolicy>,
+) -> Result<()> {
+    let engine = engine()?;
+    let mut store = store(&engine, server);
+    if let Some(policy) = policy {
+        store.data_mut().policy = Some(PolicyState::new(Arc::new(policy)));
+    }
+    run_in_store(path, &engine, &mut store).await
+}
+
+fn engine() -> Result<Engine> {
     let mut config = Config::new();
     config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
     config.wasm_component_model(true);
     config.async_support(true);
-    let engine = Engine::new(&config)?;
-    let component = Component::from_file(&engine, path)?;
-    let mut store = store(&engine, server);
+    Engine::new(&config)
+}
+
+async fn run_in_store(path: &str, engine: &Engine, store: &mut Store<Ctx>) -> Result<()> {
+    let component = Component::from_file(engine, path)?;
     let mut linker = Linker::new(&engine);
     wasmtime_wasi_http::proxy::add_to_linker(&mut linker)?;
-    let (command, _instance) = Command::instantiate_async(&mut store, &component, &linker).await?;
-    let result = command.wasi_cli_run().call_run(&mut store).await?;
+    let (command, _instance) = Command::instantiate_async(&mut *store, &component, &linker).await?;
+    let result = command.wasi_cli_run().call_run(&mut *store).await?;
     result.map_err(|()| anyhow::anyhow!("run returned an error"))
 }
 
@@ -25,6 +45,34 @@ async fn http_outbound_request_get() -> Result<()> {
     run(HTTP_OUTBOUND_REQUEST_GET_COMPONENT, &server).await
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn http_outbound_request_get_allowed_by_policy() -> Result<()> {
+    let server = Server::http1()?;
+    let mut policy = OutgoingPolicy::new();
+    policy.allow(&server.addr())?.max_requests(1);
+    run_with_policy(HTTP_OUTBOUND_REQUEST_GET_COMPONENT, &server, Some(policy)).await
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn http_outbound_request_get_denied_by_policy() -> Result<()> {
+    let server = Server::http1()?;
+    let mut policy = OutgoingPolicy::new();
+    policy.allow("example.com:80")?;
+    let engine = engine()?;
+    let mut store = store(&engine, &server);
+    store.data_mut().policy = Some(PolicyState::new(Arc::new(policy)));
+    let result = run_in_store(HTTP_OUTBOUND_REQUEST_GET_COMPONENT, &engine, &mut store).await;
+
+    // The guest panics on the error returned for its request, and the request
+    // is rejected before a connection to the server is made.
+    assert!(result.is_err());
+    let stderr = String::from_utf8(store.data().stderr.contents().to_vec())?;
+    let expected = format!("outgoing requests to `{}` are not allowed", server.addr());
+    assert!(stderr.contains(&expected), "bad stderr: {stderr}");
+    assert_eq!(server.requests(), 0);
+    Ok(())
+}
+
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn http_outbound_request_post() -> Result<()> {
     let server = Server::http1()?;
@@ -4,14 +4,18 @@ use hyper::{body::Bytes, service::service_fn, Request, Response};
 use std::{
     future::Future,
     net::{SocketAddr, TcpStream},
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
     thread::JoinHandle,
 };
 use tokio::net::TcpListener;
 
 async fn test(
     mut req: Request<hyper::body::Incoming>,
+    requests: Arc<AtomicUsize>,
 ) -> http::Result<Response<BoxBody<Bytes, std::convert::Infallible>>> {
     tracing::debug!("preparing mocked response",);
+    requests.fetch_add(1, Ordering::SeqCst);
     let method = req.method().to_string();
     let body = req.body_mut().collect().await.unwrap();
     let buf = body.to_bytes();
@@ -26,11 +30,14 @@ async fn test(
 
 pub struct Server {
     addr: SocketAddr,
+    requests: Arc<AtomicUsize>,
     worker: Option<JoinHandle<Result<()>>>,
 }
 
 impl Server {
-    fn new<F>(run: impl FnOnce(tokio::net::TcpStream) -> F + Send + Sync + 'static) -> Result<Self>
+    fn new<F>(
+        run: impl FnOnce(tokio::net::TcpStream, Arc<AtomicUsize>) -> F + Send + Sync + 'static,
+    ) -> Result<Self>
     where
         F: Future<Output = Result<()>>,
     {
@@ -47,28 +54,32 @@ impl Server {
         });
         let (rt, listener) = thread.join().unwrap()?;
         let addr = listener.local_addr().context("failed to get local addr")?;
+        let requests = Arc::new(AtomicUsize::new(0));
+        let worker_requests = requests.clone();
         let worker = std::thread::spawn(move || {
             tracing::debug!("dedicated thread to start listening");
             rt.block_on(async move {
                 tracing::debug!("preparing to accept connection");
                 let (stream, _) = listener.accept().await.map_err(anyhow::Error::from)?;
-                run(stream).await
+                run(stream, worker_requests).await
             })
         });
         Ok(Self {
             worker: Some(worker),
+            requests,
             addr,
         })
     }
 
     pub fn http1() -> Result<Self> {
         tracing::debug!("initializing http1 server");
-        Self::new(|io| async move {
+        Self::new(|io, requests| async move {
             let mut builder = hyper::server::conn::http1::Builder::new();
             let http = builder.keep_alive(false).pipeline_flush(true);
 
             tracing::debug!("preparing to bind connection to service");
-            let conn = http.serve_connection(io, service_fn(test)).await;
+            let service = service_fn(move |req| test(req, requests.clone()));
+            let conn = http.serve_connection(io, service).await;
             tracing::trace!("connection result {:?}", conn);
             conn?;
             Ok(())
@@ -77,12 +88,13 @@ impl Server {
 
     pub fn http2() -> Result<Self> {
         tracing::debug!("initializing http2 server");
-        Self::new(|io| async move {
+        Self::new(|io, requests| async move {
             let mut builder = hyper::server::conn::http2::Builder::new(TokioExecutor);
             let http = builder.max_concurrent_streams(20);
 
             tracing::debug!("preparing to bind connection to service");
-            let conn = http.serve_connection(io, service_fn(test)).await;
+            let service = service_fn(move |req| test(req, requests.clone()));
+            let conn = http.serve_connection(io, service).await;
             tracing::trace!("connection result {:?}", conn);
             if let Err(e) = &conn {
                 let message = e.to_string();
@@ -100,6 +112,11 @@ impl Server {
     pub fn addr(&self) -> String {
         format!("localhost:{}", self.addr.port())
     }
+
+    /// Returns the number of requests the server has received so far.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
 }
 
 impl Drop for Server {
@@ -122,7 +139,7 @@ impl Drop for Server {
 
 #[derive(Clone)]
 /// An Executor that uses the tokio runtime.
-struct TokioExecutor;
+pub struct TokioExecutor;
 
 impl<F> hyper::rt::Executor<F> for TokioExecutor
 where
@@ -2,21 +2,31 @@ use crate::http_server::Server;
 use anyhow::{anyhow, Context, Result};
 use futures::{channel::oneshot, future, stream, FutureExt};
 use http_body::Frame;
-use http_body_util::{combinators::BoxBody, Collected, StreamBody};
+use http_body_util::{combinators::BoxBody, BodyExt, Collected, StreamBody};
 use hyper::{body::Bytes, server::conn::http1, service::service_fn, Method, StatusCode};
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, iter, net::Ipv4Addr, str, sync::Arc};
+use std::{
+    collections::HashMap,
+    iter,
+    net::Ipv4Addr,
+    str,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::task;
 use wasmtime::{
     component::{Component, Linker, Resource},
     Config, Engine, Store,
 };
 use wasmtime_wasi::preview2::{
-    self, pipe::MemoryOutputPipe, Table, WasiCtx, WasiCtxBuilder, WasiView,
+    self, pipe::MemoryOutputPipe, Subscribe, Table, WasiCtx, WasiCtxBuilder, WasiView,
 };
 use wasmtime_wasi_http::{
     bindings::http::types::Error,
     body::HyperIncomingBody,
+    policy::PolicyState,
+    pool::{ConnectionPool, PoolConfig},
     types::{self, HostFutureIncomingResponse, IncomingResponseInternal, OutgoingRequest},
     WasiHttpCtx, WasiHttpView,
 };
@@ -33,6 +43,8 @@ struct Ctx {
     table: Table,
     wasi: WasiCtx,
     http: WasiHttpCtx,
+    policy: Option<PolicyState>,
+    pool: ConnectionPool,
     stdout: MemoryOutputPipe,
     stderr: MemoryOutputPipe,
     send_request: Option<RequestSender>,
@@ -62,6 +74,14 @@ impl WasiHttpView for Ctx {
         &mut self.table
     }
 
+    fn outgoing_policy(&mut self) -> Option<&mut PolicyState> {
+        self.policy.as_mut()
+    }
+
+    fn connection_pool(&mut self) -> ConnectionPool {
+        self.pool.clone()
+    }
+
     fn send_request(
         &mut self,
         request: OutgoingRequest,
@@ -86,7 +106,9 @@ fn store(engine: &Engine, server: &Server) -> Store<Ctx> {
     let ctx = Ctx {
         table: Table::new(),
         wasi: builder.build(),
-        http: WasiHttpCtx {},
+        http: WasiHttpCtx,
+        policy: None,
+        pool: ConnectionPool::default(),
         stderr,
         stdout,
         send_request: None,
@@ -141,11 +163,12 @@ async fn run_wasi_http(
     builder.stdout(stdout.clone());
     builder.stderr(stderr.clone());
     let wasi = builder.build();
-    let http = WasiHttpCtx;
     let ctx = Ctx {
         table,
         wasi,
-        http,
+        http: WasiHttpCtx,
+        policy: None,
+        pool: ConnectionPool::default(),
         stderr,
         stdout,
         send_request,
@@ -264,7 +287,7 @@ async fn do_wasi_http_hash_all(override_send_request: bool) -> Result<()> {
                 Ok(view.table().push(HostFutureIncomingResponse::Ready(
                     handle(request.into_parts().0).map(|resp| IncomingResponseInternal {
                         resp,
-                        worker: preview2::spawn(future::ready(Ok(()))),
+                        worker: None,
                         between_bytes_timeout,
                     }),
                 ))?)
@@ -399,6 +422,215 @@ async fn wasi_http_echo() -> Result<()> {
     Ok(())
 }
 
+#[test_log::test(tokio::test)]
+async fn wasi_http_connection_pool() -> Result<()> {
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::new(127, 0, 0, 1), 0)).await?;
+    let authority = listener.local_addr()?.to_string();
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let server = {
+        let connections = connections.clone();
+        task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                connections.fetch_add(1, Ordering::SeqCst);
+                task::spawn(async move {
+                    if let Err(e) = http1::Builder::new()
+                        .keep_alive(true)
+                        .serve_connection(
+                            stream,
+                            service_fn(|_| async {
+                                Ok::<_, anyhow::Error>(hyper::Response::new(body::full(
+                                    Bytes::from_static(b"hello"),
+                                )))
+                            }),
+                        )
+                        .await
+                    {
+                        eprintln!("error serving connection: {e:?}");
+                    }
+                });
+
+                // Help rustc with type inference:
+                if false {
+                    return Ok::<_, anyhow::Error>(());
+                }
+            }
+        })
+    };
+
+    let new_ctx = |pool: ConnectionPool| Ctx {
+        table: Table::new(),
+        wasi: WasiCtxBuilder::new().build(),
+        http: WasiHttpCtx,
+        policy: None,
+        pool,
+        stdout: MemoryOutputPipe::new(4096),
+        stderr: MemoryOutputPipe::new(4096),
+        send_request: None,
+    };
+
+    // Sequential requests through one context share a single connection.
+    let pool = ConnectionPool::new(PoolConfig::new());
+    let mut ctx = new_ctx(pool.clone());
+    for _ in 0..3 {
+        assert_eq!(send_get(&mut ctx, false, &authority).await?, "hello");
+        wait_for_idle_connection(&pool).await?;
+    }
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    // Contexts sharing a pool share its connections as well.
+    let mut other = new_ctx(pool.clone());
+    assert_eq!(send_get(&mut other, false, &authority).await?, "hello");
+    wait_for_idle_connection(&pool).await?;
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+    assert_eq!(pool.idle_connections(), 1);
+
+    pool.clear();
+    assert_eq!(send_get(&mut ctx, false, &authority).await?, "hello");
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+    // Without any idle connections allowed every request connects again.
+    let mut config = PoolConfig::new();
+    config.max_idle_per_authority(0);
+    let mut ctx = new_ctx(ConnectionPool::new(config));
+    assert_eq!(send_get(&mut ctx, false, &authority).await?, "hello");
+    assert_eq!(send_get(&mut ctx, false, &authority).await?, "hello");
+    assert_eq!(connections.load(Ordering::SeqCst), 4);
+
+    server.abort();
+    Ok(())
+}
+
+#[cfg(not(any(target_arch = "riscv64", target_arch = "s390x")))]
+#[test_log::test(tokio::test)]
+async fn wasi_http_connection_pool_http2() -> Result<()> {
+    use tokio_rustls::rustls;
+
+    // A certificate for `localhost` signed by `ca.der`, both of which are
+    // valid until 2126.
+    let ca = include_bytes!("certs/ca.der");
+    let cert = include_bytes!("certs/localhost.der");
+    let key = include_bytes!("certs/localhost.key.der");
+    let mut tls = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![rustls::Certificate(cert.to_vec())],
+            rustls::PrivateKey(key.to_vec()),
+        )?;
+    tls.alpn_protocols = vec![b"h2".to_vec()];
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls));
+
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::new(127, 0, 0, 1), 0)).await?;
+    let authority = format!("localhost:{}", listener.local_addr()?.port());
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let server = {
+        let connections = connections.clone();
+        task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                connections.fetch_add(1, Ordering::SeqCst);
+                let acceptor = acceptor.clone();
+                task::spawn(async move {
+                    let stream = match acceptor.accept(stream).await {
+                        Ok(stream) => stream,
+                        Err(e) => return eprintln!("error accepting tls: {e:?}"),
+                    };
+                    if let Err(e) =
+                        hyper::server::conn::http2::Builder::new(http_server::TokioExecutor)
+                            .serve_connection(
+                                stream,
+                                service_fn(|_| async {
+                                    Ok::<_, anyhow::Error>(hyper::Response::new(body::full(
+                                        Bytes::from_static(b"hello"),
+                                    )))
+                                }),
+                            )
+                            .await
+                    {
+                        eprintln!("error serving connection: {e:?}");
+                    }
+                });
+
+                // Help rustc with type inference:
+                if false {
+                    return Ok::<_, anyhow::Error>(());
+                }
+            }
+        })
+    };
+
+    let mut config = PoolConfig::new();
+    config.add_root_certificate(&ca[..]);
+    let pool = ConnectionPool::new(config);
+    let new_ctx = || Ctx {
+        table: Table::new(),
+        wasi: WasiCtxBuilder::new().build(),
+        http: WasiHttpCtx,
+        policy: None,
+        pool: pool.clone(),
+        stdout: MemoryOutputPipe::new(4096),
+        stderr: MemoryOutputPipe::new(4096),
+        send_request: None,
+    };
+
+    // The first request negotiates HTTP/2, after which the connection is
+    // pooled right away without waiting for the response body.
+    let mut ctx = new_ctx();
+    assert_eq!(send_get(&mut ctx, true, &authority).await?, "hello");
+    assert_eq!(pool.idle_connections(), 1);
+
+    // Concurrent requests from several contexts are all multiplexed over
+    // that one connection, which wouldn't be possible with HTTP/1.1.
+    let mut contexts = iter::repeat_with(new_ctx).take(4).collect::<Vec<_>>();
+    let responses = future::try_join_all(
+        contexts
+            .iter_mut()
+            .map(|ctx| send_get(ctx, true, &authority)),
+    )
+    .await?;
+    assert!(responses.iter().all(|body| body == "hello"));
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+    assert_eq!(pool.idle_connections(), 1);
+
+    server.abort();
+    Ok(())
+}
+
+async fn send_get(ctx: &mut Ctx, use_tls: bool, authority: &str) -> Result<Bytes> {
+    let scheme = if use_tls { "https" } else { "http" };
+    let request = hyper::Request::get(format!("{scheme}://{authority}/")).body(body::empty())?;
+    let response = types::default_send_request(
+        ctx,
+        OutgoingRequest {
+            use_tls,
+            authority: authority.to_string(),
+            request,
+            connect_timeout: Duration::from_secs(10),
+            first_byte_timeout: Duration::from_secs(10),
+            between_bytes_timeout: Duration::from_secs(10),
+        },
+    )?;
+    let mut response = ctx.table.delete(response)?;
+    response.ready().await;
+    let response = response.unwrap_ready()?;
+    Ok(response.resp.into_body().collect().await?.to_bytes())
+}
+
+/// Connections are returned to the pool in the background once a response
+/// has been read, so wait for that to happen.
+async fn wait_for_idle_connection(pool: &ConnectionPool) -> Result<()> {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while pool.idle_connections() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .context("connection was never returned to the pool")
+}
+
 mod body {
     use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
     use hyper::body::Bytes;
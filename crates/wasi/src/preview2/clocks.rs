@@ -1,5 +1,6 @@
 pub mod host;
 use cap_std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub trait HostWallClock: Send + Sync {
     fn resolution(&self) -> Duration;
@@ -9,4 +10,85 @@ pub trait HostWallClock: Send + Sync {
 pub trait HostMonotonicClock: Send + Sync {
     fn resolution(&self) -> u64;
     fn now(&self) -> u64;
+
+    /// Advances this clock to `when` unless it's already past it, returning
+    /// the time it's at afterwards.
+    ///
+    /// Clocks which don't follow the host's time implement this so that
+    /// waiting on them doesn't sleep on the host: polling subscriptions to
+    /// such a clock advances it to the earliest of their deadlines, and the
+    /// subscriptions whose deadline has then been reached are ready.
+    /// Advancing to a time the clock has already reached leaves it
+    /// unchanged. By default this returns `None`, and subscriptions sleep on
+    /// the host until their deadline.
+    fn advance_to(&self, when: u64) -> Option<u64> {
+        let _ = when;
+        None
+    }
+}
+
+/// A wall clock which doesn't follow the host's time.
+///
+/// The clock starts at `start` and every call to `now` advances it by its
+/// resolution, so the times observed by a guest only depend on the calls it
+/// has made.
+pub struct VirtualWallClock {
+    start: Duration,
+    resolution: Duration,
+    ticks: AtomicU64,
+}
+
+impl VirtualWallClock {
+    pub fn new(start: Duration, resolution: Duration) -> Self {
+        VirtualWallClock {
+            start,
+            resolution,
+            ticks: AtomicU64::new(0),
+        }
+    }
+}
+
+impl HostWallClock for VirtualWallClock {
+    fn resolution(&self) -> Duration {
+        self.resolution
+    }
+
+    fn now(&self) -> Duration {
+        let ticks = self.ticks.fetch_add(1, Ordering::Relaxed);
+        let elapsed = self.resolution.as_nanos().saturating_mul(ticks.into());
+        self.start + Duration::from_nanos(elapsed.try_into().unwrap_or(u64::MAX))
+    }
+}
+
+/// A monotonic clock which doesn't follow the host's time.
+///
+/// The clock starts at zero and every call to `now` advances it by its
+/// resolution. Polling a subscription to a later instant advances the clock
+/// to that instant immediately rather than waiting for it.
+pub struct VirtualMonotonicClock {
+    resolution: u64,
+    now: AtomicU64,
+}
+
+impl VirtualMonotonicClock {
+    pub fn new(resolution: u64) -> Self {
+        VirtualMonotonicClock {
+            resolution,
+            now: AtomicU64::new(0),
+        }
+    }
+}
+
+impl HostMonotonicClock for VirtualMonotonicClock {
+    fn resolution(&self) -> u64 {
+        self.resolution
+    }
+
+    fn now(&self) -> u64 {
+        self.now.fetch_add(self.resolution, Ordering::Relaxed)
+    }
+
+    fn advance_to(&self, when: u64) -> Option<u64> {
+        Some(self.now.fetch_max(when, Ordering::Relaxed).max(when))
+    }
 }
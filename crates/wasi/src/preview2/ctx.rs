@@ -1,17 +1,20 @@
 use super::clocks::host::{monotonic_clock, wall_clock};
 use crate::preview2::{
     clocks::{self, HostMonotonicClock, HostWallClock},
-    filesystem::Dir,
-    pipe, random, stdio,
+    filesystem::{Descriptor, Dir},
+    pipe, random, record, stdio,
     stdio::{StdinStream, StdoutStream},
+    vfs::{VirtualDescriptor, VirtualFs},
     DirPerms, FilePerms, Table,
 };
-use cap_rand::{Rng, RngCore, SeedableRng};
+use cap_rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 use cap_std::ipnet::{self, IpNet};
 use cap_std::net::Pool;
 use cap_std::{ambient_authority, AmbientAuthority};
 use std::mem;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
 
 pub struct WasiCtxBuilder {
     stdin: Box<dyn StdinStream>,
@@ -19,7 +22,7 @@ pub struct WasiCtxBuilder {
     stderr: Box<dyn StdoutStream>,
     env: Vec<(String, String)>,
     args: Vec<String>,
-    preopens: Vec<(Dir, String)>,
+    preopens: Vec<(Descriptor, String)>,
 
     pool: Pool,
     random: Box<dyn RngCore + Send + Sync>,
@@ -28,6 +31,8 @@ pub struct WasiCtxBuilder {
     wall_clock: Box<dyn HostWallClock + Send + Sync>,
     monotonic_clock: Box<dyn HostMonotonicClock + Send + Sync>,
     allow_ip_name_lookup: bool,
+    recorder: Option<record::Recorder>,
+    replayer: Option<record::Replayer>,
     built: bool,
 }
 
@@ -78,6 +83,8 @@ impl WasiCtxBuilder {
             wall_clock: wall_clock(),
             monotonic_clock: monotonic_clock(),
             allow_ip_name_lookup: false,
+            recorder: None,
+            replayer: None,
             built: false,
         }
     }
@@ -144,8 +151,29 @@ impl WasiCtxBuilder {
         file_perms: FilePerms,
         path: impl AsRef<str>,
     ) -> &mut Self {
-        self.preopens
-            .push((Dir::new(dir, perms, file_perms), path.as_ref().to_owned()));
+        self.preopens.push((
+            Descriptor::Dir(Dir::new(dir, perms, file_perms)),
+            path.as_ref().to_owned(),
+        ));
+        self
+    }
+
+    /// Preopens the root of the virtual filesystem `fs` at `path`.
+    ///
+    /// Unlike [`preopened_dir`](WasiCtxBuilder::preopened_dir) the guest's
+    /// accesses to this directory never reach the host's filesystem. See the
+    /// [`vfs`](crate::preview2::vfs) module for the implementations provided.
+    pub fn preopened_virtual_dir(
+        &mut self,
+        fs: Arc<dyn VirtualFs>,
+        perms: DirPerms,
+        file_perms: FilePerms,
+        path: impl AsRef<str>,
+    ) -> &mut Self {
+        self.preopens.push((
+            Descriptor::Virtual(VirtualDescriptor::root(fs, perms, file_perms)),
+            path.as_ref().to_owned(),
+        ));
         self
     }
 
@@ -254,6 +282,85 @@ impl WasiCtxBuilder {
         self
     }
 
+    /// Configures this context such that repeated runs of the same guest with
+    /// the same inputs behave identically.
+    ///
+    /// This replaces the settings made so far as follows:
+    ///
+    /// * clocks are [`VirtualWallClock`] and [`VirtualMonotonicClock`], which
+    ///   start at the Unix epoch and zero respectively and only advance when
+    ///   the guest reads or waits on them
+    /// * both RNGs and the insecure seed are initialized from fixed seeds
+    /// * all network access, including name lookups, is removed
+    ///
+    /// Polling already reports ready pollables in a deterministic order.
+    /// Other inputs such as stdin, environment variables and preopened
+    /// directories are left up to the embedder, and methods called after
+    /// this one can still override the settings above.
+    ///
+    /// Full determinism additionally requires that the engine is configured
+    /// with [`Config::cranelift_nan_canonicalization`] and
+    /// [`Config::relaxed_simd_deterministic`].
+    ///
+    /// [`VirtualWallClock`]: clocks::VirtualWallClock
+    /// [`VirtualMonotonicClock`]: clocks::VirtualMonotonicClock
+    /// [`Config::cranelift_nan_canonicalization`]: wasmtime::Config::cranelift_nan_canonicalization
+    /// [`Config::relaxed_simd_deterministic`]: wasmtime::Config::relaxed_simd_deterministic
+    pub fn deterministic(&mut self) -> &mut Self {
+        const RESOLUTION_NANOS: u64 = 1_000;
+        self.wall_clock(clocks::VirtualWallClock::new(
+            Duration::ZERO,
+            Duration::from_nanos(RESOLUTION_NANOS),
+        ));
+        self.monotonic_clock(clocks::VirtualMonotonicClock::new(RESOLUTION_NANOS));
+        self.secure_random(StdRng::seed_from_u64(0));
+        self.insecure_random(StdRng::seed_from_u64(1));
+        self.insecure_random_seed(0);
+        self.pool = Pool::new();
+        self.allow_ip_name_lookup = false;
+        self
+    }
+
+    /// Records the inputs the guest receives from the clocks, random number
+    /// generators, stdin and network of this context with `recorder`.
+    ///
+    /// The values are recorded from whichever implementations are configured
+    /// when [`build`](WasiCtxBuilder::build) is called. Filesystems aren't
+    /// covered by this and need to be wrapped in a [`record::RecordingFs`]
+    /// to be recorded.
+    ///
+    /// Of the network, name lookups and outgoing TCP connections are
+    /// recorded. Binding TCP sockets and creating UDP sockets fail with
+    /// `not-supported` in the built context.
+    pub fn record(&mut self, recorder: &record::Recorder) -> &mut Self {
+        self.recorder = Some(recorder.clone());
+        self
+    }
+
+    /// Replaces the clocks, random number generators and stdin of this
+    /// context with ones which replay the values recorded in the trace read
+    /// by `replayer`.
+    ///
+    /// Preopened filesystems which were recorded should be replaced with a
+    /// [`record::ReplayFs`]. Name lookups and outgoing TCP connections are
+    /// replayed without touching the network, and as with
+    /// [`record`](WasiCtxBuilder::record) other socket uses aren't supported.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the trace doesn't contain the insecure random seed, which is
+    /// recorded when a context with a recorder is built.
+    pub fn replay(&mut self, replayer: &record::Replayer) -> anyhow::Result<&mut Self> {
+        self.insecure_random_seed(record::replay_insecure_random_seed(replayer)?);
+        self.wall_clock(record::ReplayWallClock::new(replayer.clone()));
+        self.monotonic_clock(record::ReplayMonotonicClock::new(replayer.clone()));
+        self.secure_random(record::ReplayRng::secure(replayer.clone()));
+        self.insecure_random(record::ReplayRng::insecure(replayer.clone()));
+        self.stdin(record::ReplayStdin::new(replayer.clone()));
+        self.replayer = Some(replayer.clone());
+        Ok(self)
+    }
+
     /// Uses the configured context so far to construct the final `WasiCtx`.
     ///
     /// Note that each `WasiCtxBuilder` can only be used to "build" once, and
@@ -266,23 +373,43 @@ impl WasiCtxBuilder {
         assert!(!self.built);
 
         let Self {
-            stdin,
+            mut stdin,
             stdout,
             stderr,
             env,
             args,
             preopens,
             pool,
-            random,
-            insecure_random,
+            mut random,
+            mut insecure_random,
             insecure_random_seed,
-            wall_clock,
-            monotonic_clock,
+            mut wall_clock,
+            mut monotonic_clock,
             allow_ip_name_lookup,
+            recorder,
+            replayer,
             built: _,
         } = mem::replace(self, Self::new());
         self.built = true;
 
+        if let Some(recorder) = &recorder {
+            record::record_insecure_random_seed(recorder, insecure_random_seed);
+            stdin = Box::new(record::RecordingStdin::new(stdin, recorder.clone()));
+            random = Box::new(record::RecordingRng::secure(random, recorder.clone()));
+            insecure_random = Box::new(record::RecordingRng::insecure(
+                insecure_random,
+                recorder.clone(),
+            ));
+            wall_clock = Box::new(record::RecordingWallClock::new(
+                wall_clock,
+                recorder.clone(),
+            ));
+            monotonic_clock = Box::new(record::RecordingMonotonicClock::new(
+                monotonic_clock,
+                recorder.clone(),
+            ));
+        }
+
         WasiCtx {
             stdin,
             stdout,
@@ -297,6 +424,8 @@ impl WasiCtxBuilder {
             wall_clock,
             monotonic_clock,
             allow_ip_name_lookup,
+            recorder,
+            replayer,
         }
     }
 }
@@ -316,10 +445,29 @@ pub struct WasiCtx {
     pub(crate) monotonic_clock: Box<dyn HostMonotonicClock + Send + Sync>,
     pub(crate) env: Vec<(String, String)>,
     pub(crate) args: Vec<String>,
-    pub(crate) preopens: Vec<(Dir, String)>,
+    pub(crate) preopens: Vec<(Descriptor, String)>,
     pub(crate) stdin: Box<dyn StdinStream>,
     pub(crate) stdout: Box<dyn StdoutStream>,
     pub(crate) stderr: Box<dyn StdoutStream>,
     pub(crate) pool: Pool,
     pub(crate) allow_ip_name_lookup: bool,
+    pub(crate) recorder: Option<record::Recorder>,
+    pub(crate) replayer: Option<record::Replayer>,
+}
+
+impl WasiCtx {
+    /// Traps if this context replays a trace and the guest has diverged from
+    /// it in a call which couldn't report it, such as a clock reading.
+    pub(crate) fn check_replay(&self) -> anyhow::Result<()> {
+        match &self.replayer {
+            Some(replayer) => replayer.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the guest's inputs are recorded or replayed, in which case
+    /// the socket operations which traces don't cover aren't supported.
+    pub(crate) fn records_or_replays(&self) -> bool {
+        self.recorder.is_some() || self.replayer.is_some()
+    }
 }
@@ -1,4 +1,5 @@
 use crate::preview2::bindings::filesystem::types;
+use crate::preview2::vfs::VirtualDescriptor;
 use crate::preview2::{
     spawn_blocking, AbortOnDropJoinHandle, HostOutputStream, StreamError, Subscribe, TableError,
     TrappableError,
@@ -25,23 +26,25 @@ impl From<io::Error> for FsError {
     }
 }
 
+#[derive(Clone)]
 pub enum Descriptor {
     File(File),
     Dir(Dir),
+    Virtual(VirtualDescriptor),
 }
 
 impl Descriptor {
     pub fn file(&self) -> Result<&File, types::ErrorCode> {
         match self {
             Descriptor::File(f) => Ok(f),
-            Descriptor::Dir(_) => Err(types::ErrorCode::BadDescriptor),
+            Descriptor::Dir(_) | Descriptor::Virtual(_) => Err(types::ErrorCode::BadDescriptor),
         }
     }
 
     pub fn dir(&self) -> Result<&Dir, types::ErrorCode> {
         match self {
             Descriptor::Dir(d) => Ok(d),
-            Descriptor::File(_) => Err(types::ErrorCode::NotDirectory),
+            Descriptor::File(_) | Descriptor::Virtual(_) => Err(types::ErrorCode::NotDirectory),
         }
     }
 
@@ -49,6 +52,7 @@ impl Descriptor {
         match self {
             Descriptor::File(_) => true,
             Descriptor::Dir(_) => false,
+            Descriptor::Virtual(v) => !v.is_dir(),
         }
     }
 
@@ -56,6 +60,7 @@ impl Descriptor {
         match self {
             Descriptor::File(_) => false,
             Descriptor::Dir(_) => true,
+            Descriptor::Virtual(v) => v.is_dir(),
         }
     }
 }
@@ -68,6 +73,7 @@ bitflags::bitflags! {
     }
 }
 
+#[derive(Clone)]
 pub struct File {
     /// Wrapped in an Arc because the same underlying file is used for
     /// implementing the stream types. Also needed for [`spawn_blocking`].
@@ -213,7 +219,7 @@ impl FileOutputStream {
 }
 
 // FIXME: configurable? determine from how much space left in file?
-const FILE_WRITE_CAPACITY: usize = 1024 * 1024;
+pub(crate) const FILE_WRITE_CAPACITY: usize = 1024 * 1024;
 
 impl HostOutputStream for FileOutputStream {
     fn write(&mut self, buf: Bytes) -> Result<(), StreamError> {
@@ -28,6 +28,7 @@ impl TryFrom<SystemTime> for Datetime {
 impl<T: WasiView> wall_clock::Host for T {
     fn now(&mut self) -> anyhow::Result<Datetime> {
         let now = self.ctx().wall_clock.now();
+        self.ctx().check_replay()?;
         Ok(Datetime {
             seconds: now.as_secs(),
             nanoseconds: now.subsec_nanos(),
@@ -36,6 +37,7 @@ impl<T: WasiView> wall_clock::Host for T {
 
     fn resolution(&mut self) -> anyhow::Result<Datetime> {
         let res = self.ctx().wall_clock.resolution();
+        self.ctx().check_replay()?;
         Ok(Datetime {
             seconds: res.as_secs(),
             nanoseconds: res.subsec_nanos(),
@@ -45,38 +47,100 @@ impl<T: WasiView> wall_clock::Host for T {
 
 impl<T: WasiView> monotonic_clock::Host for T {
     fn now(&mut self) -> anyhow::Result<Instant> {
-        Ok(self.ctx().monotonic_clock.now())
+        let now = self.ctx().monotonic_clock.now();
+        self.ctx().check_replay()?;
+        Ok(now)
     }
 
     fn resolution(&mut self) -> anyhow::Result<Instant> {
-        Ok(self.ctx().monotonic_clock.resolution())
+        let res = self.ctx().monotonic_clock.resolution();
+        self.ctx().check_replay()?;
+        Ok(res)
     }
 
     fn subscribe(&mut self, when: Instant, absolute: bool) -> anyhow::Result<Resource<Pollable>> {
-        let clock_now = self.ctx().monotonic_clock.now();
-        let duration = if absolute {
-            Duration::from_nanos(when - clock_now)
+        let clock = &self.ctx().monotonic_clock;
+        let clock_now = clock.now();
+        self.ctx().check_replay()?;
+        // Advancing the clock to the time it was just read at leaves it
+        // unchanged, but tells whether it's a virtual clock. Those are only
+        // advanced to the deadline once the subscription is polled.
+        let sleep = if clock.advance_to(clock_now).is_some() {
+            let when = if absolute {
+                when
+            } else {
+                clock_now.saturating_add(when)
+            };
+            Sleep::Virtual { when, ready: false }
         } else {
-            Duration::from_nanos(when)
+            let duration = if absolute {
+                Duration::from_nanos(when.saturating_sub(clock_now))
+            } else {
+                Duration::from_nanos(when)
+            };
+            let deadline = tokio::time::Instant::now()
+                .checked_add(duration)
+                .ok_or_else(|| anyhow::anyhow!("time overflow: duration {duration:?}"))?;
+            Sleep::Host(deadline)
         };
-        let deadline = tokio::time::Instant::now()
-            .checked_add(duration)
-            .ok_or_else(|| anyhow::anyhow!("time overflow: duration {duration:?}"))?;
         // NB: this resource created here is not actually exposed to wasm, it's
         // only an internal implementation detail used to match the signature
         // expected by `subscribe`.
-        let sleep = self.table_mut().push(Sleep(deadline))?;
+        let sleep = self.table_mut().push(sleep)?;
         subscribe(self.table_mut(), sleep)
     }
 }
 
-struct Sleep(tokio::time::Instant);
+enum Sleep {
+    /// Sleeps until the host's time reaches the deadline.
+    Host(tokio::time::Instant),
+    /// Waits for a virtual clock to reach `when`, which happens when the
+    /// sleep is polled, see `advance_virtual_clock`.
+    Virtual { when: u64, ready: bool },
+}
 
 #[async_trait::async_trait]
 impl Subscribe for Sleep {
     async fn ready(&mut self) {
-        tokio::time::sleep_until(self.0).await;
+        match self {
+            Sleep::Host(deadline) => tokio::time::sleep_until(*deadline).await,
+            Sleep::Virtual { ready: true, .. } => {}
+            Sleep::Virtual { ready: false, .. } => std::future::pending().await,
+        }
+    }
+}
+
+/// Advances a virtual monotonic clock to the earliest deadline among the
+/// sleeps which are about to be polled, given by their indices in the table,
+/// and marks the sleeps whose deadline has then been reached as ready.
+///
+/// Other entries of `indices` are ignored, as are sleeps on the host's clock.
+pub(crate) fn advance_virtual_clock(
+    view: &mut impl WasiView,
+    indices: impl IntoIterator<Item = u32>,
+) -> anyhow::Result<()> {
+    let mut sleeps = Vec::new();
+    for index in indices {
+        let entry = view.table_mut().get_any_mut(index)?;
+        if let Some(Sleep::Virtual { when, .. }) = entry.downcast_ref::<Sleep>() {
+            sleeps.push((index, *when));
+        }
+    }
+    let Some(earliest) = sleeps.iter().map(|(_, when)| *when).min() else {
+        return Ok(());
+    };
+    let now = view
+        .ctx()
+        .monotonic_clock
+        .advance_to(earliest)
+        .unwrap_or(earliest);
+    for (index, when) in sleeps {
+        let entry = view.table_mut().get_any_mut(index)?;
+        if let Some(Sleep::Virtual { ready, .. }) = entry.downcast_mut::<Sleep>() {
+            *ready |= when <= now;
+        }
     }
+    Ok(())
 }
 
 impl<T: WasiView> timezone::Host for T {
@@ -5,7 +5,7 @@ use crate::preview2::bindings::filesystem::types::{
 };
 use crate::preview2::bindings::io::streams::{InputStream, OutputStream};
 use crate::preview2::filesystem::{Descriptor, Dir, File, ReaddirIterator};
-use crate::preview2::filesystem::{FileInputStream, FileOutputStream};
+use crate::preview2::filesystem::{FileInputStream, FileOutputMode, FileOutputStream};
 use crate::preview2::{DirPerms, FilePerms, FsError, FsResult, Table, WasiView};
 use anyhow::Context;
 use wasmtime::component::Resource;
@@ -17,10 +17,10 @@ impl<T: WasiView> preopens::Host for T {
         &mut self,
     ) -> Result<Vec<(Resource<types::Descriptor>, String)>, anyhow::Error> {
         let mut results = Vec::new();
-        for (dir, name) in self.ctx().preopens.clone() {
+        for (preopen, name) in self.ctx().preopens.clone() {
             let fd = self
                 .table_mut()
-                .push(Descriptor::Dir(dir))
+                .push(preopen)
                 .with_context(|| format!("failed to push preopen {name}"))?;
             results.push((fd, name));
         }
@@ -41,10 +41,14 @@ impl<T: WasiView> types::Host for T {
         let err = self.table_mut().get(&err)?;
 
         // Currently `err` always comes from the stream implementation which
-        // uses standard reads/writes so only check for `std::io::Error` here.
+        // uses standard reads/writes for host files, or reports an `ErrorCode`
+        // directly for virtual files.
         if let Some(err) = err.downcast_ref::<std::io::Error>() {
             return Ok(Some(ErrorCode::from(err)));
         }
+        if let Some(code) = err.downcast_ref::<ErrorCode>() {
+            return Ok(Some(*code));
+        }
 
         Ok(None)
     }
@@ -71,7 +75,11 @@ impl<T: WasiView> HostDescriptor for T {
             Advice::NoReuse => A::NoReuse,
         };
 
-        let f = self.table().get(&fd)?.file()?;
+        let f = match self.table().get(&fd)? {
+            // There's no backing storage to advise for virtual files.
+            Descriptor::Virtual(v) if !v.is_dir() => return Ok(()),
+            d => d.file()?,
+        };
         f.spawn_blocking(move |f| f.advise(offset, len, advice))
             .await?;
         Ok(())
@@ -101,6 +109,7 @@ impl<T: WasiView> HostDescriptor for T {
                 d.spawn_blocking(|d| Ok(d.open(std::path::Component::CurDir)?.sync_data()?))
                     .await
             }
+            Descriptor::Virtual(_) => Ok(()),
         }
     }
 
@@ -149,6 +158,7 @@ impl<T: WasiView> HostDescriptor for T {
                 }
                 Ok(flags)
             }
+            Descriptor::Virtual(v) => Ok(v.flags()),
         }
     }
 
@@ -164,6 +174,7 @@ impl<T: WasiView> HostDescriptor for T {
                 Ok(descriptortype_from(meta.file_type()))
             }
             Descriptor::Dir(_) => Ok(types::DescriptorType::Directory),
+            Descriptor::Virtual(v) => Ok(v.get_type()),
         }
     }
 
@@ -172,7 +183,10 @@ impl<T: WasiView> HostDescriptor for T {
         fd: Resource<types::Descriptor>,
         size: types::Filesize,
     ) -> FsResult<()> {
-        let f = self.table().get(&fd)?.file()?;
+        let f = match self.table().get(&fd)? {
+            Descriptor::Virtual(v) => return v.set_size(size),
+            d => d.file()?,
+        };
         if !f.perms.contains(FilePerms::WRITE) {
             Err(ErrorCode::NotPermitted)?;
         }
@@ -208,6 +222,8 @@ impl<T: WasiView> HostDescriptor for T {
                 d.spawn_blocking(|d| d.set_times(atim, mtim)).await?;
                 Ok(())
             }
+            // Virtual filesystems don't track timestamps.
+            Descriptor::Virtual(_) => Err(ErrorCode::Unsupported.into()),
         }
     }
 
@@ -222,7 +238,10 @@ impl<T: WasiView> HostDescriptor for T {
 
         let table = self.table();
 
-        let f = table.get(&fd)?.file()?;
+        let f = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.read(len, offset),
+            d => d.file()?,
+        };
         if !f.perms.contains(FilePerms::READ) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -259,7 +278,10 @@ impl<T: WasiView> HostDescriptor for T {
         use system_interface::fs::FileIoExt;
 
         let table = self.table();
-        let f = table.get(&fd)?.file()?;
+        let f = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.write(&buf, offset),
+            d => d.file()?,
+        };
         if !f.perms.contains(FilePerms::WRITE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -276,7 +298,13 @@ impl<T: WasiView> HostDescriptor for T {
         fd: Resource<types::Descriptor>,
     ) -> FsResult<Resource<types::DirectoryEntryStream>> {
         let table = self.table_mut();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => {
+                let entries = v.read_directory()?;
+                return Ok(table.push(ReaddirIterator::new(entries.into_iter().map(Ok)))?);
+            }
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::READ) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -360,6 +388,7 @@ impl<T: WasiView> HostDescriptor for T {
                 d.spawn_blocking(|d| Ok(d.open(std::path::Component::CurDir)?.sync_all()?))
                     .await
             }
+            Descriptor::Virtual(_) => Ok(()),
         }
     }
 
@@ -369,7 +398,10 @@ impl<T: WasiView> HostDescriptor for T {
         path: String,
     ) -> FsResult<()> {
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.create_directory_at(&path),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -390,6 +422,7 @@ impl<T: WasiView> HostDescriptor for T {
                 let meta = d.spawn_blocking(|d| d.dir_metadata()).await?;
                 Ok(descriptorstat_from(meta))
             }
+            Descriptor::Virtual(v) => v.stat(),
         }
     }
 
@@ -400,7 +433,10 @@ impl<T: WasiView> HostDescriptor for T {
         path: String,
     ) -> FsResult<types::DescriptorStat> {
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.stat_at(&path),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::READ) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -424,7 +460,10 @@ impl<T: WasiView> HostDescriptor for T {
         use cap_fs_ext::DirExt;
 
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(_) => return Err(ErrorCode::Unsupported.into()),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -462,11 +501,18 @@ impl<T: WasiView> HostDescriptor for T {
         new_path: String,
     ) -> FsResult<()> {
         let table = self.table();
-        let old_dir = table.get(&fd)?.dir()?;
+        let old_dir = match table.get(&fd)? {
+            // Virtual filesystems don't support hard links.
+            Descriptor::Virtual(_) => return Err(ErrorCode::Unsupported.into()),
+            d => d.dir()?,
+        };
         if !old_dir.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
-        let new_dir = table.get(&new_descriptor)?.dir()?;
+        let new_dir = match table.get(&new_descriptor)? {
+            Descriptor::Virtual(_) => return Err(ErrorCode::CrossDevice.into()),
+            d => d.dir()?,
+        };
         if !new_dir.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -496,7 +542,13 @@ impl<T: WasiView> HostDescriptor for T {
         use types::{DescriptorFlags, OpenFlags};
 
         let table = self.table_mut();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => {
+                let opened = v.open_at(&path, oflags, flags)?;
+                return Ok(table.push(Descriptor::Virtual(opened))?);
+            }
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::READ) {
             Err(ErrorCode::NotPermitted)?;
         }
@@ -617,7 +669,10 @@ impl<T: WasiView> HostDescriptor for T {
         path: String,
     ) -> FsResult<String> {
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.readlink_at(&path),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::READ) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -634,7 +689,10 @@ impl<T: WasiView> HostDescriptor for T {
         path: String,
     ) -> FsResult<()> {
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.remove_directory_at(&path),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -649,6 +707,15 @@ impl<T: WasiView> HostDescriptor for T {
         new_path: String,
     ) -> FsResult<()> {
         let table = self.table();
+        match (table.get(&fd)?, table.get(&new_fd)?) {
+            (Descriptor::Virtual(old_dir), Descriptor::Virtual(new_dir)) => {
+                return old_dir.rename_at(&old_path, new_dir, &new_path)
+            }
+            (Descriptor::Virtual(_), _) | (_, Descriptor::Virtual(_)) => {
+                return Err(ErrorCode::CrossDevice.into())
+            }
+            _ => {}
+        }
         let old_dir = table.get(&fd)?.dir()?;
         if !old_dir.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
@@ -674,7 +741,10 @@ impl<T: WasiView> HostDescriptor for T {
         use cap_fs_ext::DirExt;
 
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(_) => return Err(ErrorCode::Unsupported.into()),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -690,7 +760,10 @@ impl<T: WasiView> HostDescriptor for T {
         use cap_fs_ext::DirExt;
 
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.unlink_file_at(&path),
+            d => d.dir()?,
+        };
         if !d.perms.contains(DirPerms::MUTATE) {
             return Err(ErrorCode::NotPermitted.into());
         }
@@ -754,7 +827,13 @@ impl<T: WasiView> HostDescriptor for T {
         offset: types::Filesize,
     ) -> FsResult<Resource<InputStream>> {
         // Trap if fd lookup fails:
-        let f = self.table().get(&fd)?.file()?;
+        let f = match self.table().get(&fd)? {
+            Descriptor::Virtual(v) => {
+                let reader = Box::new(v.read_via_stream(offset)?);
+                return Ok(self.table_mut().push(InputStream::Host(reader))?);
+            }
+            d => d.file()?,
+        };
 
         if !f.perms.contains(FilePerms::READ) {
             Err(types::ErrorCode::BadDescriptor)?;
@@ -777,7 +856,14 @@ impl<T: WasiView> HostDescriptor for T {
         offset: types::Filesize,
     ) -> FsResult<Resource<OutputStream>> {
         // Trap if fd lookup fails:
-        let f = self.table().get(&fd)?.file()?;
+        let f = match self.table().get(&fd)? {
+            Descriptor::Virtual(v) => {
+                let writer: OutputStream =
+                    Box::new(v.write_via_stream(FileOutputMode::Position(offset))?);
+                return Ok(self.table_mut().push(writer)?);
+            }
+            d => d.file()?,
+        };
 
         if !f.perms.contains(FilePerms::WRITE) {
             Err(types::ErrorCode::BadDescriptor)?;
@@ -801,7 +887,13 @@ impl<T: WasiView> HostDescriptor for T {
         fd: Resource<types::Descriptor>,
     ) -> FsResult<Resource<OutputStream>> {
         // Trap if fd lookup fails:
-        let f = self.table().get(&fd)?.file()?;
+        let f = match self.table().get(&fd)? {
+            Descriptor::Virtual(v) => {
+                let appender: OutputStream = Box::new(v.write_via_stream(FileOutputMode::Append)?);
+                return Ok(self.table_mut().push(appender)?);
+            }
+            d => d.file()?,
+        };
 
         if !f.perms.contains(FilePerms::WRITE) {
             Err(types::ErrorCode::BadDescriptor)?;
@@ -826,6 +918,11 @@ impl<T: WasiView> HostDescriptor for T {
     ) -> anyhow::Result<bool> {
         use cap_fs_ext::MetadataExt;
         let table = self.table();
+        match (table.get(&a)?, table.get(&b)?) {
+            (Descriptor::Virtual(a), Descriptor::Virtual(b)) => return Ok(a.is_same_object(b)),
+            (Descriptor::Virtual(_), _) | (_, Descriptor::Virtual(_)) => return Ok(false),
+            _ => {}
+        }
         let meta_a = get_descriptor_metadata(table, a).await?;
         let meta_b = get_descriptor_metadata(table, b).await?;
         if meta_a.dev() == meta_b.dev() && meta_a.ino() == meta_b.ino() {
@@ -850,6 +947,9 @@ impl<T: WasiView> HostDescriptor for T {
         fd: Resource<types::Descriptor>,
     ) -> FsResult<types::MetadataHashValue> {
         let table = self.table();
+        if let Descriptor::Virtual(v) = table.get(&fd)? {
+            return v.metadata_hash();
+        }
         let meta = get_descriptor_metadata(table, fd).await?;
         Ok(calculate_metadata_hash(&meta))
     }
@@ -860,7 +960,10 @@ impl<T: WasiView> HostDescriptor for T {
         path: String,
     ) -> FsResult<types::MetadataHashValue> {
         let table = self.table();
-        let d = table.get(&fd)?.dir()?;
+        let d = match table.get(&fd)? {
+            Descriptor::Virtual(v) => return v.metadata_hash_at(&path),
+            d => d.dir()?,
+        };
         // No permissions check on metadata: if dir opened, allowed to stat it
         let meta = d
             .spawn_blocking(move |d| {
@@ -905,6 +1008,7 @@ async fn get_descriptor_metadata(
             // No permissions check on metadata: if opened, allowed to stat it
             Ok(d.spawn_blocking(|d| d.dir_metadata()).await?)
         }
+        Descriptor::Virtual(_) => Err(ErrorCode::BadDescriptor.into()),
     }
 }
 
@@ -1039,7 +1143,7 @@ impl From<std::num::TryFromIntError> for ErrorCode {
     }
 }
 
-fn descriptortype_from(ft: cap_std::fs::FileType) -> types::DescriptorType {
+pub(crate) fn descriptortype_from(ft: cap_std::fs::FileType) -> types::DescriptorType {
     use cap_fs_ext::FileTypeExt;
     use types::DescriptorType;
     if ft.is_dir() {
@@ -1079,7 +1183,7 @@ fn datetime_from(t: std::time::SystemTime) -> wall_clock::Datetime {
     wall_clock::Datetime::try_from(cap_std::time::SystemTime::from_std(t)).unwrap()
 }
 
-fn descriptorstat_from(meta: cap_std::fs::Metadata) -> types::DescriptorStat {
+pub(crate) fn descriptorstat_from(meta: cap_std::fs::Metadata) -> types::DescriptorStat {
     use cap_fs_ext::MetadataExt;
     types::DescriptorStat {
         type_: descriptortype_from(meta.file_type()),
@@ -1,4 +1,4 @@
-mod clocks;
+pub(crate) mod clocks;
 mod env;
 mod exit;
 pub(crate) mod filesystem;
@@ -1,6 +1,6 @@
 use crate::preview2::bindings::sockets::network::{
-    self, ErrorCode, IpAddressFamily, IpSocketAddress, Ipv4Address, Ipv4SocketAddress, Ipv6Address,
-    Ipv6SocketAddress,
+    self, ErrorCode, IpAddress, IpAddressFamily, IpSocketAddress, Ipv4Address, Ipv4SocketAddress,
+    Ipv6Address, Ipv6SocketAddress,
 };
 use crate::preview2::{SocketError, WasiView};
 use rustix::io::Errno;
@@ -104,6 +104,24 @@ impl From<Errno> for ErrorCode {
     }
 }
 
+impl From<IpAddress> for std::net::IpAddr {
+    fn from(addr: IpAddress) -> Self {
+        match addr {
+            IpAddress::Ipv4(v4) => Self::V4(to_ipv4_addr(v4)),
+            IpAddress::Ipv6(v6) => Self::V6(to_ipv6_addr(v6)),
+        }
+    }
+}
+
+impl From<std::net::IpAddr> for IpAddress {
+    fn from(addr: std::net::IpAddr) -> Self {
+        match addr {
+            std::net::IpAddr::V4(v4) => Self::Ipv4(from_ipv4_addr(v4)),
+            std::net::IpAddr::V6(v6) => Self::Ipv6(from_ipv6_addr(v6)),
+        }
+    }
+}
+
 impl From<IpSocketAddress> for std::net::SocketAddr {
     fn from(addr: IpSocketAddress) -> Self {
         match addr {
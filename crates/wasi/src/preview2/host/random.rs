@@ -4,27 +4,35 @@ use cap_rand::{distributions::Standard, Rng};
 
 impl<T: WasiView> random::Host for T {
     fn get_random_bytes(&mut self, len: u64) -> anyhow::Result<Vec<u8>> {
-        Ok((&mut self.ctx_mut().random)
+        let bytes = (&mut self.ctx_mut().random)
             .sample_iter(Standard)
             .take(len as usize)
-            .collect())
+            .collect();
+        self.ctx().check_replay()?;
+        Ok(bytes)
     }
 
     fn get_random_u64(&mut self) -> anyhow::Result<u64> {
-        Ok(self.ctx_mut().random.sample(Standard))
+        let n = self.ctx_mut().random.sample(Standard);
+        self.ctx().check_replay()?;
+        Ok(n)
     }
 }
 
 impl<T: WasiView> insecure::Host for T {
     fn get_insecure_random_bytes(&mut self, len: u64) -> anyhow::Result<Vec<u8>> {
-        Ok((&mut self.ctx_mut().insecure_random)
+        let bytes = (&mut self.ctx_mut().insecure_random)
             .sample_iter(Standard)
             .take(len as usize)
-            .collect())
+            .collect();
+        self.ctx().check_replay()?;
+        Ok(bytes)
     }
 
     fn get_insecure_random_u64(&mut self) -> anyhow::Result<u64> {
-        Ok(self.ctx_mut().insecure_random.sample(Standard))
+        let n = self.ctx_mut().insecure_random.sample(Standard);
+        self.ctx().check_replay()?;
+        Ok(n)
     }
 }
 
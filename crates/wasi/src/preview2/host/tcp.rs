@@ -1,3 +1,6 @@
+use crate::preview2::record::{
+    Recorder, RecordingInputStream, ReplayInputStream, Replayer, SocketOp, SocketValue,
+};
 use crate::preview2::tcp::{TcpSocket, TcpState};
 use crate::preview2::{
     bindings::{
@@ -7,7 +10,7 @@ use crate::preview2::{
     },
     tcp::SocketAddressFamily,
 };
-use crate::preview2::{Pollable, SocketResult, WasiView};
+use crate::preview2::{pipe, Pollable, SocketError, SocketResult, WasiView};
 use cap_net_ext::{Blocking, PoolExt, TcpListenerExt};
 use cap_std::net::TcpListener;
 use io_lifetimes::AsSocketlike;
@@ -26,11 +29,17 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
         network: Resource<Network>,
         local_address: IpSocketAddress,
     ) -> SocketResult<()> {
+        let records_or_replays = self.ctx().records_or_replays();
         let table = self.table_mut();
         let socket = table.get(&this)?;
         let network = table.get(&network)?;
         let local_address: SocketAddr = local_address.into();
 
+        // Incoming connections aren't part of traces.
+        if records_or_replays {
+            return Err(ErrorCode::NotSupported.into());
+        }
+
         match socket.tcp_state {
             TcpState::Default => {}
             TcpState::BindStarted => return Err(ErrorCode::ConcurrencyConflict.into()),
@@ -78,8 +87,10 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
         network: Resource<Network>,
         remote_address: IpSocketAddress,
     ) -> SocketResult<()> {
+        let recorder = self.ctx().recorder.clone();
+        let replayer = self.ctx().replayer.clone();
         let table = self.table_mut();
-        let r = {
+        let result = {
             let socket = table.get(&this)?;
             let network = table.get(&network)?;
             let remote_address: SocketAddr = remote_address.into();
@@ -102,34 +113,45 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
 
             let connecter = network.pool.tcp_connecter(remote_address)?;
 
-            // Do an OS `connect`. Our socket is non-blocking, so it'll either...
-            {
-                let view = &*socket.tcp_socket().as_socketlike_view::<TcpListener>();
-                let r = connecter.connect_existing_tcp_listener(view);
-                r
+            match &replayer {
+                // Replayed connections never touch the network.
+                Some(replayer) => {
+                    replayer.replay_socket(SocketOp::StartConnect, |value| match value {
+                        SocketValue::Connected(connected) => Some(connected),
+                        _ => None,
+                    })
+                }
+                // Do an OS `connect`. Our socket is non-blocking, so it'll either...
+                None => {
+                    let view = &*socket.tcp_socket().as_socketlike_view::<TcpListener>();
+                    match connecter.connect_existing_tcp_listener(view) {
+                        // succeed immediately,
+                        Ok(()) => Ok(true),
+                        // continue in progress,
+                        Err(err) if Errno::from_io_error(&err) == Some(INPROGRESS) => Ok(false),
+                        // or fail immediately.
+                        Err(err) => Err(match Errno::from_io_error(&err) {
+                            Some(Errno::AFNOSUPPORT) => ErrorCode::InvalidArgument.into(), // Just in case our own validations weren't sufficient.
+                            _ => err.into(),
+                        }),
+                    }
+                }
             }
         };
 
-        match r {
-            // succeed immediately,
-            Ok(()) => {
-                let socket = table.get_mut(&this)?;
-                socket.tcp_state = TcpState::ConnectReady;
-                return Ok(());
-            }
-            // continue in progress,
-            Err(err) if Errno::from_io_error(&err) == Some(INPROGRESS) => {}
-            // or fail immediately.
-            Err(err) => {
-                return Err(match Errno::from_io_error(&err) {
-                    Some(Errno::AFNOSUPPORT) => ErrorCode::InvalidArgument.into(), // Just in case our own validations weren't sufficient.
-                    _ => err.into(),
-                });
-            }
+        if let Some(recorder) = &recorder {
+            let value = result.as_ref().map(|c| SocketValue::Connected(*c));
+            recorder.record_socket(SocketOp::StartConnect, value);
         }
+        let connected = result?;
 
         let socket = table.get_mut(&this)?;
-        socket.tcp_state = TcpState::Connecting;
+        socket.replayed = replayer.is_some();
+        socket.tcp_state = if connected {
+            TcpState::ConnectReady
+        } else {
+            TcpState::Connecting
+        };
 
         Ok(())
     }
@@ -138,40 +160,39 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
         &mut self,
         this: Resource<tcp::TcpSocket>,
     ) -> SocketResult<(Resource<InputStream>, Resource<OutputStream>)> {
+        let recorder = self.ctx().recorder.clone();
+        let replayer = self.ctx().replayer.clone();
         let table = self.table_mut();
         let socket = table.get_mut(&this)?;
 
         match socket.tcp_state {
             TcpState::ConnectReady => {}
             TcpState::Connecting => {
-                // Do a `poll` to test for completion, using a timeout of zero
-                // to avoid blocking.
-                match rustix::event::poll(
-                    &mut [rustix::event::PollFd::new(
-                        socket.tcp_socket(),
-                        rustix::event::PollFlags::OUT,
-                    )],
-                    0,
-                ) {
-                    Ok(0) => return Err(ErrorCode::WouldBlock.into()),
-                    Ok(_) => (),
-                    Err(err) => Err(err).unwrap(),
+                let result = match &replayer {
+                    Some(replayer) => {
+                        replayer.replay_socket(SocketOp::FinishConnect, |value| match value {
+                            SocketValue::Unit => Some(()),
+                            _ => None,
+                        })
+                    }
+                    None => poll_connect(socket),
+                };
+                if let Some(recorder) = &recorder {
+                    let value = result.as_ref().map(|()| SocketValue::Unit);
+                    recorder.record_socket(SocketOp::FinishConnect, value);
                 }
-
-                // Check whether the connect succeeded.
-                match sockopt::get_socket_error(socket.tcp_socket()) {
-                    Ok(Ok(())) => {}
-                    Err(err) | Ok(Err(err)) => {
+                if let Err(err) = result {
+                    if err.downcast_ref() != Some(&ErrorCode::WouldBlock) {
                         socket.tcp_state = TcpState::ConnectFailed;
-                        return Err(err.into());
                     }
+                    return Err(err);
                 }
             }
             _ => return Err(ErrorCode::NotInProgress.into()),
         };
 
         socket.tcp_state = TcpState::Connected;
-        let (input, output) = socket.as_split();
+        let (input, output) = connection_streams(socket, recorder, replayer);
         let input_stream = self.table_mut().push_child(input, &this)?;
         let output_stream = self.table_mut().push_child(output, &this)?;
 
@@ -317,11 +338,19 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
             _ => {}
         }
 
-        let addr = socket
+        if let Some(replayer) = &self.ctx().replayer {
+            return replayer.replay_socket(SocketOp::LocalAddress, replayed_address);
+        }
+        let result = socket
             .tcp_socket()
             .as_socketlike_view::<std::net::TcpStream>()
-            .local_addr()?;
-        Ok(addr.into())
+            .local_addr()
+            .map_err(SocketError::from);
+        if let Some(recorder) = &self.ctx().recorder {
+            let value = result.as_ref().map(|addr| SocketValue::Address(*addr));
+            recorder.record_socket(SocketOp::LocalAddress, value);
+        }
+        Ok(result?.into())
     }
 
     fn remote_address(&mut self, this: Resource<tcp::TcpSocket>) -> SocketResult<IpSocketAddress> {
@@ -333,11 +362,19 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
             _ => return Err(ErrorCode::InvalidState.into()),
         }
 
-        let addr = socket
+        if let Some(replayer) = &self.ctx().replayer {
+            return replayer.replay_socket(SocketOp::RemoteAddress, replayed_address);
+        }
+        let result = socket
             .tcp_socket()
             .as_socketlike_view::<std::net::TcpStream>()
-            .peer_addr()?;
-        Ok(addr.into())
+            .peer_addr()
+            .map_err(SocketError::from);
+        if let Some(recorder) = &self.ctx().recorder {
+            let value = result.as_ref().map(|addr| SocketValue::Address(*addr));
+            recorder.record_socket(SocketOp::RemoteAddress, value);
+        }
+        Ok(result?.into())
     }
 
     fn address_family(
@@ -590,11 +627,22 @@ impl<T: WasiView> crate::preview2::host::tcp::tcp::HostTcpSocket for T {
             ShutdownType::Both => std::net::Shutdown::Both,
         };
 
-        socket
+        if let Some(replayer) = &self.ctx().replayer {
+            return replayer.replay_socket(SocketOp::Shutdown, |value| match value {
+                SocketValue::Unit => Some(()),
+                _ => None,
+            });
+        }
+        let result = socket
             .tcp_socket()
             .as_socketlike_view::<std::net::TcpStream>()
-            .shutdown(how)?;
-        Ok(())
+            .shutdown(how)
+            .map_err(SocketError::from);
+        if let Some(recorder) = &self.ctx().recorder {
+            let value = result.as_ref().map(|()| SocketValue::Unit);
+            recorder.record_socket(SocketOp::Shutdown, value);
+        }
+        result
     }
 
     fn drop(&mut self, this: Resource<tcp::TcpSocket>) -> Result<(), anyhow::Error> {
@@ -619,6 +667,58 @@ const INPROGRESS: Errno = Errno::INPROGRESS;
 #[cfg(windows)]
 const INPROGRESS: Errno = Errno::WOULDBLOCK;
 
+/// Checks whether a connection started by `start_connect` has been
+/// established.
+fn poll_connect(socket: &TcpSocket) -> SocketResult<()> {
+    // Do a `poll` to test for completion, using a timeout of zero
+    // to avoid blocking.
+    match rustix::event::poll(
+        &mut [rustix::event::PollFd::new(
+            socket.tcp_socket(),
+            rustix::event::PollFlags::OUT,
+        )],
+        0,
+    ) {
+        Ok(0) => return Err(ErrorCode::WouldBlock.into()),
+        Ok(_) => (),
+        Err(err) => Err(err).unwrap(),
+    }
+
+    // Check whether the connect succeeded.
+    match sockopt::get_socket_error(socket.tcp_socket()) {
+        Ok(Ok(())) => Ok(()),
+        Err(err) | Ok(Err(err)) => Err(err.into()),
+    }
+}
+
+/// Creates the streams of an established connection, recording the data
+/// received on it or replaying it from a trace.
+fn connection_streams(
+    socket: &TcpSocket,
+    recorder: Option<Recorder>,
+    replayer: Option<Replayer>,
+) -> (InputStream, OutputStream) {
+    if let Some(replayer) = replayer {
+        // Only received data is part of traces, so writes are discarded.
+        let input = Box::new(ReplayInputStream::socket(replayer));
+        return (InputStream::Host(input), Box::new(pipe::SinkOutputStream));
+    }
+    match (socket.as_split(), recorder) {
+        ((InputStream::Host(input), output), Some(recorder)) => {
+            let input = Box::new(RecordingInputStream::socket(input, recorder));
+            (InputStream::Host(input), output)
+        }
+        (streams, _) => streams,
+    }
+}
+
+fn replayed_address(value: SocketValue) -> Option<IpSocketAddress> {
+    match value {
+        SocketValue::Address(addr) => Some(addr.into()),
+        _ => None,
+    }
+}
+
 fn validate_unicast(addr: &SocketAddr) -> SocketResult<()> {
     match to_canonical(&addr.ip()) {
         IpAddr::V4(ipv4) => {
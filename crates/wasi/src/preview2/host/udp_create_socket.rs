@@ -1,4 +1,7 @@
-use crate::preview2::bindings::{sockets::network::IpAddressFamily, sockets::udp_create_socket};
+use crate::preview2::bindings::{
+    sockets::network::{ErrorCode, IpAddressFamily},
+    sockets::udp_create_socket,
+};
 use crate::preview2::udp::UdpSocket;
 use crate::preview2::{SocketResult, WasiView};
 use wasmtime::component::Resource;
@@ -8,6 +11,10 @@ impl<T: WasiView> udp_create_socket::Host for T {
         &mut self,
         address_family: IpAddressFamily,
     ) -> SocketResult<Resource<UdpSocket>> {
+        // UDP traffic isn't part of traces.
+        if self.ctx().records_or_replays() {
+            return Err(ErrorCode::NotSupported.into());
+        }
         let socket = UdpSocket::new(address_family.into())?;
         let socket = self.table_mut().push(socket)?;
         Ok(socket)
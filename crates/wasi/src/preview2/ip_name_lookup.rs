@@ -1,6 +1,7 @@
 use crate::preview2::bindings::sockets::ip_name_lookup::{Host, HostResolveAddressStream};
 use crate::preview2::bindings::sockets::network::{ErrorCode, IpAddress, IpAddressFamily, Network};
 use crate::preview2::poll::{subscribe, Pollable, Subscribe};
+use crate::preview2::record::{SocketOp, SocketValue};
 use crate::preview2::{spawn_blocking, AbortOnDropJoinHandle, SocketError, WasiView};
 use anyhow::Result;
 use std::mem;
@@ -43,6 +44,12 @@ impl<T: WasiView> Host for T {
         // rather than using the standard library to do it for us.
         let _ = include_unavailable;
 
+        // The addresses are replayed by `resolve_next_address`.
+        if self.ctx().replayer.is_some() {
+            let stream = ResolveAddressStream::Done(Ok(Vec::new().into_iter()));
+            return Ok(self.table_mut().push(stream)?);
+        }
+
         // For now use the standard library to perform actual resolution through
         // the usage of the `ToSocketAddrs` trait. This blocks the current
         // thread, so use `spawn_blocking`. Finally note that this is only
@@ -85,13 +92,44 @@ impl<T: WasiView> HostResolveAddressStream for T {
         &mut self,
         resource: Resource<ResolveAddressStream>,
     ) -> Result<Option<IpAddress>, SocketError> {
-        let stream = self.table_mut().get_mut(&resource)?;
+        if let Some(replayer) = &self.ctx().replayer {
+            self.table().get(&resource)?;
+            return replayer.replay_socket(SocketOp::ResolveNextAddress, |value| match value {
+                SocketValue::NextAddress(ip) => Some(ip.map(IpAddress::from)),
+                _ => None,
+            });
+        }
+        let result = self.table_mut().get_mut(&resource)?.next_address();
+        if let Some(recorder) = &self.ctx().recorder {
+            let value = result
+                .as_ref()
+                .map(|ip| SocketValue::NextAddress(ip.map(Into::into)));
+            recorder.record_socket(SocketOp::ResolveNextAddress, value);
+        }
+        result
+    }
+
+    fn subscribe(
+        &mut self,
+        resource: Resource<ResolveAddressStream>,
+    ) -> Result<Resource<Pollable>> {
+        subscribe(self.table_mut(), resource)
+    }
+
+    fn drop(&mut self, resource: Resource<ResolveAddressStream>) -> Result<()> {
+        self.table_mut().delete(resource)?;
+        Ok(())
+    }
+}
+
+impl ResolveAddressStream {
+    fn next_address(&mut self) -> Result<Option<IpAddress>, SocketError> {
         loop {
-            match stream {
+            match self {
                 ResolveAddressStream::Waiting(future) => {
                     match crate::preview2::poll_noop(Pin::new(future)) {
                         Some(result) => {
-                            *stream = ResolveAddressStream::Done(result.map(|v| v.into_iter()));
+                            *self = ResolveAddressStream::Done(result.map(|v| v.into_iter()));
                         }
                         None => return Err(ErrorCode::WouldBlock.into()),
                     }
@@ -104,18 +142,6 @@ impl<T: WasiView> HostResolveAddressStream for T {
             }
         }
     }
-
-    fn subscribe(
-        &mut self,
-        resource: Resource<ResolveAddressStream>,
-    ) -> Result<Resource<Pollable>> {
-        subscribe(self.table_mut(), resource)
-    }
-
-    fn drop(&mut self, resource: Resource<ResolveAddressStream>) -> Result<()> {
-        self.table_mut().delete(resource)?;
-        Ok(())
-    }
 }
 
 #[async_trait::async_trait]
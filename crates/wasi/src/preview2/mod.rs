@@ -32,14 +32,18 @@ mod poll;
 #[cfg(feature = "preview1-on-preview2")]
 pub mod preview1;
 mod random;
+pub mod record;
 mod stdio;
 mod stream;
 mod table;
 mod tcp;
 mod udp;
+pub mod vfs;
 mod write_stream;
 
-pub use self::clocks::{HostMonotonicClock, HostWallClock};
+pub use self::clocks::{
+    HostMonotonicClock, HostWallClock, VirtualMonotonicClock, VirtualWallClock,
+};
 pub use self::ctx::{WasiCtx, WasiCtxBuilder, WasiView};
 pub use self::error::{I32Exit, TrappableError};
 pub use self::filesystem::{DirPerms, FilePerms, FsError, FsResult};
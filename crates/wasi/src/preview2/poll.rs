@@ -1,3 +1,4 @@
+use crate::preview2::host::clocks::advance_virtual_clock;
 use crate::preview2::{bindings::io::poll, Table, WasiView};
 use anyhow::Result;
 use std::any::Any;
@@ -82,11 +83,17 @@ impl<T: WasiView> poll::Host for T {
             list.push(ix);
         }
 
+        advance_virtual_clock(self, table_futures.keys().copied())?;
+        let table = self.table_mut();
+
         let mut futures: Vec<(PollableFuture<'_>, Vec<ReadylistIndex>)> = Vec::new();
         for (entry, (make_future, readylist_indices)) in table.iter_entries(table_futures) {
             let entry = entry?;
             futures.push((make_future(entry), readylist_indices));
         }
+        // Poll the futures in the order their pollables were first listed,
+        // rather than the map's, so that execution is deterministic.
+        futures.sort_unstable_by_key(|(_, readylist_indices)| readylist_indices[0]);
 
         struct PollList<'a> {
             futures: Vec<(PollableFuture<'a>, Vec<ReadylistIndex>)>,
@@ -107,6 +114,7 @@ impl<T: WasiView> poll::Host for T {
                     }
                 }
                 if any_ready {
+                    results.sort_unstable();
                     Poll::Ready(results)
                 } else {
                     Poll::Pending
@@ -118,6 +126,8 @@ impl<T: WasiView> poll::Host for T {
     }
 
     async fn poll_one(&mut self, pollable: Resource<Pollable>) -> Result<()> {
+        let index = self.table().get(&pollable)?.index;
+        advance_virtual_clock(self, [index])?;
         let table = self.table_mut();
 
         let pollable = table.get(&pollable)?;
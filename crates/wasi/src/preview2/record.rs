@@ -0,0 +1,1694 @@
+//! Recording and replaying the inputs a guest receives from the host.
+//!
+//! A [`Recorder`] is installed with [`WasiCtxBuilder::record`], after which
+//! everything the context's clocks, random number generators and stdin return
+//! to the guest is written to a compact trace. Filesystems are recorded by
+//! wrapping them in a [`RecordingFs`] before they're preopened with
+//! [`WasiCtxBuilder::preopened_virtual_dir`].
+//!
+//! A [`Replayer`] reads such a trace back. [`WasiCtxBuilder::replay`] replaces
+//! the clocks, random number generators and stdin with ones which return the
+//! recorded values, and a [`ReplayFs`] stands in for each recorded filesystem,
+//! so the guest observes the same inputs as it did when it was recorded
+//! without the host being involved.
+//!
+//! Network traffic is recorded by the context as well: the results of name
+//! lookups and of connecting TCP sockets, and the data received on TCP
+//! connections. Replayed connections never touch the host's network, and
+//! the data the guest writes to them is discarded. Binding sockets, which
+//! includes listening for connections, and UDP aren't supported by contexts
+//! which record or replay. Responses to outgoing `wasi:http` requests are
+//! recorded with [`Recorder::record_http`] and replayed with
+//! [`Replayer::replay_http`], which the `wasmtime-wasi-http` crate uses.
+//!
+//! Traces are fully validated when they're read, so a malformed trace is
+//! reported by [`Replayer::new`] rather than while the guest runs. If a
+//! replayed guest diverges from the recording, for example because it was
+//! given different arguments, replayed filesystem calls and stdin reads trap.
+//! The clocks and random number generators have no way of reporting errors,
+//! so they return zeros and the divergence is reported as a trap by the next
+//! WASI call which can fail, which for the clock and random interfaces of a
+//! [`WasiCtx`] is the call itself.
+//!
+//! [`WasiCtx`]: crate::preview2::WasiCtx
+//! [`WasiCtxBuilder::record`]: crate::preview2::WasiCtxBuilder::record
+//! [`WasiCtxBuilder::replay`]: crate::preview2::WasiCtxBuilder::replay
+//! [`WasiCtxBuilder::preopened_virtual_dir`]: crate::preview2::WasiCtxBuilder::preopened_virtual_dir
+
+use crate::preview2::bindings::clocks::wall_clock::Datetime;
+use crate::preview2::bindings::filesystem::types::{self, DescriptorType, ErrorCode};
+use crate::preview2::bindings::sockets::network::ErrorCode as SocketErrorCode;
+use crate::preview2::clocks::{HostMonotonicClock, HostWallClock};
+use crate::preview2::vfs::VirtualFs;
+use crate::preview2::{
+    FsError, FsResult, HostInputStream, SocketError, SocketResult, StdinStream, StreamError,
+    StreamResult, Subscribe,
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use bytes::Bytes;
+use cap_rand::RngCore;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Bytes at the start of every trace, ending with the version of the format.
+const MAGIC: &[u8; 8] = b"\0wtrace\x01";
+
+/// The sources of input which are recorded.
+///
+/// Each event in a trace belongs to one channel and events are replayed in
+/// order within their channel. Keeping channels separate means that the
+/// relative order of, say, stdin reads made by a background thread and clock
+/// reads made by the guest doesn't need to be reproduced.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Channel {
+    WallClockNow,
+    WallClockResolution,
+    MonotonicClockNow,
+    MonotonicClockResolution,
+    SecureRandom,
+    InsecureRandom,
+    InsecureRandomSeed,
+    Stdin,
+    Filesystem,
+    Socket,
+    SocketData,
+    Http,
+}
+
+/// All channels, indexed by the byte which identifies them in a trace.
+const CHANNELS: [Channel; 12] = [
+    Channel::WallClockNow,
+    Channel::WallClockResolution,
+    Channel::MonotonicClockNow,
+    Channel::MonotonicClockResolution,
+    Channel::SecureRandom,
+    Channel::InsecureRandom,
+    Channel::InsecureRandomSeed,
+    Channel::Stdin,
+    Channel::Filesystem,
+    Channel::Socket,
+    Channel::SocketData,
+    Channel::Http,
+];
+
+/// Writes the inputs of a guest to a trace.
+///
+/// Recorders are cheaply cloneable handles to the same trace.
+#[derive(Clone)]
+pub struct Recorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+struct RecorderState {
+    out: Box<dyn Write + Send>,
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    /// Creates a recorder which writes a trace to `out`.
+    ///
+    /// Events are written as they happen, so `out` should usually be
+    /// buffered.
+    pub fn new(out: impl Write + Send + 'static) -> Result<Recorder> {
+        let mut out: Box<dyn Write + Send> = Box::new(out);
+        out.write_all(MAGIC)?;
+        Ok(Recorder {
+            state: Arc::new(Mutex::new(RecorderState { out, error: None })),
+        })
+    }
+
+    /// Creates a recorder which writes a trace to a new file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> Result<Recorder> {
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("failed to create trace file `{}`", path.display()))?;
+        Recorder::new(io::BufWriter::new(file))
+    }
+
+    /// Flushes the trace written so far.
+    ///
+    /// Recording can't fail from the guest's point of view, so this is also
+    /// where the first error encountered while writing the trace, if any, is
+    /// reported.
+    pub fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(err) = state.error.take() {
+            return Err(err).context("failed to write trace");
+        }
+        state.out.flush().context("failed to write trace")
+    }
+
+    fn record(&self, channel: Channel, payload: &[u8]) {
+        let mut event = Vec::with_capacity(payload.len() + 6);
+        event.push(channel as u8);
+        put_bytes(&mut event, payload);
+
+        let mut state = self.state.lock().unwrap();
+        if state.error.is_some() {
+            return;
+        }
+        if let Err(err) = state.out.write_all(&event) {
+            state.error = Some(err);
+        }
+    }
+}
+
+/// Serves the inputs recorded in a trace back to a guest.
+///
+/// Replayers are cheaply cloneable handles to the same trace, and each
+/// recorded event is returned exactly once.
+#[derive(Clone)]
+pub struct Replayer {
+    state: Arc<Mutex<ReplayState>>,
+}
+
+struct ReplayState {
+    channels: [VecDeque<Event>; CHANNELS.len()],
+    /// How the guest first diverged from the recording in a call which
+    /// couldn't report it.
+    divergence: Option<String>,
+}
+
+/// A decoded event of a trace.
+enum Event {
+    Duration(Duration),
+    Instant(u64),
+    Random(Vec<u8>),
+    Seed(u128),
+    Stream(StreamEvent),
+    Filesystem(FsOp, Result<FsValue, ErrorCode>),
+    Socket(SocketOp, Result<SocketValue, SocketErrorCode>),
+    Http(HttpEvent),
+}
+
+/// An event of the stdin or socket data channels.
+enum StreamEvent {
+    Data(Vec<u8>),
+    Closed,
+    Failed(String),
+    Isatty(bool),
+}
+
+/// The successful result of a filesystem operation, which depends on the
+/// [`FsOp`].
+enum FsValue {
+    Unit,
+    Stat(types::DescriptorStat),
+    Entries(Vec<types::DirectoryEntry>),
+    Data(Vec<u8>),
+}
+
+impl Replayer {
+    /// Parses a trace previously written by a [`Recorder`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if any event in the trace is malformed.
+    pub fn new(trace: &[u8]) -> Result<Replayer> {
+        let Some(mut events) = trace.strip_prefix(MAGIC) else {
+            bail!("not a trace or a trace from an incompatible version");
+        };
+        let mut channels: [VecDeque<Event>; CHANNELS.len()] = Default::default();
+        while let Some((&channel, rest)) = events.split_first() {
+            let mut reader = Reader(rest);
+            let payload = reader.bytes()?;
+            let channel = CHANNELS
+                .get(usize::from(channel))
+                .copied()
+                .ok_or_else(|| anyhow!("unknown channel {channel} in trace"))?;
+            let event = decode_event(channel, payload)
+                .with_context(|| format!("malformed {channel:?} event in trace"))?;
+            channels[channel as usize].push_back(event);
+            events = reader.0;
+        }
+        Ok(Replayer {
+            state: Arc::new(Mutex::new(ReplayState {
+                channels,
+                divergence: None,
+            })),
+        })
+    }
+
+    /// Reads the trace in the file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Replayer> {
+        let path = path.as_ref();
+        let trace = std::fs::read(path)
+            .with_context(|| format!("failed to read trace file `{}`", path.display()))?;
+        Replayer::new(&trace).with_context(|| format!("invalid trace file `{}`", path.display()))
+    }
+
+    /// Returns an error if the guest has diverged from the recording in a
+    /// call which couldn't report it.
+    pub fn check(&self) -> Result<()> {
+        match &self.state.lock().unwrap().divergence {
+            Some(divergence) => bail!("{divergence}"),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the next event of `channel`, or an error if the guest has
+    /// diverged from the recording.
+    fn next(&self, channel: Channel) -> Result<Event> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(divergence) = &state.divergence {
+            bail!("{divergence}");
+        }
+        state.channels[channel as usize].pop_front().ok_or_else(|| {
+            anyhow!("trace has no more {channel:?} events, the guest diverged from the recording")
+        })
+    }
+
+    /// Like `next` but for callers which can't report errors.
+    ///
+    /// If there's no event or `extract` doesn't accept it then the divergence
+    /// is remembered to be reported by the next call which can fail, and a
+    /// default value is returned in the meantime.
+    fn next_or_diverge<T: Default>(
+        &self,
+        channel: Channel,
+        extract: impl FnOnce(Event) -> Option<T>,
+    ) -> T {
+        let err = match self.next(channel) {
+            Ok(event) => match extract(event) {
+                Some(value) => return value,
+                None => anyhow!("guest diverged from the recording of {channel:?}"),
+            },
+            Err(err) => err,
+        };
+        let mut state = self.state.lock().unwrap();
+        if state.divergence.is_none() {
+            state.divergence = Some(err.to_string());
+        }
+        T::default()
+    }
+}
+
+fn decode_event(channel: Channel, payload: &[u8]) -> Result<Event> {
+    let mut reader = Reader(payload);
+    let event = match channel {
+        Channel::WallClockNow | Channel::WallClockResolution => Event::Duration(reader.duration()?),
+        Channel::MonotonicClockNow | Channel::MonotonicClockResolution => {
+            Event::Instant(reader.u64()?)
+        }
+        Channel::SecureRandom | Channel::InsecureRandom => Event::Random(reader.rest().to_vec()),
+        Channel::InsecureRandomSeed => {
+            let seed = reader
+                .rest()
+                .try_into()
+                .context("seed has the wrong size")?;
+            Event::Seed(u128::from_le_bytes(seed))
+        }
+        Channel::Stdin | Channel::SocketData => Event::Stream(match reader.u8()? {
+            STREAM_DATA => StreamEvent::Data(reader.rest().to_vec()),
+            STREAM_CLOSED => StreamEvent::Closed,
+            STREAM_FAILED => {
+                StreamEvent::Failed(String::from_utf8_lossy(reader.rest()).into_owned())
+            }
+            STREAM_ISATTY if channel == Channel::Stdin => StreamEvent::Isatty(reader.u8()? != 0),
+            tag => bail!("unknown stream event {tag}"),
+        }),
+        Channel::Filesystem => {
+            let op = FS_OPS
+                .get(usize::from(reader.u8()?))
+                .copied()
+                .context("unknown filesystem operation")?;
+            let result = match reader.u8()? {
+                0 => Ok(decode_fs_value(op, &mut reader)?),
+                _ => {
+                    let index = usize::from(reader.u8()?);
+                    Err(*ERROR_CODES.get(index).context("unknown error code")?)
+                }
+            };
+            Event::Filesystem(op, result)
+        }
+        Channel::Socket => {
+            let op = SOCKET_OPS
+                .get(usize::from(reader.u8()?))
+                .copied()
+                .context("unknown socket operation")?;
+            let result = match reader.u8()? {
+                0 => Ok(decode_socket_value(op, &mut reader)?),
+                _ => {
+                    let index = usize::from(reader.u8()?);
+                    Err(*SOCKET_ERROR_CODES
+                        .get(index)
+                        .context("unknown error code")?)
+                }
+            };
+            Event::Socket(op, result)
+        }
+        Channel::Http => Event::Http(decode_http_event(&mut reader)?),
+    };
+    reader.finish()?;
+    Ok(event)
+}
+
+fn put_u64(buf: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    put_u64(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn put_duration(buf: &mut Vec<u8>, duration: Duration) {
+    put_u64(buf, duration.as_secs());
+    put_u64(buf, duration.subsec_nanos().into());
+}
+
+fn put_ip_address(buf: &mut Vec<u8>, ip: IpAddr) {
+    match ip {
+        IpAddr::V4(ip) => {
+            buf.push(4);
+            buf.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            buf.push(6);
+            buf.extend_from_slice(&ip.octets());
+        }
+    }
+}
+
+fn put_socket_address(buf: &mut Vec<u8>, addr: SocketAddr) {
+    put_ip_address(buf, addr.ip());
+    put_u64(buf, addr.port().into());
+    if let SocketAddr::V6(addr) = addr {
+        put_u64(buf, addr.flowinfo().into());
+        put_u64(buf, addr.scope_id().into());
+    }
+}
+
+fn put_datetime(buf: &mut Vec<u8>, datetime: Option<Datetime>) {
+    match datetime {
+        Some(datetime) => {
+            buf.push(1);
+            put_u64(buf, datetime.seconds);
+            put_u64(buf, datetime.nanoseconds.into());
+        }
+        None => buf.push(0),
+    }
+}
+
+/// Decodes the values written by the `put_*` functions.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8> {
+        let (&byte, rest) = self.0.split_first().context("truncated trace")?;
+        self.0 = rest;
+        Ok(byte)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        bail!("integer in trace is too large")
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(self.u64()?.try_into()?)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(self.u64()?.try_into()?)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        ensure!(N <= self.0.len(), "truncated trace");
+        let (bytes, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = usize::try_from(self.u64()?)?;
+        ensure!(len <= self.0.len(), "truncated trace");
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        Ok(String::from_utf8(self.bytes()?.to_vec())?)
+    }
+
+    fn ip_address(&mut self) -> Result<IpAddr> {
+        Ok(match self.u8()? {
+            4 => Ipv4Addr::from(self.array::<4>()?).into(),
+            6 => Ipv6Addr::from(self.array::<16>()?).into(),
+            tag => bail!("unknown address family {tag}"),
+        })
+    }
+
+    fn socket_address(&mut self) -> Result<SocketAddr> {
+        Ok(match self.ip_address()? {
+            IpAddr::V4(ip) => SocketAddrV4::new(ip, self.u16()?).into(),
+            IpAddr::V6(ip) => SocketAddrV6::new(ip, self.u16()?, self.u32()?, self.u32()?).into(),
+        })
+    }
+
+    fn duration(&mut self) -> Result<Duration> {
+        Ok(Duration::new(self.u64()?, self.u32()?))
+    }
+
+    fn datetime(&mut self) -> Result<Option<Datetime>> {
+        Ok(match self.u8()? {
+            0 => None,
+            _ => Some(Datetime {
+                seconds: self.u64()?,
+                nanoseconds: self.u32()?,
+            }),
+        })
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.0)
+    }
+
+    fn finish(self) -> Result<()> {
+        ensure!(self.0.is_empty(), "trailing data in trace event");
+        Ok(())
+    }
+}
+
+pub(crate) struct RecordingWallClock {
+    inner: Box<dyn HostWallClock + Send + Sync>,
+    recorder: Recorder,
+}
+
+impl RecordingWallClock {
+    pub(crate) fn new(inner: Box<dyn HostWallClock + Send + Sync>, recorder: Recorder) -> Self {
+        RecordingWallClock { inner, recorder }
+    }
+
+    fn record(&self, channel: Channel, duration: Duration) -> Duration {
+        let mut buf = Vec::new();
+        put_duration(&mut buf, duration);
+        self.recorder.record(channel, &buf);
+        duration
+    }
+}
+
+impl HostWallClock for RecordingWallClock {
+    fn resolution(&self) -> Duration {
+        self.record(Channel::WallClockResolution, self.inner.resolution())
+    }
+
+    fn now(&self) -> Duration {
+        self.record(Channel::WallClockNow, self.inner.now())
+    }
+}
+
+pub(crate) struct ReplayWallClock {
+    replayer: Replayer,
+}
+
+impl ReplayWallClock {
+    pub(crate) fn new(replayer: Replayer) -> Self {
+        ReplayWallClock { replayer }
+    }
+
+    fn replay(&self, channel: Channel) -> Duration {
+        self.replayer.next_or_diverge(channel, |event| match event {
+            Event::Duration(duration) => Some(duration),
+            _ => None,
+        })
+    }
+}
+
+impl HostWallClock for ReplayWallClock {
+    fn resolution(&self) -> Duration {
+        self.replay(Channel::WallClockResolution)
+    }
+
+    fn now(&self) -> Duration {
+        self.replay(Channel::WallClockNow)
+    }
+}
+
+pub(crate) struct RecordingMonotonicClock {
+    inner: Box<dyn HostMonotonicClock + Send + Sync>,
+    recorder: Recorder,
+}
+
+impl RecordingMonotonicClock {
+    pub(crate) fn new(
+        inner: Box<dyn HostMonotonicClock + Send + Sync>,
+        recorder: Recorder,
+    ) -> Self {
+        RecordingMonotonicClock { inner, recorder }
+    }
+
+    fn record(&self, channel: Channel, instant: u64) -> u64 {
+        let mut buf = Vec::new();
+        put_u64(&mut buf, instant);
+        self.recorder.record(channel, &buf);
+        instant
+    }
+}
+
+impl HostMonotonicClock for RecordingMonotonicClock {
+    fn resolution(&self) -> u64 {
+        self.record(Channel::MonotonicClockResolution, self.inner.resolution())
+    }
+
+    fn now(&self) -> u64 {
+        self.record(Channel::MonotonicClockNow, self.inner.now())
+    }
+
+    fn advance_to(&self, when: u64) -> Option<u64> {
+        self.inner.advance_to(when)
+    }
+}
+
+pub(crate) struct ReplayMonotonicClock {
+    replayer: Replayer,
+}
+
+impl ReplayMonotonicClock {
+    pub(crate) fn new(replayer: Replayer) -> Self {
+        ReplayMonotonicClock { replayer }
+    }
+
+    fn replay(&self, channel: Channel) -> u64 {
+        self.replayer.next_or_diverge(channel, |event| match event {
+            Event::Instant(instant) => Some(instant),
+            _ => None,
+        })
+    }
+}
+
+impl HostMonotonicClock for ReplayMonotonicClock {
+    fn resolution(&self) -> u64 {
+        self.replay(Channel::MonotonicClockResolution)
+    }
+
+    fn now(&self) -> u64 {
+        self.replay(Channel::MonotonicClockNow)
+    }
+
+    fn advance_to(&self, when: u64) -> Option<u64> {
+        // The clock's readings come from the trace, so there's no reason to
+        // actually wait for sleeps to finish.
+        Some(when)
+    }
+}
+
+pub(crate) struct RecordingRng {
+    inner: Box<dyn RngCore + Send + Sync>,
+    recorder: Recorder,
+    channel: Channel,
+}
+
+impl RecordingRng {
+    pub(crate) fn secure(inner: Box<dyn RngCore + Send + Sync>, recorder: Recorder) -> Self {
+        RecordingRng {
+            inner,
+            recorder,
+            channel: Channel::SecureRandom,
+        }
+    }
+
+    pub(crate) fn insecure(inner: Box<dyn RngCore + Send + Sync>, recorder: Recorder) -> Self {
+        RecordingRng {
+            inner,
+            recorder,
+            channel: Channel::InsecureRandom,
+        }
+    }
+}
+
+impl RngCore for RecordingRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest);
+        self.recorder.record(self.channel, dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), cap_rand::Error> {
+        self.inner.try_fill_bytes(dest)?;
+        self.recorder.record(self.channel, dest);
+        Ok(())
+    }
+}
+
+pub(crate) struct ReplayRng {
+    replayer: Replayer,
+    channel: Channel,
+}
+
+impl ReplayRng {
+    pub(crate) fn secure(replayer: Replayer) -> Self {
+        ReplayRng {
+            replayer,
+            channel: Channel::SecureRandom,
+        }
+    }
+
+    pub(crate) fn insecure(replayer: Replayer) -> Self {
+        ReplayRng {
+            replayer,
+            channel: Channel::InsecureRandom,
+        }
+    }
+}
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        // A guest which requests a different amount of randomness than was
+        // recorded has diverged, and gets zeros.
+        let len = dest.len();
+        let bytes = self
+            .replayer
+            .next_or_diverge(self.channel, |event| match event {
+                Event::Random(bytes) if bytes.len() == len => Some(bytes),
+                _ => None,
+            });
+        if bytes.len() == len {
+            dest.copy_from_slice(&bytes);
+        } else {
+            dest.fill(0);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), cap_rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+pub(crate) fn record_insecure_random_seed(recorder: &Recorder, seed: u128) {
+    recorder.record(Channel::InsecureRandomSeed, &seed.to_le_bytes());
+}
+
+pub(crate) fn replay_insecure_random_seed(replayer: &Replayer) -> Result<u128> {
+    match replayer.next(Channel::InsecureRandomSeed)? {
+        Event::Seed(seed) => Ok(seed),
+        _ => unreachable!("seed channel only contains seeds"),
+    }
+}
+
+/// Tags of events in the stdin and socket data channels.
+const STREAM_DATA: u8 = 0;
+const STREAM_CLOSED: u8 = 1;
+const STREAM_FAILED: u8 = 2;
+const STREAM_ISATTY: u8 = 3;
+
+pub(crate) struct RecordingStdin {
+    inner: Box<dyn StdinStream>,
+    recorder: Recorder,
+}
+
+impl RecordingStdin {
+    pub(crate) fn new(inner: Box<dyn StdinStream>, recorder: Recorder) -> Self {
+        RecordingStdin { inner, recorder }
+    }
+}
+
+impl StdinStream for RecordingStdin {
+    fn stream(&self) -> Box<dyn HostInputStream> {
+        Box::new(RecordingInputStream {
+            inner: self.inner.stream(),
+            recorder: self.recorder.clone(),
+            channel: Channel::Stdin,
+        })
+    }
+
+    fn isatty(&self) -> bool {
+        let isatty = self.inner.isatty();
+        self.recorder
+            .record(Channel::Stdin, &[STREAM_ISATTY, isatty.into()]);
+        isatty
+    }
+}
+
+/// An input stream which records the data read from the stream it wraps.
+pub(crate) struct RecordingInputStream {
+    inner: Box<dyn HostInputStream>,
+    recorder: Recorder,
+    channel: Channel,
+}
+
+impl RecordingInputStream {
+    /// Records the data received on a TCP connection.
+    pub(crate) fn socket(inner: Box<dyn HostInputStream>, recorder: Recorder) -> Self {
+        RecordingInputStream {
+            inner,
+            recorder,
+            channel: Channel::SocketData,
+        }
+    }
+}
+
+impl HostInputStream for RecordingInputStream {
+    fn read(&mut self, size: usize) -> StreamResult<Bytes> {
+        let result = self.inner.read(size);
+        let mut buf = Vec::new();
+        match &result {
+            Ok(bytes) => {
+                buf.push(STREAM_DATA);
+                buf.extend_from_slice(bytes);
+            }
+            Err(StreamError::Closed) => buf.push(STREAM_CLOSED),
+            Err(StreamError::LastOperationFailed(e)) => {
+                buf.push(STREAM_FAILED);
+                buf.extend_from_slice(format!("{e:?}").as_bytes());
+            }
+            // Traps end the guest, so there's nothing to replay afterwards.
+            Err(StreamError::Trap(_)) => return result,
+        }
+        self.recorder.record(self.channel, &buf);
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for RecordingInputStream {
+    async fn ready(&mut self) {
+        self.inner.ready().await
+    }
+}
+
+pub(crate) struct ReplayStdin {
+    replayer: Replayer,
+}
+
+impl ReplayStdin {
+    pub(crate) fn new(replayer: Replayer) -> Self {
+        ReplayStdin { replayer }
+    }
+}
+
+impl StdinStream for ReplayStdin {
+    fn stream(&self) -> Box<dyn HostInputStream> {
+        Box::new(ReplayInputStream {
+            replayer: self.replayer.clone(),
+            channel: Channel::Stdin,
+        })
+    }
+
+    fn isatty(&self) -> bool {
+        self.replayer
+            .next_or_diverge(Channel::Stdin, |event| match event {
+                Event::Stream(StreamEvent::Isatty(isatty)) => Some(isatty),
+                _ => None,
+            })
+    }
+}
+
+/// An input stream which returns the data recorded by a
+/// [`RecordingInputStream`].
+pub(crate) struct ReplayInputStream {
+    replayer: Replayer,
+    channel: Channel,
+}
+
+impl ReplayInputStream {
+    /// Replays the data received on a TCP connection.
+    pub(crate) fn socket(replayer: Replayer) -> Self {
+        ReplayInputStream {
+            replayer,
+            channel: Channel::SocketData,
+        }
+    }
+}
+
+impl HostInputStream for ReplayInputStream {
+    fn read(&mut self, size: usize) -> StreamResult<Bytes> {
+        let event = self
+            .replayer
+            .next(self.channel)
+            .map_err(StreamError::Trap)?;
+        match event {
+            Event::Stream(StreamEvent::Data(data)) if data.len() <= size => Ok(data.into()),
+            Event::Stream(StreamEvent::Closed) => Err(StreamError::Closed),
+            Event::Stream(StreamEvent::Failed(msg)) => {
+                Err(StreamError::LastOperationFailed(anyhow!("{msg}")))
+            }
+            _ => Err(StreamError::Trap(anyhow!(
+                "guest diverged from the recording of {:?}",
+                self.channel
+            ))),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for ReplayInputStream {
+    async fn ready(&mut self) {}
+}
+
+/// The operations of [`VirtualFs`], which are recorded along with their
+/// results to detect when a replayed guest diverges from the recording.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum FsOp {
+    Stat,
+    ReadDir,
+    Read,
+    Write,
+    SetSize,
+    CreateFile,
+    CreateDir,
+    RemoveFile,
+    RemoveDir,
+    Rename,
+}
+
+/// All filesystem operations, indexed by the byte which identifies them in a
+/// trace.
+const FS_OPS: [FsOp; 10] = [
+    FsOp::Stat,
+    FsOp::ReadDir,
+    FsOp::Read,
+    FsOp::Write,
+    FsOp::SetSize,
+    FsOp::CreateFile,
+    FsOp::CreateDir,
+    FsOp::RemoveFile,
+    FsOp::RemoveDir,
+    FsOp::Rename,
+];
+
+const DESCRIPTOR_TYPES: [DescriptorType; 8] = [
+    DescriptorType::Unknown,
+    DescriptorType::BlockDevice,
+    DescriptorType::CharacterDevice,
+    DescriptorType::Directory,
+    DescriptorType::Fifo,
+    DescriptorType::SymbolicLink,
+    DescriptorType::RegularFile,
+    DescriptorType::Socket,
+];
+
+const ERROR_CODES: [ErrorCode; 37] = [
+    ErrorCode::Access,
+    ErrorCode::WouldBlock,
+    ErrorCode::Already,
+    ErrorCode::BadDescriptor,
+    ErrorCode::Busy,
+    ErrorCode::Deadlock,
+    ErrorCode::Quota,
+    ErrorCode::Exist,
+    ErrorCode::FileTooLarge,
+    ErrorCode::IllegalByteSequence,
+    ErrorCode::InProgress,
+    ErrorCode::Interrupted,
+    ErrorCode::Invalid,
+    ErrorCode::Io,
+    ErrorCode::IsDirectory,
+    ErrorCode::Loop,
+    ErrorCode::TooManyLinks,
+    ErrorCode::MessageSize,
+    ErrorCode::NameTooLong,
+    ErrorCode::NoDevice,
+    ErrorCode::NoEntry,
+    ErrorCode::NoLock,
+    ErrorCode::InsufficientMemory,
+    ErrorCode::InsufficientSpace,
+    ErrorCode::NotDirectory,
+    ErrorCode::NotEmpty,
+    ErrorCode::NotRecoverable,
+    ErrorCode::Unsupported,
+    ErrorCode::NoTty,
+    ErrorCode::NoSuchDevice,
+    ErrorCode::Overflow,
+    ErrorCode::NotPermitted,
+    ErrorCode::Pipe,
+    ErrorCode::ReadOnly,
+    ErrorCode::InvalidSeek,
+    ErrorCode::TextFileBusy,
+    ErrorCode::CrossDevice,
+];
+
+fn put_descriptor_type(buf: &mut Vec<u8>, ty: DescriptorType) {
+    let index = DESCRIPTOR_TYPES.iter().position(|t| *t == ty).unwrap();
+    buf.push(index as u8);
+}
+
+fn descriptor_type(reader: &mut Reader<'_>) -> Result<DescriptorType> {
+    let index = usize::from(reader.u8()?);
+    DESCRIPTOR_TYPES
+        .get(index)
+        .copied()
+        .context("invalid descriptor type in trace")
+}
+
+fn decode_fs_value(op: FsOp, reader: &mut Reader<'_>) -> Result<FsValue> {
+    Ok(match op {
+        FsOp::Stat => FsValue::Stat(types::DescriptorStat {
+            type_: descriptor_type(reader)?,
+            link_count: reader.u64()?,
+            size: reader.u64()?,
+            data_access_timestamp: reader.datetime()?,
+            data_modification_timestamp: reader.datetime()?,
+            status_change_timestamp: reader.datetime()?,
+        }),
+        FsOp::ReadDir => {
+            let len = reader.u64()?;
+            let mut entries = Vec::new();
+            for _ in 0..len {
+                entries.push(types::DirectoryEntry {
+                    type_: descriptor_type(reader)?,
+                    name: reader.string()?,
+                });
+            }
+            FsValue::Entries(entries)
+        }
+        FsOp::Read => FsValue::Data(reader.bytes()?.to_vec()),
+        FsOp::Write
+        | FsOp::SetSize
+        | FsOp::CreateFile
+        | FsOp::CreateDir
+        | FsOp::RemoveFile
+        | FsOp::RemoveDir
+        | FsOp::Rename => FsValue::Unit,
+    })
+}
+
+/// A [`VirtualFs`] which records the results of all operations on the
+/// filesystem it wraps, so they can later be replayed by a [`ReplayFs`].
+pub struct RecordingFs {
+    inner: Arc<dyn VirtualFs>,
+    recorder: Recorder,
+}
+
+impl RecordingFs {
+    /// Creates a filesystem which records the results of operations on
+    /// `inner` with `recorder`.
+    pub fn new(inner: Arc<dyn VirtualFs>, recorder: Recorder) -> Self {
+        RecordingFs { inner, recorder }
+    }
+
+    fn record<T>(
+        &self,
+        op: FsOp,
+        result: FsResult<T>,
+        encode: impl FnOnce(&mut Vec<u8>, &T),
+    ) -> FsResult<T> {
+        let mut buf = vec![op as u8];
+        match &result {
+            Ok(value) => {
+                buf.push(0);
+                encode(&mut buf, value);
+            }
+            Err(err) => match err.downcast_ref() {
+                Some(code) => {
+                    let index = ERROR_CODES.iter().position(|c| c == code).unwrap();
+                    buf.push(1);
+                    buf.push(index as u8);
+                }
+                // Traps end the guest, so there's nothing to replay
+                // afterwards.
+                None => return result,
+            },
+        }
+        self.recorder.record(Channel::Filesystem, &buf);
+        result
+    }
+}
+
+impl VirtualFs for RecordingFs {
+    fn stat(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        self.record(FsOp::Stat, self.inner.stat(path), |buf, stat| {
+            put_descriptor_type(buf, stat.type_);
+            put_u64(buf, stat.link_count);
+            put_u64(buf, stat.size);
+            put_datetime(buf, stat.data_access_timestamp);
+            put_datetime(buf, stat.data_modification_timestamp);
+            put_datetime(buf, stat.status_change_timestamp);
+        })
+    }
+
+    fn read_dir(&self, path: &str) -> FsResult<Vec<types::DirectoryEntry>> {
+        self.record(FsOp::ReadDir, self.inner.read_dir(path), |buf, entries| {
+            put_u64(buf, entries.len() as u64);
+            for entry in entries {
+                put_descriptor_type(buf, entry.type_);
+                put_bytes(buf, entry.name.as_bytes());
+            }
+        })
+    }
+
+    fn read(&self, path: &str, offset: u64, len: usize) -> FsResult<Vec<u8>> {
+        self.record(
+            FsOp::Read,
+            self.inner.read(path, offset, len),
+            |buf, data| put_bytes(buf, data),
+        )
+    }
+
+    fn write(&self, path: &str, offset: u64, data: &[u8]) -> FsResult<()> {
+        self.record(
+            FsOp::Write,
+            self.inner.write(path, offset, data),
+            |_, ()| {},
+        )
+    }
+
+    fn set_size(&self, path: &str, size: u64) -> FsResult<()> {
+        self.record(FsOp::SetSize, self.inner.set_size(path, size), |_, ()| {})
+    }
+
+    fn create_file(&self, path: &str, exclusive: bool) -> FsResult<()> {
+        let result = self.inner.create_file(path, exclusive);
+        self.record(FsOp::CreateFile, result, |_, ()| {})
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult<()> {
+        self.record(FsOp::CreateDir, self.inner.create_dir(path), |_, ()| {})
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult<()> {
+        self.record(FsOp::RemoveFile, self.inner.remove_file(path), |_, ()| {})
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult<()> {
+        self.record(FsOp::RemoveDir, self.inner.remove_dir(path), |_, ()| {})
+    }
+
+    fn rename(&self, from: &str, to: &str) -> FsResult<()> {
+        self.record(FsOp::Rename, self.inner.rename(from, to), |_, ()| {})
+    }
+}
+
+/// A [`VirtualFs`] which returns the results recorded by a [`RecordingFs`].
+///
+/// Writes are not applied anywhere, but since their effects on later reads
+/// were recorded as well the guest can't tell the difference.
+pub struct ReplayFs {
+    replayer: Replayer,
+}
+
+impl ReplayFs {
+    /// Creates a filesystem which replays the results of operations recorded
+    /// in the trace read by `replayer`.
+    pub fn new(replayer: Replayer) -> Self {
+        ReplayFs { replayer }
+    }
+
+    fn replay<T>(&self, op: FsOp, extract: impl FnOnce(FsValue) -> Option<T>) -> FsResult<T> {
+        let event = self
+            .replayer
+            .next(Channel::Filesystem)
+            .map_err(FsError::trap)?;
+        let diverged = |recorded: FsOp| {
+            FsError::trap(anyhow!(
+                "guest diverged from the recording: called {op:?} where {recorded:?} was recorded"
+            ))
+        };
+        match event {
+            Event::Filesystem(recorded, _) if recorded != op => Err(diverged(recorded)),
+            Event::Filesystem(_, Ok(value)) => extract(value).ok_or_else(|| diverged(op)),
+            Event::Filesystem(_, Err(code)) => Err(code.into()),
+            _ => unreachable!("filesystem channel only contains filesystem events"),
+        }
+    }
+
+    fn replay_unit(&self, op: FsOp) -> FsResult<()> {
+        self.replay(op, |value| match value {
+            FsValue::Unit => Some(()),
+            _ => None,
+        })
+    }
+}
+
+impl VirtualFs for ReplayFs {
+    fn stat(&self, _path: &str) -> FsResult<types::DescriptorStat> {
+        self.replay(FsOp::Stat, |value| match value {
+            FsValue::Stat(stat) => Some(stat),
+            _ => None,
+        })
+    }
+
+    fn read_dir(&self, _path: &str) -> FsResult<Vec<types::DirectoryEntry>> {
+        self.replay(FsOp::ReadDir, |value| match value {
+            FsValue::Entries(entries) => Some(entries),
+            _ => None,
+        })
+    }
+
+    fn read(&self, _path: &str, _offset: u64, len: usize) -> FsResult<Vec<u8>> {
+        let data = self.replay(FsOp::Read, |value| match value {
+            FsValue::Data(data) => Some(data),
+            _ => None,
+        })?;
+        if data.len() > len {
+            return Err(FsError::trap(anyhow!(
+                "guest diverged from the recording: read more data than requested"
+            )));
+        }
+        Ok(data)
+    }
+
+    fn write(&self, _path: &str, _offset: u64, _buf: &[u8]) -> FsResult<()> {
+        self.replay_unit(FsOp::Write)
+    }
+
+    fn set_size(&self, _path: &str, _size: u64) -> FsResult<()> {
+        self.replay_unit(FsOp::SetSize)
+    }
+
+    fn create_file(&self, _path: &str, _exclusive: bool) -> FsResult<()> {
+        self.replay_unit(FsOp::CreateFile)
+    }
+
+    fn create_dir(&self, _path: &str) -> FsResult<()> {
+        self.replay_unit(FsOp::CreateDir)
+    }
+
+    fn remove_file(&self, _path: &str) -> FsResult<()> {
+        self.replay_unit(FsOp::RemoveFile)
+    }
+
+    fn remove_dir(&self, _path: &str) -> FsResult<()> {
+        self.replay_unit(FsOp::RemoveDir)
+    }
+
+    fn rename(&self, _from: &str, _to: &str) -> FsResult<()> {
+        self.replay_unit(FsOp::Rename)
+    }
+}
+
+/// The operations on sockets whose results are recorded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SocketOp {
+    ResolveNextAddress,
+    StartConnect,
+    FinishConnect,
+    LocalAddress,
+    RemoteAddress,
+    Shutdown,
+}
+
+/// All socket operations, indexed by the byte which identifies them in a
+/// trace.
+const SOCKET_OPS: [SocketOp; 6] = [
+    SocketOp::ResolveNextAddress,
+    SocketOp::StartConnect,
+    SocketOp::FinishConnect,
+    SocketOp::LocalAddress,
+    SocketOp::RemoteAddress,
+    SocketOp::Shutdown,
+];
+
+/// The successful result of a socket operation, which depends on the
+/// [`SocketOp`].
+pub(crate) enum SocketValue {
+    Unit,
+    /// Whether `start-connect` established the connection immediately.
+    Connected(bool),
+    /// The next address a name resolved to, or `None` once there are no
+    /// more.
+    NextAddress(Option<IpAddr>),
+    Address(SocketAddr),
+}
+
+const SOCKET_ERROR_CODES: [SocketErrorCode; 21] = [
+    SocketErrorCode::Unknown,
+    SocketErrorCode::AccessDenied,
+    SocketErrorCode::NotSupported,
+    SocketErrorCode::InvalidArgument,
+    SocketErrorCode::OutOfMemory,
+    SocketErrorCode::Timeout,
+    SocketErrorCode::ConcurrencyConflict,
+    SocketErrorCode::NotInProgress,
+    SocketErrorCode::WouldBlock,
+    SocketErrorCode::InvalidState,
+    SocketErrorCode::NewSocketLimit,
+    SocketErrorCode::AddressNotBindable,
+    SocketErrorCode::AddressInUse,
+    SocketErrorCode::RemoteUnreachable,
+    SocketErrorCode::ConnectionRefused,
+    SocketErrorCode::ConnectionReset,
+    SocketErrorCode::ConnectionAborted,
+    SocketErrorCode::DatagramTooLarge,
+    SocketErrorCode::NameUnresolvable,
+    SocketErrorCode::TemporaryResolverFailure,
+    SocketErrorCode::PermanentResolverFailure,
+];
+
+fn decode_socket_value(op: SocketOp, reader: &mut Reader<'_>) -> Result<SocketValue> {
+    Ok(match op {
+        SocketOp::ResolveNextAddress => SocketValue::NextAddress(match reader.u8()? {
+            0 => None,
+            _ => Some(reader.ip_address()?),
+        }),
+        SocketOp::StartConnect => SocketValue::Connected(reader.u8()? != 0),
+        SocketOp::LocalAddress | SocketOp::RemoteAddress => {
+            SocketValue::Address(reader.socket_address()?)
+        }
+        SocketOp::FinishConnect | SocketOp::Shutdown => SocketValue::Unit,
+    })
+}
+
+impl Recorder {
+    /// Records the result of the socket operation `op`.
+    pub(crate) fn record_socket(&self, op: SocketOp, result: Result<SocketValue, &SocketError>) {
+        let mut buf = vec![op as u8];
+        match result {
+            Ok(value) => {
+                buf.push(0);
+                match value {
+                    SocketValue::Unit => {}
+                    SocketValue::Connected(connected) => buf.push(connected.into()),
+                    SocketValue::NextAddress(None) => buf.push(0),
+                    SocketValue::NextAddress(Some(ip)) => {
+                        buf.push(1);
+                        put_ip_address(&mut buf, ip);
+                    }
+                    SocketValue::Address(addr) => put_socket_address(&mut buf, addr),
+                }
+            }
+            Err(err) => match err.downcast_ref() {
+                Some(code) => {
+                    let index = SOCKET_ERROR_CODES.iter().position(|c| c == code).unwrap();
+                    buf.push(1);
+                    buf.push(index as u8);
+                }
+                // Traps end the guest, so there's nothing to replay
+                // afterwards.
+                None => return,
+            },
+        }
+        self.record(Channel::Socket, &buf);
+    }
+}
+
+impl Replayer {
+    /// Returns the recorded result of the next socket operation, which must
+    /// be `op`, with `extract` picking the expected value out of it.
+    pub(crate) fn replay_socket<T>(
+        &self,
+        op: SocketOp,
+        extract: impl FnOnce(SocketValue) -> Option<T>,
+    ) -> SocketResult<T> {
+        let event = self.next(Channel::Socket).map_err(SocketError::trap)?;
+        let diverged = |recorded: SocketOp| {
+            SocketError::trap(anyhow!(
+                "guest diverged from the recording: called {op:?} where {recorded:?} was recorded"
+            ))
+        };
+        match event {
+            Event::Socket(recorded, _) if recorded != op => Err(diverged(recorded)),
+            Event::Socket(_, Ok(value)) => extract(value).ok_or_else(|| diverged(op)),
+            Event::Socket(_, Err(code)) => Err(code.into()),
+            _ => unreachable!("socket channel only contains socket events"),
+        }
+    }
+}
+
+/// A response to an outgoing HTTP request, as recorded in a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// The status code of the response.
+    pub status: u16,
+    /// The names and values of the response's headers, in order.
+    pub headers: Vec<(String, Vec<u8>)>,
+    /// The complete body of the response.
+    pub body: Vec<u8>,
+}
+
+/// The ways in which an outgoing HTTP request can fail, which mirror the
+/// `error` variant of `wasi:http/types`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpError {
+    InvalidUrl(String),
+    Timeout(String),
+    Protocol(String),
+    Unexpected(String),
+}
+
+/// An outgoing HTTP request along with its outcome.
+struct HttpEvent {
+    method: String,
+    uri: String,
+    result: Result<HttpResponse, HttpError>,
+}
+
+fn decode_http_event(reader: &mut Reader<'_>) -> Result<HttpEvent> {
+    let method = reader.string()?;
+    let uri = reader.string()?;
+    let result = match reader.u8()? {
+        0 => {
+            let status = reader.u16()?;
+            let len = reader.u64()?;
+            let mut headers = Vec::new();
+            for _ in 0..len {
+                headers.push((reader.string()?, reader.bytes()?.to_vec()));
+            }
+            let body = reader.bytes()?.to_vec();
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        }
+        _ => {
+            let tag = reader.u8()?;
+            let message = reader.string()?;
+            Err(match tag {
+                0 => HttpError::InvalidUrl(message),
+                1 => HttpError::Timeout(message),
+                2 => HttpError::Protocol(message),
+                3 => HttpError::Unexpected(message),
+                tag => bail!("unknown HTTP error {tag}"),
+            })
+        }
+    };
+    Ok(HttpEvent {
+        method,
+        uri,
+        result,
+    })
+}
+
+impl Recorder {
+    /// Records the outcome of an outgoing HTTP request with the given method
+    /// and URI.
+    pub fn record_http(&self, method: &str, uri: &str, result: &Result<HttpResponse, HttpError>) {
+        let mut buf = Vec::new();
+        put_bytes(&mut buf, method.as_bytes());
+        put_bytes(&mut buf, uri.as_bytes());
+        match result {
+            Ok(response) => {
+                buf.push(0);
+                put_u64(&mut buf, response.status.into());
+                put_u64(&mut buf, response.headers.len() as u64);
+                for (name, value) in response.headers.iter() {
+                    put_bytes(&mut buf, name.as_bytes());
+                    put_bytes(&mut buf, value);
+                }
+                put_bytes(&mut buf, &response.body);
+            }
+            Err(err) => {
+                let (tag, message) = match err {
+                    HttpError::InvalidUrl(message) => (0, message),
+                    HttpError::Timeout(message) => (1, message),
+                    HttpError::Protocol(message) => (2, message),
+                    HttpError::Unexpected(message) => (3, message),
+                };
+                buf.push(1);
+                buf.push(tag);
+                put_bytes(&mut buf, message.as_bytes());
+            }
+        }
+        self.record(Channel::Http, &buf);
+    }
+}
+
+impl Replayer {
+    /// Returns the recorded outcome of an outgoing `method` request to `uri`.
+    ///
+    /// Requests are recorded once their response has arrived, so requests
+    /// which were in flight at the same time may have been recorded in a
+    /// different order than they were sent. The outcome returned is that of
+    /// the earliest recorded request with the same method and URI.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no more such requests were recorded, meaning that the guest
+    /// diverged from the recording.
+    pub fn replay_http(&self, method: &str, uri: &str) -> Result<Result<HttpResponse, HttpError>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(divergence) = &state.divergence {
+            bail!("{divergence}");
+        }
+        let events = &mut state.channels[Channel::Http as usize];
+        let index = events.iter().position(|event| match event {
+            Event::Http(event) => event.method == method && event.uri == uri,
+            _ => unreachable!("HTTP channel only contains HTTP events"),
+        });
+        match index.and_then(|index| events.remove(index)) {
+            Some(Event::Http(event)) => Ok(event.result),
+            _ => bail!(
+                "guest diverged from the recording: sent a {method} request to `{uri}` which \
+                 wasn't recorded"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::preview2::vfs::MemoryFs;
+
+    /// A writer which can be inspected after it's been handed to a
+    /// [`Recorder`].
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn clocks_and_random() -> Result<()> {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone())?;
+        let wall = RecordingWallClock::new(
+            Box::new(crate::preview2::VirtualWallClock::new(
+                Duration::from_secs(100),
+                Duration::from_millis(1),
+            )),
+            recorder.clone(),
+        );
+        let mut rng = RecordingRng::secure(crate::preview2::thread_rng(), recorder);
+        let recorded_now = (wall.now(), wall.now());
+        let recorded_u64 = rng.next_u64();
+        let mut recorded_bytes = [0; 10];
+        rng.fill_bytes(&mut recorded_bytes);
+
+        let replayer = Replayer::new(&buf.0.lock().unwrap())?;
+        let wall = ReplayWallClock::new(replayer.clone());
+        let mut rng = ReplayRng::secure(replayer.clone());
+        assert_eq!((wall.now(), wall.now()), recorded_now);
+        assert_eq!(rng.next_u64(), recorded_u64);
+        let mut bytes = [0; 10];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes, recorded_bytes);
+        assert!(replayer.next(Channel::WallClockNow).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn filesystem() -> Result<()> {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone())?;
+        let memory = MemoryFs::new();
+        memory.insert_file("dir/a.txt", "hello")?;
+        let fs = RecordingFs::new(Arc::new(memory), recorder);
+        let stat = fs.stat("dir/a.txt")?;
+        let entries = fs.read_dir("dir")?;
+        let data = fs.read("dir/a.txt", 1, 3)?;
+        fs.create_file("dir/b.txt", true)?;
+        fs.write("dir/b.txt", 0, b"!")?;
+        let err = fs.remove_dir("missing").unwrap_err();
+
+        let fs = ReplayFs::new(Replayer::new(&buf.0.lock().unwrap())?);
+        assert_eq!(fs.stat("dir/a.txt")?.size, stat.size);
+        let replayed = fs.read_dir("dir")?;
+        assert_eq!(replayed.len(), entries.len());
+        assert_eq!(replayed[0].name, entries[0].name);
+        assert_eq!(fs.read("dir/a.txt", 1, 3)?, data);
+        fs.create_file("dir/b.txt", true)?;
+        fs.write("dir/b.txt", 0, b"!")?;
+        assert_eq!(
+            fs.remove_dir("missing").unwrap_err().downcast_ref(),
+            err.downcast_ref()
+        );
+        // The next operation doesn't match anything in the trace.
+        assert!(fs.stat("dir").unwrap_err().downcast_ref().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn sockets() -> Result<()> {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone())?;
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let addr = SocketAddr::from(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 80, 1, 2));
+        let would_block = SocketError::from(SocketErrorCode::WouldBlock);
+        let next = SocketValue::NextAddress(Some(ip));
+        recorder.record_socket(SocketOp::ResolveNextAddress, Ok(next));
+        recorder.record_socket(SocketOp::StartConnect, Ok(SocketValue::Connected(false)));
+        recorder.record_socket(SocketOp::FinishConnect, Err(&would_block));
+        recorder.record_socket(SocketOp::FinishConnect, Ok(SocketValue::Unit));
+        recorder.record_socket(SocketOp::RemoteAddress, Ok(SocketValue::Address(addr)));
+        // Traps end the guest and aren't recorded.
+        let trap = SocketError::trap(anyhow!("trap"));
+        recorder.record_socket(SocketOp::Shutdown, Err(&trap));
+        let pipe = crate::preview2::pipe::MemoryInputPipe::new("hello".into());
+        let mut input = RecordingInputStream::socket(Box::new(pipe), recorder);
+        assert_eq!(input.read(3)?, "hel");
+        assert_eq!(input.read(10)?, "lo");
+        assert!(matches!(input.read(10), Err(StreamError::Closed)));
+
+        let replayer = Replayer::new(&buf.0.lock().unwrap())?;
+        let next = replayer.replay_socket(SocketOp::ResolveNextAddress, |value| match value {
+            SocketValue::NextAddress(ip) => Some(ip),
+            _ => None,
+        })?;
+        assert_eq!(next, Some(ip));
+        let connected = replayer.replay_socket(SocketOp::StartConnect, |value| match value {
+            SocketValue::Connected(connected) => Some(connected),
+            _ => None,
+        })?;
+        assert!(!connected);
+        let unit = |value: SocketValue| match value {
+            SocketValue::Unit => Some(()),
+            _ => None,
+        };
+        let err = replayer
+            .replay_socket(SocketOp::FinishConnect, unit)
+            .unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&SocketErrorCode::WouldBlock));
+        replayer.replay_socket(SocketOp::FinishConnect, unit)?;
+        // Asking for a different operation than was recorded traps.
+        let err = replayer
+            .replay_socket(SocketOp::LocalAddress, |_| Some(()))
+            .unwrap_err();
+        assert!(err.downcast_ref().is_none(), "{err}");
+
+        let mut input = ReplayInputStream::socket(replayer.clone());
+        assert_eq!(input.read(3)?, "hel");
+        assert_eq!(input.read(10)?, "lo");
+        assert!(matches!(input.read(10), Err(StreamError::Closed)));
+        assert!(matches!(input.read(10), Err(StreamError::Trap(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn http() -> Result<()> {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone())?;
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![("content-type".to_string(), b"text/plain".to_vec())],
+            body: b"hello".to_vec(),
+        };
+        let uri = "http://example.com/";
+        recorder.record_http("GET", uri, &Ok(response.clone()));
+        let timeout = HttpError::Timeout("connect timed out".to_string());
+        recorder.record_http("POST", uri, &Err(timeout.clone()));
+
+        let replayer = Replayer::new(&buf.0.lock().unwrap())?;
+        assert_eq!(replayer.replay_http("GET", uri)?, Ok(response.clone()));
+        let err = replayer.replay_http("GET", uri).unwrap_err();
+        assert!(err.to_string().contains("wasn't recorded"), "{err}");
+        assert!(replayer.replay_http("GET", "http://example.org/").is_err());
+        assert_eq!(replayer.replay_http("POST", uri)?, Err(timeout.clone()));
+
+        // Requests can be replayed in a different order than their
+        // responses were recorded in.
+        let replayer = Replayer::new(&buf.0.lock().unwrap())?;
+        assert_eq!(replayer.replay_http("POST", uri)?, Err(timeout));
+        assert_eq!(replayer.replay_http("GET", uri)?, Ok(response));
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_traces() {
+        assert!(Replayer::new(b"not a trace").is_err());
+        let mut trace = MAGIC.to_vec();
+        trace.extend_from_slice(&[Channel::Stdin as u8, 5, 1]);
+        assert!(Replayer::new(&trace).is_err());
+
+        // Payloads are validated up front rather than when they're replayed.
+        for event in [
+            &[Channel::WallClockNow as u8, 1, 0x80][..],
+            &[Channel::MonotonicClockNow as u8, 2, 1, 2],
+            &[Channel::InsecureRandomSeed as u8, 1, 0],
+            &[Channel::Stdin as u8, 1, 9],
+            &[Channel::Stdin as u8, 1, STREAM_ISATTY],
+            &[Channel::Filesystem as u8, 2, 42, 0],
+            &[Channel::Filesystem as u8, 3, FsOp::Stat as u8, 1, 200],
+            &[Channel::Filesystem as u8, 3, FsOp::Read as u8, 0, 4],
+            &[Channel::SocketData as u8, 2, STREAM_ISATTY, 1],
+            &[Channel::Socket as u8, 3, SocketOp::LocalAddress as u8, 0, 5],
+            &[Channel::Socket as u8, 3, SocketOp::Shutdown as u8, 1, 21],
+            &[Channel::Http as u8, 5, 0, 0, 1, 9, 0],
+        ] {
+            let mut trace = MAGIC.to_vec();
+            trace.extend_from_slice(event);
+            assert!(Replayer::new(&trace).is_err(), "accepted {event:?}");
+        }
+    }
+
+    #[test]
+    fn divergence() -> Result<()> {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone())?;
+        let mut rng = RecordingRng::secure(crate::preview2::thread_rng(), recorder.clone());
+        rng.next_u32();
+        let fs = RecordingFs::new(Arc::new(MemoryFs::new()), recorder);
+        fs.create_dir("dir")?;
+
+        // Asking for a different amount of randomness than was recorded can't
+        // be reported by the RNG, so zeros are returned and the next call
+        // which can fail traps.
+        let replayer = Replayer::new(&buf.0.lock().unwrap())?;
+        let mut rng = ReplayRng::secure(replayer.clone());
+        assert_eq!(rng.next_u64(), 0);
+        assert!(replayer.check().is_err());
+        let fs = ReplayFs::new(replayer.clone());
+        assert!(fs.create_dir("dir").unwrap_err().downcast_ref().is_none());
+
+        // The same goes for clocks reading past the end of the trace.
+        let replayer = Replayer::new(&buf.0.lock().unwrap())?;
+        let clock = ReplayMonotonicClock::new(replayer.clone());
+        assert_eq!(clock.now(), 0);
+        let err = replayer.check().unwrap_err();
+        assert!(err.to_string().contains("MonotonicClockNow"), "{err}");
+        Ok(())
+    }
+}
@@ -196,7 +196,9 @@ impl<T: WasiView> terminal_output::HostTerminalOutput for T {
 }
 impl<T: WasiView> terminal_stdin::Host for T {
     fn get_terminal_stdin(&mut self) -> anyhow::Result<Option<Resource<TerminalInput>>> {
-        if self.ctx().stdin.isatty() {
+        let isatty = self.ctx().stdin.isatty();
+        self.ctx().check_replay()?;
+        if isatty {
             let fd = self.table_mut().push(TerminalInput)?;
             Ok(Some(fd))
         } else {
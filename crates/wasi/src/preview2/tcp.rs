@@ -63,6 +63,10 @@ pub struct TcpSocket {
 
     pub(crate) family: SocketAddressFamily,
 
+    /// Whether the connection of this socket is replayed from a trace, in
+    /// which case the host socket is never connected.
+    pub(crate) replayed: bool,
+
     /// The manually configured buffer size. `None` means: no preference, use system default.
     #[cfg(target_os = "macos")]
     pub(crate) receive_buffer_size: Option<usize>,
@@ -300,6 +304,7 @@ impl TcpSocket {
             tcp_state: TcpState::Default,
             listen_backlog_size: None,
             family,
+            replayed: false,
             #[cfg(target_os = "macos")]
             receive_buffer_size: None,
             #[cfg(target_os = "macos")]
@@ -324,6 +329,11 @@ impl TcpSocket {
 #[async_trait::async_trait]
 impl Subscribe for TcpSocket {
     async fn ready(&mut self) {
+        // Replayed connections don't wait on the network.
+        if self.replayed {
+            return;
+        }
+
         // Some states are ready immediately.
         match self.tcp_state {
             TcpState::BindStarted | TcpState::ListenStarted | TcpState::ConnectReady => return,
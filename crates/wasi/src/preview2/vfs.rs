@@ -0,0 +1,1759 @@
+//! Virtual filesystems which can be exposed to guests through
+//! `wasi:filesystem` without touching the host's filesystem.
+//!
+//! A [`VirtualFs`] is made available to a guest with
+//! [`WasiCtxBuilder::preopened_virtual_dir`]. This module provides an
+//! in-memory implementation, [`MemoryFs`], which can also be loaded from a tar
+//! image, along with [`OverlayFs`] and [`ReadOnlyFs`] to compose filesystems
+//! out of layers. For example each tenant can be given a private writable
+//! [`MemoryFs`] of limited size on top of a shared read-only image:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use wasmtime_wasi::preview2::vfs::{MemoryFs, OverlayFs, ReadOnlyFs};
+//! use wasmtime_wasi::preview2::{DirPerms, FilePerms, WasiCtxBuilder};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let image = MemoryFs::new();
+//! image.insert_file("etc/motd", "hello")?;
+//! let image = Arc::new(ReadOnlyFs::new(Arc::new(image)));
+//!
+//! let scratch = MemoryFs::with_limit(16 << 20);
+//! let tenant = OverlayFs::new(Arc::new(scratch), image.clone());
+//! let ctx = WasiCtxBuilder::new()
+//!     .preopened_virtual_dir(Arc::new(tenant), DirPerms::all(), FilePerms::all(), "/")
+//!     .build();
+//! # drop(ctx);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`WasiCtxBuilder::preopened_virtual_dir`]: crate::preview2::WasiCtxBuilder::preopened_virtual_dir
+
+use crate::preview2::bindings::filesystem::types::{self, DescriptorType, ErrorCode};
+use crate::preview2::filesystem::{FileOutputMode, FILE_WRITE_CAPACITY};
+use crate::preview2::host::filesystem::{descriptorstat_from, descriptortype_from};
+use crate::preview2::{
+    DirPerms, FilePerms, FsError, FsResult, HostInputStream, HostOutputStream, StreamError,
+    StreamResult, Subscribe,
+};
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+
+/// A filesystem which can be exposed to guests through `wasi:filesystem`.
+///
+/// Paths passed to these methods are relative to the root of the filesystem
+/// and use `/` as a separator. They have already been normalized so they
+/// never contain `.` or `..` components, and the empty string refers to the
+/// root directory itself. Symbolic links and timestamps are not supported.
+///
+/// Methods are invoked directly from the guest's calls into the host, so
+/// implementations should not block for long periods of time.
+pub trait VirtualFs: Send + Sync + 'static {
+    /// Returns metadata about the file or directory at `path`.
+    fn stat(&self, path: &str) -> FsResult<types::DescriptorStat>;
+
+    /// Returns the entries of the directory at `path`.
+    fn read_dir(&self, path: &str) -> FsResult<Vec<types::DirectoryEntry>>;
+
+    /// Reads up to `len` bytes at `offset` from the file at `path`, returning
+    /// an empty buffer at the end of the file.
+    fn read(&self, path: &str, offset: u64, len: usize) -> FsResult<Vec<u8>>;
+
+    /// Writes all of `buf` at `offset` into the file at `path`, extending the
+    /// file with zeros if `offset` is past its end.
+    fn write(&self, path: &str, offset: u64, buf: &[u8]) -> FsResult<()>;
+
+    /// Truncates or extends the file at `path` to `size` bytes.
+    fn set_size(&self, path: &str, size: u64) -> FsResult<()>;
+
+    /// Creates an empty file at `path`.
+    ///
+    /// If a file already exists at `path` then this fails with
+    /// [`ErrorCode::Exist`] if `exclusive` is set and otherwise succeeds
+    /// without modifying it.
+    fn create_file(&self, path: &str, exclusive: bool) -> FsResult<()>;
+
+    /// Creates an empty directory at `path`.
+    fn create_dir(&self, path: &str) -> FsResult<()>;
+
+    /// Removes the file at `path`.
+    fn remove_file(&self, path: &str) -> FsResult<()>;
+
+    /// Removes the empty directory at `path`.
+    fn remove_dir(&self, path: &str) -> FsResult<()>;
+
+    /// Moves the file or directory at `from` to `to`, replacing any file or
+    /// empty directory already there.
+    fn rename(&self, from: &str, to: &str) -> FsResult<()>;
+}
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|name| !name.is_empty())
+}
+
+/// Splits `path` into the path of its parent directory and its final
+/// component, returning `None` for the root.
+fn split(path: &str) -> Option<(&str, &str)> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(match path.rfind('/') {
+        Some(i) => (&path[..i], &path[i + 1..]),
+        None => ("", path),
+    })
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// Returns the paths of all the directories containing `path`, excluding the
+/// root, from the outermost inwards.
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    path.match_indices('/').map(move |(i, _)| &path[..i])
+}
+
+fn is_error(err: &FsError, code: ErrorCode) -> bool {
+    err.downcast_ref() == Some(&code)
+}
+
+fn stat(type_: DescriptorType, size: u64) -> types::DescriptorStat {
+    types::DescriptorStat {
+        type_,
+        link_count: 1,
+        size,
+        data_access_timestamp: None,
+        data_modification_timestamp: None,
+        status_change_timestamp: None,
+    }
+}
+
+enum Node {
+    File(Vec<u8>),
+    Dir(BTreeMap<String, Node>),
+}
+
+impl Node {
+    fn type_(&self) -> DescriptorType {
+        match self {
+            Node::File(_) => DescriptorType::RegularFile,
+            Node::Dir(_) => DescriptorType::Directory,
+        }
+    }
+
+    fn lookup(&self, path: &str) -> FsResult<&Node> {
+        let mut node = self;
+        for name in components(path) {
+            node = match node {
+                Node::Dir(entries) => entries.get(name).ok_or(ErrorCode::NoEntry)?,
+                Node::File(_) => return Err(ErrorCode::NotDirectory.into()),
+            };
+        }
+        Ok(node)
+    }
+
+    fn lookup_mut(&mut self, path: &str) -> FsResult<&mut Node> {
+        let mut node = self;
+        for name in components(path) {
+            node = match node {
+                Node::Dir(entries) => entries.get_mut(name).ok_or(ErrorCode::NoEntry)?,
+                Node::File(_) => return Err(ErrorCode::NotDirectory.into()),
+            };
+        }
+        Ok(node)
+    }
+
+    fn file_mut(&mut self, path: &str) -> FsResult<&mut Vec<u8>> {
+        match self.lookup_mut(path)? {
+            Node::File(data) => Ok(data),
+            Node::Dir(_) => Err(ErrorCode::IsDirectory.into()),
+        }
+    }
+
+    /// Returns the entries of the directory containing `path` along with the
+    /// name of `path` within it, or fails with `root` if `path` is the root.
+    fn parent_mut<'a, 'b>(
+        &'a mut self,
+        path: &'b str,
+        root: ErrorCode,
+    ) -> FsResult<(&'a mut BTreeMap<String, Node>, &'b str)> {
+        let (parent, name) = split(path).ok_or(root)?;
+        match self.lookup_mut(parent)? {
+            Node::Dir(entries) => Ok((entries, name)),
+            Node::File(_) => Err(ErrorCode::NotDirectory.into()),
+        }
+    }
+}
+
+/// A [`VirtualFs`] which keeps all of its files in memory.
+///
+/// Files can be added by the embedder with [`MemoryFs::insert_file`], or
+/// loaded from an archive with [`MemoryFs::load_tar`].
+///
+/// The total size of the files is limited, see [`MemoryFs::with_limit`], so
+/// that guests can't exhaust the host's memory.
+pub struct MemoryFs {
+    tree: Mutex<Tree>,
+    limit: u64,
+}
+
+struct Tree {
+    root: Node,
+    /// The total size of all the files in `root`.
+    used: u64,
+}
+
+impl MemoryFs {
+    /// The limit on the total size of files used by [`MemoryFs::new`], which
+    /// is 1 GiB.
+    pub const DEFAULT_LIMIT: u64 = 1 << 30;
+
+    /// Creates a new filesystem with an empty root directory, which can hold
+    /// up to [`MemoryFs::DEFAULT_LIMIT`] bytes of file contents.
+    pub fn new() -> Self {
+        MemoryFs::with_limit(MemoryFs::DEFAULT_LIMIT)
+    }
+
+    /// Creates a new filesystem with an empty root directory, which can hold
+    /// up to `limit` bytes of file contents.
+    ///
+    /// Writes which would make a single file larger than `limit` fail with
+    /// [`ErrorCode::FileTooLarge`], and writes which would make all the files
+    /// together larger than `limit` fail with [`ErrorCode::InsufficientSpace`].
+    pub fn with_limit(limit: u64) -> Self {
+        MemoryFs {
+            tree: Mutex::new(Tree {
+                root: Node::Dir(BTreeMap::new()),
+                used: 0,
+            }),
+            limit,
+        }
+    }
+
+    /// Returns the total size of the files within this filesystem.
+    pub fn used(&self) -> u64 {
+        self.tree.lock().unwrap().used
+    }
+
+    /// Returns the total size of the files once the file `data` is resized
+    /// to `len` bytes, failing if that's over the limit.
+    fn used_after_resize(&self, used: u64, data: &[u8], len: u64) -> FsResult<u64> {
+        if len > self.limit {
+            return Err(ErrorCode::FileTooLarge.into());
+        }
+        let used = used - data.len() as u64 + len;
+        if used > self.limit {
+            return Err(ErrorCode::InsufficientSpace.into());
+        }
+        Ok(used)
+    }
+
+    /// Resizes the file `data` to `len` bytes, filling it with zeros.
+    fn resize(&self, used: &mut u64, data: &mut Vec<u8>, len: u64) -> FsResult<()> {
+        let new_used = self.used_after_resize(*used, data, len)?;
+        let len = usize::try_from(len).map_err(|_| ErrorCode::FileTooLarge)?;
+        if let Some(additional) = len.checked_sub(data.len()) {
+            data.try_reserve_exact(additional)
+                .map_err(|_| ErrorCode::InsufficientSpace)?;
+        }
+        data.resize(len, 0);
+        *used = new_used;
+        Ok(())
+    }
+
+    /// Creates the directory at `path` along with any missing parent
+    /// directories.
+    pub fn create_dir_all(&self, path: &str) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let mut node = &mut tree.root;
+        for name in components(path) {
+            node = match node {
+                Node::Dir(entries) => entries
+                    .entry(name.to_string())
+                    .or_insert_with(|| Node::Dir(BTreeMap::new())),
+                Node::File(_) => return Err(ErrorCode::NotDirectory.into()),
+            };
+        }
+        match node {
+            Node::Dir(_) => Ok(()),
+            Node::File(_) => Err(ErrorCode::Exist.into()),
+        }
+    }
+
+    /// Creates a file at `path` holding `contents`, along with any missing
+    /// parent directories. An existing file at `path` is replaced.
+    pub fn insert_file(&self, path: &str, contents: impl Into<Vec<u8>>) -> FsResult<()> {
+        let contents = contents.into();
+        let path = components(path).collect::<Vec<_>>().join("/");
+        let (parent, _) = split(&path).ok_or(ErrorCode::IsDirectory)?;
+        self.create_dir_all(parent)?;
+        let mut tree = self.tree.lock().unwrap();
+        let tree = &mut *tree;
+        let (entries, name) = tree.root.parent_mut(&path, ErrorCode::IsDirectory)?;
+        let old = match entries.get(name) {
+            Some(Node::Dir(_)) => return Err(ErrorCode::IsDirectory.into()),
+            Some(Node::File(old)) => &old[..],
+            None => &[],
+        };
+        tree.used = self.used_after_resize(tree.used, old, contents.len() as u64)?;
+        entries.insert(name.to_string(), Node::File(contents));
+        Ok(())
+    }
+
+    /// Adds the files and directories of the tar archive read from `archive`
+    /// to this filesystem, replacing existing files at the same paths.
+    ///
+    /// Archives in the ustar, GNU and pax formats are supported, but only
+    /// regular files and directories can be loaded since virtual filesystems
+    /// have no symbolic links or other special files. Metadata such as
+    /// permissions and timestamps is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is malformed, contains an unsupported
+    /// entry or a path which escapes the root, or doesn't fit within this
+    /// filesystem's size limit. Entries preceding the failure have already
+    /// been added.
+    pub fn load_tar(&self, mut archive: impl Read) -> anyhow::Result<()> {
+        let mut long_name = None;
+        let mut pax_path = None;
+        let mut pax_size = None;
+        let mut header = [0; 512];
+        loop {
+            if !tar::read_block(&mut archive, &mut header)? || header.iter().all(|b| *b == 0) {
+                return Ok(());
+            }
+            tar::check_checksum(&header)?;
+            let typeflag = header[156];
+            let mut size = tar::parse_number(&header[124..136])?;
+
+            // Entries holding metadata about the entry which follows them.
+            if let b'L' | b'x' | b'g' = typeflag {
+                if size > tar::MAX_METADATA {
+                    bail!("metadata entry in tar image is too large");
+                }
+                let data = tar::read_data(&mut archive, size)?;
+                tar::skip(&mut archive, tar::padding(size))?;
+                match typeflag {
+                    b'L' => long_name = Some(tar::string(&data)?),
+                    b'x' => {
+                        for (key, value) in tar::pax_records(&data)? {
+                            match key {
+                                "path" => pax_path = Some(value.to_string()),
+                                "size" => pax_size = Some(value.parse()?),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            if let Some(pax_size) = pax_size.take() {
+                size = pax_size;
+            }
+            let name = match long_name.take().or(pax_path.take()) {
+                Some(name) => name,
+                None => tar::header_name(&header),
+            };
+            let path = tar::normalize(&name)?;
+            let context = || format!("failed to load `{name}` from tar image");
+
+            match typeflag {
+                // Old archives mark directories with a trailing slash.
+                b'\0' if name.ends_with('/') => {
+                    self.create_dir_all(&path).with_context(context)?;
+                    tar::skip(&mut archive, size)?;
+                }
+                b'0' | b'\0' | b'7' => {
+                    if size > self.limit {
+                        let err = anyhow::Error::from(FsError::from(ErrorCode::FileTooLarge));
+                        return Err(err.context(context()));
+                    }
+                    let contents = tar::read_data(&mut archive, size)?;
+                    self.insert_file(&path, contents).with_context(context)?;
+                }
+                b'5' => {
+                    self.create_dir_all(&path).with_context(context)?;
+                    tar::skip(&mut archive, size)?;
+                }
+                other => bail!(
+                    "unsupported entry type `{}` for `{name}` in tar image",
+                    other.escape_ascii()
+                ),
+            }
+            tar::skip(&mut archive, tar::padding(size))?;
+        }
+    }
+}
+
+/// Helpers to read tar archives for [`MemoryFs::load_tar`].
+mod tar {
+    use anyhow::{bail, Result};
+    use std::io::{self, Read};
+
+    /// The largest GNU long name or pax extended header which is read, as
+    /// these are buffered in memory.
+    pub const MAX_METADATA: u64 = 1 << 20;
+
+    /// Fills `block` with the next 512 bytes of `archive`, returning `false`
+    /// if the archive ended before any of them.
+    pub fn read_block(archive: &mut impl Read, block: &mut [u8; 512]) -> Result<bool> {
+        let mut read = 0;
+        while read < block.len() {
+            match archive.read(&mut block[read..]) {
+                Ok(0) if read == 0 => return Ok(false),
+                Ok(0) => bail!("unexpected end of tar image"),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn read_data(archive: &mut impl Read, size: u64) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        archive.take(size).read_to_end(&mut data)?;
+        if data.len() as u64 != size {
+            bail!("unexpected end of tar image");
+        }
+        Ok(data)
+    }
+
+    pub fn skip(archive: &mut impl Read, size: u64) -> Result<()> {
+        if io::copy(&mut archive.take(size), &mut io::sink())? != size {
+            bail!("unexpected end of tar image");
+        }
+        Ok(())
+    }
+
+    /// Returns the number of bytes following an entry of `size` bytes to
+    /// align the next header.
+    pub fn padding(size: u64) -> u64 {
+        (512 - size % 512) % 512
+    }
+
+    /// Parses a numeric header field, which is either octal text or, for
+    /// large values in GNU archives, big-endian binary.
+    pub fn parse_number(field: &[u8]) -> Result<u64> {
+        if field[0] & 0x80 != 0 {
+            let mut value = u64::from(field[0] & 0x7f);
+            for byte in &field[1..] {
+                if value >> 56 != 0 {
+                    bail!("number in tar header is too large");
+                }
+                value = (value << 8) | u64::from(*byte);
+            }
+            return Ok(value);
+        }
+        let text = std::str::from_utf8(field)?.trim_matches(|c| c == ' ' || c == '\0');
+        if text.is_empty() {
+            return Ok(0);
+        }
+        match u64::from_str_radix(text, 8) {
+            Ok(value) => Ok(value),
+            Err(_) => bail!("invalid number `{text}` in tar header"),
+        }
+    }
+
+    pub fn check_checksum(header: &[u8; 512]) -> Result<()> {
+        let expected = parse_number(&header[148..156])?;
+        let actual = header
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(*b)
+                }
+            })
+            .sum::<u64>();
+        if expected != actual {
+            bail!("invalid tar header checksum");
+        }
+        Ok(())
+    }
+
+    /// Returns the contents of `field` up to its first NUL byte.
+    fn field(field: &[u8]) -> &[u8] {
+        let len = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+        &field[..len]
+    }
+
+    pub fn string(data: &[u8]) -> Result<String> {
+        Ok(std::str::from_utf8(field(data))?.to_string())
+    }
+
+    /// Returns the name of the entry in `header`, including the ustar
+    /// prefix if there is one.
+    pub fn header_name(header: &[u8; 512]) -> String {
+        let name = String::from_utf8_lossy(field(&header[..100]));
+        let prefix = field(&header[345..500]);
+        if &header[257..262] == b"ustar" && header[262] == 0 && !prefix.is_empty() {
+            format!("{}/{name}", String::from_utf8_lossy(prefix))
+        } else {
+            name.into_owned()
+        }
+    }
+
+    /// Converts the name of an entry into a path within the filesystem,
+    /// refusing names which would escape its root.
+    pub fn normalize(name: &str) -> Result<String> {
+        let mut path = Vec::new();
+        for component in name.split('/') {
+            match component {
+                "" | "." => {}
+                ".." => bail!("tar entry `{name}` is outside of the image's root"),
+                component => path.push(component),
+            }
+        }
+        Ok(path.join("/"))
+    }
+
+    /// Parses the `<len> <key>=<value>\n` records of a pax extended header.
+    pub fn pax_records(data: &[u8]) -> Result<Vec<(&str, &str)>> {
+        let mut records = Vec::new();
+        let mut rest = std::str::from_utf8(data)?;
+        while !rest.is_empty() {
+            match pax_record(rest) {
+                Some((key, value, len)) => {
+                    records.push((key, value));
+                    rest = &rest[len..];
+                }
+                None => bail!("invalid pax extended header"),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Parses the first record of `rest`, returning its key, value and
+    /// length.
+    fn pax_record(rest: &str) -> Option<(&str, &str, usize)> {
+        let (len, _) = rest.split_once(' ')?;
+        let len = len.parse::<usize>().ok()?;
+        let record = rest.get(..len)?.strip_suffix('\n')?;
+        let (key, value) = record.split_once(' ')?.1.split_once('=')?;
+        Some((key, value, len))
+    }
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        MemoryFs::new()
+    }
+}
+
+impl VirtualFs for MemoryFs {
+    fn stat(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        let tree = self.tree.lock().unwrap();
+        Ok(match tree.root.lookup(path)? {
+            Node::File(data) => stat(DescriptorType::RegularFile, data.len() as u64),
+            Node::Dir(_) => stat(DescriptorType::Directory, 0),
+        })
+    }
+
+    fn read_dir(&self, path: &str) -> FsResult<Vec<types::DirectoryEntry>> {
+        let tree = self.tree.lock().unwrap();
+        match tree.root.lookup(path)? {
+            Node::Dir(entries) => Ok(entries
+                .iter()
+                .map(|(name, node)| types::DirectoryEntry {
+                    type_: node.type_(),
+                    name: name.clone(),
+                })
+                .collect()),
+            Node::File(_) => Err(ErrorCode::NotDirectory.into()),
+        }
+    }
+
+    fn read(&self, path: &str, offset: u64, len: usize) -> FsResult<Vec<u8>> {
+        let tree = self.tree.lock().unwrap();
+        match tree.root.lookup(path)? {
+            Node::File(data) => {
+                let start = usize::try_from(offset)
+                    .unwrap_or(usize::MAX)
+                    .min(data.len());
+                let end = start.saturating_add(len).min(data.len());
+                Ok(data[start..end].to_vec())
+            }
+            Node::Dir(_) => Err(ErrorCode::IsDirectory.into()),
+        }
+    }
+
+    fn write(&self, path: &str, offset: u64, buf: &[u8]) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let tree = &mut *tree;
+        let data = tree.root.file_mut(path)?;
+        let end = offset
+            .checked_add(buf.len() as u64)
+            .ok_or(ErrorCode::FileTooLarge)?;
+        if (data.len() as u64) < end {
+            self.resize(&mut tree.used, data, end)?;
+        }
+        // The resize above means that these fit in a `usize`.
+        let start = offset as usize;
+        data[start..start + buf.len()].copy_from_slice(buf);
+        Ok(())
+    }
+
+    fn set_size(&self, path: &str, size: u64) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let tree = &mut *tree;
+        let data = tree.root.file_mut(path)?;
+        self.resize(&mut tree.used, data, size)
+    }
+
+    fn create_file(&self, path: &str, exclusive: bool) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let (entries, name) = tree.root.parent_mut(path, ErrorCode::IsDirectory)?;
+        match entries.get(name) {
+            None => {
+                entries.insert(name.to_string(), Node::File(Vec::new()));
+                Ok(())
+            }
+            Some(_) if exclusive => Err(ErrorCode::Exist.into()),
+            Some(Node::File(_)) => Ok(()),
+            Some(Node::Dir(_)) => Err(ErrorCode::IsDirectory.into()),
+        }
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let (entries, name) = tree.root.parent_mut(path, ErrorCode::Exist)?;
+        if entries.contains_key(name) {
+            return Err(ErrorCode::Exist.into());
+        }
+        entries.insert(name.to_string(), Node::Dir(BTreeMap::new()));
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let tree = &mut *tree;
+        let (entries, name) = tree.root.parent_mut(path, ErrorCode::IsDirectory)?;
+        match entries.get(name) {
+            Some(Node::File(data)) => {
+                tree.used -= data.len() as u64;
+                entries.remove(name);
+                Ok(())
+            }
+            Some(Node::Dir(_)) => Err(ErrorCode::IsDirectory.into()),
+            None => Err(ErrorCode::NoEntry.into()),
+        }
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let (entries, name) = tree.root.parent_mut(path, ErrorCode::Busy)?;
+        match entries.get(name) {
+            Some(Node::Dir(dir)) if dir.is_empty() => {
+                entries.remove(name);
+                Ok(())
+            }
+            Some(Node::Dir(_)) => Err(ErrorCode::NotEmpty.into()),
+            Some(Node::File(_)) => Err(ErrorCode::NotDirectory.into()),
+            None => Err(ErrorCode::NoEntry.into()),
+        }
+    }
+
+    fn rename(&self, from: &str, to: &str) -> FsResult<()> {
+        let mut tree = self.tree.lock().unwrap();
+        let tree = &mut *tree;
+        let root = &mut tree.root;
+        let is_dir = match root.lookup(from)? {
+            Node::Dir(_) => true,
+            Node::File(_) => false,
+        };
+        if from == to {
+            return Ok(());
+        }
+        // A directory can't be moved inside of itself.
+        if to.starts_with(from) && to[from.len()..].starts_with('/') {
+            return Err(ErrorCode::Invalid.into());
+        }
+        match root.lookup(to) {
+            Ok(Node::Dir(_)) if !is_dir => return Err(ErrorCode::IsDirectory.into()),
+            Ok(Node::Dir(entries)) if !entries.is_empty() => return Err(ErrorCode::NotEmpty.into()),
+            Ok(Node::File(_)) if is_dir => return Err(ErrorCode::NotDirectory.into()),
+            _ => {}
+        }
+        // Check that the destination's parent exists before detaching the
+        // source so that a failure leaves the tree untouched.
+        root.parent_mut(to, ErrorCode::Busy)?;
+        let (entries, name) = root.parent_mut(from, ErrorCode::Busy)?;
+        let node = entries.remove(name).unwrap();
+        let (entries, name) = root.parent_mut(to, ErrorCode::Busy)?;
+        if let Some(Node::File(replaced)) = entries.insert(name.to_string(), node) {
+            tree.used -= replaced.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+/// A [`VirtualFs`] which rejects all modifications of the filesystem it wraps
+/// with [`ErrorCode::ReadOnly`].
+pub struct ReadOnlyFs {
+    inner: Arc<dyn VirtualFs>,
+}
+
+impl ReadOnlyFs {
+    /// Creates a read-only view of `inner`.
+    pub fn new(inner: Arc<dyn VirtualFs>) -> Self {
+        ReadOnlyFs { inner }
+    }
+}
+
+impl VirtualFs for ReadOnlyFs {
+    fn stat(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        self.inner.stat(path)
+    }
+
+    fn read_dir(&self, path: &str) -> FsResult<Vec<types::DirectoryEntry>> {
+        self.inner.read_dir(path)
+    }
+
+    fn read(&self, path: &str, offset: u64, len: usize) -> FsResult<Vec<u8>> {
+        self.inner.read(path, offset, len)
+    }
+
+    fn write(&self, _path: &str, _offset: u64, _buf: &[u8]) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+
+    fn set_size(&self, _path: &str, _size: u64) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+
+    fn create_file(&self, _path: &str, _exclusive: bool) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+
+    fn create_dir(&self, _path: &str) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+
+    fn remove_file(&self, _path: &str) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+
+    fn remove_dir(&self, _path: &str) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+
+    fn rename(&self, _from: &str, _to: &str) -> FsResult<()> {
+        Err(ErrorCode::ReadOnly.into())
+    }
+}
+
+/// A [`VirtualFs`] which layers a writable filesystem on top of another one.
+///
+/// Reads are served from the upper layer if it contains the path and from
+/// the lower layer otherwise. The lower layer is never modified: files are
+/// copied into the upper layer before they're written to, and removed files
+/// and directories are hidden instead of being deleted. This means the lower
+/// layer can be shared between many overlays, typically wrapped in a
+/// [`ReadOnlyFs`].
+///
+/// Directories which exist in the lower layer can't be renamed and fail with
+/// [`ErrorCode::Unsupported`].
+pub struct OverlayFs {
+    upper: Arc<dyn VirtualFs>,
+    lower: Arc<dyn VirtualFs>,
+    state: Mutex<OverlayState>,
+}
+
+#[derive(Default)]
+struct OverlayState {
+    /// Paths which have been removed and are hidden in the lower layer.
+    whiteouts: HashSet<String>,
+    /// Directories which were created in the upper layer after being removed
+    /// and whose contents in the lower layer are hidden.
+    opaque: HashSet<String>,
+}
+
+impl OverlayFs {
+    /// Creates a new overlay of `upper` on top of `lower`.
+    pub fn new(upper: Arc<dyn VirtualFs>, lower: Arc<dyn VirtualFs>) -> Self {
+        OverlayFs {
+            upper,
+            lower,
+            state: Mutex::default(),
+        }
+    }
+
+    fn lower_visible(&self, path: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        !ancestors(path)
+            .chain([path])
+            .any(|p| state.whiteouts.contains(p) || state.opaque.contains(p))
+    }
+
+    fn lower_stat(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        if !self.lower_visible(path) {
+            return Err(ErrorCode::NoEntry.into());
+        }
+        self.lower.stat(path)
+    }
+
+    fn in_upper(&self, path: &str) -> FsResult<bool> {
+        match self.upper.stat(path) {
+            Ok(_) => Ok(true),
+            Err(e) if is_error(&e, ErrorCode::NoEntry) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn in_lower(&self, path: &str) -> FsResult<bool> {
+        match self.lower_stat(path) {
+            Ok(_) => Ok(true),
+            Err(e) if is_error(&e, ErrorCode::NoEntry) || is_error(&e, ErrorCode::NotDirectory) => {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Makes sure that the directory `dir` exists in the upper layer, copying
+    /// it and its parents from the lower layer if necessary.
+    fn copy_up_dir(&self, dir: &str) -> FsResult<()> {
+        if dir.is_empty() {
+            return Ok(());
+        }
+        for path in ancestors(dir).chain([dir]) {
+            if self.in_upper(path)? {
+                continue;
+            }
+            if self.lower_stat(path)?.type_ != DescriptorType::Directory {
+                return Err(ErrorCode::NotDirectory.into());
+            }
+            self.upper.create_dir(path)?;
+        }
+        Ok(())
+    }
+
+    /// Makes sure that the file at `path` exists in the upper layer, copying
+    /// its contents from the lower layer if necessary.
+    fn copy_up_file(&self, path: &str) -> FsResult<()> {
+        if self.in_upper(path)? {
+            return Ok(());
+        }
+        let stat = self.lower_stat(path)?;
+        if stat.type_ == DescriptorType::Directory {
+            return Err(ErrorCode::IsDirectory.into());
+        }
+        let len = usize::try_from(stat.size).map_err(|_| ErrorCode::FileTooLarge)?;
+        let contents = self.lower.read(path, 0, len)?;
+        self.copy_up_dir(split(path).map_or("", |(parent, _)| parent))?;
+        self.upper.create_file(path, true)?;
+        self.upper.write(path, 0, &contents)
+    }
+
+    /// Hides `path` in the lower layer if it exists there.
+    fn whiteout(&self, path: &str) -> FsResult<()> {
+        if self.in_lower(path)? {
+            self.state
+                .lock()
+                .unwrap()
+                .whiteouts
+                .insert(path.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl VirtualFs for OverlayFs {
+    fn stat(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        match self.upper.stat(path) {
+            Err(e) if is_error(&e, ErrorCode::NoEntry) => self.lower_stat(path),
+            result => result,
+        }
+    }
+
+    fn read_dir(&self, path: &str) -> FsResult<Vec<types::DirectoryEntry>> {
+        let mut entries = BTreeMap::new();
+        let mut found = false;
+        match self.upper.read_dir(path) {
+            Ok(list) => {
+                found = true;
+                entries.extend(list.into_iter().map(|e| (e.name.clone(), e)));
+            }
+            Err(e) if is_error(&e, ErrorCode::NoEntry) => {}
+            Err(e) => return Err(e),
+        }
+        if self.lower_visible(path) {
+            match self.lower.read_dir(path) {
+                Ok(list) => {
+                    found = true;
+                    for entry in list {
+                        if self.lower_visible(&join(path, &entry.name)) {
+                            entries.entry(entry.name.clone()).or_insert(entry);
+                        }
+                    }
+                }
+                // A file in the lower layer is shadowed by a directory in
+                // the upper layer.
+                Err(_) if found => {}
+                Err(e) if is_error(&e, ErrorCode::NoEntry) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !found {
+            return Err(ErrorCode::NoEntry.into());
+        }
+        Ok(entries.into_values().collect())
+    }
+
+    fn read(&self, path: &str, offset: u64, len: usize) -> FsResult<Vec<u8>> {
+        if self.in_upper(path)? {
+            self.upper.read(path, offset, len)
+        } else {
+            self.lower_stat(path)?;
+            self.lower.read(path, offset, len)
+        }
+    }
+
+    fn write(&self, path: &str, offset: u64, buf: &[u8]) -> FsResult<()> {
+        self.copy_up_file(path)?;
+        self.upper.write(path, offset, buf)
+    }
+
+    fn set_size(&self, path: &str, size: u64) -> FsResult<()> {
+        self.copy_up_file(path)?;
+        self.upper.set_size(path, size)
+    }
+
+    fn create_file(&self, path: &str, exclusive: bool) -> FsResult<()> {
+        match self.stat(path) {
+            Ok(_) if exclusive => return Err(ErrorCode::Exist.into()),
+            Ok(stat) if stat.type_ == DescriptorType::Directory => {
+                return Err(ErrorCode::IsDirectory.into())
+            }
+            Ok(_) => return Ok(()),
+            Err(e) if is_error(&e, ErrorCode::NoEntry) => {}
+            Err(e) => return Err(e),
+        }
+        let (parent, _) = split(path).ok_or(ErrorCode::IsDirectory)?;
+        self.copy_up_dir(parent)?;
+        self.upper.create_file(path, exclusive)?;
+        self.state.lock().unwrap().whiteouts.remove(path);
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult<()> {
+        match self.stat(path) {
+            Ok(_) => return Err(ErrorCode::Exist.into()),
+            Err(e) if is_error(&e, ErrorCode::NoEntry) => {}
+            Err(e) => return Err(e),
+        }
+        let (parent, _) = split(path).ok_or(ErrorCode::Exist)?;
+        self.copy_up_dir(parent)?;
+        self.upper.create_dir(path)?;
+        let mut state = self.state.lock().unwrap();
+        if state.whiteouts.remove(path) {
+            state.opaque.insert(path.to_string());
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult<()> {
+        if self.in_upper(path)? {
+            self.upper.remove_file(path)?;
+        } else if self.lower_stat(path)?.type_ == DescriptorType::Directory {
+            return Err(ErrorCode::IsDirectory.into());
+        }
+        self.whiteout(path)
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult<()> {
+        if path.is_empty() {
+            return Err(ErrorCode::Busy.into());
+        }
+        if !self.read_dir(path)?.is_empty() {
+            return Err(ErrorCode::NotEmpty.into());
+        }
+        if self.in_upper(path)? {
+            self.upper.remove_dir(path)?;
+        }
+        self.state.lock().unwrap().opaque.remove(path);
+        self.whiteout(path)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> FsResult<()> {
+        let is_dir = self.stat(from)?.type_ == DescriptorType::Directory;
+        if from == to {
+            return Ok(());
+        }
+        if is_dir && self.in_lower(from)? {
+            return Err(ErrorCode::Unsupported.into());
+        }
+        match self.stat(to) {
+            Ok(stat) if is_dir && stat.type_ != DescriptorType::Directory => {
+                return Err(ErrorCode::NotDirectory.into())
+            }
+            Ok(stat) if !is_dir && stat.type_ == DescriptorType::Directory => {
+                return Err(ErrorCode::IsDirectory.into())
+            }
+            Ok(_) if is_dir && !self.read_dir(to)?.is_empty() => {
+                return Err(ErrorCode::NotEmpty.into())
+            }
+            _ => {}
+        }
+        if !is_dir {
+            self.copy_up_file(from)?;
+        }
+        let (parent, _) = split(to).ok_or(ErrorCode::Busy)?;
+        self.copy_up_dir(parent)?;
+        // The destination now shadows anything at the same path in the lower
+        // layer, so hide it to avoid merging in a lower directory's contents.
+        let hide_to = self.in_lower(to)?;
+        self.upper.rename(from, to)?;
+        self.whiteout(from)?;
+        let mut state = self.state.lock().unwrap();
+        if state.whiteouts.remove(to) || hide_to {
+            state.opaque.insert(to.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A [`VirtualFs`] backed by a directory on the host.
+///
+/// This is mostly useful to wrap a host directory in other filesystems, for
+/// example as the lower layer of an [`OverlayFs`]. Directories which are
+/// exposed to guests unmodified should use
+/// [`WasiCtxBuilder::preopened_dir`] instead.
+///
+/// [`WasiCtxBuilder::preopened_dir`]: crate::preview2::WasiCtxBuilder::preopened_dir
+pub struct DirFs {
+    dir: cap_std::fs::Dir,
+}
+
+impl DirFs {
+    /// Creates a filesystem rooted at `dir`.
+    pub fn new(dir: cap_std::fs::Dir) -> Self {
+        DirFs { dir }
+    }
+
+    fn open(&self, path: &str, options: &cap_std::fs::OpenOptions) -> FsResult<cap_std::fs::File> {
+        Ok(self.dir.open_with(host_path(path), options)?)
+    }
+}
+
+/// Returns the path to pass to `cap_std` for `path`, which can't be empty.
+fn host_path(path: &str) -> &str {
+    if path.is_empty() {
+        "."
+    } else {
+        path
+    }
+}
+
+impl VirtualFs for DirFs {
+    fn stat(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        let meta = self.dir.metadata(host_path(path))?;
+        Ok(descriptorstat_from(meta))
+    }
+
+    fn read_dir(&self, path: &str) -> FsResult<Vec<types::DirectoryEntry>> {
+        let mut entries = Vec::new();
+        for entry in self.dir.read_dir(host_path(path))? {
+            let entry = entry?;
+            let name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| ErrorCode::IllegalByteSequence)?;
+            entries.push(types::DirectoryEntry {
+                type_: descriptortype_from(entry.file_type()?),
+                name,
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    fn read(&self, path: &str, offset: u64, len: usize) -> FsResult<Vec<u8>> {
+        let mut file = self.open(path, cap_std::fs::OpenOptions::new().read(true))?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut buf = Vec::new();
+        file.take(len as u64).read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn write(&self, path: &str, offset: u64, buf: &[u8]) -> FsResult<()> {
+        let mut file = self.open(path, cap_std::fs::OpenOptions::new().write(true))?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(buf)?;
+        Ok(())
+    }
+
+    fn set_size(&self, path: &str, size: u64) -> FsResult<()> {
+        let file = self.open(path, cap_std::fs::OpenOptions::new().write(true))?;
+        file.set_len(size)?;
+        Ok(())
+    }
+
+    fn create_file(&self, path: &str, exclusive: bool) -> FsResult<()> {
+        let mut options = cap_std::fs::OpenOptions::new();
+        options.write(true);
+        if exclusive {
+            options.create_new(true);
+        } else {
+            options.create(true);
+        }
+        self.open(path, &options)?;
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &str) -> FsResult<()> {
+        Ok(self.dir.create_dir(path)?)
+    }
+
+    fn remove_file(&self, path: &str) -> FsResult<()> {
+        Ok(self.dir.remove_file(path)?)
+    }
+
+    fn remove_dir(&self, path: &str) -> FsResult<()> {
+        Ok(self.dir.remove_dir(path)?)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> FsResult<()> {
+        Ok(self.dir.rename(from, &self.dir, to)?)
+    }
+}
+
+/// A `wasi:filesystem` descriptor for a file or directory within a
+/// [`VirtualFs`].
+#[derive(Clone)]
+pub struct VirtualDescriptor {
+    fs: Arc<dyn VirtualFs>,
+    path: String,
+    is_dir: bool,
+    perms: DirPerms,
+    file_perms: FilePerms,
+}
+
+impl VirtualDescriptor {
+    /// Creates a descriptor for the root directory of `fs`.
+    pub(crate) fn root(fs: Arc<dyn VirtualFs>, perms: DirPerms, file_perms: FilePerms) -> Self {
+        VirtualDescriptor {
+            fs,
+            path: String::new(),
+            is_dir: true,
+            perms,
+            file_perms,
+        }
+    }
+
+    pub(crate) fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Resolves the guest-provided `path` relative to this directory,
+    /// refusing to escape it like `cap-std` does for host directories.
+    fn resolve(&self, path: &str) -> FsResult<String> {
+        if path.is_empty() {
+            return Err(ErrorCode::NoEntry.into());
+        }
+        if path.starts_with('/') {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        let mut resolved = components(&self.path).collect::<Vec<_>>();
+        let base = resolved.len();
+        for name in path.split('/') {
+            match name {
+                "" | "." => {}
+                ".." if resolved.len() == base => return Err(ErrorCode::NotPermitted.into()),
+                ".." => {
+                    resolved.pop();
+                }
+                name => resolved.push(name),
+            }
+        }
+        Ok(resolved.join("/"))
+    }
+
+    fn dir(&self, perms: DirPerms) -> FsResult<()> {
+        if !self.is_dir {
+            return Err(ErrorCode::NotDirectory.into());
+        }
+        if !self.perms.contains(perms) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        Ok(())
+    }
+
+    fn file(&self, perms: FilePerms) -> FsResult<()> {
+        if self.is_dir {
+            return Err(ErrorCode::BadDescriptor.into());
+        }
+        if !self.file_perms.contains(perms) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        Ok(())
+    }
+
+    fn same_fs(&self, other: &VirtualDescriptor) -> bool {
+        Arc::ptr_eq(&self.fs, &other.fs)
+    }
+
+    pub(crate) fn flags(&self) -> types::DescriptorFlags {
+        use types::DescriptorFlags;
+        let mut flags = DescriptorFlags::empty();
+        if self.is_dir {
+            if self.perms.contains(DirPerms::READ) {
+                flags |= DescriptorFlags::READ;
+            }
+            if self.perms.contains(DirPerms::MUTATE) {
+                flags |= DescriptorFlags::MUTATE_DIRECTORY;
+            }
+        } else {
+            if self.file_perms.contains(FilePerms::READ) {
+                flags |= DescriptorFlags::READ;
+            }
+            if self.file_perms.contains(FilePerms::WRITE) {
+                flags |= DescriptorFlags::WRITE;
+            }
+        }
+        flags
+    }
+
+    pub(crate) fn get_type(&self) -> types::DescriptorType {
+        if self.is_dir {
+            DescriptorType::Directory
+        } else {
+            DescriptorType::RegularFile
+        }
+    }
+
+    pub(crate) fn set_size(&self, size: types::Filesize) -> FsResult<()> {
+        self.file(FilePerms::WRITE)?;
+        self.fs.set_size(&self.path, size)
+    }
+
+    pub(crate) fn read(
+        &self,
+        len: types::Filesize,
+        offset: types::Filesize,
+    ) -> FsResult<(Vec<u8>, bool)> {
+        self.file(FilePerms::READ)?;
+        let len = len.try_into().unwrap_or(usize::MAX);
+        let buf = self.fs.read(&self.path, offset, len)?;
+        let end = buf.is_empty() && len > 0;
+        Ok((buf, end))
+    }
+
+    pub(crate) fn write(&self, buf: &[u8], offset: types::Filesize) -> FsResult<types::Filesize> {
+        self.file(FilePerms::WRITE)?;
+        self.fs.write(&self.path, offset, buf)?;
+        Ok(buf.len() as types::Filesize)
+    }
+
+    pub(crate) fn read_directory(&self) -> FsResult<Vec<types::DirectoryEntry>> {
+        self.dir(DirPerms::READ)?;
+        self.fs.read_dir(&self.path)
+    }
+
+    pub(crate) fn stat(&self) -> FsResult<types::DescriptorStat> {
+        self.fs.stat(&self.path)
+    }
+
+    pub(crate) fn stat_at(&self, path: &str) -> FsResult<types::DescriptorStat> {
+        self.dir(DirPerms::READ)?;
+        self.fs.stat(&self.resolve(path)?)
+    }
+
+    pub(crate) fn create_directory_at(&self, path: &str) -> FsResult<()> {
+        self.dir(DirPerms::MUTATE)?;
+        self.fs.create_dir(&self.resolve(path)?)
+    }
+
+    pub(crate) fn open_at(
+        &self,
+        path: &str,
+        oflags: types::OpenFlags,
+        flags: types::DescriptorFlags,
+    ) -> FsResult<VirtualDescriptor> {
+        use types::{DescriptorFlags, OpenFlags};
+
+        self.dir(DirPerms::READ)?;
+        let mutates = oflags.intersects(OpenFlags::CREATE | OpenFlags::TRUNCATE)
+            || flags.contains(DescriptorFlags::WRITE);
+        if mutates && !self.perms.contains(DirPerms::MUTATE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        if oflags.contains(OpenFlags::DIRECTORY)
+            && oflags.intersects(OpenFlags::CREATE | OpenFlags::EXCLUSIVE | OpenFlags::TRUNCATE)
+        {
+            return Err(ErrorCode::Invalid.into());
+        }
+
+        let path = self.resolve(path)?;
+        if oflags.contains(OpenFlags::CREATE) {
+            self.fs
+                .create_file(&path, oflags.contains(OpenFlags::EXCLUSIVE))?;
+        }
+        let is_dir = self.fs.stat(&path)?.type_ == DescriptorType::Directory;
+        if is_dir {
+            if oflags.contains(OpenFlags::TRUNCATE) || flags.contains(DescriptorFlags::WRITE) {
+                return Err(ErrorCode::IsDirectory.into());
+            }
+        } else {
+            if oflags.contains(OpenFlags::DIRECTORY) {
+                return Err(ErrorCode::NotDirectory.into());
+            }
+            if oflags.contains(OpenFlags::TRUNCATE) {
+                self.fs.set_size(&path, 0)?;
+            }
+        }
+
+        let mut file_perms = FilePerms::empty();
+        if flags.contains(DescriptorFlags::READ) {
+            file_perms |= FilePerms::READ;
+        }
+        if flags.contains(DescriptorFlags::WRITE) {
+            file_perms |= FilePerms::WRITE;
+        }
+        Ok(VirtualDescriptor {
+            fs: self.fs.clone(),
+            path,
+            is_dir,
+            perms: self.perms,
+            file_perms: if is_dir {
+                self.file_perms
+            } else {
+                self.file_perms & file_perms
+            },
+        })
+    }
+
+    pub(crate) fn readlink_at(&self, path: &str) -> FsResult<String> {
+        self.stat_at(path)?;
+        // Nothing within a virtual filesystem is a symlink.
+        Err(ErrorCode::Invalid.into())
+    }
+
+    pub(crate) fn remove_directory_at(&self, path: &str) -> FsResult<()> {
+        self.dir(DirPerms::MUTATE)?;
+        self.fs.remove_dir(&self.resolve(path)?)
+    }
+
+    pub(crate) fn unlink_file_at(&self, path: &str) -> FsResult<()> {
+        self.dir(DirPerms::MUTATE)?;
+        self.fs.remove_file(&self.resolve(path)?)
+    }
+
+    pub(crate) fn rename_at(
+        &self,
+        old_path: &str,
+        new_dir: &VirtualDescriptor,
+        new_path: &str,
+    ) -> FsResult<()> {
+        self.dir(DirPerms::MUTATE)?;
+        new_dir.dir(DirPerms::MUTATE)?;
+        if !self.same_fs(new_dir) {
+            return Err(ErrorCode::CrossDevice.into());
+        }
+        self.fs
+            .rename(&self.resolve(old_path)?, &new_dir.resolve(new_path)?)
+    }
+
+    pub(crate) fn is_same_object(&self, other: &VirtualDescriptor) -> bool {
+        self.same_fs(other) && self.path == other.path
+    }
+
+    pub(crate) fn metadata_hash(&self) -> FsResult<types::MetadataHashValue> {
+        self.stat()?;
+        Ok(self.hash_path(&self.path))
+    }
+
+    pub(crate) fn metadata_hash_at(&self, path: &str) -> FsResult<types::MetadataHashValue> {
+        let path = self.resolve(path)?;
+        self.fs.stat(&path)?;
+        Ok(self.hash_path(&path))
+    }
+
+    /// Virtual files have no inode numbers, so identify them by their
+    /// filesystem and path instead.
+    fn hash_path(&self, path: &str) -> types::MetadataHashValue {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (Arc::as_ptr(&self.fs) as *const () as usize).hash(&mut hasher);
+        path.hash(&mut hasher);
+        let lower = hasher.finish();
+        // See `calculate_metadata_hash` for host files.
+        let upper = lower ^ 4614256656552045848u64;
+        types::MetadataHashValue { lower, upper }
+    }
+
+    pub(crate) fn read_via_stream(&self, offset: types::Filesize) -> FsResult<VirtualInputStream> {
+        self.file(FilePerms::READ)
+            .map_err(|_| ErrorCode::BadDescriptor)?;
+        Ok(VirtualInputStream {
+            fs: self.fs.clone(),
+            path: self.path.clone(),
+            position: offset,
+        })
+    }
+
+    pub(crate) fn write_via_stream(&self, mode: FileOutputMode) -> FsResult<VirtualOutputStream> {
+        self.file(FilePerms::WRITE)
+            .map_err(|_| ErrorCode::BadDescriptor)?;
+        Ok(VirtualOutputStream {
+            fs: self.fs.clone(),
+            path: self.path.clone(),
+            mode,
+        })
+    }
+}
+
+fn stream_error(err: FsError) -> StreamError {
+    match err.downcast() {
+        Ok(code) => StreamError::LastOperationFailed(code.into()),
+        Err(trap) => StreamError::Trap(trap),
+    }
+}
+
+pub(crate) struct VirtualInputStream {
+    fs: Arc<dyn VirtualFs>,
+    path: String,
+    position: u64,
+}
+
+impl HostInputStream for VirtualInputStream {
+    fn read(&mut self, size: usize) -> StreamResult<Bytes> {
+        let buf = self
+            .fs
+            .read(&self.path, self.position, size)
+            .map_err(stream_error)?;
+        if buf.is_empty() && size > 0 {
+            return Err(StreamError::Closed);
+        }
+        self.position += buf.len() as u64;
+        Ok(buf.into())
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for VirtualInputStream {
+    async fn ready(&mut self) {}
+}
+
+pub(crate) struct VirtualOutputStream {
+    fs: Arc<dyn VirtualFs>,
+    path: String,
+    mode: FileOutputMode,
+}
+
+impl HostOutputStream for VirtualOutputStream {
+    fn write(&mut self, buf: Bytes) -> StreamResult<()> {
+        let offset = match self.mode {
+            FileOutputMode::Position(p) => p,
+            FileOutputMode::Append => self.fs.stat(&self.path).map_err(stream_error)?.size,
+        };
+        self.fs
+            .write(&self.path, offset, &buf)
+            .map_err(stream_error)?;
+        if let FileOutputMode::Position(p) = &mut self.mode {
+            *p += buf.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(FILE_WRITE_CAPACITY)
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for VirtualOutputStream {
+    async fn ready(&mut self) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn read_to_string(fs: &dyn VirtualFs, path: &str) -> String {
+        String::from_utf8(fs.read(path, 0, usize::MAX).unwrap()).unwrap()
+    }
+
+    fn names(fs: &dyn VirtualFs, path: &str) -> Vec<String> {
+        let entries = fs.read_dir(path).unwrap();
+        entries.into_iter().map(|e| e.name).collect()
+    }
+
+    fn error(result: FsResult<impl Sized>) -> ErrorCode {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.downcast().unwrap(),
+        }
+    }
+
+    #[test]
+    fn memory_fs() {
+        let fs = MemoryFs::new();
+        fs.insert_file("a/b/c.txt", "hello").unwrap();
+        assert_eq!(names(&fs, ""), ["a"]);
+        assert_eq!(read_to_string(&fs, "a/b/c.txt"), "hello");
+
+        fs.write("a/b/c.txt", 7, b"!").unwrap();
+        assert_eq!(fs.read("a/b/c.txt", 4, 10).unwrap(), b"o\0\0!");
+        fs.set_size("a/b/c.txt", 2).unwrap();
+        assert_eq!(read_to_string(&fs, "a/b/c.txt"), "he");
+
+        assert_eq!(error(fs.create_file("a/b/c.txt", true)), ErrorCode::Exist);
+        assert_eq!(error(fs.remove_dir("a")), ErrorCode::NotEmpty);
+        assert_eq!(error(fs.rename("a", "a/b/d")), ErrorCode::Invalid);
+        assert_eq!(error(fs.stat("a/b/c.txt/d")), ErrorCode::NotDirectory);
+
+        fs.rename("a/b/c.txt", "d.txt").unwrap();
+        assert_eq!(names(&fs, ""), ["a", "d.txt"]);
+        fs.remove_dir("a/b").unwrap();
+        fs.remove_file("d.txt").unwrap();
+        assert_eq!(error(fs.stat("d.txt")), ErrorCode::NoEntry);
+    }
+
+    #[test]
+    fn memory_fs_limit() {
+        let fs = MemoryFs::with_limit(10);
+        fs.insert_file("a", "12345").unwrap();
+        fs.create_file("b", true).unwrap();
+        assert_eq!(fs.used(), 5);
+
+        // Neither a single file nor all of them together can go over the
+        // limit, and nothing is allocated for writes which are refused.
+        assert_eq!(error(fs.set_size("b", 11)), ErrorCode::FileTooLarge);
+        assert_eq!(error(fs.set_size("b", u64::MAX)), ErrorCode::FileTooLarge);
+        assert_eq!(
+            error(fs.write("b", u64::MAX, b"x")),
+            ErrorCode::FileTooLarge
+        );
+        assert_eq!(error(fs.write("b", 5, b"x")), ErrorCode::InsufficientSpace);
+        assert_eq!(fs.stat("b").unwrap().size, 0);
+        assert_eq!(fs.used(), 5);
+
+        fs.write("b", 3, b"xx").unwrap();
+        assert_eq!(fs.used(), 10);
+        assert_eq!(
+            error(fs.insert_file("c", "c")),
+            ErrorCode::InsufficientSpace
+        );
+
+        // Space is given back when files shrink or are removed.
+        fs.set_size("b", 1).unwrap();
+        fs.rename("b", "a").unwrap();
+        assert_eq!(fs.used(), 1);
+        fs.remove_file("a").unwrap();
+        assert_eq!(fs.used(), 0);
+    }
+
+    /// Returns a ustar header and the data for an entry.
+    fn tar_entry(name: &str, typeflag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut header = [0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        set_checksum(&mut header);
+
+        let mut entry = header.to_vec();
+        entry.extend_from_slice(contents);
+        entry.resize(
+            entry.len() + tar::padding(contents.len() as u64) as usize,
+            0,
+        );
+        entry
+    }
+
+    /// Updates the checksum of the tar header at the start of `header`.
+    fn set_checksum(header: &mut [u8]) {
+        header[148..156].fill(b' ');
+        let checksum = header[..512].iter().map(|b| u32::from(*b)).sum::<u32>();
+        header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+    }
+
+    #[test]
+    fn tar_image() {
+        let long_name = format!("{}/file.txt", "d".repeat(120));
+        let mut image = Vec::new();
+        image.extend(tar_entry("./etc/", b'5', b""));
+        image.extend(tar_entry("./etc/motd", b'0', b"hello"));
+        image.extend(tar_entry("././@LongLink", b'L', long_name.as_bytes()));
+        image.extend(tar_entry("truncated", b'0', b"long"));
+        let pax = b"16 path=pax.txt\n";
+        image.extend(tar_entry("PaxHeaders/pax.txt", b'x', pax));
+        image.extend(tar_entry("ignored.txt", b'0', b"pax"));
+        image.extend([0; 1024]);
+
+        let fs = MemoryFs::new();
+        fs.load_tar(&image[..]).unwrap();
+        assert_eq!(
+            names(&fs, ""),
+            ["d".repeat(120), "etc".into(), "pax.txt".into()]
+        );
+        assert_eq!(read_to_string(&fs, "etc/motd"), "hello");
+        assert_eq!(read_to_string(&fs, &long_name), "long");
+        assert_eq!(read_to_string(&fs, "pax.txt"), "pax");
+
+        // Images which don't fit, escape the root, or contain links are
+        // rejected.
+        let fs = MemoryFs::with_limit(4);
+        let err = fs.load_tar(&image[..]).unwrap_err();
+        assert_eq!(
+            err.downcast::<FsError>().unwrap().downcast().unwrap(),
+            ErrorCode::FileTooLarge
+        );
+        let err = MemoryFs::new()
+            .load_tar(&tar_entry("../escape", b'0', b"")[..])
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("outside of the image's root"),
+            "{err}"
+        );
+        let err = MemoryFs::new()
+            .load_tar(&tar_entry("link", b'2', b"")[..])
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("unsupported entry type `2`"),
+            "{err}"
+        );
+
+        let mut corrupt = tar_entry("a", b'0', b"a");
+        corrupt[0] = b'b';
+        let err = MemoryFs::new().load_tar(&corrupt[..]).unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{err}");
+    }
+
+    #[test]
+    fn tar_image_malformed() {
+        let load = |image: &[u8]| {
+            let fs = MemoryFs::new();
+            let err = fs.load_tar(image).unwrap_err().to_string();
+            (fs, err)
+        };
+
+        // Images which end in the middle of a header or of an entry's data.
+        let entry = tar_entry("a.txt", b'0', b"hello");
+        let (fs, err) = load(&entry[..100]);
+        assert!(err.contains("unexpected end of tar image"), "{err}");
+        assert!(names(&fs, "").is_empty());
+        let (_, err) = load(&entry[..514]);
+        assert!(err.contains("unexpected end of tar image"), "{err}");
+
+        // Headers whose checksum doesn't match, or isn't a number at all.
+        // Entries before the corrupt one are still loaded.
+        let mut image = entry.clone();
+        let mut corrupt = tar_entry("b.txt", b'0', b"b");
+        corrupt[124..135].copy_from_slice(b"00000000002");
+        image.extend(corrupt);
+        let (fs, err) = load(&image);
+        assert!(err.contains("invalid tar header checksum"), "{err}");
+        assert_eq!(names(&fs, ""), ["a.txt"]);
+        let mut corrupt = entry.clone();
+        corrupt[148..156].copy_from_slice(b"garbage\0");
+        let (_, err) = load(&corrupt);
+        assert!(err.contains("invalid number `garbage`"), "{err}");
+
+        // `..` is refused wherever the name of an entry comes from: the
+        // header itself, its ustar prefix, a GNU long name or a pax header.
+        let (_, err) = load(&tar_entry("a/../../b.txt", b'0', b"")[..]);
+        assert!(err.contains("outside of the image's root"), "{err}");
+        let mut prefixed = tar_entry("b.txt", b'0', b"");
+        prefixed[345..347].copy_from_slice(b"..");
+        set_checksum(&mut prefixed);
+        let (_, err) = load(&prefixed);
+        assert!(err.contains("`../b.txt` is outside"), "{err}");
+        let mut image = tar_entry("././@LongLink", b'L', b"../b.txt");
+        image.extend(tar_entry("b.txt", b'0', b""));
+        let (_, err) = load(&image);
+        assert!(err.contains("`../b.txt` is outside"), "{err}");
+        let mut image = tar_entry("PaxHeaders/b.txt", b'x', b"17 path=../b.txt\n");
+        image.extend(tar_entry("b.txt", b'5', b""));
+        let (fs, err) = load(&image);
+        assert!(err.contains("`../b.txt` is outside"), "{err}");
+        assert!(names(&fs, "").is_empty());
+    }
+
+    #[test]
+    fn overlay_fs() {
+        let lower = MemoryFs::new();
+        lower.insert_file("etc/motd", "hello").unwrap();
+        lower.insert_file("etc/passwd", "root").unwrap();
+        lower.insert_file("tmp/old", "").unwrap();
+        let lower: Arc<dyn VirtualFs> = Arc::new(ReadOnlyFs::new(Arc::new(lower)));
+        assert_eq!(error(lower.remove_file("etc/motd")), ErrorCode::ReadOnly);
+
+        let upper = Arc::new(MemoryFs::new());
+        let fs = OverlayFs::new(upper.clone(), lower.clone());
+
+        // Writes are copied up without modifying the lower layer.
+        fs.write("etc/motd", 5, b" world").unwrap();
+        assert_eq!(read_to_string(&fs, "etc/motd"), "hello world");
+        assert_eq!(read_to_string(&*upper, "etc/motd"), "hello world");
+        assert_eq!(read_to_string(&*lower, "etc/motd"), "hello");
+
+        // Removed files are hidden.
+        fs.remove_file("etc/passwd").unwrap();
+        assert_eq!(names(&fs, "etc"), ["motd"]);
+        assert_eq!(error(fs.stat("etc/passwd")), ErrorCode::NoEntry);
+        fs.create_file("etc/passwd", true).unwrap();
+        assert_eq!(read_to_string(&fs, "etc/passwd"), "");
+
+        // Recreating a removed directory doesn't resurrect its contents.
+        fs.remove_file("tmp/old").unwrap();
+        fs.remove_dir("tmp").unwrap();
+        assert_eq!(names(&fs, ""), ["etc"]);
+        fs.create_dir("tmp").unwrap();
+        assert!(names(&fs, "tmp").is_empty());
+
+        fs.rename("etc/motd", "tmp/motd").unwrap();
+        assert_eq!(names(&fs, "etc"), ["passwd"]);
+        assert_eq!(read_to_string(&fs, "tmp/motd"), "hello world");
+        assert_eq!(error(fs.rename("etc", "var")), ErrorCode::Unsupported);
+    }
+
+    #[test]
+    fn descriptor_paths() {
+        let fs = MemoryFs::new();
+        fs.insert_file("a/b.txt", "b").unwrap();
+        let root = VirtualDescriptor::root(Arc::new(fs), DirPerms::all(), FilePerms::all());
+        let a = root
+            .open_at(
+                "a",
+                types::OpenFlags::DIRECTORY,
+                types::DescriptorFlags::READ,
+            )
+            .unwrap();
+        assert_eq!(a.stat_at("./b.txt").unwrap().size, 1);
+        assert_eq!(error(a.stat_at("../a")), ErrorCode::NotPermitted);
+        assert_eq!(error(root.stat_at("/a")), ErrorCode::NotPermitted);
+
+        let b = a
+            .open_at(
+                "b.txt",
+                types::OpenFlags::TRUNCATE,
+                types::DescriptorFlags::WRITE,
+            )
+            .unwrap();
+        assert_eq!(error(b.read(1, 0)), ErrorCode::NotPermitted);
+        b.write(b"bb", 0).unwrap();
+        assert_eq!(b.stat().unwrap().size, 2);
+    }
+}
@@ -97,6 +97,78 @@ async fn api_time() -> Result<()> {
         .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn deterministic_ctx() -> Result<()> {
+    use wasmtime_wasi::preview2::bindings::clocks::monotonic_clock;
+    use wasmtime_wasi::preview2::bindings::io::poll;
+    use wasmtime_wasi::preview2::bindings::random::random;
+
+    async fn observe() -> Result<(wall_clock::Datetime, u64, u64, Vec<u8>)> {
+        let mut ctx = CommandCtx {
+            table: Table::new(),
+            wasi: WasiCtxBuilder::new().deterministic().build(),
+        };
+        let start = monotonic_clock::Host::now(&mut ctx)?;
+
+        // Sleeping on the virtual monotonic clock completes immediately once
+        // polled and moves the clock forward to the deadline.
+        let sleep = monotonic_clock::Host::subscribe(&mut ctx, 60_000_000_000, false)?;
+        poll::Host::poll_one(&mut ctx, sleep).await?;
+        let after_sleep = monotonic_clock::Host::now(&mut ctx)?;
+        assert!(after_sleep - start >= 60_000_000_000);
+
+        let wall = wall_clock::Host::now(&mut ctx)?;
+        let bytes = random::Host::get_random_bytes(&mut ctx, 16)?;
+        Ok((wall, start, after_sleep, bytes))
+    }
+
+    let first = observe().await?;
+    let second = observe().await?;
+    assert_eq!(first.0.seconds, second.0.seconds);
+    assert_eq!(first.0.nanoseconds, second.0.nanoseconds);
+    assert_eq!(first.1, second.1);
+    assert_eq!(first.2, second.2);
+    assert_eq!(first.3, second.3);
+    Ok(())
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn deterministic_timers() -> Result<()> {
+    use wasmtime::component::Resource;
+    use wasmtime_wasi::preview2::bindings::clocks::monotonic_clock;
+    use wasmtime_wasi::preview2::bindings::io::poll;
+
+    const SECOND: u64 = 1_000_000_000;
+    let mut ctx = CommandCtx {
+        table: Table::new(),
+        wasi: WasiCtxBuilder::new().deterministic().build(),
+    };
+
+    // Subscribing doesn't move the clock.
+    let short = monotonic_clock::Host::subscribe(&mut ctx, SECOND, false)?;
+    let long = monotonic_clock::Host::subscribe(&mut ctx, 60 * SECOND, false)?;
+    assert!(monotonic_clock::Host::now(&mut ctx)? < SECOND);
+
+    // Polling only the short timer advances the clock to its deadline, but
+    // not to that of the long timer, which isn't polled.
+    let ready = poll::Host::poll_list(&mut ctx, vec![Resource::new_borrow(short.rep())]).await?;
+    assert_eq!(ready, [0]);
+    let now = monotonic_clock::Host::now(&mut ctx)?;
+    assert!(now >= SECOND && now < 60 * SECOND, "{now}");
+
+    // When both are polled, only the earlier one is ready.
+    let both = vec![
+        Resource::new_borrow(long.rep()),
+        Resource::new_borrow(short.rep()),
+    ];
+    assert_eq!(poll::Host::poll_list(&mut ctx, both).await?, [1]);
+    assert!(monotonic_clock::Host::now(&mut ctx)? < 60 * SECOND);
+
+    poll::Host::poll_one(&mut ctx, Resource::new_borrow(long.rep())).await?;
+    assert!(monotonic_clock::Host::now(&mut ctx)? >= 60 * SECOND);
+    Ok(())
+}
+
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn api_read_only() -> Result<()> {
     let dir = tempfile::tempdir()?;
@@ -120,6 +192,48 @@ async fn api_read_only() -> Result<()> {
         .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn api_virtual_dir() -> Result<()> {
+    use std::sync::Arc;
+    use wasmtime_wasi::preview2::vfs::{MemoryFs, OverlayFs, ReadOnlyFs, VirtualFs};
+
+    let image = MemoryFs::new();
+    image.insert_file("etc/motd", "hello from the image")?;
+    image.insert_file("etc/passwd", "root")?;
+    image.create_dir_all("data")?;
+    let image: Arc<dyn VirtualFs> = Arc::new(ReadOnlyFs::new(Arc::new(image)));
+    let scratch = Arc::new(MemoryFs::with_limit(4096));
+    let tenant = OverlayFs::new(scratch.clone(), image.clone());
+
+    let table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .preopened_virtual_dir(Arc::new(tenant), DirPerms::all(), FilePerms::all(), "/")
+        .build();
+
+    let (mut store, command) =
+        instantiate(API_VIRTUAL_DIR_COMPONENT, CommandCtx { table, wasi }).await?;
+
+    command
+        .wasi_cli_run()
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    // The guest's changes only landed in its own layer.
+    let read = |fs: &dyn VirtualFs, path: &str| {
+        String::from_utf8(fs.read(path, 0, usize::MAX).unwrap()).unwrap()
+    };
+    assert_eq!(read(&*image, "etc/motd"), "hello from the image");
+    assert_eq!(read(&*image, "etc/passwd"), "root");
+    assert_eq!(
+        read(&*scratch, "etc/motd"),
+        "hello from the image and the tenant"
+    );
+    assert_eq!(read(&*scratch, "data/renamed.txt"), "new");
+    assert_eq!(scratch.used(), 38);
+    Ok(())
+}
+
 // This is tested in the wasi-http crate, but need to satisfy the `foreach_api!`
 // macro above.
 #[allow(dead_code)]
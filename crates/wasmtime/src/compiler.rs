@@ -334,11 +334,13 @@ impl<'a> CompileInputs<'a> {
         }
     }
 
-    /// Compile these `CompileInput`s (maybe in parallel) and return the
-    /// resulting `UnlinkedCompileOutput`s.
-    pub fn compile(self, engine: &Engine) -> Result<UnlinkedCompileOutputs> {
-        let compiler = engine.compiler();
-
+    /// Compile these `CompileInput`s (maybe in parallel) with the given
+    /// compiler and return the resulting `UnlinkedCompileOutput`s.
+    pub fn compile(
+        self,
+        engine: &Engine,
+        compiler: &dyn Compiler,
+    ) -> Result<UnlinkedCompileOutputs> {
         // Compile each individual input in parallel.
         let raw_outputs = engine.run_maybe_parallel(self.inputs, |f| f(compiler))?;
 
@@ -1,4 +1,6 @@
 use crate::code::CodeObject;
+use crate::component::matching::InstanceType;
+use crate::component::types;
 use crate::signatures::SignatureCollection;
 use crate::{Engine, Module, ResourcesRequired};
 use anyhow::{bail, Context, Result};
@@ -9,8 +11,8 @@ use std::path::Path;
 use std::ptr::NonNull;
 use std::sync::Arc;
 use wasmtime_environ::component::{
-    AllCallFunc, ComponentTypes, GlobalInitializer, InstantiateModule, StaticModuleIndex,
-    TrampolineIndex, Translator, VMComponentOffsets,
+    AllCallFunc, ComponentTypes, GlobalInitializer, InstantiateModule, ResourceIndex,
+    StaticModuleIndex, TrampolineIndex, Translator, VMComponentOffsets,
 };
 use wasmtime_environ::{FunctionLoc, HostPtr, ObjectKind, PrimaryMap, ScopeVec};
 use wasmtime_jit::{CodeMemory, CompiledModuleInfo};
@@ -179,6 +181,14 @@ impl Component {
         let tunables = &engine.config().tunables;
         let compiler = engine.compiler();
 
+        // Winch doesn't support components, so with tiered compilation they're
+        // compiled with the optimizing compiler right away.
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        let compiler = match engine.tiering() {
+            Some(tiering) => &*tiering.compiler,
+            None => compiler,
+        };
+
         let scope = ScopeVec::new();
         let mut validator =
             wasmparser::Validator::new_with_features(engine.config().features.clone());
@@ -197,7 +207,7 @@ impl Component {
                 (i, &*translation, functions)
             }),
         );
-        let unlinked_compile_outputs = compile_inputs.compile(&engine)?;
+        let unlinked_compile_outputs = compile_inputs.compile(&engine, compiler)?;
         let (compiled_funcs, function_indices) = unlinked_compile_outputs.pre_link();
 
         let mut object = compiler.object(ObjectKind::Component)?;
@@ -452,11 +462,65 @@ impl Component {
                 | GlobalInitializer::ExtractMemory(_)
                 | GlobalInitializer::ExtractRealloc(_)
                 | GlobalInitializer::ExtractPostReturn(_)
-                | GlobalInitializer::Resource(_) => {}
+                | GlobalInitializer::Resource(_)
+                | GlobalInitializer::Start(_) => {}
             }
         }
         Some(resources)
     }
+
+    /// Returns the type of this component, describing everything it imports
+    /// and exports.
+    ///
+    /// This can be used to inspect a component's imports and exports, for
+    /// example to validate it or generate host definitions for it, without
+    /// instantiating it.
+    ///
+    /// Note that resource types imported or defined by a component are
+    /// abstract until the component is instantiated. The
+    /// [`ResourceType`](types::ResourceType)s found within the returned type
+    /// are only equal to other resource types from this component's type and
+    /// never to those of an instance of this component or of the host.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wasmtime::{Config, Engine};
+    /// use wasmtime::component::Component;
+    /// use wasmtime::component::types::ComponentItem;
+    ///
+    /// # fn main() -> wasmtime::Result<()> {
+    /// let mut config = Config::new();
+    /// config.wasm_component_model(true);
+    /// let engine = Engine::new(&config)?;
+    /// let component = Component::new(&engine, r#"
+    ///     (component
+    ///         (import "log" (func (param "msg" string)))
+    ///     )
+    /// "#)?;
+    ///
+    /// let ty = component.component_type();
+    /// let (name, item) = ty.imports().next().unwrap();
+    /// assert_eq!(name, "log");
+    /// match item {
+    ///     ComponentItem::ComponentFunc(func) => assert_eq!(func.params().len(), 1),
+    ///     _ => unreachable!(),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn component_type(&self) -> types::Component {
+        let resources = (0..self.env_component().num_resources)
+            .map(|i| types::ResourceType::uninstantiated(self.types(), ResourceIndex::from_u32(i)))
+            .collect();
+        types::Component::from(
+            self.env_component().ty,
+            &InstanceType {
+                types: self.types(),
+                resources: &Arc::new(resources),
+            },
+        )
+    }
 }
 
 impl ComponentRuntimeInfo for ComponentInner {
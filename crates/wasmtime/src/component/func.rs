@@ -89,6 +89,7 @@ union ParamsAndResults<Params: Copy, Return: Copy> {
 //
 // FIXME: write more docs here
 #[derive(Copy, Clone, Debug)]
+#[repr(transparent)] // here for the C API
 pub struct Func(Stored<FuncData>);
 
 #[doc(hidden)]
@@ -335,7 +336,62 @@ impl Func {
             .await?
     }
 
-    fn call_impl(
+    /// Calls this function and then immediately invokes
+    /// [`Func::post_return`].
+    ///
+    /// The results of the call are written to `results` as owned values which
+    /// remain valid after post-return has run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either the call itself or post-return fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`Func::call`].
+    pub fn call_and_post_return(
+        &self,
+        mut store: impl AsContextMut,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<()> {
+        self.call(&mut store, params, results)?;
+        self.post_return(&mut store)
+    }
+
+    /// Exactly like [`Self::call_and_post_return`] except for use on async
+    /// stores.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`Func::call_async`].
+    #[cfg(feature = "async")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "async")))]
+    pub async fn call_and_post_return_async<T>(
+        &self,
+        mut store: impl AsContextMut<Data = T>,
+        params: &[Val],
+        results: &mut [Val],
+    ) -> Result<()>
+    where
+        T: Send,
+    {
+        self.call_async(&mut store, params, results).await?;
+        self.post_return_async(&mut store).await
+    }
+
+    /// Returns whether bindings generated by
+    /// [`bindgen!`](crate::component::bindgen) invoke post-return right after
+    /// calling this function, as configured with
+    /// [`Linker::auto_post_return`](crate::component::Linker::auto_post_return).
+    #[doc(hidden)]
+    pub fn auto_post_return(&self, store: impl AsContext) -> bool {
+        let store = store.as_context();
+        let instance = store[self.0].instance;
+        store[instance.0].as_ref().unwrap().auto_post_return()
+    }
+
+    pub(crate) fn call_impl(
         &self,
         mut store: impl AsContextMut,
         params: &[Val],
@@ -598,7 +654,7 @@ impl Func {
     }
 
     #[inline]
-    fn post_return_impl(&self, mut store: impl AsContextMut) -> Result<()> {
+    pub(crate) fn post_return_impl(&self, mut store: impl AsContextMut) -> Result<()> {
         let mut store = store.as_context_mut();
         let data = &mut store.0[self.0];
         let instance = data.instance;
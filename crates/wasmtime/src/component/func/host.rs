@@ -1,6 +1,7 @@
 use crate::component::func::{LiftContext, LowerContext, Options};
 use crate::component::matching::InstanceType;
 use crate::component::storage::slice_to_storage_mut;
+use crate::component::types;
 use crate::component::{ComponentNamedList, ComponentType, Lift, Lower, Type, Val};
 use crate::{AsContextMut, StoreContextMut, ValRaw};
 use anyhow::{anyhow, bail, Context, Result};
@@ -96,6 +97,26 @@ impl HostFunc {
         })
     }
 
+    /// Same as `new_dynamic` except that the type of the function is `ty`,
+    /// which may come from a different component than the one importing this
+    /// function, and is compared structurally during type-checking.
+    pub(crate) fn new_dynamic_with_type<T, F>(func: F, ty: types::ComponentFunc) -> Arc<HostFunc>
+    where
+        F: Fn(StoreContextMut<'_, T>, &[Val], &mut [Val]) -> Result<()> + Send + Sync + 'static,
+    {
+        Arc::new(HostFunc {
+            entrypoint: dynamic_entrypoint::<T, F>,
+            typecheck: Box::new(move |expected_index, expected_types| {
+                if types::ComponentFunc::from(expected_index, expected_types) == ty {
+                    Ok(())
+                } else {
+                    Err(anyhow!("function type mismatch"))
+                }
+            }),
+            func: Box::new(func),
+        })
+    }
+
     pub fn typecheck(&self, ty: TypeFuncIndex, types: &InstanceType<'_>) -> Result<()> {
         (self.typecheck)(ty, types)
     }
@@ -189,6 +189,57 @@ where
             .await?
     }
 
+    /// Calls this function and then immediately invokes
+    /// [`TypedFunc::post_return`], returning the results of the call.
+    ///
+    /// This is a convenience for the common case where the results of a call
+    /// are fully owned by the host, such as `String` or `Vec<T>`, and don't
+    /// need to be inspected before post-return runs. Results which borrow from
+    /// the instance's linear memory, such as [`WasmStr`] and [`WasmList`],
+    /// must not be used after this returns since post-return may have
+    /// deallocated the memory they point to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either the call itself or post-return fails. See
+    /// [`TypedFunc::call`] for more information.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`TypedFunc::call`].
+    pub fn call_and_post_return(
+        &self,
+        mut store: impl AsContextMut,
+        params: Params,
+    ) -> Result<Return> {
+        let ret = self.call(&mut store, params)?;
+        self.post_return(&mut store)?;
+        Ok(ret)
+    }
+
+    /// Exactly like [`Self::call_and_post_return`], except for use on
+    /// asynchronous stores.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`TypedFunc::call_async`].
+    #[cfg(feature = "async")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "async")))]
+    pub async fn call_and_post_return_async<T>(
+        &self,
+        mut store: impl AsContextMut<Data = T>,
+        params: Params,
+    ) -> Result<Return>
+    where
+        T: Send,
+        Params: Send + Sync,
+        Return: Send + Sync,
+    {
+        let ret = self.call_async(&mut store, params).await?;
+        self.post_return_async(&mut store).await?;
+        Ok(ret)
+    }
+
     fn call_impl(&self, mut store: impl AsContextMut, params: Params) -> Result<Return> {
         let store = &mut store.as_context_mut();
         // Note that this is in theory simpler than it might read at this time.
@@ -1,10 +1,15 @@
 use crate::component::func::HostFunc;
 use crate::component::matching::InstanceType;
-use crate::component::{Component, ComponentNamedList, Func, Lift, Lower, ResourceType, TypedFunc};
+use crate::component::resources::ResourceDtor;
+use crate::component::types;
+use crate::component::{
+    Component, ComponentNamedList, Func, Lift, Lower, ResourceHandleInfo, ResourceType, TypedFunc,
+    Val,
+};
 use crate::instance::OwnedImports;
 use crate::linker::DefinitionType;
 use crate::store::{StoreOpaque, Stored};
-use crate::{AsContextMut, Module, StoreContextMut};
+use crate::{AsContext, AsContextMut, Module, StoreContextMut};
 use anyhow::{anyhow, Context, Result};
 use indexmap::IndexMap;
 use std::marker;
@@ -23,6 +28,7 @@ use wasmtime_runtime::VMFuncRef;
 //
 // FIXME: need to write more docs here.
 #[derive(Copy, Clone)]
+#[repr(transparent)] // here for the C API
 pub struct Instance(pub(crate) Stored<Option<Box<InstanceData>>>);
 
 pub(crate) struct InstanceData {
@@ -56,6 +62,14 @@ pub(crate) struct InstanceData {
     /// entire list here though we're guaranteed that nothing is lost for the
     /// duration of the lifetime of this instance.
     imports: Arc<PrimaryMap<RuntimeImportIndex, RuntimeImport>>,
+
+    /// Component-level values produced by `start` functions during
+    /// instantiation.
+    values: PrimaryMap<RuntimeValueIndex, Val>,
+
+    /// Whether bindings generated by `bindgen!` invoke post-return after each
+    /// call into this instance, configured with `Linker::auto_post_return`.
+    auto_post_return: bool,
 }
 
 impl Instance {
@@ -143,6 +157,78 @@ impl Instance {
     pub fn get_resource(&self, mut store: impl AsContextMut, name: &str) -> Option<ResourceType> {
         self.exports(store.as_context_mut()).root().resource(name)
     }
+
+    /// Looks up an exported component-level value by name within this
+    /// [`Instance`].
+    ///
+    /// The `store` specified must be the store that this instance lives within
+    /// and `name` is the name of the value to lookup. If the value is found
+    /// `Some` is returned otherwise `None` is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn get_value(&self, mut store: impl AsContextMut, name: &str) -> Option<Val> {
+        self.exports(store.as_context_mut()).root().value(name)
+    }
+
+    /// Returns information about all live resource handles within the
+    /// resource tables of this instance.
+    ///
+    /// This includes both `own` and `borrow` handles which the guest currently
+    /// holds, in no particular order. Handles held by the host, such as
+    /// [`ResourceAny`](crate::component::ResourceAny) values, aren't included.
+    ///
+    /// This is intended for debugging, for example to track down a guest which
+    /// never drops the handles it's given. See also
+    /// [`Store::component_resource_handles`](crate::Store::component_resource_handles).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn resource_handles(&self, store: impl AsContext) -> Vec<ResourceHandleInfo> {
+        let store = store.as_context();
+        store.0[self.0].as_ref().unwrap().resource_handles()
+    }
+}
+
+/// Returns the live resource handles of all component instances within
+/// `store`.
+///
+/// Instances which are currently being instantiated, or whose exports are
+/// being looked up, are skipped.
+pub(crate) fn store_resource_handles(
+    store: &StoreOpaque,
+) -> Vec<(Instance, Vec<ResourceHandleInfo>)> {
+    store
+        .store_data()
+        .iter::<Option<Box<InstanceData>>>()
+        .filter_map(|i| {
+            let data = store[i].as_ref()?;
+            Some((Instance(i), data.resource_handles()))
+        })
+        .collect()
+}
+
+/// Emits a `tracing` warning for every component instance within `store` which still has
+/// `own` handles in its resource tables.
+///
+/// This is called when a store is dropped and
+/// [`Config::component_resource_leak_reporting`](crate::Config::component_resource_leak_reporting)
+/// is enabled.
+pub(crate) fn report_resource_leaks(store: &StoreOpaque) {
+    for (instance, handles) in store_resource_handles(store) {
+        let leaked = handles.iter().filter(|h| h.owned()).collect::<Vec<_>>();
+        if leaked.is_empty() {
+            continue;
+        }
+        tracing::warn!(
+            "component instance {} dropped with {} outstanding owned resource handle(s): {:?}",
+            instance.0.index(),
+            leaked.len(),
+            leaked,
+        );
+    }
 }
 
 impl InstanceData {
@@ -194,6 +280,16 @@ impl InstanceData {
         instance.get_export_by_index(idx)
     }
 
+    fn value(&self, def: &ValueDef) -> &Val {
+        match def {
+            ValueDef::Import(idx) => match &self.imports[*idx] {
+                RuntimeImport::Value(val) => val,
+                _ => unreachable!(),
+            },
+            ValueDef::Start(idx) => &self.values[*idx],
+        }
+    }
+
     #[inline]
     pub fn instance(&self) -> &ComponentInstance {
         &self.state
@@ -209,11 +305,34 @@ impl InstanceData {
         self.component.types()
     }
 
+    #[inline]
+    pub fn auto_post_return(&self) -> bool {
+        self.auto_post_return
+    }
+
     #[inline]
     pub fn ty(&self) -> InstanceType<'_> {
         InstanceType::new(self.instance())
     }
 
+    /// Returns the type of this instance's component with resource types
+    /// resolved to those of this instantiation.
+    pub fn component_type(&self) -> types::Component {
+        types::Component::from(self.component.env_component().ty, &self.ty())
+    }
+
+    fn resource_handles(&self) -> Vec<ResourceHandleInfo> {
+        let ty = self.ty();
+        self.instance()
+            .component_resource_tables_ref()
+            .iter()
+            .flat_map(|(idx, table)| {
+                let ty = ty.resource_type(idx);
+                table.handles().map(move |h| ResourceHandleInfo::new(ty, h))
+            })
+            .collect()
+    }
+
     // NB: This method is only intended to be called during the instantiation
     // process because the `Arc::get_mut` here is fallible and won't generally
     // succeed once the instance has been handed to the embedder. Before that
@@ -237,6 +356,7 @@ struct Instantiator<'a> {
 pub(crate) enum RuntimeImport {
     Func(Arc<HostFunc>),
     Module(Module),
+    Value(Val),
     Resource {
         ty: ResourceType,
 
@@ -283,11 +403,24 @@ impl<'a> Instantiator<'a> {
                     store.traitobj(),
                 ),
                 imports: imports.clone(),
+                values: PrimaryMap::with_capacity(env_component.num_runtime_values as usize),
+                auto_post_return: false,
             },
         }
     }
 
-    fn run<T>(&mut self, store: &mut StoreContextMut<'_, T>) -> Result<()> {
+    /// Runs all initializers of this component, returning the final state of
+    /// the instance.
+    ///
+    /// The `instance` provided is the reserved slot in the store that the
+    /// instance will be stored at. The in-progress state is temporarily placed
+    /// there while `start` functions are invoked since they need to be able to
+    /// refer to the instance.
+    fn run<T>(
+        mut self,
+        store: &mut StoreContextMut<'_, T>,
+        instance: Instance,
+    ) -> Result<InstanceData> {
         let env_component = self.component.env_component();
 
         // Before all initializers are processed configure all destructors for
@@ -391,8 +524,50 @@ impl<'a> Instantiator<'a> {
                 }
 
                 GlobalInitializer::Resource(r) => self.resource(store.0, r),
+
+                GlobalInitializer::Start(start) => {
+                    store.0[instance.0] = Some(Box::new(self.data));
+                    let result = Self::start(store, instance, start);
+                    self.data = *store.0[instance.0].take().unwrap();
+                    result?;
+                }
             }
         }
+        Ok(self.data)
+    }
+
+    fn start<T>(
+        store: &mut StoreContextMut<'_, T>,
+        instance: Instance,
+        start: &Start,
+    ) -> Result<()> {
+        // Note that, like `Exports`, the data is taken out of the store while
+        // the function is created to appease the borrow checker. It's put back
+        // before the function is actually invoked though.
+        let data = store.0[instance.0].take().unwrap();
+        let func = Func::from_lifted_func(
+            store.0,
+            &instance,
+            &data,
+            start.ty,
+            &start.func,
+            &start.options,
+        );
+        let args = start
+            .args
+            .iter()
+            .map(|arg| data.value(arg).clone())
+            .collect::<Vec<_>>();
+        store.0[instance.0] = Some(data);
+
+        let mut results = vec![Val::Bool(false); start.results as usize];
+        func.call_impl(&mut *store, &args, &mut results)?;
+        func.post_return_impl(&mut *store)?;
+
+        let data = store.0[instance.0].as_mut().unwrap();
+        for result in results {
+            data.values.push(result);
+        }
         Ok(())
     }
 
@@ -520,6 +695,7 @@ impl<'a> Instantiator<'a> {
 pub struct InstancePre<T> {
     component: Component,
     imports: Arc<PrimaryMap<RuntimeImportIndex, RuntimeImport>>,
+    auto_post_return: bool,
     _marker: marker::PhantomData<fn() -> T>,
 }
 
@@ -529,6 +705,7 @@ impl<T> Clone for InstancePre<T> {
         Self {
             component: self.component.clone(),
             imports: self.imports.clone(),
+            auto_post_return: self.auto_post_return,
             _marker: self._marker,
         }
     }
@@ -544,10 +721,12 @@ impl<T> InstancePre<T> {
     pub(crate) unsafe fn new_unchecked(
         component: Component,
         imports: PrimaryMap<RuntimeImportIndex, RuntimeImport>,
+        auto_post_return: bool,
     ) -> InstancePre<T> {
         InstancePre {
             component,
             imports: Arc::new(imports),
+            auto_post_return,
             _marker: marker::PhantomData,
         }
     }
@@ -595,16 +774,17 @@ impl<T> InstancePre<T> {
             .engine()
             .allocator()
             .increment_component_instance_count()?;
-        let mut instantiator = Instantiator::new(&self.component, store.0, &self.imports);
-        instantiator.run(&mut store).map_err(|e| {
+        let instantiator = Instantiator::new(&self.component, store.0, &self.imports);
+        let instance = Instance(store.0.store_data_mut().insert(None));
+        let mut data = instantiator.run(&mut store, instance).map_err(|e| {
             store
                 .engine()
                 .allocator()
                 .decrement_component_instance_count();
             e
         })?;
-        let data = Box::new(instantiator.data);
-        let instance = Instance(store.0.store_data_mut().insert(Some(data)));
+        data.auto_post_return = self.auto_post_return;
+        store.0[instance.0] = Some(Box::new(data));
         store.0.push_component_instance(instance);
         Ok(instance)
     }
@@ -698,7 +878,8 @@ impl<'a, 'store> ExportInstance<'a, 'store> {
             Export::ModuleStatic(_)
             | Export::ModuleImport(_)
             | Export::Instance(_)
-            | Export::Type(_) => None,
+            | Export::Type(_)
+            | Export::Value(_) => None,
         }
     }
 
@@ -736,7 +917,28 @@ impl<'a, 'store> ExportInstance<'a, 'store> {
             | Export::LiftedFunction { .. }
             | Export::ModuleStatic(_)
             | Export::ModuleImport(_)
-            | Export::Instance(_) => None,
+            | Export::Instance(_)
+            | Export::Value(_) => None,
+        }
+    }
+
+    /// Same as [`Instance::get_value`]
+    pub fn value(&mut self, name: &str) -> Option<Val> {
+        match self.exports.get(name)? {
+            Export::Value(def) => Some(self.data.value(def).clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the destructor of the resource type exported as `name`.
+    pub(crate) fn resource_dtor(&self, name: &str) -> Option<ResourceDtor> {
+        match self.exports.get(name)? {
+            Export::Type(TypeDef::Resource(id)) => Some(ResourceDtor::new(
+                self.store.id(),
+                self.data.instance(),
+                *id,
+            )),
+            _ => None,
         }
     }
 
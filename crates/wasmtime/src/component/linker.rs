@@ -1,19 +1,23 @@
 use crate::component::func::HostFunc;
 use crate::component::instance::RuntimeImport;
 use crate::component::matching::TypeChecker;
+use crate::component::types::ComponentItem;
 use crate::component::{
-    Component, ComponentNamedList, Instance, InstancePre, Lift, Lower, ResourceType, Val,
+    Component, ComponentNamedList, ExportInstance, Instance, InstancePre, Lift, Lower,
+    ResourceType, Val,
 };
+use crate::store::StoreId;
 use crate::{AsContextMut, Engine, Module, StoreContextMut};
 use anyhow::{anyhow, bail, Context, Result};
 use indexmap::IndexMap;
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
 use std::future::Future;
 use std::marker;
 use std::ops::Deref;
 use std::pin::Pin;
 use std::sync::Arc;
-use wasmtime_environ::component::TypeDef;
+use wasmtime_environ::component::{ResourceIndex, TypeDef};
 use wasmtime_environ::PrimaryMap;
 
 /// A type used to instantiate [`Component`]s.
@@ -28,6 +32,7 @@ pub struct Linker<T> {
     map: NameMap,
     path: Vec<usize>,
     allow_shadowing: bool,
+    auto_post_return: bool,
     _marker: marker::PhantomData<fn() -> T>,
 }
 
@@ -39,6 +44,7 @@ impl<T> Clone for Linker<T> {
             map: self.map.clone(),
             path: self.path.clone(),
             allow_shadowing: self.allow_shadowing,
+            auto_post_return: self.auto_post_return,
             _marker: self._marker,
         }
     }
@@ -73,6 +79,7 @@ pub(crate) enum Definition {
     Func(Arc<HostFunc>),
     Module(Module),
     Resource(ResourceType, Arc<crate::func::HostFunc>),
+    Value(Val),
 }
 
 impl<T> Linker<T> {
@@ -84,6 +91,7 @@ impl<T> Linker<T> {
             strings: Strings::default(),
             map: NameMap::default(),
             allow_shadowing: false,
+            auto_post_return: true,
             path: Vec::new(),
             _marker: marker::PhantomData,
         }
@@ -103,6 +111,30 @@ impl<T> Linker<T> {
         self
     }
 
+    /// Configures whether bindings generated by
+    /// [`bindgen!`](crate::component::bindgen) automatically run post-return
+    /// after each call into instances created by this linker.
+    ///
+    /// When this is enabled, which is the default, the generated `call_*`
+    /// methods invoke the export's `post_return` right after the call, as the
+    /// results they return are fully owned by the host. When this is disabled
+    /// the generated methods only call the export, and the embedder is
+    /// responsible for invoking [`Func::post_return`] on the export before
+    /// the instance is entered again.
+    ///
+    /// This doesn't affect [`Func::call`] or [`TypedFunc::call`], which always
+    /// require a separate call to `post_return`, see
+    /// [`TypedFunc::call_and_post_return`] for a convenience which does both.
+    ///
+    /// [`Func::post_return`]: crate::component::Func::post_return
+    /// [`Func::call`]: crate::component::Func::call
+    /// [`TypedFunc::call`]: crate::component::TypedFunc::call
+    /// [`TypedFunc::call_and_post_return`]: crate::component::TypedFunc::call_and_post_return
+    pub fn auto_post_return(&mut self, enable: bool) -> &mut Self {
+        self.auto_post_return = enable;
+        self
+    }
+
     /// Returns the "root instance" of this linker, used to define names into
     /// the root namespace.
     pub fn root(&mut self) -> LinkerInstance<'_, T> {
@@ -126,6 +158,20 @@ impl<T> Linker<T> {
         self.root().into_instance(name)
     }
 
+    /// Defines the exports of the component `instance` as a new instance named
+    /// `name` within this linker.
+    ///
+    /// This is a convenience for calling [`LinkerInstance::instance_from`] on
+    /// [`Linker::root`], see its documentation for more information.
+    pub fn instance_from(
+        &mut self,
+        store: impl AsContextMut<Data = T>,
+        name: &str,
+        instance: Instance,
+    ) -> Result<()> {
+        self.root().instance_from(store, name, instance)
+    }
+
     /// Performs a "pre-instantiation" to resolve the imports of the
     /// [`Component`] specified with the items defined within this linker.
     ///
@@ -188,6 +234,7 @@ impl<T> Linker<T> {
             let import = match cur {
                 Definition::Module(m) => RuntimeImport::Module(m.clone()),
                 Definition::Func(f) => RuntimeImport::Func(f.clone()),
+                Definition::Value(v) => RuntimeImport::Value(v.clone()),
                 Definition::Resource(t, dtor) => RuntimeImport::Resource {
                     ty: t.clone(),
                     _dtor: dtor.clone(),
@@ -201,7 +248,52 @@ impl<T> Linker<T> {
             let i = imports.push(import);
             assert_eq!(i, idx);
         }
-        Ok(unsafe { InstancePre::new_unchecked(component.clone(), imports) })
+        let auto_post_return = self.auto_post_return;
+        Ok(unsafe { InstancePre::new_unchecked(component.clone(), imports, auto_post_return) })
+    }
+
+    /// Implement any imports of the given [`Component`] with a function which
+    /// traps.
+    ///
+    /// By default a [`Linker`] will error when unknown imports are encountered
+    /// while using [`Linker::instantiate`]. This method walks the imports of
+    /// `component`, including the exports of imported instances, and defines
+    /// every function which isn't already defined in this linker as one which
+    /// returns an error when called. Resources which aren't already defined
+    /// are defined as a placeholder host resource type, and no values of that
+    /// type will ever be created.
+    ///
+    /// Note that imports of other kinds of items, such as core wasm modules,
+    /// are not defined by this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmtime::{Config, Engine, Store};
+    /// # use wasmtime::component::{Component, Linker};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let mut config = Config::new();
+    /// # config.wasm_component_model(true);
+    /// # let engine = Engine::new(&config)?;
+    /// # let component = Component::new(&engine, r#"(component (import "unknown" (func)))"#)?;
+    /// # let mut store = Store::new(&engine, ());
+    /// let mut linker = Linker::new(&engine);
+    /// linker.define_unknown_imports_as_traps(&component)?;
+    /// linker.instantiate(&mut store, &component)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn define_unknown_imports_as_traps(&mut self, component: &Component) -> Result<()> {
+        let dtor = Arc::new(crate::func::HostFunc::wrap(
+            &self.engine,
+            |_cx: crate::Caller<'_, T>, _param: u32| {},
+        ));
+        let mut resources = HashSet::new();
+        let mut root = self.root();
+        for (_, (name, ty)) in component.env_component().import_types.iter() {
+            root.define_unknown_import(component, name, ty, &dtor, &mut resources)?;
+        }
+        Ok(())
     }
 
     /// Instantiates the [`Component`] provided into the `store` specified.
@@ -428,6 +520,16 @@ impl<T> LinkerInstance<'_, T> {
         self.insert(name, Definition::Resource(ResourceType::host::<U>(), dtor))
     }
 
+    /// Defines a component-level value within this instance.
+    ///
+    /// This can be used to satisfy a `(value ...)` import of a component. The
+    /// `value` provided is type-checked against the component's import when
+    /// the component is instantiated.
+    pub fn value(&mut self, name: &str, value: Val) -> Result<()> {
+        let name = self.strings.intern(name);
+        self.insert(name, Definition::Value(value))
+    }
+
     /// Defines a nested instance within this instance.
     ///
     /// This can be used to describe arbitrarily nested levels of instances
@@ -462,6 +564,190 @@ impl<T> LinkerInstance<'_, T> {
         Ok(self)
     }
 
+    /// Defines the exports of the component `instance` as a new nested
+    /// instance named `name`.
+    ///
+    /// This can be used to satisfy the imports of one component with the
+    /// exports of another component which has already been instantiated in
+    /// `store`, composing components at runtime instead of ahead-of-time with
+    /// tools such as `wasm-tools compose`. Every export of `instance` is
+    /// defined within the new instance:
+    ///
+    /// * Functions are defined as host functions which call the exported
+    ///   function, including its `post-return` if any. No fused adapter is
+    ///   compiled between the two components; instead arguments and results
+    ///   are lifted into [`Val`]s by the host and lowered again on the other
+    ///   side. Calls are therefore slower than calls between components
+    ///   composed ahead-of-time, so composing with `wasm-tools compose` is
+    ///   still preferable where performance matters.
+    /// * Resource types are defined as the exported resource type and
+    ///   resources dropped by the importing component are destroyed with the
+    ///   exporting instance's destructor.
+    /// * Nested instances, modules, and values are defined recursively as-is.
+    ///
+    /// Note that items defined here are only valid for use with `store`, and
+    /// functions defined here return an error when called from a component
+    /// instantiated in another store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is already defined within this instance or if
+    /// `store` is configured with async support, which isn't supported yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own `instance`.
+    pub fn instance_from(
+        &mut self,
+        mut store: impl AsContextMut<Data = T>,
+        name: &str,
+        instance: Instance,
+    ) -> Result<()> {
+        let mut store = store.as_context_mut();
+        if store.0.async_support() {
+            bail!("`instance_from` is not supported with async stores");
+        }
+        let ty = store.0[instance.0].as_ref().unwrap().component_type();
+        let store_id = store.0.id();
+        let mut exports = instance.exports(&mut store);
+        let mut linker = self.instance(name)?;
+        linker.define_exports(store_id, exports.root(), ty.exports())
+    }
+
+    fn define_exports<'a>(
+        &mut self,
+        store_id: StoreId,
+        mut exports: ExportInstance<'_, '_>,
+        items: impl Iterator<Item = (&'a str, ComponentItem)>,
+    ) -> Result<()> {
+        for (name, item) in items {
+            match item {
+                ComponentItem::ComponentFunc(ty) => {
+                    let func = exports.func(name).unwrap();
+                    let func = HostFunc::new_dynamic_with_type(
+                        move |mut store: StoreContextMut<'_, T>,
+                              params: &[Val],
+                              results: &mut [Val]| {
+                            if store.0.id() != store_id {
+                                bail!("function from `instance_from` used with a different store");
+                            }
+                            func.call(&mut store, params, results)?;
+                            func.post_return(&mut store)?;
+                            for param in params {
+                                drop_borrows(&mut store, param)?;
+                            }
+                            Ok(())
+                        },
+                        ty,
+                    );
+                    let name = self.strings.intern(name);
+                    self.insert(name, Definition::Func(func))?;
+                }
+                ComponentItem::ComponentInstance(ty) => {
+                    let nested = exports.instance(name).unwrap();
+                    self.instance(name)?
+                        .define_exports(store_id, nested, ty.exports())?;
+                }
+                ComponentItem::Resource(ty) => {
+                    let dtor = exports.resource_dtor(name).unwrap();
+                    let dtor = Arc::new(crate::func::HostFunc::wrap(
+                        self.engine,
+                        move |mut cx: crate::Caller<'_, T>, rep: u32| {
+                            dtor.call(&mut cx.as_context_mut(), rep)
+                        },
+                    ));
+                    let name = self.strings.intern(name);
+                    self.insert(name, Definition::Resource(ty, dtor))?;
+                }
+                ComponentItem::Module(_) => self.module(name, exports.module(name).unwrap())?,
+                ComponentItem::Value(_) => self.value(name, exports.value(name).unwrap())?,
+
+                // Types other than resources don't need a definition, and
+                // components, as well as core functions, can't be exported
+                // from a component instance.
+                ComponentItem::Type(_)
+                | ComponentItem::Component(_)
+                | ComponentItem::CoreFunc(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn define_unknown_import(
+        &mut self,
+        component: &Component,
+        name: &str,
+        ty: &TypeDef,
+        dtor: &Arc<crate::func::HostFunc>,
+        resources: &mut HashSet<ResourceIndex>,
+    ) -> Result<()> {
+        let key = self.strings.intern(name);
+        let defined = self.map.contains_key(&key);
+        match *ty {
+            TypeDef::ComponentFunc(index) => {
+                if defined {
+                    return Ok(());
+                }
+                let path = self.path[..self.path_len]
+                    .iter()
+                    .map(|i| self.strings.strings[*i].deref())
+                    .chain([name])
+                    .collect::<Vec<_>>()
+                    .join("#");
+                let func = HostFunc::new_dynamic::<T, _>(
+                    move |_, _, _| bail!("unknown import: `{path}` has not been defined"),
+                    index,
+                    component.types(),
+                );
+                self.insert(key, Definition::Func(func))
+            }
+            TypeDef::ComponentInstance(index) => {
+                if !defined {
+                    self.insert(key, Definition::Instance(NameMap::default()))?;
+                }
+                self.path.truncate(self.path_len);
+                self.path.push(key);
+                let map = match self.map.get_mut(&key) {
+                    Some(Definition::Instance(map)) => map,
+                    // Other definitions are left as-is to be reported as a type
+                    // error during instantiation.
+                    _ => return Ok(()),
+                };
+                let mut instance = LinkerInstance {
+                    engine: self.engine,
+                    path: self.path,
+                    path_len: self.path_len + 1,
+                    strings: self.strings,
+                    map,
+                    allow_shadowing: self.allow_shadowing,
+                    _marker: self._marker,
+                };
+                for (name, ty) in component.types()[index].exports.iter() {
+                    instance.define_unknown_import(component, name, ty, dtor, resources)?;
+                }
+                Ok(())
+            }
+            TypeDef::Resource(index) => {
+                // Only the first import of a resource needs a definition, any
+                // later imports of the same resource are `(eq ...)` imports
+                // which are already satisfied.
+                let resource = component.types()[index].ty;
+                if !resources.insert(resource) || defined {
+                    return Ok(());
+                }
+                self.insert(
+                    key,
+                    Definition::Resource(ResourceType::host::<UnknownImport>(), dtor.clone()),
+                )
+            }
+            TypeDef::Module(_)
+            | TypeDef::Component(_)
+            | TypeDef::Interface(_)
+            | TypeDef::Value(_)
+            | TypeDef::CoreFunc(_) => Ok(()),
+        }
+    }
+
     fn insert(&mut self, key: usize, item: Definition) -> Result<()> {
         match self.map.entry(key) {
             Entry::Occupied(_) if !self.allow_shadowing => {
@@ -478,6 +764,50 @@ impl<T> LinkerInstance<'_, T> {
     }
 }
 
+/// Drops all of the `borrow` handles within `val`, which the host received as
+/// an argument of a function defined by [`LinkerInstance::instance_from`].
+fn drop_borrows<T>(store: &mut StoreContextMut<'_, T>, val: &Val) -> Result<()> {
+    match val {
+        Val::Resource(resource) if !resource.owned() => resource.resource_drop(&mut *store)?,
+        Val::List(list) => {
+            for val in list.iter() {
+                drop_borrows(store, val)?;
+            }
+        }
+        Val::Record(record) => {
+            for (_, val) in record.fields() {
+                drop_borrows(store, val)?;
+            }
+        }
+        Val::Tuple(tuple) => {
+            for val in tuple.values() {
+                drop_borrows(store, val)?;
+            }
+        }
+        Val::Variant(variant) => {
+            if let Some(val) = variant.payload() {
+                drop_borrows(store, val)?;
+            }
+        }
+        Val::Option(option) => {
+            if let Some(val) = option.value() {
+                drop_borrows(store, val)?;
+            }
+        }
+        Val::Result(result) => {
+            if let Ok(Some(val)) | Err(Some(val)) = result.value() {
+                drop_borrows(store, val)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Placeholder host resource type used by
+/// [`Linker::define_unknown_imports_as_traps`].
+struct UnknownImport;
+
 impl Strings {
     fn intern(&mut self, string: &str) -> usize {
         if let Some(idx) = self.string2idx.get(string) {
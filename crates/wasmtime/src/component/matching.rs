@@ -1,14 +1,14 @@
 use crate::component::func::HostFunc;
 use crate::component::linker::{Definition, NameMap, Strings};
-use crate::component::ResourceType;
+use crate::component::{ResourceType, Type, Val};
 use crate::types::matching;
 use crate::Module;
 use anyhow::{anyhow, bail, Context, Result};
 use std::any::Any;
 use std::sync::Arc;
 use wasmtime_environ::component::{
-    ComponentTypes, ResourceIndex, TypeComponentInstance, TypeDef, TypeFuncIndex, TypeModule,
-    TypeResourceTableIndex,
+    ComponentTypes, InterfaceType, ResourceIndex, TypeComponentInstance, TypeDef, TypeFuncIndex,
+    TypeModule, TypeResourceTableIndex,
 };
 use wasmtime_environ::PrimaryMap;
 use wasmtime_runtime::component::ComponentInstance;
@@ -49,6 +49,10 @@ impl TypeChecker<'_> {
             },
             TypeDef::Component(_) => bail!("expected component found {}", desc(actual)),
             TypeDef::Interface(_) => bail!("expected type found {}", desc(actual)),
+            TypeDef::Value(t) => match actual {
+                Some(Definition::Value(actual)) => self.value(&t, actual),
+                _ => bail!("expected value found {}", desc(actual)),
+            },
 
             TypeDef::Resource(i) => {
                 let i = self.types[i].ty;
@@ -159,6 +163,14 @@ impl TypeChecker<'_> {
         Ok(())
     }
 
+    fn value(&self, expected: &InterfaceType, actual: &Val) -> Result<()> {
+        let instance_type = InstanceType {
+            types: self.types,
+            resources: &self.imported_resources,
+        };
+        Type::from(expected, &instance_type).check(actual)
+    }
+
     fn func(&self, expected: TypeFuncIndex, actual: &HostFunc) -> Result<()> {
         let instance_type = InstanceType {
             types: self.types,
@@ -182,6 +194,7 @@ impl Definition {
             Definition::Func(_) => "func",
             Definition::Instance(_) => "instance",
             Definition::Resource(..) => "resource",
+            Definition::Value(_) => "value",
         }
     }
 }
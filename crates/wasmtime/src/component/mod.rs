@@ -15,13 +15,16 @@ mod storage;
 mod store;
 pub mod types;
 mod values;
+#[cfg(feature = "serde")]
+mod values_serde;
 pub use self::component::Component;
 pub use self::func::{
     ComponentNamedList, ComponentType, Func, Lift, Lower, TypedFunc, WasmList, WasmStr,
 };
+pub(crate) use self::instance::{report_resource_leaks, store_resource_handles};
 pub use self::instance::{ExportInstance, Exports, Instance, InstancePre};
 pub use self::linker::{Linker, LinkerInstance};
-pub use self::resources::{Resource, ResourceAny};
+pub use self::resources::{Resource, ResourceAny, ResourceHandleInfo};
 pub use self::types::{ResourceType, Type};
 pub use self::values::{Enum, Flags, List, OptionVal, Record, ResultVal, Tuple, Val, Variant};
 pub use wasmtime_component_macro::{flags, ComponentType, Lift, Lower};
@@ -342,6 +345,19 @@ pub(crate) use self::store::ComponentStoreData;
 ///         "wasi:random/random": some::other::wasi::random::random,
 ///         "wasi:filesystem/types/descriptor": MyDescriptorType,
 ///     },
+///
+///     // Generate a struct, here named `Forward`, in the module of an
+///     // imported interface which implements its `Host` trait by calling the
+///     // same interface exported from an instance in another `Store`. This is
+///     // useful for interposing on calls between components, for example to
+///     // log or filter them.
+///     //
+///     // The interface must be both imported and exported by the world, in
+///     // which case the exported interface reuses the types of the import.
+///     // Interfaces using resources or trappable errors can't be forwarded.
+///     forward: {
+///         "my:logging/logger": Forward,
+///     },
 /// });
 /// ```
 ///
@@ -9,8 +9,13 @@ use std::fmt;
 use std::marker;
 use std::mem::MaybeUninit;
 use std::sync::atomic::{AtomicU32, Ordering::Relaxed};
-use wasmtime_environ::component::{CanonicalAbiInfo, DefinedResourceIndex, InterfaceType};
-use wasmtime_runtime::component::{ComponentInstance, InstanceFlags, ResourceTables};
+use wasmtime_environ::component::{
+    CanonicalAbiInfo, ComponentTypes, DefinedResourceIndex, InterfaceType, ResourceIndex,
+    TypeResourceTableIndex,
+};
+use wasmtime_runtime::component::{
+    ComponentInstance, InstanceFlags, ResourceHandle, ResourceTables,
+};
 use wasmtime_runtime::{SendSyncPtr, VMFuncRef, ValRaw};
 
 /// Representation of a resource type in the component model.
@@ -65,6 +70,15 @@ impl ResourceType {
             },
         }
     }
+
+    pub(crate) fn uninstantiated(types: &ComponentTypes, index: ResourceIndex) -> ResourceType {
+        ResourceType {
+            kind: ResourceTypeKind::Uninstantiated {
+                component: types as *const _ as usize,
+                index,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -78,6 +92,14 @@ enum ResourceTypeKind {
         instance: usize,
         id: DefinedResourceIndex,
     },
+    Uninstantiated {
+        // Like `instance` above this is a pointer, here to the
+        // `ComponentTypes` of the component this resource belongs to. This
+        // represents the abstract type of a resource imported or defined by a
+        // component before it's been instantiated.
+        component: usize,
+        index: ResourceIndex,
+    },
 }
 
 /// A host-defined resource in the component model.
@@ -578,32 +600,7 @@ impl ResourceAny {
             "wrong store used to destroy resource"
         );
 
-        // Implement the reentrance check required by the canonical ABI. Note
-        // that this happens whether or not a destructor is present.
-        //
-        // Note that this should be safe because the raw pointer access in
-        // `flags` is valid due to `store` being the owner of the flags and
-        // flags are never destroyed within the store.
-        if let Some(flags) = state.flags {
-            unsafe {
-                if !flags.may_enter() {
-                    bail!(Trap::CannotEnterComponent);
-                }
-            }
-        }
-
-        let dtor = match state.dtor {
-            Some(dtor) => dtor.as_non_null(),
-            None => return Ok(()),
-        };
-        let mut args = [ValRaw::u32(rep)];
-
-        // This should be safe because `dtor` has been checked to belong to the
-        // `store` provided which means it's valid and still alive. Additionally
-        // destructors have al been previously type-checked and are guaranteed
-        // to take one i32 argument and return no results, so the parameters
-        // here should be configured correctly.
-        unsafe { crate::Func::call_unchecked_raw(store, dtor, args.as_mut_ptr(), args.len()) }
+        state.run_dtor(store, rep)
     }
 
     fn lower_to_index<U>(&self, cx: &mut LowerContext<'_, U>, ty: InterfaceType) -> Result<u32> {
@@ -704,6 +701,77 @@ unsafe impl Lift for ResourceAny {
     }
 }
 
+impl OwnState {
+    /// Runs the destructor, if any, for the resource `rep` whose ownership has
+    /// already been relinquished.
+    ///
+    /// The caller must have already checked that `store` is the store this
+    /// state belongs to.
+    fn run_dtor<T>(&self, store: &mut StoreContextMut<'_, T>, rep: u32) -> Result<()> {
+        debug_assert_eq!(store.0.id(), self.store);
+
+        // Implement the reentrance check required by the canonical ABI. Note
+        // that this happens whether or not a destructor is present.
+        //
+        // Note that this should be safe because the raw pointer access in
+        // `flags` is valid due to `store` being the owner of the flags and
+        // flags are never destroyed within the store.
+        if let Some(flags) = self.flags {
+            unsafe {
+                if !flags.may_enter() {
+                    bail!(Trap::CannotEnterComponent);
+                }
+            }
+        }
+
+        let dtor = match self.dtor {
+            Some(dtor) => dtor.as_non_null(),
+            None => return Ok(()),
+        };
+        let mut args = [ValRaw::u32(rep)];
+
+        // This should be safe because `dtor` has been checked to belong to the
+        // `store` provided which means it's valid and still alive. Additionally
+        // destructors have al been previously type-checked and are guaranteed
+        // to take one i32 argument and return no results, so the parameters
+        // here should be configured correctly.
+        unsafe { crate::Func::call_unchecked_raw(store, dtor, args.as_mut_ptr(), args.len()) }
+    }
+}
+
+/// The destructor of a resource type exported from a component instance.
+///
+/// This is used by [`LinkerInstance::instance_from`] to destroy resources
+/// owned by a component importing the resource type, which only knows the
+/// resource's representation.
+///
+/// [`LinkerInstance::instance_from`]: crate::component::LinkerInstance::instance_from
+#[derive(Copy, Clone)]
+pub(crate) struct ResourceDtor(OwnState);
+
+impl ResourceDtor {
+    pub(crate) fn new(
+        store: StoreId,
+        instance: &ComponentInstance,
+        ty: TypeResourceTableIndex,
+    ) -> ResourceDtor {
+        let (dtor, flags) = instance.dtor_and_flags(ty);
+        ResourceDtor(OwnState {
+            store,
+            flags,
+            dtor: dtor.map(SendSyncPtr::new),
+        })
+    }
+
+    /// Runs this destructor for the resource `rep`.
+    pub(crate) fn call<T>(&self, store: &mut StoreContextMut<'_, T>, rep: u32) -> Result<()> {
+        if store.0.id() != self.0.store {
+            bail!("resource type from `instance_from` used with a different store");
+        }
+        self.0.run_dtor(store, rep)
+    }
+}
+
 impl fmt::Debug for OwnState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("OwnState")
@@ -723,3 +791,55 @@ impl PartialEq for OwnState {
 }
 
 impl Eq for OwnState {}
+
+/// Information about a live resource handle within the resource tables of a
+/// component instance.
+///
+/// This is returned by [`Instance::resource_handles`] and
+/// [`Store::component_resource_handles`] and is intended for introspection
+/// and debugging, for example to find handles which a guest never dropped.
+///
+/// [`Instance::resource_handles`]: crate::component::Instance::resource_handles
+/// [`Store::component_resource_handles`]: crate::Store::component_resource_handles
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ResourceHandleInfo {
+    ty: ResourceType,
+    handle: ResourceHandle,
+}
+
+impl ResourceHandleInfo {
+    pub(crate) fn new(ty: ResourceType, handle: ResourceHandle) -> ResourceHandleInfo {
+        ResourceHandleInfo { ty, handle }
+    }
+
+    /// Returns the type of the resource that this handle points to.
+    pub fn ty(&self) -> ResourceType {
+        self.ty
+    }
+
+    /// Returns the index of this handle within its table, which is the
+    /// 32-bit integer the guest uses to refer to it.
+    pub fn index(&self) -> u32 {
+        self.handle.idx
+    }
+
+    /// Returns the representation of the resource that this handle points
+    /// to, as passed to `resource.new` by the component defining it.
+    pub fn rep(&self) -> u32 {
+        self.handle.rep
+    }
+
+    /// Returns whether this is an `own` handle, and if not it's a `borrow`
+    /// handle.
+    pub fn owned(&self) -> bool {
+        self.handle.own
+    }
+
+    /// Returns the number of `borrow` handles currently lent out from this
+    /// `own` handle.
+    ///
+    /// This is always zero for `borrow` handles.
+    pub fn lend_count(&self) -> u32 {
+        self.handle.lend_count
+    }
+}
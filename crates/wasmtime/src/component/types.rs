@@ -1,16 +1,21 @@
 //! This module defines the `Type` type, representing the dynamic form of a component interface type.
+//!
+//! Additionally the types of components themselves, and the items they import
+//! and export, are defined here such as [`Component`] and [`ComponentItem`].
 
 use crate::component::matching::InstanceType;
 use crate::component::values::{self, Val};
+use crate::{ExternType, FuncType};
 use anyhow::{anyhow, Result};
 use std::fmt;
 use std::mem;
 use std::ops::Deref;
 use std::sync::Arc;
 use wasmtime_environ::component::{
-    CanonicalAbiInfo, ComponentTypes, InterfaceType, ResourceIndex, TypeEnumIndex, TypeFlagsIndex,
-    TypeListIndex, TypeOptionIndex, TypeRecordIndex, TypeResourceTableIndex, TypeResultIndex,
-    TypeTupleIndex, TypeVariantIndex,
+    CanonicalAbiInfo, ComponentTypes, InterfaceType, ResourceIndex, TypeComponentIndex,
+    TypeComponentInstanceIndex, TypeDef, TypeEnumIndex, TypeFlagsIndex, TypeFuncIndex,
+    TypeListIndex, TypeModuleIndex, TypeOptionIndex, TypeRecordIndex, TypeResourceTableIndex,
+    TypeResultIndex, TypeTupleIndex, TypeVariantIndex,
 };
 use wasmtime_environ::PrimaryMap;
 
@@ -242,6 +247,12 @@ impl TypeChecker<'_> {
         let b = &self.b_types[f2];
         a.names == b.names
     }
+
+    fn funcs_equal(&self, f1: TypeFuncIndex, f2: TypeFuncIndex) -> bool {
+        let a = &self.a_types[f1];
+        let b = &self.b_types[f2];
+        self.tuples_equal(a.params, b.params) && self.tuples_equal(a.results, b.results)
+    }
 }
 
 /// A `list` interface type
@@ -675,6 +686,39 @@ impl Type {
         }
     }
 
+    /// Deserializes a [`Val`] of this type from `deserializer`.
+    ///
+    /// This is the inverse of the [`Serialize`](serde::Serialize)
+    /// implementation for [`Val`], and the shape of the input is driven by
+    /// this type:
+    ///
+    /// * `bool`, integers, floats, `char`, and `string` use the corresponding
+    ///   serde primitives.
+    /// * `list` and `tuple` are sequences.
+    /// * `record` is a map from field names to values.
+    /// * `flags` is a sequence of the names of the flags which are set.
+    /// * `enum` is the name of its case as a string.
+    /// * `variant` cases are tagged by name: a case without a payload is the
+    ///   name as a string, such as `"a"`, and a case with a payload is a map
+    ///   with a single entry from the name to the payload, such as
+    ///   `{"b": 1}`.
+    /// * `option` and `result` use the same form as `variant` with the cases
+    ///   `none`/`some` and `ok`/`err`, such as `"none"`, `{"some": 1}` or
+    ///   `"ok"`.
+    ///
+    /// Cases are distinguished by looking at the input, so this requires a
+    /// self-describing format such as JSON.
+    ///
+    /// Resources can't be deserialized and produce an error.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "serde")))]
+    pub fn deserialize_val<'de, D>(&self, deserializer: D) -> Result<Val, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::de::DeserializeSeed::deserialize(self, deserializer)
+    }
+
     pub(crate) fn check(&self, value: &Val) -> Result<()> {
         let other = &value.ty();
         if self == other {
@@ -750,3 +794,177 @@ impl Type {
         }
     }
 }
+
+/// The type of a component function.
+#[derive(Clone, Debug)]
+pub struct ComponentFunc(Handle<TypeFuncIndex>);
+
+impl ComponentFunc {
+    pub(crate) fn from(index: TypeFuncIndex, ty: &InstanceType<'_>) -> Self {
+        ComponentFunc(Handle::new(index, ty))
+    }
+
+    /// Iterates over the types of the parameters of this function.
+    pub fn params(&self) -> impl ExactSizeIterator<Item = Type> + '_ {
+        let params = self.0.types[self.0.index].params;
+        self.0.types[params]
+            .types
+            .iter()
+            .map(|ty| Type::from(ty, &self.0.instance()))
+    }
+
+    /// Iterates over the types of the results of this function.
+    pub fn results(&self) -> impl ExactSizeIterator<Item = Type> + '_ {
+        let results = self.0.types[self.0.index].results;
+        self.0.types[results]
+            .types
+            .iter()
+            .map(|ty| Type::from(ty, &self.0.instance()))
+    }
+}
+
+impl PartialEq for ComponentFunc {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.equivalent(&other.0, TypeChecker::funcs_equal)
+    }
+}
+
+impl Eq for ComponentFunc {}
+
+/// The type of a core WebAssembly module within a component.
+#[derive(Clone, Debug)]
+pub struct Module(Handle<TypeModuleIndex>);
+
+impl Module {
+    pub(crate) fn from(index: TypeModuleIndex, ty: &InstanceType<'_>) -> Self {
+        Module(Handle::new(index, ty))
+    }
+
+    /// Iterates over the imports of this module, as pairs of the module and
+    /// field name along with the type of the import.
+    pub fn imports(&self) -> impl ExactSizeIterator<Item = ((&str, &str), ExternType)> + '_ {
+        self.0.types[self.0.index]
+            .imports
+            .iter()
+            .map(|((module, name), ty)| {
+                (
+                    (module.as_str(), name.as_str()),
+                    ExternType::from_wasmtime(self.0.types.module_types(), ty),
+                )
+            })
+    }
+
+    /// Iterates over the exports of this module.
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = (&str, ExternType)> + '_ {
+        self.0.types[self.0.index].exports.iter().map(|(name, ty)| {
+            (
+                name.as_str(),
+                ExternType::from_wasmtime(self.0.types.module_types(), ty),
+            )
+        })
+    }
+}
+
+/// The type of a component, in terms of what it imports and exports.
+#[derive(Clone, Debug)]
+pub struct Component(Handle<TypeComponentIndex>);
+
+impl Component {
+    pub(crate) fn from(index: TypeComponentIndex, ty: &InstanceType<'_>) -> Self {
+        Component(Handle::new(index, ty))
+    }
+
+    /// Returns the type of the import named `name`, if any.
+    pub fn get_import(&self, name: &str) -> Option<ComponentItem> {
+        let ty = self.0.types[self.0.index].imports.get(name)?;
+        Some(ComponentItem::from(ty, &self.0.instance()))
+    }
+
+    /// Iterates over the imports of this component in declaration order.
+    pub fn imports(&self) -> impl ExactSizeIterator<Item = (&str, ComponentItem)> + '_ {
+        self.0.types[self.0.index]
+            .imports
+            .iter()
+            .map(|(name, ty)| (name.as_str(), ComponentItem::from(ty, &self.0.instance())))
+    }
+
+    /// Returns the type of the export named `name`, if any.
+    pub fn get_export(&self, name: &str) -> Option<ComponentItem> {
+        let ty = self.0.types[self.0.index].exports.get(name)?;
+        Some(ComponentItem::from(ty, &self.0.instance()))
+    }
+
+    /// Iterates over the exports of this component in declaration order.
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = (&str, ComponentItem)> + '_ {
+        self.0.types[self.0.index]
+            .exports
+            .iter()
+            .map(|(name, ty)| (name.as_str(), ComponentItem::from(ty, &self.0.instance())))
+    }
+}
+
+/// The type of a component instance, in terms of what it exports.
+#[derive(Clone, Debug)]
+pub struct ComponentInstance(Handle<TypeComponentInstanceIndex>);
+
+impl ComponentInstance {
+    pub(crate) fn from(index: TypeComponentInstanceIndex, ty: &InstanceType<'_>) -> Self {
+        ComponentInstance(Handle::new(index, ty))
+    }
+
+    /// Returns the type of the export named `name`, if any.
+    pub fn get_export(&self, name: &str) -> Option<ComponentItem> {
+        let ty = self.0.types[self.0.index].exports.get(name)?;
+        Some(ComponentItem::from(ty, &self.0.instance()))
+    }
+
+    /// Iterates over the exports of this instance in declaration order.
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = (&str, ComponentItem)> + '_ {
+        self.0.types[self.0.index]
+            .exports
+            .iter()
+            .map(|(name, ty)| (name.as_str(), ComponentItem::from(ty, &self.0.instance())))
+    }
+}
+
+/// The type of an item imported or exported by a component.
+#[derive(Clone, Debug)]
+pub enum ComponentItem {
+    /// A component function.
+    ComponentFunc(ComponentFunc),
+    /// A core WebAssembly function.
+    CoreFunc(FuncType),
+    /// A core WebAssembly module.
+    Module(Module),
+    /// A component.
+    Component(Component),
+    /// An instance of a component.
+    ComponentInstance(ComponentInstance),
+    /// An interface type.
+    Type(Type),
+    /// A component-level value of the specified type.
+    Value(Type),
+    /// A resource type.
+    Resource(ResourceType),
+}
+
+impl ComponentItem {
+    pub(crate) fn from(def: &TypeDef, ty: &InstanceType<'_>) -> Self {
+        match def {
+            TypeDef::Component(index) => ComponentItem::Component(Component::from(*index, ty)),
+            TypeDef::ComponentInstance(index) => {
+                ComponentItem::ComponentInstance(ComponentInstance::from(*index, ty))
+            }
+            TypeDef::ComponentFunc(index) => {
+                ComponentItem::ComponentFunc(ComponentFunc::from(*index, ty))
+            }
+            TypeDef::Interface(iface) => ComponentItem::Type(Type::from(iface, ty)),
+            TypeDef::Value(iface) => ComponentItem::Value(Type::from(iface, ty)),
+            TypeDef::Module(index) => ComponentItem::Module(Module::from(*index, ty)),
+            TypeDef::CoreFunc(index) => {
+                ComponentItem::CoreFunc(FuncType::from_wasm_func_type(ty.types[*index].clone()))
+            }
+            TypeDef::Resource(index) => ComponentItem::Resource(ty.resource_type(*index)),
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Support for converting component values to and from serde's data model.
+//!
+//! See [`Type::deserialize_val`] for a description of the format used here.
+
+use crate::component::types::{self, Type};
+use crate::component::Val;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use serde::ser::{self, SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Serializes this value in the format described by [`Type::deserialize_val`].
+///
+/// Resources can't be serialized and produce an error.
+impl Serialize for Val {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Val::Bool(b) => serializer.serialize_bool(*b),
+            Val::S8(i) => serializer.serialize_i8(*i),
+            Val::U8(i) => serializer.serialize_u8(*i),
+            Val::S16(i) => serializer.serialize_i16(*i),
+            Val::U16(i) => serializer.serialize_u16(*i),
+            Val::S32(i) => serializer.serialize_i32(*i),
+            Val::U32(i) => serializer.serialize_u32(*i),
+            Val::S64(i) => serializer.serialize_i64(*i),
+            Val::U64(i) => serializer.serialize_u64(*i),
+            Val::Float32(f) => serializer.serialize_f32(*f),
+            Val::Float64(f) => serializer.serialize_f64(*f),
+            Val::Char(c) => serializer.serialize_char(*c),
+            Val::String(s) => serializer.serialize_str(s),
+            Val::List(list) => serializer.collect_seq(list.iter()),
+            Val::Tuple(tuple) => serializer.collect_seq(tuple.values()),
+            Val::Record(record) => serializer.collect_map(record.fields()),
+            Val::Variant(variant) => {
+                serialize_case(serializer, variant.discriminant(), variant.payload())
+            }
+            Val::Enum(e) => serializer.serialize_str(e.discriminant()),
+            Val::Option(option) => match option.value() {
+                Some(val) => serialize_case(serializer, "some", Some(val)),
+                None => serialize_case(serializer, "none", None),
+            },
+            Val::Result(result) => match result.value() {
+                Ok(val) => serialize_case(serializer, "ok", val),
+                Err(val) => serialize_case(serializer, "err", val),
+            },
+            Val::Flags(flags) => serializer.collect_seq(flags.flags()),
+            Val::Resource(_) => Err(ser::Error::custom("resources cannot be serialized")),
+        }
+    }
+}
+
+fn serialize_case<S: Serializer>(
+    serializer: S,
+    name: &str,
+    payload: Option<&Val>,
+) -> Result<S::Ok, S::Error> {
+    match payload {
+        Some(val) => {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(name, val)?;
+            map.end()
+        }
+        None => serializer.serialize_str(name),
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &Type {
+    type Value = Val;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Val, D::Error> {
+        Ok(match self {
+            Type::Bool => Val::Bool(bool::deserialize(deserializer)?),
+            Type::S8 => Val::S8(i8::deserialize(deserializer)?),
+            Type::U8 => Val::U8(u8::deserialize(deserializer)?),
+            Type::S16 => Val::S16(i16::deserialize(deserializer)?),
+            Type::U16 => Val::U16(u16::deserialize(deserializer)?),
+            Type::S32 => Val::S32(i32::deserialize(deserializer)?),
+            Type::U32 => Val::U32(u32::deserialize(deserializer)?),
+            Type::S64 => Val::S64(i64::deserialize(deserializer)?),
+            Type::U64 => Val::U64(u64::deserialize(deserializer)?),
+            Type::Float32 => Val::Float32(f32::deserialize(deserializer)?),
+            Type::Float64 => Val::Float64(f64::deserialize(deserializer)?),
+            Type::Char => Val::Char(char::deserialize(deserializer)?),
+            Type::String => Val::String(String::deserialize(deserializer)?.into()),
+            Type::List(ty) => deserializer.deserialize_seq(ListVisitor(ty))?,
+            Type::Tuple(ty) => {
+                deserializer.deserialize_tuple(ty.types().len(), TupleVisitor(ty))?
+            }
+            Type::Record(ty) => deserializer.deserialize_map(RecordVisitor(ty))?,
+            Type::Flags(ty) => {
+                let names = Vec::<String>::deserialize(deserializer)?;
+                let names = names.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                ty.new_val(&names).map_err(D::Error::custom)?
+            }
+            Type::Enum(ty) => {
+                let (name, _) = deserializer.deserialize_any(CaseVisitor {
+                    expecting: "an enum case name",
+                    case: |name: &str| ty.names().any(|n| n == name).then_some(None),
+                })?;
+                ty.new_val(&name).map_err(D::Error::custom)?
+            }
+            Type::Variant(ty) => {
+                let (name, payload) = deserializer.deserialize_any(CaseVisitor {
+                    expecting: "a variant case",
+                    case: |name: &str| ty.cases().find(|c| c.name == name).map(|c| c.ty),
+                })?;
+                ty.new_val(&name, payload).map_err(D::Error::custom)?
+            }
+            Type::Option(ty) => {
+                let (_, payload) = deserializer.deserialize_any(CaseVisitor {
+                    expecting: "`none` or `some`",
+                    case: |name: &str| match name {
+                        "none" => Some(None),
+                        "some" => Some(Some(ty.ty())),
+                        _ => None,
+                    },
+                })?;
+                ty.new_val(payload).map_err(D::Error::custom)?
+            }
+            Type::Result(ty) => {
+                let (name, payload) = deserializer.deserialize_any(CaseVisitor {
+                    expecting: "`ok` or `err`",
+                    case: |name: &str| match name {
+                        "ok" => Some(ty.ok()),
+                        "err" => Some(ty.err()),
+                        _ => None,
+                    },
+                })?;
+                let value = if name == "ok" {
+                    Ok(payload)
+                } else {
+                    Err(payload)
+                };
+                ty.new_val(value).map_err(D::Error::custom)?
+            }
+            Type::Own(_) | Type::Borrow(_) => {
+                return Err(D::Error::custom("resources cannot be deserialized"))
+            }
+        })
+    }
+}
+
+struct ListVisitor<'a>(&'a types::List);
+
+impl<'de> Visitor<'de> for ListVisitor<'_> {
+    type Value = Val;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Val, A::Error> {
+        let ty = self.0.ty();
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element_seed(&ty)? {
+            values.push(value);
+        }
+        self.0.new_val(values.into()).map_err(A::Error::custom)
+    }
+}
+
+struct TupleVisitor<'a>(&'a types::Tuple);
+
+impl<'de> Visitor<'de> for TupleVisitor<'_> {
+    type Value = Val;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a tuple of {} element(s)", self.0.types().len())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Val, A::Error> {
+        let mut values = Vec::with_capacity(self.0.types().len());
+        for (i, ty) in self.0.types().enumerate() {
+            match seq.next_element_seed(&ty)? {
+                Some(value) => values.push(value),
+                None => return Err(de::Error::invalid_length(i, &self)),
+            }
+        }
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(values.len() + 1, &self));
+        }
+        self.0.new_val(values.into()).map_err(A::Error::custom)
+    }
+}
+
+struct RecordVisitor<'a>(&'a types::Record);
+
+impl<'de> Visitor<'de> for RecordVisitor<'_> {
+    type Value = Val;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a record")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Val, A::Error> {
+        let fields = self.0.fields().collect::<Vec<_>>();
+        let mut values = fields.iter().map(|_| None).collect::<Vec<Option<Val>>>();
+        while let Some(name) = map.next_key::<String>()? {
+            let i = fields
+                .iter()
+                .position(|f| f.name == name)
+                .ok_or_else(|| A::Error::custom(format!("unknown field `{name}`")))?;
+            if values[i].is_some() {
+                return Err(A::Error::custom(format!("duplicate field `{name}`")));
+            }
+            values[i] = Some(map.next_value_seed(&fields[i].ty)?);
+        }
+        let values = fields
+            .iter()
+            .zip(values)
+            .map(|(field, value)| match value {
+                Some(value) => Ok((field.name, value)),
+                None => Err(A::Error::custom(format!("missing field `{}`", field.name))),
+            })
+            .collect::<Result<Vec<_>, A::Error>>()?;
+        self.0.new_val(values).map_err(A::Error::custom)
+    }
+}
+
+/// Visitor for the cases of variant-like types, which are either the name of
+/// a case without a payload or a single-entry map from the name of a case to
+/// its payload.
+///
+/// The `case` function returns `None` for unknown cases and otherwise the type
+/// of the case's payload, if any.
+struct CaseVisitor<F> {
+    expecting: &'static str,
+    case: F,
+}
+
+impl<'de, F> Visitor<'de> for CaseVisitor<F>
+where
+    F: Fn(&str) -> Option<Option<Type>>,
+{
+    type Value = (String, Option<Val>);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+        match (self.case)(name) {
+            Some(None) => Ok((name.to_string(), None)),
+            Some(Some(_)) => Err(E::custom(format!("case `{name}` requires a payload"))),
+            None => Err(E::custom(format!("unknown case `{name}`"))),
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let name = match map.next_key::<String>()? {
+            Some(name) => name,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let payload = match (self.case)(&name) {
+            Some(Some(ty)) => Some(map.next_value_seed(&ty)?),
+            Some(None) => {
+                map.next_value::<()>()?;
+                None
+            }
+            None => return Err(A::Error::custom(format!("unknown case `{name}`"))),
+        };
+        if map.next_key::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok((name, payload))
+    }
+}
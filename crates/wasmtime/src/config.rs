@@ -121,6 +121,8 @@ pub struct Config {
     pub(crate) wmemcheck: bool,
     pub(crate) coredump_on_trap: bool,
     pub(crate) macos_use_mach_ports: bool,
+    #[cfg(feature = "component-model")]
+    pub(crate) component_resource_leak_reporting: bool,
 }
 
 /// User-provided configuration for the compiler.
@@ -135,6 +137,8 @@ struct CompilerConfig {
     cache_store: Option<Arc<dyn CacheStore>>,
     clif_dir: Option<std::path::PathBuf>,
     wmemcheck: bool,
+    tier_up_threshold: u32,
+    tier_up_threads: usize,
 }
 
 #[cfg(any(feature = "cranelift", feature = "winch"))]
@@ -148,6 +152,8 @@ impl CompilerConfig {
             cache_store: None,
             clif_dir: None,
             wmemcheck: false,
+            tier_up_threshold: 1000,
+            tier_up_threads: 1,
         }
     }
 
@@ -217,6 +223,8 @@ impl Config {
             wmemcheck: false,
             coredump_on_trap: false,
             macos_use_mach_ports: true,
+            #[cfg(feature = "component-model")]
+            component_resource_leak_reporting: false,
         };
         #[cfg(any(feature = "cranelift", feature = "winch"))]
         {
@@ -854,6 +862,8 @@ impl Config {
     /// be enabled for compilation.
     ///
     /// Note that this feature is a work-in-progress and is incomplete.
+    /// Enabling it also enables component-level values, which are used by
+    /// `start` functions as well as value imports and exports.
     ///
     /// This is `false` by default.
     ///
@@ -861,6 +871,7 @@ impl Config {
     #[cfg(feature = "component-model")]
     pub fn wasm_component_model(&mut self, enable: bool) -> &mut Self {
         self.features.component_model = enable;
+        self.features.component_model_values = enable;
         self
     }
 
@@ -878,6 +889,31 @@ impl Config {
         self
     }
 
+    /// Configures how many times a function needs to be called before its
+    /// module is recompiled with Cranelift when [`Strategy::Tiered`] is used.
+    ///
+    /// Calls are counted per function and shared by all instances of a
+    /// module. The default value for this is 1000.
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    #[cfg_attr(nightlydoc, doc(cfg(any(feature = "cranelift", feature = "winch"))))]
+    pub fn tier_up_threshold(&mut self, calls: u32) -> &mut Self {
+        self.compiler_config.tier_up_threshold = calls.max(1);
+        self
+    }
+
+    /// Configures the maximum number of background threads per [`Engine`]
+    /// which recompile modules with Cranelift when [`Strategy::Tiered`] is
+    /// used.
+    ///
+    /// Further recompilations are queued until a thread is available. The
+    /// default value for this is 1.
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    #[cfg_attr(nightlydoc, doc(cfg(any(feature = "cranelift", feature = "winch"))))]
+    pub fn tier_up_threads(&mut self, threads: usize) -> &mut Self {
+        self.compiler_config.tier_up_threads = threads.max(1);
+        self
+    }
+
     /// Creates a default profiler based on the profiling strategy chosen.
     ///
     /// Profiler creation calls the type's default initializer where the purpose is
@@ -1524,6 +1560,29 @@ impl Config {
         self
     }
 
+    /// Configures whether leaked component resource handles are reported when
+    /// a [`Store`](crate::Store) is dropped.
+    ///
+    /// When enabled, dropping a store will emit a warning through the
+    /// `tracing` crate for every component instance within it that still has
+    /// `own` resource handles in its resource tables, listing the type, index
+    /// and representation of each handle. The warnings are emitted on the
+    /// thread dropping the store, so they're seen by that thread's current
+    /// subscriber.
+    ///
+    /// This is intended as a debugging aid for guests that fail to drop the
+    /// resources they're given. The handles themselves can be inspected at
+    /// any time with
+    /// [`Store::component_resource_handles`](crate::Store::component_resource_handles).
+    ///
+    /// This option is disabled by default.
+    #[cfg(feature = "component-model")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "component-model")))]
+    pub fn component_resource_leak_reporting(&mut self, enable: bool) -> &mut Self {
+        self.component_resource_leak_reporting = enable;
+        self
+    }
+
     /// Enables memory error checking for wasm programs.
     ///
     /// This option is disabled by default.
@@ -1657,15 +1716,17 @@ impl Config {
             Strategy::Winch => wasmtime_winch::builder(),
             #[cfg(not(feature = "winch"))]
             Strategy::Winch => bail!("winch support not compiled in"),
+            #[cfg(all(feature = "cranelift", feature = "winch"))]
+            Strategy::Tiered => wasmtime_winch::builder(),
+            #[cfg(not(all(feature = "cranelift", feature = "winch")))]
+            Strategy::Tiered => {
+                bail!("tiered compilation requires both cranelift and winch support")
+            }
         };
 
-        if let Some(target) = &self.compiler_config.target {
-            compiler.target(target.clone())?;
-        }
-
-        if let Some(path) = &self.compiler_config.clif_dir {
-            compiler.clif_dir(path)?;
-        }
+        // Winch code counts the calls of its functions to find the hot code
+        // which tiered compilation recompiles.
+        self.tunables.tier_up_counters = matches!(self.compiler_config.strategy, Strategy::Tiered);
 
         // If probestack is enabled for a target, Wasmtime will always use the
         // inline strategy which doesn't require us to define a `__probestack`
@@ -1740,6 +1801,49 @@ impl Config {
             bail!("cannot disable the simd proposal but enable the relaxed simd proposal");
         }
 
+        self.configure_compiler(&mut *compiler)?;
+
+        Ok((self, compiler.build()?))
+    }
+
+    /// Builds the optimizing compiler and the threads used to recompile hot
+    /// code in the background when [`Strategy::Tiered`] is configured.
+    ///
+    /// Must be called after [`Config::build_compiler`], so that both
+    /// compilers share the same settings.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn build_tiering(&self) -> Result<Option<crate::tier_up::Tiering>> {
+        match self.compiler_config.strategy {
+            Strategy::Tiered => {
+                let mut compiler = wasmtime_cranelift::builder();
+                self.configure_compiler(&mut *compiler)?;
+                Ok(Some(crate::tier_up::Tiering {
+                    compiler: compiler.build()?,
+                    pool: Arc::new(crate::tier_up::TierUpPool::new(
+                        self.compiler_config.tier_up_threads,
+                    )),
+                    threshold: self.compiler_config.tier_up_threshold,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Applies the target, settings, flags and tunables of this configuration
+    /// to the given compiler builder.
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    fn configure_compiler(
+        &self,
+        compiler: &mut dyn wasmtime_environ::CompilerBuilder,
+    ) -> Result<()> {
+        if let Some(target) = &self.compiler_config.target {
+            compiler.target(target.clone())?;
+        }
+
+        if let Some(path) = &self.compiler_config.clif_dir {
+            compiler.clif_dir(path)?;
+        }
+
         // Apply compiler settings and flags
         for (k, v) in self.compiler_config.settings.iter() {
             compiler.set(k, v)?;
@@ -1754,8 +1858,7 @@ impl Config {
 
         compiler.set_tunables(self.tunables.clone())?;
         compiler.wmemcheck(self.compiler_config.wmemcheck);
-
-        Ok((self, compiler.build()?))
+        Ok(())
     }
 
     /// Internal setting for whether adapter modules for components will have
@@ -1886,6 +1989,35 @@ pub enum Strategy {
     /// A baseline compiler for WebAssembly, currently under active development and not ready for
     /// production applications.
     Winch,
+
+    /// Tiered compilation: modules are compiled with Winch, which keeps
+    /// [`Module::new`](crate::Module::new) latency low, and hot modules are
+    /// then recompiled with Cranelift in the background.
+    ///
+    /// Winch code counts the calls of each function, and once a function
+    /// has been called [`Config::tier_up_threshold`] times its whole module
+    /// is recompiled on one of at most [`Config::tier_up_threads`] threads.
+    /// Whether that has finished can be checked with
+    /// [`Module::is_tiered_up`](crate::Module::is_tiered_up).
+    ///
+    /// Winch and Cranelift code can't call each other, so a
+    /// [`Store`](crate::Store) switches to Cranelift code as a whole once
+    /// all modules instantiated in it have been recompiled. Existing
+    /// instances are switched over as well, including the functions in their
+    /// tables. The switch happens the next time the host calls into wasm
+    /// while no wasm of the store is running, so calls which are in progress
+    /// finish running Winch code.
+    ///
+    /// Modules which are deserialized rather than compiled aren't
+    /// recompiled, so stores with instances of them keep running Winch
+    /// code, and instantiating them in a store which has already switched
+    /// to Cranelift code fails.
+    ///
+    /// Winch doesn't support the component model, so components are compiled
+    /// with Cranelift right away.
+    ///
+    /// Requires both the `cranelift` and `winch` features.
+    Tiered,
 }
 
 /// Possible optimization levels for the Cranelift codegen backend.
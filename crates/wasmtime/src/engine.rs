@@ -49,6 +49,11 @@ struct EngineInner {
     config: Config,
     #[cfg(any(feature = "cranelift", feature = "winch"))]
     compiler: Box<dyn wasmtime_environ::Compiler>,
+    /// The compiler and threads used to recompile hot code in the background,
+    /// only present when [`Strategy::Tiered`](crate::Strategy::Tiered) is
+    /// configured.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    tiering: Option<crate::tier_up::Tiering>,
     allocator: Box<dyn InstanceAllocator + Send + Sync>,
     profiler: Box<dyn ProfilingAgent>,
     signatures: SignatureRegistry,
@@ -87,6 +92,8 @@ impl Engine {
 
         #[cfg(any(feature = "cranelift", feature = "winch"))]
         let (config, compiler) = config.build_compiler()?;
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        let tiering = config.build_tiering()?;
 
         let allocator = config.build_allocator()?;
         let profiler = config.build_profiler()?;
@@ -95,6 +102,8 @@ impl Engine {
             inner: Arc::new(EngineInner {
                 #[cfg(any(feature = "cranelift", feature = "winch"))]
                 compiler,
+                #[cfg(all(feature = "cranelift", feature = "winch"))]
+                tiering,
                 config,
                 allocator,
                 profiler,
@@ -138,6 +147,11 @@ impl Engine {
         &*self.inner.compiler
     }
 
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn tiering(&self) -> Option<&crate::tier_up::Tiering> {
+        self.inner.tiering.as_ref()
+    }
+
     pub(crate) fn allocator(&self) -> &dyn InstanceAllocator {
         self.inner.allocator.as_ref()
     }
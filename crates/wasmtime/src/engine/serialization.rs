@@ -220,7 +220,9 @@ impl Metadata<'_> {
             memory_control,
             function_references,
             gc,
-            component_model_values,
+
+            // Always enabled along with `component_model`.
+            component_model_values: _,
 
             // Always on; we don't currently have knobs for these.
             mutable_global: _,
@@ -231,7 +233,6 @@ impl Metadata<'_> {
 
         assert!(!memory_control);
         assert!(!gc);
-        assert!(!component_model_values);
 
         Metadata {
             target: engine.compiler().triple().to_string(),
@@ -346,6 +347,7 @@ impl Metadata<'_> {
             guard_before_linear_memory,
             relaxed_simd_deterministic,
             tail_callable,
+            tier_up_counters,
 
             // This doesn't affect compilation, it's just a runtime setting.
             dynamic_memory_growth_reserve: _,
@@ -407,6 +409,11 @@ impl Metadata<'_> {
             "relaxed simd deterministic semantics",
         )?;
         Self::check_bool(tail_callable, other.tail_callable, "WebAssembly tail calls")?;
+        Self::check_bool(
+            tier_up_counters,
+            other.tier_up_counters,
+            "tiered compilation",
+        )?;
 
         Ok(())
     }
@@ -1362,6 +1362,11 @@ pub(crate) fn invoke_wasm_and_catch_traps<T>(
     closure: impl FnMut(*mut VMContext),
 ) -> Result<()> {
     unsafe {
+        // With tiered compilation this is where stores switch to recompiled
+        // code, which is only possible while none of their wasm is running.
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        store.0.maybe_tier_up();
+
         let exit = enter_wasm(store);
 
         if let Err(trap) = store.0.call_hook(CallHook::CallingWasm) {
@@ -11,10 +11,14 @@ use std::ptr::NonNull;
 use std::sync::Arc;
 use wasmtime_environ::{EntityType, FuncIndex, GlobalIndex, MemoryIndex, PrimaryMap, TableIndex};
 use wasmtime_runtime::{
-    Imports, InstanceAllocationRequest, StorePtr, VMContext, VMFuncRef, VMFunctionImport,
-    VMGlobalImport, VMMemoryImport, VMNativeCallFunction, VMOpaqueContext, VMTableImport,
+    Imports, InstanceAllocationRequest, StorePtr, VMArrayCallFunction, VMContext, VMFuncRef,
+    VMFunctionImport, VMGlobalImport, VMMemoryImport, VMNativeCallFunction, VMOpaqueContext,
+    VMTableImport,
 };
 
+mod snapshot;
+pub use self::snapshot::InstanceSnapshot;
+
 /// An instantiated WebAssembly module.
 ///
 /// This type represents the instantiation of a [`Module`]. Once instantiated
@@ -202,7 +206,7 @@ impl Instance {
         module: &Module,
         imports: Imports<'_>,
     ) -> Result<Instance> {
-        let (instance, start) = Instance::new_raw(store.0, module, imports)?;
+        let (instance, start) = Instance::new_raw(store.0, module, imports, None)?;
         if let Some(start) = start {
             instance.start_raw(store, start)?;
         }
@@ -237,6 +241,9 @@ impl Instance {
     /// This is not intended to be exposed from Wasmtime, it's intended to
     /// refactor out common code from `new_started` and `new_started_async`.
     ///
+    /// If `snapshot` is provided then memories are created from the
+    /// snapshot's images instead of the module's own.
+    ///
     /// Note that this step needs to be run on a fiber in async mode even
     /// though it doesn't do any blocking work because an async resource
     /// limiter may need to yield.
@@ -250,6 +257,7 @@ impl Instance {
         store: &mut StoreOpaque,
         module: &Module,
         imports: Imports<'_>,
+        snapshot: Option<&InstanceSnapshot>,
     ) -> Result<(Instance, Option<FuncIndex>)> {
         if !Engine::same(store.engine(), module.engine()) {
             bail!("cross-`Engine` instantiation is not currently supported");
@@ -261,7 +269,40 @@ impl Instance {
         // Register the module just before instantiation to ensure we keep the module
         // properly referenced while in use by the store.
         let module_id = store.modules_mut().register_module(module);
+
+        // With tiered compilation the instance may run code recompiled from
+        // the module instead, in which case its imports are redirected to the
+        // recompiled code of their own modules as well.
+        let tiered = store.tiered_code(module)?;
         store.fill_func_refs();
+        let functions;
+        let (code, imports) = match &tiered {
+            Some((code, redirects)) => {
+                let redirect = |addr: usize| redirects.get(&addr).copied().unwrap_or(addr);
+                functions = imports
+                    .functions
+                    .iter()
+                    .map(|f| VMFunctionImport {
+                        wasm_call: NonNull::new(redirect(f.wasm_call.as_ptr() as usize) as *mut _)
+                            .unwrap(),
+                        native_call: NonNull::new(
+                            redirect(f.native_call.as_ptr() as usize) as *mut _
+                        )
+                        .unwrap(),
+                        array_call: mem::transmute::<usize, VMArrayCallFunction>(redirect(
+                            f.array_call as usize,
+                        )),
+                        vmctx: f.vmctx,
+                    })
+                    .collect::<Vec<_>>();
+                let imports = Imports {
+                    functions: &functions,
+                    ..imports
+                };
+                (code, imports)
+            }
+            None => (module, imports),
+        };
 
         // The first thing we do is issue an instance allocation request
         // to the instance allocator. This, on success, will give us an
@@ -273,13 +314,17 @@ impl Instance {
         // this instance, so we determine what the ID is and then assert
         // it's the same later when we do actually insert it.
         let instance_to_be = store.store_data().next_id::<InstanceData>();
+        let runtime_info = match snapshot {
+            Some(snapshot) => snapshot.runtime_info(code),
+            None => code.runtime_info(),
+        };
 
         let mut instance_handle =
             store
                 .engine()
                 .allocator()
                 .allocate_module(InstanceAllocationRequest {
-                    runtime_info: &module.runtime_info(),
+                    runtime_info: &runtime_info,
                     imports,
                     host_state: Box::new(Instance(instance_to_be)),
                     store: StorePtr::new(store.traitobj()),
@@ -364,6 +409,28 @@ impl Instance {
         Ok(())
     }
 
+    /// Captures the current state of this instance so that new instances of
+    /// its module can later be created in the same state.
+    ///
+    /// See [`InstanceSnapshot`] for what is captured and
+    /// [`InstancePre::instantiate_from_snapshot`] for creating instances from
+    /// the returned snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state of this instance can't be represented in
+    /// a snapshot. This happens if the instance defines a shared memory, if a
+    /// defined table or global holds a non-null `externref`, or if one holds a
+    /// reference to a function which is neither defined nor imported by this
+    /// instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn snapshot(&self, mut store: impl AsContextMut) -> Result<InstanceSnapshot> {
+        InstanceSnapshot::new(store.as_context_mut().0, self)
+    }
+
     /// Get this instance's module.
     pub fn module<'a, T: 'a>(&self, store: impl Into<StoreContext<'a, T>>) -> &'a Module {
         self._module(store.into().0)
@@ -825,6 +892,53 @@ impl<T> InstancePre<T> {
         unsafe { Instance::new_started(&mut store, &self.module, imports.as_ref()) }
     }
 
+    /// Instantiates this instance in the state recorded by `snapshot`.
+    ///
+    /// This behaves like [`InstancePre::instantiate`] except that the new
+    /// instance's defined memories, mutable globals and tables are set to the
+    /// contents captured by [`Instance::snapshot`]. The module's start function
+    /// is not run since its effects are already part of the snapshot.
+    ///
+    /// Where supported, the contents of each memory within its initial size
+    /// are mapped copy-on-write from an image created along with the snapshot,
+    /// so this is considerably cheaper than re-running whatever initialization
+    /// produced the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `snapshot` was taken from an instance of a
+    /// different module, or for any of the reasons instantiation can otherwise
+    /// fail.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same situations as [`InstancePre::instantiate`].
+    pub fn instantiate_from_snapshot(
+        &self,
+        mut store: impl AsContextMut<Data = T>,
+        snapshot: &InstanceSnapshot,
+    ) -> Result<Instance> {
+        let mut store = store.as_context_mut();
+        assert!(
+            !store.0.async_support(),
+            "cannot instantiate from a snapshot when async support is enabled",
+        );
+        snapshot.check_module(&self.module)?;
+        let imports = pre_instantiate_raw(
+            &mut store.0,
+            &self.module,
+            &self.items,
+            self.host_funcs,
+            &self.func_refs,
+        )?;
+
+        // See `instantiate` for notes on this unsafety.
+        let (instance, _start) =
+            unsafe { Instance::new_raw(store.0, &self.module, imports.as_ref(), Some(snapshot))? };
+        snapshot.restore(&mut store, instance)?;
+        Ok(instance)
+    }
+
     /// Creates a new instance, running the start function asynchronously
     /// instead of inline.
     ///
@@ -873,6 +987,14 @@ fn pre_instantiate_raw(
     host_funcs: usize,
     func_refs: &Arc<[VMFuncRef]>,
 ) -> Result<OwnedImports> {
+    // With tiered compilation each store switches its trampolines to
+    // recompiled code on its own, so the pre-patched function references
+    // can't be shared and stores fill in their own copies instead.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    let share_func_refs = store.engine().tiering().is_none();
+    #[cfg(not(all(feature = "cranelift", feature = "winch")))]
+    let share_func_refs = true;
+
     if host_funcs > 0 {
         // Any linker-defined function of the `Definition::HostFunc` variant
         // will insert a function into the store automatically as part of
@@ -885,7 +1007,9 @@ fn pre_instantiate_raw(
         // items into the store once. This avoids cloning each individual item
         // below.
         store.push_rooted_funcs(items.clone());
-        store.push_instance_pre_func_refs(func_refs.clone());
+        if share_func_refs {
+            store.push_instance_pre_func_refs(func_refs.clone());
+        }
     }
 
     let mut func_refs = func_refs.iter().map(|f| NonNull::from(f));
@@ -903,7 +1027,7 @@ fn pre_instantiate_raw(
             Definition::HostFunc(func) => unsafe {
                 func.to_func_store_rooted(
                     store,
-                    if func.func_ref().wasm_call.is_none() {
+                    if func.func_ref().wasm_call.is_none() && share_func_refs {
                         Some(func_refs.next().unwrap())
                     } else {
                         None
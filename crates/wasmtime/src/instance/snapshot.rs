@@ -0,0 +1,436 @@
+//! Support for capturing the state of an instance and creating new instances
+//! from that state later on.
+
+use super::Instance;
+use crate::store::StoreOpaque;
+use crate::{Memory, Module, StoreContextMut, Table, Val, ValType};
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::ptr::{self, NonNull};
+use std::sync::Arc;
+use wasmtime_environ::{
+    DefinedFuncIndex, DefinedGlobalIndex, DefinedMemoryIndex, DefinedTableIndex, FuncIndex,
+    HostPtr, PrimaryMap, VMOffsets, WasmHeapType, WasmRefType, WasmType, WASM_PAGE_SIZE,
+};
+use wasmtime_runtime::{
+    CompiledModuleId, InstanceHandle, MemoryImage, ModuleRuntimeInfo, TableElement,
+    VMArrayCallFunction, VMFuncRef, VMNativeCallFunction, VMSharedSignatureIndex,
+    VMWasmCallFunction,
+};
+
+/// The state of an [`Instance`] at a point in time, created with
+/// [`Instance::snapshot`].
+///
+/// A snapshot records the contents of the linear memories, mutable globals
+/// and tables defined by an instance. New instances of the same module can
+/// then be created in that state with
+/// [`InstancePre::instantiate_from_snapshot`], which is typically much cheaper
+/// than re-running whatever initialization produced it in the first place.
+///
+/// Linear memories are captured sparsely at the granularity of host pages.
+/// The portion of each memory within its initial size is additionally turned
+/// into a copy-on-write image where the platform supports it, the same as the
+/// images used for a module's data segments, so restoring a snapshot maps
+/// that portion of memory lazily rather than copying it.
+///
+/// Snapshots are not tied to any particular [`Store`](crate::Store) and may be
+/// used to create any number of instances within stores of the
+/// [`Engine`](crate::Engine) that the module belongs to.
+///
+/// Some state of an instance is not captured:
+///
+/// * Imported memories, tables and globals are owned elsewhere and are not
+///   part of the snapshot.
+/// * Whether passive data or element segments have been dropped is not
+///   recorded, so they're available again in restored instances.
+///
+/// [`InstancePre::instantiate_from_snapshot`]: crate::InstancePre::instantiate_from_snapshot
+pub struct InstanceSnapshot {
+    module: Module,
+    memories: PrimaryMap<DefinedMemoryIndex, MemorySnapshot>,
+    images: Arc<PrimaryMap<DefinedMemoryIndex, Option<Arc<MemoryImage>>>>,
+    globals: Vec<(DefinedGlobalIndex, GlobalSnapshot)>,
+    tables: PrimaryMap<DefinedTableIndex, Vec<Option<FuncIndex>>>,
+}
+
+struct MemorySnapshot {
+    /// The size of the memory, in wasm pages.
+    size: u64,
+
+    /// Runs of host pages containing non-zero bytes, as an offset into linear
+    /// memory paired with the contents of those pages.
+    segments: Vec<(usize, Box<[u8]>)>,
+
+    /// The number of leading entries in `segments` which are covered by this
+    /// memory's image, if it has one, and therefore don't need to be copied
+    /// when the image is mapped.
+    image_segments: usize,
+}
+
+enum GlobalSnapshot {
+    /// The raw bits of a value type global.
+    Bits(u128),
+    /// A `funcref` global, described by the function it refers to.
+    FuncRef(Option<FuncIndex>),
+    /// An `externref` global, which can only be snapshotted when null.
+    NullExternRef,
+}
+
+impl InstanceSnapshot {
+    pub(super) fn new(store: &mut StoreOpaque, instance: &Instance) -> Result<InstanceSnapshot> {
+        let id = store[instance.0].id;
+        let module = instance._module(store).clone();
+        let env_module = module.env_module();
+        let mut funcs = FuncRefIndices::default();
+
+        let mut memories = PrimaryMap::new();
+        let mut images = PrimaryMap::new();
+        for (index, plan) in env_module
+            .memory_plans
+            .iter()
+            .skip(env_module.num_imported_memories)
+        {
+            if plan.memory.shared {
+                bail!("cannot snapshot an instance which defines a shared memory");
+            }
+            let export = store.instance_mut(id).get_exported_memory(index);
+            let data = unsafe {
+                let definition = &*export.definition;
+                std::slice::from_raw_parts(definition.base, definition.current_length())
+            };
+            let minimum = usize::try_from(plan.memory.minimum * u64::from(WASM_PAGE_SIZE))?;
+            let (memory, image) = MemorySnapshot::new(data, minimum)?;
+            memories.push(memory);
+            images.push(image);
+        }
+
+        let mut globals = Vec::new();
+        let defined_globals = store.instance_mut(id).defined_globals().collect::<Vec<_>>();
+        for (index, global) in defined_globals {
+            if !global.global.mutability {
+                continue;
+            }
+            let definition = unsafe { &*global.definition };
+            let value = match global.global.wasm_ty {
+                WasmType::Ref(WasmRefType {
+                    heap_type: WasmHeapType::Extern,
+                    ..
+                }) => {
+                    if unsafe { definition.as_externref().is_some() } {
+                        bail!("cannot snapshot a global containing a non-null `externref`");
+                    }
+                    GlobalSnapshot::NullExternRef
+                }
+                WasmType::Ref(_) => {
+                    let func_ref = unsafe { definition.as_func_ref() };
+                    GlobalSnapshot::FuncRef(funcs.index(store.instance_mut(id), func_ref)?)
+                }
+                _ => GlobalSnapshot::Bits(unsafe { *definition.as_u128() }),
+            };
+            globals.push((index, value));
+        }
+
+        let mut tables = PrimaryMap::new();
+        for (index, _) in env_module
+            .table_plans
+            .iter()
+            .skip(env_module.num_imported_tables)
+        {
+            let index = env_module.defined_table_index(index).unwrap();
+            let handle = store.instance_mut(id);
+            let size = unsafe { (*handle.get_defined_table(index)).size() };
+            let table = handle.get_defined_table_with_lazy_init(index, 0..size);
+            let mut elements = Vec::with_capacity(usize::try_from(size).unwrap());
+            for i in 0..size {
+                let element = match unsafe { (*table).get(i).unwrap() } {
+                    TableElement::FuncRef(func_ref) => funcs.index(handle, func_ref)?,
+                    TableElement::ExternRef(None) => None,
+                    TableElement::ExternRef(Some(_)) => {
+                        bail!("cannot snapshot a table containing a non-null `externref`")
+                    }
+                    TableElement::UninitFunc => {
+                        unreachable!("lazy init above should have converted UninitFunc")
+                    }
+                };
+                elements.push(element);
+            }
+            tables.push(elements);
+        }
+
+        Ok(InstanceSnapshot {
+            module,
+            memories,
+            images: Arc::new(images),
+            globals,
+            tables,
+        })
+    }
+
+    /// Returns an error if this snapshot can't be used to create instances of
+    /// `module`.
+    pub(super) fn check_module(&self, module: &Module) -> Result<()> {
+        // With tiered compilation instances may have switched to recompiled
+        // code since the snapshot was taken, but they're still recorded as
+        // instances of the module they were created from.
+        if self.module.id() != module.id() {
+            bail!("snapshot was taken from an instance of a different module");
+        }
+        Ok(())
+    }
+
+    /// Returns the runtime information to instantiate `module` with such that
+    /// its memories are initialized from this snapshot's images rather than
+    /// from the module's data segments.
+    pub(super) fn runtime_info(&self, module: &Module) -> Arc<dyn ModuleRuntimeInfo> {
+        Arc::new(SnapshotRuntimeInfo {
+            module: module.runtime_info(),
+            images: self.images.clone(),
+        })
+    }
+
+    /// Overwrites the state of the freshly-initialized `instance` with the
+    /// state recorded in this snapshot.
+    pub(super) fn restore<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        instance: Instance,
+    ) -> Result<()> {
+        let id = store.0[instance.0].id;
+        let env_module = self.module.env_module();
+
+        for (index, snapshot) in self.memories.iter() {
+            let handle = store.0.instance_mut(id);
+            // Memories which weren't able to use the snapshot's image have
+            // been initialized from the module's data segments instead, so
+            // they need to be cleared and have the full contents copied in.
+            let needs_init = unsafe { (*handle.get_defined_memory(index)).needs_init() };
+            let export = handle.get_exported_memory(env_module.memory_index(index));
+            let memory = unsafe { Memory::from_wasmtime_memory(export, store.0) };
+            let segments = if needs_init {
+                memory.data_mut(&mut *store).fill(0);
+                &snapshot.segments[..]
+            } else {
+                &snapshot.segments[snapshot.image_segments..]
+            };
+            let size = memory.size(&*store);
+            if snapshot.size > size {
+                memory.grow(&mut *store, snapshot.size - size)?;
+            }
+            let data = memory.data_mut(&mut *store);
+            for (offset, bytes) in segments {
+                data[*offset..][..bytes.len()].copy_from_slice(bytes);
+            }
+        }
+
+        let handle = store.0.instance_mut(id);
+        for (index, value) in self.globals.iter() {
+            let global = handle.get_exported_global(env_module.global_index(*index));
+            unsafe {
+                let definition = &mut *global.definition;
+                match value {
+                    GlobalSnapshot::Bits(bits) => *definition.as_u128_mut() = *bits,
+                    GlobalSnapshot::FuncRef(func) => {
+                        *definition.as_func_ref_mut() = func_ref(handle, *func);
+                    }
+                    GlobalSnapshot::NullExternRef => {
+                        drop(definition.as_externref_mut().take());
+                    }
+                }
+            }
+        }
+
+        for (index, elements) in self.tables.iter() {
+            let export = store
+                .0
+                .instance_mut(id)
+                .get_exported_table(env_module.table_index(index));
+            let table = unsafe { Table::from_wasmtime_table(export, store.0) };
+            let null = match table.ty(&*store).element() {
+                ValType::ExternRef => Val::ExternRef(None),
+                _ => Val::FuncRef(None),
+            };
+            let len = u32::try_from(elements.len()).unwrap();
+            let size = table.size(&*store);
+            if len > size {
+                table.grow(&mut *store, len - size, null.clone())?;
+            }
+            table.fill(&mut *store, 0, null, len)?;
+
+            let handle = store.0.instance_mut(id);
+            let table = handle.get_defined_table(index);
+            for (i, func) in elements.iter().enumerate() {
+                if func.is_none() {
+                    continue;
+                }
+                let element = TableElement::FuncRef(func_ref(handle, *func));
+                unsafe {
+                    (*table)
+                        .set(u32::try_from(i).unwrap(), element)
+                        .expect("table was grown to fit the snapshot");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl MemorySnapshot {
+    /// Captures the contents of `data`, creating an image for the portion of
+    /// it within the first `minimum` bytes.
+    fn new(data: &[u8], minimum: usize) -> Result<(MemorySnapshot, Option<Arc<MemoryImage>>)> {
+        let page_size = wasmtime_runtime::page_size();
+        let minimum = minimum / page_size * page_size;
+
+        // Collect runs of non-zero pages, splitting any run which straddles
+        // `minimum` so that the image only ever covers whole segments.
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for (i, page) in data.chunks(page_size).enumerate() {
+            if page.iter().all(|b| *b == 0) {
+                continue;
+            }
+            let start = i * page_size;
+            let end = start + page.len();
+            match runs.last_mut() {
+                Some((_, prev_end)) if *prev_end == start && start != minimum => *prev_end = end,
+                _ => runs.push((start, end)),
+            }
+        }
+        let segments = runs
+            .iter()
+            .map(|(start, end)| (*start, data[*start..*end].into()))
+            .collect::<Vec<_>>();
+
+        // The image spans from the first to the last non-zero page within
+        // `minimum`. Note that an image is created even if there aren't any
+        // such pages, as that's what tells instantiation that this memory
+        // doesn't need to be initialized from the module's data segments.
+        let image_segments = runs.iter().take_while(|(_, end)| *end <= minimum).count();
+        let image_range = match &runs[..image_segments] {
+            [] => 0..0,
+            [(start, _), .., (_, end)] | [(start, end)] => *start..*end,
+        };
+        let image =
+            MemoryImage::from_data(image_range.start as u64, &data[image_range])?.map(Arc::new);
+
+        let snapshot = MemorySnapshot {
+            size: (data.len() / WASM_PAGE_SIZE as usize) as u64,
+            segments,
+            image_segments,
+        };
+        Ok((snapshot, image))
+    }
+}
+
+/// Lazily-built map from function references back to the index of the
+/// function within the instance that they refer to.
+#[derive(Default)]
+struct FuncRefIndices(Option<HashMap<(usize, usize), FuncIndex>>);
+
+impl FuncRefIndices {
+    fn index(
+        &mut self,
+        handle: &mut InstanceHandle,
+        func_ref: *mut VMFuncRef,
+    ) -> Result<Option<FuncIndex>> {
+        let func_ref = match NonNull::new(func_ref) {
+            Some(func_ref) => func_ref,
+            None => return Ok(None),
+        };
+        let map = self.0.get_or_insert_with(|| {
+            let module = handle.module().clone();
+            module
+                .functions
+                .iter()
+                .filter(|(_, func)| func.is_escaping())
+                .map(|(index, _)| {
+                    (
+                        func_ref_key(handle.get_exported_func(index).func_ref),
+                        index,
+                    )
+                })
+                .collect()
+        });
+        match map.get(&func_ref_key(func_ref)) {
+            Some(index) => Ok(Some(*index)),
+            None => bail!("cannot snapshot a reference to a function from another instance"),
+        }
+    }
+}
+
+/// Function references are compared by their entry point and closure rather
+/// than by address, as the same function may be referenced through several
+/// `VMFuncRef`s, for example when imported.
+fn func_ref_key(func_ref: NonNull<VMFuncRef>) -> (usize, usize) {
+    let func_ref = unsafe { func_ref.as_ref() };
+    (func_ref.array_call as usize, func_ref.vmctx as usize)
+}
+
+fn func_ref(handle: &mut InstanceHandle, func: Option<FuncIndex>) -> *mut VMFuncRef {
+    func.map_or(ptr::null_mut(), |index| {
+        handle.get_exported_func(index).func_ref.as_ptr()
+    })
+}
+
+/// Runtime information for instances created from a snapshot, which uses the
+/// snapshot's memory images and otherwise defers to the original module.
+struct SnapshotRuntimeInfo {
+    module: Arc<dyn ModuleRuntimeInfo>,
+    images: Arc<PrimaryMap<DefinedMemoryIndex, Option<Arc<MemoryImage>>>>,
+}
+
+impl ModuleRuntimeInfo for SnapshotRuntimeInfo {
+    fn module(&self) -> &Arc<wasmtime_environ::Module> {
+        self.module.module()
+    }
+
+    fn function(&self, index: DefinedFuncIndex) -> NonNull<VMWasmCallFunction> {
+        self.module.function(index)
+    }
+
+    fn native_to_wasm_trampoline(
+        &self,
+        index: DefinedFuncIndex,
+    ) -> Option<NonNull<VMNativeCallFunction>> {
+        self.module.native_to_wasm_trampoline(index)
+    }
+
+    fn array_to_wasm_trampoline(&self, index: DefinedFuncIndex) -> Option<VMArrayCallFunction> {
+        self.module.array_to_wasm_trampoline(index)
+    }
+
+    fn wasm_to_native_trampoline(
+        &self,
+        signature: VMSharedSignatureIndex,
+    ) -> Option<NonNull<VMWasmCallFunction>> {
+        self.module.wasm_to_native_trampoline(signature)
+    }
+
+    fn memory_image(&self, memory: DefinedMemoryIndex) -> Result<Option<&Arc<MemoryImage>>> {
+        Ok(self.images[memory].as_ref())
+    }
+
+    fn unique_id(&self) -> Option<CompiledModuleId> {
+        self.module.unique_id()
+    }
+
+    fn wasm_data(&self) -> &[u8] {
+        self.module.wasm_data()
+    }
+
+    fn signature_ids(&self) -> &[VMSharedSignatureIndex] {
+        self.module.signature_ids()
+    }
+
+    fn offsets(&self) -> &VMOffsets<HostPtr> {
+        self.module.offsets()
+    }
+
+    fn tier_up_counters(&self) -> *mut u32 {
+        self.module.tier_up_counters()
+    }
+
+    fn function_hot(&self, index: DefinedFuncIndex) {
+        self.module.function_hot(index)
+    }
+}
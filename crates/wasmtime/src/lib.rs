@@ -425,6 +425,8 @@ mod r#ref;
 mod resources;
 mod signatures;
 mod store;
+#[cfg(all(feature = "cranelift", feature = "winch"))]
+mod tier_up;
 mod trampoline;
 mod trap;
 mod types;
@@ -438,7 +440,7 @@ pub use crate::config::*;
 pub use crate::engine::*;
 pub use crate::externals::*;
 pub use crate::func::*;
-pub use crate::instance::{Instance, InstancePre};
+pub use crate::instance::{Instance, InstancePre, InstanceSnapshot};
 pub use crate::limits::*;
 pub use crate::linker::*;
 pub use crate::memory::*;
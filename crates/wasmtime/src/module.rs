@@ -130,6 +130,12 @@ struct ModuleInner {
 
     /// Runtime offset information for `VMContext`.
     offsets: VMOffsets<HostPtr>,
+
+    /// The state of tiered compilation of this module, only present when its
+    /// code was compiled with Winch by
+    /// [`Strategy::Tiered`](crate::Strategy::Tiered).
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    tier_up: OnceCell<Arc<crate::tier_up::TierUp>>,
 }
 
 impl std::fmt::Debug for Module {
@@ -340,7 +346,8 @@ impl Module {
         };
 
         let info_and_types = info_and_types.map(|(info, types)| (info, types.into()));
-        return Self::from_parts(engine, code, info_and_types);
+        let module = Self::from_parts(engine, code, info_and_types)?;
+        return Ok(module.with_tier_up(Some(binary)));
 
         fn publish_mmap(mmap: MmapVec) -> Result<Arc<CodeMemory>> {
             let mut code = CodeMemory::new(mmap)?;
@@ -376,7 +383,7 @@ impl Module {
         let mmap = MmapVec::from_file(file.as_ref())?;
         if &mmap[0..4] == b"\x7fELF" {
             let code = engine.load_code(mmap, ObjectKind::Module)?;
-            return Module::from_parts(engine, code, None);
+            return Ok(Module::from_parts(engine, code, None)?.with_tier_up(None));
         }
 
         Module::new(engine, &*mmap)
@@ -398,11 +405,21 @@ impl Module {
     pub(crate) fn build_artifacts(
         engine: &Engine,
         wasm: &[u8],
+    ) -> Result<(MmapVec, Option<(CompiledModuleInfo, ModuleTypes)>)> {
+        Module::build_artifacts_with(engine, engine.compiler(), wasm)
+    }
+
+    /// Same as [`Module::build_artifacts`], but compiling with the given
+    /// compiler instead of the engine's default one.
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    fn build_artifacts_with(
+        engine: &Engine,
+        compiler: &dyn wasmtime_environ::Compiler,
+        wasm: &[u8],
     ) -> Result<(MmapVec, Option<(CompiledModuleInfo, ModuleTypes)>)> {
         use crate::compiler::CompileInputs;
 
         let tunables = &engine.config().tunables;
-        let compiler = engine.compiler();
 
         // First a `ModuleEnvironment` is created which records type information
         // about the wasm module. This is where the WebAssembly is parsed and
@@ -419,12 +436,12 @@ impl Module {
         let types = types.finish();
 
         let compile_inputs = CompileInputs::for_module(&types, &translation, functions);
-        let unlinked_compile_outputs = compile_inputs.compile(engine)?;
+        let unlinked_compile_outputs = compile_inputs.compile(engine, compiler)?;
         let (compiled_funcs, function_indices) = unlinked_compile_outputs.pre_link();
 
         // Emplace all compiled functions into the object file with any other
         // sections associated with code as well.
-        let mut object = engine.compiler().object(ObjectKind::Module)?;
+        let mut object = compiler.object(ObjectKind::Module)?;
         // Insert `Engine` and type-level information into the compiled
         // artifact so if this module is deserialized later it contains all
         // information necessary.
@@ -442,7 +459,7 @@ impl Module {
         // initialize memory or otherwise enabling virtual-memory-tricks
         // such as mmap'ing from a file to get copy-on-write.
         if engine.config().memory_init_cow {
-            let align = engine.compiler().page_size_align();
+            let align = compiler.page_size_align();
             let max_always_allowed = engine.config().memory_guaranteed_dense_image_size;
             translation.try_static_init(align, max_always_allowed);
         }
@@ -511,7 +528,7 @@ impl Module {
     /// future versions of wasmtime will reject old cache entries).
     pub unsafe fn deserialize(engine: &Engine, bytes: impl AsRef<[u8]>) -> Result<Module> {
         let code = engine.load_code_bytes(bytes.as_ref(), ObjectKind::Module)?;
-        Module::from_parts(engine, code, None)
+        Ok(Module::from_parts(engine, code, None)?.with_tier_up(None))
     }
 
     /// Same as [`deserialize`], except that the contents of `path` are read to
@@ -539,7 +556,7 @@ impl Module {
     /// state of the file.
     pub unsafe fn deserialize_file(engine: &Engine, path: impl AsRef<Path>) -> Result<Module> {
         let code = engine.load_code_file(path.as_ref(), ObjectKind::Module)?;
-        Module::from_parts(engine, code, None)
+        Ok(Module::from_parts(engine, code, None)?.with_tier_up(None))
     }
 
     /// Entrypoint for creating a `Module` for all above functions, both
@@ -605,6 +622,8 @@ impl Module {
                 module,
                 serializable,
                 offsets,
+                #[cfg(all(feature = "cranelift", feature = "winch"))]
+                tier_up: OnceCell::new(),
             }),
         })
     }
@@ -689,6 +708,145 @@ impl Module {
         Ok(self.compiled_module().mmap().to_vec())
     }
 
+    /// Sets up [`Strategy::Tiered`](crate::Strategy::Tiered) compilation
+    /// for this module, whose code was just compiled from `wasm` or
+    /// deserialized if `wasm` is `None`.
+    ///
+    /// This does nothing if the engine uses another strategy.
+    fn with_tier_up(self, wasm: Option<&[u8]>) -> Module {
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        {
+            // Modules without functions are recompiled as well since their
+            // trampolines are still baseline code.
+            let module = self.env_module();
+            let funcs = module.functions.len() - module.num_imported_funcs;
+            if let Some(tier_up) = crate::tier_up::TierUp::new(self.engine(), wasm, funcs) {
+                let _ = self.inner.tier_up.set(tier_up);
+            }
+        }
+        #[cfg(not(all(feature = "cranelift", feature = "winch")))]
+        let _ = wasm;
+        self
+    }
+
+    /// Recompiles `wasm` with the optimizing compiler for tiered compilation.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    fn compile_tiered(
+        engine: &Engine,
+        compiler: &dyn wasmtime_environ::Compiler,
+        wasm: &[u8],
+    ) -> Result<Module> {
+        let (mmap, info_and_types) = Module::build_artifacts_with(engine, compiler, wasm)?;
+        let mut code = CodeMemory::new(mmap)?;
+        code.publish()?;
+        Module::from_parts(engine, Arc::new(code), info_and_types)
+    }
+
+    /// Returns whether this module's code was compiled by the baseline
+    /// compiler of [`Strategy::Tiered`](crate::Strategy::Tiered) compilation,
+    /// meaning that it can't be mixed with optimized code in a store.
+    pub(crate) fn is_baseline(&self) -> bool {
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        if self.inner.tier_up.get().is_some() {
+            return true;
+        }
+        false
+    }
+
+    /// Returns the recompiled version of this baseline module if it's ready.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn tiered(&self) -> Option<&Module> {
+        self.inner.tier_up.get()?.ready()
+    }
+
+    /// Queues the recompilation of this baseline module, as if one of its
+    /// functions had become hot.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn request_tier_up(&self) {
+        if let Some(tier_up) = self.inner.tier_up.get() {
+            tier_up.hot(Module::compile_tiered);
+        }
+    }
+
+    /// Returns the recompiled version of this baseline module, recompiling
+    /// it on the current thread if that hasn't happened yet.
+    ///
+    /// Fails for deserialized modules, which can't be recompiled.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn tiered_now(&self) -> Result<Module> {
+        self.inner
+            .tier_up
+            .get()
+            .unwrap()
+            .compile_now(Module::compile_tiered)
+    }
+
+    /// Records in `redirects` the address of each function and trampoline in
+    /// this module's code along with the address of its equivalent in
+    /// `tiered`, which must have been compiled from the same wasm.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn code_redirects(
+        &self,
+        tiered: &Module,
+        redirects: &mut std::collections::HashMap<usize, usize>,
+    ) {
+        let baseline = self.compiled_module();
+        let optimized = tiered.compiled_module();
+        let mut redirect = |from: &[u8], to: &[u8]| {
+            redirects.insert(from.as_ptr() as usize, to.as_ptr() as usize);
+        };
+        for (index, func) in baseline.finished_functions() {
+            redirect(func, optimized.finished_function(index));
+            if let (Some(from), Some(to)) = (
+                baseline.native_to_wasm_trampoline(index),
+                optimized.native_to_wasm_trampoline(index),
+            ) {
+                redirect(from, to);
+            }
+            if let (Some(from), Some(to)) = (
+                baseline.array_to_wasm_trampoline(index),
+                optimized.array_to_wasm_trampoline(index),
+            ) {
+                redirect(from, to);
+            }
+        }
+        for ty in self.env_module().types.values() {
+            let sig = ty.unwrap_function();
+            redirect(
+                baseline.wasm_to_native_trampoline(sig),
+                optimized.wasm_to_native_trampoline(sig),
+            );
+        }
+    }
+
+    /// Returns whether this module has been recompiled with Cranelift by
+    /// [`Strategy::Tiered`](crate::Strategy::Tiered) compilation.
+    ///
+    /// Once this is the case stores switch over to the recompiled code, see
+    /// [`Strategy::Tiered`](crate::Strategy::Tiered) for details. This is
+    /// always `false` for other strategies.
+    pub fn is_tiered_up(&self) -> bool {
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        if self.tiered().is_some() {
+            return true;
+        }
+        false
+    }
+
+    /// Blocks until an in-progress recompilation of this module by
+    /// [`Strategy::Tiered`](crate::Strategy::Tiered) compilation finishes.
+    ///
+    /// Returns whether this module has been recompiled, which is `false` if
+    /// none of its functions has been called often enough to be recompiled
+    /// yet, and returns an error if recompiling it failed.
+    pub fn wait_for_tier_up(&self) -> Result<bool> {
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        if let Some(tier_up) = self.inner.tier_up.get() {
+            return tier_up.wait();
+        }
+        Ok(false)
+    }
+
     pub(crate) fn compiled_module(&self) -> &CompiledModule {
         &self.inner.module
     }
@@ -1233,6 +1391,22 @@ impl wasmtime_runtime::ModuleRuntimeInfo for ModuleInner {
     fn offsets(&self) -> &VMOffsets<HostPtr> {
         &self.offsets
     }
+
+    fn tier_up_counters(&self) -> *mut u32 {
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        if let Some(tier_up) = self.tier_up.get() {
+            return tier_up.counters();
+        }
+        std::ptr::null_mut()
+    }
+
+    fn function_hot(&self, _index: DefinedFuncIndex) {
+        // The whole module is recompiled once any of its functions is hot.
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        if let Some(tier_up) = self.tier_up.get() {
+            tier_up.hot(Module::compile_tiered);
+        }
+    }
 }
 
 impl wasmtime_runtime::ModuleInfo for ModuleInner {
@@ -1341,6 +1515,14 @@ impl wasmtime_runtime::ModuleRuntimeInfo for BareModuleInfo {
     fn offsets(&self) -> &VMOffsets<HostPtr> {
         &self.offsets
     }
+
+    fn tier_up_counters(&self) -> *mut u32 {
+        std::ptr::null_mut()
+    }
+
+    fn function_hot(&self, _index: DefinedFuncIndex) {
+        unreachable!()
+    }
 }
 
 /// Helper method to construct a `ModuleMemoryImages` for an associated
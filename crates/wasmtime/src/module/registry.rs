@@ -32,6 +32,10 @@ pub struct ModuleRegistry {
 
     // Preserved for keeping data segments alive or similar
     modules_without_code: Vec<Module>,
+
+    // With tiered compilation, the code which the store runs.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    code_tier: crate::tier_up::CodeTier,
 }
 
 struct LoadedCode {
@@ -94,6 +98,19 @@ impl ModuleRegistry {
             .chain(self.modules_without_code.iter())
     }
 
+    /// Returns the code which the store runs with tiered compilation.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn code_tier(&self) -> crate::tier_up::CodeTier {
+        self.code_tier
+    }
+
+    /// Sets the code which the store runs with tiered compilation, which
+    /// determines the code that `wasm_to_native_trampoline` returns.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn set_code_tier(&mut self, tier: crate::tier_up::CodeTier) {
+        self.code_tier = tier;
+    }
+
     /// Registers a new module with the registry.
     pub fn register_module(&mut self, module: &Module) -> RegisteredModuleId {
         self.register(module.code_object(), Some(module)).unwrap()
@@ -198,6 +215,20 @@ impl ModuleRegistry {
         // See also the comment in `ModuleInner::wasm_to_native_trampoline`.
         for (_, code) in self.loaded_code.values() {
             for module in code.modules.values() {
+                // Trampolines must come from code which the store's other
+                // code can call.
+                #[cfg(all(feature = "cranelift", feature = "winch"))]
+                {
+                    use crate::tier_up::CodeTier;
+                    let skip = match self.code_tier {
+                        CodeTier::Any => false,
+                        CodeTier::Baseline => !module.is_baseline(),
+                        CodeTier::Optimized => module.is_baseline(),
+                    };
+                    if skip {
+                        continue;
+                    }
+                }
                 if let Some(trampoline) = module.runtime_info().wasm_to_native_trampoline(sig) {
                     return Some(trampoline);
                 }
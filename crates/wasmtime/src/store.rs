@@ -556,6 +556,14 @@ impl<T> Store<T> {
             instance
         };
 
+        // With tiered compilation stores start out running the baseline code.
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        if engine.tiering().is_some() {
+            inner
+                .modules
+                .set_code_tier(crate::tier_up::CodeTier::Baseline);
+        }
+
         Self {
             inner: ManuallyDrop::new(inner),
         }
@@ -780,6 +788,25 @@ impl<T> Store<T> {
         self.inner.gc()
     }
 
+    /// Returns information about the live resource handles of every component
+    /// instance within this store.
+    ///
+    /// Each component instance is paired with the handles held in its resource
+    /// tables. See [`component::Instance::resource_handles`] for more
+    /// information.
+    ///
+    /// [`component::Instance::resource_handles`]: crate::component::Instance::resource_handles
+    #[cfg(feature = "component-model")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "component-model")))]
+    pub fn component_resource_handles(
+        &self,
+    ) -> Vec<(
+        crate::component::Instance,
+        Vec<crate::component::ResourceHandleInfo>,
+    )> {
+        crate::component::store_resource_handles(&self.inner)
+    }
+
     /// Returns the amount fuel in this [`Store`].
     ///
     /// If fuel consumption is not enabled via
@@ -992,6 +1019,21 @@ impl<'a, T> StoreContext<'a, T> {
     pub fn get_fuel(&self) -> Result<u64> {
         self.0.get_fuel()
     }
+
+    /// Returns information about the live resource handles of every component
+    /// instance within this store.
+    ///
+    /// For more information see [`Store::component_resource_handles`].
+    #[cfg(feature = "component-model")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "component-model")))]
+    pub fn component_resource_handles(
+        &self,
+    ) -> Vec<(
+        crate::component::Instance,
+        Vec<crate::component::ResourceHandleInfo>,
+    )> {
+        crate::component::store_resource_handles(self.0)
+    }
 }
 
 impl<'a, T> StoreContextMut<'a, T> {
@@ -1021,6 +1063,21 @@ impl<'a, T> StoreContextMut<'a, T> {
         self.0.gc()
     }
 
+    /// Returns information about the live resource handles of every component
+    /// instance within this store.
+    ///
+    /// For more information see [`Store::component_resource_handles`].
+    #[cfg(feature = "component-model")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "component-model")))]
+    pub fn component_resource_handles(
+        &self,
+    ) -> Vec<(
+        crate::component::Instance,
+        Vec<crate::component::ResourceHandleInfo>,
+    )> {
+        crate::component::store_resource_handles(self.0)
+    }
+
     /// Returns remaining fuel in this store.
     ///
     /// For more information see [`Store::get_fuel`]
@@ -1224,6 +1281,114 @@ impl StoreOpaque {
         self.func_refs.push_instance_pre_func_refs(func_refs);
     }
 
+    /// Switches this store over to the code recompiled by tiered compilation
+    /// if all of the store's baseline modules have been recompiled, returning
+    /// the addresses of the previous code mapped to those of the recompiled
+    /// code if it did.
+    ///
+    /// This does nothing while any wasm of this store is executing, as the
+    /// baseline code can't return into the recompiled code.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn maybe_tier_up(&mut self) -> Option<std::collections::HashMap<usize, usize>> {
+        use crate::tier_up::CodeTier;
+
+        if self.modules.code_tier() != CodeTier::Baseline
+            || unsafe { *self.runtime_limits.stack_limit.get() } != usize::MAX
+        {
+            return None;
+        }
+
+        let mut tiered = Vec::new();
+        let mut pending = Vec::new();
+        for module in self.modules.all_modules().filter(|m| m.is_baseline()) {
+            match module.tiered() {
+                Some(optimized) => tiered.push((module.clone(), optimized.clone())),
+                None => pending.push(module.clone()),
+            }
+        }
+        if tiered.is_empty() {
+            return None;
+        }
+        if !pending.is_empty() {
+            // The store can only switch once all of its modules have been
+            // recompiled, so the rest of them are recompiled too now that one
+            // of them was hot.
+            for module in pending {
+                module.request_tier_up();
+            }
+            return None;
+        }
+
+        let mut redirects = std::collections::HashMap::new();
+        for (module, optimized) in tiered.iter() {
+            module.code_redirects(optimized, &mut redirects);
+            self.modules.register_module(optimized);
+        }
+        self.modules.set_code_tier(CodeTier::Optimized);
+        let redirect = |addr: usize| redirects.get(&addr).copied().unwrap_or(addr);
+
+        for instance in self.instances.iter_mut() {
+            let module_id = match instance.kind {
+                StoreInstanceKind::Real { module_id } => module_id,
+                StoreInstanceKind::Dummy => continue,
+            };
+            let module = self.modules.lookup_module_by_id(module_id).unwrap();
+            if let Some(optimized) = module.tiered() {
+                // Note that the runtime info of instances created from a
+                // snapshot is replaced as well, which is fine as the
+                // snapshot's memory images are only used to allocate them.
+                unsafe {
+                    instance
+                        .handle
+                        .switch_code(optimized.runtime_info(), &redirect);
+                }
+            }
+        }
+        self.func_refs.redirect(&redirect);
+        Some(redirects)
+    }
+
+    /// Returns the code to instantiate `module` with, if that's not the
+    /// module's own code, along with the addresses of the previous code mapped
+    /// to those of the returned code.
+    ///
+    /// With tiered compilation this is the recompiled code of baseline modules
+    /// once the store has switched to recompiled code, which may happen now.
+    /// `module` must have already been registered with this store.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn tiered_code(
+        &mut self,
+        module: &Module,
+    ) -> Result<Option<(Module, std::collections::HashMap<usize, usize>)>> {
+        use crate::tier_up::CodeTier;
+
+        if !module.is_baseline() {
+            return Ok(None);
+        }
+        match self.modules.code_tier() {
+            CodeTier::Any => Ok(None),
+            CodeTier::Baseline => Ok(self
+                .maybe_tier_up()
+                .map(|redirects| (module.tiered().unwrap().clone(), redirects))),
+            CodeTier::Optimized => {
+                let optimized = module.tiered_now()?;
+                let mut redirects = std::collections::HashMap::new();
+                module.code_redirects(&optimized, &mut redirects);
+                self.modules.register_module(&optimized);
+                Ok(Some((optimized, redirects)))
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "cranelift", feature = "winch")))]
+    pub(crate) fn tiered_code(
+        &mut self,
+        module: &Module,
+    ) -> Result<Option<(Module, std::collections::HashMap<usize, usize>)>> {
+        let _ = module;
+        Ok(None)
+    }
+
     pub(crate) fn host_globals(&mut self) -> &mut Vec<StoreBox<VMHostGlobalContext>> {
         &mut self.host_globals
     }
@@ -2236,6 +2401,9 @@ impl Drop for StoreOpaque {
 
             #[cfg(feature = "component-model")]
             {
+                if self.engine.config().component_resource_leak_reporting {
+                    crate::component::report_resource_leaks(self);
+                }
                 for _ in 0..self.num_component_instances {
                     allocator.decrement_component_instance_count();
                 }
@@ -17,6 +17,12 @@ pub struct FuncRefs {
     /// in.
     with_holes: Vec<SendSyncPtr<VMFuncRef>>,
 
+    /// Pointers into `self.bump` for entries whose `wasm_call` field has been
+    /// filled in, which is redirected if the store switches to other code
+    /// with tiered compilation.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    filled: Vec<SendSyncPtr<VMFuncRef>>,
+
     /// Pinned `VMFuncRef`s that had their `wasm_call` field
     /// pre-patched when constructing an `InstancePre`, and which we need to
     /// keep alive for our owning store's lifetime.
@@ -63,6 +69,8 @@ impl FuncRefs {
 
     /// Patch any `VMFuncRef::wasm_call`s that need filling in.
     pub fn fill(&mut self, modules: &ModuleRegistry) {
+        #[cfg(all(feature = "cranelift", feature = "winch"))]
+        let filled = &mut self.filled;
         self.with_holes.retain_mut(|f| {
             unsafe {
                 let func_ref = f.as_mut();
@@ -73,11 +81,28 @@ impl FuncRefs {
                 let _ = VMNativeCallHostFuncContext::from_opaque(func_ref.vmctx);
 
                 func_ref.wasm_call = modules.wasm_to_native_trampoline(func_ref.type_index);
+                #[cfg(all(feature = "cranelift", feature = "winch"))]
+                if func_ref.wasm_call.is_some() {
+                    filled.push(*f);
+                }
                 func_ref.wasm_call.is_none()
             }
         });
     }
 
+    /// Redirects the `wasm_call` trampolines which have been filled in so far
+    /// when the store switches to other code with tiered compilation.
+    #[cfg(all(feature = "cranelift", feature = "winch"))]
+    pub fn redirect(&mut self, redirect: &dyn Fn(usize) -> usize) {
+        for f in self.filled.iter_mut() {
+            unsafe {
+                let func_ref = f.as_mut();
+                let wasm_call = func_ref.wasm_call.unwrap().as_ptr() as usize;
+                func_ref.wasm_call = NonNull::new(redirect(wasm_call) as *mut _);
+            }
+        }
+    }
+
     /// Push pre-patched `VMFuncRef`s from an `InstancePre`.
     pub fn push_instance_pre_func_refs(&mut self, func_refs: Arc<[VMFuncRef]>) {
         self.instance_pre_func_refs.push(func_refs);
@@ -0,0 +1,261 @@
+//! Background recompilation of modules for
+//! [`Strategy::Tiered`](crate::Strategy::Tiered).
+//!
+//! Modules are first compiled with Winch, whose code decrements a counter of
+//! the called function on every call. Once a function's counter reaches zero
+//! the `tier_up` builtin is invoked, which hands the module's original wasm
+//! to the engine's [`TierUpPool`] to be recompiled with Cranelift. The whole
+//! module is recompiled at once.
+//!
+//! Winch and Cranelift code don't use the same internal calling convention,
+//! so code from one compiler can't call code from the other. Each store
+//! therefore runs code of a single [`CodeTier`]. Stores start out running
+//! Winch code and switch to Cranelift code as a whole once every module
+//! they've instantiated has been recompiled. The switch happens the next
+//! time the host calls into wasm while no wasm is running in the store, and
+//! patches the instances' function imports and `VMFuncRef`s so that existing
+//! instances, and the tables referring to their functions, run the
+//! Cranelift code from then on. Stores which are created after a module has
+//! been recompiled instantiate its Cranelift code right away.
+
+use crate::{Engine, Module};
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU32;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Recompiles the given wasm with the given optimizing compiler.
+type Compile = fn(&Engine, &dyn wasmtime_environ::Compiler, &[u8]) -> Result<Module>;
+
+/// The engine-wide configuration of tiered compilation.
+pub(crate) struct Tiering {
+    /// The optimizing compiler used to recompile hot modules, and to compile
+    /// components.
+    pub(crate) compiler: Box<dyn wasmtime_environ::Compiler>,
+    /// The threads which recompilations run on.
+    pub(crate) pool: Arc<TierUpPool>,
+    /// The number of calls of a function after which its module is
+    /// recompiled.
+    pub(crate) threshold: u32,
+}
+
+/// The code that a store runs when its engine uses tiered compilation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum CodeTier {
+    /// The engine doesn't use tiered compilation, so all code can be used.
+    #[default]
+    Any,
+    /// The store runs code compiled by the baseline compiler.
+    Baseline,
+    /// The store runs code recompiled by the optimizing compiler.
+    Optimized,
+}
+
+/// A bounded set of threads which run tier-up compilations for an engine.
+///
+/// Threads are started on demand, up to the configured maximum, and exit once
+/// there are no more queued compilations.
+pub(crate) struct TierUpPool {
+    max_threads: usize,
+    state: Mutex<PoolState>,
+}
+
+struct PoolState {
+    jobs: VecDeque<Job>,
+    threads: usize,
+}
+
+impl TierUpPool {
+    pub(crate) fn new(max_threads: usize) -> TierUpPool {
+        TierUpPool {
+            max_threads: max_threads.max(1),
+            state: Mutex::new(PoolState {
+                jobs: VecDeque::new(),
+                threads: 0,
+            }),
+        }
+    }
+
+    /// Queues `job` to run on one of this pool's threads.
+    fn submit(self: &Arc<Self>, job: Job) {
+        let mut state = self.state.lock().unwrap();
+        state.jobs.push_back(job);
+        if state.threads >= self.max_threads {
+            return;
+        }
+        state.threads += 1;
+        drop(state);
+
+        let pool = self.clone();
+        let spawned = std::thread::Builder::new()
+            .name("wasmtime-tier-up".to_string())
+            .spawn(move || pool.run());
+        if let Err(e) = spawned {
+            // If no other thread is around to pick up the job then it's run
+            // here instead, as otherwise it would never complete.
+            log::warn!("failed to spawn a tier-up thread: {e}");
+            self.run();
+        }
+    }
+
+    fn run(&self) {
+        loop {
+            let job = {
+                let mut state = self.state.lock().unwrap();
+                match state.jobs.pop_front() {
+                    Some(job) => job,
+                    None => {
+                        state.threads -= 1;
+                        return;
+                    }
+                }
+            };
+            job();
+        }
+    }
+}
+
+/// The tiering state of a module compiled with Winch.
+pub(crate) struct TierUp {
+    engine: Engine,
+    /// The call counters of the module's functions, indexed by
+    /// `DefinedFuncIndex`, which the Winch code decrements.
+    ///
+    /// The counters are shared by all instances of the module and compiled
+    /// code doesn't update them atomically, so concurrent calls may be
+    /// miscounted. That only delays the recompilation a little.
+    counters: Box<[AtomicU32]>,
+    /// The recompiled module, once it's available.
+    tiered: OnceCell<Module>,
+    state: Mutex<State>,
+    done: Condvar,
+}
+
+enum State {
+    /// Not hot enough to be recompiled yet, along with the original wasm to
+    /// recompile. The wasm isn't available for deserialized modules, which
+    /// are never recompiled.
+    Baseline { wasm: Option<Vec<u8>> },
+    /// Queued for or undergoing recompilation.
+    Compiling,
+    /// Recompiled with the optimizing compiler, see `TierUp::tiered`.
+    Ready,
+    /// Recompiling failed with the given error, so the baseline code is used
+    /// from now on.
+    Failed(String),
+}
+
+impl TierUp {
+    /// Creates the state for code with `funcs` defined functions which was
+    /// compiled from `wasm`, or returns `None` if `engine` doesn't use tiered
+    /// compilation.
+    pub(crate) fn new(engine: &Engine, wasm: Option<&[u8]>, funcs: usize) -> Option<Arc<TierUp>> {
+        let threshold = engine.tiering()?.threshold;
+        Some(Arc::new(TierUp {
+            engine: engine.clone(),
+            counters: (0..funcs).map(|_| AtomicU32::new(threshold)).collect(),
+            tiered: OnceCell::new(),
+            state: Mutex::new(State::Baseline {
+                wasm: wasm.map(|wasm| wasm.to_vec()),
+            }),
+            done: Condvar::new(),
+        }))
+    }
+
+    /// Returns the call counters which the baseline code decrements.
+    pub(crate) fn counters(&self) -> *mut u32 {
+        self.counters.as_ptr().cast::<u32>().cast_mut()
+    }
+
+    /// Queues the recompilation of the original wasm with `compile`, unless
+    /// it's already been recompiled or queued.
+    ///
+    /// This is invoked once a function of the module is hot.
+    pub(crate) fn hot(self: &Arc<Self>, compile: Compile) {
+        let tiering = self.engine.tiering().unwrap();
+        let Some(wasm) = self.start_compiling() else {
+            return;
+        };
+
+        // Only a weak reference is held by the job so that queued
+        // compilations of code which has since been dropped are skipped.
+        let tier_up = Arc::downgrade(self);
+        let engine = self.engine.clone();
+        tiering.pool.submit(Box::new(move || {
+            if Weak::strong_count(&tier_up) == 0 {
+                return;
+            }
+            let compiler = &*engine.tiering().unwrap().compiler;
+            let result = compile(&engine, compiler, &wasm);
+            if let Some(tier_up) = tier_up.upgrade() {
+                tier_up.finish(result);
+            }
+        }));
+    }
+
+    /// Recompiles the original wasm with `compile` on the current thread if
+    /// that hasn't happened yet, or waits for an ongoing recompilation, and
+    /// returns the recompiled module.
+    pub(crate) fn compile_now(&self, compile: Compile) -> Result<Module> {
+        if let Some(wasm) = self.start_compiling() {
+            let compiler = &*self.engine.tiering().unwrap().compiler;
+            self.finish(compile(&self.engine, compiler, &wasm));
+        }
+        if !self.wait()? {
+            bail!("a deserialized module can't be recompiled by tiered compilation");
+        }
+        Ok(self.tiered.get().unwrap().clone())
+    }
+
+    /// Moves to the `Compiling` state, returning the wasm to recompile, if
+    /// the module hasn't been recompiled yet.
+    fn start_compiling(&self) -> Option<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        match &mut *state {
+            State::Baseline { wasm } => {
+                let wasm = wasm.take()?;
+                *state = State::Compiling;
+                Some(wasm)
+            }
+            _ => None,
+        }
+    }
+
+    fn finish(&self, result: Result<Module>) {
+        let mut state = self.state.lock().unwrap();
+        *state = match result {
+            Ok(tiered) => {
+                let _ = self.tiered.set(tiered);
+                State::Ready
+            }
+            Err(e) => {
+                log::warn!("failed to tier up compiled code: {e:?}");
+                State::Failed(format!("{e:?}"))
+            }
+        };
+        self.done.notify_all();
+    }
+
+    /// Returns the recompiled module if it's ready.
+    pub(crate) fn ready(&self) -> Option<&Module> {
+        self.tiered.get()
+    }
+
+    /// Blocks until any in-progress recompilation finishes, returning whether
+    /// the code has been recompiled.
+    pub(crate) fn wait(&self) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            match &*state {
+                State::Baseline { .. } => return Ok(false),
+                State::Compiling => {}
+                State::Ready => return Ok(true),
+                State::Failed(e) => return Err(anyhow!("failed to tier up: {e}")),
+            }
+            state = self.done.wait(state).unwrap();
+        }
+    }
+}
@@ -81,6 +81,9 @@ pub fn link_component_spectest<T>(linker: &mut component::Linker<T>) -> Result<(
     )?;
     i.module("simple-module", &module)?;
 
+    i.value("value-u32", component::Val::U32(42))?;
+    i.value("value-string", component::Val::String("hello".into()))?;
+
     struct Resource1;
     struct Resource2;
 
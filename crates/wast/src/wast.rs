@@ -283,7 +283,17 @@ where
     }
 
     /// Get the value of an exported global from an instance.
+    ///
+    /// For component instances this instead gets the value of an exported
+    /// component-level value.
     fn get(&mut self, instance_name: Option<&str>, field: &str) -> Result<Outcome> {
+        #[cfg(feature = "component-model")]
+        if let (None, Some(InstanceKind::Component(i))) = (instance_name, &self.current) {
+            let value = i
+                .get_value(&mut self.store, field)
+                .ok_or_else(|| anyhow!("no value named `{field}`"))?;
+            return Ok(Outcome::Ok(Results::Component(vec![value])));
+        }
         let global = match self.get_export(instance_name, field)? {
             Export::Core(e) => e
                 .into_global()
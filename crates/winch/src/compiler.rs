@@ -7,7 +7,8 @@ use wasmparser::FuncValidatorAllocations;
 use wasmtime_cranelift_shared::{CompiledFunction, ModuleTextBuilder};
 use wasmtime_environ::{
     CompileError, DefinedFuncIndex, FilePos, FuncIndex, FunctionBodyData, FunctionLoc,
-    ModuleTranslation, ModuleTypes, PrimaryMap, TrapEncodingBuilder, VMOffsets, WasmFunctionInfo,
+    ModuleTranslation, ModuleTypes, PrimaryMap, TrapEncodingBuilder, Tunables, VMOffsets,
+    WasmFunctionInfo,
 };
 use winch_codegen::{BuiltinFunctions, TargetIsa, TrampolineKind};
 
@@ -23,6 +24,7 @@ struct CompilationContext {
 
 pub(crate) struct Compiler {
     isa: Box<dyn TargetIsa>,
+    tunables: Tunables,
     contexts: Mutex<Vec<CompilationContext>>,
 }
 
@@ -38,9 +40,10 @@ impl wasmtime_cranelift_shared::CompiledFuncEnv for CompiledFuncEnv {
 }
 
 impl Compiler {
-    pub fn new(isa: Box<dyn TargetIsa>) -> Self {
+    pub fn new(isa: Box<dyn TargetIsa>, tunables: Tunables) -> Self {
         Self {
             isa,
+            tunables,
             contexts: Mutex::new(Vec::new()),
         }
     }
@@ -72,8 +75,8 @@ impl wasmtime_environ::Compiler for Compiler {
         data: FunctionBodyData<'_>,
         types: &ModuleTypes,
     ) -> Result<(WasmFunctionInfo, Box<dyn Any + Send>), CompileError> {
-        let index = translation.module.func_index(index);
-        let sig = translation.module.functions[index].signature;
+        let func_index = translation.module.func_index(index);
+        let sig = translation.module.functions[func_index].signature;
         let ty = &types[sig];
         let FunctionBodyData { body, validator } = data;
         let start_srcloc = FilePos::new(
@@ -87,10 +90,12 @@ impl wasmtime_environ::Compiler for Compiler {
         let buffer = self
             .isa
             .compile_function(
+                index,
                 ty,
                 &body,
                 translation,
                 types,
+                &self.tunables,
                 &mut context.builtins,
                 &mut validator,
             )
@@ -0,0 +1,570 @@
+//! Generation of C headers for the component model C API of Wasmtime.
+//!
+//! The generated header contains, for a world:
+//!
+//! * A `*_imports_t` structure with a callback for every imported function,
+//!   along with a `*_add_to_linker` function defining all of them in a
+//!   `wasmtime_component_linker_t`.
+//! * A typed wrapper for every exported function which looks up the export in
+//!   a `wasmtime_component_instance_t` and calls it.
+//!
+//! Scalar parameters and results use their native C types while all other
+//! values are passed as `wasmtime_component_val_t`.
+
+use crate::source::Source;
+use heck::*;
+use std::fmt::Write as _;
+use wit_parser::*;
+
+/// Generates a C header for the `world` within `resolve`.
+pub fn generate_c_header(resolve: &Resolve, world: WorldId) -> String {
+    let world = &resolve.worlds[world];
+    let prefix = to_c_ident(&world.name);
+    let guard = format!("{}_H", prefix.to_shouty_snake_case());
+    let mut gen = CHeader {
+        resolve,
+        prefix,
+        src: Source::default(),
+    };
+
+    uwriteln!(
+        gen.src,
+        "// Generated by wasmtime-wit-bindgen from the `{}` world. DO NOT EDIT!",
+        world.name
+    );
+    uwriteln!(gen.src, "#ifndef {guard}");
+    uwriteln!(gen.src, "#define {guard}");
+    uwriteln!(gen.src, "");
+    uwriteln!(gen.src, "#include <wasmtime.h>");
+    uwriteln!(gen.src, "");
+    uwriteln!(gen.src, "#ifdef __cplusplus");
+    // Written directly so that the contents of the block aren't indented.
+    gen.src.as_mut_string().push_str("extern \"C\" {\n");
+    uwriteln!(gen.src, "#endif");
+
+    gen.imports(world);
+    for (key, item) in world.exports.iter() {
+        match item {
+            WorldItem::Function(func) => gen.export_func(None, func),
+            WorldItem::Interface(id) => {
+                let name = resolve.name_world_key(key);
+                for func in resolve.interfaces[*id].functions.values() {
+                    gen.export_func(Some(&name), func);
+                }
+            }
+            WorldItem::Type(_) => {}
+        }
+    }
+
+    uwriteln!(gen.src, "");
+    uwriteln!(gen.src, "#ifdef __cplusplus");
+    uwriteln!(gen.src, "}}  // extern \"C\"");
+    uwriteln!(gen.src, "#endif");
+    uwriteln!(gen.src, "");
+    uwriteln!(gen.src, "#endif // {guard}");
+    gen.src.into()
+}
+
+struct CHeader<'a> {
+    resolve: &'a Resolve,
+    prefix: String,
+    src: Source,
+}
+
+/// The C representation of a WIT type.
+enum CType {
+    /// A scalar which is passed as the given C type, stored in the given
+    /// field of `wasmtime_component_valunion_t` with the given kind.
+    Scalar {
+        ty: &'static str,
+        field: &'static str,
+        kind: &'static str,
+    },
+    /// Any other value, passed as a `wasmtime_component_val_t`.
+    Val,
+}
+
+impl CHeader<'_> {
+    fn imports(&mut self, world: &World) {
+        let mut root = Vec::new();
+        let mut interfaces = Vec::new();
+        for (key, item) in world.imports.iter() {
+            match item {
+                WorldItem::Function(func) => root.push(func),
+                WorldItem::Interface(id) => {
+                    let iface = &self.resolve.interfaces[*id];
+                    if !iface.functions.is_empty() {
+                        interfaces.push((self.resolve.name_world_key(key), iface));
+                    }
+                }
+                WorldItem::Type(_) => {}
+            }
+        }
+        if root.is_empty() && interfaces.is_empty() {
+            return;
+        }
+        let prefix = self.prefix.clone();
+
+        for (name, iface) in interfaces.iter() {
+            let ident = to_c_ident(name);
+            uwriteln!(self.src, "");
+            uwriteln!(
+                self.src,
+                "/// Host implementations of the functions imported from `{name}`."
+            );
+            uwriteln!(self.src, "typedef struct {prefix}_{ident}_imports {{");
+            for func in iface.functions.values() {
+                self.import_field(func);
+            }
+            uwriteln!(self.src, "}} {prefix}_{ident}_imports_t;");
+        }
+
+        uwriteln!(self.src, "");
+        uwriteln!(
+            self.src,
+            "/// Host implementations of the functions imported by the `{}` world.",
+            world.name
+        );
+        uwriteln!(self.src, "typedef struct {prefix}_imports {{");
+        uwriteln!(
+            self.src,
+            "/// Argument passed to all of the callbacks below."
+        );
+        uwriteln!(self.src, "void *env;");
+        for func in root.iter() {
+            self.import_field(func);
+        }
+        for (name, _) in interfaces.iter() {
+            let ident = to_c_ident(name);
+            uwriteln!(self.src, "/// Functions imported from `{name}`.");
+            uwriteln!(self.src, "{prefix}_{ident}_imports_t {ident};");
+        }
+        uwriteln!(self.src, "}} {prefix}_imports_t;");
+
+        uwriteln!(self.src, "");
+        uwriteln!(self.src, "/**");
+        uwriteln!(
+            self.src,
+            " * \\brief Defines all of the functions in `imports` within `linker`."
+        );
+        uwriteln!(self.src, " *");
+        uwriteln!(
+            self.src,
+            " * The types of the functions are taken from the imports of `component`,"
+        );
+        uwriteln!(
+            self.src,
+            " * see #wasmtime_component_linker_instance_add_func."
+        );
+        uwriteln!(self.src, " */");
+        uwriteln!(
+            self.src,
+            "static inline wasmtime_error_t *{prefix}_add_to_linker("
+        );
+        uwriteln!(self.src, "  wasmtime_component_linker_t *linker,");
+        uwriteln!(self.src, "  const wasmtime_component_t *component,");
+        uwriteln!(self.src, "  const {prefix}_imports_t *imports");
+        uwriteln!(self.src, ") {{");
+        uwriteln!(
+            self.src,
+            "wasmtime_component_linker_instance_t *root = wasmtime_component_linker_root(linker);"
+        );
+        uwriteln!(self.src, "wasmtime_error_t *error = NULL;");
+        for func in root.iter() {
+            self.add_func(
+                "root",
+                func,
+                &format!("imports->{}", to_c_ident(&func.name)),
+            );
+        }
+        for (name, iface) in interfaces.iter() {
+            let ident = to_c_ident(name);
+            uwriteln!(self.src, "if (error == NULL) {{");
+            uwriteln!(
+                self.src,
+                "wasmtime_component_linker_instance_t *instance = NULL;"
+            );
+            uwriteln!(
+                self.src,
+                "error = wasmtime_component_linker_instance_add_instance(root, \"{name}\", {}, &instance);",
+                name.len()
+            );
+            for func in iface.functions.values() {
+                let field = format!("imports->{ident}.{}", to_c_ident(&func.name));
+                self.add_func("instance", func, &field);
+            }
+            uwriteln!(self.src, "if (instance != NULL)");
+            uwriteln!(
+                self.src,
+                "  wasmtime_component_linker_instance_delete(instance);"
+            );
+            uwriteln!(self.src, "}}");
+        }
+        uwriteln!(self.src, "wasmtime_component_linker_instance_delete(root);");
+        uwriteln!(self.src, "return error;");
+        uwriteln!(self.src, "}}");
+    }
+
+    fn import_field(&mut self, func: &Function) {
+        uwriteln!(
+            self.src,
+            "/// Implementation of `{}`.",
+            self.signature(func)
+        );
+        uwriteln!(self.src, "///");
+        uwriteln!(
+            self.src,
+            "/// Receives {} argument(s) and must produce {} result(s).",
+            func.params.len(),
+            func.results.len()
+        );
+        uwriteln!(
+            self.src,
+            "wasmtime_component_func_callback_t {};",
+            to_c_ident(&func.name)
+        );
+    }
+
+    fn add_func(&mut self, instance: &str, func: &Function, callback: &str) {
+        uwriteln!(self.src, "if (error == NULL)");
+        uwriteln!(
+            self.src,
+            "  error = wasmtime_component_linker_instance_add_func({instance}, component, \"{}\", {}, {callback}, imports->env, NULL);",
+            func.name,
+            func.name.len(),
+        );
+    }
+
+    fn export_func(&mut self, interface: Option<&str>, func: &Function) {
+        let prefix = self.prefix.clone();
+        let fn_name = match interface {
+            Some(name) => format!("{prefix}_{}_{}", to_c_ident(name), to_c_ident(&func.name)),
+            None => format!("{prefix}_{}", to_c_ident(&func.name)),
+        };
+        let results = match &func.results {
+            Results::Named(results) => results
+                .iter()
+                .map(|(name, ty)| (to_c_param(name), *ty))
+                .collect::<Vec<_>>(),
+            Results::Anon(ty) => vec![("ret".to_string(), *ty)],
+        };
+
+        uwriteln!(self.src, "");
+        uwriteln!(self.src, "/**");
+        match interface {
+            Some(name) => uwriteln!(
+                self.src,
+                " * \\brief Calls `{}` exported from `{name}`.",
+                self.signature(func)
+            ),
+            None => uwriteln!(
+                self.src,
+                " * \\brief Calls the exported `{}`.",
+                self.signature(func)
+            ),
+        }
+        if let Some(docs) = &func.docs.contents {
+            uwriteln!(self.src, " *");
+            for line in docs.trim().lines() {
+                uwriteln!(self.src, " * {}", line.trim());
+            }
+        }
+        uwriteln!(self.src, " *");
+        uwriteln!(
+            self.src,
+            " * Arguments remain owned by the caller. Results passed as"
+        );
+        uwriteln!(
+            self.src,
+            " * #wasmtime_component_val_t are owned by the caller on success and must"
+        );
+        uwriteln!(
+            self.src,
+            " * be released with #wasmtime_component_val_delete. Errors and traps are"
+        );
+        uwriteln!(self.src, " * reported as in #wasmtime_component_func_call.");
+        uwriteln!(self.src, " */");
+
+        uwriteln!(self.src, "static inline wasmtime_error_t *{fn_name}(");
+        uwriteln!(self.src, "  wasmtime_context_t *store,");
+        uwriteln!(self.src, "  const wasmtime_component_instance_t *instance,");
+        for (name, ty) in func.params.iter() {
+            match self.c_type(ty) {
+                CType::Scalar { ty, .. } => {
+                    uwriteln!(self.src, "  {ty} {},", to_c_param(name))
+                }
+                CType::Val => uwriteln!(
+                    self.src,
+                    "  const wasmtime_component_val_t *{},",
+                    to_c_param(name)
+                ),
+            }
+        }
+        for (name, ty) in results.iter() {
+            match self.c_type(ty) {
+                CType::Scalar { ty, .. } => uwriteln!(self.src, "  {ty} *{name},"),
+                CType::Val => uwriteln!(self.src, "  wasmtime_component_val_t *{name},"),
+            }
+        }
+        uwriteln!(self.src, "  wasm_trap_t **trap");
+        uwriteln!(self.src, ") {{");
+
+        uwriteln!(self.src, "wasmtime_component_func_t func;");
+        let lookup = match interface {
+            Some(name) => format!(
+                "wasmtime_component_instance_get_instance_func(store, instance, \"{name}\", {}, \"{}\", {}, &func)",
+                name.len(),
+                func.name,
+                func.name.len(),
+            ),
+            None => format!(
+                "wasmtime_component_instance_get_func(store, instance, \"{}\", {}, &func)",
+                func.name,
+                func.name.len(),
+            ),
+        };
+        let export = match interface {
+            Some(name) => format!("{name}#{}", func.name),
+            None => func.name.clone(),
+        };
+        uwriteln!(self.src, "if (!{lookup})");
+        uwriteln!(
+            self.src,
+            "  return wasmtime_error_new(\"export `{export}` not found\");"
+        );
+
+        let args = if func.params.is_empty() {
+            "NULL".to_string()
+        } else {
+            uwriteln!(
+                self.src,
+                "wasmtime_component_val_t args[{}];",
+                func.params.len()
+            );
+            for (i, (name, ty)) in func.params.iter().enumerate() {
+                let name = to_c_param(name);
+                match self.c_type(ty) {
+                    CType::Scalar { field, kind, .. } => {
+                        uwriteln!(self.src, "args[{i}].kind = {kind};");
+                        uwriteln!(self.src, "args[{i}].of.{field} = {name};");
+                    }
+                    CType::Val => uwriteln!(self.src, "args[{i}] = *{name};"),
+                }
+            }
+            "args".to_string()
+        };
+        let rets = if results.is_empty() {
+            "NULL".to_string()
+        } else {
+            uwriteln!(
+                self.src,
+                "wasmtime_component_val_t results[{}];",
+                results.len()
+            );
+            "results".to_string()
+        };
+        uwriteln!(self.src, "*trap = NULL;");
+        uwriteln!(
+            self.src,
+            "wasmtime_error_t *error = wasmtime_component_func_call(store, &func, {args}, {}, {rets}, {}, trap);",
+            func.params.len(),
+            results.len()
+        );
+        uwriteln!(self.src, "if (error != NULL || *trap != NULL)");
+        uwriteln!(self.src, "  return error;");
+        for (i, (name, ty)) in results.iter().enumerate() {
+            match self.c_type(ty) {
+                CType::Scalar { field, .. } => {
+                    uwriteln!(self.src, "*{name} = results[{i}].of.{field};")
+                }
+                CType::Val => uwriteln!(self.src, "*{name} = results[{i}];"),
+            }
+        }
+        uwriteln!(self.src, "return NULL;");
+        uwriteln!(self.src, "}}");
+    }
+
+    fn c_type(&self, ty: &Type) -> CType {
+        let (ty, field, kind) = match ty {
+            Type::Bool => ("bool", "boolean", "WASMTIME_COMPONENT_BOOL"),
+            Type::S8 => ("int8_t", "s8", "WASMTIME_COMPONENT_S8"),
+            Type::U8 => ("uint8_t", "u8", "WASMTIME_COMPONENT_U8"),
+            Type::S16 => ("int16_t", "s16", "WASMTIME_COMPONENT_S16"),
+            Type::U16 => ("uint16_t", "u16", "WASMTIME_COMPONENT_U16"),
+            Type::S32 => ("int32_t", "s32", "WASMTIME_COMPONENT_S32"),
+            Type::U32 => ("uint32_t", "u32", "WASMTIME_COMPONENT_U32"),
+            Type::S64 => ("int64_t", "s64", "WASMTIME_COMPONENT_S64"),
+            Type::U64 => ("uint64_t", "u64", "WASMTIME_COMPONENT_U64"),
+            Type::Float32 => ("float", "float32", "WASMTIME_COMPONENT_FLOAT32"),
+            Type::Float64 => ("double", "float64", "WASMTIME_COMPONENT_FLOAT64"),
+            Type::Char => ("uint32_t", "character", "WASMTIME_COMPONENT_CHAR"),
+            Type::String => return CType::Val,
+            Type::Id(id) => match &self.resolve.types[*id].kind {
+                TypeDefKind::Type(t) => return self.c_type(t),
+                _ => return CType::Val,
+            },
+        };
+        CType::Scalar { ty, field, kind }
+    }
+
+    /// Returns the WIT signature of `func`, used in documentation.
+    fn signature(&self, func: &Function) -> String {
+        let mut sig = format!("{}: func(", func.name);
+        for (i, (name, ty)) in func.params.iter().enumerate() {
+            if i > 0 {
+                sig.push_str(", ");
+            }
+            uwrite!(sig, "{name}: {}", self.wit_type(ty));
+        }
+        sig.push(')');
+        match &func.results {
+            Results::Anon(ty) => uwrite!(sig, " -> {}", self.wit_type(ty)),
+            Results::Named(results) if results.is_empty() => {}
+            Results::Named(results) => {
+                sig.push_str(" -> (");
+                for (i, (name, ty)) in results.iter().enumerate() {
+                    if i > 0 {
+                        sig.push_str(", ");
+                    }
+                    uwrite!(sig, "{name}: {}", self.wit_type(ty));
+                }
+                sig.push(')');
+            }
+        }
+        sig
+    }
+
+    fn wit_type(&self, ty: &Type) -> String {
+        let id = match ty {
+            Type::Bool => return "bool".to_string(),
+            Type::U8 => return "u8".to_string(),
+            Type::U16 => return "u16".to_string(),
+            Type::U32 => return "u32".to_string(),
+            Type::U64 => return "u64".to_string(),
+            Type::S8 => return "s8".to_string(),
+            Type::S16 => return "s16".to_string(),
+            Type::S32 => return "s32".to_string(),
+            Type::S64 => return "s64".to_string(),
+            Type::Float32 => return "float32".to_string(),
+            Type::Float64 => return "float64".to_string(),
+            Type::Char => return "char".to_string(),
+            Type::String => return "string".to_string(),
+            Type::Id(id) => *id,
+        };
+        let ty = &self.resolve.types[id];
+        if let Some(name) = &ty.name {
+            return name.clone();
+        }
+        let opt = |ty: &Option<Type>| match ty {
+            Some(ty) => self.wit_type(ty),
+            None => "_".to_string(),
+        };
+        match &ty.kind {
+            TypeDefKind::Type(t) => self.wit_type(t),
+            TypeDefKind::List(t) => format!("list<{}>", self.wit_type(t)),
+            TypeDefKind::Option(t) => format!("option<{}>", self.wit_type(t)),
+            TypeDefKind::Result(r) => match (&r.ok, &r.err) {
+                (None, None) => "result".to_string(),
+                (ok, err) => format!("result<{}, {}>", opt(ok), opt(err)),
+            },
+            TypeDefKind::Tuple(t) => {
+                let types = t.types.iter().map(|t| self.wit_type(t));
+                format!("tuple<{}>", types.collect::<Vec<_>>().join(", "))
+            }
+            TypeDefKind::Handle(Handle::Own(r)) => {
+                format!("own<{}>", self.wit_type(&Type::Id(*r)))
+            }
+            TypeDefKind::Handle(Handle::Borrow(r)) => {
+                format!("borrow<{}>", self.wit_type(&Type::Id(*r)))
+            }
+            TypeDefKind::Future(t) => format!("future<{}>", opt(t)),
+            TypeDefKind::Stream(s) => format!("stream<{}, {}>", opt(&s.element), opt(&s.end)),
+            kind => kind.as_str().to_string(),
+        }
+    }
+}
+
+/// Converts a WIT name, such as `ns:pkg/iface` or `[method]res.name`, to a C
+/// identifier.
+fn to_c_ident(name: &str) -> String {
+    name.to_snake_case()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Same as `to_c_ident` but additionally avoids the names of C keywords and
+/// the fixed parameters of generated functions.
+fn to_c_param(name: &str) -> String {
+    let ident = to_c_ident(name);
+    match ident.as_str() {
+        "auto" | "break" | "case" | "char" | "const" | "continue" | "default" | "do" | "double"
+        | "else" | "enum" | "extern" | "float" | "for" | "goto" | "if" | "inline" | "int"
+        | "long" | "register" | "restrict" | "return" | "short" | "signed" | "sizeof"
+        | "static" | "struct" | "switch" | "typedef" | "union" | "unsigned" | "void"
+        | "volatile" | "while" | "bool" | "store" | "instance" | "trap" | "func" | "args"
+        | "results" | "error" => format!("{ident}_"),
+        _ => ident,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(wit: &str) -> String {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), wit).unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        generate_c_header(&resolve, world)
+    }
+
+    #[test]
+    fn imports_and_exports() {
+        let header = generate(
+            "
+                package demo:app;
+
+                interface logging {
+                    log: func(msg: string);
+                }
+
+                interface math {
+                    record point { x: s32, y: s32 }
+                    add: func(a: u32, b: u32) -> u32;
+                    norm: func(p: point) -> float64;
+                }
+
+                world calc {
+                    import logging;
+                    import now: func() -> u64;
+                    export math;
+                    export run: func(args: list<string>) -> result<_, string>;
+                }
+            ",
+        );
+
+        assert!(header.contains("#ifndef CALC_H"));
+        assert!(header.contains("typedef struct calc_demo_app_logging_imports {"));
+        assert!(header.contains("wasmtime_component_func_callback_t log;"));
+        assert!(header.contains("wasmtime_component_func_callback_t now;"));
+        assert!(header.contains("calc_demo_app_logging_imports_t demo_app_logging;"));
+        assert!(header.contains(
+            "wasmtime_component_linker_instance_add_instance(root, \"demo:app/logging\", 16, &instance);"
+        ));
+        assert!(header.contains("imports->demo_app_logging.log"));
+
+        assert!(header.contains("static inline wasmtime_error_t *calc_demo_app_math_add("));
+        assert!(header.contains("  uint32_t a,\n  uint32_t b,\n  uint32_t *ret,\n"));
+        assert!(header.contains("  const wasmtime_component_val_t *p,\n  double *ret,\n"));
+        assert!(header.contains(
+            "wasmtime_component_instance_get_instance_func(store, instance, \"demo:app/math\", 13, \"add\", 3, &func)"
+        ));
+
+        assert!(header.contains("static inline wasmtime_error_t *calc_run("));
+        assert!(header.contains("const wasmtime_component_val_t *args_,"));
+        assert!(header.contains("`run: func(args: list<string>) -> result<_, string>`"));
+        assert!(header.contains("args[0] = *args_;"));
+    }
+}
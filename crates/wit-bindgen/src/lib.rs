@@ -22,9 +22,11 @@ macro_rules! uwriteln {
     };
 }
 
+mod c;
 mod rust;
 mod source;
 mod types;
+pub use c::generate_c_header;
 use source::Source;
 
 #[derive(Clone)]
@@ -108,6 +110,11 @@ pub struct Opts {
     /// Remapping of interface names to rust module names.
     /// TODO: is there a better type to use for the value of this map?
     pub with: HashMap<String, String>,
+
+    /// Interfaces which are both imported and exported by the world whose
+    /// imports should be implemented by forwarding to another instance's
+    /// exports, mapped to the name of the generated forwarding struct.
+    pub forward: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone)]
@@ -263,6 +270,10 @@ impl Wasmtime {
             assert!(prev.is_none());
         }
 
+        if let Err(e) = self.check_forwards(resolve, id) {
+            panic!("{e}");
+        }
+
         let world = &resolve.worlds[id];
         for (name, import) in world.imports.iter() {
             if !self.opts.only_interfaces || matches!(import, WorldItem::Interface(_)) {
@@ -278,6 +289,106 @@ impl Wasmtime {
         self.finish(resolve, id)
     }
 
+    /// Checks that every interface in [`Opts::forward`] can be forwarded,
+    /// which requires it to be both imported and exported by the world and
+    /// to only use types which can be passed from one store to another.
+    fn check_forwards(&self, resolve: &Resolve, world: WorldId) -> anyhow::Result<()> {
+        let world = &resolve.worlds[world];
+        let mut forwarded = HashSet::new();
+        for (key, item) in world.imports.iter() {
+            let id = match item {
+                WorldItem::Interface(id) => *id,
+                _ => continue,
+            };
+            let name = match self.lookup_forward(resolve, key) {
+                Some(name) => name,
+                None => continue,
+            };
+            forwarded.insert(name);
+            let key_name = resolve.name_world_key(key);
+
+            if self.lookup_replacement(resolve, key, None).is_some() {
+                bail!("interface `{key_name}` cannot be both remapped with `with` and forwarded");
+            }
+            match world.exports.get(key) {
+                Some(WorldItem::Interface(export)) if *export == id => {}
+                _ => {
+                    bail!("interface `{key_name}` must be exported by the world to be forwarded");
+                }
+            }
+            if !matches!(self.opts.ownership, Ownership::Owning) {
+                bail!("forwarding interface `{key_name}` requires `Owning` ownership");
+            }
+            if get_resources(resolve, id).next().is_some() {
+                bail!("forwarding interface `{key_name}` with resources is not supported");
+            }
+
+            for (_, func) in resolve.interfaces[id].functions.iter() {
+                let mut types = func
+                    .params
+                    .iter()
+                    .map(|(_, ty)| ty)
+                    .chain(func.results.iter_types());
+                if types.any(|ty| match ty {
+                    Type::Id(id) => self.types.get(*id).has_handle,
+                    _ => false,
+                }) {
+                    bail!(
+                        "forwarding function `{}` of `{key_name}` with handles is not supported",
+                        func.name
+                    );
+                }
+                if self.has_trappable_error(resolve, &func.results) {
+                    bail!(
+                        "forwarding function `{}` of `{key_name}` with a trappable error \
+                         is not supported",
+                        func.name
+                    );
+                }
+                if self.opts.async_.maybe_async() && !self.opts.async_.is_import_async(&func.name) {
+                    bail!(
+                        "forwarding function `{}` of `{key_name}` must be async since exports \
+                         are async",
+                        func.name
+                    );
+                }
+            }
+        }
+
+        // Any remaining entries didn't match an imported interface.
+        let mut unused = self
+            .opts
+            .forward
+            .iter()
+            .filter(|(_, name)| !forwarded.contains(name.as_str()))
+            .collect::<Vec<_>>();
+        unused.sort();
+        if let Some((key, _)) = unused.first() {
+            bail!("interface `{key}` must be imported by the world to be forwarded");
+        }
+        Ok(())
+    }
+
+    /// Returns whether `results` is a `result` whose error is one of the
+    /// trappable error types, see `InterfaceGenerator::special_case_trappable_error`.
+    fn has_trappable_error(&self, resolve: &Resolve, results: &Results) -> bool {
+        let mut types = results.iter_types();
+        let id = match (types.next(), types.next()) {
+            (Some(Type::Id(id)), None) => id,
+            _ => return false,
+        };
+        match &resolve.types[*id].kind {
+            TypeDefKind::Result(Result_ {
+                err: Some(Type::Id(err)),
+                ..
+            }) => {
+                let err = resolve_type_definition_id(resolve, *err);
+                self.trappable_errors.contains_key(&err)
+            }
+            _ => false,
+        }
+    }
+
     fn import(&mut self, resolve: &Resolve, world: WorldId, name: &WorldKey, item: &WorldItem) {
         let mut gen = InterfaceGenerator::new(self, resolve);
         match item {
@@ -304,6 +415,10 @@ impl Wasmtime {
                 let key_name = resolve.name_world_key(name);
 
                 gen.generate_add_to_linker(*id, &key_name);
+                if let Some(forward) = gen.gen.lookup_forward(resolve, name) {
+                    let forward = forward.to_string();
+                    gen.generate_forward(*id, name, &forward);
+                }
 
                 let module = &gen.src[..];
 
@@ -358,10 +473,24 @@ impl Wasmtime {
             }
             WorldItem::Type(_) => unreachable!(),
             WorldItem::Interface(id) => {
+                // Forwarded interfaces reuse the type definitions of the
+                // import so values can be passed straight through.
+                // `check_forwards` ensured that these were imported without
+                // being remapped.
+                let import_path = match gen.gen.lookup_forward(resolve, name) {
+                    Some(_) => match gen.gen.interface_names.get(id) {
+                        Some(InterfaceName::Path(path)) => Some(path.clone()),
+                        _ => unreachable!(),
+                    },
+                    None => None,
+                };
                 gen.gen.interface_last_seen_as_import.insert(*id, false);
                 gen.gen.name_interface(resolve, *id, name, true);
                 gen.current_interface = Some((*id, name, true));
-                gen.types(*id);
+                match import_path {
+                    Some(path) => gen.reexport_types(*id, &path),
+                    None => gen.types(*id),
+                }
                 let iface = &resolve.interfaces[*id];
                 let iface_name = match name {
                     WorldKey::Name(name) => name,
@@ -666,39 +795,52 @@ impl Wasmtime {
         key: &WorldKey,
         item: Option<&str>,
     ) -> Option<&str> {
-        // First try to lookup the exact name of the interface as specified
-        // via `name_world_key`.
-        let name = resolve.name_world_key(key);
-        let candidate1 = match item {
-            Some(item) => format!("{name}/{item}"),
-            None => name.clone(),
-        };
-        if let Some(ret) = self.opts.with.get(&candidate1) {
-            return Some(ret);
-        }
-
-        // .. if the above failed allow omitting the `@...` version information
-        // and see if there's a `with` key for that.
-        //
-        // NB: this means that in a scenario where there's packages with the
-        // same name/namespace where one has a version and one doesn't there's
-        // no way to use `with` to specify just one and not the other. That
-        // should be ok for now, but this should ideally detect a situation like
-        // that and omit this fallback in such a situation.
-        let version_start = name.find('@')?;
-        let name = &name[..version_start];
-        let candidate2 = match item {
-            Some(item) => format!("{name}/{item}"),
-            None => name.to_string(),
-        };
-        if let Some(ret) = self.opts.with.get(&candidate2) {
-            return Some(ret);
-        }
+        lookup_world_key(&self.opts.with, resolve, key, item)
+    }
 
-        None
+    fn lookup_forward(&self, resolve: &Resolve, key: &WorldKey) -> Option<&str> {
+        lookup_world_key(&self.opts.forward, resolve, key, None)
     }
 }
 
+fn lookup_world_key<'a>(
+    map: &'a HashMap<String, String>,
+    resolve: &Resolve,
+    key: &WorldKey,
+    item: Option<&str>,
+) -> Option<&'a str> {
+    // First try to lookup the exact name of the interface as specified
+    // via `name_world_key`.
+    let name = resolve.name_world_key(key);
+    let candidate1 = match item {
+        Some(item) => format!("{name}/{item}"),
+        None => name.clone(),
+    };
+    if let Some(ret) = map.get(&candidate1) {
+        return Some(ret);
+    }
+
+    // .. if the above failed allow omitting the `@...` version information
+    // and see if there's a key for that.
+    //
+    // NB: this means that in a scenario where there's packages with the
+    // same name/namespace where one has a version and one doesn't there's
+    // no way to use `with` to specify just one and not the other. That
+    // should be ok for now, but this should ideally detect a situation like
+    // that and omit this fallback in such a situation.
+    let version_start = name.find('@')?;
+    let name = &name[..version_start];
+    let candidate2 = match item {
+        Some(item) => format!("{name}/{item}"),
+        None => name.to_string(),
+    };
+    if let Some(ret) = map.get(&candidate2) {
+        return Some(ret);
+    }
+
+    None
+}
+
 impl Wasmtime {
     fn has_world_trait(&self, resolve: &Resolve, world: WorldId) -> bool {
         !self.import_functions.is_empty() || get_world_resources(resolve, world).count() > 0
@@ -1620,6 +1762,154 @@ impl<'a> InterfaceGenerator<'a> {
         uwriteln!(self.src, "}}");
     }
 
+    /// Generates a struct named `name` which implements the `Host` trait of
+    /// the imported interface `id` by calling the same interface exported
+    /// from an instance in a separate store.
+    ///
+    /// The interface is expected to have been validated by
+    /// `Wasmtime::check_forwards`.
+    fn generate_forward(&mut self, id: InterfaceId, key: &WorldKey, name: &str) {
+        let iface = &self.resolve.interfaces[id];
+        let key_name = self.resolve.name_world_key(key);
+
+        let mut guest = self.path_to_root();
+        guest.push_str("exports::");
+        let iface_name = match key {
+            WorldKey::Name(name) => name,
+            WorldKey::Interface(_) => {
+                let pkg = iface.package.unwrap();
+                let pkgname = &self.resolve.packages[pkg].name;
+                uwrite!(
+                    guest,
+                    "{}::{}::",
+                    pkgname.namespace.to_snake_case(),
+                    self.gen.name_package_module(self.resolve, pkg),
+                );
+                iface.name.as_ref().unwrap()
+            }
+        };
+        uwrite!(
+            guest,
+            "{}::{}",
+            iface_name.to_snake_case(),
+            to_rust_upper_camel_case(iface_name)
+        );
+
+        uwriteln!(
+            self.src,
+            "
+                /// Implementation of [`Host`] which forwards all calls to
+                /// the `{key_name}` export of an instance within `store`.
+                pub struct {name}<T> {{
+                    pub store: wasmtime::Store<T>,
+                    pub guest: {guest},
+                }}
+
+                impl<T> {name}<T> {{
+                    pub fn new(store: wasmtime::Store<T>, guest: {guest}) -> Self {{
+                        {name} {{ store, guest }}
+                    }}
+                }}
+            "
+        );
+
+        let (bounds, await_) = if self.gen.opts.async_.maybe_async() {
+            uwriteln!(self.src, "#[wasmtime::component::__internal::async_trait]");
+            (": Send", ".await")
+        } else {
+            ("", "")
+        };
+        uwriteln!(self.src, "impl<T{bounds}> Host for {name}<T> {{");
+        for (_, func) in iface.functions.iter() {
+            self.generate_function_trait_sig(func);
+            // Turn the `;` of the trait signature into a body.
+            let len = self.src.as_mut_string().trim_end().len() - 1;
+            self.src.as_mut_string().truncate(len);
+            uwrite!(
+                self.src,
+                "{{ self.guest.call_{}(&mut self.store, ",
+                func.item_name().to_snake_case()
+            );
+            for (name, ty) in func.params.iter() {
+                let name = to_rust_ident(name);
+                let arg = self.forward_arg(ty, &name);
+                uwrite!(self.src, "{arg}, ");
+            }
+            uwriteln!(self.src, "){await_} }}");
+        }
+        uwriteln!(self.src, "}}");
+    }
+
+    /// Returns an expression converting the owned parameter `name` of a host
+    /// import into the borrowed form taken by the matching guest export.
+    fn forward_arg(&self, ty: &Type, name: &str) -> String {
+        match ty {
+            Type::String => format!("&{name}"),
+            Type::Id(id) if self.info(*id).has_list => {
+                let ty = &self.resolve.types[*id];
+                if ty.name.is_some() || matches!(ty.kind, TypeDefKind::List(_)) {
+                    format!("&{name}")
+                } else {
+                    self.forward_borrow(&Type::Id(*id), &format!("&{name}"))
+                }
+            }
+            _ => name.to_string(),
+        }
+    }
+
+    /// Same as `forward_arg`, but `expr` is a reference to the owned value.
+    fn forward_borrow(&self, ty: &Type, expr: &str) -> String {
+        let id = match ty {
+            Type::String => return format!("({expr}).as_str()"),
+            Type::Id(id) if self.info(*id).has_list => *id,
+            _ => return format!("*({expr})"),
+        };
+        let ty = &self.resolve.types[id];
+        if ty.name.is_some() {
+            return expr.to_string();
+        }
+        let optional = |ty: Option<&Type>| match ty {
+            Some(ty) => format!("|e| {}", self.forward_borrow(ty, "e")),
+            None => "|_| ()".to_string(),
+        };
+        match &ty.kind {
+            TypeDefKind::List(_) => format!("({expr}).as_slice()"),
+            TypeDefKind::Option(t) => {
+                format!("({expr}).as_ref().map({})", optional(Some(t)))
+            }
+            TypeDefKind::Result(r) => format!(
+                "({expr}).as_ref().map({}).map_err({})",
+                optional(r.ok.as_ref()),
+                optional(r.err.as_ref()),
+            ),
+            TypeDefKind::Tuple(t) => {
+                let mut ret = "(".to_string();
+                for (i, ty) in t.types.iter().enumerate() {
+                    ret.push_str(&self.forward_borrow(ty, &format!("&({expr}).{i}")));
+                    ret.push_str(", ");
+                }
+                ret.push(')');
+                ret
+            }
+            TypeDefKind::Type(t) => self.forward_borrow(t, expr),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Re-exports the types of the imported interface at `path` in place of
+    /// generating new definitions for the exported interface `id`.
+    fn reexport_types(&mut self, id: InterfaceId, path: &[String]) {
+        let root = self.path_to_root();
+        for (name, _) in self.resolve.interfaces[id].types.iter() {
+            uwriteln!(
+                self.src,
+                "pub use {root}{}::{};",
+                path.join("::"),
+                name.to_upper_camel_case()
+            );
+        }
+    }
+
     fn generate_add_function_to_linker(&mut self, owner: TypeOwner, func: &Function, linker: &str) {
         uwrite!(
             self.src,
@@ -1928,7 +2218,9 @@ impl<'a> InterfaceGenerator<'a> {
 
         uwriteln!(
             self.src,
-            "callee.post_return{async__}(store.as_context_mut()){await_}?;"
+            "if callee.func().auto_post_return(store.as_context()) {{
+                callee.post_return{async__}(store.as_context_mut()){await_}?;
+            }}"
         );
 
         self.src.push_str("Ok(");
@@ -0,0 +1,190 @@
+/*
+Example of instantiating a WebAssembly component, defining a host function it
+imports, and calling its exported function with component model values.
+
+You can compile and run this example on Linux with:
+
+   cargo build --release -p wasmtime-c-api
+   cc examples/component.c \
+       -I crates/c-api/include \
+       -I crates/c-api/wasm-c-api/include \
+       target/release/libwasmtime.a \
+       -lpthread -ldl -lm \
+       -o component
+   ./component
+
+Note that on Windows and macOS the command will be similar, but you'll need
+to tweak the `-lpthread` and such annotations as well as the name of the
+`libwasmtime.a` file on Windows.
+
+You can also build using cmake:
+
+mkdir build && cd build && cmake .. && cmake --build . --target wasmtime-component
+*/
+
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <wasm.h>
+#include <wasmtime.h>
+
+static void exit_with_error(const char *message, wasmtime_error_t *error, wasm_trap_t *trap);
+
+// Fills in `val` with a `point` record. The field names are copied into
+// memory owned by the value, so everything is released by
+// `wasmtime_component_val_delete`.
+static void point_new(wasmtime_component_val_t *val, int32_t x, int32_t y) {
+  val->kind = WASMTIME_COMPONENT_RECORD;
+  wasmtime_component_val_record_new_uninitialized(&val->of.record, 2);
+  wasmtime_component_val_record_field_t *fields = val->of.record.data;
+  wasm_name_new_from_string(&fields[0].name, "x");
+  fields[0].val.kind = WASMTIME_COMPONENT_S32;
+  fields[0].val.of.s32 = x;
+  wasm_name_new_from_string(&fields[1].name, "y");
+  fields[1].val.kind = WASMTIME_COMPONENT_S32;
+  fields[1].val.of.s32 = y;
+}
+
+static wasm_trap_t* report_callback(
+    void *env,
+    wasmtime_context_t *store,
+    const wasmtime_component_val_t *args,
+    size_t nargs,
+    wasmtime_component_val_t *results,
+    size_t nresults
+) {
+  // The `point` record arrives with its fields in the order its type
+  // declares them.
+  assert(nargs == 1);
+  assert(nresults == 0);
+  assert(args[0].kind == WASMTIME_COMPONENT_RECORD);
+  const wasmtime_component_val_record_t *point = &args[0].of.record;
+  assert(point->size == 2);
+  for (size_t i = 0; i < point->size; i++) {
+    const wasmtime_component_val_record_field_t *field = &point->data[i];
+    assert(field->val.kind == WASMTIME_COMPONENT_S32);
+    printf("> %.*s = %d\n", (int) field->name.size, field->name.data, field->val.of.s32);
+  }
+  return NULL;
+}
+
+int main() {
+  int ret = 0;
+  // Components must be enabled in the configuration before they can be
+  // compiled.
+  printf("Initializing...\n");
+  wasm_config_t *config = wasm_config_new();
+  assert(config != NULL);
+  wasmtime_config_wasm_component_model_set(config, true);
+  wasm_engine_t *engine = wasm_engine_new_with_config(config);
+  assert(engine != NULL);
+  wasmtime_store_t *store = wasmtime_store_new(engine, NULL, NULL);
+  assert(store != NULL);
+  wasmtime_context_t *context = wasmtime_store_context(store);
+
+  // Read our input file, which in this case is a component in the wasm text
+  // format.
+  FILE* file = fopen("examples/component.wat", "r");
+  assert(file != NULL);
+  fseek(file, 0L, SEEK_END);
+  size_t file_size = ftell(file);
+  fseek(file, 0L, SEEK_SET);
+  wasm_byte_vec_t wat;
+  wasm_byte_vec_new_uninitialized(&wat, file_size);
+  assert(fread(wat.data, file_size, 1, file) == 1);
+  fclose(file);
+
+  // Parse the wat into the binary wasm format
+  wasm_byte_vec_t wasm;
+  wasmtime_error_t *error = wasmtime_wat2wasm(wat.data, wat.size, &wasm);
+  if (error != NULL)
+    exit_with_error("failed to parse wat", error, NULL);
+  wasm_byte_vec_delete(&wat);
+
+  printf("Compiling component...\n");
+  wasmtime_component_t *component = NULL;
+  error = wasmtime_component_new(engine, (uint8_t*) wasm.data, wasm.size, &component);
+  wasm_byte_vec_delete(&wasm);
+  if (error != NULL)
+    exit_with_error("failed to compile component", error, NULL);
+
+  // Define the `report` function which the component imports. Its type is
+  // taken from the component's import of the same name.
+  printf("Defining imports...\n");
+  wasmtime_component_linker_t *linker = wasmtime_component_linker_new(engine);
+  wasmtime_component_linker_instance_t *root = wasmtime_component_linker_root(linker);
+  error = wasmtime_component_linker_instance_add_func(
+      root, component, "report", strlen("report"), report_callback, NULL, NULL);
+  if (error != NULL)
+    exit_with_error("failed to define `report`", error, NULL);
+  wasmtime_component_linker_instance_delete(root);
+
+  printf("Instantiating component...\n");
+  wasm_trap_t *trap = NULL;
+  wasmtime_component_instance_t instance;
+  error = wasmtime_component_linker_instantiate(linker, context, component, &instance, &trap);
+  if (error != NULL || trap != NULL)
+    exit_with_error("failed to instantiate", error, trap);
+
+  printf("Extracting export...\n");
+  wasmtime_component_func_t add;
+  bool ok = wasmtime_component_instance_get_func(context, &instance, "add", strlen("add"), &add);
+  assert(ok);
+
+  // Call `add` with two points. The arguments remain owned by us while the
+  // result is owned by us once the call succeeds.
+  printf("Calling export...\n");
+  wasmtime_component_val_t args[2];
+  point_new(&args[0], 1, 2);
+  point_new(&args[1], 30, 40);
+  wasmtime_component_val_t result;
+  error = wasmtime_component_func_call(context, &add, args, 2, &result, 1, &trap);
+  wasmtime_component_val_delete(&args[0]);
+  wasmtime_component_val_delete(&args[1]);
+  if (error != NULL || trap != NULL)
+    exit_with_error("failed to call function", error, trap);
+
+  assert(result.kind == WASMTIME_COMPONENT_S32);
+  printf("> sum = %d\n", result.of.s32);
+  assert(result.of.s32 == 73);
+  wasmtime_component_val_delete(&result);
+
+  // The post-return of `add` has already run, so it can be called again
+  // right away.
+  point_new(&args[0], -1, -2);
+  point_new(&args[1], 1, 2);
+  error = wasmtime_component_func_call(context, &add, args, 2, &result, 1, &trap);
+  wasmtime_component_val_delete(&args[0]);
+  wasmtime_component_val_delete(&args[1]);
+  if (error != NULL || trap != NULL)
+    exit_with_error("failed to call function again", error, trap);
+  assert(result.kind == WASMTIME_COMPONENT_S32);
+  assert(result.of.s32 == 0);
+  wasmtime_component_val_delete(&result);
+
+  // Clean up after ourselves at this point
+  printf("All finished!\n");
+  ret = 0;
+
+  wasmtime_component_linker_delete(linker);
+  wasmtime_component_delete(component);
+  wasmtime_store_delete(store);
+  wasm_engine_delete(engine);
+  return ret;
+}
+
+static void exit_with_error(const char *message, wasmtime_error_t *error, wasm_trap_t *trap) {
+  fprintf(stderr, "error: %s\n", message);
+  wasm_byte_vec_t error_message;
+  if (error != NULL) {
+    wasmtime_error_message(error, &error_message);
+    wasmtime_error_delete(error);
+  } else {
+    wasm_trap_message(trap, &error_message);
+    wasm_trap_delete(trap);
+  }
+  fprintf(stderr, "%.*s\n", (int) error_message.size, error_message.data);
+  wasm_byte_vec_delete(&error_message);
+  exit(1);
+}
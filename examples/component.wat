@@ -0,0 +1,25 @@
+(component
+  (type $point' (record (field "x" s32) (field "y" s32)))
+  (import "point" (type $point (eq $point')))
+  (import "report" (func $report (param "p" $point)))
+
+  (core module $m
+    (import "host" "report" (func $report (param i32 i32)))
+    (func (export "add") (param i32 i32 i32 i32) (result i32)
+      (local $x i32)
+      (local $y i32)
+      (local.set $x (i32.add (local.get 0) (local.get 2)))
+      (local.set $y (i32.add (local.get 1) (local.get 3)))
+      (call $report (local.get $x) (local.get $y))
+      (i32.add (local.get $x) (local.get $y))
+    )
+  )
+
+  (core func $report (canon lower (func $report)))
+  (core instance $host (export "report" (func $report)))
+  (core instance $i (instantiate $m (with "host" (instance $host))))
+
+  (func $add (param "a" $point) (param "b" $point) (result s32)
+    (canon lift (core func $i "add")))
+  (export "add" (func $add))
+)
@@ -81,10 +81,13 @@ fn execute_one(data: &[u8]) -> Result<()> {
     // When fuzzing Winch, explicitly override the compiler strategy, which by
     // default its arbitrary implementation unconditionally returns
     // `Cranelift`.
-    // We also explicitly disable multi-value support.
+    // We also explicitly disable SIMD support on targets other than x64,
+    // where Winch doesn't support it.
     if fuzz_winch {
         config.wasmtime.compiler_strategy = CompilerStrategy::Winch;
-        config.module_config.config.multi_value_enabled = false;
+        if !cfg!(target_arch = "x86_64") {
+            config.module_config.config.simd_enabled = false;
+        }
     }
 
     // Choose an engine that Wasmtime will be differentially executed against.
@@ -277,9 +280,7 @@ impl RuntimeStats {
 }
 
 // Returns true if the module only contains operators supported by
-// Winch. Winch's x86_64 target has broader support for Wasm operators
-// than the aarch64 target. This list assumes fuzzing on the x86_64
-// target.
+// Winch.
 fn winch_supports_module(module: &[u8]) -> bool {
     use wasmparser::{Operator::*, Parser, Payload};
 
@@ -399,7 +400,70 @@ fn winch_supports_module(module: &[u8]) -> bool {
                         | TableFill { .. }
                         | TableGrow { .. }
                         | TableSize { .. }
-                        | TableInit { .. } => {}
+                        | TableInit { .. }
+                        | I32Load { .. }
+                        | I32Load8S { .. }
+                        | I32Load8U { .. }
+                        | I32Load16S { .. }
+                        | I32Load16U { .. }
+                        | I64Load { .. }
+                        | I64Load8S { .. }
+                        | I64Load8U { .. }
+                        | I64Load16S { .. }
+                        | I64Load16U { .. }
+                        | I64Load32S { .. }
+                        | I64Load32U { .. }
+                        | F32Load { .. }
+                        | F64Load { .. }
+                        | I32Store { .. }
+                        | I32Store8 { .. }
+                        | I32Store16 { .. }
+                        | I64Store { .. }
+                        | I64Store8 { .. }
+                        | I64Store16 { .. }
+                        | I64Store32 { .. }
+                        | F32Store { .. }
+                        | F64Store { .. }
+                        | I32WrapI64 { .. }
+                        | I64ExtendI32S { .. }
+                        | I64ExtendI32U { .. }
+                        | I32Extend8S { .. }
+                        | I32Extend16S { .. }
+                        | I64Extend8S { .. }
+                        | I64Extend16S { .. }
+                        | I64Extend32S { .. }
+                        | F32ConvertI32S { .. }
+                        | F32ConvertI32U { .. }
+                        | F32ConvertI64S { .. }
+                        | F32ConvertI64U { .. }
+                        | F64ConvertI32S { .. }
+                        | F64ConvertI32U { .. }
+                        | F64ConvertI64S { .. }
+                        | F64ConvertI64U { .. }
+                        | F32DemoteF64 { .. }
+                        | F64PromoteF32 { .. }
+                        | I32ReinterpretF32 { .. }
+                        | I64ReinterpretF64 { .. }
+                        | F32ReinterpretI32 { .. }
+                        | F64ReinterpretI64 { .. }
+                        | I32TruncF32S { .. }
+                        | I32TruncF32U { .. }
+                        | I32TruncF64S { .. }
+                        | I32TruncF64U { .. }
+                        | I64TruncF32S { .. }
+                        | I64TruncF32U { .. }
+                        | I64TruncF64S { .. }
+                        | I64TruncF64U { .. }
+                        | I32TruncSatF32S { .. }
+                        | I32TruncSatF32U { .. }
+                        | I32TruncSatF64S { .. }
+                        | I32TruncSatF64U { .. }
+                        | I64TruncSatF32S { .. }
+                        | I64TruncSatF32U { .. }
+                        | I64TruncSatF64S { .. }
+                        | I64TruncSatF64U { .. } => {}
+                        // Winch only supports SIMD on x64.
+                        op if cfg!(target_arch = "x86_64") && is_simd(&op) => {}
                         _ => {
                             supported = false;
                             break 'main;
@@ -413,3 +477,19 @@ fn winch_supports_module(module: &[u8]) -> bool {
 
     supported
 }
+
+macro_rules! define_is_simd {
+    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
+        // Returns true if the operator is part of the SIMD proposal, which
+        // doesn't include relaxed SIMD.
+        fn is_simd(op: &wasmparser::Operator) -> bool {
+            #[allow(unreachable_patterns)]
+            match op {
+                $(wasmparser::Operator::$op { .. } => stringify!($proposal) == "simd",)*
+                _ => false,
+            }
+        }
+    };
+}
+
+wasmparser::for_each_operator!(define_is_simd);
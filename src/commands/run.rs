@@ -25,7 +25,7 @@ use wasmtime_wasi_nn::WasiNnCtx;
 use wasmtime_wasi_threads::WasiThreadsCtx;
 
 #[cfg(feature = "wasi-http")]
-use wasmtime_wasi_http::WasiHttpCtx;
+use wasmtime_wasi_http::{policy::PolicyState, pool::ConnectionPool, WasiHttpCtx};
 
 fn parse_env_var(s: &str) -> Result<(String, Option<String>)> {
     let mut parts = s.splitn(2, '=');
@@ -80,6 +80,11 @@ pub struct RunCommand {
     vars: Vec<(String, Option<String>)>,
 
     /// The name of the function to run
+    ///
+    /// For components the function's arguments are passed as part of this
+    /// argument using the WAVE text syntax, for example
+    /// `--invoke 'my-func("hello", [1, 2, 3], {a: 5})'`. Functions exported
+    /// from an exported instance are named as `instance#func`.
     #[clap(long, value_name = "FUNCTION")]
     invoke: Option<String>,
 
@@ -92,6 +97,28 @@ pub struct RunCommand {
     )]
     preloads: Vec<(String, PathBuf)>,
 
+    /// Record the inputs the guest receives through WASI to a trace file.
+    ///
+    /// Clock readings, random numbers, stdin, name lookups, the data received
+    /// on outgoing TCP connections and the responses to outgoing HTTP
+    /// requests are recorded so the run can be reproduced later with
+    /// `--replay`. This requires WASI preview2 and can't be combined with
+    /// `--dir`, as the filesystem isn't recorded.
+    ///
+    /// While recording, binding TCP sockets and creating UDP sockets fail
+    /// with a `not-supported` error.
+    #[clap(long, value_name = "TRACE", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Replay a trace written by `--record`.
+    ///
+    /// The guest receives the recorded inputs instead of accessing the host's
+    /// clocks, randomness, stdin and network. Network access must still be
+    /// granted with the same options as when recording, but no traffic is
+    /// sent.
+    #[clap(long, value_name = "TRACE")]
+    replay: Option<PathBuf>,
+
     /// The WebAssembly module to run and arguments to pass to it.
     ///
     /// Arguments passed to the wasm module will be configured as WASI CLI
@@ -210,14 +237,22 @@ impl RunCommand {
         }
 
         // Load the main wasm module.
-        match self
+        let result = self
             .load_main_module(&mut store, &mut linker, &main, modules)
             .with_context(|| {
                 format!(
                     "failed to run main module `{}`",
                     self.module_and_args[0].to_string_lossy()
                 )
-            }) {
+            });
+
+        // The trace needs to be complete before the process possibly exits
+        // below.
+        if let Some(recorder) = &store.data().recorder {
+            recorder.flush()?;
+        }
+
+        match result {
             Ok(()) => (),
             Err(e) => {
                 // Exit the process if Wasmtime understands the error;
@@ -390,7 +425,10 @@ impl RunCommand {
                 CliLinker::Core(linker) => {
                     linker.define_unknown_imports_as_traps(module.unwrap_core())?;
                 }
-                _ => bail!("cannot use `--trap-unknown-imports` with components"),
+                #[cfg(feature = "component-model")]
+                CliLinker::Component(linker) => {
+                    linker.define_unknown_imports_as_traps(module.unwrap_component())?;
+                }
             }
             #[cfg(not(feature = "cranelift"))]
             bail!("support for `unknown-imports-trap` disabled at compile time");
@@ -446,26 +484,12 @@ impl RunCommand {
             }
             #[cfg(feature = "component-model")]
             CliLinker::Component(linker) => {
-                if self.invoke.is_some() {
-                    bail!("using `--invoke` with components is not supported");
-                }
-
                 let component = module.unwrap_component();
 
-                let (command, _instance) =
-                    preview2::command::sync::Command::instantiate(&mut *store, component, linker)?;
-                let result = command
-                    .wasi_cli_run()
-                    .call_run(&mut *store)
-                    .context("failed to invoke `run` function")
-                    .map_err(|e| self.handle_core_dump(&mut *store, e));
-
-                // Translate the `Result<(),()>` produced by wasm into a feigned
-                // explicit exit here with status 1 if `Err(())` is returned.
-                result.and_then(|wasm_result| match wasm_result {
-                    Ok(()) => Ok(()),
-                    Err(()) => Err(wasmtime_wasi::I32Exit(1).into()),
-                })
+                match &self.invoke {
+                    Some(call) => self.invoke_component_func(store, component, linker, call),
+                    None => self.run_component_command(store, component, linker),
+                }
             }
         };
         finish_epoch_handler(store);
@@ -473,6 +497,29 @@ impl RunCommand {
         result
     }
 
+    #[cfg(feature = "component-model")]
+    fn run_component_command(
+        &self,
+        store: &mut Store<Host>,
+        component: &wasmtime::component::Component,
+        linker: &wasmtime::component::Linker<Host>,
+    ) -> Result<()> {
+        let (command, _instance) =
+            preview2::command::sync::Command::instantiate(&mut *store, component, linker)?;
+        let result = command
+            .wasi_cli_run()
+            .call_run(&mut *store)
+            .context("failed to invoke `run` function")
+            .map_err(|e| self.handle_core_dump(&mut *store, e));
+
+        // Translate the `Result<(),()>` produced by wasm into a feigned
+        // explicit exit here with status 1 if `Err(())` is returned.
+        result.and_then(|wasm_result| match wasm_result {
+            Ok(()) => Ok(()),
+            Err(()) => Err(wasmtime_wasi::I32Exit(1).into()),
+        })
+    }
+
     fn invoke_func(&self, store: &mut Store<Host>, func: Func) -> Result<()> {
         let ty = func.ty(&store);
         if ty.params().len() > 0 {
@@ -548,6 +595,54 @@ impl RunCommand {
         Ok(())
     }
 
+    #[cfg(feature = "component-model")]
+    fn invoke_component_func(
+        &self,
+        store: &mut Store<Host>,
+        component: &wasmtime::component::Component,
+        linker: &wasmtime::component::Linker<Host>,
+        call: &str,
+    ) -> Result<()> {
+        use crate::wave;
+
+        if self.module_and_args.len() > 1 {
+            bail!(
+                "arguments to component functions must be passed as part of \
+                 `--invoke`, for example `--invoke 'foo(1, \"bar\")'`"
+            );
+        }
+        let (name, args) = wave::split_func_call(call)?;
+
+        let instance = linker.instantiate(&mut *store, component).context(format!(
+            "failed to instantiate {:?}",
+            self.module_and_args[0]
+        ))?;
+        let func = {
+            let mut exports = instance.exports(&mut *store);
+            match name.rsplit_once('#') {
+                Some((instance, func)) => exports
+                    .instance(instance)
+                    .and_then(|mut instance| instance.func(func)),
+                None => exports.root().func(name),
+            }
+        }
+        .ok_or_else(|| anyhow!("no func export named `{}` found", name))?;
+
+        let params = wave::parse_args(args, &func.params(&*store))
+            .with_context(|| format!("failed to parse the arguments of `{}`", name))?;
+        let mut results = vec![wasmtime::component::Val::Bool(false); func.results(&*store).len()];
+        func.call(&mut *store, &params, &mut results)
+            .and_then(|()| func.post_return(&mut *store))
+            .with_context(|| format!("failed to invoke `{}`", name))
+            .map_err(|e| self.handle_core_dump(&mut *store, e))?;
+
+        for result in results.iter() {
+            println!("{}", wave::DisplayVal(result));
+        }
+
+        Ok(())
+    }
+
     #[cfg(feature = "coredump")]
     fn handle_core_dump(&self, store: &mut Store<Host>, err: Error) -> Error {
         let coredump_path = match &self.run.common.debug.coredump {
@@ -688,7 +783,11 @@ impl RunCommand {
                     }
                 }
 
-                store.data_mut().wasi_http = Some(Arc::new(WasiHttpCtx {}));
+                let policy = self.run.wasi_http_policy()?;
+                let host = store.data_mut();
+                host.wasi_http = Some(Arc::new(WasiHttpCtx));
+                host.wasi_http_policy = policy.map(|policy| Arc::new(PolicyState::new(policy)));
+                host.wasi_http_pool = self.run.wasi_http_pool();
             }
         }
 
@@ -696,6 +795,15 @@ impl RunCommand {
     }
 
     fn set_preview1_ctx(&self, store: &mut Store<Host>) -> Result<()> {
+        if self.run.common.wasi.deterministic == Some(true) {
+            bail!("-S deterministic requires the preview2 implementation of WASI (-S preview2)");
+        }
+        if self.record.is_some() || self.replay.is_some() {
+            bail!(
+                "--record and --replay require the preview2 implementation of WASI (-S preview2)"
+            );
+        }
+
         let mut builder = WasiCtxBuilder::new();
         builder.inherit_stdio().args(&self.compute_argv()?)?;
 
@@ -747,6 +855,23 @@ impl RunCommand {
             bail!("components do not support --tcplisten");
         }
 
+        if (self.record.is_some() || self.replay.is_some()) && !self.dirs.is_empty() {
+            bail!(
+                "--record and --replay cannot be combined with --dir, \
+                 as the filesystem isn't recorded"
+            );
+        }
+
+        if let Some(path) = &self.replay {
+            let replayer = preview2::record::Replayer::open(path)?;
+            builder.replay(&replayer)?;
+            store.data_mut().replayer = Some(replayer);
+        } else if let Some(path) = &self.record {
+            let recorder = preview2::record::Recorder::create(path)?;
+            builder.record(&recorder);
+            store.data_mut().recorder = Some(recorder);
+        }
+
         for (name, dir) in self.compute_preopen_dirs()? {
             builder.preopened_dir(
                 dir,
@@ -762,6 +887,14 @@ impl RunCommand {
         if let Some(enable) = self.run.common.wasi.allow_ip_name_lookup {
             builder.allow_ip_name_lookup(enable);
         }
+        if self.run.common.wasi.deterministic == Some(true) {
+            if self.run.common.wasi.inherit_network == Some(true)
+                || self.run.common.wasi.allow_ip_name_lookup == Some(true)
+            {
+                bail!("-S deterministic cannot be combined with network access");
+            }
+            builder.deterministic();
+        }
 
         store.data_mut().preview2_ctx = Some(Arc::new(builder.build()));
         Ok(())
@@ -788,9 +921,15 @@ struct Host {
     wasi_threads: Option<Arc<WasiThreadsCtx<Host>>>,
     #[cfg(feature = "wasi-http")]
     wasi_http: Option<Arc<WasiHttpCtx>>,
+    #[cfg(feature = "wasi-http")]
+    wasi_http_policy: Option<Arc<PolicyState>>,
+    #[cfg(feature = "wasi-http")]
+    wasi_http_pool: ConnectionPool,
     limits: StoreLimits,
     #[cfg(feature = "profiling")]
     guest_profiler: Option<Arc<wasmtime::GuestProfiler>>,
+    recorder: Option<preview2::record::Recorder>,
+    replayer: Option<preview2::record::Replayer>,
 }
 
 impl preview2::WasiView for Host {
@@ -832,6 +971,29 @@ impl wasmtime_wasi_http::types::WasiHttpView for Host {
     fn table(&mut self) -> &mut preview2::Table {
         Arc::get_mut(&mut self.preview2_table).expect("preview2 is not compatible with threads")
     }
+
+    fn outgoing_policy(&mut self) -> Option<&mut PolicyState> {
+        let policy = self.wasi_http_policy.as_mut()?;
+        Some(Arc::get_mut(policy).expect("preview2 is not compatible with threads"))
+    }
+
+    fn connection_pool(&mut self) -> ConnectionPool {
+        self.wasi_http_pool.clone()
+    }
+
+    fn send_request(
+        &mut self,
+        request: wasmtime_wasi_http::types::OutgoingRequest,
+    ) -> Result<wasmtime::component::Resource<wasmtime_wasi_http::types::HostFutureIncomingResponse>>
+    {
+        if let Some(replayer) = self.replayer.clone() {
+            wasmtime_wasi_http::record::replay_send_request(self, request, &replayer)
+        } else if let Some(recorder) = self.recorder.clone() {
+            wasmtime_wasi_http::record::record_send_request(self, request, &recorder)
+        } else {
+            wasmtime_wasi_http::types::default_send_request(self, request)
+        }
+    }
 }
 
 #[cfg(not(unix))]
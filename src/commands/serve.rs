@@ -12,7 +12,9 @@ use std::{
 use wasmtime::component::{InstancePre, Linker};
 use wasmtime::{Engine, Store, StoreLimits};
 use wasmtime_wasi::preview2::{Table, WasiCtx, WasiCtxBuilder, WasiView};
-use wasmtime_wasi_http::{body::HyperOutgoingBody, WasiHttpCtx, WasiHttpView};
+use wasmtime_wasi_http::{
+    body::HyperOutgoingBody, policy::PolicyState, pool::ConnectionPool, WasiHttpCtx, WasiHttpView,
+};
 
 #[cfg(feature = "wasi-nn")]
 use wasmtime_wasi_nn::WasiNnCtx;
@@ -21,6 +23,8 @@ struct Host {
     table: Table,
     ctx: WasiCtx,
     http: WasiHttpCtx,
+    http_policy: Option<PolicyState>,
+    http_pool: ConnectionPool,
 
     limits: StoreLimits,
 
@@ -54,6 +58,14 @@ impl WasiHttpView for Host {
     fn ctx(&mut self) -> &mut WasiHttpCtx {
         &mut self.http
     }
+
+    fn outgoing_policy(&mut self) -> Option<&mut PolicyState> {
+        self.http_policy.as_mut()
+    }
+
+    fn connection_pool(&mut self) -> ConnectionPool {
+        self.http_pool.clone()
+    }
 }
 
 const DEFAULT_ADDR: std::net::SocketAddr = std::net::SocketAddr::new(
@@ -135,7 +147,7 @@ impl ServeCommand {
         Ok(())
     }
 
-    fn new_store(&self, engine: &Engine) -> Result<Store<Host>> {
+    fn new_store(&self, engine: &Engine, pool: &ConnectionPool) -> Result<Store<Host>> {
         let mut builder = WasiCtxBuilder::new();
 
         // TODO: connect stdio to logging infrastructure
@@ -144,6 +156,8 @@ impl ServeCommand {
             table: Table::new(),
             ctx: builder.build(),
             http: WasiHttpCtx,
+            http_policy: self.run.wasi_http_policy()?.map(PolicyState::new),
+            http_pool: pool.clone(),
 
             limits: StoreLimits::default(),
 
@@ -298,6 +312,8 @@ struct ProxyHandlerInner {
     cmd: ServeCommand,
     engine: Engine,
     instance_pre: InstancePre<Host>,
+    /// Connections for outgoing requests, shared by all the stores.
+    pool: ConnectionPool,
 }
 
 #[derive(Clone)]
@@ -305,10 +321,12 @@ struct ProxyHandler(Arc<ProxyHandlerInner>);
 
 impl ProxyHandler {
     fn new(cmd: ServeCommand, engine: Engine, instance_pre: InstancePre<Host>) -> Self {
+        let pool = cmd.run.wasi_http_pool();
         Self(Arc::new(ProxyHandlerInner {
             cmd,
             engine,
             instance_pre,
+            pool,
         }))
     }
 }
@@ -329,7 +347,10 @@ impl hyper::service::Service<Request> for ProxyHandler {
 
         // TODO: need to track the join handle, but don't want to block the response on it
         tokio::task::spawn(async move {
-            let mut store = handler.0.cmd.new_store(&handler.0.engine)?;
+            let mut store = handler
+                .0
+                .cmd
+                .new_store(&handler.0.engine, &handler.0.pool)?;
 
             let req = store.data_mut().new_incoming_request(
                 req.map(|body| body.map_err(|e| anyhow::anyhow!(e)).boxed()),
@@ -97,6 +97,53 @@ impl RunCommon {
         limits.build()
     }
 
+    /// Creates the pool of connections used for outgoing wasi-http requests,
+    /// configured by the `-S http-*` connection options.
+    #[cfg(feature = "wasi-http")]
+    pub fn wasi_http_pool(&self) -> wasmtime_wasi_http::pool::ConnectionPool {
+        let wasi = &self.common.wasi;
+        let mut config = wasmtime_wasi_http::pool::PoolConfig::new();
+        if let Some(max) = wasi.http_max_idle_connections {
+            config.max_idle_per_authority(max);
+        }
+        if let Some(timeout) = wasi.http_idle_timeout {
+            config.idle_timeout(timeout);
+        }
+        if let Some(enable) = wasi.http2 {
+            config.http2(enable);
+        }
+        wasmtime_wasi_http::pool::ConnectionPool::new(config)
+    }
+
+    /// Creates the policy applied to outgoing wasi-http requests from the
+    /// `-S http-*` restrictions, if any are set.
+    #[cfg(feature = "wasi-http")]
+    pub fn wasi_http_policy(
+        &self,
+    ) -> Result<Option<std::sync::Arc<wasmtime_wasi_http::policy::OutgoingPolicy>>> {
+        let wasi = &self.common.wasi;
+        if wasi.http_allow.is_empty()
+            && wasi.http_max_requests.is_none()
+            && wasi.http_max_bytes.is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut policy = wasmtime_wasi_http::policy::OutgoingPolicy::new();
+        for pattern in wasi.http_allow.iter() {
+            policy
+                .allow(pattern)
+                .context("invalid `-S http-allow` option")?;
+        }
+        if let Some(max) = wasi.http_max_requests {
+            policy.max_requests(max);
+        }
+        if let Some(max) = wasi.http_max_bytes {
+            policy.max_bytes(max);
+        }
+        Ok(Some(std::sync::Arc::new(policy)))
+    }
+
     pub fn ensure_allow_precompiled(&self) -> Result<()> {
         if self.allow_precompiled {
             Ok(())
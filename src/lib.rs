@@ -13,3 +13,6 @@
 pub mod commands;
 
 pub(crate) mod common;
+
+#[cfg(feature = "component-model")]
+pub(crate) mod wave;
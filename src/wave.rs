@@ -0,0 +1,674 @@
+//! Parsing and printing of component model values using the WebAssembly
+//! Value Encoding (WAVE) text syntax.
+//!
+//! This is used by `wasmtime run --invoke` to call component exports from
+//! the command line, for example:
+//!
+//! ```text
+//! wasmtime run --invoke 'my-func("hello", [1, 2, 3], {a: 5})' component.wasm
+//! ```
+//!
+//! Values are always parsed according to the [`Type`] expected by the
+//! function, which resolves the ambiguities of the syntax (e.g. `{a, b}` is
+//! a value of a `flags` type and `{a: 1}` a value of a `record` type).
+
+use anyhow::{anyhow, bail, Error, Result};
+use std::fmt;
+use std::str::FromStr;
+use wasmtime::component::{Type, Val};
+
+/// Labels which must be prefixed with `%` to be used as the name of a case,
+/// field or flag, given that they are otherwise parsed as keywords.
+const KEYWORDS: &[&str] = &["true", "false", "some", "none", "ok", "err", "inf", "nan"];
+
+/// Splits a function call such as `foo(1, "bar")` into the name of the
+/// function and its (still unparsed) parenthesized arguments.
+pub fn split_func_call(call: &str) -> Result<(&str, &str)> {
+    let call = call.trim();
+    match call.find('(') {
+        Some(i) if i > 0 && call.ends_with(')') => Ok((call[..i].trim(), &call[i..])),
+        _ => bail!("expected a function call such as `{call}()`, found `{call}`"),
+    }
+}
+
+/// Parses the parenthesized arguments of a function call, such as
+/// `(1, "bar")`, according to the types of the function params.
+pub fn parse_args(args: &str, params: &[Type]) -> Result<Vec<Val>> {
+    let mut parser = Parser::new(args);
+    let mut values = Vec::with_capacity(params.len());
+    parser.seq('(', ')', |p| {
+        let ty = params
+            .get(values.len())
+            .ok_or_else(|| p.error(format!("expected {} arguments", params.len())))?;
+        values.push(p.value(ty)?);
+        Ok(())
+    })?;
+    if values.len() != params.len() {
+        bail!(
+            "expected {} arguments, found {}",
+            params.len(),
+            values.len()
+        );
+    }
+    parser.finish()?;
+    Ok(values)
+}
+
+/// A wrapper around [`Val`] to display it using the WAVE syntax.
+pub struct DisplayVal<'a>(pub &'a Val);
+
+impl fmt::Display for DisplayVal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Val::Bool(b) => write!(f, "{b}"),
+            Val::S8(i) => write!(f, "{i}"),
+            Val::U8(i) => write!(f, "{i}"),
+            Val::S16(i) => write!(f, "{i}"),
+            Val::U16(i) => write!(f, "{i}"),
+            Val::S32(i) => write!(f, "{i}"),
+            Val::U32(i) => write!(f, "{i}"),
+            Val::S64(i) => write!(f, "{i}"),
+            Val::U64(i) => write!(f, "{i}"),
+            Val::Float32(x) => write_float(f, *x),
+            Val::Float64(x) => write_float(f, *x),
+            Val::Char(c) => write_quoted(f, &c.to_string(), '\''),
+            Val::String(s) => write_quoted(f, s, '"'),
+            Val::List(list) => write_seq(f, '[', ']', list.iter().map(DisplayVal)),
+            Val::Record(record) => write_seq(
+                f,
+                '{',
+                '}',
+                record
+                    .fields()
+                    .map(|(name, val)| DisplayField(name, DisplayVal(val))),
+            ),
+            Val::Tuple(tuple) => write_seq(f, '(', ')', tuple.values().iter().map(DisplayVal)),
+            Val::Variant(variant) => write_case(f, variant.discriminant(), variant.payload()),
+            Val::Enum(e) => write_label(f, e.discriminant()),
+            Val::Option(option) => match option.value() {
+                Some(val) => write_keyword_case(f, "some", Some(val)),
+                None => f.write_str("none"),
+            },
+            Val::Result(result) => match result.value() {
+                Ok(val) => write_keyword_case(f, "ok", val),
+                Err(val) => write_keyword_case(f, "err", val),
+            },
+            Val::Flags(flags) => write_seq(f, '{', '}', flags.flags().map(DisplayLabel)),
+            Val::Resource(_) => f.write_str("<resource>"),
+        }
+    }
+}
+
+struct DisplayLabel<'a>(&'a str);
+
+impl fmt::Display for DisplayLabel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_label(f, self.0)
+    }
+}
+
+struct DisplayField<'a>(&'a str, DisplayVal<'a>);
+
+impl fmt::Display for DisplayField<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_label(f, self.0)?;
+        write!(f, ": {}", self.1)
+    }
+}
+
+fn write_float<T: fmt::Display + Into<f64> + Copy>(
+    f: &mut fmt::Formatter<'_>,
+    x: T,
+) -> fmt::Result {
+    let wide: f64 = x.into();
+    if wide.is_nan() {
+        f.write_str("nan")
+    } else if wide.is_infinite() {
+        f.write_str(if wide > 0.0 { "inf" } else { "-inf" })
+    } else {
+        write!(f, "{x}")
+    }
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str, quote: char) -> fmt::Result {
+    write!(f, "{quote}")?;
+    for c in s.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c == quote => write!(f, "\\{c}")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "{quote}")
+}
+
+fn write_seq<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    open: char,
+    close: char,
+    items: impl Iterator<Item = T>,
+) -> fmt::Result {
+    write!(f, "{open}")?;
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    write!(f, "{close}")
+}
+
+fn write_label(f: &mut fmt::Formatter<'_>, label: &str) -> fmt::Result {
+    if KEYWORDS.contains(&label) {
+        f.write_str("%")?;
+    }
+    f.write_str(label)
+}
+
+fn write_case(f: &mut fmt::Formatter<'_>, name: &str, payload: Option<&Val>) -> fmt::Result {
+    write_label(f, name)?;
+    write_payload(f, payload)
+}
+
+/// Writes a case of an option or result, whose name is a keyword rather
+/// than a label.
+fn write_keyword_case(
+    f: &mut fmt::Formatter<'_>,
+    keyword: &str,
+    payload: Option<&Val>,
+) -> fmt::Result {
+    f.write_str(keyword)?;
+    write_payload(f, payload)
+}
+
+fn write_payload(f: &mut fmt::Formatter<'_>, payload: Option<&Val>) -> fmt::Result {
+    match payload {
+        Some(val) => write!(f, "({})", DisplayVal(val)),
+        None => Ok(()),
+    }
+}
+
+/// A recursive descent parser of WAVE values, driven by their types.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn error(&self, msg: impl fmt::Display) -> Error {
+        anyhow!("{msg} at offset {} in `{}`", self.pos, self.src)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{c}`")))
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("unexpected trailing input")),
+        }
+    }
+
+    /// Parses a comma separated sequence of items between the `open` and
+    /// `close` delimiters, allowing a trailing comma.
+    fn seq(
+        &mut self,
+        open: char,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        self.expect(open)?;
+        loop {
+            if self.eat(close) {
+                return Ok(());
+            }
+            item(self)?;
+            if !self.eat(',') {
+                return self.expect(close);
+            }
+        }
+    }
+
+    /// Consumes the longest sequence of characters satisfying `pred`.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Parses a case, field or flag name, which may be prefixed with `%`
+    /// to disambiguate it from keywords.
+    fn label(&mut self) -> Result<&'a str> {
+        self.eat('%');
+        let label = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-');
+        if label.is_empty() {
+            return Err(self.error("expected a label"));
+        }
+        Ok(label)
+    }
+
+    fn number<T>(&mut self, ty: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        // Note that this also covers the `nan`, `inf` and `-inf` floats.
+        let token = self.take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'));
+        token
+            .parse()
+            .map_err(|e| self.error(format!("invalid {ty} `{token}`: {e}")))
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String> {
+        self.expect(quote)?;
+        let rest = self.rest();
+        let mut chars = rest.char_indices();
+        let mut out = String::new();
+        loop {
+            let (i, c) = chars
+                .next()
+                .ok_or_else(|| self.error("unterminated literal"))?;
+            match c {
+                c if c == quote => {
+                    self.pos += i + c.len_utf8();
+                    return Ok(out);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('\\') => out.push('\\'),
+                    Some('"') => out.push('"'),
+                    Some('\'') => out.push('\''),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => {
+                        let start = match chars.next() {
+                            Some((i, '{')) => i + 1,
+                            _ => return Err(self.error("expected `{` in unicode escape")),
+                        };
+                        let end = loop {
+                            match chars.next() {
+                                Some((i, '}')) => break i,
+                                Some(_) => {}
+                                None => return Err(self.error("unterminated unicode escape")),
+                            }
+                        };
+                        let c = u32::from_str_radix(&rest[start..end], 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("invalid unicode escape"))?;
+                        out.push(c);
+                    }
+                    _ => return Err(self.error("invalid escape")),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// Parses an optional parenthesized payload of the given type.
+    fn payload(&mut self, name: &str, ty: Option<Type>) -> Result<Option<Val>> {
+        match ty {
+            Some(ty) => {
+                self.expect('(')?;
+                let val = self.value(&ty)?;
+                self.expect(')')?;
+                Ok(Some(val))
+            }
+            None if self.peek() == Some('(') => {
+                Err(self.error(format!("unexpected payload for `{name}`")))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn value(&mut self, ty: &Type) -> Result<Val> {
+        Ok(match ty {
+            Type::Bool => match self.label()? {
+                "true" => Val::Bool(true),
+                "false" => Val::Bool(false),
+                other => return Err(self.error(format!("invalid bool `{other}`"))),
+            },
+            Type::S8 => Val::S8(self.number("s8")?),
+            Type::U8 => Val::U8(self.number("u8")?),
+            Type::S16 => Val::S16(self.number("s16")?),
+            Type::U16 => Val::U16(self.number("u16")?),
+            Type::S32 => Val::S32(self.number("s32")?),
+            Type::U32 => Val::U32(self.number("u32")?),
+            Type::S64 => Val::S64(self.number("s64")?),
+            Type::U64 => Val::U64(self.number("u64")?),
+            Type::Float32 => Val::Float32(self.number("float32")?),
+            Type::Float64 => Val::Float64(self.number("float64")?),
+            Type::Char => {
+                let s = self.quoted('\'')?;
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Val::Char(c),
+                    _ => return Err(self.error(format!("invalid char `{s}`"))),
+                }
+            }
+            Type::String => Val::String(self.quoted('"')?.into()),
+            Type::List(list) => {
+                let ty = list.ty();
+                let mut values = Vec::new();
+                self.seq('[', ']', |p| {
+                    values.push(p.value(&ty)?);
+                    Ok(())
+                })?;
+                list.new_val(values.into())?
+            }
+            Type::Record(record) => {
+                let fields = record.fields().collect::<Vec<_>>();
+                let mut values = vec![None; fields.len()];
+                self.seq('{', '}', |p| {
+                    let name = p.label()?;
+                    let index = fields
+                        .iter()
+                        .position(|f| f.name == name)
+                        .ok_or_else(|| p.error(format!("unknown field `{name}`")))?;
+                    if values[index].is_some() {
+                        return Err(p.error(format!("duplicate field `{name}`")));
+                    }
+                    p.expect(':')?;
+                    values[index] = Some(p.value(&fields[index].ty)?);
+                    Ok(())
+                })?;
+                // Fields of option types may be omitted, in which case they
+                // default to `none`.
+                let values = fields
+                    .iter()
+                    .zip(values)
+                    .map(|(field, val)| match (val, &field.ty) {
+                        (Some(val), _) => Ok((field.name, val)),
+                        (None, Type::Option(option)) => Ok((field.name, option.new_val(None)?)),
+                        (None, _) => Err(self.error(format!("missing field `{}`", field.name))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                record.new_val(values)?
+            }
+            Type::Tuple(tuple) => {
+                let types = tuple.types().collect::<Vec<_>>();
+                let mut values = Vec::with_capacity(types.len());
+                self.seq('(', ')', |p| {
+                    let ty = types
+                        .get(values.len())
+                        .ok_or_else(|| p.error(format!("expected {} values", types.len())))?;
+                    values.push(p.value(ty)?);
+                    Ok(())
+                })?;
+                if values.len() != types.len() {
+                    return Err(self.error(format!("expected {} values", types.len())));
+                }
+                tuple.new_val(values.into())?
+            }
+            Type::Variant(variant) => {
+                let name = self.label()?;
+                let case = variant
+                    .cases()
+                    .find(|c| c.name == name)
+                    .ok_or_else(|| self.error(format!("unknown case `{name}`")))?;
+                let payload = self.payload(name, case.ty)?;
+                variant.new_val(name, payload)?
+            }
+            Type::Enum(e) => e.new_val(self.label()?)?,
+            Type::Option(option) => match self.label()? {
+                "none" => option.new_val(None)?,
+                "some" => {
+                    let payload = self.payload("some", Some(option.ty()))?;
+                    option.new_val(payload)?
+                }
+                other => {
+                    return Err(self.error(format!("expected `some` or `none`, found `{other}`")))
+                }
+            },
+            Type::Result(result) => match self.label()? {
+                "ok" => {
+                    let payload = self.payload("ok", result.ok())?;
+                    result.new_val(Ok(payload))?
+                }
+                "err" => {
+                    let payload = self.payload("err", result.err())?;
+                    result.new_val(Err(payload))?
+                }
+                other => return Err(self.error(format!("expected `ok` or `err`, found `{other}`"))),
+            },
+            Type::Flags(flags) => {
+                let mut names = Vec::new();
+                self.seq('{', '}', |p| {
+                    names.push(p.label()?);
+                    Ok(())
+                })?;
+                flags.new_val(&names)?
+            }
+            Type::Own(_) | Type::Borrow(_) => {
+                bail!("resources cannot be passed as arguments from the command line")
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime::component::{Component, Linker};
+    use wasmtime::{Config, Engine, Store};
+
+    /// Returns the types of the params of a component function, which are
+    /// a string, a char, a list of variants, flags, a tuple and a variant.
+    fn param_types() -> Result<Vec<Type>> {
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        let engine = Engine::new(&config)?;
+        // The trailing padding param makes the params be passed through
+        // memory, so the core function's signature doesn't depend on them.
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                  (core module $m
+                    (memory (export "memory") 1)
+                    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                      i32.const 0)
+                    (func (export "f") (param i32)))
+                  (core instance $i (instantiate $m))
+
+                  (type $point' (record (field "x" s32) (field "y" s32)))
+                  (export $point "point" (type $point'))
+                  (type $perms' (flags "read" "write" "nan"))
+                  (export $perms "perms" (type $perms'))
+                  (type $shape' (variant
+                    (case "circle" float64)
+                    (case "square" $point)
+                    (case "nested" (option (result u8 (error string))))
+                    (case "empty")))
+                  (export $shape "shape" (type $shape'))
+
+                  (func (export "f")
+                    (param "a" string)
+                    (param "b" char)
+                    (param "c" (list $shape))
+                    (param "d" $perms)
+                    (param "e" (tuple u8 float32))
+                    (param "f" $shape)
+                    (param "pad" (tuple u64 u64 u64 u64 u64 u64 u64 u64 u64
+                                        u64 u64 u64 u64 u64 u64 u64 u64))
+                    (canon lift (core func $i "f")
+                      (memory $i "memory") (realloc (func $i "realloc"))))
+                )
+            "#,
+        )?;
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::new(&engine).instantiate(&mut store, &component)?;
+        let func = instance.get_func(&mut store, "f").unwrap();
+        let mut types = func.params(&store).into_vec();
+        types.pop();
+        Ok(types)
+    }
+
+    fn parse(ty: &Type, value: &str) -> Result<Val> {
+        let mut values = parse_args(&format!("({value})"), std::slice::from_ref(ty))?;
+        Ok(values.remove(0))
+    }
+
+    /// Checks that `value` is printed as `expected`, which parses back to
+    /// the same value.
+    fn round_trip(ty: &Type, value: &str, expected: &str) -> Result<()> {
+        let val = parse(ty, value)?;
+        let printed = DisplayVal(&val).to_string();
+        assert_eq!(printed, expected);
+        assert_eq!(parse(ty, &printed)?, val);
+        Ok(())
+    }
+
+    fn assert_error(ty: &Type, value: &str, msg: &str) {
+        match parse(ty, value) {
+            Ok(val) => panic!("`{value}` parsed as `{}`", DisplayVal(&val)),
+            Err(e) => assert!(e.to_string().contains(msg), "`{value}`: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trip_strings() -> Result<()> {
+        let types = param_types()?;
+        let (string, char) = (&types[0], &types[1]);
+        round_trip(string, r#""""#, r#""""#)?;
+        round_trip(
+            string,
+            r#""a\"b\\c\n\r\t\u{1f600}'""#,
+            "\"a\\\"b\\\\c\\n\\r\\t\u{1f600}'\"",
+        )?;
+        round_trip(string, r#""\u{7}""#, r#""\u{7}""#)?;
+        round_trip(char, r#"'\''"#, r#"'\''"#)?;
+        round_trip(char, r#"'"'"#, r#"'"'"#)?;
+        round_trip(char, r#"'\u{41}'"#, "'A'")?;
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_nested_variants() -> Result<()> {
+        let types = param_types()?;
+        let (list, shape) = (&types[2], &types[5]);
+        round_trip(shape, "circle(1.5)", "circle(1.5)")?;
+        round_trip(shape, "square({y: -2, x: 1})", "square({x: 1, y: -2})")?;
+        round_trip(shape, "nested(some(ok(3)))", "nested(some(ok(3)))")?;
+        round_trip(
+            shape,
+            r#"nested(some(err("no")))"#,
+            r#"nested(some(err("no")))"#,
+        )?;
+        round_trip(shape, "nested(none)", "nested(none)")?;
+        round_trip(shape, " empty ", "empty")?;
+        round_trip(
+            list,
+            "[circle(nan), circle(-inf), square({x: 0, y: 0}), empty,]",
+            "[circle(nan), circle(-inf), square({x: 0, y: 0}), empty]",
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_flags_and_tuples() -> Result<()> {
+        let types = param_types()?;
+        let (flags, tuple) = (&types[3], &types[4]);
+        round_trip(flags, "{}", "{}")?;
+        round_trip(flags, "{write, read}", "{read, write}")?;
+        // Labels which are keywords are escaped with `%`, which is optional
+        // where they can't be mistaken for a keyword.
+        round_trip(flags, "{%nan}", "{%nan}")?;
+        round_trip(flags, "{nan, write}", "{write, %nan}")?;
+        round_trip(tuple, "(255, inf)", "(255, inf)")?;
+        round_trip(tuple, "(0, -0.5)", "(0, -0.5)")?;
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_escapes() -> Result<()> {
+        let types = param_types()?;
+        let (string, char) = (&types[0], &types[1]);
+        assert_error(string, r#""\q""#, "invalid escape");
+        assert_error(string, r#""\u41""#, "expected `{` in unicode escape");
+        assert_error(string, r#""\u{41""#, "unterminated unicode escape");
+        assert_error(string, r#""\u{zz}""#, "invalid unicode escape");
+        assert_error(string, r#""\u{d800}""#, "invalid unicode escape");
+        assert_error(string, r#""\u{110000}""#, "invalid unicode escape");
+        assert_error(string, r#""abc"#, "unterminated literal");
+        assert_error(string, "abc", "expected `\"`");
+        assert_error(char, "'ab'", "invalid char");
+        assert_error(char, "''", "invalid char");
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_variants() -> Result<()> {
+        let types = param_types()?;
+        let shape = &types[5];
+        assert_error(shape, "triangle", "unknown case `triangle`");
+        assert_error(shape, "circle", "expected `(`");
+        assert_error(shape, "empty(1)", "unexpected payload for `empty`");
+        assert_error(shape, "square(1)", "expected `{`");
+        assert_error(shape, "square({x: 1})", "missing field `y`");
+        assert_error(shape, "square({x: 1, x: 2})", "duplicate field `x`");
+        assert_error(shape, "square({x: 1, y: 2, z: 3})", "unknown field `z`");
+        assert_error(shape, "nested(some(ok))", "expected `(`");
+        assert_error(shape, "nested(some(maybe(1)))", "expected `ok` or `err`");
+        assert_error(shape, "nested(some(ok(256)))", "invalid u8 `256`");
+        assert_error(shape, "nested(some(ok(1))", "expected `)`");
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_flags_and_args() -> Result<()> {
+        let types = param_types()?;
+        let (flags, tuple) = (&types[3], &types[4]);
+        assert!(parse(flags, "{read, exec}").is_err());
+        assert_error(flags, "{read", "expected `}`");
+        assert_error(flags, "{read,, write}", "expected a label");
+        assert_error(tuple, "(1)", "expected 2 values");
+        assert_error(tuple, "(1, 2, 3)", "expected 2 values");
+
+        assert!(parse_args("((1, 2.5)) extra", &types[4..5]).is_err());
+        assert!(parse_args("((1, 2.5), (1, 2.5))", &types[4..5]).is_err());
+        assert!(parse_args("", &types[4..5]).is_err());
+        assert_eq!(split_func_call(" f(1, 2) ")?, ("f", "(1, 2)"));
+        assert!(split_func_call("f").is_err());
+        assert!(split_func_call("(1)").is_err());
+        Ok(())
+    }
+}
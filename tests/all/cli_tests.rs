@@ -790,6 +790,40 @@ fn run_basic_component() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "component-model"), ignore)]
+fn run_component_invoke() -> Result<()> {
+    let path = "tests/all/cli_tests/component-invoke.wat";
+    let invoke = |call: &str| {
+        run_wasmtime(&[
+            "run",
+            "-Ccache=n",
+            "-Wcomponent-model",
+            "--invoke",
+            call,
+            path,
+        ])
+    };
+
+    assert_eq!(invoke("add(1, 2)")?, "3\n");
+    assert_eq!(invoke("len(\"hello \\u{1f600}\")")?, "10\n");
+    assert_eq!(invoke("sum({x: 1, y: -3})")?, "-2\n");
+    assert_eq!(invoke("unwrap-or(some(3), 7)")?, "3\n");
+    assert_eq!(invoke("unwrap-or(none, 7)")?, "7\n");
+    assert_eq!(invoke("is-red(red)")?, "true\n");
+    assert_eq!(invoke("is-red(blue)")?, "false\n");
+
+    // Arguments must match the function's params.
+    assert!(invoke("add(1)").is_err());
+    assert!(invoke("add(1, -2)").is_err());
+    assert!(invoke("sum({x: 1})").is_err());
+    assert!(invoke("is-red(purple)").is_err());
+    assert!(invoke("add").is_err());
+    assert!(invoke("missing()").is_err());
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "component-model"), ignore)]
 fn run_precompiled_component() -> Result<()> {
@@ -1122,6 +1156,62 @@ mod test_programs {
         Ok(())
     }
 
+    #[test]
+    fn cli_stdin_record_replay() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let trace = dir.path().join("trace.bin");
+        let stdin = dir.path().join("stdin");
+        std::fs::write(&stdin, b"So rested he by the Tumtum tree")?;
+
+        let output = run_wasmtime_for_output(
+            &[
+                "run",
+                "-Wcomponent-model",
+                &format!("--record={}", trace.to_str().unwrap()),
+                CLI_STDIN_COMPONENT,
+            ],
+            Some(&stdin),
+        )?;
+        assert!(output.status.success(), "{output:?}");
+
+        // Stdin comes from the trace when replaying.
+        std::fs::write(&stdin, b"")?;
+        let output = run_wasmtime_for_output(
+            &[
+                "run",
+                "-Wcomponent-model",
+                &format!("--replay={}", trace.to_str().unwrap()),
+                CLI_STDIN_COMPONENT,
+            ],
+            Some(&stdin),
+        )?;
+        assert!(output.status.success(), "{output:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn cli_record_rejects_dir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let trace = dir.path().join("trace.bin");
+        let output = run_wasmtime_for_output(
+            &[
+                "run",
+                "-Wcomponent-model",
+                &format!("--record={}", trace.to_str().unwrap()),
+                &format!("--dir={}::/", dir.path().to_str().unwrap()),
+                CLI_FILE_READ_COMPONENT,
+            ],
+            None,
+        )?;
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("cannot be combined with --dir"),
+            "bad stderr: {stderr}"
+        );
+        Ok(())
+    }
+
     #[test]
     fn cli_file_append() -> Result<()> {
         let dir = tempfile::tempdir()?;
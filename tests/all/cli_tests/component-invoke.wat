@@ -0,0 +1,40 @@
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 8))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ret i32)
+      (local.set $ret (global.get $heap))
+      (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+      (local.get $ret))
+    (func (export "add") (param i32 i32) (result i32)
+      (i32.add (local.get 0) (local.get 1)))
+    (func (export "len") (param i32 i32) (result i32)
+      (local.get 1))
+    (func (export "sum") (param i32 i32) (result i32)
+      (i32.add (local.get 0) (local.get 1)))
+    (func (export "unwrap-or") (param i32 i32 i32) (result i32)
+      (if (result i32) (local.get 0)
+        (then (local.get 1))
+        (else (local.get 2))))
+    (func (export "is-red") (param i32) (result i32)
+      (i32.eqz (local.get 0)))
+  )
+  (core instance $i (instantiate $m))
+
+  (type $point' (record (field "x" s32) (field "y" s32)))
+  (export $point "point" (type $point'))
+  (type $color' (enum "red" "green" "blue"))
+  (export $color "color" (type $color'))
+
+  (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+    (canon lift (core func $i "add")))
+  (func (export "len") (param "s" string) (result u32)
+    (canon lift (core func $i "len") (memory $i "memory") (realloc (func $i "realloc"))))
+  (func (export "sum") (param "p" $point) (result s32)
+    (canon lift (core func $i "sum")))
+  (func (export "unwrap-or") (param "a" (option u8)) (param "b" u8) (result u8)
+    (canon lift (core func $i "unwrap-or")))
+  (func (export "is-red") (param "c" $color) (result bool)
+    (canon lift (core func $i "is-red")))
+)
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use component_test_util::{async_engine, engine, TypedFuncExt};
+use component_test_util::{async_engine, engine};
 use std::fmt::Write;
 use std::iter;
 use wasmtime::component::Component;
@@ -16,7 +16,9 @@ mod macros;
 mod nested;
 mod post_return;
 mod resources;
+mod serde;
 mod strings;
+mod types;
 
 #[test]
 #[cfg_attr(miri, ignore)]
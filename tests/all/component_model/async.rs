@@ -36,6 +36,7 @@ async fn smoke() -> Result<()> {
 
     thunk.call_async(&mut store, ()).await?;
     thunk.post_return_async(&mut store).await?;
+    thunk.call_and_post_return_async(&mut store, ()).await?;
 
     let err = instance
         .get_typed_func::<(), ()>(&mut store, "thunk-trap")?
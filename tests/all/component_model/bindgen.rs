@@ -638,3 +638,148 @@ mod exported_resources {
         Ok(())
     }
 }
+
+mod forward {
+    use super::*;
+    use foo::foo::math::Forward;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo;
+
+            interface math {
+                add: func(a: u32, b: u32) -> u32;
+            }
+
+            world proxy {
+                import math;
+                export math;
+            }
+        ",
+        forward: {
+            "foo:foo/math": Forward,
+        },
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let provider = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export "add") (param i32 i32) (result i32)
+                            (i32.add (local.get 0) (local.get 1)))
+                    )
+                    (core instance $i (instantiate $m))
+                    (func $add (param "a" u32) (param "b" u32) (result u32)
+                        (canon lift (core func $i "add")))
+                    (instance $math (export "add" (func $add)))
+                    (export (interface "foo:foo/math") (instance $math))
+                )
+            "#,
+        )?;
+        let consumer = Component::new(
+            &engine,
+            r#"
+                (component
+                    (import (interface "foo:foo/math") (instance $math
+                        (export "add" (func (param "a" u32) (param "b" u32) (result u32)))
+                    ))
+                    (core func $add (canon lower (func $math "add")))
+                    (core module $m
+                        (import "" "add" (func $add (param i32 i32) (result i32)))
+                        (func (export "run") (param i32) (result i32)
+                            (call $add (local.get 0) (i32.const 1)))
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "add" (func $add))))
+                    ))
+                    (func (export "run") (param "x" u32) (result u32)
+                        (canon lift (core func $i "run")))
+                )
+            "#,
+        )?;
+
+        let mut provider_store = Store::new(&engine, ());
+        let instance = Linker::new(&engine).instantiate(&mut provider_store, &provider)?;
+        let guest = {
+            let mut exports = instance.exports(&mut provider_store);
+            exports::foo::foo::math::Math::new(&mut exports.instance("foo:foo/math").unwrap())?
+        };
+
+        let mut linker = Linker::new(&engine);
+        foo::foo::math::add_to_linker(&mut linker, |f: &mut Forward<()>| f)?;
+        let mut store = Store::new(&engine, Forward::new(provider_store, guest));
+        let instance = linker.instantiate(&mut store, &consumer)?;
+        let run = instance.get_typed_func::<(u32,), (u32,)>(&mut store, "run")?;
+        assert_eq!(run.call(&mut store, (5,))?, (6,));
+        run.post_return(&mut store)?;
+        Ok(())
+    }
+}
+
+mod auto_post_return {
+    use super::*;
+    use wasmtime::Trap;
+
+    wasmtime::component::bindgen!({
+        inline: "
+            package foo:foo;
+
+            world auto {
+                export thunk: func() -> u32;
+            }
+        ",
+    });
+
+    #[test]
+    fn run() -> Result<()> {
+        let engine = engine();
+
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (core module $m
+                        (func (export "thunk") (result i32) i32.const 42)
+                        (func (export "post-return") (param i32))
+                    )
+                    (core instance $i (instantiate $m))
+                    (func (export "thunk") (result u32)
+                        (canon lift
+                            (core func $i "thunk")
+                            (post-return (func $i "post-return"))
+                        )
+                    )
+                )
+            "#,
+        )?;
+        let mut store = Store::new(&engine, ());
+
+        // By default the generated bindings run post-return after each call.
+        let linker = Linker::new(&engine);
+        let (auto, _) = Auto::instantiate(&mut store, &component, &linker)?;
+        assert_eq!(auto.call_thunk(&mut store)?, 42);
+        assert_eq!(auto.call_thunk(&mut store)?, 42);
+
+        // Otherwise running post-return is left to the embedder.
+        let mut linker = Linker::new(&engine);
+        linker.auto_post_return(false);
+        let (auto, instance) = Auto::instantiate(&mut store, &component, &linker)?;
+        assert_eq!(auto.call_thunk(&mut store)?, 42);
+        let err = auto.call_thunk(&mut store).unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&Trap::CannotEnterComponent),
+            "{err}",
+        );
+        let thunk = instance.get_func(&mut store, "thunk").unwrap();
+        thunk.post_return(&mut store)?;
+        assert_eq!(auto.call_thunk(&mut store)?, 42);
+        thunk.post_return(&mut store)?;
+        Ok(())
+    }
+}
@@ -2,7 +2,6 @@
 
 use super::{make_echo_component, make_echo_component_with_params, Param, Type};
 use anyhow::Result;
-use component_test_util::FuncExt;
 use wasmtime::component::{self, Component, Linker, Val};
 use wasmtime::Store;
 
@@ -985,3 +985,76 @@ fn use_types_across_component_boundaries() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn define_unknown_imports_as_traps() -> Result<()> {
+    let engine = super::engine();
+    let component = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "host" (instance $host
+                    (export "known" (func (result u32)))
+                    (export "unknown" (func))
+                    (export "r" (type (sub resource)))
+                ))
+                (import "missing" (func $missing))
+
+                (core func $known-lower (canon lower (func $host "known")))
+                (core func $unknown-lower (canon lower (func $host "unknown")))
+                (core func $missing-lower (canon lower (func $missing)))
+                (core module $m
+                    (import "" "known" (func $known (result i32)))
+                    (import "" "unknown" (func $unknown))
+                    (import "" "missing" (func $missing))
+                    (func (export "known") (result i32) call $known)
+                    (func (export "unknown") call $unknown)
+                    (func (export "missing") call $missing)
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "known" (func $known-lower))
+                        (export "unknown" (func $unknown-lower))
+                        (export "missing" (func $missing-lower))
+                    ))
+                ))
+                (func (export "known") (result u32) (canon lift (core func $i "known")))
+                (func (export "unknown") (canon lift (core func $i "unknown")))
+                (func (export "call-missing") (canon lift (core func $i "missing")))
+            )
+        "#,
+    )?;
+
+    let mut linker = Linker::new(&engine);
+    linker
+        .instance("host")?
+        .func_wrap("known", |_, ()| Ok((42u32,)))?;
+    assert!(linker.instantiate_pre(&component).is_err());
+    linker.define_unknown_imports_as_traps(&component)?;
+    let pre = linker.instantiate_pre(&component)?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = pre.instantiate(&mut store)?;
+    let known = instance.get_typed_func::<(), (u32,)>(&mut store, "known")?;
+    assert_eq!(known.call(&mut store, ())?, (42,));
+    known.post_return(&mut store)?;
+    let unknown = instance.get_typed_func::<(), ()>(&mut store, "unknown")?;
+    let err = unknown.call(&mut store, ()).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("unknown import: `host#unknown` has not been defined"),
+        "bad error: {err:?}"
+    );
+
+    let mut store = Store::new(&engine, ());
+    let instance = pre.instantiate(&mut store)?;
+    let missing = instance.get_typed_func::<(), ()>(&mut store, "call-missing")?;
+    let err = missing.call(&mut store, ()).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("unknown import: `missing` has not been defined"),
+        "bad error: {err:?}"
+    );
+
+    // Calling the method again doesn't redefine anything.
+    linker.define_unknown_imports_as_traps(&component)?;
+    Ok(())
+}
@@ -55,3 +55,305 @@ fn instance_exports() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn value_imports_and_exports() -> Result<()> {
+    let engine = super::engine();
+    let component = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "x" (value $x string))
+                (core module $m
+                    (func (export "start") (param i32 i32) (result i32)
+                        local.get 1)
+                    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                        i32.const 100)
+                    (memory (export "memory") 1)
+                )
+                (core instance $i (instantiate $m))
+                (func $start (param "s" string) (result u32)
+                    (canon lift (core func $i "start")
+                        (memory $i "memory")
+                        (realloc (func $i "realloc"))))
+                (start $start (value $x) (result (value $len)))
+                (export "len" (value $len))
+            )
+        "#,
+    )?;
+
+    let ty = component.component_type();
+    assert!(matches!(
+        ty.get_import("x"),
+        Some(types::ComponentItem::Value(Type::String))
+    ));
+    assert!(matches!(
+        ty.get_export("len"),
+        Some(types::ComponentItem::Value(Type::U32))
+    ));
+
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    linker.root().value("x", Val::U32(0))?;
+    assert!(linker.instantiate(&mut store, &component).is_err());
+
+    linker.allow_shadowing(true);
+    linker.root().value("x", Val::String("hello".into()))?;
+    let instance = linker.instantiate(&mut store, &component)?;
+    assert!(matches!(
+        instance.get_value(&mut store, "len"),
+        Some(Val::U32(5))
+    ));
+    assert!(instance.get_value(&mut store, "missing").is_none());
+    assert!(instance.get_func(&mut store, "len").is_none());
+    Ok(())
+}
+
+#[test]
+fn instance_from() -> Result<()> {
+    let engine = super::engine();
+    let provider = Component::new(
+        &engine,
+        r#"
+            (component
+                (core module $m
+                    (global $drops (mut i32) i32.const 0)
+                    (func (export "dtor") (param i32)
+                        (global.set $drops (i32.add (global.get $drops) (i32.const 1))))
+                    (func (export "drops") (result i32) global.get $drops)
+                    (func (export "add") (param i32 i32) (result i32)
+                        (i32.add (local.get 0) (local.get 1)))
+                    (func (export "rep") (param i32) (result i32) local.get 0)
+                )
+                (core instance $i (instantiate $m))
+                (type $t' (resource (rep i32) (dtor (func $i "dtor"))))
+                (export $t "t" (type $t'))
+                (core func $ctor (canon resource.new $t))
+                (func (export "[constructor]t") (param "rep" u32) (result (own $t))
+                    (canon lift (core func $ctor)))
+                (func (export "[method]t.rep") (param "self" (borrow $t)) (result u32)
+                    (canon lift (core func $i "rep")))
+                (func (export "drops") (result u32)
+                    (canon lift (core func $i "drops")))
+                (func (export "add") (param "a" u32) (param "b" u32) (result u32)
+                    (canon lift (core func $i "add")))
+            )
+        "#,
+    )?;
+    let consumer = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "api" (instance $api
+                    (export $t "t" (type (sub resource)))
+                    (export "[constructor]t" (func (param "rep" u32) (result (own $t))))
+                    (export "[method]t.rep" (func (param "self" (borrow $t)) (result u32)))
+                    (export "add" (func (param "a" u32) (param "b" u32) (result u32)))
+                ))
+                (alias export $api "t" (type $t))
+                (core func $ctor (canon lower (func $api "[constructor]t")))
+                (core func $rep (canon lower (func $api "[method]t.rep")))
+                (core func $add (canon lower (func $api "add")))
+                (core func $drop (canon resource.drop $t))
+                (core module $m
+                    (import "" "ctor" (func $ctor (param i32) (result i32)))
+                    (import "" "rep" (func $rep (param i32) (result i32)))
+                    (import "" "add" (func $add (param i32 i32) (result i32)))
+                    (import "" "drop" (func $drop (param i32)))
+                    (func (export "run") (param i32) (result i32)
+                        (local $h i32)
+                        (local.set $h (call $ctor (local.get 0)))
+                        (call $add (call $rep (local.get $h)) (i32.const 1))
+                        (call $drop (local.get $h))
+                    )
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "ctor" (func $ctor))
+                        (export "rep" (func $rep))
+                        (export "add" (func $add))
+                        (export "drop" (func $drop))
+                    ))
+                ))
+                (func (export "run") (param "x" u32) (result u32)
+                    (canon lift (core func $i "run")))
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let provider = Linker::new(&engine).instantiate(&mut store, &provider)?;
+    let mut linker = Linker::new(&engine);
+    linker.instance_from(&mut store, "api", provider)?;
+    let instance = linker.instantiate(&mut store, &consumer)?;
+
+    let run = instance.get_typed_func::<(u32,), (u32,)>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, (5,))?, (6,));
+    run.post_return(&mut store)?;
+
+    // The resource dropped by the consumer was destroyed by the provider.
+    let drops = provider.get_typed_func::<(), (u32,)>(&mut store, "drops")?;
+    assert_eq!(drops.call(&mut store, ())?, (1,));
+    drops.post_return(&mut store)?;
+
+    // Functions are type-checked against the importing component.
+    let mismatch = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "api" (instance
+                    (export "add" (func (param "a" u32) (result u32)))
+                ))
+            )
+        "#,
+    )?;
+    assert!(linker.instantiate(&mut store, &mismatch).is_err());
+
+    // Items defined with `instance_from` can't be used in other stores.
+    let mut store2 = Store::new(&engine, ());
+    let instance = linker.instantiate(&mut store2, &consumer)?;
+    let run = instance.get_typed_func::<(u32,), (u32,)>(&mut store2, "run")?;
+    let err = run.call(&mut store2, (5,)).unwrap_err();
+    assert!(format!("{err:?}").contains("different store"), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn instance_from_resources() -> Result<()> {
+    let engine = super::engine();
+    let provider = Component::new(
+        &engine,
+        r#"
+            (component
+                (core module $m
+                    (global $drops (mut i32) i32.const 0)
+                    (func (export "dtor") (param i32)
+                        (global.set $drops (i32.add (global.get $drops) (i32.const 1))))
+                    (func (export "drops") (result i32) global.get $drops)
+                    (func (export "sum2") (param i32 i32) (result i32)
+                        (i32.add (local.get 0) (local.get 1)))
+                    (func (export "maybe") (param i32 i32) (result i32)
+                        (select (local.get 1) (i32.const 0) (local.get 0)))
+                )
+                (core instance $i (instantiate $m))
+                (type $t' (resource (rep i32) (dtor (func $i "dtor"))))
+                (export $t "t" (type $t'))
+                (core func $ctor (canon resource.new $t))
+                (core func $rep (canon resource.rep $t))
+                (core func $drop (canon resource.drop $t))
+                (core module $m2
+                    (import "" "rep" (func $rep (param i32) (result i32)))
+                    (import "" "drop" (func $drop (param i32)))
+                    (func (export "consume") (param i32) (result i32)
+                        (local $rep i32)
+                        (local.set $rep (call $rep (local.get 0)))
+                        (call $drop (local.get 0))
+                        local.get $rep)
+                )
+                (core instance $i2 (instantiate $m2
+                    (with "" (instance
+                        (export "rep" (func $rep))
+                        (export "drop" (func $drop))
+                    ))
+                ))
+                (func (export "[constructor]t") (param "rep" u32) (result (own $t))
+                    (canon lift (core func $ctor)))
+                (func (export "sum2") (param "x" (tuple (borrow $t) (borrow $t))) (result u32)
+                    (canon lift (core func $i "sum2")))
+                (func (export "maybe") (param "x" (option (borrow $t))) (result u32)
+                    (canon lift (core func $i "maybe")))
+                (func (export "consume") (param "x" (own $t)) (result u32)
+                    (canon lift (core func $i2 "consume")))
+                (func (export "drops") (result u32)
+                    (canon lift (core func $i "drops")))
+            )
+        "#,
+    )?;
+    let consumer = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "api" (instance $api
+                    (export $t "t" (type (sub resource)))
+                    (export "[constructor]t" (func (param "rep" u32) (result (own $t))))
+                    (export "sum2" (func (param "x" (tuple (borrow $t) (borrow $t))) (result u32)))
+                    (export "maybe" (func (param "x" (option (borrow $t))) (result u32)))
+                    (export "consume" (func (param "x" (own $t)) (result u32)))
+                ))
+                (alias export $api "t" (type $t))
+                (core func $ctor (canon lower (func $api "[constructor]t")))
+                (core func $sum2 (canon lower (func $api "sum2")))
+                (core func $maybe (canon lower (func $api "maybe")))
+                (core func $consume (canon lower (func $api "consume")))
+                (core func $drop (canon resource.drop $t))
+                (core module $m
+                    (import "" "ctor" (func $ctor (param i32) (result i32)))
+                    (import "" "sum2" (func $sum2 (param i32 i32) (result i32)))
+                    (import "" "maybe" (func $maybe (param i32 i32) (result i32)))
+                    (import "" "consume" (func $consume (param i32) (result i32)))
+                    (import "" "drop" (func $drop (param i32)))
+                    (func (export "run") (result i32)
+                        (local $a i32)
+                        (local $b i32)
+                        (local $sum i32)
+                        (local.set $a (call $ctor (i32.const 10)))
+                        (local.set $b (call $ctor (i32.const 20)))
+
+                        ;; Borrows within a tuple, which must be released
+                        ;; after every call.
+                        (local.set $sum (call $sum2 (local.get $a) (local.get $b)))
+                        (local.set $sum (i32.add (local.get $sum)
+                            (call $sum2 (local.get $b) (local.get $b))))
+
+                        ;; Borrows within an option, along with `none`.
+                        (local.set $sum (i32.add (local.get $sum)
+                            (call $maybe (i32.const 1) (local.get $a))))
+                        (local.set $sum (i32.add (local.get $sum)
+                            (call $maybe (i32.const 0) (i32.const 0))))
+
+                        ;; This traps if a borrow of `a` was leaked.
+                        (call $drop (local.get $a))
+
+                        ;; Ownership of `b` moves to the provider.
+                        (i32.add (local.get $sum) (call $consume (local.get $b)))
+                    )
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "ctor" (func $ctor))
+                        (export "sum2" (func $sum2))
+                        (export "maybe" (func $maybe))
+                        (export "consume" (func $consume))
+                        (export "drop" (func $drop))
+                    ))
+                ))
+                (func (export "run") (result u32)
+                    (canon lift (core func $i "run")))
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let provider = Linker::new(&engine).instantiate(&mut store, &provider)?;
+    let mut linker = Linker::new(&engine);
+    linker.instance_from(&mut store, "api", provider)?;
+    let instance = linker.instantiate(&mut store, &consumer)?;
+
+    // 30 and 40 from `sum2`, 10 and 0 from `maybe`, and 20 from `consume`.
+    let run = instance.get_typed_func::<(), (u32,)>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, ())?, (100,));
+    run.post_return(&mut store)?;
+
+    // Both resources were destroyed by the provider: `a` when the consumer
+    // dropped it and `b` when the provider dropped it after taking ownership.
+    let drops = provider.get_typed_func::<(), (u32,)>(&mut store, "drops")?;
+    assert_eq!(drops.call(&mut store, ())?, (2,));
+    drops.post_return(&mut store)?;
+
+    // Running again works as no borrows were left behind by the host.
+    assert_eq!(run.call(&mut store, ())?, (100,));
+    run.post_return(&mut store)?;
+    assert_eq!(drops.call(&mut store, ())?, (4,));
+    drops.post_return(&mut store)?;
+    Ok(())
+}
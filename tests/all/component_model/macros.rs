@@ -1,6 +1,6 @@
 #![cfg(not(miri))]
 
-use super::{make_echo_component, TypedFuncExt};
+use super::make_echo_component;
 use anyhow::Result;
 use component_macro_test::{add_variants, flags_test};
 use wasmtime::component::{Component, ComponentType, Lift, Linker, Lower};
@@ -298,3 +298,62 @@ fn trap_in_post_return_poisons_instance() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn call_and_post_return() -> Result<()> {
+    let component = r#"
+        (component
+            (import "f" (func $f))
+
+            (core func $f_lower
+                (canon lower (func $f))
+            )
+            (core module $m
+                (import "" "" (func $f))
+
+                (func (export "thunk") (result i32)
+                    i32.const 42)
+
+                (func $post_return (param i32)
+                    call $f)
+                (export "post-return" (func $post_return))
+            )
+            (core instance $i (instantiate $m
+                (with "" (instance
+                    (export "" (func $f_lower))
+                ))
+            ))
+            (func (export "thunk") (result u32)
+                (canon lift
+                    (core func $i "thunk")
+                    (post-return (func $i "post-return"))
+                )
+            )
+        )
+    "#;
+
+    let engine = super::engine();
+    let component = Component::new(&engine, component)?;
+    let mut store = Store::new(&engine, 0);
+    let mut linker = Linker::new(&engine);
+    linker.root().func_wrap(
+        "f",
+        |mut store: StoreContextMut<'_, u32>, _: ()| -> Result<()> {
+            *store.data_mut() += 1;
+            Ok(())
+        },
+    )?;
+
+    let instance = linker.instantiate(&mut store, &component)?;
+    let thunk = instance.get_typed_func::<(), (u32,)>(&mut store, "thunk")?;
+    assert_eq!(thunk.call_and_post_return(&mut store, ())?, (42,));
+    assert_eq!(*store.data(), 1);
+
+    let thunk = instance.get_func(&mut store, "thunk").unwrap();
+    let mut results = [Val::U32(0)];
+    thunk.call_and_post_return(&mut store, &[], &mut results)?;
+    assert_eq!(results, [Val::U32(42)]);
+    assert_eq!(*store.data(), 2);
+
+    Ok(())
+}
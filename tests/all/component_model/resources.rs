@@ -1,6 +1,7 @@
 #![cfg(not(miri))]
 
 use anyhow::Result;
+use std::sync::{Arc, Mutex};
 use wasmtime::component::*;
 use wasmtime::{Store, Trap};
 
@@ -1283,3 +1284,158 @@ fn guest_different_host_same() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn resource_handles() -> Result<()> {
+    let mut config = component_test_util::config();
+    config.component_resource_leak_reporting(true);
+    let engine = wasmtime::Engine::new(&config)?;
+    let c = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "t" (type $t (sub resource)))
+                (import "inspect" (func $inspect (param "x" (borrow $t))))
+
+                (core func $drop (canon resource.drop $t))
+                (core func $inspect (canon lower (func $inspect)))
+                (core module $m
+                    (import "" "inspect" (func $inspect (param i32)))
+                    (import "" "drop" (func $drop (param i32)))
+                    (func (export "take") (param i32))
+                    (func (export "lend") (param i32)
+                        (call $inspect (local.get 0)))
+                    (func (export "borrow") (param i32)
+                        (call $inspect (local.get 0))
+                        (call $drop (local.get 0)))
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "inspect" (func $inspect))
+                        (export "drop" (func $drop))
+                    ))
+                ))
+
+                (func (export "take") (param "x" (own $t))
+                    (canon lift (core func $i "take")))
+                (func (export "lend") (param "x" u32)
+                    (canon lift (core func $i "lend")))
+                (func (export "borrow") (param "x" (borrow $t))
+                    (canon lift (core func $i "borrow")))
+                (func (export "drop") (param "x" u32)
+                    (canon lift (core func $drop)))
+            )
+        "#,
+    )?;
+
+    struct MyType;
+
+    // The store's data records the handles seen from within `inspect`,
+    // which is called while the guest is lending out a handle.
+    let mut store = Store::new(&engine, Vec::new());
+    let mut linker = Linker::new(&engine);
+    linker.root().resource::<MyType>("t", |_, _| Ok(()))?;
+    linker.root().func_wrap(
+        "inspect",
+        |mut cx: wasmtime::StoreContextMut<'_, Vec<ResourceHandleInfo>>,
+         (_r,): (Resource<MyType>,)| {
+            let all = cx.as_context().component_resource_handles();
+            assert_eq!(all.len(), 1);
+            *cx.data_mut() = all.into_iter().next().unwrap().1;
+            Ok(())
+        },
+    )?;
+    let i = linker.instantiate(&mut store, &c)?;
+    let take = i.get_typed_func::<(Resource<MyType>,), ()>(&mut store, "take")?;
+    let lend = i.get_typed_func::<(u32,), ()>(&mut store, "lend")?;
+    let borrow = i.get_typed_func::<(&Resource<MyType>,), ()>(&mut store, "borrow")?;
+    let dtor = i.get_typed_func::<(u32,), ()>(&mut store, "drop")?;
+
+    assert!(i.resource_handles(&store).is_empty());
+    let all = store.component_resource_handles();
+    assert_eq!(all.len(), 1);
+    assert!(all[0].1.is_empty());
+
+    take.call(&mut store, (Resource::new_own(42),))?;
+    take.post_return(&mut store)?;
+    take.call(&mut store, (Resource::new_own(43),))?;
+    take.post_return(&mut store)?;
+
+    let handles = i.resource_handles(&store);
+    assert_eq!(handles.len(), 2);
+    for (handle, rep) in handles.iter().zip([42, 43]) {
+        assert_eq!(handle.ty(), ResourceType::host::<MyType>());
+        assert_eq!(handle.rep(), rep);
+        assert!(handle.owned());
+        assert_eq!(handle.lend_count(), 0);
+    }
+    assert_eq!(store.component_resource_handles()[0].1, handles);
+
+    // While an `own` handle is lent to an import its lend count is raised.
+    lend.call(&mut store, (handles[1].index(),))?;
+    lend.post_return(&mut store)?;
+    let during = std::mem::take(store.data_mut());
+    assert_eq!(during.len(), 2);
+    assert_eq!(during[0].lend_count(), 0);
+    assert_eq!(during[1].rep(), 43);
+    assert_eq!(during[1].lend_count(), 1);
+    assert_eq!(i.resource_handles(&store), handles);
+
+    // A `borrow` given to the guest shows up as a handle which isn't owned
+    // for the duration of the call.
+    borrow.call(&mut store, (&Resource::new_borrow(44),))?;
+    borrow.post_return(&mut store)?;
+    let during = std::mem::take(store.data_mut());
+    assert_eq!(during.len(), 3);
+    assert_eq!(during[2].rep(), 44);
+    assert!(!during[2].owned());
+    assert_eq!(during[2].lend_count(), 0);
+    assert_eq!(i.resource_handles(&store), handles);
+
+    dtor.call(&mut store, (handles[0].index(),))?;
+    dtor.post_return(&mut store)?;
+
+    let handles = i.resource_handles(&store);
+    assert_eq!(handles.len(), 1);
+    assert_eq!(handles[0].rep(), 43);
+
+    // The remaining handle is reported as leaked when the store is dropped.
+    let report = capture_warnings(|| drop(store));
+    assert!(
+        report.contains("dropped with 1 outstanding owned resource handle(s)"),
+        "{report}"
+    );
+    assert!(report.contains("rep: 43"), "{report}");
+    assert!(!report.contains("rep: 42"), "{report}");
+    assert!(!report.contains("rep: 44"), "{report}");
+
+    Ok(())
+}
+
+/// Runs `f` and returns the `tracing` warnings it emitted on this thread.
+fn capture_warnings(f: impl FnOnce()) -> String {
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .with_max_level(tracing::Level::WARN)
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    let output = captured.0.lock().unwrap().clone();
+    String::from_utf8(output).unwrap()
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use serde_json::json;
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, Type};
+
+fn param_types(ty: &str) -> Result<Vec<Type>> {
+    let engine = super::engine();
+    let component = Component::new(
+        &engine,
+        format!(
+            r#"
+                (component
+                    (import "host" (instance
+                        (type $r' (record (field "a" u32) (field "b" string)))
+                        (export $r "r" (type (eq $r')))
+                        (type $v' (variant (case "x") (case "y" u8)))
+                        (export $v "v" (type (eq $v')))
+                        (type $e' (enum "p" "q"))
+                        (export $e "e" (type (eq $e')))
+                        (type $fl' (flags "f1" "f2" "f3"))
+                        (export $fl "fl" (type (eq $fl')))
+                        (export "f" (func (param "x" {ty})))
+                    ))
+                )
+            "#
+        ),
+    )?;
+    let host = match component.component_type().get_import("host") {
+        Some(ComponentItem::ComponentInstance(i)) => i,
+        other => panic!("unexpected import {other:?}"),
+    };
+    match host.get_export("f") {
+        Some(ComponentItem::ComponentFunc(f)) => Ok(f.params().collect()),
+        other => panic!("unexpected export {other:?}"),
+    }
+}
+
+fn roundtrip(ty: &str, value: serde_json::Value) -> Result<()> {
+    let ty = param_types(ty)?.remove(0);
+    let val = ty.deserialize_val(&value)?;
+    assert_eq!(serde_json::to_value(&val)?, value);
+    Ok(())
+}
+
+fn deserialize_err(ty: &str, value: serde_json::Value) -> Result<String> {
+    let ty = param_types(ty)?.remove(0);
+    Ok(ty.deserialize_val(&value).unwrap_err().to_string())
+}
+
+#[test]
+fn roundtrip_values() -> Result<()> {
+    roundtrip("bool", json!(true))?;
+    roundtrip("s8", json!(-8))?;
+    roundtrip("u64", json!(u64::MAX))?;
+    roundtrip("float64", json!(1.5))?;
+    roundtrip("char", json!("🦀"))?;
+    roundtrip("string", json!("hello"))?;
+    roundtrip("(list u32)", json!([1, 2, 3]))?;
+    roundtrip("(tuple s8 string)", json!([-1, "a"]))?;
+    roundtrip("$r", json!({"a": 1, "b": "x"}))?;
+    roundtrip("$v", json!("x"))?;
+    roundtrip("$v", json!({"y": 2}))?;
+    roundtrip("$e", json!("q"))?;
+    roundtrip("$fl", json!(["f1", "f3"]))?;
+    roundtrip("(option u32)", json!("none"))?;
+    roundtrip("(option u32)", json!({"some": 1}))?;
+    roundtrip("(option (option u32))", json!({"some": "none"}))?;
+    roundtrip("(result u32 (error string))", json!({"ok": 1}))?;
+    roundtrip("(result u32 (error string))", json!({"err": "bad"}))?;
+    roundtrip("(result)", json!("ok"))?;
+    roundtrip("(list $r)", json!([{"a": 1, "b": "x"}, {"a": 2, "b": "y"}]))?;
+    Ok(())
+}
+
+#[test]
+fn record_fields_in_any_order() -> Result<()> {
+    let ty = param_types("$r")?.remove(0);
+    let val = ty.deserialize_val(&json!({"b": "x", "a": 1}))?;
+    assert_eq!(serde_json::to_value(&val)?, json!({"a": 1, "b": "x"}));
+    Ok(())
+}
+
+#[test]
+fn deserialize_errors() -> Result<()> {
+    let err = deserialize_err("u8", json!(256))?;
+    assert!(err.contains("invalid value"), "{err}");
+    let err = deserialize_err("$r", json!({"a": 1}))?;
+    assert!(err.contains("missing field `b`"), "{err}");
+    let err = deserialize_err("$r", json!({"a": 1, "b": "x", "c": 2}))?;
+    assert!(err.contains("unknown field `c`"), "{err}");
+    let err = deserialize_err("$v", json!("z"))?;
+    assert!(err.contains("unknown case `z`"), "{err}");
+    let err = deserialize_err("$v", json!("y"))?;
+    assert!(err.contains("case `y` requires a payload"), "{err}");
+    let err = deserialize_err("$v", json!({"x": 1}))?;
+    assert!(err.contains("invalid type"), "{err}");
+    let err = deserialize_err("$e", json!("r"))?;
+    assert!(err.contains("unknown case `r`"), "{err}");
+    let err = deserialize_err("$fl", json!(["f4"]))?;
+    assert!(err.contains("f4"), "{err}");
+    let err = deserialize_err("(tuple u8 u8)", json!([1]))?;
+    assert!(err.contains("invalid length 1"), "{err}");
+    let err = deserialize_err("(option u8)", json!(1))?;
+    assert!(err.contains("invalid type"), "{err}");
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{Component, ResourceType, Type};
+use wasmtime::ExternType;
+
+#[test]
+fn component_type() -> Result<()> {
+    let engine = super::engine();
+    let component = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "host" (instance
+                    (export "t" (type (sub resource)))
+                    (export "f" (func (param "x" u32) (result string)))
+                ))
+                (import "m" (core module
+                    (import "a" "b" (func))
+                    (export "mem" (memory 1))
+                ))
+
+                (type $r (resource (rep i32)))
+                (core module $m
+                    (func (export "f") (param i32) (result i32) local.get 0)
+                )
+                (core instance $i (instantiate $m))
+                (func (export "id") (param "a" u32) (result u32)
+                    (canon lift (core func $i "f")))
+                (export "mod" (core module $m))
+                (export "r" (type $r))
+            )
+        "#,
+    )?;
+
+    let ty = component.component_type();
+    assert_eq!(ty.imports().len(), 2);
+    assert_eq!(ty.exports().len(), 3);
+    assert!(ty.get_import("missing").is_none());
+    assert!(ty.get_export("missing").is_none());
+
+    let host = match ty.get_import("host") {
+        Some(ComponentItem::ComponentInstance(i)) => i,
+        other => panic!("unexpected import {other:?}"),
+    };
+    let imported_resource = match host.get_export("t") {
+        Some(ComponentItem::Resource(r)) => r,
+        other => panic!("unexpected export {other:?}"),
+    };
+    match host.get_export("f") {
+        Some(ComponentItem::ComponentFunc(f)) => {
+            assert_eq!(f.params().collect::<Vec<_>>(), [Type::U32]);
+            assert_eq!(f.results().collect::<Vec<_>>(), [Type::String]);
+        }
+        other => panic!("unexpected export {other:?}"),
+    }
+
+    match ty.get_import("m") {
+        Some(ComponentItem::Module(m)) => {
+            let imports = m.imports().collect::<Vec<_>>();
+            assert_eq!(imports.len(), 1);
+            assert_eq!(imports[0].0, ("a", "b"));
+            assert!(matches!(imports[0].1, ExternType::Func(_)));
+            let exports = m.exports().collect::<Vec<_>>();
+            assert_eq!(exports.len(), 1);
+            assert_eq!(exports[0].0, "mem");
+            assert!(matches!(exports[0].1, ExternType::Memory(_)));
+        }
+        other => panic!("unexpected import {other:?}"),
+    }
+
+    let names = ty.exports().map(|(name, _)| name).collect::<Vec<_>>();
+    assert_eq!(names, ["id", "mod", "r"]);
+    match ty.get_export("id") {
+        Some(ComponentItem::ComponentFunc(f)) => {
+            assert_eq!(f.params().collect::<Vec<_>>(), [Type::U32]);
+            assert_eq!(f.results().collect::<Vec<_>>(), [Type::U32]);
+        }
+        other => panic!("unexpected export {other:?}"),
+    }
+    match ty.get_export("mod") {
+        Some(ComponentItem::Module(m)) => {
+            assert_eq!(m.imports().len(), 0);
+            assert_eq!(m.exports().map(|(name, _)| name).collect::<Vec<_>>(), ["f"]);
+        }
+        other => panic!("unexpected export {other:?}"),
+    }
+    let defined_resource = match ty.get_export("r") {
+        Some(ComponentItem::Resource(r)) => r,
+        other => panic!("unexpected export {other:?}"),
+    };
+
+    // Resource types are abstract before instantiation, so they're only equal
+    // to themselves.
+    assert_ne!(imported_resource, defined_resource);
+    assert_ne!(defined_resource, ResourceType::host::<u32>());
+    match component.component_type().get_export("r") {
+        Some(ComponentItem::Resource(r)) => assert_eq!(r, defined_resource),
+        other => panic!("unexpected export {other:?}"),
+    }
+    Ok(())
+}
@@ -1183,3 +1183,59 @@ fn total_memories_limit() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn shared_memory_outlives_instance() -> Result<()> {
+    if skip_pooling_allocator_tests() {
+        return Ok(());
+    }
+
+    let mut pool = crate::small_pool_config();
+    pool.linear_memory_keep_resident(4096);
+    let mut config = Config::new();
+    config.allocation_strategy(InstanceAllocationStrategy::Pooling(pool));
+    config.wasm_threads(true);
+    config.static_memory_maximum_size(65536);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, r#"(module (memory (export "m") 1 1 shared))"#)?;
+
+    let memory = {
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        instance.get_shared_memory(&mut store, "m").unwrap()
+    };
+
+    // The memory is still alive after its store has been dropped, so its slot
+    // in the pool can't be handed out to another instance yet.
+    {
+        let mut store = Store::new(&engine, ());
+        match Instance::new(&mut store, &module, &[]) {
+            Ok(_) => panic!("should have hit memory limit"),
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "maximum concurrent memory limit of 1 reached for stripe 0"
+            ),
+        }
+    }
+
+    // Use and drop the last reference to the memory on another thread, which
+    // returns the slot to the pool.
+    std::thread::spawn(move || {
+        unsafe {
+            *memory.data()[0].get() = 42;
+        }
+        drop(memory);
+    })
+    .join()
+    .unwrap();
+
+    // The slot can now be reused and has been reset for the next instance.
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let memory = instance.get_shared_memory(&mut store, "m").unwrap();
+    assert_eq!(unsafe { *memory.data()[0].get() }, 0);
+
+    Ok(())
+}
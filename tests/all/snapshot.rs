@@ -0,0 +1,215 @@
+use super::skip_pooling_allocator_tests;
+use anyhow::Result;
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+        (import "" "start" (func $start))
+        (start $start)
+
+        (memory (export "memory") 1)
+        (data (i32.const 0) "hello")
+
+        (global $g (export "g") (mut i32) (i32.const 0))
+        (global $f (mut funcref) (ref.null func))
+
+        (table $t 2 funcref)
+        (elem (i32.const 0) $one)
+        (elem declare func $two)
+
+        (func $one (result i32) i32.const 1)
+        (func $two (result i32) i32.const 2)
+
+        (func (export "init")
+            (i32.store8 (i32.const 100) (i32.const 42))
+            (drop (memory.grow (i32.const 2)))
+            (i32.store8 (i32.const 131072) (i32.const 7))
+            (global.set $g (i32.const 1234))
+            (global.set $f (ref.func $two))
+            (table.set $t (i32.const 1) (ref.func $two)))
+
+        (func (export "call") (param i32) (result i32)
+            (call_indirect $t (result i32) (local.get 0)))
+
+        (func (export "call-global") (result i32)
+            (table.set $t (i32.const 0) (global.get $f))
+            (call_indirect $t (result i32) (i32.const 0)))
+    )
+"#;
+
+fn assert_snapshot_state(store: &mut Store<u32>, instance: Instance) -> Result<()> {
+    let memory = instance.get_memory(&mut *store, "memory").unwrap();
+    assert_eq!(memory.size(&*store), 3);
+    let data = memory.data(&*store);
+    assert_eq!(&data[..5], b"hello");
+    assert_eq!(data[100], 42);
+    assert_eq!(data[131072], 7);
+
+    let g = instance.get_global(&mut *store, "g").unwrap();
+    assert_eq!(g.get(&mut *store).i32(), Some(1234));
+
+    let call = instance.get_typed_func::<u32, u32>(&mut *store, "call")?;
+    assert_eq!(call.call(&mut *store, 0)?, 1);
+    assert_eq!(call.call(&mut *store, 1)?, 2);
+
+    let call_global = instance.get_typed_func::<(), u32>(&mut *store, "call-global")?;
+    assert_eq!(call_global.call(&mut *store, ())?, 2);
+    Ok(())
+}
+
+fn snapshot_and_restore(engine: &Engine) -> Result<()> {
+    let module = Module::new(engine, WAT)?;
+    let mut linker = Linker::new(engine);
+    linker.func_wrap("", "start", |mut caller: Caller<'_, u32>| {
+        *caller.data_mut() += 1;
+    })?;
+    let pre = linker.instantiate_pre(&module)?;
+
+    let mut store = Store::new(engine, 0);
+    let instance = pre.instantiate(&mut store)?;
+    assert_eq!(*store.data(), 1);
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    init.call(&mut store, ())?;
+    let snapshot = instance.snapshot(&mut store)?;
+    drop(store);
+
+    for _ in 0..3 {
+        let mut store = Store::new(engine, 0);
+        let instance = pre.instantiate_from_snapshot(&mut store, &snapshot)?;
+
+        // The start function has already run as part of the snapshot.
+        assert_eq!(*store.data(), 0);
+        assert_snapshot_state(&mut store, instance)?;
+
+        // Modifications to a restored instance are not visible to other
+        // instances restored from the same snapshot.
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+        memory.data_mut(&mut store)[..5].copy_from_slice(b"world");
+        memory.data_mut(&mut store)[131072] = 0;
+    }
+
+    // Instantiating normally afterwards doesn't observe the snapshot.
+    let mut store = Store::new(engine, 0);
+    let instance = pre.instantiate(&mut store)?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.size(&store), 1);
+    assert_eq!(&memory.data(&store)[..5], b"hello");
+    assert_eq!(memory.data(&store)[100], 0);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn restore_on_demand() -> Result<()> {
+    snapshot_and_restore(&Engine::default())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn restore_pooling() -> Result<()> {
+    if skip_pooling_allocator_tests() {
+        return Ok(());
+    }
+    let mut pool = crate::small_pool_config();
+    pool.memory_pages(3);
+    pool.total_memories(2);
+    pool.total_tables(2);
+    let mut config = Config::new();
+    config.allocation_strategy(InstanceAllocationStrategy::Pooling(pool));
+    config.static_memory_maximum_size(3 * 65536);
+    snapshot_and_restore(&Engine::new(&config)?)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn restore_after_tier_up() -> Result<()> {
+    let mut config = Config::new();
+    config.strategy(Strategy::Tiered).tier_up_threshold(1);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (memory (export "memory") 1)
+                (global $g (export "g") (mut i32) (i32.const 0))
+                (table $t 1 funcref)
+                (elem (i32.const 0) $one)
+                (func $one (result i32) i32.const 1)
+                (func (export "init")
+                    (i32.store8 (i32.const 100) (i32.const 42))
+                    (global.set $g (i32.const 1234)))
+                (func (export "call") (result i32)
+                    (call_indirect $t (result i32) (i32.const 0)))
+            )
+        "#,
+    )?;
+    let pre = Linker::new(&engine).instantiate_pre(&module)?;
+    let check = |store: &mut Store<()>, instance: Instance| -> Result<()> {
+        let memory = instance.get_memory(&mut *store, "memory").unwrap();
+        assert_eq!(memory.data(&*store)[100], 42);
+        let g = instance.get_global(&mut *store, "g").unwrap();
+        assert_eq!(g.get(&mut *store).i32(), Some(1234));
+        let call = instance.get_typed_func::<(), i32>(&mut *store, "call")?;
+        assert_eq!(call.call(&mut *store, ())?, 1);
+        Ok(())
+    };
+
+    // The snapshot is taken while the instance runs the baseline code, and
+    // the call to `init` is enough for the module to be recompiled.
+    let mut store = Store::new(&engine, ());
+    let instance = pre.instantiate(&mut store)?;
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    init.call(&mut store, ())?;
+    let snapshot = instance.snapshot(&mut store)?;
+    assert!(module.wait_for_tier_up()?);
+
+    // Restoring it switches the store to the recompiled code, both for the
+    // restored instance and the original one.
+    let restored = pre.instantiate_from_snapshot(&mut store, &snapshot)?;
+    check(&mut store, restored)?;
+    check(&mut store, instance)?;
+
+    // Snapshots of instances running the recompiled code are still
+    // snapshots of the same module.
+    let snapshot = restored.snapshot(&mut store)?;
+    let mut store = Store::new(&engine, ());
+    let restored = pre.instantiate_from_snapshot(&mut store, &snapshot)?;
+    check(&mut store, restored)?;
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn restore_different_module() -> Result<()> {
+    let engine = Engine::default();
+    let a = Module::new(&engine, r#"(module (memory 1))"#)?;
+    let b = Module::new(&engine, r#"(module (memory 1))"#)?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &a, &[])?;
+    let snapshot = instance.snapshot(&mut store)?;
+
+    let pre = Linker::new(&engine).instantiate_pre(&b)?;
+    let result = pre.instantiate_from_snapshot(&mut store, &snapshot);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_externref_unsupported() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(
+        &engine,
+        r#"(module (global (export "g") (mut externref) (ref.null extern)))"#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    instance.snapshot(&mut store)?;
+
+    let g = instance.get_global(&mut store, "g").unwrap();
+    g.set(&mut store, Val::ExternRef(Some(ExternRef::new(1))))?;
+    assert!(instance.snapshot(&mut store).is_err());
+    Ok(())
+}
@@ -239,3 +239,515 @@ fn wasm_to_native_trap() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg_attr(windows, ignore)]
+fn heap_accesses() -> Result<()> {
+    let wat = r#"
+        (module
+          (memory 1)
+          (func (export "store") (param i32 i64)
+            (i64.store (local.get 0) (local.get 1)))
+          (func (export "load") (param i32) (result i64)
+            (i64.load (local.get 0)))
+          (func (export "load_offset") (param i32) (result i32)
+            (i32.load8_u offset=65535 (local.get 0))))
+    "#;
+
+    // Exercise the different heap styles and guard page configurations:
+    // static heaps with and without guard pages, which respectively elide
+    // and emit explicit bounds checks, and dynamic heaps.
+    let configs = [
+        (4 << 30, 2 << 30, true),
+        (4 << 30, 0, false),
+        (1 << 16, 0, false),
+        (0, 0, false),
+        (0, 64 << 10, true),
+    ];
+
+    for (static_maximum_size, guard_size, guard_before) in configs {
+        let mut c = Config::new();
+        c.strategy(Strategy::Winch)
+            .static_memory_maximum_size(static_maximum_size)
+            .static_memory_guard_size(guard_size)
+            .dynamic_memory_guard_size(guard_size)
+            .guard_before_linear_memory(guard_before);
+        let engine = Engine::new(&c)?;
+        let mut store = Store::new(&engine, ());
+        let module = Module::new(&engine, wat)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let store_fn = instance.get_typed_func::<(i32, i64), ()>(&mut store, "store")?;
+        let load_fn = instance.get_typed_func::<i32, i64>(&mut store, "load")?;
+        let load_offset_fn = instance.get_typed_func::<i32, i32>(&mut store, "load_offset")?;
+
+        store_fn.call(&mut store, (0, 42))?;
+        assert_eq!(load_fn.call(&mut store, 0)?, 42);
+        store_fn.call(&mut store, (65528, -1))?;
+        assert_eq!(load_fn.call(&mut store, 65528)?, -1);
+        assert_eq!(load_offset_fn.call(&mut store, 0)?, 0xff);
+
+        for index in [65529, 65536, -8, -1] {
+            let trap = load_fn
+                .call(&mut store, index)
+                .unwrap_err()
+                .downcast::<Trap>()?;
+            assert_eq!(trap, Trap::MemoryOutOfBounds);
+            let trap = store_fn
+                .call(&mut store, (index, 0))
+                .unwrap_err()
+                .downcast::<Trap>()?;
+            assert_eq!(trap, Trap::MemoryOutOfBounds);
+        }
+        let trap = load_offset_fn
+            .call(&mut store, 1)
+            .unwrap_err()
+            .downcast::<Trap>()?;
+        assert_eq!(trap, Trap::MemoryOutOfBounds);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+// SIMD is only supported on x64.
+#[cfg_attr(not(target_arch = "x86_64"), ignore)]
+fn simd() -> Result<()> {
+    let wat = r#"
+        (module
+          (memory 1)
+          (data (i32.const 0) "\01\00\00\00\02\00\00\00\03\00\00\00\04\00\00\00")
+          (data (i32.const 16) "\05\00\00\00\06\00\00\00\07\00\00\00\08\00\00\00")
+          (func (export "sum") (result i32)
+            (local $v v128)
+            (local.set $v (i32x4.add (v128.load (i32.const 0)) (v128.load (i32.const 16))))
+            (local.set $v (i32x4.add (local.get $v)
+              (i8x16.shuffle 8 9 10 11 12 13 14 15 0 1 2 3 4 5 6 7
+                (local.get $v) (local.get $v))))
+            (i32.add
+              (i32x4.extract_lane 0 (local.get $v))
+              (i32x4.extract_lane 1 (local.get $v))))
+          (func (export "swap") (param v128) (result v128)
+            (i8x16.shuffle 8 9 10 11 12 13 14 15 0 1 2 3 4 5 6 7
+              (local.get 0) (local.get 0)))
+          (func (export "min") (param f32 f32) (result f32)
+            (f32x4.extract_lane 2
+              (f32x4.min (f32x4.splat (local.get 0)) (f32x4.splat (local.get 1)))))
+          (func (export "mul") (param i64 i64) (result i64)
+            (i64x2.extract_lane 1
+              (i64x2.mul (i64x2.splat (local.get 0)) (i64x2.splat (local.get 1)))))
+          (func (export "shr_s") (param i32 i32) (result i32)
+            (i8x16.extract_lane_s 5
+              (i8x16.shr_s (i8x16.splat (local.get 0)) (local.get 1))))
+          (func (export "lt_u") (param i32 i32) (result i32)
+            (i8x16.bitmask
+              (i8x16.lt_u (i8x16.splat (local.get 0)) (i8x16.splat (local.get 1)))))
+          (func (export "popcnt") (param i32) (result i32)
+            (i8x16.extract_lane_u 15 (i8x16.popcnt (i8x16.splat (local.get 0)))))
+          (func (export "trunc_sat") (param f32) (result i32)
+            (i32x4.extract_lane 3 (i32x4.trunc_sat_f32x4_u (f32x4.splat (local.get 0)))))
+          (func (export "all_true") (param i32) (result i32)
+            (i32x4.all_true (i32x4.replace_lane 2 (v128.const i32x4 1 1 1 1) (local.get 0)))))
+    "#;
+
+    let mut c = Config::new();
+    c.strategy(Strategy::Winch);
+    let engine = Engine::new(&c)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    let sum = instance.get_typed_func::<(), i32>(&mut store, "sum")?;
+    assert_eq!(sum.call(&mut store, ())?, 36);
+
+    let swap = instance.get_typed_func::<V128, V128>(&mut store, "swap")?;
+    let swapped = swap.call(
+        &mut store,
+        V128::from(0x1111_2222_3333_4444_5555_6666_7777_8888),
+    )?;
+    assert_eq!(swapped.as_u128(), 0x5555_6666_7777_8888_1111_2222_3333_4444);
+
+    let min = instance.get_typed_func::<(f32, f32), f32>(&mut store, "min")?;
+    assert_eq!(min.call(&mut store, (1.0, -2.0))?, -2.0);
+    assert!(min.call(&mut store, (1.0, f32::NAN))?.is_nan());
+    assert!(min.call(&mut store, (0.0, -0.0))?.is_sign_negative());
+
+    let mul = instance.get_typed_func::<(i64, i64), i64>(&mut store, "mul")?;
+    assert_eq!(mul.call(&mut store, (-3, 1 << 40))?, -3 << 40);
+    assert_eq!(
+        mul.call(&mut store, (i64::MAX, 3))?,
+        i64::MAX.wrapping_mul(3)
+    );
+
+    let shr_s = instance.get_typed_func::<(i32, i32), i32>(&mut store, "shr_s")?;
+    assert_eq!(shr_s.call(&mut store, (-128, 3))?, -16);
+    assert_eq!(shr_s.call(&mut store, (64, 9))?, 32);
+
+    let lt_u = instance.get_typed_func::<(i32, i32), i32>(&mut store, "lt_u")?;
+    assert_eq!(lt_u.call(&mut store, (1, 0xff))?, 0xffff);
+    assert_eq!(lt_u.call(&mut store, (0xff, 1))?, 0);
+
+    let popcnt = instance.get_typed_func::<i32, i32>(&mut store, "popcnt")?;
+    assert_eq!(popcnt.call(&mut store, 0xb7)?, 6);
+
+    let trunc_sat = instance.get_typed_func::<f32, i32>(&mut store, "trunc_sat")?;
+    assert_eq!(trunc_sat.call(&mut store, 3e9)? as u32, 3_000_000_000);
+    assert_eq!(trunc_sat.call(&mut store, -1.0)?, 0);
+    assert_eq!(trunc_sat.call(&mut store, f32::NAN)?, 0);
+    assert_eq!(trunc_sat.call(&mut store, f32::INFINITY)?, -1);
+
+    let all_true = instance.get_typed_func::<i32, i32>(&mut store, "all_true")?;
+    assert_eq!(all_true.call(&mut store, 7)?, 1);
+    assert_eq!(all_true.call(&mut store, 0)?, 0);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn simd_unsupported() -> Result<()> {
+    let wat = r#"
+        (module
+          (func (export "splat") (param i32) (result i32)
+            (i32x4.extract_lane 0 (i32x4.splat (local.get 0)))))
+    "#;
+
+    // SIMD isn't supported on aarch64, and requires SSE4.1 on x64; in both
+    // cases compilation fails instead of emitting unsupported code.
+    let mut c = Config::new();
+    c.strategy(Strategy::Winch);
+    if cfg!(target_arch = "x86_64") {
+        unsafe {
+            c.cranelift_flag_set("has_sse41", "false");
+        }
+    }
+    let engine = Engine::new(&c)?;
+    let err = Module::new(&engine, wat).unwrap_err();
+    let err = format!("{err:?}");
+    if cfg!(target_arch = "x86_64") {
+        assert!(err.contains("requires the `sse41` target feature"), "{err}");
+    } else {
+        assert!(err.contains("SIMD is not supported by Winch"), "{err}");
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fuel_and_epoch_interruption() -> Result<()> {
+    let wat = r#"
+        (module
+          (func $spin (export "spin")
+            (loop $l
+              (br $l)))
+          (func (export "count") (param i32) (result i32)
+            (local $i i32)
+            (loop $l
+              (local.set $i (i32.add (local.get $i) (i32.const 1)))
+              (br_if $l (i32.lt_u (local.get $i) (local.get 0))))
+            (local.get $i))
+          (func (export "call_spin")
+            (call $spin)))
+    "#;
+
+    let mut c = Config::new();
+    c.strategy(Strategy::Winch).consume_fuel(true);
+    let engine = Engine::new(&c)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, ());
+    store.set_fuel(10_000)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    let count = instance.get_typed_func::<i32, i32>(&mut store, "count")?;
+    assert_eq!(count.call(&mut store, 100)?, 100);
+    let remaining = store.get_fuel()?;
+    assert!(remaining < 10_000 - 100);
+
+    for name in ["spin", "call_spin"] {
+        let f = instance.get_typed_func::<(), ()>(&mut store, name)?;
+        store.set_fuel(10_000)?;
+        let trap = f.call(&mut store, ()).unwrap_err().downcast::<Trap>()?;
+        assert_eq!(trap, Trap::OutOfFuel);
+    }
+
+    let mut c = Config::new();
+    c.strategy(Strategy::Winch).epoch_interruption(true);
+    let engine = Engine::new(&c)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, ());
+    store.set_epoch_deadline(1);
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    let count = instance.get_typed_func::<i32, i32>(&mut store, "count")?;
+    assert_eq!(count.call(&mut store, 100)?, 100);
+
+    for name in ["spin", "call_spin"] {
+        let f = instance.get_typed_func::<(), ()>(&mut store, name)?;
+        store.set_epoch_deadline(1);
+        engine.increment_epoch();
+        let trap = f.call(&mut store, ()).unwrap_err().downcast::<Trap>()?;
+        assert_eq!(trap, Trap::Interrupt);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn tiered_compilation() -> Result<()> {
+    let mut c = Config::new();
+    c.strategy(Strategy::Tiered).tier_up_threshold(2);
+    let engine = Engine::new(&c)?;
+    let wat = r#"
+        (module
+          (import "" "" (func $add (param i32 i32) (result i32)))
+          (table 2 funcref)
+          (elem (i32.const 0) $add $call_add)
+          (func $call_add (export "call_add") (param i32 i32) (result i32)
+            (call $add (local.get 0) (local.get 1)))
+          (func (export "call_indirect") (param i32 i32 i32) (result i32)
+            (call_indirect (param i32 i32) (result i32)
+              (local.get 0) (local.get 1) (local.get 2)))
+          (func (export "trap")
+            unreachable))
+    "#;
+    let module = Module::new(&engine, wat)?;
+    let mut c = Config::new();
+    c.strategy(Strategy::Cranelift);
+    let cranelift = Module::new(&Engine::new(&c)?, wat)?;
+
+    // Returns the module whose code the `trap` export of `instance` runs.
+    let trap_code = |store: &mut Store<()>, instance: Instance| -> Result<Module> {
+        let trap = instance.get_typed_func::<(), ()>(&mut *store, "trap")?;
+        let err = trap.call(&mut *store, ()).unwrap_err();
+        let backtrace = err.downcast_ref::<WasmBacktrace>().unwrap();
+        Ok(backtrace.frames()[0].module().clone())
+    };
+
+    let mut store = Store::new(&engine, ());
+    let add = add_fn(&mut store);
+    let instance = Instance::new(&mut store, &module, &[add.into()])?;
+    let call_add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "call_add")?;
+    let call_indirect =
+        instance.get_typed_func::<(i32, i32, i32), i32>(&mut store, "call_indirect")?;
+
+    // Below the threshold nothing is recompiled and the Winch code runs.
+    assert_eq!(call_add.call(&mut store, (41, 1))?, 42);
+    assert!(!module.wait_for_tier_up()?);
+    assert!(!module.is_tiered_up());
+    assert_eq!(trap_code(&mut store, instance)?.text(), module.text());
+
+    // Reaching the threshold recompiles the module in the background.
+    assert_eq!(call_add.call(&mut store, (41, 1))?, 42);
+    assert!(module.wait_for_tier_up()?);
+    assert!(module.is_tiered_up());
+
+    // The next call into the store switches the existing instance, and the
+    // functions in its table, to exactly the code that Cranelift produces,
+    // while it's still an instance of the original module.
+    let tiered = trap_code(&mut store, instance)?;
+    assert_ne!(tiered.text(), module.text());
+    assert_eq!(tiered.text(), cranelift.text());
+    assert_eq!(instance.module(&store).text(), module.text());
+    assert_eq!(call_add.call(&mut store, (41, 1))?, 42);
+    assert_eq!(call_indirect.call(&mut store, (41, 1, 0))?, 42);
+    assert_eq!(call_indirect.call(&mut store, (41, 1, 1))?, 42);
+
+    // New stores run the recompiled code right away.
+    let mut store = Store::new(&engine, ());
+    let add = add_fn(&mut store);
+    let instance = Instance::new(&mut store, &module, &[add.into()])?;
+    let call_indirect =
+        instance.get_typed_func::<(i32, i32, i32), i32>(&mut store, "call_indirect")?;
+    assert_eq!(call_indirect.call(&mut store, (41, 1, 1))?, 42);
+    assert_eq!(trap_code(&mut store, instance)?.text(), cranelift.text());
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn tiered_compilation_of_components() -> Result<()> {
+    let mut c = Config::new();
+    c.strategy(Strategy::Tiered).wasm_component_model(true);
+    let engine = Engine::new(&c)?;
+
+    // Winch can't compile components, so they're compiled with Cranelift.
+    let component = wasmtime::component::Component::new(
+        &engine,
+        r#"
+            (component
+              (core module $m
+                (func (export "add") (param i32 i32) (result i32)
+                  (i32.add (local.get 0) (local.get 1))))
+              (core instance $i (instantiate $m))
+              (func (export "add") (param "a" s32) (param "b" s32) (result s32)
+                (canon lift (core func $i "add")))
+            )
+        "#,
+    )?;
+    let mut store = Store::new(&engine, ());
+    let linker = wasmtime::component::Linker::new(&engine);
+    let instance = linker.instantiate(&mut store, &component)?;
+    let add = instance.get_typed_func::<(i32, i32), (i32,)>(&mut store, "add")?;
+    assert_eq!(add.call(&mut store, (41, 1))?, (42,));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn multi_value() -> Result<()> {
+    let wat = r#"
+        (module
+          (import "" "" (func $swap (param i32 i64) (result i64 i32)))
+          (func $triple (export "triple") (param i32) (result i32 i64 f32)
+            (local.get 0)
+            (i64.extend_i32_s (local.get 0))
+            (f32.convert_i32_s (local.get 0)))
+          (func (export "call_triple") (param i32) (result f32)
+            (local.get 0)
+            (call $triple)
+            (drop)
+            (drop)
+            (f32.convert_i32_s))
+          (func (export "call_swap") (param i32 i64) (result i64 i32)
+            (call $swap (local.get 0) (local.get 1)))
+          (func (export "block") (param i32) (result i32)
+            (i32.const 10)
+            (local.get 0)
+            (block (param i32 i32) (result i32 i32)
+              (i32.const 1)
+              (i32.add))
+            (i32.sub))
+          (func (export "loop") (param i32) (result i32)
+            (i32.const 0)
+            (local.get 0)
+            (loop (param i32 i32) (result i32)
+              (local.set 0)
+              (local.get 0)
+              (i32.add)
+              (local.get 0)
+              (i32.const 1)
+              (i32.sub)
+              (local.tee 0)
+              (local.get 0)
+              (br_if 0)
+              (drop)))
+          (func (export "if_else") (param i32) (result i32 i32)
+            (i32.const 2)
+            (i32.const 3)
+            (if (param i32 i32) (result i32 i32) (local.get 0)
+              (then (i32.mul) (i32.const 1))
+              (else (i32.add) (i32.const 0))))
+          (func (export "if_no_else") (param i32) (result i32)
+            (i32.const 2)
+            (if (param i32) (result i32) (local.get 0)
+              (then (i32.const 40) (i32.add)))
+            (i32.const 1)
+            (i32.add))
+          (func (export "br_if") (param i32) (result i32 i32)
+            (block (result i32 i32)
+              (i32.const 1)
+              (i32.const 2)
+              (local.get 0)
+              (br_if 0)
+              (drop)
+              (drop)
+              (i32.const 3)
+              (i32.const 4)))
+          (func (export "br_table") (param i32) (result i32 i32)
+            (block (result i32 i32)
+              (block (result i32 i32)
+                (i32.const 5)
+                (i32.const 6)
+                (local.get 0)
+                (br_table 0 1))
+              (i32.add)
+              (i32.const 0))))
+    "#;
+
+    let mut c = Config::new();
+    c.strategy(Strategy::Winch);
+    let engine = Engine::new(&c)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, ());
+    let swap = Func::wrap(&mut store, |a: i32, b: i64| (b, a));
+    let instance = Instance::new(&mut store, &module, &[swap.into()])?;
+
+    let triple = instance.get_typed_func::<i32, (i32, i64, f32)>(&mut store, "triple")?;
+    assert_eq!(triple.call(&mut store, -7)?, (-7, -7, -7.0));
+
+    let triple = instance
+        .get_func(&mut store, "triple")
+        .ok_or(anyhow::anyhow!("triple function not found"))?;
+    let mut returns = vec![Val::null(); 3];
+    triple.call(&mut store, &[Val::I32(3)], &mut returns)?;
+    assert_eq!(returns[0].unwrap_i32(), 3);
+    assert_eq!(returns[1].unwrap_i64(), 3);
+    assert_eq!(returns[2].unwrap_f32(), 3.0);
+
+    let call_triple = instance.get_typed_func::<i32, f32>(&mut store, "call_triple")?;
+    assert_eq!(call_triple.call(&mut store, 5)?, 5.0);
+
+    let call_swap = instance.get_typed_func::<(i32, i64), (i64, i32)>(&mut store, "call_swap")?;
+    assert_eq!(call_swap.call(&mut store, (1, 2))?, (2, 1));
+
+    let block = instance.get_typed_func::<i32, i32>(&mut store, "block")?;
+    assert_eq!(block.call(&mut store, 4)?, 5);
+
+    let loop_ = instance.get_typed_func::<i32, i32>(&mut store, "loop")?;
+    assert_eq!(loop_.call(&mut store, 4)?, 10);
+
+    let if_else = instance.get_typed_func::<i32, (i32, i32)>(&mut store, "if_else")?;
+    assert_eq!(if_else.call(&mut store, 1)?, (6, 1));
+    assert_eq!(if_else.call(&mut store, 0)?, (5, 0));
+
+    let if_no_else = instance.get_typed_func::<i32, i32>(&mut store, "if_no_else")?;
+    assert_eq!(if_no_else.call(&mut store, 1)?, 43);
+    assert_eq!(if_no_else.call(&mut store, 0)?, 3);
+
+    let br_if = instance.get_typed_func::<i32, (i32, i32)>(&mut store, "br_if")?;
+    assert_eq!(br_if.call(&mut store, 1)?, (1, 2));
+    assert_eq!(br_if.call(&mut store, 0)?, (3, 4));
+
+    let br_table = instance.get_typed_func::<i32, (i32, i32)>(&mut store, "br_table")?;
+    assert_eq!(br_table.call(&mut store, 0)?, (11, 0));
+    assert_eq!(br_table.call(&mut store, 1)?, (5, 6));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn memory_grow_and_size() -> Result<()> {
+    let wat = r#"
+        (module
+          (memory 1 3)
+          (func (export "grow") (param i32) (result i32)
+            (memory.grow (local.get 0)))
+          (func (export "size") (result i32)
+            (memory.size)))
+    "#;
+
+    let mut c = Config::new();
+    c.strategy(Strategy::Winch);
+    let engine = Engine::new(&c)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let grow = instance.get_typed_func::<i32, i32>(&mut store, "grow")?;
+    let size = instance.get_typed_func::<(), i32>(&mut store, "size")?;
+
+    assert_eq!(size.call(&mut store, ())?, 1);
+    assert_eq!(grow.call(&mut store, 1)?, 1);
+    assert_eq!(size.call(&mut store, ())?, 2);
+    assert_eq!(grow.call(&mut store, 0)?, 2);
+    // Growing past the maximum fails without changing the size.
+    assert_eq!(grow.call(&mut store, 2)?, -1);
+    assert_eq!(size.call(&mut store, ())?, 2);
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+;; a start function with no arguments or results runs during instantiation
+(component
+  (core module $m
+    (global $g (mut i32) i32.const 0)
+    (func (export "start") (global.set $g (i32.const 1)))
+    (func (export "get") (result i32) global.get $g)
+  )
+  (core instance $i (instantiate $m))
+  (func $start (canon lift (core func $i "start")))
+  (func (export "get") (result u32) (canon lift (core func $i "get")))
+  (start $start)
+)
+(assert_return (invoke "get") (u32.const 1))
+
+;; traps in a start function are reported from instantiation
+(assert_trap
+  (component
+    (core module $m
+      (func (export "start") unreachable)
+    )
+    (core instance $i (instantiate $m))
+    (func $start (canon lift (core func $i "start")))
+    (start $start)
+  )
+  "unreachable")
+
+;; results of start functions can be exported as values
+(component
+  (core module $m
+    (func (export "start") (result i32) i32.const 100)
+  )
+  (core instance $i (instantiate $m))
+  (func $start (result u32) (canon lift (core func $i "start")))
+  (start $start (result (value $v)))
+  (export "v" (value $v))
+)
+(assert_return (get "v") (u32.const 100))
+
+;; values imported from the host can be passed to start functions and
+;; reexported
+(component
+  (import "host" (instance $host
+    (export "value-u32" (value u32))
+    (export "value-string" (value string))
+  ))
+  (alias export $host "value-u32" (value $a))
+  (alias export $host "value-string" (value $s))
+
+  (core module $m
+    (func (export "start") (param i32) (result i32)
+      local.get 0
+      i32.const 42
+      i32.ne
+      if unreachable end
+      local.get 0
+      i32.const 1
+      i32.add)
+  )
+  (core instance $i (instantiate $m))
+  (func $start (param "x" u32) (result u32) (canon lift (core func $i "start")))
+  (start $start (value $a) (result (value $b)))
+  (export "b" (value $b))
+  (export "s" (value $s))
+)
+(assert_return (get "b") (u32.const 43))
+(assert_return (get "s") (str.const "hello"))
+
+;; values flow between nested components
+(component
+  (component $producer
+    (core module $m
+      (func (export "start") (result i32) i32.const 7)
+    )
+    (core instance $i (instantiate $m))
+    (func $start (result u32) (canon lift (core func $i "start")))
+    (start $start (result (value $v)))
+    (export "v" (value $v))
+  )
+  (component $consumer
+    (import "v" (value $v u32))
+    (core module $m
+      (global $g (mut i32) i32.const 0)
+      (func (export "start") (param i32) (global.set $g (local.get 0)))
+      (func (export "get") (result i32) global.get $g)
+    )
+    (core instance $i (instantiate $m))
+    (func $start (param "x" u32) (canon lift (core func $i "start")))
+    (start $start (value $v))
+    (func (export "get") (result u32) (canon lift (core func $i "get")))
+  )
+  (instance $p (instantiate $producer))
+  (instance $c (instantiate $consumer (with "v" (value $p "v"))))
+  (export "get" (func $c "get"))
+)
+(assert_return (invoke "get") (u32.const 7))
+
+;; start functions run in order with respect to other instantiations
+(component
+  (core module $m
+    (global $g (mut i32) i32.const 0)
+    (func (export "next") (result i32)
+      (global.set $g (i32.add (global.get $g) (i32.const 1)))
+      global.get $g)
+  )
+  (core instance $i (instantiate $m))
+  (func $next (result u32) (canon lift (core func $i "next")))
+  (component $c
+    (import "next" (func $next (result u32)))
+    (start $next (result (value $v)))
+    (export "v" (value $v))
+  )
+  (instance $a (instantiate $c (with "next" (func $next))))
+  (instance $b (instantiate $c (with "next" (func $next))))
+  (export "a" (value $a "v"))
+  (export "b" (value $b "v"))
+)
+(assert_return (get "a") (u32.const 1))
+(assert_return (get "b") (u32.const 2))
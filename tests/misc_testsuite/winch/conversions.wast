@@ -0,0 +1,174 @@
+(module
+  (func (export "i32.wrap_i64") (param i64) (result i32)
+    (i32.wrap_i64 (local.get 0)))
+  (func (export "i64.extend_i32_s") (param i32) (result i64)
+    (i64.extend_i32_s (local.get 0)))
+  (func (export "i64.extend_i32_u") (param i32) (result i64)
+    (i64.extend_i32_u (local.get 0)))
+  (func (export "i32.extend8_s") (param i32) (result i32)
+    (i32.extend8_s (local.get 0)))
+  (func (export "i32.extend16_s") (param i32) (result i32)
+    (i32.extend16_s (local.get 0)))
+  (func (export "i64.extend8_s") (param i64) (result i64)
+    (i64.extend8_s (local.get 0)))
+  (func (export "i64.extend16_s") (param i64) (result i64)
+    (i64.extend16_s (local.get 0)))
+  (func (export "i64.extend32_s") (param i64) (result i64)
+    (i64.extend32_s (local.get 0)))
+
+  (func (export "f32.convert_i32_s") (param i32) (result f32)
+    (f32.convert_i32_s (local.get 0)))
+  (func (export "f32.convert_i32_u") (param i32) (result f32)
+    (f32.convert_i32_u (local.get 0)))
+  (func (export "f32.convert_i64_s") (param i64) (result f32)
+    (f32.convert_i64_s (local.get 0)))
+  (func (export "f32.convert_i64_u") (param i64) (result f32)
+    (f32.convert_i64_u (local.get 0)))
+  (func (export "f64.convert_i32_s") (param i32) (result f64)
+    (f64.convert_i32_s (local.get 0)))
+  (func (export "f64.convert_i32_u") (param i32) (result f64)
+    (f64.convert_i32_u (local.get 0)))
+  (func (export "f64.convert_i64_s") (param i64) (result f64)
+    (f64.convert_i64_s (local.get 0)))
+  (func (export "f64.convert_i64_u") (param i64) (result f64)
+    (f64.convert_i64_u (local.get 0)))
+
+  (func (export "f32.demote_f64") (param f64) (result f32)
+    (f32.demote_f64 (local.get 0)))
+  (func (export "f64.promote_f32") (param f32) (result f64)
+    (f64.promote_f32 (local.get 0)))
+
+  (func (export "i32.reinterpret_f32") (param f32) (result i32)
+    (i32.reinterpret_f32 (local.get 0)))
+  (func (export "i64.reinterpret_f64") (param f64) (result i64)
+    (i64.reinterpret_f64 (local.get 0)))
+  (func (export "f32.reinterpret_i32") (param i32) (result f32)
+    (f32.reinterpret_i32 (local.get 0)))
+  (func (export "f64.reinterpret_i64") (param i64) (result f64)
+    (f64.reinterpret_i64 (local.get 0)))
+
+  (func (export "i32.trunc_f32_s") (param f32) (result i32)
+    (i32.trunc_f32_s (local.get 0)))
+  (func (export "i32.trunc_f32_u") (param f32) (result i32)
+    (i32.trunc_f32_u (local.get 0)))
+  (func (export "i32.trunc_f64_s") (param f64) (result i32)
+    (i32.trunc_f64_s (local.get 0)))
+  (func (export "i32.trunc_f64_u") (param f64) (result i32)
+    (i32.trunc_f64_u (local.get 0)))
+  (func (export "i64.trunc_f32_s") (param f32) (result i64)
+    (i64.trunc_f32_s (local.get 0)))
+  (func (export "i64.trunc_f32_u") (param f32) (result i64)
+    (i64.trunc_f32_u (local.get 0)))
+  (func (export "i64.trunc_f64_s") (param f64) (result i64)
+    (i64.trunc_f64_s (local.get 0)))
+  (func (export "i64.trunc_f64_u") (param f64) (result i64)
+    (i64.trunc_f64_u (local.get 0)))
+
+  (func (export "i32.trunc_sat_f32_s") (param f32) (result i32)
+    (i32.trunc_sat_f32_s (local.get 0)))
+  (func (export "i32.trunc_sat_f32_u") (param f32) (result i32)
+    (i32.trunc_sat_f32_u (local.get 0)))
+  (func (export "i32.trunc_sat_f64_s") (param f64) (result i32)
+    (i32.trunc_sat_f64_s (local.get 0)))
+  (func (export "i32.trunc_sat_f64_u") (param f64) (result i32)
+    (i32.trunc_sat_f64_u (local.get 0)))
+  (func (export "i64.trunc_sat_f32_s") (param f32) (result i64)
+    (i64.trunc_sat_f32_s (local.get 0)))
+  (func (export "i64.trunc_sat_f32_u") (param f32) (result i64)
+    (i64.trunc_sat_f32_u (local.get 0)))
+  (func (export "i64.trunc_sat_f64_s") (param f64) (result i64)
+    (i64.trunc_sat_f64_s (local.get 0)))
+  (func (export "i64.trunc_sat_f64_u") (param f64) (result i64)
+    (i64.trunc_sat_f64_u (local.get 0)))
+)
+
+(assert_return (invoke "i32.wrap_i64" (i64.const 0x1_0000_0001)) (i32.const 1))
+(assert_return (invoke "i32.wrap_i64" (i64.const -1)) (i32.const -1))
+(assert_return (invoke "i64.extend_i32_s" (i32.const -1)) (i64.const -1))
+(assert_return (invoke "i64.extend_i32_s" (i32.const 0x7fffffff)) (i64.const 0x7fffffff))
+(assert_return (invoke "i64.extend_i32_u" (i32.const -1)) (i64.const 0xffffffff))
+(assert_return (invoke "i64.extend_i32_u" (i32.const 0x80000000)) (i64.const 0x80000000))
+(assert_return (invoke "i32.extend8_s" (i32.const 0x80)) (i32.const -128))
+(assert_return (invoke "i32.extend8_s" (i32.const 0x7f)) (i32.const 127))
+(assert_return (invoke "i32.extend16_s" (i32.const 0x8000)) (i32.const -32768))
+(assert_return (invoke "i32.extend16_s" (i32.const 0x12347fff)) (i32.const 32767))
+(assert_return (invoke "i64.extend8_s" (i64.const 0xff)) (i64.const -1))
+(assert_return (invoke "i64.extend16_s" (i64.const 0x8000)) (i64.const -32768))
+(assert_return (invoke "i64.extend32_s" (i64.const 0x80000000)) (i64.const -0x80000000))
+(assert_return (invoke "i64.extend32_s" (i64.const 0x1_7fffffff)) (i64.const 0x7fffffff))
+
+(assert_return (invoke "f32.convert_i32_s" (i32.const -1)) (f32.const -1))
+(assert_return (invoke "f32.convert_i32_u" (i32.const 0x80000000)) (f32.const 2147483648))
+(assert_return (invoke "f32.convert_i32_u" (i32.const -1)) (f32.const 4294967296))
+(assert_return (invoke "f32.convert_i64_s" (i64.const -1)) (f32.const -1))
+(assert_return (invoke "f32.convert_i64_u" (i64.const -1)) (f32.const 18446744073709551616))
+(assert_return (invoke "f32.convert_i64_u" (i64.const 0x7fffffffffffffff)) (f32.const 9223372036854775807))
+(assert_return (invoke "f64.convert_i32_s" (i32.const -2147483648)) (f64.const -2147483648))
+(assert_return (invoke "f64.convert_i32_u" (i32.const -1)) (f64.const 4294967295))
+(assert_return (invoke "f64.convert_i64_s" (i64.const 0x7fffffffffffffff)) (f64.const 9223372036854775807))
+(assert_return (invoke "f64.convert_i64_u" (i64.const 0x8000000000000000)) (f64.const 9223372036854775808))
+(assert_return (invoke "f64.convert_i64_u" (i64.const -1)) (f64.const 18446744073709551616))
+
+(assert_return (invoke "f32.demote_f64" (f64.const 0x1.fffffe0000000p-127)) (f32.const 0x1p-126))
+(assert_return (invoke "f32.demote_f64" (f64.const -0x1p+0)) (f32.const -0x1p+0))
+(assert_return (invoke "f32.demote_f64" (f64.const inf)) (f32.const inf))
+(assert_return (invoke "f32.demote_f64" (f64.const nan)) (f32.const nan:canonical))
+(assert_return (invoke "f64.promote_f32" (f32.const 0x1p-149)) (f64.const 0x1p-149))
+(assert_return (invoke "f64.promote_f32" (f32.const -inf)) (f64.const -inf))
+(assert_return (invoke "f64.promote_f32" (f32.const nan)) (f64.const nan:canonical))
+
+(assert_return (invoke "i32.reinterpret_f32" (f32.const -0.0)) (i32.const 0x80000000))
+(assert_return (invoke "i32.reinterpret_f32" (f32.const nan:0x200000)) (i32.const 0x7fa00000))
+(assert_return (invoke "i64.reinterpret_f64" (f64.const -0.0)) (i64.const 0x8000000000000000))
+(assert_return (invoke "f32.reinterpret_i32" (i32.const 0x7f800000)) (f32.const inf))
+(assert_return (invoke "f32.reinterpret_i32" (i32.const 0x7fa00000)) (f32.const nan:0x200000))
+(assert_return (invoke "f64.reinterpret_i64" (i64.const 0x8000000000000000)) (f64.const -0.0))
+(assert_return (invoke "f64.reinterpret_i64" (i64.const 0x3ff0000000000000)) (f64.const 1))
+
+(assert_return (invoke "i32.trunc_f32_s" (f32.const -1.9)) (i32.const -1))
+(assert_return (invoke "i32.trunc_f32_s" (f32.const -2147483648.0)) (i32.const -2147483648))
+(assert_trap (invoke "i32.trunc_f32_s" (f32.const 2147483648.0)) "integer overflow")
+(assert_trap (invoke "i32.trunc_f32_s" (f32.const nan)) "invalid conversion to integer")
+(assert_return (invoke "i32.trunc_f32_u" (f32.const 4294967040.0)) (i32.const -256))
+(assert_return (invoke "i32.trunc_f32_u" (f32.const -0.9)) (i32.const 0))
+(assert_trap (invoke "i32.trunc_f32_u" (f32.const 4294967296.0)) "integer overflow")
+(assert_trap (invoke "i32.trunc_f32_u" (f32.const -1.0)) "integer overflow")
+(assert_trap (invoke "i32.trunc_f32_u" (f32.const nan)) "invalid conversion to integer")
+(assert_return (invoke "i32.trunc_f64_s" (f64.const -2147483648.9)) (i32.const -2147483648))
+(assert_trap (invoke "i32.trunc_f64_s" (f64.const -2147483649.0)) "integer overflow")
+(assert_trap (invoke "i32.trunc_f64_s" (f64.const -nan)) "invalid conversion to integer")
+(assert_return (invoke "i32.trunc_f64_u" (f64.const 4294967295.9)) (i32.const -1))
+(assert_trap (invoke "i32.trunc_f64_u" (f64.const 4294967296.0)) "integer overflow")
+(assert_trap (invoke "i32.trunc_f64_u" (f64.const inf)) "integer overflow")
+(assert_return (invoke "i64.trunc_f32_s" (f32.const -9223372036854775808.0)) (i64.const -9223372036854775808))
+(assert_trap (invoke "i64.trunc_f32_s" (f32.const 9223372036854775808.0)) "integer overflow")
+(assert_trap (invoke "i64.trunc_f32_s" (f32.const nan)) "invalid conversion to integer")
+(assert_return (invoke "i64.trunc_f32_u" (f32.const 18446742974197923840.0)) (i64.const -1099511627776))
+(assert_trap (invoke "i64.trunc_f32_u" (f32.const 18446744073709551616.0)) "integer overflow")
+(assert_trap (invoke "i64.trunc_f32_u" (f32.const -1.0)) "integer overflow")
+(assert_return (invoke "i64.trunc_f64_s" (f64.const -1.5)) (i64.const -1))
+(assert_trap (invoke "i64.trunc_f64_s" (f64.const -inf)) "integer overflow")
+(assert_trap (invoke "i64.trunc_f64_s" (f64.const nan)) "invalid conversion to integer")
+(assert_return (invoke "i64.trunc_f64_u" (f64.const 9223372036854775808.0)) (i64.const 0x8000000000000000))
+(assert_trap (invoke "i64.trunc_f64_u" (f64.const 18446744073709551616.0)) "integer overflow")
+(assert_trap (invoke "i64.trunc_f64_u" (f64.const nan)) "invalid conversion to integer")
+
+(assert_return (invoke "i32.trunc_sat_f32_s" (f32.const 2147483648.0)) (i32.const 0x7fffffff))
+(assert_return (invoke "i32.trunc_sat_f32_s" (f32.const -inf)) (i32.const 0x80000000))
+(assert_return (invoke "i32.trunc_sat_f32_s" (f32.const nan)) (i32.const 0))
+(assert_return (invoke "i32.trunc_sat_f32_u" (f32.const 4294967296.0)) (i32.const -1))
+(assert_return (invoke "i32.trunc_sat_f32_u" (f32.const -1.0)) (i32.const 0))
+(assert_return (invoke "i32.trunc_sat_f32_u" (f32.const nan)) (i32.const 0))
+(assert_return (invoke "i32.trunc_sat_f64_s" (f64.const -2147483649.0)) (i32.const 0x80000000))
+(assert_return (invoke "i32.trunc_sat_f64_s" (f64.const -nan)) (i32.const 0))
+(assert_return (invoke "i32.trunc_sat_f64_u" (f64.const 1e10)) (i32.const -1))
+(assert_return (invoke "i32.trunc_sat_f64_u" (f64.const -inf)) (i32.const 0))
+(assert_return (invoke "i64.trunc_sat_f32_s" (f32.const inf)) (i64.const 0x7fffffffffffffff))
+(assert_return (invoke "i64.trunc_sat_f32_s" (f32.const nan)) (i64.const 0))
+(assert_return (invoke "i64.trunc_sat_f32_u" (f32.const 18446744073709551616.0)) (i64.const -1))
+(assert_return (invoke "i64.trunc_sat_f32_u" (f32.const -0.5)) (i64.const 0))
+(assert_return (invoke "i64.trunc_sat_f64_s" (f64.const -9223372036854777856.0)) (i64.const 0x8000000000000000))
+(assert_return (invoke "i64.trunc_sat_f64_s" (f64.const 1.9)) (i64.const 1))
+(assert_return (invoke "i64.trunc_sat_f64_u" (f64.const 9223372036854775808.0)) (i64.const 0x8000000000000000))
+(assert_return (invoke "i64.trunc_sat_f64_u" (f64.const inf)) (i64.const 0xffffffffffffffff))
+(assert_return (invoke "i64.trunc_sat_f64_u" (f64.const nan)) (i64.const 0))
@@ -0,0 +1,72 @@
+(module
+  (memory 1)
+  (data (i32.const 0) "\01\02\03\04\05\06\07\08\ff\fe\fd\fc\fb\fa\f9\f8")
+  (data (i32.const 65528) "\01\02\03\04\05\06\07\08")
+
+  (func (export "i32.load") (param i32) (result i32)
+    (i32.load (local.get 0)))
+  (func (export "i32.load8_s") (param i32) (result i32)
+    (i32.load8_s (local.get 0)))
+  (func (export "i32.load8_u") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+  (func (export "i32.load16_s") (param i32) (result i32)
+    (i32.load16_s (local.get 0)))
+  (func (export "i32.load16_u") (param i32) (result i32)
+    (i32.load16_u (local.get 0)))
+  (func (export "i64.load") (param i32) (result i64)
+    (i64.load (local.get 0)))
+  (func (export "i64.load8_s") (param i32) (result i64)
+    (i64.load8_s (local.get 0)))
+  (func (export "i64.load8_u") (param i32) (result i64)
+    (i64.load8_u (local.get 0)))
+  (func (export "i64.load16_s") (param i32) (result i64)
+    (i64.load16_s (local.get 0)))
+  (func (export "i64.load16_u") (param i32) (result i64)
+    (i64.load16_u (local.get 0)))
+  (func (export "i64.load32_s") (param i32) (result i64)
+    (i64.load32_s (local.get 0)))
+  (func (export "i64.load32_u") (param i32) (result i64)
+    (i64.load32_u (local.get 0)))
+  (func (export "f32.load") (param i32) (result f32)
+    (f32.load (local.get 0)))
+  (func (export "f64.load") (param i32) (result f64)
+    (f64.load (local.get 0)))
+
+  (func (export "i32.load_offset") (param i32) (result i32)
+    (i32.load offset=4 (local.get 0)))
+  (func (export "i32.load_max_offset") (param i32) (result i32)
+    (i32.load offset=4294967295 (local.get 0)))
+  (func (export "i64.load_out_of_bounds_offset") (result i64)
+    (i64.load offset=65529 (i32.const 0)))
+)
+
+(assert_return (invoke "i32.load" (i32.const 0)) (i32.const 0x04030201))
+(assert_return (invoke "i32.load" (i32.const 1)) (i32.const 0x05040302))
+(assert_return (invoke "i32.load8_s" (i32.const 0)) (i32.const 1))
+(assert_return (invoke "i32.load8_s" (i32.const 8)) (i32.const -1))
+(assert_return (invoke "i32.load8_u" (i32.const 8)) (i32.const 255))
+(assert_return (invoke "i32.load16_s" (i32.const 8)) (i32.const -257))
+(assert_return (invoke "i32.load16_u" (i32.const 8)) (i32.const 65279))
+(assert_return (invoke "i64.load" (i32.const 0)) (i64.const 0x0807060504030201))
+(assert_return (invoke "i64.load8_s" (i32.const 8)) (i64.const -1))
+(assert_return (invoke "i64.load8_u" (i32.const 8)) (i64.const 255))
+(assert_return (invoke "i64.load16_s" (i32.const 8)) (i64.const -257))
+(assert_return (invoke "i64.load16_u" (i32.const 8)) (i64.const 65279))
+(assert_return (invoke "i64.load32_s" (i32.const 8)) (i64.const -50462977))
+(assert_return (invoke "i64.load32_u" (i32.const 8)) (i64.const 4244504319))
+(assert_return (invoke "f32.load" (i32.const 16)) (f32.const 0))
+(assert_return (invoke "f64.load" (i32.const 16)) (f64.const 0))
+(assert_return (invoke "i32.load_offset" (i32.const 0)) (i32.const 0x08070605))
+
+(assert_return (invoke "i64.load" (i32.const 65528)) (i64.const 0x0807060504030201))
+(assert_return (invoke "i32.load" (i32.const 65532)) (i32.const 0x08070605))
+(assert_return (invoke "i32.load8_u" (i32.const 65535)) (i32.const 8))
+(assert_trap (invoke "i32.load" (i32.const 65533)) "out of bounds memory access")
+(assert_trap (invoke "i64.load" (i32.const 65529)) "out of bounds memory access")
+(assert_trap (invoke "i32.load8_u" (i32.const 65536)) "out of bounds memory access")
+(assert_trap (invoke "i32.load" (i32.const -1)) "out of bounds memory access")
+(assert_trap (invoke "i64.load32_u" (i32.const -4)) "out of bounds memory access")
+(assert_trap (invoke "i32.load_offset" (i32.const 65532)) "out of bounds memory access")
+(assert_trap (invoke "i32.load_max_offset" (i32.const 0)) "out of bounds memory access")
+(assert_trap (invoke "i32.load_max_offset" (i32.const -1)) "out of bounds memory access")
+(assert_trap (invoke "i64.load_out_of_bounds_offset") "out of bounds memory access")
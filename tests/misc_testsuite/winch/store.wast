@@ -0,0 +1,83 @@
+(module
+  (memory 1)
+
+  (func (export "i32.store") (param i32 i32)
+    (i32.store (local.get 0) (local.get 1)))
+  (func (export "i32.store8") (param i32 i32)
+    (i32.store8 (local.get 0) (local.get 1)))
+  (func (export "i32.store16") (param i32 i32)
+    (i32.store16 (local.get 0) (local.get 1)))
+  (func (export "i64.store") (param i32 i64)
+    (i64.store (local.get 0) (local.get 1)))
+  (func (export "i64.store8") (param i32 i64)
+    (i64.store8 (local.get 0) (local.get 1)))
+  (func (export "i64.store16") (param i32 i64)
+    (i64.store16 (local.get 0) (local.get 1)))
+  (func (export "i64.store32") (param i32 i64)
+    (i64.store32 (local.get 0) (local.get 1)))
+  (func (export "f32.store") (param i32 f32)
+    (f32.store (local.get 0) (local.get 1)))
+  (func (export "f64.store") (param i32 f64)
+    (f64.store (local.get 0) (local.get 1)))
+
+  (func (export "i32.load") (param i32) (result i32)
+    (i32.load (local.get 0)))
+  (func (export "i64.load") (param i32) (result i64)
+    (i64.load (local.get 0)))
+  (func (export "f32.load") (param i32) (result f32)
+    (f32.load (local.get 0)))
+  (func (export "f64.load") (param i32) (result f64)
+    (f64.load (local.get 0)))
+
+  (func (export "i32.store_offset") (param i32 i32)
+    (i32.store offset=8 (local.get 0) (local.get 1)))
+)
+
+(assert_return (invoke "i64.store" (i32.const 0) (i64.const 0)))
+(assert_return (invoke "i32.store8" (i32.const 0) (i32.const 0x1ff)))
+(assert_return (invoke "i32.load" (i32.const 0)) (i32.const 0xff))
+(assert_return (invoke "i32.store16" (i32.const 0) (i32.const 0x1abcd)))
+(assert_return (invoke "i32.load" (i32.const 0)) (i32.const 0xabcd))
+(assert_return (invoke "i32.store" (i32.const 0) (i32.const -1)))
+(assert_return (invoke "i64.load" (i32.const 0)) (i64.const 0xffffffff))
+(assert_return (invoke "i64.store8" (i32.const 4) (i64.const 0x1ff)))
+(assert_return (invoke "i64.load" (i32.const 0)) (i64.const 0xff_ffffffff))
+(assert_return (invoke "i64.store16" (i32.const 4) (i64.const 0x1abcd)))
+(assert_return (invoke "i64.load" (i32.const 0)) (i64.const 0xabcd_ffffffff))
+(assert_return (invoke "i64.store32" (i32.const 4) (i64.const 0x1_12345678)))
+(assert_return (invoke "i64.load" (i32.const 0)) (i64.const 0x12345678_ffffffff))
+(assert_return (invoke "i64.store" (i32.const 8) (i64.const -2)))
+(assert_return (invoke "i64.load" (i32.const 8)) (i64.const -2))
+(assert_return (invoke "f32.store" (i32.const 16) (f32.const 1.5)))
+(assert_return (invoke "f32.load" (i32.const 16)) (f32.const 1.5))
+(assert_return (invoke "f64.store" (i32.const 24) (f64.const -2.25)))
+(assert_return (invoke "f64.load" (i32.const 24)) (f64.const -2.25))
+(assert_return (invoke "i32.store_offset" (i32.const 0) (i32.const 42)))
+(assert_return (invoke "i32.load" (i32.const 8)) (i32.const 42))
+
+(assert_return (invoke "i64.store" (i32.const 65528) (i64.const 1)))
+(assert_return (invoke "i64.load" (i32.const 65528)) (i64.const 1))
+(assert_return (invoke "i32.store8" (i32.const 65535) (i32.const 2)))
+(assert_trap (invoke "i32.store" (i32.const 65533) (i32.const 0)) "out of bounds memory access")
+(assert_trap (invoke "i64.store" (i32.const 65529) (i64.const 0)) "out of bounds memory access")
+(assert_trap (invoke "i32.store8" (i32.const 65536) (i32.const 0)) "out of bounds memory access")
+(assert_trap (invoke "f64.store" (i32.const -1) (f64.const 0)) "out of bounds memory access")
+(assert_trap (invoke "i32.store_offset" (i32.const 65528) (i32.const 0)) "out of bounds memory access")
+
+;; Imported memories.
+(module $mem
+  (memory (export "mem") 1)
+)
+(register "mem" $mem)
+
+(module
+  (import "mem" "mem" (memory 1))
+  (func (export "store") (param i32 i32)
+    (i32.store (local.get 0) (local.get 1)))
+  (func (export "load") (param i32) (result i32)
+    (i32.load (local.get 0)))
+)
+
+(assert_return (invoke "store" (i32.const 100) (i32.const 7)))
+(assert_return (invoke "load" (i32.const 100)) (i32.const 7))
+(assert_trap (invoke "load" (i32.const 65534)) "out of bounds memory access")
@@ -15,7 +15,10 @@
 //!
 //! The calling convention in the default ABI, uses registers to a
 //! certain fixed count for arguments and return values, and then the
-//! stack is used for all additional arguments.
+//! stack is used for all additional arguments. Functions returning
+//! more than one value receive an extra parameter, pointing to the
+//! return area in which all the results but the first one are
+//! written; see [`ABIResult`].
 //!
 //! Generally the stack layout looks like:
 //! +-------------------------------+
@@ -45,7 +48,7 @@
 //! |                               |
 use crate::isa::{reg::Reg, CallingConvention};
 use crate::masm::OperandSize;
-use smallvec::SmallVec;
+use smallvec::{smallvec, SmallVec};
 use std::collections::HashSet;
 use std::ops::{Add, BitAnd, Not, Sub};
 use wasmtime_environ::{WasmFuncType, WasmHeapType, WasmType};
@@ -78,7 +81,8 @@ pub(crate) trait ABI {
         -> ABISig;
 
     /// Construct the ABI-specific result from a slice of
-    /// [`wasmtime_environ::WasmtType`].
+    /// [`wasmtime_environ::WasmtType`]. See [`ABIResult`] for more details
+    /// on how multiple results are passed.
     fn result(returns: &[WasmType], call_conv: &CallingConvention) -> ABIResult;
 
     /// Returns the number of bits in a word.
@@ -92,6 +96,20 @@ pub(crate) trait ABI {
     /// Returns the designated scratch register.
     fn scratch_reg() -> Reg;
 
+    /// Returns the designated floating point scratch register.
+    fn float_scratch_reg() -> Reg;
+
+    /// Returns the scratch register to use when moving values of the
+    /// given type. `v128` values don't fit in a general purpose
+    /// register, so the floating point scratch register is used for
+    /// them instead.
+    fn scratch_for(ty: &WasmType) -> Reg {
+        match ty {
+            WasmType::V128 => Self::float_scratch_reg(),
+            _ => Self::scratch_reg(),
+        }
+    }
+
     /// Returns the frame pointer register.
     fn fp_reg() -> Reg;
 
@@ -108,6 +126,16 @@ pub(crate) trait ABI {
 
     /// Returns the size of each argument stack slot per argument type.
     fn stack_arg_slot_size_for_type(ty: WasmType) -> u32;
+
+    /// Returns the size of the stack slot used to hold a value of the
+    /// given type when pushed to the machine stack; see
+    /// [`crate::masm::MacroAssembler::push`].
+    fn stack_slot_size_for_type(ty: &WasmType) -> u32 {
+        match ty {
+            WasmType::F32 | WasmType::F64 | WasmType::V128 => ty_size(ty),
+            _ => Self::word_bytes(),
+        }
+    }
 }
 
 /// ABI-specific representation of a function argument.
@@ -164,66 +192,102 @@ impl ABIArg {
     }
 }
 
-/// ABI-specific representation of the function result.
-#[derive(Copy, Clone, Debug)]
-pub(crate) enum ABIResult {
-    Void,
-    Reg {
-        /// Type of the result.
-        ty: WasmType,
-        /// Register to hold the result.
-        reg: Reg,
-    },
+/// ABI-specific representation of the function results.
+///
+/// Following Wasmtime's calling convention, the first result is
+/// returned in a register and any other results are written to a
+/// return area, whose address is passed by the caller as the last
+/// parameter of the function. Stack results are laid out in the return
+/// area in order and naturally aligned; this matches the `#[repr(C)]`
+/// layout expected by the host when calling or being called by
+/// WebAssembly functions.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ABIResult {
+    /// The result operands. Only the first operand can be a register;
+    /// the offset of stack operands is relative to the start of the
+    /// return area.
+    operands: SmallVec<[ABIArg; 1]>,
+    /// The size, in bytes, of the return area.
+    ret_area_size: u32,
 }
 
-impl Default for ABIResult {
-    fn default() -> Self {
-        Self::Void
+impl ABIResult {
+    /// Create the ABI result for the given result types, using the
+    /// provided function to resolve the register holding the first
+    /// result.
+    pub fn from<F>(returns: &[WasmType], mut reg_for: F) -> Self
+    where
+        F: FnMut(&WasmType) -> Reg,
+    {
+        let mut ret_area_size = 0u32;
+        let operands = returns
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                if i == 0 {
+                    ABIArg::reg(reg_for(ty), *ty)
+                } else {
+                    let size = ty_size(ty);
+                    let offset = align_to(ret_area_size, size);
+                    ret_area_size = offset + size;
+                    ABIArg::stack_offset(offset, *ty)
+                }
+            })
+            .collect();
+
+        Self {
+            operands,
+            ret_area_size,
+        }
     }
-}
 
-impl ABIResult {
     /// Create a register ABI result.
     pub fn reg(ty: WasmType, reg: Reg) -> Self {
-        Self::Reg { ty, reg }
+        Self {
+            operands: smallvec![ABIArg::reg(reg, ty)],
+            ret_area_size: 0,
+        }
     }
 
     /// Createa void ABI result.
     pub fn void() -> Self {
-        Self::Void
+        Self::default()
     }
 
-    /// Get the result reg.
+    /// Get the register holding the first result, if any.
     pub fn result_reg(&self) -> Option<Reg> {
-        match self {
-            Self::Reg { reg, .. } => Some(*reg),
-            _ => None,
-        }
+        self.operands.first().and_then(|o| o.get_reg())
     }
 
     /// Checks if the result is void.
     pub fn is_void(&self) -> bool {
-        match self {
-            Self::Void => true,
-            _ => false,
-        }
+        self.operands.is_empty()
     }
 
-    /// Returns the length of the result.
+    /// Returns the number of results.
     pub fn len(&self) -> usize {
-        if self.is_void() {
-            0
-        } else {
-            1
-        }
+        self.operands.len()
+    }
+
+    /// Returns the result operands.
+    pub fn operands(&self) -> &[ABIArg] {
+        &self.operands
+    }
+
+    /// Returns true if any of the results is passed through the return
+    /// area.
+    pub fn has_stack_results(&self) -> bool {
+        self.operands.len() > 1
+    }
+
+    /// Returns the size, in bytes, of the return area.
+    pub fn ret_area_size(&self) -> u32 {
+        self.ret_area_size
     }
 
     /// Returns an iterator over the result registers.
-    ///
-    /// NOTE: Currently only one or zero registers
-    /// will be returned until suport for multi-value is introduced.
     pub fn regs(&self) -> impl Iterator<Item = Reg> + '_ {
-        std::iter::once(self.result_reg()).filter_map(|v| v)
+        self.operands.iter().filter_map(|o| o.get_reg())
     }
 }
 
@@ -232,9 +296,10 @@ pub(crate) type ABIParams = SmallVec<[ABIArg; 6]>;
 /// An ABI-specific representation of a function signature.
 #[derive(Debug, Clone)]
 pub(crate) struct ABISig {
-    /// Function parameters.
+    /// Function parameters. If the function has stack results, the last
+    /// parameter is the pointer to the return area.
     pub params: ABIParams,
-    /// Function result.
+    /// Function results.
     pub result: ABIResult,
     /// Stack space needed for stack arguments.
     pub stack_bytes: u32,
@@ -262,6 +327,24 @@ impl ABISig {
             regs: HashSet::from_iter(chained),
         }
     }
+
+    /// Returns the parameters of the signature, excluding the return
+    /// area pointer, if any.
+    pub fn params_without_retptr(&self) -> &[ABIArg] {
+        if self.result.has_stack_results() {
+            &self.params[..self.params.len() - 1]
+        } else {
+            &self.params
+        }
+    }
+
+    /// Returns the parameter holding the address of the return area, if
+    /// the signature has any stack results.
+    pub fn retptr(&self) -> Option<&ABIArg> {
+        self.result
+            .has_stack_results()
+            .then(|| self.params.last().expect("return area pointer parameter"))
+    }
 }
 
 /// Returns the size in bytes of a given WebAssembly type.
@@ -269,6 +352,7 @@ pub(crate) fn ty_size(ty: &WasmType) -> u32 {
     match *ty {
         WasmType::I32 | WasmType::F32 => 4,
         WasmType::I64 | WasmType::F64 => 8,
+        WasmType::V128 => 16,
         WasmType::Ref(rt) => match rt.heap_type {
             // TODO: Similar to the comment in visitor.rs at impl From<WasmType> for
             // OperandSize, Once Wasmtime supports 32-bit architectures, this will
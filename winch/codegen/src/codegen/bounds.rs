@@ -0,0 +1,224 @@
+//! Exposes heap bounds checks functionality for WebAssembly.
+//! Bounds checks in WebAssembly are critical for safety, so extreme caution is
+//! recommended when working on this area of Winch.
+use super::env::HeapData;
+use crate::{
+    abi::ABI,
+    codegen::CodeGenContext,
+    isa::reg::Reg,
+    masm::{CmpKind, MacroAssembler, OperandSize, RegImm, TrapCode},
+};
+
+/// A newtype to represent an immediate offset argument for a heap access.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ImmOffset(u32);
+
+impl ImmOffset {
+    /// Construct an [ImmOffset] from a u32.
+    pub fn from_u32(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    /// Return the underlying u32 value.
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+/// An enum to represent the heap bounds.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Bounds {
+    /// Static, known ahead-of-time.
+    Static(u64),
+    /// Dynamic. Loaded at runtime.
+    Dynamic(Reg),
+}
+
+impl Bounds {
+    /// Construct a [Bounds] from a [Reg].
+    pub fn from_reg(reg: Reg) -> Self {
+        Self::Dynamic(reg)
+    }
+
+    /// Construct a [Bounds] from a u64.
+    pub fn from_u64(raw: u64) -> Self {
+        Self::Static(raw)
+    }
+
+    /// Return the underlying [Reg].
+    pub fn as_reg(&self) -> Reg {
+        match self {
+            Self::Dynamic(reg) => *reg,
+            _ => panic!(),
+        }
+    }
+
+    /// Return the underlying u64 value.
+    pub fn as_u64(&self) -> u64 {
+        match self {
+            Self::Static(v) => *v,
+            _ => panic!(),
+        }
+    }
+}
+
+/// A newtype to represent a heap access index via a [Reg].
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Index(Reg);
+
+impl Index {
+    /// Construct an [Index] from a [Reg].
+    pub fn from_reg(reg: Reg) -> Self {
+        Self(reg)
+    }
+
+    /// Return the underlying [Reg].
+    pub fn as_reg(&self) -> Reg {
+        self.0
+    }
+}
+
+/// Loads the bounds of the dynamic heap.
+pub(crate) fn load_dynamic_heap_bounds<M>(
+    context: &mut CodeGenContext,
+    masm: &mut M,
+    heap: &HeapData,
+) -> Bounds
+where
+    M: MacroAssembler,
+{
+    let dst = context.any_gpr(masm);
+    match heap.import_from {
+        Some(offset) => {
+            let scratch = <M::ABI as ABI>::scratch_reg();
+            let definition = masm.address_at_vmctx(offset);
+            masm.load_ptr(definition, scratch);
+            let current_length = masm.address_at_reg(scratch, heap.current_length_offset);
+            masm.load_ptr(current_length, dst);
+        }
+        None => {
+            let current_length = masm.address_at_vmctx(heap.current_length_offset);
+            masm.load_ptr(current_length, dst);
+        }
+    }
+
+    Bounds::from_reg(dst)
+}
+
+/// This function ensures the following:
+/// * The immediate offset and memory access size fit in a single u64. Given:
+///   that the memory access size is a `u8`, we must guarantee that the immediate
+///   offset will fit in a `u32`, making the result of their addition fit in a u64
+///   and overflow safe.
+/// * Adjust the index to account for the immediate offset via an addition
+///   operation in case the immediate offset doesn't fit in a `u32`.
+/// * Zero extend the index to the pointer size, for 32-bit heaps.
+pub(crate) fn ensure_index_and_offset<M: MacroAssembler>(
+    masm: &mut M,
+    index: Index,
+    offset: u64,
+    heap: &HeapData,
+    ptr_size: OperandSize,
+) -> ImmOffset {
+    let index_size: OperandSize = heap.ty.into();
+    if index_size == OperandSize::S32 {
+        // 32-bit moves clear the upper bits of the destination register,
+        // ensuring that the index can be safely used in pointer-sized
+        // operations.
+        let reg = index.as_reg();
+        masm.mov(reg.into(), reg, OperandSize::S32);
+    }
+
+    match u32::try_from(offset) {
+        // If the immediate offset fits in a u32, then we simply return.
+        Ok(offs) => ImmOffset::from_u32(offs),
+        // Else we adjust the index to be index = index + offset, including an
+        // overflow check, and return 0 as the offset.
+        Err(_) => {
+            let reg = index.as_reg();
+            masm.checked_uadd(
+                reg,
+                reg,
+                RegImm::i64(offset as i64),
+                ptr_size,
+                TrapCode::HeapOutOfBounds,
+            );
+
+            ImmOffset::from_u32(0)
+        }
+    }
+}
+
+/// Performs the out-of-bounds check and returns the heap address if the access
+/// criteria is in bounds.
+pub(crate) fn load_heap_addr_checked<M, F>(
+    masm: &mut M,
+    context: &mut CodeGenContext,
+    ptr_size: OperandSize,
+    heap: &HeapData,
+    enable_spectre_mitigation: bool,
+    bounds: Bounds,
+    index: Index,
+    offset: ImmOffset,
+    mut emit_check_condition: F,
+) -> Reg
+where
+    M: MacroAssembler,
+    F: FnMut(&mut M, Bounds, Index) -> CmpKind,
+{
+    let cmp_kind = emit_check_condition(masm, bounds, index);
+
+    masm.trapif(cmp_kind, TrapCode::HeapOutOfBounds);
+    let addr = context.any_gpr(masm);
+
+    load_heap_addr_unchecked(masm, heap, index, offset, addr, ptr_size);
+    if enable_spectre_mitigation {
+        // Conditionally assign 0 to the register holding the base address if
+        // the comparison kind is met. The comparison must be emitted again,
+        // given that computing the heap address clobbers the flags.
+        let tmp = context.any_gpr(masm);
+        masm.mov(RegImm::i64(0), tmp, ptr_size);
+        let cmp_kind = emit_check_condition(masm, bounds, index);
+        masm.cmov(tmp, addr, cmp_kind, ptr_size);
+        context.free_reg(tmp);
+    }
+
+    addr
+}
+
+/// Load the requested heap address into the specified destination register.
+/// This function doesn't perform any bounds checks and assumes the caller
+/// performed the right checks.
+pub(crate) fn load_heap_addr_unchecked<M>(
+    masm: &mut M,
+    heap: &HeapData,
+    index: Index,
+    offset: ImmOffset,
+    dst: Reg,
+    ptr_size: OperandSize,
+) where
+    M: MacroAssembler,
+{
+    let base = if let Some(offset) = heap.import_from {
+        // If the WebAssembly memory is imported or shared, load the address
+        // of its definition into the scratch register.
+        let scratch = <M::ABI as ABI>::scratch_reg();
+        let definition = masm.address_at_vmctx(offset);
+        masm.load_ptr(definition, scratch);
+        scratch
+    } else {
+        // Else if the WebAssembly memory is owned by the instance, use the
+        // vmctx register as the base of the heap.
+        <M::ABI as ABI>::vmctx_reg()
+    };
+
+    // Load the base of the memory into the `dst` register.
+    let heap_base = masm.address_at_reg(base, heap.offset);
+    masm.load_ptr(heap_base, dst);
+    // Start by adding the index to the heap base addr.
+    masm.add(dst, dst, index.as_reg().into(), ptr_size);
+
+    if offset.as_u32() > 0 {
+        masm.add(dst, dst, RegImm::i64(offset.as_u32() as i64), ptr_size);
+    }
+}
@@ -56,15 +56,22 @@
 //! │                                                  │
 //! │                                                  │
 //! └──────────────────────────────────────────────────┘ ------> Stack pointer when emitting the call
+//!
+//! If the callee returns more than one value, the stack space for the
+//! callee arguments is followed by the return area, which holds all the
+//! results except the first one, and whose address is passed to the
+//! callee as its last argument. Once the call returns, the results are
+//! moved to stack slots right above the stack space consumed by the call.
 
 use crate::{
-    abi::{ABIArg, ABISig, ABI},
+    abi::{align_to, ABIArg, ABISig, ABI},
     codegen::{
         ptr_type_from_ptr_size, BuiltinFunction, BuiltinType, Callee, CalleeInfo, CodeGenContext,
         TypedReg,
     },
     masm::{CalleeKind, MacroAssembler, OperandSize},
     reg::Reg,
+    stack::Val,
     CallingConvention,
 };
 use smallvec::SmallVec;
@@ -94,13 +101,19 @@ impl FnCall {
         let sig = Self::get_sig::<M>(&callee, ptr_type);
         let sig = sig.as_ref();
 
-        let arg_stack_space = sig.stack_bytes;
+        // The return area, if any, is placed right after the stack
+        // arguments.
+        let ret_area_offset = align_to(sig.stack_bytes, <M::ABI as ABI>::call_stack_align().into());
+        let arg_stack_space = if sig.result.has_stack_results() {
+            ret_area_offset + sig.result.ret_area_size()
+        } else {
+            sig.stack_bytes
+        };
         let kind = Self::map(&context.vmoffsets, &callee, sig, context, masm);
         let call_stack_space = Self::save(context, masm, &sig);
 
         let reserved_stack = masm.call(arg_stack_space, |masm| {
-            let scratch = <M::ABI as ABI>::scratch_reg();
-            Self::assign(sig, context, masm, scratch);
+            Self::assign(sig, ret_area_offset, context, masm);
             kind
         });
 
@@ -111,6 +124,7 @@ impl FnCall {
         Self::cleanup(
             sig,
             call_stack_space.checked_add(reserved_stack).unwrap(),
+            ret_area_offset,
             masm,
             context,
         );
@@ -211,7 +225,7 @@ impl FnCall {
         // range of the stack so that they are used as first
         // and second arguments.
         let stack = &mut context.stack;
-        let location = stack.len() - (sig.params.len() - 2);
+        let location = stack.len() - (sig.params_without_retptr().len() - 2);
         let values = [
             TypedReg::new(ptr_type, callee_vmctx).into(),
             TypedReg::new(ptr_type, caller_vmctx).into(),
@@ -249,14 +263,15 @@ impl FnCall {
     /// Assign arguments for the function call.
     fn assign<M: MacroAssembler>(
         sig: &ABISig,
+        ret_area_offset: u32,
         context: &mut CodeGenContext,
         masm: &mut M,
-        scratch: Reg,
     ) {
-        let arg_count = sig.params.len();
+        let params = sig.params_without_retptr();
+        let arg_count = params.len();
         let stack = &context.stack;
         let mut stack_values = stack.peekn(arg_count);
-        for arg in &sig.params {
+        for arg in params {
             let val = stack_values
                 .next()
                 .unwrap_or_else(|| panic!("expected stack value for function argument"));
@@ -267,11 +282,28 @@ impl FnCall {
                 &ABIArg::Stack { ty, offset } => {
                     let addr = masm.address_at_sp(*offset);
                     let size: OperandSize = (*ty).into();
+                    let scratch = <M::ABI as ABI>::scratch_for(ty);
                     context.move_val_to_reg(val, scratch, masm);
                     masm.store(scratch.into(), addr, size);
                 }
             }
         }
+
+        if let Some(retptr) = sig.retptr() {
+            let ret_area = masm.address_at_sp(ret_area_offset);
+            match retptr {
+                ABIArg::Reg { reg, .. } => masm.load_addr(ret_area, *reg, OperandSize::S64),
+                ABIArg::Stack { offset, .. } => {
+                    let scratch = <M::ABI as ABI>::scratch_reg();
+                    masm.load_addr(ret_area, scratch, OperandSize::S64);
+                    masm.store(
+                        scratch.into(),
+                        masm.address_at_sp(*offset),
+                        OperandSize::S64,
+                    );
+                }
+            }
+        }
     }
 
     /// Save any live registers prior to emitting the call.
@@ -302,7 +334,7 @@ impl FnCall {
     // |                  |  |
     // +------------------+  |
     fn save<M: MacroAssembler>(context: &mut CodeGenContext, masm: &mut M, sig: &ABISig) -> u32 {
-        let callee_params = &sig.params;
+        let callee_params = sig.params_without_retptr();
         let stack = &context.stack;
         match callee_params.len() {
             0 => {
@@ -322,9 +354,20 @@ impl FnCall {
     fn cleanup<M: MacroAssembler>(
         sig: &ABISig,
         total_space: u32,
+        ret_area_offset: u32,
         masm: &mut M,
         context: &mut CodeGenContext,
     ) {
+        if sig.result.has_stack_results() {
+            return Self::cleanup_with_stack_results(
+                sig,
+                total_space,
+                ret_area_offset,
+                masm,
+                context,
+            );
+        }
+
         masm.free_stack(total_space);
         // Only account for registers given that any memory entries
         // consumed by the call (assigned to a register or to a stack
@@ -343,11 +386,51 @@ impl FnCall {
         // * Rely on the new implementation of `drop_last` to calcuate
         // the stack memory entries consumed by the call and then free
         // the calculated stack space.
-        context.drop_last(sig.params.len(), |regalloc, v| {
+        context.drop_last(sig.params_without_retptr().len(), |regalloc, v| {
             if v.is_reg() {
                 regalloc.free(v.get_reg().into());
             }
         });
         context.push_abi_results(&sig.result, masm);
     }
+
+    /// Cleanup stack space and free registers after emitting a call to a
+    /// function returning more than one value.
+    ///
+    /// The results are moved to consecutive stack slots, right above the
+    /// stack space consumed by the call, before reclaiming the rest of
+    /// the stack space.
+    fn cleanup_with_stack_results<M: MacroAssembler>(
+        sig: &ABISig,
+        total_space: u32,
+        ret_area_offset: u32,
+        masm: &mut M,
+        context: &mut CodeGenContext,
+    ) {
+        let call_sp_offset = masm.sp_offset();
+        let base_sp = call_sp_offset - total_space;
+        context.drop_last(sig.params_without_retptr().len(), |regalloc, v| {
+            if v.is_reg() {
+                regalloc.free(v.get_reg().into());
+            }
+        });
+
+        // Push all the results to the top of the machine stack before
+        // moving them down, given that the return area could overlap
+        // with their final location.
+        for operand in sig.result.operands() {
+            let val = match operand {
+                ABIArg::Reg { ty, reg } => Val::mem(*ty, masm.push(*reg, (*ty).into())),
+                ABIArg::Stack { ty, offset } => {
+                    let scratch = <M::ABI as ABI>::scratch_for(ty);
+                    let addr = masm.address_from_sp(call_sp_offset - ret_area_offset - offset);
+                    masm.load(addr, scratch, (*ty).into());
+                    Val::mem(*ty, masm.push(scratch, (*ty).into()))
+                }
+            };
+            context.stack.push(val);
+        }
+
+        context.move_to_stack_slots(sig.result.len(), base_sp, masm);
+    }
 }
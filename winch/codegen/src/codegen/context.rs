@@ -1,16 +1,17 @@
 use wasmtime_environ::{VMOffsets, WasmHeapType, WasmType};
 
-use super::ControlStackFrame;
+use super::{ControlStackFrame, ControlValues};
 use crate::{
-    abi::{ABIResult, ABI},
+    abi::{ABIArg, ABIResult, ABI},
     codegen::BuiltinFunctions,
     frame::Frame,
     isa::reg::RegClass,
-    masm::{MacroAssembler, OperandSize, RegImm},
+    masm::{MacroAssembler, OperandSize, RegImm, StackSlot},
     reg::Reg,
     regalloc::RegAlloc,
     stack::{Stack, TypedReg, Val},
 };
+use smallvec::SmallVec;
 use std::ops::RangeBounds;
 
 /// The code generation context.
@@ -75,7 +76,9 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
         use WasmType::*;
         match ty {
             I32 | I64 => self.reg_for_class(RegClass::Int, masm),
-            F32 | F64 => self.reg_for_class(RegClass::Float, masm),
+            // `v128` values live in the same register class as floating
+            // point values.
+            F32 | F64 | V128 => self.reg_for_class(RegClass::Float, masm),
             Ref(rt) => match rt.heap_type {
                 WasmHeapType::Func => self.reg_for_class(RegClass::Int, masm),
                 ht => unimplemented!("Support for WasmHeapType: {ht}"),
@@ -192,6 +195,7 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
             Val::I64(imm) => masm.mov(RegImm::i64(*imm), dst, size),
             Val::F32(imm) => masm.mov(RegImm::f32(imm.bits()), dst, size),
             Val::F64(imm) => masm.mov(RegImm::f64(imm.bits()), dst, size),
+            Val::V128(imm) => masm.mov(RegImm::v128(*imm), dst, size),
             Val::Local(local) => {
                 let slot = self
                     .frame
@@ -276,6 +280,47 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
         }
     }
 
+    /// Prepares arguments for emitting a conversion operation, in which the
+    /// source and destination types differ.
+    pub fn convert_op<F, M>(&mut self, masm: &mut M, dst_ty: WasmType, mut emit: F)
+    where
+        F: FnMut(&mut M, Reg, Reg, OperandSize),
+        M: MacroAssembler,
+    {
+        let src = self.pop_to_reg(masm, None);
+        let dst = self.reg_for_type(dst_ty, masm);
+        let dst_size = match dst_ty {
+            WasmType::I32 | WasmType::F32 => OperandSize::S32,
+            WasmType::I64 | WasmType::F64 => OperandSize::S64,
+            WasmType::V128 => OperandSize::S128,
+            _ => unreachable!(),
+        };
+
+        emit(masm, dst, src.into(), dst_size);
+
+        self.free_reg(src);
+        self.stack.push(TypedReg::new(dst_ty, dst).into());
+    }
+
+    /// Prepares arguments for emitting a conversion operation that requires
+    /// an additional temporary register of the given class.
+    pub fn convert_op_with_tmp_reg<F, M>(
+        &mut self,
+        masm: &mut M,
+        dst_ty: WasmType,
+        tmp_reg_class: RegClass,
+        mut emit: F,
+    ) where
+        F: FnMut(&mut M, Reg, Reg, Reg, OperandSize),
+        M: MacroAssembler,
+    {
+        let tmp = self.reg_for_class(tmp_reg_class, masm);
+        self.convert_op(masm, dst_ty, |masm, dst, src, dst_size| {
+            emit(masm, dst, src, tmp, dst_size);
+        });
+        self.free_reg(tmp);
+    }
+
     /// Saves any live registers in the value stack in a particular
     /// range defined by the caller.  This is a specialization of the
     /// spill function; made available for cases in which spilling
@@ -341,12 +386,13 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
         M: MacroAssembler,
         F: FnMut(&mut M, &mut Self, &mut ControlStackFrame),
     {
-        let (_, target_sp) = dest.original_stack_len_and_sp_offset();
-        // Invariant: The SP, must be greater or equal to the target
-        // SP, given that we haven't popped any results by this point
-        // yet. But it may happen in the callback.
-        assert!(masm.sp_offset() >= target_sp);
+        let (_, base_sp) = dest.original_stack_len_and_sp_offset();
+        // Invariant: The SP, must be greater or equal to the base
+        // SP of the target, given that we haven't popped any results
+        // by this point yet. But it may happen in the callback.
+        assert!(masm.sp_offset() >= base_sp);
         f(masm, self, dest);
+        let target_sp = dest.target_sp_offset();
 
         // The following snippet, pops the stack pointer to ensure
         // that it is correctly placed according to the expectations
@@ -373,6 +419,9 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
         // memory slot for 1+ returns). This could happen in the
         // callback invocation above if the callback invokes
         // `CodeGenContext::pop_abi_results` (e.g. `br` instruction).
+        // Values passed in stack slots are placed right above the
+        // base SP of the destination, which is accounted for in the
+        // target SP.
         let current_sp = masm.sp_offset();
         if current_sp > target_sp {
             masm.free_stack(current_sp - target_sp);
@@ -387,29 +436,179 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
     /// of a block or function to pop the results from the value stack into the
     /// corresponding ABI result representation.
     pub fn pop_abi_results<M: MacroAssembler>(&mut self, result: &ABIResult, masm: &mut M) {
-        match result {
-            ABIResult::Void => {}
-            ABIResult::Reg { reg, .. } => {
-                let TypedReg { reg, ty: _ } = self.pop_to_reg(masm, Some(*reg));
-                self.free_reg(reg);
+        if result.has_stack_results() {
+            // Results other than the first one are written to the return
+            // area, in reverse order, given that the last result is at
+            // the top of the value stack.
+            let slot = self
+                .frame
+                .retptr_slot
+                .clone()
+                .expect("return area pointer slot");
+            let retptr = self.any_gpr(masm);
+            let addr = masm.local_address(&slot);
+            masm.load_ptr(addr, retptr);
+
+            for operand in result.operands()[1..].iter().rev() {
+                let (ty, offset) = match operand {
+                    ABIArg::Stack { ty, offset } => (ty, offset),
+                    _ => unreachable!("Expected stack result, found {:?}", operand),
+                };
+                let src = self.pop_to_reg(masm, None);
+                masm.store(
+                    src.reg.into(),
+                    masm.address_at_reg(retptr, *offset),
+                    (*ty).into(),
+                );
+                self.free_reg(src);
             }
+            self.free_reg(retptr);
+        }
+
+        if let Some(reg) = result.result_reg() {
+            let TypedReg { reg, ty: _ } = self.pop_to_reg(masm, Some(reg));
+            self.free_reg(reg);
         }
     }
 
     /// Push ABI results in to the value stack. This function is used at the end
     /// of a block or after a function call to push the corresponding ABI
     /// results into the value stack.
+    ///
+    /// Only register results are handled; the handling of results passed
+    /// through the return area is specific to each call site.
     pub fn push_abi_results<M: MacroAssembler>(&mut self, result: &ABIResult, masm: &mut M) {
-        match result {
-            ABIResult::Void => {}
-            ABIResult::Reg { ty, reg } => {
-                assert!(self.regalloc.reg_available(*reg));
-                let typed_reg = TypedReg::new(*ty, self.reg(*reg, masm));
-                self.stack.push(typed_reg.into());
+        assert!(!result.has_stack_results());
+        if let Some(ABIArg::Reg { ty, reg }) = result.operands().first() {
+            assert!(self.regalloc.reg_available(*reg));
+            let typed_reg = TypedReg::new(*ty, self.reg(*reg, masm));
+            self.stack.push(typed_reg.into());
+        }
+    }
+
+    /// Pops the given control values from the value stack, placing them at
+    /// the location expected by the control frame with the given base stack
+    /// pointer offset.
+    pub fn pop_control_values<M: MacroAssembler>(
+        &mut self,
+        values: &ControlValues,
+        base_sp: u32,
+        masm: &mut M,
+    ) {
+        match values {
+            ControlValues::Abi(result) => self.pop_abi_results(result, masm),
+            ControlValues::Stack { types, .. } => {
+                self.move_to_stack_slots(types.len(), base_sp, masm);
+                // The values are now in their expected location so we only
+                // need to drop them from the value stack.
+                self.drop_last(types.len(), |_, _| {});
             }
         }
     }
 
+    /// Pushes the given control values into the value stack, assuming that
+    /// they were previously placed in their expected location through
+    /// [`Self::pop_control_values`].
+    pub fn push_control_values<M: MacroAssembler>(
+        &mut self,
+        values: &ControlValues,
+        base_sp: u32,
+        masm: &mut M,
+    ) {
+        match values {
+            ControlValues::Abi(result) => self.push_abi_results(result, masm),
+            ControlValues::Stack { types, .. } => {
+                let mut offset = base_sp;
+                for ty in types {
+                    let size = <M::ABI as ABI>::stack_slot_size_for_type(ty);
+                    offset += size;
+                    self.stack.push(Val::mem(*ty, StackSlot { offset, size }));
+                }
+            }
+        }
+    }
+
+    /// Moves the top `n` values of the value stack to consecutive stack
+    /// slots, located right above the given stack pointer offset, and
+    /// frees any stack space above them. The values in the value stack are
+    /// updated to reflect their new location.
+    ///
+    /// This is the canonical location of block params and of the results
+    /// of blocks producing more than one value, which guarantees that all
+    /// the control flow edges reaching a block agree on the location of
+    /// its values. The caller must ensure that any stack space above the
+    /// given offset is not used by values other than the ones being moved.
+    pub fn move_to_stack_slots<M: MacroAssembler>(&mut self, n: usize, base_sp: u32, masm: &mut M) {
+        let len = self.stack.len();
+        assert!(n <= len);
+        let mut values: SmallVec<[Val; 4]> = self.stack.inner_mut().drain(len - n..).collect();
+
+        if masm.sp_offset() < base_sp {
+            masm.reserve_stack(base_sp - masm.sp_offset());
+        }
+
+        // Memory values in ascending stack order can be moved in place:
+        // the destination of each value is never above its source, so
+        // moving them in order never clobbers a value that hasn't been
+        // moved yet.
+        let mut next_free = base_sp;
+        let in_place = values.iter().all(|v| match v {
+            Val::Memory(mem) => {
+                let start = mem.slot.offset - mem.slot.size;
+                let in_order = start >= next_free;
+                next_free = mem.slot.offset;
+                in_order
+            }
+            _ => false,
+        });
+
+        // Else, push a copy of all the values to fresh stack slots, above
+        // any of the destination slots.
+        if !in_place {
+            for v in values.iter_mut() {
+                let ty = v.ty();
+                let slot = match v {
+                    Val::Reg(tr) => {
+                        let slot = masm.push(tr.reg, ty.into());
+                        self.regalloc.free(tr.reg);
+                        slot
+                    }
+                    v => {
+                        let scratch = <M::ABI as ABI>::scratch_for(&ty);
+                        self.move_val_to_reg(v, scratch, masm);
+                        masm.push(scratch, ty.into())
+                    }
+                };
+                *v = Val::mem(ty, slot);
+            }
+        }
+
+        let mut offset = base_sp;
+        for v in values.iter_mut() {
+            let mem = match v {
+                Val::Memory(mem) => mem,
+                v => unreachable!("Expected memory value, found {:?}", v),
+            };
+            offset += mem.slot.size;
+            if mem.slot.offset != offset {
+                let scratch = <M::ABI as ABI>::scratch_for(&mem.ty);
+                masm.load(
+                    masm.address_from_sp(mem.slot.offset),
+                    scratch,
+                    mem.ty.into(),
+                );
+                masm.store(scratch.into(), masm.address_from_sp(offset), mem.ty.into());
+                mem.slot.offset = offset;
+            }
+        }
+
+        let current_sp = masm.sp_offset();
+        if current_sp > offset {
+            masm.free_stack(current_sp - offset);
+        }
+        self.stack.extend(values);
+    }
+
     /// Spill locals and registers to memory.
     // TODO optimize the spill range;
     //
@@ -433,7 +632,7 @@ impl<'a, 'builtins> CodeGenContext<'a, 'builtins> {
             Val::Local(local) => {
                 let slot = frame.get_local(local.index).expect("valid local at slot");
                 let addr = masm.local_address(&slot);
-                let scratch = <M::ABI as ABI>::scratch_reg();
+                let scratch = <M::ABI as ABI>::scratch_for(&slot.ty);
                 masm.load(addr, scratch, slot.ty.into());
                 let stack_slot = masm.push(scratch, slot.ty.into());
                 *v = Val::mem(slot.ty, stack_slot);
@@ -1,18 +1,17 @@
 //! Data structures for control flow emission.
 //!
-//! As of the current implementation, Winch doesn't offer support for the
-//! multi-value proposal, which in the context of control flow constructs it
-//! means that blocks don't take any params and produce 0 or 1 return. The
-//! intention is to implement support for multi-value across the compiler, when
-//! that time comes, here are some general changes that will be needed for
-//! control flow:
+//! Before entering a control frame, all the values in the value stack are
+//! spilled to memory, which ensures that no registers are live across
+//! control flow edges. The values flowing into or out of a control frame
+//! are handled as follows:
 //!
-//! * Consider having a copy of the block params on the side, and push them when
-//! encountering an else or duplicate the block params upfront. If no else is
-//! present, clean the extra copies from the stack.
-//!
-//! * Eagerly load the block params. Params can flow "downward" as the block
-//! results in the case of an empty then or else block:
+//! * Block params are eagerly moved to consecutive stack slots, right above
+//! the frame's base stack pointer offset. Branches to a loop place the loop
+//! params in the same location. The params of an `if` are also pushed again
+//! to the value stack when entering the `else` branch; an `if` with params
+//! and without an `else` branch is handled as if it had an empty `else`
+//! branch, given that the params flow "downward" as the results of the
+//! block:
 //!   (module
 //!     (func (export "params") (param i32) (result i32)
 //!       (i32.const 2)
@@ -22,6 +21,12 @@
 //!     (i32.add)
 //!   )
 //!
+//! * Blocks producing a single value pass it in the register defined by the
+//! ABI for the result type, while blocks producing more than one value use
+//! consecutive stack slots right above the frame's base stack pointer
+//! offset. The function body frame passes its results according to the
+//! function's [`ABIResult`].
+//!
 //! As a future optimization, we could perform a look ahead to the next
 //! instruction when reaching any of the comparison instructions. If the next
 //! instruction is a control instruction, we could avoid emitting
@@ -32,10 +37,93 @@ use super::{CodeGenContext, MacroAssembler, OperandSize};
 use crate::{
     abi::{ABIResult, ABI},
     masm::CmpKind,
+    stack::Val,
     CallingConvention,
 };
 use cranelift_codegen::MachLabel;
-use wasmtime_environ::WasmType;
+use smallvec::SmallVec;
+use std::mem;
+use wasmtime_environ::{WasmFuncType, WasmType};
+
+/// Describes how the values flowing into or out of a control stack frame,
+/// that is, its params or its results, are passed.
+#[derive(Debug, Clone)]
+pub(crate) enum ControlValues {
+    /// The values are passed according to the given ABI result. This is
+    /// the case for the function body and for frames with at most one
+    /// value, which is passed in a register.
+    Abi(ABIResult),
+    /// The values are passed in consecutive stack slots, right above the
+    /// base stack pointer offset of the control stack frame.
+    Stack {
+        /// The types of the values.
+        types: SmallVec<[WasmType; 4]>,
+        /// The size, in bytes, of the stack slots holding the values.
+        size: u32,
+    },
+}
+
+impl Default for ControlValues {
+    fn default() -> Self {
+        Self::Abi(ABIResult::void())
+    }
+}
+
+impl ControlValues {
+    /// Returns the [`ControlValues`] for the given block params.
+    pub fn params<A: ABI>(types: &[WasmType]) -> Self {
+        if types.is_empty() {
+            Self::default()
+        } else {
+            Self::stack::<A>(types)
+        }
+    }
+
+    /// Returns the [`ControlValues`] for the given block results.
+    pub fn results<A: ABI>(types: &[WasmType]) -> Self {
+        if types.len() <= 1 {
+            Self::Abi(<A as ABI>::result(types, &CallingConvention::Default))
+        } else {
+            Self::stack::<A>(types)
+        }
+    }
+
+    fn stack<A: ABI>(types: &[WasmType]) -> Self {
+        Self::Stack {
+            types: types.into(),
+            size: types
+                .iter()
+                .map(|ty| <A as ABI>::stack_slot_size_for_type(ty))
+                .sum(),
+        }
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Abi(result) => result.len(),
+            Self::Stack { types, .. } => types.len(),
+        }
+    }
+
+    /// Returns the stack space, in bytes, used by the values right above
+    /// the base stack pointer offset of the control stack frame.
+    pub fn stack_size(&self) -> u32 {
+        match self {
+            Self::Abi(_) => 0,
+            Self::Stack { size, .. } => *size,
+        }
+    }
+
+    /// Returns the [`ABIResult`] describing the values if they are passed
+    /// exclusively in registers.
+    pub fn as_reg_result(&self) -> Option<&ABIResult> {
+        match self {
+            Self::Abi(result) if !result.has_stack_results() => Some(result),
+            _ => None,
+        }
+    }
+}
 
 /// Holds the necessary metdata to support the emission
 /// of control flow instructions.
@@ -46,11 +134,15 @@ pub(crate) enum ControlStackFrame {
         cont: MachLabel,
         /// The exit label of the block.
         exit: MachLabel,
+        /// The params of the block.
+        params: ControlValues,
         /// The return values of the block.
-        result: ABIResult,
-        /// The size of the value stack at the beginning of the If.
+        results: ControlValues,
+        /// The size of the value stack at the beginning of the If,
+        /// excluding the block params.
         original_stack_len: usize,
-        /// The stack pointer offset at the beginning of the If.
+        /// The stack pointer offset at the beginning of the If,
+        /// excluding the block params.
         original_sp_offset: u32,
         /// Local reachability state when entering the block.
         reachable: bool,
@@ -59,7 +151,7 @@ pub(crate) enum ControlStackFrame {
         /// The exit label of the block.
         exit: MachLabel,
         /// The return values of the block.
-        result: ABIResult,
+        results: ControlValues,
         /// The size of the value stack at the beginning of the Else.
         original_stack_len: usize,
         /// The stack pointer offset at the beginning of the Else.
@@ -70,11 +162,13 @@ pub(crate) enum ControlStackFrame {
     Block {
         /// The block exit label.
         exit: MachLabel,
-        /// The size of the value stack at the beginning of the block.
+        /// The size of the value stack at the beginning of the block,
+        /// excluding the block params.
         original_stack_len: usize,
         /// The return values of the block.
-        result: ABIResult,
-        /// The stack pointer offset at the beginning of the Block.
+        results: ControlValues,
+        /// The stack pointer offset at the beginning of the Block,
+        /// excluding the block params.
         original_sp_offset: u32,
         /// Exit state of the block.
         ///
@@ -86,33 +180,37 @@ pub(crate) enum ControlStackFrame {
     Loop {
         /// The start of the loop.
         head: MachLabel,
-        /// The size of the value stack at the beginning of the block.
+        /// The size of the value stack at the beginning of the block,
+        /// excluding the block params.
         original_stack_len: usize,
-        /// The stack pointer offset at the beginning of the Block.
+        /// The stack pointer offset at the beginning of the Block,
+        /// excluding the block params.
         original_sp_offset: u32,
+        /// The params of the block.
+        params: ControlValues,
         /// The return values of the block.
-        result: ABIResult,
+        results: ControlValues,
     },
 }
 
 impl ControlStackFrame {
     /// Returns [`ControlStackFrame`] for an if.
     pub fn if_<M: MacroAssembler>(
-        returns: &[WasmType],
+        sig: &WasmFuncType,
         masm: &mut M,
         context: &mut CodeGenContext,
     ) -> Self {
-        let result = <M::ABI as ABI>::result(&returns, &CallingConvention::Default);
         let mut control = Self::If {
             cont: masm.get_label(),
             exit: masm.get_label(),
-            result,
+            params: ControlValues::params::<M::ABI>(sig.params()),
+            results: ControlValues::results::<M::ABI>(sig.returns()),
             reachable: context.reachable,
             original_stack_len: 0,
             original_sp_offset: 0,
         };
 
-        control.emit(masm, context);
+        control.emit(sig.params().len(), masm, context);
         control
     }
 
@@ -125,7 +223,7 @@ impl ControlStackFrame {
     ) -> Self {
         Self::Block {
             original_stack_len: context.stack.len(),
-            result,
+            results: ControlValues::Abi(result),
             is_branch_target: false,
             exit: masm.get_label(),
             original_sp_offset: masm.sp_offset(),
@@ -134,42 +232,46 @@ impl ControlStackFrame {
 
     /// Returns [`ControlStackFrame`] for a block.
     pub fn block<M: MacroAssembler>(
-        returns: &[WasmType],
+        sig: &WasmFuncType,
         masm: &mut M,
         context: &mut CodeGenContext,
     ) -> Self {
-        let result = <M::ABI as ABI>::result(&returns, &CallingConvention::Default);
         let mut control = Self::Block {
             original_stack_len: 0,
-            result,
+            results: ControlValues::results::<M::ABI>(sig.returns()),
             is_branch_target: false,
             exit: masm.get_label(),
             original_sp_offset: 0,
         };
 
-        control.emit(masm, context);
+        control.emit(sig.params().len(), masm, context);
         control
     }
 
     /// Returns [`ControlStackFrame`] for a loop.
     pub fn loop_<M: MacroAssembler>(
-        returns: &[WasmType],
+        sig: &WasmFuncType,
         masm: &mut M,
         context: &mut CodeGenContext,
     ) -> Self {
-        let result = <M::ABI as ABI>::result(&returns, &CallingConvention::Default);
         let mut control = Self::Loop {
             original_stack_len: 0,
-            result,
+            params: ControlValues::params::<M::ABI>(sig.params()),
+            results: ControlValues::results::<M::ABI>(sig.returns()),
             head: masm.get_label(),
             original_sp_offset: 0,
         };
 
-        control.emit(masm, context);
+        control.emit(sig.params().len(), masm, context);
         control
     }
 
-    fn emit<M: MacroAssembler>(&mut self, masm: &mut M, context: &mut CodeGenContext) {
+    fn emit<M: MacroAssembler>(
+        &mut self,
+        params_len: usize,
+        masm: &mut M,
+        context: &mut CodeGenContext,
+    ) {
         use ControlStackFrame::*;
 
         // Do not perform any emissions if we are in an unreachable state.
@@ -190,8 +292,7 @@ impl ControlStackFrame {
                 // Unconditionall spill before emitting control flow.
                 context.spill(masm);
 
-                *original_stack_len = context.stack.len();
-                *original_sp_offset = masm.sp_offset();
+                (*original_stack_len, *original_sp_offset) = Self::enter(params_len, masm, context);
                 masm.branch(
                     CmpKind::Eq,
                     top.reg.into(),
@@ -210,8 +311,7 @@ impl ControlStackFrame {
                 // We assume that there are no live registers when
                 // exiting the block.
                 context.spill(masm);
-                *original_stack_len = context.stack.len();
-                *original_sp_offset = masm.sp_offset();
+                (*original_stack_len, *original_sp_offset) = Self::enter(params_len, masm, context);
             }
             Loop {
                 original_stack_len,
@@ -221,34 +321,65 @@ impl ControlStackFrame {
             } => {
                 // Unconditional spill before entering the loop block.
                 context.spill(masm);
-                *original_stack_len = context.stack.len();
-                *original_sp_offset = masm.sp_offset();
+                (*original_stack_len, *original_sp_offset) = Self::enter(params_len, masm, context);
                 masm.bind(*head);
             }
             _ => unreachable!(),
         }
     }
 
+    /// Moves the block params, if any, to their canonical location, right
+    /// above the base stack pointer offset of the block.
+    ///
+    /// Returns the value stack length and the stack pointer offset at the
+    /// beginning of the block, excluding the params.
+    fn enter<M: MacroAssembler>(
+        params_len: usize,
+        masm: &mut M,
+        context: &mut CodeGenContext,
+    ) -> (usize, u32) {
+        let stack_len = context.stack.len() - params_len;
+        // After spilling, the params are either constants or memory values
+        // at the top of the machine stack, so the base of the block is
+        // right below the first memory value, if any.
+        let base_sp = context
+            .stack
+            .peekn(params_len)
+            .filter_map(|v| match v {
+                Val::Memory(mem) => Some(mem.slot.offset - mem.slot.size),
+                _ => None,
+            })
+            .min()
+            .unwrap_or_else(|| masm.sp_offset());
+
+        if params_len > 0 {
+            context.move_to_stack_slots(params_len, base_sp, masm);
+        }
+
+        (stack_len, base_sp)
+    }
+
     /// Handles the else branch if the current control stack frame is
     /// [`ControlStackFrame::If`].
     pub fn emit_else<M: MacroAssembler>(&mut self, masm: &mut M, context: &mut CodeGenContext) {
         use ControlStackFrame::*;
         match self {
             If {
-                result,
+                results,
                 original_stack_len,
+                original_sp_offset,
                 exit,
                 ..
             } => {
-                assert!((*original_stack_len + result.len()) == context.stack.len());
+                assert!((*original_stack_len + results.len()) == context.stack.len());
                 // Before emitting an unconditional jump to the exit branch,
                 // we handle the result of the if-then block.
-                context.pop_abi_results(&result, masm);
+                context.pop_control_values(results, *original_sp_offset, masm);
                 // Before binding the else branch, we emit the jump to the end
                 // label.
                 masm.jmp(*exit);
                 // Bind the else branch.
-                self.bind_else(masm, context.reachable);
+                self.bind_else(masm, context);
             }
             _ => unreachable!(),
         }
@@ -256,12 +387,13 @@ impl ControlStackFrame {
 
     /// Binds the else branch label and converts `self` to
     /// [`ControlStackFrame::Else`].
-    pub fn bind_else<M: MacroAssembler>(&mut self, masm: &mut M, reachable: bool) {
+    pub fn bind_else<M: MacroAssembler>(&mut self, masm: &mut M, context: &mut CodeGenContext) {
         use ControlStackFrame::*;
         match self {
             If {
                 cont,
-                result,
+                params,
+                results,
                 original_stack_len,
                 original_sp_offset,
                 exit,
@@ -270,12 +402,18 @@ impl ControlStackFrame {
                 // Bind the else branch.
                 masm.bind(*cont);
 
+                // The else branch starts with the same machine stack as the
+                // if-then branch, so the params are pushed again to the
+                // value stack.
+                masm.reset_stack_pointer(*original_sp_offset + params.stack_size());
+                context.push_control_values(params, *original_sp_offset, masm);
+
                 // Update the stack control frame with an else control frame.
                 *self = ControlStackFrame::Else {
                     exit: *exit,
                     original_stack_len: *original_stack_len,
-                    result: *result,
-                    reachable,
+                    results: mem::take(results),
+                    reachable: context.reachable,
                     original_sp_offset: *original_sp_offset,
                 };
             }
@@ -286,37 +424,44 @@ impl ControlStackFrame {
     /// Handles the end of a control stack frame.
     pub fn emit_end<M: MacroAssembler>(&mut self, masm: &mut M, context: &mut CodeGenContext) {
         use ControlStackFrame::*;
+        // An if with params and without an else branch behaves as if it
+        // had an empty else branch, which forwards the params as the block
+        // results.
+        if matches!(self, If { params, .. } if params.len() > 0) {
+            self.emit_else(masm, context);
+            return self.emit_end(masm, context);
+        }
+
         match self {
             If {
-                result,
+                results,
                 original_stack_len,
+                original_sp_offset,
                 ..
             }
             | Else {
-                result,
+                results,
                 original_stack_len,
+                original_sp_offset,
                 ..
-            } => {
-                assert!((*original_stack_len + result.len()) == context.stack.len());
-                // Before binding the exit label, we handle the block results.
-                context.pop_abi_results(&result, masm);
-                self.bind_end(masm, context);
             }
-            Block {
+            | Block {
+                results,
                 original_stack_len,
-                result,
+                original_sp_offset,
                 ..
             } => {
-                assert!((*original_stack_len + result.len()) == context.stack.len());
-                context.pop_abi_results(&result, masm);
+                assert!((*original_stack_len + results.len()) == context.stack.len());
+                // Before binding the exit label, we handle the block results.
+                context.pop_control_values(results, *original_sp_offset, masm);
                 self.bind_end(masm, context);
             }
             Loop {
-                result,
+                results,
                 original_stack_len,
                 ..
             } => {
-                assert!((*original_stack_len + result.len()) == context.stack.len());
+                assert!((*original_stack_len + results.len()) == context.stack.len());
             }
         }
     }
@@ -325,10 +470,10 @@ impl ControlStackFrame {
     /// ABI results to the value stack.
     pub fn bind_end<M: MacroAssembler>(&self, masm: &mut M, context: &mut CodeGenContext) {
         // Push the results to the value stack.
-        context.push_abi_results(self.result(), masm);
+        let (_, base_sp) = self.original_stack_len_and_sp_offset();
+        context.push_control_values(self.results(), base_sp, masm);
         self.bind_exit_label(masm);
     }
-
     /// Binds the exit label of the control stack frame.
     pub fn bind_exit_label<M: MacroAssembler>(&self, masm: &mut M) {
         use ControlStackFrame::*;
@@ -379,16 +524,15 @@ impl ControlStackFrame {
         }
     }
 
-    /// Returns [`crate::abi::ABIResult`] of the control stack frame
-    /// block.
-    pub fn result(&self) -> &ABIResult {
+    /// Returns the results of the control stack frame.
+    pub fn results(&self) -> &ControlValues {
         use ControlStackFrame::*;
 
         match self {
-            If { result, .. }
-            | Else { result, .. }
-            | Block { result, .. }
-            | Loop { result, .. } => result,
+            If { results, .. }
+            | Else { results, .. }
+            | Block { results, .. }
+            | Loop { results, .. } => results,
         }
     }
 
@@ -443,14 +587,41 @@ impl ControlStackFrame {
     }
 
     /// Resolves how to handle results when the current frame is a
-    /// jump target Notably in the case of loops we don't take into
-    /// account the frame's results, just the params (void until
-    /// multi-value is supported).
-    pub fn as_target_result(&self) -> ABIResult {
+    /// jump target. Notably in the case of loops we don't take into
+    /// account the frame's results, just the params.
+    pub fn target_values(&self) -> &ControlValues {
         use ControlStackFrame::*;
         match self {
-            Loop { .. } => ABIResult::void(),
-            f => *f.result(),
+            Loop { params, .. } => params,
+            f => f.results(),
         }
     }
+
+    /// Returns the stack pointer offset expected when jumping to the
+    /// current frame.
+    pub fn target_sp_offset(&self) -> u32 {
+        let (_, base_sp) = self.original_stack_len_and_sp_offset();
+        base_sp + self.target_values().stack_size()
+    }
+
+    /// Returns the stack pointer offset expected at the exit of the
+    /// current frame.
+    pub fn exit_sp_offset(&self) -> u32 {
+        let (_, base_sp) = self.original_stack_len_and_sp_offset();
+        base_sp + self.results().stack_size()
+    }
+
+    /// Pops the values expected when jumping to the current frame from
+    /// the value stack, placing them in their expected location.
+    pub fn pop_target_values<M: MacroAssembler>(&self, context: &mut CodeGenContext, masm: &mut M) {
+        let (_, base_sp) = self.original_stack_len_and_sp_offset();
+        context.pop_control_values(self.target_values(), base_sp, masm);
+    }
+
+    /// Pops the results of the current frame from the value stack,
+    /// placing them in their expected location.
+    pub fn pop_results<M: MacroAssembler>(&self, context: &mut CodeGenContext, masm: &mut M) {
+        let (_, base_sp) = self.original_stack_len_and_sp_offset();
+        context.pop_control_values(self.results(), base_sp, masm);
+    }
 }
@@ -1,13 +1,16 @@
-use crate::codegen::{BuiltinFunction, OperandSize};
-use smallvec::{smallvec, SmallVec};
+use crate::{
+    codegen::{BuiltinFunction, OperandSize},
+    isa::TargetIsa,
+};
 use std::collections::{
     hash_map::Entry::{Occupied, Vacant},
     HashMap,
 };
 use wasmparser::BlockType;
 use wasmtime_environ::{
-    FuncIndex, GlobalIndex, ModuleTranslation, ModuleTypes, PtrSize, TableIndex, TablePlan,
-    TypeConvert, TypeIndex, VMOffsets, WasmFuncType, WasmType,
+    DefinedFuncIndex, FuncIndex, GlobalIndex, MemoryIndex, MemoryPlan, MemoryStyle,
+    ModuleTranslation, ModuleTypes, PtrSize, TableIndex, TablePlan, Tunables, TypeConvert,
+    TypeIndex, VMOffsets, WasmFuncType, WasmType, WASM_PAGE_SIZE,
 };
 
 /// Table metadata.
@@ -26,6 +29,47 @@ pub struct TableData {
     pub(crate) current_elements_size: OperandSize,
 }
 
+/// Style of the heap.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum HeapStyle {
+    /// Static heap, which has a fixed address and a fixed size; the
+    /// heap is never moved when grown.
+    Static {
+        /// The heap bound in bytes, not including the bytes for the
+        /// offset guard pages.
+        bound: u64,
+    },
+    /// Dynamic heap, which can be relocated to a different address
+    /// when grown. The bounds are loaded at runtime on every access.
+    Dynamic,
+}
+
+/// Heap metadata.
+///
+/// Heaps represent a WebAssembly linear memory.
+#[derive(Debug, Copy, Clone)]
+pub struct HeapData {
+    /// The offset to the base of the heap.
+    /// Relative to the `VMContext` pointer if the WebAssembly memory is
+    /// owned by the instance; relative to the `VMMemoryDefinition`
+    /// pointer loaded from `import_from` otherwise.
+    pub offset: u32,
+    /// The offset to the current length field.
+    /// Relative to the same base as `offset`.
+    pub current_length_offset: u32,
+    /// If the WebAssembly memory is imported or shared, the offset,
+    /// relative to the `VMContext`, of the pointer to its
+    /// `VMMemoryDefinition`.
+    pub import_from: Option<u32>,
+    /// The type of the memory index (`i32` or `i64` for 64-bit memories).
+    pub ty: WasmType,
+    /// The style of the heap.
+    pub style: HeapStyle,
+    /// Size, in bytes, of the offset guard pages located after the heap
+    /// bounds.
+    pub offset_guard_size: u64,
+}
+
 /// A function callee.
 /// It categorizes how the callee should be treated
 /// when performing the call.
@@ -62,8 +106,17 @@ pub struct FuncEnv<'a, 'translation: 'a, 'data: 'translation, P: PtrSize> {
     pub translation: &'translation ModuleTranslation<'data>,
     /// The module's function types.
     pub types: &'translation ModuleTypes,
+    /// Compilation tunables, e.g. whether fuel consumption or epoch
+    /// interruption checks should be emitted.
+    pub tunables: &'translation Tunables,
+    /// The index of the function being compiled.
+    pub index: DefinedFuncIndex,
     /// Track resolved table information.
     resolved_tables: HashMap<TableIndex, TableData>,
+    /// Track resolved heap information.
+    resolved_heaps: HashMap<MemoryIndex, HeapData>,
+    /// The target ISA.
+    pub isa: &'a dyn TargetIsa,
 }
 
 pub fn ptr_type_from_ptr_size(size: u8) -> WasmType {
@@ -78,12 +131,19 @@ impl<'a, 'translation, 'data, P: PtrSize> FuncEnv<'a, 'translation, 'data, P> {
         vmoffsets: &'a VMOffsets<P>,
         translation: &'translation ModuleTranslation<'data>,
         types: &'translation ModuleTypes,
+        tunables: &'translation Tunables,
+        index: DefinedFuncIndex,
+        isa: &'a dyn TargetIsa,
     ) -> Self {
         Self {
             vmoffsets,
             translation,
             types,
+            tunables,
+            index,
             resolved_tables: HashMap::new(),
+            resolved_heaps: HashMap::new(),
+            isa,
         }
     }
 
@@ -121,12 +181,19 @@ impl<'a, 'translation, 'data, P: PtrSize> FuncEnv<'a, 'translation, 'data, P> {
     }
 
     /// Resolves the type of the block in terms of [`wasmtime_environ::WasmType`].
-    pub fn resolve_block_type(&self, blockty: BlockType) -> SmallVec<[WasmType; 1]> {
+    pub fn resolve_block_type(&self, blockty: BlockType) -> WasmFuncType {
         use BlockType::*;
         match blockty {
-            Empty => smallvec![],
-            Type(ty) => smallvec![self.translation.module.convert_valtype(ty)],
-            _ => unimplemented!("multi-value"),
+            Empty => WasmFuncType::new([].into(), [].into()),
+            Type(ty) => WasmFuncType::new(
+                [].into(),
+                [self.translation.module.convert_valtype(ty)].into(),
+            ),
+            FuncType(idx) => {
+                let sig_index =
+                    self.translation.module.types[TypeIndex::from_u32(idx)].unwrap_function();
+                self.types[sig_index].clone()
+            }
         }
     }
 
@@ -178,4 +245,90 @@ impl<'a, 'translation, 'data, P: PtrSize> FuncEnv<'a, 'translation, 'data, P> {
     pub fn table_plan(&mut self, index: TableIndex) -> &TablePlan {
         &self.translation.module.table_plans[index]
     }
+
+    /// Returns the heap information for the given memory index.
+    pub fn resolve_heap(&mut self, index: MemoryIndex) -> HeapData {
+        match self.resolved_heaps.entry(index) {
+            Occupied(entry) => *entry.get(),
+            Vacant(entry) => {
+                let module = &self.translation.module;
+                let plan = &module.memory_plans[index];
+                let (import_from, base_offset, current_length_offset) =
+                    match module.defined_memory_index(index) {
+                        // Shared memories store a pointer to their
+                        // `VMMemoryDefinition`, which lives outside the
+                        // instance; treat them like imported memories.
+                        Some(defined) if plan.memory.shared => (
+                            Some(self.vmoffsets.vmctx_vmmemory_pointer(defined)),
+                            self.vmoffsets.ptr.vmmemory_definition_base().into(),
+                            self.vmoffsets
+                                .ptr
+                                .vmmemory_definition_current_length()
+                                .into(),
+                        ),
+                        Some(defined) => {
+                            let owned = module.owned_memory_index(defined);
+                            (
+                                None,
+                                self.vmoffsets.vmctx_vmmemory_definition_base(owned),
+                                self.vmoffsets
+                                    .vmctx_vmmemory_definition_current_length(owned),
+                            )
+                        }
+                        None => (
+                            Some(self.vmoffsets.vmctx_vmmemory_import_from(index)),
+                            self.vmoffsets.ptr.vmmemory_definition_base().into(),
+                            self.vmoffsets
+                                .ptr
+                                .vmmemory_definition_current_length()
+                                .into(),
+                        ),
+                    };
+
+                let (style, offset_guard_size) = heap_style_and_offset_guard_size(plan);
+
+                *entry.insert(HeapData {
+                    offset: base_offset,
+                    current_length_offset,
+                    import_from,
+                    ty: if plan.memory.memory64 {
+                        WasmType::I64
+                    } else {
+                        WasmType::I32
+                    },
+                    style,
+                    offset_guard_size,
+                })
+            }
+        }
+    }
+
+    /// Returns true if Spectre mitigations are enabled for heap accesses.
+    pub fn heap_access_spectre_mitigation(&self) -> bool {
+        self.isa.flags().enable_heap_access_spectre_mitigation()
+    }
+}
+
+/// Derives the [`HeapStyle`] and the size of the offset guard pages from
+/// the [`MemoryPlan`]. The guard pages located before the linear memory
+/// (`pre_guard_size`) don't require any special treatment during code
+/// generation, as accesses never underflow the heap base.
+fn heap_style_and_offset_guard_size(plan: &MemoryPlan) -> (HeapStyle, u64) {
+    match plan {
+        MemoryPlan {
+            style: MemoryStyle::Static { bound },
+            offset_guard_size,
+            ..
+        } => (
+            HeapStyle::Static {
+                bound: bound * u64::from(WASM_PAGE_SIZE),
+            },
+            *offset_guard_size,
+        ),
+        MemoryPlan {
+            style: MemoryStyle::Dynamic { .. },
+            offset_guard_size,
+            ..
+        } => (HeapStyle::Dynamic, *offset_guard_size),
+    }
 }
@@ -1,13 +1,18 @@
 use crate::{
-    abi::{ABISig, ABI},
+    abi::{ABIArg, ABISig, ABI},
     isa::reg::Reg,
-    masm::{CmpKind, MacroAssembler, OperandSize, RegImm, TrapCode},
+    masm::{CmpKind, ExtendKind, MacroAssembler, OperandSize, RegImm, ShiftKind, TrapCode},
     stack::{TypedReg, Val},
 };
 use anyhow::Result;
 use smallvec::SmallVec;
-use wasmparser::{BinaryReader, FuncValidator, Operator, ValidatorResources, VisitOperator};
-use wasmtime_environ::{PtrSize, TableIndex, TypeIndex, WasmHeapType, WasmType, FUNCREF_MASK};
+use wasmparser::{
+    BinaryReader, FuncValidator, MemArg, Operator, ValidatorResources, VisitOperator,
+};
+use wasmtime_environ::{
+    MemoryIndex, PtrSize, TableIndex, TypeIndex, WasmHeapType, WasmType, FUNCREF_MASK,
+    WASM_PAGE_SIZE,
+};
 
 mod context;
 pub(crate) use context::*;
@@ -19,6 +24,9 @@ mod control;
 pub(crate) use control::*;
 mod builtin;
 pub use builtin::*;
+mod bounds;
+
+use bounds::{Bounds, Index};
 
 /// The code generation abstraction.
 pub(crate) struct CodeGen<'a, 'translation: 'a, 'data: 'translation, M>
@@ -41,6 +49,16 @@ where
     // NB The 64 is set arbitrarily, we can adjust it as
     // we see fit.
     pub control_frames: SmallVec<[ControlStackFrame; 64]>,
+
+    /// Fuel consumed by the operators visited since the last time the
+    /// fuel counter in `VMRuntimeLimits` was updated. Only relevant
+    /// when fuel consumption is enabled.
+    fuel_consumed: i64,
+
+    /// The first error reported by the MacroAssembler while visiting the
+    /// current operator. Operators can't return errors themselves, so this
+    /// fails the compilation once the operator has been visited.
+    error: Option<anyhow::Error>,
 }
 
 impl<'a, 'translation, 'data, M> CodeGen<'a, 'translation, 'data, M>
@@ -59,6 +77,18 @@ where
             masm,
             env,
             control_frames: Default::default(),
+            // Every function consumes at least one unit of fuel, even if
+            // its body is empty.
+            fuel_consumed: 1,
+            error: None,
+        }
+    }
+
+    /// Records the outcome of a fallible MacroAssembler operation, keeping
+    /// only the first error.
+    pub fn handle_result(&mut self, result: Result<()>) {
+        if let Err(e) = result {
+            self.error.get_or_insert(e);
         }
     }
 
@@ -84,7 +114,7 @@ where
         // base control flow block.
         self.control_frames
             .push(ControlStackFrame::function_body_block(
-                self.sig.result,
+                self.sig.result.clone(),
                 self.masm,
                 &mut self.context,
             ));
@@ -113,7 +143,7 @@ where
                     self.context.reachable = true;
                     // Reset the stack to the original length and offset.
                     Self::reset_stack(&mut self.context, *original_stack_len);
-                    frame.bind_else(self.masm, self.context.reachable);
+                    frame.bind_else(self.masm, &mut self.context);
                 }
             }
             _ => unreachable!(),
@@ -121,7 +151,7 @@ where
     }
 
     pub fn handle_unreachable_end(&mut self) {
-        let frame = self.control_frames.pop().unwrap();
+        let mut frame = self.control_frames.pop().unwrap();
         // We just popped the outermost block.
         let is_outermost = self.control_frames.len() == 0;
         if frame.is_next_sequence_reachable() {
@@ -136,7 +166,16 @@ where
             // process, which involves pushing results to the value stack.
             if is_outermost {
                 frame.bind_exit_label(self.masm);
+            } else if matches!(frame, ControlStackFrame::If { ref params, .. } if params.len() > 0)
+            {
+                // An if with params and without an else branch forwards
+                // its params as results through the implicit else branch.
+                frame.bind_else(self.masm, &mut self.context);
+                frame.emit_end(self.masm, &mut self.context);
             } else {
+                // All the edges reaching the exit of the frame agree on
+                // the stack pointer offset at this point.
+                self.masm.reset_stack_pointer(frame.exit_sp_offset());
                 frame.bind_end(self.masm, &mut self.context);
             }
         } else if is_outermost {
@@ -186,6 +225,10 @@ where
             OperandSize::S64,
         );
 
+        self.maybe_emit_fuel_check();
+        self.maybe_emit_epoch_check();
+        self.maybe_emit_tier_up_check();
+
         while !body.eof() {
             let offset = body.original_position();
             body.visit_operator(&mut ValidateThenVisit(validator.visitor(offset), self))??;
@@ -200,14 +243,21 @@ where
                 $(
                     fn $visit(&mut self $($(,$arg: $argty)*)?) -> Self::Output {
                         self.0.$visit($($($arg.clone()),*)?)?;
+                        if matches!(stringify!($proposal), "simd" | "relaxed_simd") {
+                            self.1.check_simd_support()?;
+                        }
+                        let op = Operator::$op $({ $($arg: $arg.clone()),* })?;
                         // Only visit operators if the compiler is in a reachable code state. If
                         // the compiler is in an unrechable code state, most of the operators are
                         // ignored except for If, Block, Loop, Else and End. These operators need
                         // to be observed in order to keep the control stack frames balanced and to
                         // determine if reachability should be restored.
-                        let visit_when_unreachable = visit_op_when_unreachable(Operator::$op $({ $($arg: $arg.clone()),* })?);
+                        let visit_when_unreachable = visit_op_when_unreachable(&op);
                         if self.1.is_reachable() || visit_when_unreachable  {
-                            Ok(self.1.$visit($($($arg),*)?))
+                            self.1.before_visit_op(&op);
+                            let output = self.1.$visit($($($arg),*)?);
+                            self.1.take_error()?;
+                            Ok(output)
                         } else {
                             Ok(U::Output::default())
                         }
@@ -216,7 +266,7 @@ where
             };
         }
 
-        fn visit_op_when_unreachable(op: Operator) -> bool {
+        fn visit_op_when_unreachable(op: &Operator) -> bool {
             use Operator::*;
             match op {
                 If { .. } | Block { .. } | Loop { .. } | Else | End => true,
@@ -236,10 +286,43 @@ where
             }
         }
 
+        /// Trait to hook into the visiting of each operator.
+        trait VisitorHooks {
+            /// Invoked right before visiting the given operator.
+            fn before_visit_op(&mut self, op: &Operator);
+
+            /// Invoked before visiting any SIMD operator, to fail compilation
+            /// if the target doesn't support them.
+            fn check_simd_support(&self) -> Result<()>;
+
+            /// Invoked after visiting an operator, to fail compilation if
+            /// the MacroAssembler reported an error while emitting it.
+            fn take_error(&mut self) -> Result<()>;
+        }
+
+        impl<'a, 'b, 'c, M: MacroAssembler> VisitorHooks for CodeGen<'a, 'b, 'c, M> {
+            fn before_visit_op(&mut self, op: &Operator) {
+                if self.env.tunables.consume_fuel {
+                    self.fuel_before_visit_op(op);
+                }
+            }
+
+            fn check_simd_support(&self) -> Result<()> {
+                self.env.isa.check_simd_support()
+            }
+
+            fn take_error(&mut self) -> Result<()> {
+                match self.error.take() {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
+            }
+        }
+
         impl<'a, T, U> VisitOperator<'a> for ValidateThenVisit<'_, T, U>
         where
             T: VisitOperator<'a, Output = wasmparser::Result<()>>,
-            U: VisitOperator<'a> + ReachableState,
+            U: VisitOperator<'a> + ReachableState + VisitorHooks,
             U::Output: Default,
         {
             type Output = Result<U::Output>;
@@ -248,6 +331,220 @@ where
         }
     }
 
+    /// Accounts for the fuel consumed by the given operator, updating the
+    /// fuel counter in `VMRuntimeLimits` whenever the operator transfers
+    /// control out of the current basic block.
+    ///
+    /// The accounting mirrors the one performed by Cranelift: `nop`, `drop`
+    /// and most control flow operators are free; every other operator
+    /// consumes one unit of fuel.
+    fn fuel_before_visit_op(&mut self, op: &Operator) {
+        if !self.context.reachable {
+            // Any fuel consumed before entering an unreachable state
+            // must have been accounted for by the operator that caused
+            // the transition; the only exception is a statically out of
+            // bounds memory access, which unconditionally traps.
+            self.fuel_consumed = 0;
+            return;
+        }
+
+        self.fuel_consumed += match op {
+            Operator::Nop | Operator::Drop => 0,
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::Unreachable
+            | Operator::Return
+            | Operator::Else
+            | Operator::End => 0,
+            _ => 1,
+        };
+
+        match op {
+            Operator::Unreachable
+            | Operator::Return
+            | Operator::CallIndirect { .. }
+            | Operator::Call { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::End
+            | Operator::Else => self.emit_fuel_increment(),
+            _ => {}
+        }
+    }
+
+    /// Adds the fuel consumed since the last update to the fuel counter
+    /// in `VMRuntimeLimits`.
+    fn emit_fuel_increment(&mut self) {
+        let fuel_consumed = std::mem::replace(&mut self.fuel_consumed, 0);
+        if fuel_consumed == 0 {
+            return;
+        }
+
+        let limits_offset = self.env.vmoffsets.vmctx_runtime_limits();
+        let fuel_offset = self.env.vmoffsets.ptr.vmruntime_limits_fuel_consumed();
+        let limits = self.context.any_gpr(self.masm);
+        let fuel = self.context.any_gpr(self.masm);
+
+        self.masm
+            .load_ptr(self.masm.address_at_vmctx(limits_offset), limits);
+        let fuel_addr = self.masm.address_at_reg(limits, fuel_offset.into());
+        self.masm.load(fuel_addr, fuel, OperandSize::S64);
+        self.masm
+            .add(fuel, fuel, RegImm::i64(fuel_consumed), OperandSize::S64);
+        self.masm.store(fuel.into(), fuel_addr, OperandSize::S64);
+
+        self.context.free_reg(fuel);
+        self.context.free_reg(limits);
+    }
+
+    /// Emits a check to determine if the store has run out of fuel, calling
+    /// the `out_of_gas` builtin if that's the case. Only emitted at the
+    /// function entry and at loop headers, if fuel consumption is enabled.
+    pub fn maybe_emit_fuel_check(&mut self) {
+        if !self.env.tunables.consume_fuel {
+            return;
+        }
+
+        self.emit_fuel_increment();
+        // The check introduces a conditional call; spill to ensure that
+        // the value stack is in the same state in both paths.
+        self.context.spill(self.masm);
+
+        let limits_offset = self.env.vmoffsets.vmctx_runtime_limits();
+        let fuel_offset = self.env.vmoffsets.ptr.vmruntime_limits_fuel_consumed();
+        let fuel = self.context.any_gpr(self.masm);
+        self.masm
+            .load_ptr(self.masm.address_at_vmctx(limits_offset), fuel);
+        self.masm.load(
+            self.masm.address_at_reg(fuel, fuel_offset.into()),
+            fuel,
+            OperandSize::S64,
+        );
+
+        // Fuel is encoded as a negative number which gets incremented as
+        // fuel is consumed, so the store runs out of fuel once the counter
+        // is no longer negative.
+        let cont = self.masm.get_label();
+        self.masm
+            .branch(CmpKind::LtS, RegImm::i64(0), fuel, cont, OperandSize::S64);
+        self.context.free_reg(fuel);
+
+        let builtin = self.context.builtins.out_of_gas::<M::ABI, M::Ptr>();
+        self.emit_vmctx_builtin_call(builtin);
+        self.masm.bind(cont);
+    }
+
+    /// Emits a check to determine if the epoch deadline has been reached,
+    /// calling the `new_epoch` builtin if that's the case. Only emitted at
+    /// the function entry and at loop headers, if epoch interruption is
+    /// enabled.
+    pub fn maybe_emit_epoch_check(&mut self) {
+        if !self.env.tunables.epoch_interruption {
+            return;
+        }
+
+        // The check introduces a conditional call; spill to ensure that
+        // the value stack is in the same state in both paths.
+        self.context.spill(self.masm);
+
+        let epoch_ptr_offset = self.env.vmoffsets.vmctx_epoch_ptr();
+        let limits_offset = self.env.vmoffsets.vmctx_runtime_limits();
+        let deadline_offset = self.env.vmoffsets.ptr.vmruntime_limits_epoch_deadline();
+        let epoch = self.context.any_gpr(self.masm);
+        let deadline = self.context.any_gpr(self.masm);
+
+        self.masm
+            .load_ptr(self.masm.address_at_vmctx(epoch_ptr_offset), epoch);
+        self.masm
+            .load(self.masm.address_at_reg(epoch, 0), epoch, OperandSize::S64);
+        self.masm
+            .load_ptr(self.masm.address_at_vmctx(limits_offset), deadline);
+        self.masm.load(
+            self.masm.address_at_reg(deadline, deadline_offset.into()),
+            deadline,
+            OperandSize::S64,
+        );
+
+        let cont = self.masm.get_label();
+        self.masm
+            .branch(CmpKind::LtU, deadline.into(), epoch, cont, OperandSize::S64);
+        self.context.free_reg(epoch);
+        self.context.free_reg(deadline);
+
+        let builtin = self.context.builtins.new_epoch::<M::ABI, M::Ptr>();
+        self.emit_vmctx_builtin_call(builtin);
+        // The new deadline is reloaded on every check, so the result of
+        // the builtin can be discarded.
+        let deadline = self.context.pop_to_reg(self.masm, None);
+        self.context.free_reg(deadline);
+        self.masm.bind(cont);
+    }
+
+    /// Emits a check which counts the calls of the current function,
+    /// calling the `tier_up` builtin once it has been called often enough
+    /// to be recompiled. Only emitted at the function entry, if tiered
+    /// compilation is enabled.
+    pub fn maybe_emit_tier_up_check(&mut self) {
+        if !self.env.tunables.tier_up_counters {
+            return;
+        }
+
+        // The check introduces a conditional call; spill to ensure that
+        // the value stack is in the same state in both paths.
+        self.context.spill(self.masm);
+
+        let counters_offset = self.env.vmoffsets.vmctx_tier_up_counters();
+        let counter_offset = self.env.index.as_u32() * 4;
+        let counters = self.context.any_gpr(self.masm);
+        let counter = self.context.any_gpr(self.masm);
+        self.masm
+            .load_ptr(self.masm.address_at_vmctx(counters_offset), counters);
+        let counter_addr = self.masm.address_at_reg(counters, counter_offset);
+        self.masm.load(counter_addr, counter, OperandSize::S32);
+        self.masm
+            .sub(counter, counter, RegImm::i32(1), OperandSize::S32);
+        self.masm
+            .store(counter.into(), counter_addr, OperandSize::S32);
+
+        // The counter keeps counting down once it reaches zero, so the
+        // builtin is only called again after it wraps around.
+        let cont = self.masm.get_label();
+        self.masm
+            .branch(CmpKind::Ne, RegImm::i32(0), counter, cont, OperandSize::S32);
+        self.context.free_reg(counters);
+        self.context.free_reg(counter);
+
+        let builtin = self.context.builtins.tier_up::<M::ABI, M::Ptr>();
+        let ptr_type = self.env.ptr_type();
+        self.context
+            .stack
+            .push(TypedReg::new(ptr_type, <M::ABI as ABI>::vmctx_reg()).into());
+        self.context
+            .stack
+            .push(Val::i32(i32::try_from(self.env.index.as_u32()).unwrap()));
+        FnCall::emit::<M, M::Ptr, _>(self.masm, &mut self.context, |_| {
+            Callee::Builtin(builtin.clone())
+        });
+        self.masm.bind(cont);
+    }
+
+    /// Emits a call to a builtin function which takes the `VMContext`
+    /// as its only argument.
+    fn emit_vmctx_builtin_call(&mut self, builtin: BuiltinFunction) {
+        let ptr_type = self.env.ptr_type();
+        self.context
+            .stack
+            .push(TypedReg::new(ptr_type, <M::ABI as ABI>::vmctx_reg()).into());
+        FnCall::emit::<M, M::Ptr, _>(self.masm, &mut self.context, |_| {
+            Callee::Builtin(builtin.clone())
+        });
+    }
+
     /// Emits a a series of instructions that will type check a function reference call.
     pub fn emit_typecheck_funcref(&mut self, funcref_ptr: Reg, type_index: TypeIndex) {
         let ptr_size: OperandSize = self.env.ptr_type().into();
@@ -302,7 +599,7 @@ where
     fn spill_register_arguments(&mut self) {
         use WasmType::*;
         self.sig
-            .params
+            .params_without_retptr()
             .iter()
             .enumerate()
             .filter(|(_, a)| a.is_reg())
@@ -319,7 +616,7 @@ where
                     .expect("arg should be associated to a register");
 
                 match &ty {
-                    I32 | I64 | F32 | F64 => self.masm.store(src.into(), addr, ty.into()),
+                    I32 | I64 | F32 | F64 | V128 => self.masm.store(src.into(), addr, ty.into()),
                     Ref(rt) => match rt.heap_type {
                         WasmHeapType::Func => self.masm.store_ptr(src.into(), addr),
                         ht => unimplemented!("Support for WasmHeapType: {ht}"),
@@ -327,6 +624,19 @@ where
                     _ => unimplemented!("Support for WasmType {ty}"),
                 }
             });
+
+        // The return area pointer is not a WebAssembly local, so it gets
+        // spilled to its dedicated slot.
+        if let Some(ABIArg::Reg { reg, .. }) = self.sig.retptr() {
+            let slot = self
+                .context
+                .frame
+                .retptr_slot
+                .as_ref()
+                .expect("return area pointer slot");
+            let addr = self.masm.local_address(slot);
+            self.masm.store_ptr(*reg, addr);
+        }
     }
 
     /// Pops the value at the stack top and assigns it to the local at
@@ -410,6 +720,219 @@ where
 
         self.masm.bind(cont);
     }
+
+    /// Emits a series of instructions to compute the address of a
+    /// WebAssembly linear memory access, performing the bounds checks
+    /// required by the heap style. The index of the access is expected to
+    /// be at the top of the value stack.
+    ///
+    /// Returns `None` if the access is statically known to be out of
+    /// bounds; in that case an unconditional trap is emitted and the rest
+    /// of the current block is marked as unreachable.
+    pub fn emit_compute_heap_address(
+        &mut self,
+        memarg: &MemArg,
+        access_size: OperandSize,
+    ) -> Option<Reg> {
+        let ptr_size: OperandSize = self.env.ptr_type().into();
+        let enable_spectre_mitigation = self.env.heap_access_spectre_mitigation();
+        let memory_index = MemoryIndex::from_u32(memarg.memory);
+        let heap = self.env.resolve_heap(memory_index);
+        let index = Index::from_reg(self.context.pop_to_reg(self.masm, None).into());
+        let offset =
+            bounds::ensure_index_and_offset(self.masm, index, memarg.offset, &heap, ptr_size);
+        // The immediate offset fits in a u32 and the access size in a u8, so
+        // the addition below can't overflow.
+        let offset_with_access_size = u64::from(offset.as_u32()) + u64::from(access_size.bytes());
+
+        let addr = match heap.style {
+            // Dynamic heaps: the bounds are loaded at runtime and the full
+            // `index + offset + access_size` expression is checked against
+            // them.
+            HeapStyle::Dynamic => {
+                let bounds = bounds::load_dynamic_heap_bounds(&mut self.context, self.masm, &heap);
+
+                let index_reg = index.as_reg();
+                let index_offset_and_access_size = self.context.any_gpr(self.masm);
+                self.masm
+                    .mov(index_reg.into(), index_offset_and_access_size, ptr_size);
+                // Perform
+                // index = index + offset + access_size, trapping if the
+                // addition overflows.
+                self.masm.checked_uadd(
+                    index_offset_and_access_size,
+                    index_offset_and_access_size,
+                    RegImm::i64(offset_with_access_size as i64),
+                    ptr_size,
+                    TrapCode::HeapOutOfBounds,
+                );
+
+                let addr = bounds::load_heap_addr_checked(
+                    self.masm,
+                    &mut self.context,
+                    ptr_size,
+                    &heap,
+                    enable_spectre_mitigation,
+                    bounds,
+                    index,
+                    offset,
+                    |masm, bounds, _| {
+                        let bounds_reg = bounds.as_reg();
+                        masm.cmp(bounds_reg.into(), index_offset_and_access_size, ptr_size);
+                        CmpKind::GtU
+                    },
+                );
+                self.context.free_reg(bounds.as_reg());
+                self.context.free_reg(index_offset_and_access_size);
+                Some(addr)
+            }
+
+            // Static heaps, in which the access is statically known to be
+            // out of bounds. This is the case when the offset and the access
+            // size are greater than the heap bound.
+            HeapStyle::Static { bound } if offset_with_access_size > bound => {
+                self.masm.trap(TrapCode::HeapOutOfBounds);
+                self.context.reachable = false;
+                // Set the implicit outermost frame as target to perform the
+                // necessary stack clean up.
+                let outermost = &mut self.control_frames[0];
+                outermost.set_as_target();
+                None
+            }
+
+            // Static heaps with 32-bit indices, in which the bound plus the
+            // offset guard pages are large enough to cover any possible
+            // `index + offset + access_size`. In this case, the bounds
+            // checks can be elided: any out of bounds access will land in
+            // the guard pages and trap.
+            HeapStyle::Static { bound }
+                if heap.ty == WasmType::I32
+                    && u64::from(u32::MAX)
+                        <= bound.saturating_add(heap.offset_guard_size)
+                            - offset_with_access_size =>
+            {
+                let addr = self.context.any_gpr(self.masm);
+                bounds::load_heap_addr_unchecked(self.masm, &heap, index, offset, addr, ptr_size);
+                Some(addr)
+            }
+
+            // Static heaps, in which the bounds checks can't be elided:
+            // check that `index > bound - (offset + access_size)`. The
+            // subtraction can't underflow given the previous cases.
+            HeapStyle::Static { bound } => {
+                let bounds = Bounds::from_u64(bound);
+                let addr = bounds::load_heap_addr_checked(
+                    self.masm,
+                    &mut self.context,
+                    ptr_size,
+                    &heap,
+                    enable_spectre_mitigation,
+                    bounds,
+                    index,
+                    offset,
+                    |masm, bounds, index| {
+                        let adjusted_bounds = bounds.as_u64() - offset_with_access_size;
+                        let index_reg = index.as_reg();
+                        masm.cmp(RegImm::i64(adjusted_bounds as i64), index_reg, ptr_size);
+                        CmpKind::GtU
+                    },
+                );
+                Some(addr)
+            }
+        };
+
+        self.context.free_reg(index.as_reg());
+        addr
+    }
+
+    /// Emit `memory.size`, which derives the number of pages from the
+    /// current length of the memory in bytes.
+    pub fn emit_memory_size(&mut self, memory: MemoryIndex) {
+        let heap = self.env.resolve_heap(memory);
+        let length = bounds::load_dynamic_heap_bounds(&mut self.context, self.masm, &heap);
+        self.context
+            .stack
+            .push(TypedReg::i64(length.as_reg()).into());
+        self.context
+            .stack
+            .push(Val::i64(i64::from(WASM_PAGE_SIZE.trailing_zeros())));
+        self.masm
+            .shift(&mut self.context, ShiftKind::ShrU, OperandSize::S64);
+
+        if heap.ty == WasmType::I32 {
+            self.context
+                .convert_op(self.masm, WasmType::I32, |masm, dst, src, _size| {
+                    masm.wrap(src, dst);
+                });
+        }
+    }
+
+    /// Emit `memory.grow` through the `memory32_grow` builtin, which
+    /// expects the delta, in pages, at the top of the value stack.
+    pub fn emit_memory_grow(&mut self, memory: MemoryIndex) {
+        let heap = self.env.resolve_heap(memory);
+        let vmctx = TypedReg::new(self.env.ptr_type(), <M::ABI as ABI>::vmctx_reg());
+        let builtin = self.context.builtins.memory32_grow::<M::ABI, M::Ptr>();
+
+        // The builtin takes a 64-bit delta regardless of the memory's index
+        // type.
+        if heap.ty == WasmType::I32 {
+            self.context
+                .convert_op(self.masm, WasmType::I64, |masm, dst, src, _size| {
+                    masm.extend(src, dst, ExtendKind::I64ExtendI32U);
+                });
+        }
+
+        // The value stack at this point should contain:
+        // [ delta ] (stack top)
+        // while the builtin expects the vmctx and the memory index around
+        // it.
+        let at = self.context.stack.len() - 1;
+        self.context.stack.insert_many(at, [vmctx.into()]);
+        self.context
+            .stack
+            .push(Val::i32(i32::try_from(memory.as_u32()).unwrap()));
+        FnCall::emit::<M, M::Ptr, _>(self.masm, &mut self.context, |_| {
+            Callee::Builtin(builtin.clone())
+        });
+
+        // The builtin returns the previous size in pages as a pointer-sized
+        // value, or -1 if the memory couldn't be grown.
+        if heap.ty == WasmType::I32 {
+            self.context
+                .convert_op(self.masm, WasmType::I32, |masm, dst, src, _size| {
+                    masm.wrap(src, dst);
+                });
+        }
+    }
+
+    /// Emit a WebAssembly load.
+    pub fn emit_wasm_load(
+        &mut self,
+        arg: &MemArg,
+        ty: WasmType,
+        size: OperandSize,
+        kind: Option<ExtendKind>,
+    ) {
+        if let Some(addr) = self.emit_compute_heap_address(arg, size) {
+            let dst = self.context.reg_for_type(ty, self.masm);
+            let src = self.masm.address_at_reg(addr, 0);
+            self.masm.wasm_load(src, dst, size, kind);
+            self.context.stack.push(TypedReg::new(ty, dst).into());
+            self.context.free_reg(addr);
+        }
+    }
+
+    /// Emit a WebAssembly store.
+    pub fn emit_wasm_store(&mut self, arg: &MemArg, size: OperandSize) {
+        let src = self.context.pop_to_reg(self.masm, None);
+        if let Some(addr) = self.emit_compute_heap_address(arg, size) {
+            let dst = self.masm.address_at_reg(addr, 0);
+            self.masm.wasm_store(src.reg, dst, size);
+            self.context.free_reg(addr);
+        }
+        self.context.free_reg(src);
+    }
 }
 
 /// Returns the index of the [`ControlStackFrame`] for the given
@@ -82,12 +82,19 @@ pub(crate) struct Frame {
 
     /// The offset to the slot containing the `VMContext`.
     pub vmctx_slot: LocalSlot,
+
+    /// The slot holding the pointer to the return area, for functions
+    /// returning more than one value.
+    ///
+    /// The return area pointer is a hidden parameter, so this slot
+    /// isn't reachable through a local index.
+    pub retptr_slot: Option<LocalSlot>,
 }
 
 impl Frame {
     /// Allocate a new Frame.
     pub fn new<A: ABI>(sig: &ABISig, defined_locals: &DefinedLocals) -> Result<Self> {
-        let (mut locals, defined_locals_start) = Self::compute_arg_slots::<A>(sig)?;
+        let (mut locals, retptr_slot, defined_locals_start) = Self::compute_arg_slots::<A>(sig)?;
 
         // The defined locals have a zero-based offset by default
         // so we need to add the defined locals start to the offset.
@@ -107,6 +114,7 @@ impl Frame {
             locals,
             locals_size,
             vmctx_slot: LocalSlot::i64(vmctx_offset),
+            retptr_slot,
             defined_locals_range: DefinedLocalsRange(
                 defined_locals_start..defined_locals.stack_size,
             ),
@@ -132,7 +140,7 @@ impl Frame {
             .unwrap_or_else(|| panic!("Invalid local slot: {}", index))
     }
 
-    fn compute_arg_slots<A: ABI>(sig: &ABISig) -> Result<(Locals, u32)> {
+    fn compute_arg_slots<A: ABI>(sig: &ABISig) -> Result<(Locals, Option<LocalSlot>, u32)> {
         // Go over the function ABI-signature and
         // calculate the stack slots.
         //
@@ -164,12 +172,16 @@ impl Frame {
         let arg_base_offset = <A as ABI>::arg_base_offset().into();
         let mut next_stack = 0u32;
         let slots: Locals = sig
-            .params
+            .params_without_retptr()
             .iter()
             .map(|arg| Self::abi_arg_slot(&arg, &mut next_stack, arg_base_offset))
             .collect();
 
-        Ok((slots, next_stack))
+        let retptr_slot = sig
+            .retptr()
+            .map(|arg| Self::abi_arg_slot(&arg, &mut next_stack, arg_base_offset));
+
+        Ok((slots, retptr_slot, next_stack))
     }
 
     fn abi_arg_slot(arg: &ABIArg, next_stack: &mut u32, arg_base_offset: u32) -> LocalSlot {
@@ -3,7 +3,7 @@ use crate::abi::{ABIArg, ABIResult, ABISig, ABI};
 use crate::isa::{reg::Reg, CallingConvention};
 use crate::masm::OperandSize;
 use smallvec::SmallVec;
-use wasmtime_environ::{WasmFuncType, WasmType};
+use wasmtime_environ::{WasmFuncType, WasmHeapType, WasmType};
 
 #[derive(Default)]
 pub(crate) struct Aarch64ABI;
@@ -73,46 +73,50 @@ impl ABI for Aarch64ABI {
         returns: &[WasmType],
         call_conv: &CallingConvention,
     ) -> ABISig {
-        assert!(call_conv.is_apple_aarch64() || call_conv.is_default());
-
-        if returns.len() > 1 {
-            panic!("multi-value not supported");
-        }
+        assert!(call_conv.is_apple_aarch64() || call_conv.is_systemv() || call_conv.is_default());
 
         let mut stack_offset = 0;
         let mut index_env = RegIndexEnv::default();
 
-        let params: SmallVec<[ABIArg; 6]> = params
+        let mut params: SmallVec<[ABIArg; 6]> = params
             .iter()
             .map(|arg| Self::to_abi_arg(arg, &mut stack_offset, &mut index_env))
             .collect();
 
         let result = Self::result(returns, call_conv);
+        // The pointer to the return area is passed as the last parameter.
+        if result.has_stack_results() {
+            params.push(Self::to_abi_arg(
+                &WasmType::I64,
+                &mut stack_offset,
+                &mut index_env,
+            ));
+        }
         ABISig::new(params, result, stack_offset)
     }
 
     fn result(returns: &[WasmType], _call_conv: &CallingConvention) -> ABIResult {
-        // This invariant will be lifted once support for multi-value is added.
-        assert!(returns.len() <= 1, "multi-value not supported");
-
-        let ty = returns.get(0).copied();
-        ty.map(|ty| {
-            let reg = match ty {
-                WasmType::I32 | WasmType::I64 => regs::xreg(0),
-                WasmType::F32 | WasmType::F64 => regs::vreg(0),
-                t => panic!("Unsupported return type {:?}", t),
-            };
-            ABIResult::reg(ty, reg)
+        ABIResult::from(returns, |ty| match ty {
+            WasmType::I32 | WasmType::I64 => regs::xreg(0),
+            WasmType::F32 | WasmType::F64 => regs::vreg(0),
+            WasmType::Ref(rt) => {
+                assert!(rt.heap_type == WasmHeapType::Func);
+                regs::xreg(0)
+            }
+            t => panic!("Unsupported return type {:?}", t),
         })
-        .unwrap_or_else(|| ABIResult::void())
     }
 
     fn scratch_reg() -> Reg {
         regs::scratch()
     }
 
+    fn float_scratch_reg() -> Reg {
+        regs::float_scratch()
+    }
+
     fn sp_reg() -> Reg {
-        todo!()
+        regs::sp()
     }
 
     fn fp_reg() -> Reg {
@@ -127,8 +131,17 @@ impl ABI for Aarch64ABI {
         regs::callee_saved()
     }
 
-    fn stack_arg_slot_size_for_type(_ty: WasmType) -> u32 {
-        todo!()
+    fn stack_arg_slot_size_for_type(ty: WasmType) -> u32 {
+        // Stack arguments are always 8-byte aligned; see
+        // https://github.com/ARM-software/abi-aa/blob/2021Q1/aapcs64/aapcs64.rst#parameter-passing-rules
+        match ty {
+            WasmType::Ref(rt) => match rt.heap_type {
+                WasmHeapType::Func => Self::word_bytes(),
+                ht => unimplemented!("Support for WasmHeapType: {ht}"),
+            },
+            WasmType::I32 | WasmType::I64 | WasmType::F32 | WasmType::F64 => Self::word_bytes(),
+            ty => unimplemented!("Support for WasmType: {ty}"),
+        }
     }
 }
 
@@ -139,6 +152,11 @@ impl Aarch64ABI {
         index_env: &mut RegIndexEnv,
     ) -> ABIArg {
         let (reg, ty) = match wasm_arg {
+            ty @ WasmType::Ref(rt) => match rt.heap_type {
+                WasmHeapType::Func => (index_env.next_xreg().map(regs::xreg), ty),
+                ht => unimplemented!("Support for WasmHeapType: {ht}"),
+            },
+
             ty @ (WasmType::I32 | WasmType::I64) => (index_env.next_xreg().map(regs::xreg), ty),
 
             ty @ (WasmType::F32 | WasmType::F64) => (index_env.next_vreg().map(regs::vreg), ty),
@@ -148,7 +166,7 @@ impl Aarch64ABI {
 
         let ty = *ty;
         let default = || {
-            let size = Self::word_bytes();
+            let size = Self::stack_arg_slot_size_for_type(ty);
             let arg = ABIArg::stack_offset(*stack_offset, ty);
             *stack_offset += size;
             arg
@@ -1,17 +1,24 @@
 //! Assembler library implementation for Aarch64.
 
 use super::{address::Address, regs};
+use crate::masm::{CmpKind, ExtendKind, RoundingMode, ShiftKind};
 use crate::{masm::OperandSize, reg::Reg};
 use cranelift_codegen::{
-    ir::MemFlags,
-    isa::aarch64::inst::{
-        self,
-        emit::{EmitInfo, EmitState},
-        ALUOp, ALUOp3, AMode, ExtendOp, Imm12, Inst, PairAMode,
+    ir::{types, ExternalName, LibCall, MemFlags, Opcode, TrapCode, UserExternalNameRef},
+    isa::{
+        aarch64::inst::{
+            self,
+            emit::{EmitInfo, EmitState},
+            ALUOp, ALUOp3, AMode, BitOp, BranchTarget, CallIndInfo, CallInfo, Cond, CondBrKind,
+            ExtendOp, FPUOp1, FPUOp2, FpuRoundMode, FpuToIntOp, Imm12, ImmLogic, ImmShift, Inst,
+            IntToFpuOp, PairAMode, ScalarSize, VecLanesOp, VecMisc2, VectorSize,
+        },
+        CallConv,
     },
     settings, Final, MachBuffer, MachBufferFinalized, MachInstEmit, MachInstEmitState, MachLabel,
     Writable,
 };
+use smallvec::smallvec;
 
 impl From<OperandSize> for inst::OperandSize {
     fn from(size: OperandSize) -> Self {
@@ -23,6 +30,35 @@ impl From<OperandSize> for inst::OperandSize {
     }
 }
 
+impl From<OperandSize> for ScalarSize {
+    fn from(size: OperandSize) -> Self {
+        match size {
+            OperandSize::S8 => Self::Size8,
+            OperandSize::S16 => Self::Size16,
+            OperandSize::S32 => Self::Size32,
+            OperandSize::S64 => Self::Size64,
+            OperandSize::S128 => Self::Size128,
+        }
+    }
+}
+
+impl From<CmpKind> for Cond {
+    fn from(value: CmpKind) -> Self {
+        match value {
+            CmpKind::Eq => Cond::Eq,
+            CmpKind::Ne => Cond::Ne,
+            CmpKind::LtS => Cond::Lt,
+            CmpKind::LtU => Cond::Lo,
+            CmpKind::GtS => Cond::Gt,
+            CmpKind::GtU => Cond::Hi,
+            CmpKind::LeS => Cond::Le,
+            CmpKind::LeU => Cond::Ls,
+            CmpKind::GeS => Cond::Ge,
+            CmpKind::GeU => Cond::Hs,
+        }
+    }
+}
+
 /// Low level assembler implementation for Aarch64.
 pub(crate) struct Assembler {
     /// The machine instruction buffer.
@@ -78,43 +114,109 @@ impl Assembler {
     }
 
     /// Store a register.
-    pub fn str(&mut self, reg: Reg, addr: Address, size: OperandSize) {
-        let mem: AMode = addr.try_into().unwrap();
-        let flags = MemFlags::trusted();
+    pub fn str(&mut self, reg: Reg, addr: Address, size: OperandSize, flags: MemFlags) {
+        let mem = Self::to_amode(addr, size);
 
         use OperandSize::*;
-        let inst = match size {
-            S64 => Inst::Store64 {
+        let inst = match (reg.is_int(), size) {
+            (true, S8) => Inst::Store8 {
                 rd: reg.into(),
                 mem,
                 flags,
             },
-            S32 => Inst::Store32 {
+            (true, S16) => Inst::Store16 {
                 rd: reg.into(),
                 mem,
                 flags,
             },
+            (true, S32) => Inst::Store32 {
+                rd: reg.into(),
+                mem,
+                flags,
+            },
+            (true, S64) => Inst::Store64 {
+                rd: reg.into(),
+                mem,
+                flags,
+            },
+            (false, S32) => Inst::FpuStore32 {
+                rd: reg.into(),
+                mem,
+                flags,
+            },
+            (false, S64) => Inst::FpuStore64 {
+                rd: reg.into(),
+                mem,
+                flags,
+            },
+            _ => unreachable!(),
+        };
+
+        self.emit(inst);
+    }
+
+    /// Load a register. Integer loads narrower than 64 bits are zero
+    /// extended.
+    pub fn ldr(&mut self, addr: Address, rd: Reg, size: OperandSize, flags: MemFlags) {
+        use OperandSize::*;
+        let writable_reg = Writable::from_reg(rd.into());
+        let mem = Self::to_amode(addr, size);
 
+        let inst = match (rd.is_int(), size) {
+            (true, S8) => Inst::ULoad8 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
+            (true, S16) => Inst::ULoad16 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
+            (true, S32) => Inst::ULoad32 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
+            (true, S64) => Inst::ULoad64 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
+            (false, S32) => Inst::FpuLoad32 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
+            (false, S64) => Inst::FpuLoad64 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
             _ => unreachable!(),
         };
 
         self.emit(inst);
     }
 
-    /// Load a register.
-    pub fn ldr(&mut self, addr: Address, rd: Reg, size: OperandSize) {
+    /// Load a register, sign extending the loaded value to 64 bits.
+    pub fn sldr(&mut self, addr: Address, rd: Reg, size: OperandSize, flags: MemFlags) {
         use OperandSize::*;
         let writable_reg = Writable::from_reg(rd.into());
-        let mem: AMode = addr.try_into().unwrap();
-        let flags = MemFlags::trusted();
+        let mem = Self::to_amode(addr, size);
 
         let inst = match size {
-            S64 => Inst::ULoad64 {
+            S8 => Inst::SLoad8 {
                 rd: writable_reg,
                 mem,
                 flags,
             },
-            S32 => Inst::ULoad32 {
+            S16 => Inst::SLoad16 {
+                rd: writable_reg,
+                mem,
+                flags,
+            },
+            S32 => Inst::SLoad32 {
                 rd: writable_reg,
                 mem,
                 flags,
@@ -149,6 +251,35 @@ impl Assembler {
         });
     }
 
+    /// Floating point register to register move.
+    pub fn fmov_rr(&mut self, rn: Reg, rd: Reg) {
+        self.emit(Inst::FpuMove64 {
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+        });
+    }
+
+    /// Move the bits of a general purpose register to a floating point
+    /// register.
+    pub fn mov_to_fpu(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit(Inst::MovToFpu {
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+            size: size.into(),
+        });
+    }
+
+    /// Move the bits of a floating point register to a general purpose
+    /// register.
+    pub fn mov_from_vec(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit(Inst::MovFromVec {
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+            idx: 0,
+            size: size.into(),
+        });
+    }
+
     /// Add with three registers.
     pub fn add_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
         self.emit_alu_rrr_extend(ALUOp::Add, rm, rn, rd, size);
@@ -166,6 +297,23 @@ impl Assembler {
         }
     }
 
+    /// Add with three registers, setting the overflow flags.
+    pub fn adds_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr_extend(ALUOp::AddS, rm, rn, rd, size);
+    }
+
+    /// Add immediate and register, setting the overflow flags.
+    pub fn adds_ir(&mut self, imm: u64, rn: Reg, rd: Reg, size: OperandSize) {
+        let alu_op = ALUOp::AddS;
+        if let Some(imm) = Imm12::maybe_from_u64(imm) {
+            self.emit_alu_rri(alu_op, imm, rn, rd, size);
+        } else {
+            let scratch = regs::scratch();
+            self.load_constant(imm, scratch);
+            self.emit_alu_rrr_extend(alu_op, scratch, rn, rd, size);
+        }
+    }
+
     /// Substract with three registers.
     pub fn sub_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
         self.emit_alu_rrr_extend(ALUOp::Sub, rm, rn, rd, size);
@@ -183,6 +331,11 @@ impl Assembler {
         }
     }
 
+    /// Negate a register.
+    pub fn neg_rr(&mut self, rm: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::Sub, rm, regs::zero(), rd, size);
+    }
+
     /// Multiply with three registers.
     pub fn mul_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
         self.emit_alu_rrrr(ALUOp3::MAdd, rm, rn, rd, regs::zero(), size);
@@ -195,11 +348,418 @@ impl Assembler {
         self.emit_alu_rrrr(ALUOp3::MAdd, scratch, rn, rd, regs::zero(), size);
     }
 
+    /// Multiply and subtract: rd = ra - (rn * rm).
+    pub fn msub_rrrr(&mut self, rm: Reg, rn: Reg, ra: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrrr(ALUOp3::MSub, rm, rn, rd, ra, size);
+    }
+
+    /// Signed division with three registers.
+    pub fn sdiv_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::SDiv, rm, rn, rd, size);
+    }
+
+    /// Unsigned division with three registers.
+    pub fn udiv_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::UDiv, rm, rn, rd, size);
+    }
+
+    /// Bitwise and with three registers.
+    pub fn and_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::And, rm, rn, rd, size);
+    }
+
+    /// Bitwise and immediate and register.
+    pub fn and_ir(&mut self, imm: u64, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rri_logic(ALUOp::And, imm, rn, rd, size);
+    }
+
+    /// Bitwise or with three registers.
+    pub fn or_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::Orr, rm, rn, rd, size);
+    }
+
+    /// Bitwise or immediate and register.
+    pub fn or_ir(&mut self, imm: u64, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rri_logic(ALUOp::Orr, imm, rn, rd, size);
+    }
+
+    /// Bitwise exclusive or with three registers.
+    pub fn xor_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::Eor, rm, rn, rd, size);
+    }
+
+    /// Bitwise exclusive or immediate and register.
+    pub fn xor_ir(&mut self, imm: u64, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_alu_rri_logic(ALUOp::Eor, imm, rn, rd, size);
+    }
+
+    /// Shift with three registers. Left rotations are not natively
+    /// supported and must be lowered by the caller.
+    pub fn shift_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, kind: ShiftKind, size: OperandSize) {
+        let alu_op = Self::shift_kind_to_alu_op(kind);
+        self.emit_alu_rrr(alu_op, rm, rn, rd, size);
+    }
+
+    /// Shift immediate and register. Left rotations are not natively
+    /// supported and must be lowered by the caller.
+    pub fn shift_ir(&mut self, imm: u64, rn: Reg, rd: Reg, kind: ShiftKind, size: OperandSize) {
+        let alu_op = Self::shift_kind_to_alu_op(kind);
+        if let Some(immshift) = ImmShift::maybe_from_u64(imm) {
+            self.emit(Inst::AluRRImmShift {
+                alu_op,
+                size: size.into(),
+                rd: Writable::from_reg(rd.into()),
+                rn: rn.into(),
+                immshift,
+            });
+        } else {
+            let scratch = regs::scratch();
+            self.load_constant(imm, scratch);
+            self.emit_alu_rrr(alu_op, scratch, rn, rd, size);
+        }
+    }
+
+    /// Count leading zeros.
+    pub fn clz(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_bit_rr(BitOp::Clz, rn, rd, size);
+    }
+
+    /// Reverse the bits of the source register.
+    pub fn rbit(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_bit_rr(BitOp::RBit, rn, rd, size);
+    }
+
+    /// Count the number of set bits in the source register, using
+    /// the given floating point register as a temporary.
+    pub fn cnt(&mut self, rn: Reg, rd: Reg, tmp: Reg, size: OperandSize) {
+        let writable_tmp = Writable::from_reg(tmp.into());
+        self.mov_to_fpu(rn, tmp, size);
+        self.emit(Inst::VecMisc {
+            op: VecMisc2::Cnt,
+            rd: writable_tmp,
+            rn: tmp.into(),
+            size: VectorSize::Size8x8,
+        });
+        self.emit(Inst::VecLanes {
+            op: VecLanesOp::Addv,
+            rd: writable_tmp,
+            rn: tmp.into(),
+            size: VectorSize::Size8x8,
+        });
+        self.mov_from_vec(tmp, rd, OperandSize::S8);
+    }
+
+    /// Zero or sign extend the source register.
+    pub fn extend(&mut self, rn: Reg, rd: Reg, kind: ExtendKind) {
+        use ExtendKind::*;
+        let (from_bits, to_bits) = match kind {
+            I32Extend8S | I32Extend8U => (8, 32),
+            I32Extend16S | I32Extend16U => (16, 32),
+            I64Extend8S | I64Extend8U => (8, 64),
+            I64Extend16S | I64Extend16U => (16, 64),
+            I64ExtendI32S | I64ExtendI32U => (32, 64),
+        };
+
+        self.emit(Inst::Extend {
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+            signed: kind.signed(),
+            from_bits,
+            to_bits,
+        });
+    }
+
+    /// Compare two registers; sets the flags for `rn - rm`.
+    pub fn cmp_rr(&mut self, rm: Reg, rn: Reg, size: OperandSize) {
+        self.emit_alu_rrr(ALUOp::SubS, rm, rn, regs::zero(), size);
+    }
+
+    /// Compare a register and an immediate; sets the flags for `rn - imm`.
+    pub fn cmp_ir(&mut self, imm: u64, rn: Reg, size: OperandSize) {
+        let alu_op = ALUOp::SubS;
+        if let Some(imm) = Imm12::maybe_from_u64(imm) {
+            self.emit_alu_rri(alu_op, imm, rn, regs::zero(), size);
+        } else {
+            let scratch = regs::scratch();
+            self.load_constant(imm, scratch);
+            self.emit_alu_rrr(alu_op, scratch, rn, regs::zero(), size);
+        }
+    }
+
+    /// Conditional select: `rd = cond ? rn : rm`.
+    pub fn csel(&mut self, rn: Reg, rm: Reg, rd: Reg, cond: Cond) {
+        self.emit(Inst::CSel {
+            rd: Writable::from_reg(rd.into()),
+            cond,
+            rn: rn.into(),
+            rm: rm.into(),
+        });
+    }
+
+    /// Floating point conditional select: `rd = cond ? rn : rm`.
+    pub fn fpu_csel(&mut self, rn: Reg, rm: Reg, rd: Reg, cond: Cond, size: OperandSize) {
+        let rd = Writable::from_reg(rd.into());
+        let (rn, rm) = (rn.into(), rm.into());
+        let inst = match size {
+            OperandSize::S32 => Inst::FpuCSel32 { rd, rn, rm, cond },
+            OperandSize::S64 => Inst::FpuCSel64 { rd, rn, rm, cond },
+            _ => unreachable!(),
+        };
+        self.emit(inst);
+    }
+
+    /// Set the destination register to 1 if the condition is met,
+    /// else to 0.
+    pub fn cset(&mut self, rd: Reg, cond: Cond) {
+        self.emit(Inst::CSet {
+            rd: Writable::from_reg(rd.into()),
+            cond,
+        });
+    }
+
+    /// Floating point add with three registers.
+    pub fn fadd_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rrr(FPUOp2::Add, rm, rn, rd, size);
+    }
+
+    /// Floating point subtract with three registers.
+    pub fn fsub_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rrr(FPUOp2::Sub, rm, rn, rd, size);
+    }
+
+    /// Floating point multiply with three registers.
+    pub fn fmul_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rrr(FPUOp2::Mul, rm, rn, rd, size);
+    }
+
+    /// Floating point division with three registers.
+    pub fn fdiv_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rrr(FPUOp2::Div, rm, rn, rd, size);
+    }
+
+    /// Floating point minimum with three registers.
+    pub fn fmin_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rrr(FPUOp2::Min, rm, rn, rd, size);
+    }
+
+    /// Floating point maximum with three registers.
+    pub fn fmax_rrr(&mut self, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rrr(FPUOp2::Max, rm, rn, rd, size);
+    }
+
+    /// Floating point absolute value.
+    pub fn fabs_rr(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rr(FPUOp1::Abs, rn, rd, size);
+    }
+
+    /// Floating point negation.
+    pub fn fneg_rr(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rr(FPUOp1::Neg, rn, rd, size);
+    }
+
+    /// Floating point square root.
+    pub fn fsqrt_rr(&mut self, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit_fpu_rr(FPUOp1::Sqrt, rn, rd, size);
+    }
+
+    /// Convert between single and double precision floating point values.
+    pub fn fcvt_rr(&mut self, rn: Reg, rd: Reg, src_size: OperandSize, dst_size: OperandSize) {
+        let (op, size) = match (src_size, dst_size) {
+            (OperandSize::S32, OperandSize::S64) => (FPUOp1::Cvt32To64, OperandSize::S32),
+            (OperandSize::S64, OperandSize::S32) => (FPUOp1::Cvt64To32, OperandSize::S64),
+            _ => unreachable!(),
+        };
+        self.emit_fpu_rr(op, rn, rd, size);
+    }
+
+    /// Round a floating point value to an integral floating point value.
+    pub fn fround_rr(&mut self, rn: Reg, rd: Reg, mode: RoundingMode, size: OperandSize) {
+        let op = match (mode, size) {
+            (RoundingMode::Nearest, OperandSize::S32) => FpuRoundMode::Nearest32,
+            (RoundingMode::Up, OperandSize::S32) => FpuRoundMode::Plus32,
+            (RoundingMode::Down, OperandSize::S32) => FpuRoundMode::Minus32,
+            (RoundingMode::Zero, OperandSize::S32) => FpuRoundMode::Zero32,
+            (RoundingMode::Nearest, OperandSize::S64) => FpuRoundMode::Nearest64,
+            (RoundingMode::Up, OperandSize::S64) => FpuRoundMode::Plus64,
+            (RoundingMode::Down, OperandSize::S64) => FpuRoundMode::Minus64,
+            (RoundingMode::Zero, OperandSize::S64) => FpuRoundMode::Zero64,
+            _ => unreachable!(),
+        };
+        self.emit(Inst::FpuRound {
+            op,
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+        });
+    }
+
+    /// Floating point comparison; sets the flags for `rn - rm`.
+    pub fn fcmp(&mut self, rm: Reg, rn: Reg, size: OperandSize) {
+        self.emit(Inst::FpuCmp {
+            size: size.into(),
+            rn: rn.into(),
+            rm: rm.into(),
+        });
+    }
+
+    /// Convert an integer to a floating point value.
+    pub fn cvt_int_to_float(
+        &mut self,
+        rn: Reg,
+        rd: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        signed: bool,
+    ) {
+        use OperandSize::*;
+        let op = match (signed, src_size, dst_size) {
+            (true, S32, S32) => IntToFpuOp::I32ToF32,
+            (true, S32, S64) => IntToFpuOp::I32ToF64,
+            (true, S64, S32) => IntToFpuOp::I64ToF32,
+            (true, S64, S64) => IntToFpuOp::I64ToF64,
+            (false, S32, S32) => IntToFpuOp::U32ToF32,
+            (false, S32, S64) => IntToFpuOp::U32ToF64,
+            (false, S64, S32) => IntToFpuOp::U64ToF32,
+            (false, S64, S64) => IntToFpuOp::U64ToF64,
+            _ => unreachable!(),
+        };
+        self.emit(Inst::IntToFpu {
+            op,
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+        });
+    }
+
+    /// Convert a floating point value to an integer, rounding towards
+    /// zero. Out of range values saturate to the bounds of the
+    /// destination type and NaN is converted to zero.
+    pub fn cvt_float_to_int(
+        &mut self,
+        rn: Reg,
+        rd: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        signed: bool,
+    ) {
+        use OperandSize::*;
+        let op = match (signed, src_size, dst_size) {
+            (true, S32, S32) => FpuToIntOp::F32ToI32,
+            (true, S32, S64) => FpuToIntOp::F32ToI64,
+            (true, S64, S32) => FpuToIntOp::F64ToI32,
+            (true, S64, S64) => FpuToIntOp::F64ToI64,
+            (false, S32, S32) => FpuToIntOp::F32ToU32,
+            (false, S32, S64) => FpuToIntOp::F32ToU64,
+            (false, S64, S32) => FpuToIntOp::F64ToU32,
+            (false, S64, S64) => FpuToIntOp::F64ToU64,
+            _ => unreachable!(),
+        };
+        self.emit(Inst::FpuToInt {
+            op,
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+        });
+    }
+
     /// Return instruction.
     pub fn ret(&mut self) {
         self.emit(Inst::Ret {});
     }
 
+    /// Emit a call to a locally defined function through an index.
+    pub fn call_with_index(&mut self, index: u32) {
+        let dest = ExternalName::user(UserExternalNameRef::new(index as usize));
+        self.call_with_name(dest);
+    }
+
+    /// Emit a call to a well-known libcall.
+    pub fn call_with_lib(&mut self, lib: LibCall) {
+        self.call_with_name(ExternalName::LibCall(lib));
+    }
+
+    /// Emit an indirect call to the address held in the given register.
+    pub fn call_with_reg(&mut self, callee: Reg) {
+        self.emit(Inst::CallInd {
+            info: Box::new(CallIndInfo {
+                rn: callee.into(),
+                uses: smallvec![],
+                defs: smallvec![],
+                clobbers: Default::default(),
+                opcode: Opcode::CallIndirect,
+                caller_callconv: CallConv::SystemV,
+                callee_callconv: CallConv::SystemV,
+                callee_pop_size: 0,
+            }),
+        });
+    }
+
+    fn call_with_name(&mut self, dest: ExternalName) {
+        self.emit(Inst::Call {
+            info: Box::new(CallInfo {
+                dest,
+                uses: smallvec![],
+                defs: smallvec![],
+                clobbers: Default::default(),
+                opcode: Opcode::Call,
+                caller_callconv: CallConv::SystemV,
+                callee_callconv: CallConv::SystemV,
+                callee_pop_size: 0,
+            }),
+        });
+    }
+
+    /// Performs an unconditional jump to the given label.
+    pub fn jmp(&mut self, target: MachLabel) {
+        self.emit(Inst::Jump {
+            dest: BranchTarget::Label(target),
+        });
+    }
+
+    /// Emits a conditional jump to the given label; falls through to the
+    /// next instruction if the condition is not met.
+    pub fn jmp_if(&mut self, cond: Cond, taken: MachLabel) {
+        let fallthrough = self.get_label();
+        self.emit(Inst::CondBr {
+            taken: BranchTarget::Label(taken),
+            not_taken: BranchTarget::Label(fallthrough),
+            kind: CondBrKind::Cond(cond),
+        });
+        self.buffer
+            .bind_label(fallthrough, self.emit_state.ctrl_plane_mut());
+    }
+
+    /// Emits a jump table sequence. The jump table sequence expects
+    /// the flags to be set by a comparison between the index and
+    /// the number of targets, and jumps to the default target if the
+    /// index is out of bounds.
+    pub fn jmp_table(
+        &mut self,
+        targets: &[MachLabel],
+        default: MachLabel,
+        index: Reg,
+        tmp1: Reg,
+        tmp2: Reg,
+    ) {
+        self.emit(Inst::JTSequence {
+            default,
+            targets: Box::new(targets.to_vec()),
+            ridx: index.into(),
+            rtmp1: Writable::from_reg(tmp1.into()),
+            rtmp2: Writable::from_reg(tmp2.into()),
+        });
+    }
+
+    /// Emit a trap instruction.
+    pub fn trap(&mut self, code: TrapCode) {
+        self.emit(Inst::Udf { trap_code: code });
+    }
+
+    /// Conditional trap.
+    pub fn trapif(&mut self, cond: Cond, trap_code: TrapCode) {
+        self.emit(Inst::TrapIf {
+            kind: CondBrKind::Cond(cond),
+            trap_code,
+        });
+    }
+
     // Helpers for ALU operations.
 
     fn emit_alu_rri(&mut self, op: ALUOp, imm: Imm12, rn: Reg, rd: Reg, size: OperandSize) {
@@ -212,6 +772,38 @@ impl Assembler {
         });
     }
 
+    fn emit_alu_rri_logic(&mut self, op: ALUOp, imm: u64, rn: Reg, rd: Reg, size: OperandSize) {
+        let ty = match size {
+            OperandSize::S32 => types::I32,
+            OperandSize::S64 => types::I64,
+            _ => unreachable!(),
+        };
+
+        if let Some(imml) = ImmLogic::maybe_from_u64(imm, ty) {
+            self.emit(Inst::AluRRImmLogic {
+                alu_op: op,
+                size: size.into(),
+                rd: Writable::from_reg(rd.into()),
+                rn: rn.into(),
+                imml,
+            });
+        } else {
+            let scratch = regs::scratch();
+            self.load_constant(imm, scratch);
+            self.emit_alu_rrr(op, scratch, rn, rd, size);
+        }
+    }
+
+    fn emit_alu_rrr(&mut self, op: ALUOp, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit(Inst::AluRRR {
+            alu_op: op,
+            size: size.into(),
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+            rm: rm.into(),
+        });
+    }
+
     fn emit_alu_rrr_extend(&mut self, op: ALUOp, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
         self.emit(Inst::AluRRRExtend {
             alu_op: op,
@@ -234,6 +826,63 @@ impl Assembler {
         });
     }
 
+    fn emit_bit_rr(&mut self, op: BitOp, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit(Inst::BitRR {
+            op,
+            size: size.into(),
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+        });
+    }
+
+    fn emit_fpu_rrr(&mut self, op: FPUOp2, rm: Reg, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit(Inst::FpuRRR {
+            fpu_op: op,
+            size: size.into(),
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+            rm: rm.into(),
+        });
+    }
+
+    fn emit_fpu_rr(&mut self, op: FPUOp1, rn: Reg, rd: Reg, size: OperandSize) {
+        self.emit(Inst::FpuRR {
+            fpu_op: op,
+            size: size.into(),
+            rd: Writable::from_reg(rd.into()),
+            rn: rn.into(),
+        });
+    }
+
+    // Lower an address to Cranelift's addressing mode. Register and
+    // offset addresses are scaled by the size of the access, which
+    // is used when encoding large offsets.
+    fn to_amode(addr: Address, size: OperandSize) -> AMode {
+        match addr.try_into().unwrap() {
+            AMode::RegOffset { rn, off, .. } => {
+                let ty = match size {
+                    OperandSize::S8 => types::I8,
+                    OperandSize::S16 => types::I16,
+                    OperandSize::S32 => types::I32,
+                    OperandSize::S64 => types::I64,
+                    OperandSize::S128 => types::I128,
+                };
+                AMode::RegOffset { rn, off, ty }
+            }
+            mem => mem,
+        }
+    }
+
+    fn shift_kind_to_alu_op(kind: ShiftKind) -> ALUOp {
+        match kind {
+            ShiftKind::Shl => ALUOp::Lsl,
+            ShiftKind::ShrS => ALUOp::Asr,
+            ShiftKind::ShrU => ALUOp::Lsr,
+            ShiftKind::Rotr => ALUOp::RotR,
+            ShiftKind::Rotl => unreachable!("left rotations must be lowered to right rotations"),
+        }
+    }
+
     /// Get a label from the underlying machine code buffer.
     pub fn get_label(&mut self) -> MachLabel {
         self.buffer.get_label()
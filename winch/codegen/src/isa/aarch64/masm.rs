@@ -1,14 +1,20 @@
 use super::{abi::Aarch64ABI, address::Address, asm::Assembler, regs};
 use crate::{
-    abi::{self, local::LocalSlot},
-    codegen::{CodeGenContext, TableData},
-    isa::reg::Reg,
+    abi::{self, align_to, calculate_frame_adjustment, local::LocalSlot, ABI},
+    codegen::{ptr_type_from_ptr_size, CodeGenContext, TableData},
+    isa::reg::{Reg, RegClass},
     masm::{
-        CalleeKind, CmpKind, DivKind, Imm as I, MacroAssembler as Masm, OperandSize, RegImm,
-        RemKind, RoundingMode, ShiftKind, StackSlot, TrapCode,
+        CalleeKind, CmpKind, DivKind, ExtendKind, FloatCmpKind, Imm as I, MacroAssembler as Masm,
+        OperandSize, RegImm, RemKind, RoundingMode, ShiftKind, StackSlot, TrapCode, TruncKind,
+        V128BinaryOp, V128Lanes, V128UnaryOp,
     },
+    stack::TypedReg,
 };
-use cranelift_codegen::{settings, Final, MachBufferFinalized, MachLabel};
+use anyhow::{bail, Result};
+use cranelift_codegen::{
+    ir::MemFlags, isa::aarch64::inst::Cond, settings, Final, MachBufferFinalized, MachLabel,
+};
+use wasmtime_environ::PtrSize;
 
 /// Aarch64 MacroAssembler.
 pub(crate) struct MacroAssembler {
@@ -16,14 +22,20 @@ pub(crate) struct MacroAssembler {
     asm: Assembler,
     /// Stack pointer offset.
     sp_offset: u32,
+    /// Shared flags.
+    shared_flags: settings::Flags,
+    /// The target pointer size.
+    ptr_size: OperandSize,
 }
 
 impl MacroAssembler {
     /// Create an Aarch64 MacroAssembler.
-    pub fn new(shared_flags: settings::Flags) -> Self {
+    pub fn new(ptr_size: impl PtrSize, shared_flags: settings::Flags) -> Self {
         Self {
-            asm: Assembler::new(shared_flags),
+            asm: Assembler::new(shared_flags.clone()),
             sp_offset: 0u32,
+            shared_flags,
+            ptr_size: ptr_type_from_ptr_size(ptr_size.size()).into(),
         }
     }
 }
@@ -41,6 +53,15 @@ impl Masm for MacroAssembler {
 
         self.asm.stp(fp, lr, addr);
         self.asm.mov_rr(sp, fp, OperandSize::S64);
+        // The shadow stack pointer is callee-saved; preserve the
+        // caller's value before clobbering it.
+        let addr = Address::pre_indexed_from_sp(-16);
+        self.asm.str(
+            regs::shadow_sp(),
+            addr,
+            OperandSize::S64,
+            MemFlags::trusted(),
+        );
         self.move_sp_to_shadow_sp();
     }
 
@@ -54,6 +75,14 @@ impl Masm for MacroAssembler {
             self.move_sp_to_shadow_sp();
         }
 
+        let addr = Address::post_indexed_from_sp(16);
+        self.asm.ldr(
+            addr,
+            regs::shadow_sp(),
+            OperandSize::S64,
+            MemFlags::trusted(),
+        );
+
         let lr = regs::lr();
         let fp = regs::fp();
         let addr = Address::post_indexed_from_sp(16);
@@ -74,8 +103,16 @@ impl Masm for MacroAssembler {
         self.increment_sp(bytes);
     }
 
-    fn free_stack(&mut self, _bytes: u32) {
-        todo!()
+    fn free_stack(&mut self, bytes: u32) {
+        if bytes == 0 {
+            return;
+        }
+
+        let sp = regs::sp();
+        self.asm.add_ir(bytes as u64, sp, sp, OperandSize::S64);
+        self.move_sp_to_shadow_sp();
+
+        self.decrement_sp(bytes);
     }
 
     fn reset_stack_pointer(&mut self, offset: u32) {
@@ -99,32 +136,99 @@ impl Masm for MacroAssembler {
 
     fn table_elem_address(
         &mut self,
-        _index: Reg,
-        _base: Reg,
-        _table_data: &TableData,
-        _context: &mut CodeGenContext,
+        index: Reg,
+        ptr_base: Reg,
+        table_data: &TableData,
+        context: &mut CodeGenContext,
     ) -> Self::Address {
-        todo!()
+        let vmctx = <Self::ABI as ABI>::vmctx_reg();
+        let scratch = regs::scratch();
+        let bound = context.any_gpr(self);
+        let tmp = context.any_gpr(self);
+
+        if let Some(offset) = table_data.base {
+            // If the table data declares a particular offset base,
+            // load the address into a register to further use it as
+            // the table address.
+            self.load_ptr(self.address_at_vmctx(offset), ptr_base);
+        } else {
+            // Else, simply move the vmctx register into the addr register as
+            // the base to calculate the table address.
+            self.asm.mov_rr(vmctx, ptr_base, self.ptr_size);
+        };
+
+        // OOB check.
+        let bound_addr = self.address_at_reg(ptr_base, table_data.current_elems_offset);
+        let bound_size = table_data.current_elements_size;
+        self.asm
+            .ldr(bound_addr, bound, bound_size, MemFlags::trusted());
+        self.asm.cmp_rr(bound, index, bound_size);
+        self.asm
+            .trapif(CmpKind::GeU.into(), TrapCode::TableOutOfBounds);
+
+        // Calculate the offset of the table element in a temporary
+        // register. The scratch register can't hold the offset, given
+        // that it might be clobbered when loading the table base
+        // below, if the offset of the base doesn't fit in the
+        // immediate of the load instruction.
+        // Moving the value of the index register to the temporary register
+        // also avoids overwriting the context of the index register.
+        self.asm.mov_rr(index, tmp, bound_size);
+        self.asm
+            .load_constant(table_data.element_size.bytes() as u64, scratch);
+        self.asm.mul_rrr(scratch, tmp, tmp, self.ptr_size);
+        self.load_ptr(self.address_at_reg(ptr_base, table_data.offset), ptr_base);
+        if self.shared_flags.enable_table_access_spectre_mitigation() {
+            // Perform a bounds check and override the value of the
+            // table element offset in case the index is out of bounds,
+            // so that the table base is accessed on misspeculation.
+            self.asm.cmp_rr(bound, index, bound_size);
+            self.asm.csel(regs::zero(), tmp, tmp, CmpKind::GeU.into());
+        }
+        // Calculate the address of the table element.
+        self.asm.add_rrr(tmp, ptr_base, ptr_base, self.ptr_size);
+
+        context.free_reg(bound);
+        context.free_reg(tmp);
+        self.address_at_reg(ptr_base, 0)
     }
 
-    fn table_size(&mut self, _table_data: &TableData, _context: &mut CodeGenContext) {
-        todo!()
+    fn table_size(&mut self, table_data: &TableData, context: &mut CodeGenContext) {
+        let vmctx = <Self::ABI as ABI>::vmctx_reg();
+        let scratch = regs::scratch();
+        let size = context.any_gpr(self);
+
+        if let Some(offset) = table_data.base {
+            self.load_ptr(self.address_at_vmctx(offset), scratch);
+        } else {
+            self.asm.mov_rr(vmctx, scratch, self.ptr_size);
+        };
+
+        let size_addr = Address::offset(scratch, table_data.current_elems_offset as i64);
+        self.asm.ldr(
+            size_addr,
+            size,
+            table_data.current_elements_size,
+            MemFlags::trusted(),
+        );
+
+        context.stack.push(TypedReg::i32(size).into());
     }
 
-    fn address_from_sp(&self, _offset: u32) -> Self::Address {
-        todo!()
+    fn address_from_sp(&self, offset: u32) -> Self::Address {
+        Address::from_shadow_sp((self.sp_offset - offset) as i64)
     }
 
-    fn address_at_sp(&self, _offset: u32) -> Self::Address {
-        todo!()
+    fn address_at_sp(&self, offset: u32) -> Self::Address {
+        Address::from_shadow_sp(offset as i64)
     }
 
-    fn address_at_vmctx(&self, _offset: u32) -> Self::Address {
-        todo!()
+    fn address_at_vmctx(&self, offset: u32) -> Self::Address {
+        Address::offset(<Self::ABI as ABI>::vmctx_reg(), offset as i64)
     }
 
-    fn store_ptr(&mut self, _src: Reg, _dst: Self::Address) {
-        todo!()
+    fn store_ptr(&mut self, src: Reg, dst: Self::Address) {
+        self.store(src.into(), dst, self.ptr_size);
     }
 
     fn store(&mut self, src: RegImm, dst: Address, size: OperandSize) {
@@ -139,30 +243,93 @@ impl Masm for MacroAssembler {
                 self.asm.load_constant(imm, scratch);
                 scratch
             }
+            // The stack pointer can't be used as the source of a
+            // store; given that the shadow stack pointer always
+            // mirrors its value, use it instead.
+            RegImm::Reg(reg) if reg == regs::sp() => regs::shadow_sp(),
             RegImm::Reg(reg) => reg,
         };
 
-        self.asm.str(src, dst, size);
+        self.asm.str(src, dst, size, MemFlags::trusted());
     }
 
     fn call(
         &mut self,
-        _stack_args_size: u32,
-        _load_callee: impl FnMut(&mut Self) -> CalleeKind,
+        stack_args_size: u32,
+        mut load_callee: impl FnMut(&mut Self) -> CalleeKind,
     ) -> u32 {
-        todo!()
+        let alignment: u32 = <Self::ABI as abi::ABI>::call_stack_align().into();
+        let addend: u32 = <Self::ABI as abi::ABI>::arg_base_offset().into();
+        let delta = calculate_frame_adjustment(self.sp_offset(), addend, alignment);
+        let aligned_args_size = align_to(stack_args_size, alignment);
+        let total_stack = delta + aligned_args_size;
+        self.reserve_stack(total_stack);
+        let callee = load_callee(self);
+        match callee {
+            CalleeKind::Indirect(reg) => self.asm.call_with_reg(reg),
+            CalleeKind::Direct(idx) => self.asm.call_with_index(idx),
+            CalleeKind::Known(lib) => self.asm.call_with_lib(lib),
+        };
+        total_stack
     }
 
     fn load(&mut self, src: Address, dst: Reg, size: OperandSize) {
-        self.asm.ldr(src, dst, size);
+        self.asm.ldr(src, dst, size, MemFlags::trusted());
+    }
+
+    fn load_ptr(&mut self, src: Self::Address, dst: Reg) {
+        self.load(src, dst, self.ptr_size);
+    }
+
+    fn load_addr(&mut self, src: Self::Address, dst: Reg, size: OperandSize) {
+        match src {
+            Address::Offset { base, offset } => {
+                self.asm.add_ir(offset as u64, base, dst, size);
+            }
+            Address::IndexedSPOffset { .. } => {
+                unreachable!("Indexed addressing is only used for pushes and pops")
+            }
+        }
+    }
+
+    fn wasm_load(
+        &mut self,
+        src: Self::Address,
+        dst: Reg,
+        size: OperandSize,
+        kind: Option<ExtendKind>,
+    ) {
+        // Heap accesses are not trusted: the emitted instruction
+        // might trap, in which case a `HeapOutOfBounds` trap is
+        // registered for it.
+        let flags = MemFlags::new();
+        match kind {
+            Some(kind) if kind.signed() => {
+                self.asm.sldr(src, dst, size, flags);
+                // Signed loads always extend to 64 bits; clear the
+                // upper bits for 32-bit destinations.
+                if matches!(kind, ExtendKind::I32Extend8S | ExtendKind::I32Extend16S) {
+                    self.asm.mov_rr(dst, dst, OperandSize::S32);
+                }
+            }
+            _ => self.asm.ldr(src, dst, size, flags),
+        }
     }
 
-    fn load_ptr(&mut self, _src: Self::Address, _dst: Reg) {
-        todo!()
+    fn wasm_store(&mut self, src: Reg, dst: Self::Address, size: OperandSize) {
+        self.asm.str(src, dst, size, MemFlags::new());
     }
 
-    fn pop(&mut self, _dst: Reg, _size: OperandSize) {
-        todo!()
+    fn pop(&mut self, dst: Reg, size: OperandSize) {
+        let addr = self.address_from_sp(self.sp_offset);
+        if dst.is_int() {
+            self.asm
+                .ldr(addr, dst, OperandSize::S64, MemFlags::trusted());
+            self.free_stack(<Self::ABI as abi::ABI>::word_bytes());
+        } else {
+            self.asm.ldr(addr, dst, size, MemFlags::trusted());
+            self.free_stack(size.bytes());
+        }
     }
 
     fn sp_offset(&self) -> u32 {
@@ -177,23 +344,35 @@ impl Masm for MacroAssembler {
         match (src, dst) {
             (RegImm::Imm(v), rd) => {
                 let imm = match v {
-                    I::I32(v) => v as u64,
-                    I::I64(v) => v,
-                    _ => panic!(),
+                    I::I32(v) | I::F32(v) => v as u64,
+                    I::I64(v) | I::F64(v) => v,
+                    // Compiling `v128` values is rejected up front on aarch64,
+                    // see `TargetIsa::check_simd_support`.
+                    I::V128(_) => unreachable!(),
                 };
 
                 let scratch = regs::scratch();
-                self.asm.load_constant(imm as u64, scratch);
-                self.asm.mov_rr(scratch, rd, size);
-            }
-            (RegImm::Reg(rs), rd) => {
-                self.asm.mov_rr(rs, rd, size);
+                self.asm.load_constant(imm, scratch);
+                if rd.is_int() {
+                    self.asm.mov_rr(scratch, rd, size);
+                } else {
+                    self.asm.mov_to_fpu(scratch, rd, size);
+                }
             }
+            (RegImm::Reg(rs), rd) => match (rs.class(), rd.class()) {
+                (RegClass::Int, RegClass::Int) => self.asm.mov_rr(rs, rd, size),
+                (RegClass::Float, RegClass::Float) => self.asm.fmov_rr(rs, rd),
+                _ => panic!("Invalid operand combination; src={:?}, dst={:?}", rs, rd),
+            },
         }
     }
 
-    fn cmov(&mut self, _src: Reg, _dst: Reg, _cc: CmpKind, _size: OperandSize) {
-        todo!()
+    fn cmov(&mut self, src: Reg, dst: Reg, cc: CmpKind, size: OperandSize) {
+        match (src.class(), dst.class()) {
+            (RegClass::Int, RegClass::Int) => self.asm.csel(src, dst, dst, cc.into()),
+            (RegClass::Float, RegClass::Float) => self.asm.fpu_csel(src, dst, dst, cc.into(), size),
+            _ => panic!("Invalid operand combination; src={:?}, dst={:?}", src, dst),
+        }
     }
 
     fn add(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize) {
@@ -214,6 +393,26 @@ impl Masm for MacroAssembler {
         }
     }
 
+    fn checked_uadd(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize, trap: TrapCode) {
+        match (rhs, lhs, dst) {
+            (RegImm::Imm(v), rn, rd) => {
+                let imm = match v {
+                    I::I32(v) => v as u64,
+                    I::I64(v) => v,
+                    _ => unreachable!(),
+                };
+
+                self.asm.adds_ir(imm, rn, rd, size);
+            }
+
+            (RegImm::Reg(rm), rn, rd) => {
+                self.asm.adds_rrr(rm, rn, rd, size);
+            }
+        }
+        // An unsigned overflow sets the carry flag.
+        self.asm.trapif(Cond::Hs, trap);
+    }
+
     fn sub(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize) {
         match (rhs, lhs, dst) {
             (RegImm::Imm(v), rn, rd) => {
@@ -250,96 +449,261 @@ impl Masm for MacroAssembler {
         }
     }
 
-    fn float_add(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_add(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        self.asm.fadd_rrr(rhs, lhs, dst, size);
     }
 
-    fn float_sub(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_sub(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        self.asm.fsub_rrr(rhs, lhs, dst, size);
     }
 
-    fn float_mul(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_mul(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        self.asm.fmul_rrr(rhs, lhs, dst, size);
     }
 
-    fn float_div(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_div(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        self.asm.fdiv_rrr(rhs, lhs, dst, size);
     }
 
-    fn float_min(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_min(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        self.asm.fmin_rrr(rhs, lhs, dst, size);
     }
 
-    fn float_max(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_max(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        self.asm.fmax_rrr(rhs, lhs, dst, size);
     }
 
-    fn float_copysign(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _size: OperandSize) {
-        todo!()
+    fn float_copysign(&mut self, dst: Reg, lhs: Reg, rhs: Reg, size: OperandSize) {
+        let (abs_mask, sign_mask) = match size {
+            OperandSize::S32 => (0x7fffffff, 0x80000000),
+            OperandSize::S64 => (0x7fffffffffffffff, 0x8000000000000000),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
+        };
+        let scratch = regs::scratch();
+        let tmp = regs::ip1();
+
+        // Clear the sign bit of the magnitude and keep only the sign bit
+        // of the sign operand, then combine both. Both masks are valid
+        // logical immediates, so the scratch register is not clobbered.
+        self.asm.mov_from_vec(lhs, scratch, size);
+        self.asm.and_ir(abs_mask, scratch, scratch, size);
+        self.asm.mov_from_vec(rhs, tmp, size);
+        self.asm.and_ir(sign_mask, tmp, tmp, size);
+        self.asm.or_rrr(tmp, scratch, scratch, size);
+        self.asm.mov_to_fpu(scratch, dst, size);
     }
 
-    fn float_neg(&mut self, _dst: Reg, _size: OperandSize) {
-        todo!()
+    fn float_neg(&mut self, dst: Reg, size: OperandSize) {
+        self.asm.fneg_rr(dst, dst, size);
     }
 
-    fn float_abs(&mut self, _dst: Reg, _size: OperandSize) {
-        todo!()
+    fn float_abs(&mut self, dst: Reg, size: OperandSize) {
+        self.asm.fabs_rr(dst, dst, size);
     }
 
-    fn float_round(
-        &mut self,
-        _mode: RoundingMode,
-        _context: &mut CodeGenContext,
-        _size: OperandSize,
-    ) {
-        todo!();
+    fn float_round(&mut self, mode: RoundingMode, context: &mut CodeGenContext, size: OperandSize) {
+        let src = context.pop_to_reg(self, None);
+        self.asm.fround_rr(src.into(), src.into(), mode, size);
+        context.stack.push(src.into());
     }
 
-    fn float_sqrt(&mut self, _dst: Reg, _src: Reg, _size: OperandSize) {
-        todo!()
+    fn float_sqrt(&mut self, dst: Reg, src: Reg, size: OperandSize) {
+        self.asm.fsqrt_rr(src, dst, size);
     }
 
-    fn and(&mut self, _dst: Reg, _lhs: Reg, _rhs: RegImm, _size: OperandSize) {
-        todo!()
+    fn and(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize) {
+        match (rhs, lhs, dst) {
+            (RegImm::Imm(v), rn, rd) => {
+                let imm = match v {
+                    I::I32(v) => v as u64,
+                    I::I64(v) => v,
+                    _ => unreachable!(),
+                };
+
+                self.asm.and_ir(imm, rn, rd, size);
+            }
+
+            (RegImm::Reg(rm), rn, rd) => {
+                self.asm.and_rrr(rm, rn, rd, size);
+            }
+        }
     }
 
-    fn or(&mut self, _dst: Reg, _lhs: Reg, _rhs: RegImm, _size: OperandSize) {
-        todo!()
+    fn or(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize) {
+        match (rhs, lhs, dst) {
+            (RegImm::Imm(v), rn, rd) => {
+                let imm = match v {
+                    I::I32(v) => v as u64,
+                    I::I64(v) => v,
+                    _ => unreachable!(),
+                };
+
+                self.asm.or_ir(imm, rn, rd, size);
+            }
+
+            (RegImm::Reg(rm), rn, rd) => {
+                self.asm.or_rrr(rm, rn, rd, size);
+            }
+        }
     }
 
-    fn xor(&mut self, _dst: Reg, _lhs: Reg, _rhs: RegImm, _size: OperandSize) {
-        todo!()
+    fn xor(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize) {
+        match (rhs, lhs, dst) {
+            (RegImm::Imm(v), rn, rd) => {
+                let imm = match v {
+                    I::I32(v) => v as u64,
+                    I::I64(v) => v,
+                    _ => unreachable!(),
+                };
+
+                self.asm.xor_ir(imm, rn, rd, size);
+            }
+
+            (RegImm::Reg(rm), rn, rd) => {
+                self.asm.xor_rrr(rm, rn, rd, size);
+            }
+        }
     }
 
-    fn shift(&mut self, _context: &mut CodeGenContext, _kind: ShiftKind, _size: OperandSize) {
-        todo!()
+    fn shift(&mut self, context: &mut CodeGenContext, kind: ShiftKind, size: OperandSize) {
+        let top = context.stack.peek().expect("value at stack top");
+        let mask = size.num_bits() as u64 - 1;
+
+        if (size == OperandSize::S32 && top.is_i32_const())
+            || (size == OperandSize::S64 && top.is_i64_const())
+        {
+            let val = if size == OperandSize::S32 {
+                context
+                    .stack
+                    .pop_i32_const()
+                    .expect("i32 const value at stack top") as u64
+            } else {
+                context
+                    .stack
+                    .pop_i64_const()
+                    .expect("i64 const value at stack top") as u64
+            };
+            let typed_reg = context.pop_to_reg(self, None);
+            let reg = typed_reg.into();
+
+            // Shift amounts are taken modulo the operand width. Left
+            // rotations are lowered as right rotations by the
+            // complementary amount.
+            let (val, kind) = match kind {
+                ShiftKind::Rotl => ((val.wrapping_neg()) & mask, ShiftKind::Rotr),
+                kind => (val & mask, kind),
+            };
+            self.asm.shift_ir(val, reg, reg, kind, size);
+
+            context.stack.push(typed_reg.into());
+        } else {
+            let src = context.pop_to_reg(self, None);
+            let dst = context.pop_to_reg(self, None);
+
+            // Register shifts implicitly mask the shift amount.
+            let (amount, kind) = match kind {
+                ShiftKind::Rotl => {
+                    let scratch = regs::scratch();
+                    self.asm.neg_rr(src.into(), scratch, size);
+                    (scratch, ShiftKind::Rotr)
+                }
+                kind => (src.into(), kind),
+            };
+            self.asm
+                .shift_rrr(amount, dst.into(), dst.into(), kind, size);
+
+            context.free_reg(src);
+            context.stack.push(dst.into());
+        }
     }
 
-    fn div(&mut self, _context: &mut CodeGenContext, _kind: DivKind, _size: OperandSize) {
-        todo!()
+    fn div(&mut self, context: &mut CodeGenContext, kind: DivKind, size: OperandSize) {
+        let divisor = context.pop_to_reg(self, None);
+        let dividend = context.pop_to_reg(self, None);
+
+        self.ensure_nonzero_divisor(divisor.into(), size);
+
+        match kind {
+            DivKind::Signed => {
+                // Signed division overflows if the dividend is the
+                // minimum integer value and the divisor is -1.
+                let done = self.get_label();
+                self.asm.adds_ir(1, divisor.into(), regs::zero(), size);
+                self.asm.jmp_if(Cond::Ne, done);
+                // Subtracting one from the dividend overflows only if
+                // it's the minimum integer value.
+                self.asm.cmp_ir(1, dividend.into(), size);
+                self.asm.trapif(Cond::Vs, TrapCode::IntegerOverflow);
+                self.bind(done);
+
+                self.asm
+                    .sdiv_rrr(divisor.into(), dividend.into(), dividend.into(), size);
+            }
+            DivKind::Unsigned => {
+                self.asm
+                    .udiv_rrr(divisor.into(), dividend.into(), dividend.into(), size);
+            }
+        }
+
+        context.free_reg(divisor);
+        context.stack.push(dividend.into());
     }
 
-    fn rem(&mut self, _context: &mut CodeGenContext, _kind: RemKind, _size: OperandSize) {
-        todo!()
+    fn rem(&mut self, context: &mut CodeGenContext, kind: RemKind, size: OperandSize) {
+        let divisor = context.pop_to_reg(self, None);
+        let dividend = context.pop_to_reg(self, None);
+
+        self.ensure_nonzero_divisor(divisor.into(), size);
+
+        // Calculate the quotient into the scratch register and compute the
+        // remainder as `dividend - (quotient * divisor)`. Signed division
+        // doesn't trap on overflow, which yields the expected remainder of
+        // zero for the minimum integer value divided by -1.
+        let scratch = regs::scratch();
+        match kind {
+            RemKind::Signed => self
+                .asm
+                .sdiv_rrr(divisor.into(), dividend.into(), scratch, size),
+            RemKind::Unsigned => self
+                .asm
+                .udiv_rrr(divisor.into(), dividend.into(), scratch, size),
+        }
+        self.asm.msub_rrrr(
+            divisor.into(),
+            scratch,
+            dividend.into(),
+            dividend.into(),
+            size,
+        );
+
+        context.free_reg(divisor);
+        context.stack.push(dividend.into());
     }
 
     fn zero(&mut self, reg: Reg) {
         self.asm.load_constant(0, reg);
     }
 
-    fn popcnt(&mut self, _context: &mut CodeGenContext, _size: OperandSize) {
-        todo!()
+    fn popcnt(&mut self, context: &mut CodeGenContext, size: OperandSize) {
+        let src = context.pop_to_reg(self, None);
+        self.asm
+            .cnt(src.into(), src.into(), regs::float_scratch(), size);
+        context.stack.push(src.into());
     }
 
-    fn push(&mut self, reg: Reg, _size: OperandSize) -> StackSlot {
-        let size = <Self::ABI as abi::ABI>::word_bytes();
-        self.reserve_stack(size);
-        let address = Address::from_shadow_sp(size as i64);
-        self.asm.str(reg, address, OperandSize::S64);
+    fn push(&mut self, reg: Reg, size: OperandSize) -> StackSlot {
+        let (bytes, size) = if reg.is_int() {
+            (<Self::ABI as abi::ABI>::word_bytes(), OperandSize::S64)
+        } else {
+            (size.bytes(), size)
+        };
+        self.reserve_stack(bytes);
+        let address = Address::from_shadow_sp(0);
+        self.asm.str(reg, address, size, MemFlags::trusted());
 
         StackSlot {
             offset: self.sp_offset,
-            size,
+            size: bytes,
         }
     }
 
@@ -347,20 +711,211 @@ impl Masm for MacroAssembler {
         Address::offset(reg, offset as i64)
     }
 
-    fn cmp_with_set(&mut self, _src: RegImm, _dst: Reg, _kind: CmpKind, _size: OperandSize) {
-        todo!()
+    fn cmp_with_set(&mut self, src: RegImm, dst: Reg, kind: CmpKind, size: OperandSize) {
+        self.cmp(src, dst, size);
+        self.asm.cset(dst, kind.into());
+    }
+
+    fn cmp(&mut self, src: RegImm, dest: Reg, size: OperandSize) {
+        match src {
+            RegImm::Imm(v) => {
+                let imm = match v {
+                    I::I32(v) => v as u64,
+                    I::I64(v) => v,
+                    _ => unreachable!(),
+                };
+
+                self.asm.cmp_ir(imm, dest, size);
+            }
+            RegImm::Reg(src) => {
+                self.asm.cmp_rr(src, dest, size);
+            }
+        }
+    }
+
+    fn clz(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        self.asm.clz(src, dst, size);
+    }
+
+    fn ctz(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        // Reversing the bits turns trailing zeros into leading zeros.
+        self.asm.rbit(src, dst, size);
+        self.asm.clz(dst, dst, size);
+    }
+
+    // None of the `v128` operators are lowered on aarch64. `TargetIsa`'s
+    // default `check_simd_support` already fails the compilation of any
+    // function which uses them, and these fail it as well in case an
+    // operator gets past that check.
+    fn v128_splat(&mut self, _src: Reg, _dst: Reg, _lanes: V128Lanes) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_extract_lane(
+        &mut self,
+        _src: Reg,
+        _dst: Reg,
+        _lane: u8,
+        _lanes: V128Lanes,
+        _signed: bool,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_replace_lane(
+        &mut self,
+        _src: Reg,
+        _dst: Reg,
+        _lane: u8,
+        _lanes: V128Lanes,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_shuffle(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _indices: [u8; 16]) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_binop(
+        &mut self,
+        _context: &mut CodeGenContext,
+        _dst: Reg,
+        _lhs: Reg,
+        _rhs: Reg,
+        _op: V128BinaryOp,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_unop(
+        &mut self,
+        _context: &mut CodeGenContext,
+        _dst: Reg,
+        _op: V128UnaryOp,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_bitselect(&mut self, _dst: Reg, _lhs: Reg, _rhs: Reg, _mask: Reg) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_cmp(
+        &mut self,
+        _dst: Reg,
+        _lhs: Reg,
+        _rhs: Reg,
+        _kind: CmpKind,
+        _lanes: V128Lanes,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_float_cmp(
+        &mut self,
+        _dst: Reg,
+        _lhs: Reg,
+        _rhs: Reg,
+        _kind: FloatCmpKind,
+        _lanes: V128Lanes,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_shift(
+        &mut self,
+        _context: &mut CodeGenContext,
+        _kind: ShiftKind,
+        _lanes: V128Lanes,
+    ) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_any_true(&mut self, _src: Reg, _dst: Reg) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_all_true(&mut self, _src: Reg, _dst: Reg, _lanes: V128Lanes) -> Result<()> {
+        Self::unsupported_simd()
+    }
+
+    fn v128_bitmask(&mut self, _src: Reg, _dst: Reg, _lanes: V128Lanes) -> Result<()> {
+        Self::unsupported_simd()
     }
 
-    fn cmp(&mut self, _src: RegImm, _dest: Reg, _size: OperandSize) {
-        todo!()
+    fn wrap(&mut self, src: Reg, dst: Reg) {
+        // 32-bit moves clear the upper 32 bits of the destination register.
+        self.asm.mov_rr(src, dst, OperandSize::S32);
     }
 
-    fn clz(&mut self, _src: Reg, _dst: Reg, _size: OperandSize) {
-        todo!()
+    fn extend(&mut self, src: Reg, dst: Reg, kind: ExtendKind) {
+        self.asm.extend(src, dst, kind);
     }
 
-    fn ctz(&mut self, _src: Reg, _dst: Reg, _size: OperandSize) {
-        todo!()
+    fn signed_convert(&mut self, src: Reg, dst: Reg, src_size: OperandSize, dst_size: OperandSize) {
+        self.asm
+            .cvt_int_to_float(src, dst, src_size, dst_size, true);
+    }
+
+    fn unsigned_convert(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        _tmp_gpr: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+    ) {
+        self.asm
+            .cvt_int_to_float(src, dst, src_size, dst_size, false);
+    }
+
+    fn signed_truncate(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        kind: TruncKind,
+    ) {
+        if kind == TruncKind::Checked {
+            self.check_truncate_bounds(src, regs::float_scratch(), src_size, dst_size, true);
+        }
+        self.asm
+            .cvt_float_to_int(src, dst, src_size, dst_size, true);
+    }
+
+    fn unsigned_truncate(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_fpr: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        kind: TruncKind,
+    ) {
+        if kind == TruncKind::Checked {
+            self.check_truncate_bounds(src, tmp_fpr, src_size, dst_size, false);
+        }
+        self.asm
+            .cvt_float_to_int(src, dst, src_size, dst_size, false);
+    }
+
+    fn reinterpret_float_as_int(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        self.asm.mov_from_vec(src, dst, size);
+    }
+
+    fn reinterpret_int_as_float(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        self.asm.mov_to_fpu(src, dst, size);
+    }
+
+    fn demote(&mut self, src: Reg, dst: Reg) {
+        self.asm
+            .fcvt_rr(src, dst, OperandSize::S64, OperandSize::S32);
+    }
+
+    fn promote(&mut self, src: Reg, dst: Reg) {
+        self.asm
+            .fcvt_rr(src, dst, OperandSize::S32, OperandSize::S64);
     }
 
     fn get_label(&mut self) -> MachLabel {
@@ -374,41 +929,82 @@ impl Masm for MacroAssembler {
 
     fn branch(
         &mut self,
-        _kind: CmpKind,
-        _lhs: RegImm,
-        _rhs: Reg,
-        _taken: MachLabel,
-        _size: OperandSize,
+        kind: CmpKind,
+        lhs: RegImm,
+        rhs: Reg,
+        taken: MachLabel,
+        size: OperandSize,
     ) {
-        todo!()
+        use CmpKind::*;
+
+        match &(lhs, rhs) {
+            // If the comparison kind is zero or not zero and both operands
+            // are the same register, compare against zero.
+            (RegImm::Reg(rlhs), rrhs) if (kind == Eq || kind == Ne) && (rlhs == rrhs) => {
+                self.asm.cmp_ir(0, *rrhs, size);
+            }
+            _ => self.cmp(lhs, rhs, size),
+        }
+        self.asm.jmp_if(kind.into(), taken);
     }
 
-    fn jmp(&mut self, _target: MachLabel) {
-        todo!()
+    fn jmp(&mut self, target: MachLabel) {
+        self.asm.jmp(target);
     }
 
     fn unreachable(&mut self) {
-        todo!()
+        self.asm.trap(TrapCode::UnreachableCodeReached);
+    }
+
+    fn trap(&mut self, code: TrapCode) {
+        self.asm.trap(code);
     }
 
-    fn jmp_table(&mut self, _targets: &[MachLabel], _index: Reg, _tmp: Reg) {
-        todo!()
+    fn jmp_table(&mut self, targets: &[MachLabel], index: Reg, tmp: Reg) {
+        // At least one default target.
+        assert!(targets.len() >= 1);
+        let default_index = targets.len() - 1;
+        // The jump table sequence jumps to the default target if the
+        // index is greater or equal than the number of cases, based on
+        // the flags set by this comparison.
+        self.asm
+            .cmp_ir(default_index as u64, index, OperandSize::S32);
+
+        let default = targets[default_index];
+        let rest = &targets[0..default_index];
+        let tmp1 = regs::scratch();
+        self.asm.jmp_table(rest, default, index, tmp1, tmp);
     }
 
-    fn trapz(&mut self, _src: Reg, _code: TrapCode) {
-        todo!()
+    fn trapz(&mut self, src: Reg, code: TrapCode) {
+        self.asm.cmp_ir(0, src, self.ptr_size);
+        self.asm.trapif(Cond::Eq, code);
     }
 
-    fn trapif(&mut self, _cc: CmpKind, _code: TrapCode) {
-        todo!()
+    fn trapif(&mut self, cc: CmpKind, code: TrapCode) {
+        self.asm.trapif(cc.into(), code);
     }
 }
 
 impl MacroAssembler {
+    fn unsupported_simd() -> Result<()> {
+        bail!("SIMD is not supported by Winch on aarch64")
+    }
+
     fn increment_sp(&mut self, bytes: u32) {
         self.sp_offset += bytes;
     }
 
+    fn decrement_sp(&mut self, bytes: u32) {
+        assert!(
+            self.sp_offset >= bytes,
+            "sp offset = {}; bytes = {}",
+            self.sp_offset,
+            bytes
+        );
+        self.sp_offset -= bytes;
+    }
+
     // Copies the value of the stack pointer to the shadow stack
     // pointer: mov x28, sp
 
@@ -422,4 +1018,61 @@ impl MacroAssembler {
         let shadow_sp = regs::shadow_sp();
         self.asm.mov_rr(sp, shadow_sp, OperandSize::S64);
     }
+
+    // Traps with `IntegerDivisionByZero` if the divisor is zero.
+    fn ensure_nonzero_divisor(&mut self, divisor: Reg, size: OperandSize) {
+        self.asm.cmp_ir(0, divisor, size);
+        self.asm.trapif(Cond::Eq, TrapCode::IntegerDivisionByZero);
+    }
+
+    // Emits the checks performed by the trapping float to integer
+    // truncation: NaN inputs trap with `BadConversionToInteger` and inputs
+    // outside of the range of the destination type trap with
+    // `IntegerOverflow`. The bounds are exclusive and loaded into the
+    // given temporary floating point register.
+    fn check_truncate_bounds(
+        &mut self,
+        src: Reg,
+        tmp: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        signed: bool,
+    ) {
+        use OperandSize::*;
+
+        self.asm.fcmp(src, src, src_size);
+        self.asm.trapif(Cond::Vs, TrapCode::BadConversionToInteger);
+
+        // The lower bound is inclusive when it can be represented exactly
+        // as the minimum value of the destination type.
+        let (min, min_cond, max) = match (signed, src_size, dst_size) {
+            (true, S32, S32) => (i32::MIN as f64, Cond::Lt, 2f64.powi(31)),
+            (true, S32, S64) | (true, S64, S64) => (i64::MIN as f64, Cond::Lt, 2f64.powi(63)),
+            (true, S64, S32) => (i32::MIN as f64 - 1.0, Cond::Le, 2f64.powi(31)),
+            (false, _, S32) => (-1.0, Cond::Le, 2f64.powi(32)),
+            (false, _, S64) => (-1.0, Cond::Le, 2f64.powi(64)),
+            _ => unreachable!(),
+        };
+
+        self.load_float_constant(min, tmp, src_size);
+        self.asm.fcmp(tmp, src, src_size);
+        self.asm.trapif(min_cond, TrapCode::IntegerOverflow);
+
+        self.load_float_constant(max, tmp, src_size);
+        self.asm.fcmp(tmp, src, src_size);
+        self.asm.trapif(Cond::Ge, TrapCode::IntegerOverflow);
+    }
+
+    // Loads a floating point constant of the given size into the
+    // destination register, through the scratch register.
+    fn load_float_constant(&mut self, value: f64, dst: Reg, size: OperandSize) {
+        let bits = match size {
+            OperandSize::S32 => (value as f32).to_bits() as u64,
+            OperandSize::S64 => value.to_bits(),
+            _ => unreachable!(),
+        };
+        let scratch = regs::scratch();
+        self.asm.load_constant(bits, scratch);
+        self.asm.mov_to_fpu(scratch, dst, size);
+    }
 }
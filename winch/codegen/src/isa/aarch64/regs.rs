@@ -6,7 +6,7 @@ use smallvec::{smallvec, SmallVec};
 
 /// FPR index bound.
 pub(crate) const MAX_FPR: u32 = 32;
-/// FPR index bound.
+/// GPR index bound.
 pub(crate) const MAX_GPR: u32 = 32;
 
 /// Construct a X-register from an index.
@@ -38,6 +38,11 @@ pub(crate) const fn ip1() -> Reg {
     xreg(17)
 }
 
+/// Floating point scratch register.
+pub(crate) const fn float_scratch() -> Reg {
+    vreg(31)
+}
+
 /// Register used to carry platform state.
 const fn platform() -> Reg {
     xreg(18)
@@ -127,7 +132,8 @@ pub(crate) const fn sp() -> Reg {
 ///   entering a function.
 /// * The value of x28 doesn't change between
 ///   function calls (as it's callee saved), compliant with
-///   Aarch64's ABI.
+///   Aarch64's ABI. The caller's value is saved in the function
+///   prologue and restored in the epilogue.
 /// * x28 is not available during register allocation.
 /// * Since the real stack pointer is always primary, there's no need
 ///   to copy the shadow stack pointer into the real stack
@@ -149,6 +155,12 @@ pub(crate) const NON_ALLOCATABLE_GPR: u32 = (1 << ip0().hw_enc())
 /// Bitmask to represent the available general purpose registers.
 pub(crate) const ALL_GPR: u32 = u32::MAX & !NON_ALLOCATABLE_GPR;
 
+/// Bitmask for non-allocatable FPR.
+pub(crate) const NON_ALLOCATABLE_FPR: u32 = 1 << float_scratch().hw_enc();
+
+/// Bitmask to represent the available floating point registers.
+pub(crate) const ALL_FPR: u32 = u32::MAX & !NON_ALLOCATABLE_FPR;
+
 /// Returns the callee-saved registers.
 ///
 /// This function will return the set of registers that need to be saved
@@ -166,7 +178,6 @@ pub(crate) fn callee_saved() -> SmallVec<[(Reg, OperandSize); 18]> {
         xreg(25),
         xreg(26),
         xreg(27),
-        xreg(28),
         vreg(8),
         vreg(9),
         vreg(10),
@@ -1,5 +1,5 @@
 use crate::{BuiltinFunctions, TrampolineKind};
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use core::fmt::Formatter;
 use cranelift_codegen::isa::{CallConv, IsaBuilder};
 use cranelift_codegen::settings;
@@ -10,7 +10,9 @@ use std::{
 };
 use target_lexicon::{Architecture, Triple};
 use wasmparser::{FuncValidator, FunctionBody, ValidatorResources};
-use wasmtime_environ::{ModuleTranslation, ModuleTypes, WasmFuncType};
+use wasmtime_environ::{
+    DefinedFuncIndex, ModuleTranslation, ModuleTypes, Tunables, WasmFuncType, WasmType,
+};
 
 #[cfg(feature = "x64")]
 pub(crate) mod x64;
@@ -148,10 +150,12 @@ pub trait TargetIsa: Send + Sync {
     /// Compile a function.
     fn compile_function(
         &self,
+        index: DefinedFuncIndex,
         sig: &WasmFuncType,
         body: &FunctionBody,
         translation: &ModuleTranslation,
         types: &ModuleTypes,
+        tunables: &Tunables,
         builtins: &mut BuiltinFunctions,
         validator: &mut FuncValidator<ValidatorResources>,
     ) -> Result<MachBufferFinalized<Final>>;
@@ -204,6 +208,30 @@ pub trait TargetIsa: Send + Sync {
         let width = self.triple().pointer_width().unwrap();
         width.bytes()
     }
+
+    /// Checks that `v128` values and operators can be compiled for this ISA
+    /// with the flags it was configured with, returning an error describing
+    /// why not otherwise.
+    fn check_simd_support(&self) -> Result<()> {
+        bail!("SIMD is not supported by Winch on {}", self.name())
+    }
+
+    /// Checks that `v128` values can be compiled if the given function type
+    /// or locals make use of them.
+    fn check_v128_types<'a>(
+        &self,
+        sig: &WasmFuncType,
+        locals: impl IntoIterator<Item = &'a WasmType>,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let mut types = sig.params().iter().chain(sig.returns()).chain(locals);
+        if types.any(|ty| *ty == WasmType::V128) {
+            self.check_simd_support()?;
+        }
+        Ok(())
+    }
 }
 
 impl Debug for &dyn TargetIsa {
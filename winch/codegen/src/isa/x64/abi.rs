@@ -1,6 +1,6 @@
 use super::regs;
 use crate::{
-    abi::{ABIArg, ABIResult, ABISig, ABI},
+    abi::{align_to, ABIArg, ABIResult, ABISig, ABI},
     isa::{reg::Reg, CallingConvention},
     masm::OperandSize,
 };
@@ -104,10 +104,6 @@ impl ABI for X64ABI {
     ) -> ABISig {
         assert!(call_conv.is_fastcall() || call_conv.is_systemv() || call_conv.is_default());
 
-        if returns.len() > 1 {
-            panic!("multi-value not supported");
-        }
-
         let is_fastcall = call_conv.is_fastcall();
         // In the fastcall calling convention, the callee gets a contiguous
         // stack area of 32 bytes (4 register arguments) just before its frame.
@@ -119,12 +115,21 @@ impl ABI for X64ABI {
             (0, RegIndexEnv::default())
         };
 
-        let params: SmallVec<[ABIArg; 6]> = params
+        let mut params: SmallVec<[ABIArg; 6]> = params
             .iter()
             .map(|arg| Self::to_abi_arg(arg, &mut stack_offset, &mut index_env, is_fastcall))
             .collect();
 
         let result = Self::result(returns, call_conv);
+        // The pointer to the return area is passed as the last parameter.
+        if result.has_stack_results() {
+            params.push(Self::to_abi_arg(
+                &WasmType::I64,
+                &mut stack_offset,
+                &mut index_env,
+                is_fastcall,
+            ));
+        }
         ABISig::new(params, result, stack_offset)
     }
 
@@ -133,30 +138,26 @@ impl ABI for X64ABI {
     }
 
     fn result(returns: &[WasmType], _call_conv: &CallingConvention) -> ABIResult {
-        // This invariant will be lifted once support for multi-value is added.
-        assert!(returns.len() <= 1, "multi-value not supported");
-        let ty = returns.get(0).copied();
-        ty.map(|ty| {
-            let reg = match ty {
-                // The `Default`, `WasmtimeFastcall` and `WasmtimeSystemV use `rax` and `xmm0`.
-                // NOTE This should be updated when supporting multi-value.
-                WasmType::I32 | WasmType::I64 => regs::rax(),
-                WasmType::F32 | WasmType::F64 => regs::xmm0(),
-                WasmType::Ref(rt) => {
-                    assert!(rt.heap_type == WasmHeapType::Func);
-                    regs::rax()
-                }
-                t => panic!("Unsupported return type {:?}", t),
-            };
-            ABIResult::reg(ty, reg)
+        ABIResult::from(returns, |ty| match ty {
+            // The `Default`, `WasmtimeFastcall` and `WasmtimeSystemV use `rax` and `xmm0`.
+            WasmType::I32 | WasmType::I64 => regs::rax(),
+            WasmType::F32 | WasmType::F64 | WasmType::V128 => regs::xmm0(),
+            WasmType::Ref(rt) => {
+                assert!(rt.heap_type == WasmHeapType::Func);
+                regs::rax()
+            }
+            t => panic!("Unsupported return type {:?}", t),
         })
-        .unwrap_or_else(|| ABIResult::void())
     }
 
     fn scratch_reg() -> Reg {
         regs::scratch()
     }
 
+    fn float_scratch_reg() -> Reg {
+        regs::scratch_xmm()
+    }
+
     fn fp_reg() -> Reg {
         regs::rbp()
     }
@@ -181,6 +182,7 @@ impl ABI for X64ABI {
             },
             WasmType::F64 | WasmType::I32 | WasmType::I64 => Self::word_bytes(),
             WasmType::F32 => Self::word_bytes() / 2,
+            WasmType::V128 => Self::word_bytes() * 2,
             ty => unimplemented!("Support for WasmType: {ty}"),
         }
     }
@@ -203,7 +205,7 @@ impl X64ABI {
                 (Self::int_reg_for(index_env.next_gpr(), fastcall), ty)
             }
 
-            ty @ (WasmType::F32 | WasmType::F64) => {
+            ty @ (WasmType::F32 | WasmType::F64 | WasmType::V128) => {
                 (Self::float_reg_for(index_env.next_fpr(), fastcall), ty)
             }
 
@@ -211,8 +213,12 @@ impl X64ABI {
         };
 
         let default = || {
-            let arg = ABIArg::stack_offset(*stack_offset, *ty);
             let size = Self::stack_arg_slot_size_for_type(*ty);
+            // Vector arguments passed on the stack are naturally aligned.
+            if *ty == WasmType::V128 {
+                *stack_offset = align_to(*stack_offset, size);
+            }
+            let arg = ABIArg::stack_offset(*stack_offset, *ty);
             *stack_offset += size;
             arg
         };
@@ -388,6 +394,27 @@ mod tests {
         match_stack_arg(params.get(5).unwrap(), F32, 40);
     }
 
+    #[test]
+    fn multi_value_abi_sig() {
+        let wasm_sig = WasmFuncType::new([I32, F64].into(), [F32, I32, I64, F32, V128].into());
+
+        let sig = X64ABI::sig(&wasm_sig, &CallingConvention::Default);
+        let params = sig.params_without_retptr();
+
+        assert_eq!(params.len(), 2);
+        match_reg_arg(params.get(0).unwrap(), I32, regs::rdi());
+        match_reg_arg(params.get(1).unwrap(), F64, regs::xmm0());
+        match_reg_arg(sig.retptr().unwrap(), I64, regs::rsi());
+
+        let results = sig.result.operands();
+        match_reg_arg(results.get(0).unwrap(), F32, regs::xmm0());
+        match_stack_arg(results.get(1).unwrap(), I32, 0);
+        match_stack_arg(results.get(2).unwrap(), I64, 8);
+        match_stack_arg(results.get(3).unwrap(), F32, 16);
+        match_stack_arg(results.get(4).unwrap(), V128, 32);
+        assert_eq!(sig.result.ret_area_size(), 48);
+    }
+
     fn match_reg_arg(abi_arg: &ABIArg, expected_ty: WasmType, expected_reg: Reg) {
         match abi_arg {
             &ABIArg::Reg { reg, ty } => {
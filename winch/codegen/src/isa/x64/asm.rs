@@ -2,18 +2,20 @@
 
 use crate::{
     isa::reg::Reg,
-    masm::{CmpKind, DivKind, OperandSize, RemKind, RoundingMode, ShiftKind},
+    masm::{CmpKind, DivKind, ExtendKind, OperandSize, RemKind, RoundingMode, ShiftKind},
 };
 use cranelift_codegen::{
     entity::EntityRef,
-    ir::{types, ConstantPool, ExternalName, LibCall, Opcode, TrapCode, UserExternalNameRef},
+    ir::{
+        types, ConstantPool, ExternalName, LibCall, MemFlags, Opcode, TrapCode, UserExternalNameRef,
+    },
     isa::{
         x64::{
             args::{
                 self, AluRmiROpcode, Amode, CmpOpcode, DivSignedness, ExtMode, FromWritableReg,
                 Gpr, GprMem, GprMemImm, Imm8Gpr, Imm8Reg, RegMem, RegMemImm,
                 ShiftKind as CraneliftShiftKind, SseOpcode, SyntheticAmode, WritableGpr,
-                WritableXmm, Xmm, XmmMem, XmmMemAligned, CC,
+                WritableXmm, Xmm, XmmMem, XmmMemAligned, XmmMemAlignedImm, CC,
             },
             settings as x64_settings, CallInfo, EmitInfo, EmitState, Inst,
         },
@@ -87,6 +89,8 @@ impl From<Reg> for Xmm {
 impl From<OperandSize> for args::OperandSize {
     fn from(size: OperandSize) -> Self {
         match size {
+            OperandSize::S8 => Self::Size8,
+            OperandSize::S16 => Self::Size16,
             OperandSize::S32 => Self::Size32,
             OperandSize::S64 => Self::Size64,
             s => panic!("Invalid operand size {:?}", s),
@@ -94,6 +98,18 @@ impl From<OperandSize> for args::OperandSize {
     }
 }
 
+impl From<ExtendKind> for ExtMode {
+    fn from(value: ExtendKind) -> Self {
+        match value {
+            ExtendKind::I32Extend8S | ExtendKind::I32Extend8U => ExtMode::BL,
+            ExtendKind::I32Extend16S | ExtendKind::I32Extend16U => ExtMode::WL,
+            ExtendKind::I64Extend8S | ExtendKind::I64Extend8U => ExtMode::BQ,
+            ExtendKind::I64Extend16S | ExtendKind::I64Extend16U => ExtMode::WQ,
+            ExtendKind::I64ExtendI32S | ExtendKind::I64ExtendI32U => ExtMode::LQ,
+        }
+    }
+}
+
 impl From<DivKind> for DivSignedness {
     fn from(kind: DivKind) -> DivSignedness {
         match kind {
@@ -190,11 +206,14 @@ impl Assembler {
         pool: &mut ConstantPool,
         constants: &mut VCodeConstants,
         buffer: &mut MachBuffer<Inst>,
+        flags: MemFlags,
     ) -> SyntheticAmode {
         match addr {
-            Address::Offset { base, offset } => {
-                SyntheticAmode::real(Amode::imm_reg(*offset as i32, (*base).into()))
-            }
+            Address::Offset { base, offset } => SyntheticAmode::real(Amode::ImmReg {
+                simm32: *offset as i32,
+                base: (*base).into(),
+                flags,
+            }),
             Address::Const(c) => {
                 // Defer the creation of the
                 // `SyntheticAmode::ConstantOffset` addressing mode
@@ -238,10 +257,15 @@ impl Assembler {
     }
 
     /// Register-to-memory move.
-    pub fn mov_rm(&mut self, src: Reg, addr: &Address, size: OperandSize) {
+    pub fn mov_rm(&mut self, src: Reg, addr: &Address, size: OperandSize, flags: MemFlags) {
         assert!(addr.is_offset());
-        let dst =
-            Self::to_synthetic_amode(addr, &mut self.pool, &mut self.constants, &mut self.buffer);
+        let dst = Self::to_synthetic_amode(
+            addr,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
         self.emit(Inst::MovRM {
             size: size.into(),
             src: src.into(),
@@ -250,10 +274,15 @@ impl Assembler {
     }
 
     /// Immediate-to-memory move.
-    pub fn mov_im(&mut self, src: i32, addr: &Address, size: OperandSize) {
+    pub fn mov_im(&mut self, src: i32, addr: &Address, size: OperandSize, flags: MemFlags) {
         assert!(addr.is_offset());
-        let dst =
-            Self::to_synthetic_amode(addr, &mut self.pool, &mut self.constants, &mut self.buffer);
+        let dst = Self::to_synthetic_amode(
+            addr,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
         self.emit(Inst::MovImmM {
             size: size.into(),
             simm32: src,
@@ -273,12 +302,34 @@ impl Assembler {
         });
     }
 
+    /// Load effective address.
+    pub fn lea(&mut self, addr: &Address, dst: Reg, size: OperandSize) {
+        let addr = Self::to_synthetic_amode(
+            addr,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            MemFlags::trusted(),
+        );
+
+        self.emit(Inst::LoadEffectiveAddress {
+            addr,
+            dst: dst.into(),
+            size: size.into(),
+        });
+    }
+
     /// Memory-to-register load.
-    pub fn mov_mr(&mut self, addr: &Address, dst: Reg, size: OperandSize) {
+    pub fn mov_mr(&mut self, addr: &Address, dst: Reg, size: OperandSize, flags: MemFlags) {
         use OperandSize::S64;
 
-        let src =
-            Self::to_synthetic_amode(addr, &mut self.pool, &mut self.constants, &mut self.buffer);
+        let src = Self::to_synthetic_amode(
+            addr,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
 
         if size == S64 {
             self.emit(Inst::Mov64MR {
@@ -286,15 +337,54 @@ impl Assembler {
                 dst: dst.into(),
             });
         } else {
+            let ext_mode = match size {
+                OperandSize::S8 => ExtMode::BQ,
+                OperandSize::S16 => ExtMode::WQ,
+                _ => ExtMode::LQ,
+            };
             let reg_mem = RegMem::mem(src);
             self.emit(Inst::MovzxRmR {
-                ext_mode: ExtMode::LQ,
+                ext_mode,
                 src: GprMem::new(reg_mem).expect("valid memory address"),
                 dst: dst.into(),
             });
         }
     }
 
+    /// Memory-to-register load with zero extension.
+    pub fn movzx_mr(&mut self, addr: &Address, dst: Reg, kind: ExtendKind, flags: MemFlags) {
+        let src = Self::to_synthetic_amode(
+            addr,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
+        let src = GprMem::new(RegMem::mem(src)).expect("valid memory address");
+        self.emit(Inst::MovzxRmR {
+            ext_mode: kind.into(),
+            src,
+            dst: dst.into(),
+        });
+    }
+
+    /// Memory-to-register load with sign extension.
+    pub fn movsx_mr(&mut self, addr: &Address, dst: Reg, kind: ExtendKind, flags: MemFlags) {
+        let src = Self::to_synthetic_amode(
+            addr,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
+        let src = GprMem::new(RegMem::mem(src)).expect("valid memory address");
+        self.emit(Inst::MovsxRmR {
+            ext_mode: kind.into(),
+            src,
+            dst: dst.into(),
+        });
+    }
+
     /// Integer register conditional move.
     pub fn cmov(&mut self, src: Reg, dst: Reg, cc: CmpKind, size: OperandSize) {
         self.emit(Inst::Cmove {
@@ -315,6 +405,7 @@ impl Assembler {
             S32 => SseOpcode::Movaps,
             S64 => SseOpcode::Movapd,
             S128 => SseOpcode::Movdqa,
+            S8 | S16 => unreachable!(),
         };
 
         self.emit(Inst::XmmUnaryRmRUnaligned {
@@ -325,7 +416,7 @@ impl Assembler {
     }
 
     /// Single and double precision floating point load.
-    pub fn xmm_mov_mr(&mut self, src: &Address, dst: Reg, size: OperandSize) {
+    pub fn xmm_mov_mr(&mut self, src: &Address, dst: Reg, size: OperandSize, flags: MemFlags) {
         use OperandSize::*;
 
         assert!(dst.is_float());
@@ -333,10 +424,16 @@ impl Assembler {
             S32 => SseOpcode::Movss,
             S64 => SseOpcode::Movsd,
             S128 => SseOpcode::Movdqu,
+            S8 | S16 => unreachable!(),
         };
 
-        let src =
-            Self::to_synthetic_amode(src, &mut self.pool, &mut self.constants, &mut self.buffer);
+        let src = Self::to_synthetic_amode(
+            src,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
         self.emit(Inst::XmmUnaryRmRUnaligned {
             op,
             src: XmmMem::new(RegMem::mem(src)).expect("valid xmm unaligned"),
@@ -345,7 +442,7 @@ impl Assembler {
     }
 
     /// Single and double precision floating point store.
-    pub fn xmm_mov_rm(&mut self, src: Reg, dst: &Address, size: OperandSize) {
+    pub fn xmm_mov_rm(&mut self, src: Reg, dst: &Address, size: OperandSize, flags: MemFlags) {
         use OperandSize::*;
 
         assert!(src.is_float());
@@ -354,10 +451,16 @@ impl Assembler {
             S32 => SseOpcode::Movss,
             S64 => SseOpcode::Movsd,
             S128 => SseOpcode::Movdqu,
+            S8 | S16 => unreachable!(),
         };
 
-        let dst =
-            Self::to_synthetic_amode(dst, &mut self.pool, &mut self.constants, &mut self.buffer);
+        let dst = Self::to_synthetic_amode(
+            dst,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            flags,
+        );
         self.emit(Inst::XmmMovRM {
             op,
             src: src.into(),
@@ -372,6 +475,7 @@ impl Assembler {
             OperandSize::S64 => types::F64,
             // Move the entire 128 bits via movdqa.
             OperandSize::S128 => types::I128,
+            OperandSize::S8 | OperandSize::S16 => unreachable!(),
         };
 
         self.emit(Inst::XmmCmove {
@@ -383,6 +487,152 @@ impl Assembler {
         })
     }
 
+    /// Move a value from an xmm register to a general purpose register.
+    pub fn xmm_to_gpr(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        let op = match size {
+            OperandSize::S32 => SseOpcode::Movd,
+            OperandSize::S64 => SseOpcode::Movq,
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
+        };
+
+        self.emit(Inst::XmmToGpr {
+            op,
+            src: src.into(),
+            dst: dst.into(),
+            dst_size: size.into(),
+        });
+    }
+
+    /// Register-to-register move with sign extension.
+    pub fn movsx_rr(&mut self, src: Reg, dst: Reg, kind: ExtendKind) {
+        self.emit(Inst::MovsxRmR {
+            ext_mode: kind.into(),
+            src: src.into(),
+            dst: dst.into(),
+        });
+    }
+
+    /// Register-to-register move with zero extension.
+    pub fn movzx_rr(&mut self, src: Reg, dst: Reg, kind: ExtendKind) {
+        self.emit(Inst::MovzxRmR {
+            ext_mode: kind.into(),
+            src: src.into(),
+            dst: dst.into(),
+        });
+    }
+
+    /// Convert a signed integer to a floating point value.
+    pub fn cvt_sint_to_float(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+    ) {
+        let op = match dst_size {
+            OperandSize::S32 => SseOpcode::Cvtsi2ss,
+            OperandSize::S64 => SseOpcode::Cvtsi2sd,
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
+        };
+
+        self.emit(Inst::CvtIntToFloat {
+            op,
+            src1: dst.into(),
+            src2: src.into(),
+            dst: dst.into(),
+            src2_size: src_size.into(),
+        });
+    }
+
+    /// Convert an unsigned 64-bit integer to a floating point value.
+    pub fn cvt_uint64_to_float_seq(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_gpr1: Reg,
+        tmp_gpr2: Reg,
+        dst_size: OperandSize,
+    ) {
+        self.emit(Inst::CvtUint64ToFloatSeq {
+            dst_size: dst_size.into(),
+            src: src.into(),
+            dst: dst.into(),
+            tmp_gpr1: tmp_gpr1.into(),
+            tmp_gpr2: tmp_gpr2.into(),
+        });
+    }
+
+    /// Convert a floating point value to a signed integer.
+    /// Non-saturating conversions trap if the value is NaN or if it
+    /// overflows the destination type.
+    pub fn cvt_float_to_sint_seq(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_gpr: Reg,
+        tmp_xmm: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        saturating: bool,
+    ) {
+        self.emit(Inst::CvtFloatToSintSeq {
+            dst_size: dst_size.into(),
+            src_size: src_size.into(),
+            is_saturating: saturating,
+            src: src.into(),
+            dst: dst.into(),
+            tmp_gpr: tmp_gpr.into(),
+            tmp_xmm: tmp_xmm.into(),
+        });
+    }
+
+    /// Convert a floating point value to an unsigned integer.
+    /// Non-saturating conversions trap if the value is NaN or if it
+    /// overflows the destination type.
+    pub fn cvt_float_to_uint_seq(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_gpr: Reg,
+        tmp_xmm: Reg,
+        tmp_xmm2: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        saturating: bool,
+    ) {
+        self.emit(Inst::CvtFloatToUintSeq {
+            dst_size: dst_size.into(),
+            src_size: src_size.into(),
+            is_saturating: saturating,
+            src: src.into(),
+            dst: dst.into(),
+            tmp_gpr: tmp_gpr.into(),
+            tmp_xmm: tmp_xmm.into(),
+            tmp_xmm2: tmp_xmm2.into(),
+        });
+    }
+
+    /// Convert between single and double precision floating point values.
+    pub fn cvt_float_to_float(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+    ) {
+        let op = match (src_size, dst_size) {
+            (OperandSize::S32, OperandSize::S64) => SseOpcode::Cvtss2sd,
+            (OperandSize::S64, OperandSize::S32) => SseOpcode::Cvtsd2ss,
+            _ => unreachable!(),
+        };
+
+        self.emit(Inst::XmmUnaryRmRUnaligned {
+            op,
+            src: XmmMem::new(src.into()).expect("valid xmm unaligned"),
+            dst: dst.into(),
+        });
+    }
+
     /// Subtract register and register
     pub fn sub_rr(&mut self, src: Reg, dst: Reg, size: OperandSize) {
         self.emit(Inst::AluRmiR {
@@ -434,7 +684,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Andps,
             OperandSize::S64 => SseOpcode::Andpd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmR {
@@ -450,7 +700,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Andnps,
             OperandSize::S64 => SseOpcode::Andnpd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmR {
@@ -465,7 +715,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Movd,
             OperandSize::S64 => SseOpcode::Movq,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::GprToXmm {
@@ -502,7 +752,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Orps,
             OperandSize::S64 => SseOpcode::Orpd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmR {
@@ -541,7 +791,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Xorps,
             OperandSize::S64 => SseOpcode::Xorpd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmR {
@@ -853,7 +1103,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Addss,
             OperandSize::S64 => SseOpcode::Addsd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmRUnaligned {
@@ -869,7 +1119,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Subss,
             OperandSize::S64 => SseOpcode::Subsd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmRUnaligned {
@@ -885,7 +1135,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Mulss,
             OperandSize::S64 => SseOpcode::Mulsd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmRUnaligned {
@@ -901,7 +1151,7 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Divss,
             OperandSize::S64 => SseOpcode::Divsd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmRmRUnaligned {
@@ -940,29 +1190,28 @@ impl Assembler {
         let op = match size {
             OperandSize::S32 => SseOpcode::Roundss,
             OperandSize::S64 => SseOpcode::Roundsd,
-            OperandSize::S128 => unreachable!(),
-        };
-
-        let imm: u8 = match mode {
-            RoundingMode::Nearest => 0x00,
-            RoundingMode::Down => 0x01,
-            RoundingMode::Up => 0x02,
-            RoundingMode::Zero => 0x03,
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmUnaryRmRImm {
             op,
             src: XmmMemAligned::from(Xmm::from(src)),
-            imm,
+            imm: rounding_imm(mode),
             dst: dst.into(),
         })
     }
 
+    /// Perform a lane-wise rounding operation on the vector register src and
+    /// place the results in dst.
+    pub fn xmm_vector_round(&mut self, op: SseOpcode, src: Reg, dst: Reg, mode: RoundingMode) {
+        self.xmm_vector_unary_rr_imm(op, src, dst, rounding_imm(mode));
+    }
+
     pub fn sqrt(&mut self, src: Reg, dst: Reg, size: OperandSize) {
         let op = match size {
             OperandSize::S32 => SseOpcode::Sqrtss,
             OperandSize::S64 => SseOpcode::Sqrtsd,
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
 
         self.emit(Inst::XmmUnaryRmR {
@@ -972,6 +1221,150 @@ impl Assembler {
         })
     }
 
+    /// Packed binary operation on two xmm registers: `dst = dst <op> src`.
+    /// Requires `has_sse41` flag.
+    pub fn xmm_vector_rr(&mut self, op: SseOpcode, src: Reg, dst: Reg) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmRmR {
+            op,
+            src1: dst.into(),
+            src2: XmmMemAligned::from(Xmm::from(src)),
+            dst: dst.into(),
+        });
+    }
+
+    /// Packed binary operation on an xmm register and a 16-byte aligned
+    /// memory operand, typically a constant: `dst = dst <op> [src]`.
+    /// Requires `has_sse41` flag.
+    pub fn xmm_vector_mr(&mut self, op: SseOpcode, src: &Address, dst: Reg) {
+        self.assert_simd_support();
+        let src = Self::to_synthetic_amode(
+            src,
+            &mut self.pool,
+            &mut self.constants,
+            &mut self.buffer,
+            MemFlags::trusted(),
+        );
+        self.emit(Inst::XmmRmR {
+            op,
+            src1: dst.into(),
+            src2: XmmMemAligned::new(RegMem::mem(src)).expect("valid aligned memory operand"),
+            dst: dst.into(),
+        });
+    }
+
+    /// Packed binary operation with an 8-bit immediate, e.g. `cmpps` or
+    /// `pinsrb`: `dst = op(dst, src, imm)`. The source operand can be
+    /// either an xmm or a general purpose register, depending on the
+    /// opcode. Requires `has_sse41` flag.
+    pub fn xmm_vector_rr_imm(
+        &mut self,
+        op: SseOpcode,
+        src: Reg,
+        dst: Reg,
+        imm: u8,
+        size: OperandSize,
+    ) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmRmRImm {
+            op,
+            src1: dst.into(),
+            src2: src.into(),
+            dst: Writable::from_reg(dst.into()),
+            imm,
+            size: size.into(),
+        });
+    }
+
+    /// Packed unary operation: `dst = op(src)`.
+    /// Requires `has_sse41` flag.
+    pub fn xmm_vector_unary_rr(&mut self, op: SseOpcode, src: Reg, dst: Reg) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmUnaryRmR {
+            op,
+            src: XmmMemAligned::from(Xmm::from(src)),
+            dst: dst.into(),
+        });
+    }
+
+    /// Packed unary operation with an 8-bit immediate, e.g. `pshufd` or
+    /// `roundps`: `dst = op(src, imm)`. Requires `has_sse41` flag.
+    pub fn xmm_vector_unary_rr_imm(&mut self, op: SseOpcode, src: Reg, dst: Reg, imm: u8) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmUnaryRmRImm {
+            op,
+            src: XmmMemAligned::from(Xmm::from(src)),
+            imm,
+            dst: dst.into(),
+        });
+    }
+
+    /// Packed shift by an immediate amount.
+    /// Requires `has_sse41` flag.
+    pub fn xmm_vector_shift_ir(&mut self, op: SseOpcode, imm: u32, dst: Reg) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmRmiReg {
+            opcode: op,
+            src1: dst.into(),
+            src2: XmmMemAlignedImm::new(RegMemImm::imm(imm)).expect("valid immediate"),
+            dst: dst.into(),
+        });
+    }
+
+    /// Packed shift by the amount held in the low 64 bits of the `src`
+    /// xmm register. Requires `has_sse41` flag.
+    pub fn xmm_vector_shift_rr(&mut self, op: SseOpcode, src: Reg, dst: Reg) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmRmiReg {
+            opcode: op,
+            src1: dst.into(),
+            src2: XmmMemAlignedImm::new(src.into()).expect("valid xmm register"),
+            dst: dst.into(),
+        });
+    }
+
+    /// Extract a lane from an xmm register into a general purpose register
+    /// through one of the `pextr` instructions. Requires `has_sse41` flag.
+    pub fn xmm_extract_lane(&mut self, op: SseOpcode, src: Reg, dst: Reg, lane: u8) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmToGprImm {
+            op,
+            src: src.into(),
+            dst: dst.into(),
+            imm: lane,
+        });
+    }
+
+    /// Gather the sign bits of the lanes of `src` into `dst` through one of
+    /// the `movmsk` instructions.
+    pub fn xmm_movmsk(&mut self, op: SseOpcode, src: Reg, dst: Reg) {
+        self.emit(Inst::XmmToGpr {
+            op,
+            src: src.into(),
+            dst: dst.into(),
+            dst_size: args::OperandSize::Size32,
+        });
+    }
+
+    /// Bitwise test of two xmm registers, setting the zero flag if
+    /// `src & dst` is zero. Requires `has_sse41` flag.
+    pub fn ptest(&mut self, src: Reg, dst: Reg) {
+        self.assert_simd_support();
+        self.emit(Inst::XmmCmpRmR {
+            op: SseOpcode::Ptest,
+            src: XmmMemAligned::from(Xmm::from(src)),
+            dst: dst.into(),
+        });
+    }
+
+    /// The lowering of `v128` operators relies on instructions up to
+    /// SSE4.1; functions using them fail to compile without those flags,
+    /// see `X64::check_simd_support`.
+    fn assert_simd_support(&self) {
+        assert!(self.isa_flags.has_ssse3(), "Requires has_ssse3 flag");
+        assert!(self.isa_flags.has_sse41(), "Requires has_sse41 flag");
+    }
+
     /// Emit a call to an unknown location through a register.
     pub fn call_with_reg(&mut self, callee: Reg) {
         self.emit(Inst::CallUnknown {
@@ -1063,3 +1456,13 @@ impl Assembler {
         });
     }
 }
+
+/// Immediate selecting the rounding mode of the `round*` instructions.
+fn rounding_imm(mode: RoundingMode) -> u8 {
+    match mode {
+        RoundingMode::Nearest => 0x00,
+        RoundingMode::Down => 0x01,
+        RoundingMode::Up => 0x02,
+        RoundingMode::Zero => 0x03,
+    }
+}
@@ -6,8 +6,9 @@ use super::{
 };
 
 use crate::masm::{
-    CmpKind, DivKind, Imm as I, MacroAssembler as Masm, OperandSize, RegImm, RemKind, RoundingMode,
-    ShiftKind, TrapCode,
+    CmpKind, DivKind, ExtendKind, FloatCmpKind, Imm as I, MacroAssembler as Masm, OperandSize,
+    RegImm, RemKind, RoundingMode, ShiftKind, TrapCode, TruncKind, V128BinaryOp, V128Lanes,
+    V128UnaryOp,
 };
 use crate::{abi::ABI, masm::StackSlot, stack::TypedReg};
 use crate::{
@@ -19,8 +20,11 @@ use crate::{
     isa::reg::{Reg, RegClass},
     masm::CalleeKind,
 };
+use anyhow::Result;
 use cranelift_codegen::{
-    isa::x64::settings as x64_settings, settings, Final, MachBufferFinalized, MachLabel,
+    ir::MemFlags,
+    isa::x64::{args::SseOpcode, settings as x64_settings},
+    settings, Final, MachBufferFinalized, MachLabel,
 };
 
 use wasmtime_environ::PtrSize;
@@ -62,8 +66,12 @@ impl Masm for MacroAssembler {
         } else {
             let bytes = size.bytes();
             self.reserve_stack(bytes);
-            self.asm
-                .xmm_mov_rm(reg, &self.address_from_sp(self.sp_offset), size);
+            self.asm.xmm_mov_rm(
+                reg,
+                &self.address_from_sp(self.sp_offset),
+                size,
+                MemFlags::trusted(),
+            );
             bytes
         };
 
@@ -125,8 +133,12 @@ impl Masm for MacroAssembler {
             // If the table data declares a particular offset base,
             // load the address into a register to further use it as
             // the table address.
-            self.asm
-                .mov_mr(&self.address_at_vmctx(offset), ptr_base, self.ptr_size);
+            self.asm.mov_mr(
+                &self.address_at_vmctx(offset),
+                ptr_base,
+                self.ptr_size,
+                MemFlags::trusted(),
+            );
         } else {
             // Else, simply move the vmctx register into the addr register as
             // the base to calculate the table address.
@@ -136,7 +148,8 @@ impl Masm for MacroAssembler {
         // OOB check.
         let bound_addr = self.address_at_reg(ptr_base, table_data.current_elems_offset);
         let bound_size = table_data.current_elements_size;
-        self.asm.mov_mr(&bound_addr, bound, bound_size);
+        self.asm
+            .mov_mr(&bound_addr, bound, bound_size, MemFlags::trusted());
         self.asm.cmp_rr(bound, index, bound_size);
         self.asm.trapif(CmpKind::GeU, TrapCode::TableOutOfBounds);
 
@@ -154,6 +167,7 @@ impl Masm for MacroAssembler {
             &self.address_at_reg(ptr_base, table_data.offset),
             ptr_base,
             self.ptr_size,
+            MemFlags::trusted(),
         );
         // Copy the value of the table base into a temporary register
         // so that we can use it later in case of a misspeculation.
@@ -177,15 +191,23 @@ impl Masm for MacroAssembler {
         let size = context.any_gpr(self);
 
         if let Some(offset) = table_data.base {
-            self.asm
-                .mov_mr(&self.address_at_vmctx(offset), scratch, self.ptr_size);
+            self.asm.mov_mr(
+                &self.address_at_vmctx(offset),
+                scratch,
+                self.ptr_size,
+                MemFlags::trusted(),
+            );
         } else {
             self.asm.mov_rr(vmctx, scratch, self.ptr_size);
         };
 
         let size_addr = Address::offset(scratch, table_data.current_elems_offset);
-        self.asm
-            .mov_mr(&size_addr, size, table_data.current_elements_size);
+        self.asm.mov_mr(
+            &size_addr,
+            size,
+            table_data.current_elements_size,
+            MemFlags::trusted(),
+        );
 
         context.stack.push(TypedReg::i32(size).into());
     }
@@ -209,9 +231,9 @@ impl Masm for MacroAssembler {
     fn store(&mut self, src: RegImm, dst: Address, size: OperandSize) {
         match src {
             RegImm::Imm(imm) => match imm {
-                I::I32(v) => self.asm.mov_im(v as i32, &dst, size),
+                I::I32(v) => self.asm.mov_im(v as i32, &dst, size, MemFlags::trusted()),
                 I::I64(v) => match v.try_into() {
-                    Ok(v) => self.asm.mov_im(v, &dst, size),
+                    Ok(v) => self.asm.mov_im(v, &dst, size, MemFlags::trusted()),
                     Err(_) => {
                         panic!("Immediate-to-memory moves require immediate operand to sign-extend to 64 bits.");
                     }
@@ -223,9 +245,9 @@ impl Masm for MacroAssembler {
             },
             RegImm::Reg(reg) => {
                 if reg.is_int() {
-                    self.asm.mov_rm(reg, &dst, size);
+                    self.asm.mov_rm(reg, &dst, size, MemFlags::trusted());
                 } else {
-                    self.asm.xmm_mov_rm(reg, &dst, size);
+                    self.asm.xmm_mov_rm(reg, &dst, size, MemFlags::trusted());
                 }
             }
         }
@@ -237,7 +259,7 @@ impl Masm for MacroAssembler {
             self.decrement_sp(<Self::ABI as abi::ABI>::word_bytes());
         } else {
             let addr = self.address_from_sp(self.sp_offset);
-            self.asm.xmm_mov_mr(&addr, dst, size);
+            self.asm.xmm_mov_mr(&addr, dst, size, MemFlags::trusted());
             self.free_stack(size.bytes());
         }
     }
@@ -266,11 +288,43 @@ impl Masm for MacroAssembler {
         self.load(src, dst, self.ptr_size);
     }
 
+    fn load_addr(&mut self, src: Self::Address, dst: Reg, size: OperandSize) {
+        self.asm.lea(&src, dst, size);
+    }
+
     fn load(&mut self, src: Address, dst: Reg, size: OperandSize) {
         if dst.is_int() {
-            self.asm.mov_mr(&src, dst, size);
+            self.asm.mov_mr(&src, dst, size, MemFlags::trusted());
+        } else {
+            self.asm.xmm_mov_mr(&src, dst, size, MemFlags::trusted());
+        }
+    }
+
+    fn wasm_load(
+        &mut self,
+        src: Self::Address,
+        dst: Reg,
+        size: OperandSize,
+        kind: Option<ExtendKind>,
+    ) {
+        // Heap accesses are not trusted: the emitted instruction
+        // might trap, in which case a `HeapOutOfBounds` trap is
+        // registered for it.
+        let flags = MemFlags::new();
+        match kind {
+            Some(kind) if kind.signed() => self.asm.movsx_mr(&src, dst, kind, flags),
+            Some(kind) => self.asm.movzx_mr(&src, dst, kind, flags),
+            None if dst.is_float() => self.asm.xmm_mov_mr(&src, dst, size, flags),
+            None => self.asm.mov_mr(&src, dst, size, flags),
+        }
+    }
+
+    fn wasm_store(&mut self, src: Reg, dst: Self::Address, size: OperandSize) {
+        let flags = MemFlags::new();
+        if src.is_int() {
+            self.asm.mov_rm(src, &dst, size, flags);
         } else {
-            self.asm.xmm_mov_mr(&src, dst, size);
+            self.asm.xmm_mov_rm(src, &dst, size, flags);
         }
     }
 
@@ -294,11 +348,15 @@ impl Masm for MacroAssembler {
                 I::I64(v) => self.asm.mov_ir(v as u64, dst, size),
                 I::F32(v) => {
                     let addr = self.asm.add_constant(v.to_le_bytes().as_slice());
-                    self.asm.xmm_mov_mr(&addr, dst, size);
+                    self.asm.xmm_mov_mr(&addr, dst, size, MemFlags::trusted());
                 }
                 I::F64(v) => {
                     let addr = self.asm.add_constant(v.to_le_bytes().as_slice());
-                    self.asm.xmm_mov_mr(&addr, dst, size);
+                    self.asm.xmm_mov_mr(&addr, dst, size, MemFlags::trusted());
+                }
+                I::V128(v) => {
+                    let addr = self.asm.add_constant(v.to_le_bytes().as_slice());
+                    self.asm.xmm_mov_mr(&addr, dst, size, MemFlags::trusted());
                 }
             },
         }
@@ -331,6 +389,12 @@ impl Masm for MacroAssembler {
         }
     }
 
+    fn checked_uadd(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize, trap: TrapCode) {
+        self.add(dst, lhs, rhs, size);
+        // An unsigned overflow sets the carry flag.
+        self.asm.trapif(CmpKind::LtU, trap);
+    }
+
     fn sub(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize) {
         Self::ensure_two_argument_form(&dst, &lhs);
         match (rhs, dst) {
@@ -406,7 +470,7 @@ impl Masm for MacroAssembler {
         let sign_mask = match size {
             OperandSize::S32 => I::I32(0x80000000),
             OperandSize::S64 => I::I64(0x8000000000000000),
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
         self.load_constant(&sign_mask, scratch_gpr, size);
         self.asm.gpr_to_xmm(scratch_gpr, scratch_xmm, size);
@@ -428,7 +492,7 @@ impl Masm for MacroAssembler {
         let mask = match size {
             OperandSize::S32 => I::I32(0x80000000),
             OperandSize::S64 => I::I64(0x8000000000000000),
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
         let scratch_gpr = regs::scratch();
         self.load_constant(&mask, scratch_gpr, size);
@@ -442,7 +506,7 @@ impl Masm for MacroAssembler {
         let mask = match size {
             OperandSize::S32 => I::I32(0x7fffffff),
             OperandSize::S64 => I::I64(0x7fffffffffffffff),
-            OperandSize::S128 => unreachable!(),
+            OperandSize::S8 | OperandSize::S16 | OperandSize::S128 => unreachable!(),
         };
         let scratch_gpr = regs::scratch();
         self.load_constant(&mask, scratch_gpr, size);
@@ -707,6 +771,921 @@ impl Masm for MacroAssembler {
             self.asm.add_rr(scratch, dst, size);
         }
     }
+    fn v128_splat(&mut self, src: Reg, dst: Reg, lanes: V128Lanes) -> Result<()> {
+        match lanes {
+            V128Lanes::I8x16 => {
+                let scratch = regs::scratch_xmm();
+                self.asm.gpr_to_xmm(src, dst, OperandSize::S32);
+                // Shuffling with an all-zeroes mask broadcasts the first byte.
+                self.asm.xmm_vector_rr(SseOpcode::Pxor, scratch, scratch);
+                self.asm.xmm_vector_rr(SseOpcode::Pshufb, scratch, dst);
+            }
+            V128Lanes::I16x8 => {
+                self.asm.gpr_to_xmm(src, dst, OperandSize::S32);
+                self.asm
+                    .xmm_vector_unary_rr_imm(SseOpcode::Pshuflw, dst, dst, 0);
+                self.asm
+                    .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, dst, dst, 0);
+            }
+            V128Lanes::I32x4 => {
+                self.asm.gpr_to_xmm(src, dst, OperandSize::S32);
+                self.asm
+                    .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, dst, dst, 0);
+            }
+            V128Lanes::I64x2 => {
+                self.asm.gpr_to_xmm(src, dst, OperandSize::S64);
+                self.asm
+                    .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, dst, dst, 0x44);
+            }
+            V128Lanes::F32x4 => self
+                .asm
+                .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, src, dst, 0),
+            V128Lanes::F64x2 => self
+                .asm
+                .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, src, dst, 0x44),
+        }
+        Ok(())
+    }
+
+    fn v128_extract_lane(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        lane: u8,
+        lanes: V128Lanes,
+        signed: bool,
+    ) -> Result<()> {
+        match lanes {
+            V128Lanes::I8x16 => {
+                self.asm.xmm_extract_lane(SseOpcode::Pextrb, src, dst, lane);
+                if signed {
+                    self.asm.movsx_rr(dst, dst, ExtendKind::I32Extend8S);
+                }
+            }
+            V128Lanes::I16x8 => {
+                self.asm.xmm_extract_lane(SseOpcode::Pextrw, src, dst, lane);
+                if signed {
+                    self.asm.movsx_rr(dst, dst, ExtendKind::I32Extend16S);
+                }
+            }
+            V128Lanes::I32x4 => self.asm.xmm_extract_lane(SseOpcode::Pextrd, src, dst, lane),
+            V128Lanes::I64x2 => self.asm.xmm_extract_lane(SseOpcode::Pextrq, src, dst, lane),
+            // The scalar is the low lane of the destination; the rest of
+            // the register is ignored.
+            V128Lanes::F32x4 | V128Lanes::F64x2 if lane == 0 => {
+                if src != dst {
+                    self.asm.xmm_mov_rr(src, dst, OperandSize::S128);
+                }
+            }
+            V128Lanes::F32x4 => self
+                .asm
+                .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, src, dst, lane),
+            V128Lanes::F64x2 => self
+                .asm
+                .xmm_vector_unary_rr_imm(SseOpcode::Pshufd, src, dst, 0xee),
+        }
+        Ok(())
+    }
+
+    fn v128_replace_lane(&mut self, src: Reg, dst: Reg, lane: u8, lanes: V128Lanes) -> Result<()> {
+        use OperandSize::*;
+        match lanes {
+            V128Lanes::I8x16 => self
+                .asm
+                .xmm_vector_rr_imm(SseOpcode::Pinsrb, src, dst, lane, S32),
+            V128Lanes::I16x8 => self
+                .asm
+                .xmm_vector_rr_imm(SseOpcode::Pinsrw, src, dst, lane, S32),
+            V128Lanes::I32x4 => self
+                .asm
+                .xmm_vector_rr_imm(SseOpcode::Pinsrd, src, dst, lane, S32),
+            // `pinsrq` is the 64-bit form of `pinsrd`.
+            V128Lanes::I64x2 => self
+                .asm
+                .xmm_vector_rr_imm(SseOpcode::Pinsrd, src, dst, lane, S64),
+            V128Lanes::F32x4 => {
+                self.asm
+                    .xmm_vector_rr_imm(SseOpcode::Insertps, src, dst, lane << 4, S32)
+            }
+            V128Lanes::F64x2 if lane == 0 => self.asm.xmm_vector_rr(SseOpcode::Movsd, src, dst),
+            V128Lanes::F64x2 => self.asm.xmm_vector_rr(SseOpcode::Movlhps, src, dst),
+        }
+        Ok(())
+    }
+
+    fn v128_shuffle(&mut self, dst: Reg, lhs: Reg, rhs: Reg, indices: [u8; 16]) -> Result<()> {
+        Self::ensure_two_argument_form(&dst, &lhs);
+        // `pshufb` zeroes the bytes whose index has the most significant bit
+        // set, so each operand is shuffled with a mask selecting only its
+        // own bytes and the results are combined.
+        let lhs_mask: Vec<u8> = indices
+            .iter()
+            .map(|&i| if i < 16 { i } else { 0x80 })
+            .collect();
+        let rhs_mask: Vec<u8> = indices
+            .iter()
+            .map(|&i| if i >= 16 { i - 16 } else { 0x80 })
+            .collect();
+        let scratch = regs::scratch_xmm();
+
+        self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+        let addr = self.asm.add_constant(&rhs_mask);
+        self.asm.xmm_vector_mr(SseOpcode::Pshufb, &addr, scratch);
+        let addr = self.asm.add_constant(&lhs_mask);
+        self.asm.xmm_vector_mr(SseOpcode::Pshufb, &addr, dst);
+        self.asm.xmm_vector_rr(SseOpcode::Por, scratch, dst);
+        Ok(())
+    }
+
+    fn v128_binop(
+        &mut self,
+        context: &mut CodeGenContext,
+        dst: Reg,
+        lhs: Reg,
+        rhs: Reg,
+        op: V128BinaryOp,
+    ) -> Result<()> {
+        use SseOpcode::*;
+        use V128BinaryOp as Op;
+        use V128Lanes::*;
+
+        Self::ensure_two_argument_form(&dst, &lhs);
+        let scratch = regs::scratch_xmm();
+
+        let opcode = match op {
+            Op::And => Pand,
+            Op::Or => Por,
+            Op::Xor => Pxor,
+            Op::AndNot => {
+                // `pandn` complements its destination operand.
+                self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+                self.asm.xmm_vector_rr(Pandn, dst, scratch);
+                self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+                return Ok(());
+            }
+            Op::Add(lanes) => match lanes {
+                I8x16 => Paddb,
+                I16x8 => Paddw,
+                I32x4 => Paddd,
+                I64x2 => Paddq,
+                F32x4 => Addps,
+                F64x2 => Addpd,
+            },
+            Op::Sub(lanes) => match lanes {
+                I8x16 => Psubb,
+                I16x8 => Psubw,
+                I32x4 => Psubd,
+                I64x2 => Psubq,
+                F32x4 => Subps,
+                F64x2 => Subpd,
+            },
+            Op::AddSatS(lanes) => match lanes {
+                I8x16 => Paddsb,
+                I16x8 => Paddsw,
+                _ => unreachable!(),
+            },
+            Op::AddSatU(lanes) => match lanes {
+                I8x16 => Paddusb,
+                I16x8 => Paddusw,
+                _ => unreachable!(),
+            },
+            Op::SubSatS(lanes) => match lanes {
+                I8x16 => Psubsb,
+                I16x8 => Psubsw,
+                _ => unreachable!(),
+            },
+            Op::SubSatU(lanes) => match lanes {
+                I8x16 => Psubusb,
+                I16x8 => Psubusw,
+                _ => unreachable!(),
+            },
+            Op::Mul(I64x2) => {
+                self.v128_i64x2_mul(context, dst, rhs);
+                return Ok(());
+            }
+            Op::Mul(lanes) => match lanes {
+                I16x8 => Pmullw,
+                I32x4 => Pmulld,
+                F32x4 => Mulps,
+                F64x2 => Mulpd,
+                _ => unreachable!(),
+            },
+            Op::Div(lanes) => match lanes {
+                F32x4 => Divps,
+                F64x2 => Divpd,
+                _ => unreachable!(),
+            },
+            Op::MinS(lanes) => match lanes {
+                I8x16 => Pminsb,
+                I16x8 => Pminsw,
+                I32x4 => Pminsd,
+                _ => unreachable!(),
+            },
+            Op::MinU(lanes) => match lanes {
+                I8x16 => Pminub,
+                I16x8 => Pminuw,
+                I32x4 => Pminud,
+                _ => unreachable!(),
+            },
+            Op::MaxS(lanes) => match lanes {
+                I8x16 => Pmaxsb,
+                I16x8 => Pmaxsw,
+                I32x4 => Pmaxsd,
+                _ => unreachable!(),
+            },
+            Op::MaxU(lanes) => match lanes {
+                I8x16 => Pmaxub,
+                I16x8 => Pmaxuw,
+                I32x4 => Pmaxud,
+                _ => unreachable!(),
+            },
+            Op::AvgrU(lanes) => match lanes {
+                I8x16 => Pavgb,
+                I16x8 => Pavgw,
+                _ => unreachable!(),
+            },
+            Op::Min(lanes) => {
+                self.v128_float_min(dst, rhs, lanes);
+                return Ok(());
+            }
+            Op::Max(lanes) => {
+                self.v128_float_max(context, dst, rhs, lanes);
+                return Ok(());
+            }
+            Op::PMin(lanes) | Op::PMax(lanes) => {
+                // The pseudo-minimum and pseudo-maximum return the first
+                // operand unless the second one is strictly less (or
+                // greater), which matches the behaviour of `min` and `max`
+                // with their operands swapped.
+                let opcode = match (op, lanes) {
+                    (Op::PMin(_), F32x4) => Minps,
+                    (Op::PMin(_), F64x2) => Minpd,
+                    (Op::PMax(_), F32x4) => Maxps,
+                    (Op::PMax(_), F64x2) => Maxpd,
+                    _ => unreachable!(),
+                };
+                self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+                self.asm.xmm_vector_rr(opcode, lhs, scratch);
+                self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+                return Ok(());
+            }
+            Op::NarrowS(lanes) => match lanes {
+                I8x16 => Packsswb,
+                I16x8 => Packssdw,
+                _ => unreachable!(),
+            },
+            Op::NarrowU(lanes) => match lanes {
+                I8x16 => Packuswb,
+                I16x8 => Packusdw,
+                _ => unreachable!(),
+            },
+            Op::ExtMulLowS(lanes)
+            | Op::ExtMulHighS(lanes)
+            | Op::ExtMulLowU(lanes)
+            | Op::ExtMulHighU(lanes) => {
+                let signed = matches!(op, Op::ExtMulLowS(_) | Op::ExtMulHighS(_));
+                let high = matches!(op, Op::ExtMulHighS(_) | Op::ExtMulHighU(_));
+                self.v128_extend(dst, dst, lanes, signed, high);
+                self.v128_extend(rhs, scratch, lanes, signed, high);
+                // After the extension, the 64-bit multiplications only need
+                // the low 32 bits of each lane.
+                let opcode = match lanes {
+                    I16x8 => Pmullw,
+                    I32x4 => Pmulld,
+                    I64x2 if signed => Pmuldq,
+                    I64x2 => Pmuludq,
+                    _ => unreachable!(),
+                };
+                self.asm.xmm_vector_rr(opcode, scratch, dst);
+                return Ok(());
+            }
+            Op::Q15MulrSatS => {
+                // `pmulhrsw` only overflows when multiplying `i16::MIN` by
+                // itself, producing `0x8000` instead of `0x7fff`; flip the
+                // bits of the lanes holding `0x8000`.
+                self.asm.xmm_vector_rr(Pmulhrsw, rhs, dst);
+                let addr = self.v128_splat_constant(&0x8000u16.to_le_bytes());
+                self.asm
+                    .xmm_mov_mr(&addr, scratch, OperandSize::S128, MemFlags::trusted());
+                self.asm.xmm_vector_rr(Pcmpeqw, dst, scratch);
+                self.asm.xmm_vector_rr(Pxor, scratch, dst);
+                return Ok(());
+            }
+            Op::DotI16x8S => Pmaddwd,
+            Op::Swizzle => {
+                // Saturating the indices with 0x70 sets the most
+                // significant bit of the out of range ones, which makes
+                // `pshufb` zero the corresponding bytes.
+                self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+                let addr = self.v128_splat_constant(&[0x70]);
+                self.asm.xmm_vector_mr(Paddusb, &addr, scratch);
+                self.asm.xmm_vector_rr(Pshufb, scratch, dst);
+                return Ok(());
+            }
+        };
+
+        self.asm.xmm_vector_rr(opcode, rhs, dst);
+        Ok(())
+    }
+
+    fn v128_unop(&mut self, context: &mut CodeGenContext, dst: Reg, op: V128UnaryOp) -> Result<()> {
+        use SseOpcode::*;
+        use V128Lanes::*;
+        use V128UnaryOp as Op;
+
+        let scratch = regs::scratch_xmm();
+        match op {
+            Op::Not => self.v128_not(dst),
+            Op::Abs(I64x2) => {
+                // Broadcast the sign of each lane and compute
+                // `(x ^ sign) - sign`.
+                self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+                self.asm.xmm_vector_shift_ir(Psrad, 31, scratch);
+                self.asm
+                    .xmm_vector_unary_rr_imm(Pshufd, scratch, scratch, 0xf5);
+                self.asm.xmm_vector_rr(Pxor, scratch, dst);
+                self.asm.xmm_vector_rr(Psubq, scratch, dst);
+            }
+            Op::Abs(lanes @ (F32x4 | F64x2)) => {
+                // Clear the sign bit.
+                let (shift, and) = if lanes == F32x4 {
+                    (Psrld, Andps)
+                } else {
+                    (Psrlq, Andpd)
+                };
+                self.asm.xmm_vector_rr(Pcmpeqd, scratch, scratch);
+                self.asm.xmm_vector_shift_ir(shift, 1, scratch);
+                self.asm.xmm_vector_rr(and, scratch, dst);
+            }
+            Op::Abs(lanes) => {
+                let opcode = match lanes {
+                    I8x16 => Pabsb,
+                    I16x8 => Pabsw,
+                    I32x4 => Pabsd,
+                    _ => unreachable!(),
+                };
+                self.asm.xmm_vector_unary_rr(opcode, dst, dst);
+            }
+            Op::Neg(lanes @ (F32x4 | F64x2)) => {
+                // Flip the sign bit.
+                let (shift, amount, xor) = if lanes == F32x4 {
+                    (Pslld, 31, Xorps)
+                } else {
+                    (Psllq, 63, Xorpd)
+                };
+                self.asm.xmm_vector_rr(Pcmpeqd, scratch, scratch);
+                self.asm.xmm_vector_shift_ir(shift, amount, scratch);
+                self.asm.xmm_vector_rr(xor, scratch, dst);
+            }
+            Op::Neg(lanes) => {
+                let opcode = match lanes {
+                    I8x16 => Psubb,
+                    I16x8 => Psubw,
+                    I32x4 => Psubd,
+                    I64x2 => Psubq,
+                    _ => unreachable!(),
+                };
+                self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+                self.asm.xmm_vector_rr(Pxor, dst, dst);
+                self.asm.xmm_vector_rr(opcode, scratch, dst);
+            }
+            Op::Popcnt => {
+                // Look up the population count of each nibble in a table and
+                // add the counts of the low and high nibbles.
+                let tmp = context.reg_for_class(RegClass::Float, self);
+                let mask = self.v128_splat_constant(&[0x0f]);
+                let table = self
+                    .asm
+                    .add_constant(&[0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4]);
+
+                self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+                self.asm.xmm_vector_mr(Pand, &mask, scratch);
+                self.asm.xmm_vector_shift_ir(Psrlw, 4, dst);
+                self.asm.xmm_vector_mr(Pand, &mask, dst);
+                self.asm
+                    .xmm_mov_mr(&table, tmp, OperandSize::S128, MemFlags::trusted());
+                self.asm.xmm_vector_rr(Pshufb, scratch, tmp);
+                self.asm
+                    .xmm_mov_mr(&table, scratch, OperandSize::S128, MemFlags::trusted());
+                self.asm.xmm_vector_rr(Pshufb, dst, scratch);
+                self.asm.xmm_mov_rr(tmp, dst, OperandSize::S128);
+                self.asm.xmm_vector_rr(Paddb, scratch, dst);
+                context.free_reg(tmp);
+            }
+            Op::Sqrt(lanes) => {
+                let opcode = if lanes == F32x4 { Sqrtps } else { Sqrtpd };
+                self.asm.xmm_vector_unary_rr(opcode, dst, dst);
+            }
+            Op::Round(lanes, mode) => {
+                let opcode = if lanes == F32x4 { Roundps } else { Roundpd };
+                self.asm.xmm_vector_round(opcode, dst, dst, mode);
+            }
+            Op::ExtendLowS(lanes) => self.v128_extend(dst, dst, lanes, true, false),
+            Op::ExtendHighS(lanes) => self.v128_extend(dst, dst, lanes, true, true),
+            Op::ExtendLowU(lanes) => self.v128_extend(dst, dst, lanes, false, false),
+            Op::ExtendHighU(lanes) => self.v128_extend(dst, dst, lanes, false, true),
+            Op::ExtAddPairwiseS(I16x8) => {
+                // `pmaddubsw` treats its source operand as signed bytes.
+                let ones = self.v128_splat_constant(&[1]);
+                self.asm
+                    .xmm_mov_mr(&ones, scratch, OperandSize::S128, MemFlags::trusted());
+                self.asm.xmm_vector_rr(Pmaddubsw, dst, scratch);
+                self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+            }
+            Op::ExtAddPairwiseU(I16x8) => {
+                let ones = self.v128_splat_constant(&[1]);
+                self.asm.xmm_vector_mr(Pmaddubsw, &ones, dst);
+            }
+            Op::ExtAddPairwiseS(I32x4) => {
+                let ones = self.v128_splat_constant(&1u16.to_le_bytes());
+                self.asm.xmm_vector_mr(Pmaddwd, &ones, dst);
+            }
+            Op::ExtAddPairwiseU(I32x4) => {
+                // Bias the lanes to make them signed, add them in pairs and
+                // remove the bias.
+                let bias = self.v128_splat_constant(&0x8000u16.to_le_bytes());
+                let ones = self.v128_splat_constant(&1u16.to_le_bytes());
+                let unbias = self.v128_splat_constant(&0x10000u32.to_le_bytes());
+                self.asm.xmm_vector_mr(Pxor, &bias, dst);
+                self.asm.xmm_vector_mr(Pmaddwd, &ones, dst);
+                self.asm.xmm_vector_mr(Paddd, &unbias, dst);
+            }
+            Op::ExtAddPairwiseS(_) | Op::ExtAddPairwiseU(_) => unreachable!(),
+            Op::F32x4ConvertI32x4S => self.asm.xmm_vector_unary_rr(Cvtdq2ps, dst, dst),
+            Op::F32x4ConvertI32x4U => {
+                // Convert the low 16 bits of each lane exactly, and the high
+                // bits halved, to stay within the signed range of
+                // `cvtdq2ps`; then add both halves.
+                self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+                self.asm.xmm_vector_shift_ir(Pslld, 16, scratch);
+                self.asm.xmm_vector_shift_ir(Psrld, 16, scratch);
+                self.asm.xmm_vector_rr(Psubd, scratch, dst);
+                self.asm.xmm_vector_unary_rr(Cvtdq2ps, scratch, scratch);
+                self.asm.xmm_vector_shift_ir(Psrld, 1, dst);
+                self.asm.xmm_vector_unary_rr(Cvtdq2ps, dst, dst);
+                self.asm.xmm_vector_rr(Addps, dst, dst);
+                self.asm.xmm_vector_rr(Addps, scratch, dst);
+            }
+            Op::F64x2ConvertLowI32x4S => self.asm.xmm_vector_unary_rr(Cvtdq2pd, dst, dst),
+            Op::F64x2ConvertLowI32x4U => {
+                // Use each lane as the low bits of the mantissa of 2^52 and
+                // subtract 2^52 from the result.
+                let high = self.v128_splat_constant(&0x43300000u32.to_le_bytes());
+                let bias = self.v128_splat_constant(&0x4330000000000000u64.to_le_bytes());
+                self.asm.xmm_vector_mr(Unpcklps, &high, dst);
+                self.asm.xmm_vector_mr(Subpd, &bias, dst);
+            }
+            Op::I32x4TruncSatF32x4S => {
+                // Zero the NaN lanes and use the fact that `cvttps2dq`
+                // produces `0x80000000` on overflow to saturate the
+                // positive lanes.
+                self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+                self.asm
+                    .xmm_vector_rr_imm(Cmpps, scratch, scratch, 0, OperandSize::S32);
+                self.asm.xmm_vector_rr(Andps, scratch, dst);
+                self.asm.xmm_vector_rr(Pxor, dst, scratch);
+                self.asm.xmm_vector_unary_rr(Cvttps2dq, dst, dst);
+                self.asm.xmm_vector_rr(Pand, dst, scratch);
+                self.asm.xmm_vector_shift_ir(Psrad, 31, scratch);
+                self.asm.xmm_vector_rr(Pxor, scratch, dst);
+            }
+            Op::I32x4TruncSatF32x4U => {
+                let tmp = context.reg_for_class(RegClass::Float, self);
+                // Clamp the negative and NaN lanes to zero.
+                self.asm.xmm_vector_rr(Xorps, scratch, scratch);
+                self.asm.xmm_vector_rr(Maxps, scratch, dst);
+                // 2^31 as a float.
+                self.asm.xmm_vector_rr(Pcmpeqd, scratch, scratch);
+                self.asm.xmm_vector_shift_ir(Psrld, 1, scratch);
+                self.asm.xmm_vector_unary_rr(Cvtdq2ps, scratch, scratch);
+                // Convert the lanes below 2^31 in `dst` and the excess over
+                // 2^31 in `tmp`, saturating the latter.
+                self.asm.xmm_mov_rr(dst, tmp, OperandSize::S128);
+                self.asm.xmm_vector_unary_rr(Cvttps2dq, dst, dst);
+                self.asm.xmm_vector_rr(Subps, scratch, tmp);
+                self.asm
+                    .xmm_vector_rr_imm(Cmpps, tmp, scratch, 2, OperandSize::S32);
+                self.asm.xmm_vector_unary_rr(Cvttps2dq, tmp, tmp);
+                self.asm.xmm_vector_rr(Pxor, scratch, tmp);
+                self.asm.xmm_vector_rr(Pxor, scratch, scratch);
+                self.asm.xmm_vector_rr(Pmaxsd, scratch, tmp);
+                self.asm.xmm_vector_rr(Paddd, tmp, dst);
+                context.free_reg(tmp);
+            }
+            Op::I32x4TruncSatF64x2SZero => {
+                // Zero the NaN lanes and clamp the rest to the maximum
+                // `i32`; `cvttpd2dq` saturates the negative overflow.
+                let max = self.v128_splat_constant(&2147483647.0f64.to_bits().to_le_bytes());
+                self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+                self.asm
+                    .xmm_vector_rr_imm(Cmppd, dst, scratch, 0, OperandSize::S32);
+                self.asm.xmm_vector_mr(Andps, &max, scratch);
+                self.asm.xmm_vector_rr(Minpd, scratch, dst);
+                self.asm.xmm_vector_unary_rr(Cvttpd2dq, dst, dst);
+            }
+            Op::I32x4TruncSatF64x2UZero => {
+                // Clamp the lanes to the `u32` range, truncate them and
+                // use them as the low bits of the mantissa of 2^52 to
+                // extract the integers.
+                let max = self.v128_splat_constant(&4294967295.0f64.to_bits().to_le_bytes());
+                let bias = self.v128_splat_constant(&0x4330000000000000u64.to_le_bytes());
+                self.asm.xmm_vector_rr(Xorpd, scratch, scratch);
+                self.asm.xmm_vector_rr(Maxpd, scratch, dst);
+                self.asm.xmm_vector_mr(Minpd, &max, dst);
+                self.asm
+                    .xmm_vector_round(Roundpd, dst, dst, RoundingMode::Zero);
+                self.asm.xmm_vector_mr(Addpd, &bias, dst);
+                self.asm
+                    .xmm_vector_rr_imm(Shufps, scratch, dst, 0x88, OperandSize::S32);
+            }
+            Op::F32x4DemoteF64x2Zero => self.asm.xmm_vector_unary_rr(Cvtpd2ps, dst, dst),
+            Op::F64x2PromoteLowF32x4 => self.asm.xmm_vector_unary_rr(Cvtps2pd, dst, dst),
+        }
+        Ok(())
+    }
+
+    fn v128_bitselect(&mut self, dst: Reg, lhs: Reg, rhs: Reg, mask: Reg) -> Result<()> {
+        Self::ensure_two_argument_form(&dst, &lhs);
+        let scratch = regs::scratch_xmm();
+        // dst = (lhs & mask) | (rhs & !mask)
+        self.asm.xmm_mov_rr(mask, scratch, OperandSize::S128);
+        self.asm.xmm_vector_rr(SseOpcode::Pandn, rhs, scratch);
+        self.asm.xmm_vector_rr(SseOpcode::Pand, mask, dst);
+        self.asm.xmm_vector_rr(SseOpcode::Por, scratch, dst);
+        Ok(())
+    }
+
+    fn v128_cmp(
+        &mut self,
+        dst: Reg,
+        lhs: Reg,
+        rhs: Reg,
+        kind: CmpKind,
+        lanes: V128Lanes,
+    ) -> Result<()> {
+        use SseOpcode::*;
+        use V128Lanes::*;
+
+        Self::ensure_two_argument_form(&dst, &lhs);
+        let scratch = regs::scratch_xmm();
+
+        let (eq, gt) = match lanes {
+            I8x16 => (Pcmpeqb, Pcmpgtb),
+            I16x8 => (Pcmpeqw, Pcmpgtw),
+            I32x4 => (Pcmpeqd, Pcmpgtd),
+            I64x2 => (Pcmpeqq, Pcmpgtq),
+            F32x4 | F64x2 => unreachable!(),
+        };
+
+        if lanes == I64x2 && !matches!(kind, CmpKind::Eq | CmpKind::Ne) {
+            assert!(self.flags.has_sse42(), "Requires has_sse42 flag");
+        }
+
+        match kind {
+            CmpKind::Eq => self.asm.xmm_vector_rr(eq, rhs, dst),
+            CmpKind::Ne => {
+                self.asm.xmm_vector_rr(eq, rhs, dst);
+                self.v128_not(dst);
+            }
+            CmpKind::GtS => self.asm.xmm_vector_rr(gt, rhs, dst),
+            CmpKind::LtS => {
+                self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+                self.asm.xmm_vector_rr(gt, lhs, scratch);
+                self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+            }
+            // There are no 64-bit integer minimum and maximum instructions;
+            // negate the strict comparisons instead.
+            CmpKind::LeS if lanes == I64x2 => {
+                self.asm.xmm_vector_rr(gt, rhs, dst);
+                self.v128_not(dst);
+            }
+            CmpKind::GeS if lanes == I64x2 => {
+                self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+                self.asm.xmm_vector_rr(gt, lhs, scratch);
+                self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+                self.v128_not(dst);
+            }
+            CmpKind::LeS | CmpKind::GeS | CmpKind::LeU | CmpKind::GeU => {
+                // `lhs <= rhs` if and only if `lhs == min(lhs, rhs)`, and
+                // similarly for the maximum.
+                let opcode = match (kind, lanes) {
+                    (CmpKind::LeS, I8x16) => Pminsb,
+                    (CmpKind::LeS, I16x8) => Pminsw,
+                    (CmpKind::LeS, I32x4) => Pminsd,
+                    (CmpKind::GeS, I8x16) => Pmaxsb,
+                    (CmpKind::GeS, I16x8) => Pmaxsw,
+                    (CmpKind::GeS, I32x4) => Pmaxsd,
+                    (CmpKind::LeU, I8x16) => Pminub,
+                    (CmpKind::LeU, I16x8) => Pminuw,
+                    (CmpKind::LeU, I32x4) => Pminud,
+                    (CmpKind::GeU, I8x16) => Pmaxub,
+                    (CmpKind::GeU, I16x8) => Pmaxuw,
+                    (CmpKind::GeU, I32x4) => Pmaxud,
+                    _ => unreachable!(),
+                };
+                self.asm.xmm_mov_rr(lhs, scratch, OperandSize::S128);
+                self.asm.xmm_vector_rr(opcode, rhs, scratch);
+                self.asm.xmm_vector_rr(eq, scratch, dst);
+            }
+            CmpKind::GtU => {
+                self.v128_cmp(dst, lhs, rhs, CmpKind::LeU, lanes)?;
+                self.v128_not(dst);
+            }
+            CmpKind::LtU => {
+                self.v128_cmp(dst, lhs, rhs, CmpKind::GeU, lanes)?;
+                self.v128_not(dst);
+            }
+        }
+        Ok(())
+    }
+
+    fn v128_float_cmp(
+        &mut self,
+        dst: Reg,
+        lhs: Reg,
+        rhs: Reg,
+        kind: FloatCmpKind,
+        lanes: V128Lanes,
+    ) -> Result<()> {
+        Self::ensure_two_argument_form(&dst, &lhs);
+        let opcode = match lanes {
+            V128Lanes::F32x4 => SseOpcode::Cmpps,
+            V128Lanes::F64x2 => SseOpcode::Cmppd,
+            _ => unreachable!(),
+        };
+        // Comparison predicates of `cmpps` and `cmppd`.
+        let (imm, swap) = match kind {
+            FloatCmpKind::Eq => (0, false),
+            FloatCmpKind::Lt => (1, false),
+            FloatCmpKind::Le => (2, false),
+            FloatCmpKind::Ne => (4, false),
+            FloatCmpKind::Gt => (1, true),
+            FloatCmpKind::Ge => (2, true),
+        };
+
+        if swap {
+            let scratch = regs::scratch_xmm();
+            self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+            self.asm
+                .xmm_vector_rr_imm(opcode, lhs, scratch, imm, OperandSize::S32);
+            self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+        } else {
+            self.asm
+                .xmm_vector_rr_imm(opcode, rhs, dst, imm, OperandSize::S32);
+        }
+        Ok(())
+    }
+
+    fn v128_shift(
+        &mut self,
+        context: &mut CodeGenContext,
+        kind: ShiftKind,
+        lanes: V128Lanes,
+    ) -> Result<()> {
+        use SseOpcode::*;
+        use V128Lanes::*;
+
+        // The shift amount is taken modulo the lane width.
+        let mask = lanes.lane_size().num_bits() - 1;
+        let top = context.stack.peek().expect("value at stack top");
+        let (amount, dst) = if top.is_i32_const() {
+            let val = context
+                .stack
+                .pop_i32_const()
+                .expect("i32 const value at stack top");
+            let dst = context.pop_to_reg(self, None);
+            (RegImm::i32(val & mask), dst)
+        } else {
+            let amount = context.pop_to_reg(self, None);
+            let dst = context.pop_to_reg(self, None);
+            (RegImm::reg(amount.into()), dst)
+        };
+
+        // Allocate the temporary register, if any, before using the scratch
+        // registers, given that spilling might clobber them.
+        let needs_tmp = matches!((kind, lanes), (_, I8x16) | (ShiftKind::ShrS, I64x2));
+        let tmp = needs_tmp.then(|| context.reg_for_class(RegClass::Float, self));
+
+        let amount = match amount {
+            RegImm::Reg(reg) => {
+                self.asm.and_ir(mask, reg, OperandSize::S32);
+                reg
+            }
+            RegImm::Imm(imm) => {
+                let scratch = regs::scratch();
+                self.load_constant(&imm, scratch, OperandSize::S32);
+                scratch
+            }
+        };
+        // The arithmetic shift of the bytes is performed on 16-bit lanes
+        // holding the bytes in their high half.
+        if lanes == I8x16 && kind == ShiftKind::ShrS {
+            self.asm.add_ir(8, amount, OperandSize::S32);
+        }
+        let count = regs::scratch_xmm();
+        self.asm.gpr_to_xmm(amount, count, OperandSize::S32);
+        if amount != regs::scratch() {
+            context.free_reg(amount);
+        }
+
+        match (kind, lanes) {
+            (ShiftKind::Shl | ShiftKind::ShrU, I8x16) => {
+                // Shift as 16-bit lanes and clear the bits shifted across
+                // bytes, using a mask made of 0xff shifted by the same
+                // amount and broadcast to every byte.
+                let tmp = tmp.expect("temporary register");
+                let shift = if kind == ShiftKind::Shl { Psllw } else { Psrlw };
+                self.asm.xmm_vector_shift_rr(shift, count, dst.into());
+                self.asm.xmm_vector_rr(Pcmpeqd, tmp, tmp);
+                self.asm.xmm_vector_shift_ir(Psrlw, 8, tmp);
+                self.asm.xmm_vector_shift_rr(shift, count, tmp);
+                self.asm.xmm_vector_rr(Pxor, count, count);
+                self.asm.xmm_vector_rr(Pshufb, count, tmp);
+                self.asm.xmm_vector_rr(Pand, tmp, dst.into());
+            }
+            (ShiftKind::ShrS, I8x16) => {
+                let tmp = tmp.expect("temporary register");
+                self.asm.xmm_mov_rr(dst.into(), tmp, OperandSize::S128);
+                self.asm.xmm_vector_rr(Punpcklbw, tmp, tmp);
+                self.asm.xmm_vector_rr(Punpckhbw, dst.into(), dst.into());
+                self.asm.xmm_vector_shift_rr(Psraw, count, tmp);
+                self.asm.xmm_vector_shift_rr(Psraw, count, dst.into());
+                self.asm.xmm_vector_rr(Packsswb, dst.into(), tmp);
+                self.asm.xmm_mov_rr(tmp, dst.into(), OperandSize::S128);
+            }
+            (ShiftKind::ShrS, I64x2) => {
+                // There's no 64-bit arithmetic shift; shift logically and
+                // sign extend with `(x ^ m) - m`, in which `m` is the sign
+                // bit shifted by the same amount.
+                let tmp = tmp.expect("temporary register");
+                let sign = self.v128_splat_constant(&0x8000000000000000u64.to_le_bytes());
+                self.asm.xmm_vector_shift_rr(Psrlq, count, dst.into());
+                self.asm
+                    .xmm_mov_mr(&sign, tmp, OperandSize::S128, MemFlags::trusted());
+                self.asm.xmm_vector_shift_rr(Psrlq, count, tmp);
+                self.asm.xmm_vector_rr(Pxor, tmp, dst.into());
+                self.asm.xmm_vector_rr(Psubq, tmp, dst.into());
+            }
+            _ => {
+                let opcode = match (kind, lanes) {
+                    (ShiftKind::Shl, I16x8) => Psllw,
+                    (ShiftKind::Shl, I32x4) => Pslld,
+                    (ShiftKind::Shl, I64x2) => Psllq,
+                    (ShiftKind::ShrU, I16x8) => Psrlw,
+                    (ShiftKind::ShrU, I32x4) => Psrld,
+                    (ShiftKind::ShrU, I64x2) => Psrlq,
+                    (ShiftKind::ShrS, I16x8) => Psraw,
+                    (ShiftKind::ShrS, I32x4) => Psrad,
+                    _ => unreachable!(),
+                };
+                self.asm.xmm_vector_shift_rr(opcode, count, dst.into());
+            }
+        }
+
+        if let Some(tmp) = tmp {
+            context.free_reg(tmp);
+        }
+        context.stack.push(dst.into());
+        Ok(())
+    }
+
+    fn v128_any_true(&mut self, src: Reg, dst: Reg) -> Result<()> {
+        self.asm.ptest(src, src);
+        self.asm.setcc(CmpKind::Ne, dst);
+        Ok(())
+    }
+
+    fn v128_all_true(&mut self, src: Reg, dst: Reg, lanes: V128Lanes) -> Result<()> {
+        let eq = match lanes {
+            V128Lanes::I8x16 => SseOpcode::Pcmpeqb,
+            V128Lanes::I16x8 => SseOpcode::Pcmpeqw,
+            V128Lanes::I32x4 => SseOpcode::Pcmpeqd,
+            V128Lanes::I64x2 => SseOpcode::Pcmpeqq,
+            _ => unreachable!(),
+        };
+        // Set the lanes equal to zero and check that none is set.
+        let scratch = regs::scratch_xmm();
+        self.asm.xmm_vector_rr(SseOpcode::Pxor, scratch, scratch);
+        self.asm.xmm_vector_rr(eq, src, scratch);
+        self.asm.ptest(scratch, scratch);
+        self.asm.setcc(CmpKind::Eq, dst);
+        Ok(())
+    }
+
+    fn v128_bitmask(&mut self, src: Reg, dst: Reg, lanes: V128Lanes) -> Result<()> {
+        match lanes {
+            V128Lanes::I8x16 => self.asm.xmm_movmsk(SseOpcode::Pmovmskb, src, dst),
+            V128Lanes::I16x8 => {
+                // Narrow the lanes to bytes, preserving their sign, and
+                // discard the duplicated high half of the mask.
+                let scratch = regs::scratch_xmm();
+                self.asm.xmm_mov_rr(src, scratch, OperandSize::S128);
+                self.asm
+                    .xmm_vector_rr(SseOpcode::Packsswb, scratch, scratch);
+                self.asm.xmm_movmsk(SseOpcode::Pmovmskb, scratch, dst);
+                self.asm.shift_ir(8, dst, ShiftKind::ShrU, OperandSize::S32);
+            }
+            V128Lanes::I32x4 => self.asm.xmm_movmsk(SseOpcode::Movmskps, src, dst),
+            V128Lanes::I64x2 => self.asm.xmm_movmsk(SseOpcode::Movmskpd, src, dst),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn wrap(&mut self, src: Reg, dst: Reg) {
+        // 32-bit moves clear the upper 32 bits of the destination register.
+        self.asm.mov_rr(src, dst, OperandSize::S32);
+    }
+
+    fn extend(&mut self, src: Reg, dst: Reg, kind: ExtendKind) {
+        if kind.signed() {
+            self.asm.movsx_rr(src, dst, kind);
+        } else {
+            self.asm.movzx_rr(src, dst, kind);
+        }
+    }
+
+    fn signed_convert(&mut self, src: Reg, dst: Reg, src_size: OperandSize, dst_size: OperandSize) {
+        self.asm.cvt_sint_to_float(src, dst, src_size, dst_size);
+    }
+
+    fn unsigned_convert(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_gpr: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+    ) {
+        if src_size == OperandSize::S32 {
+            // Unsigned 32-bit integers always fit in a signed 64-bit integer,
+            // so zero extend the source and perform a signed conversion.
+            self.extend(src, src, ExtendKind::I64ExtendI32U);
+            self.asm
+                .cvt_sint_to_float(src, dst, OperandSize::S64, dst_size);
+        } else {
+            self.asm
+                .cvt_uint64_to_float_seq(src, dst, regs::scratch(), tmp_gpr, dst_size);
+        }
+    }
+
+    fn signed_truncate(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        kind: TruncKind,
+    ) {
+        self.asm.cvt_float_to_sint_seq(
+            src,
+            dst,
+            regs::scratch(),
+            regs::scratch_xmm(),
+            src_size,
+            dst_size,
+            kind == TruncKind::Saturating,
+        );
+    }
+
+    fn unsigned_truncate(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_fpr: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        kind: TruncKind,
+    ) {
+        self.asm.cvt_float_to_uint_seq(
+            src,
+            dst,
+            regs::scratch(),
+            regs::scratch_xmm(),
+            tmp_fpr,
+            src_size,
+            dst_size,
+            kind == TruncKind::Saturating,
+        );
+    }
+
+    fn reinterpret_float_as_int(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        self.asm.xmm_to_gpr(src, dst, size);
+    }
+
+    fn reinterpret_int_as_float(&mut self, src: Reg, dst: Reg, size: OperandSize) {
+        self.asm.gpr_to_xmm(src, dst, size);
+    }
+
+    fn demote(&mut self, src: Reg, dst: Reg) {
+        self.asm
+            .cvt_float_to_float(src, dst, OperandSize::S64, OperandSize::S32);
+    }
+
+    fn promote(&mut self, src: Reg, dst: Reg) {
+        self.asm
+            .cvt_float_to_float(src, dst, OperandSize::S32, OperandSize::S64);
+    }
 
     fn get_label(&mut self) -> MachLabel {
         let buffer = self.asm.buffer_mut();
@@ -818,6 +1797,10 @@ impl Masm for MacroAssembler {
         self.asm.trap(TrapCode::UnreachableCodeReached)
     }
 
+    fn trap(&mut self, code: TrapCode) {
+        self.asm.trap(code);
+    }
+
     fn trapif(&mut self, cc: CmpKind, code: TrapCode) {
         self.asm.trapif(cc, code);
     }
@@ -885,6 +1868,128 @@ impl MacroAssembler {
         }
     }
 
+    /// Adds a constant made of the given lane value, replicated to fill
+    /// 128 bits, to the constant pool.
+    fn v128_splat_constant(&mut self, lane: &[u8]) -> Address {
+        let bytes: Vec<u8> = lane.iter().copied().cycle().take(16).collect();
+        self.asm.add_constant(&bytes)
+    }
+
+    /// Bitwise complement of a vector register.
+    fn v128_not(&mut self, dst: Reg) {
+        let scratch = regs::scratch_xmm();
+        self.asm.xmm_vector_rr(SseOpcode::Pcmpeqd, scratch, scratch);
+        self.asm.xmm_vector_rr(SseOpcode::Pxor, scratch, dst);
+    }
+
+    /// Sign or zero extend the lanes in the low or high half of `src` into
+    /// the given, wider, `lanes` of `dst`.
+    fn v128_extend(&mut self, src: Reg, dst: Reg, lanes: V128Lanes, signed: bool, high: bool) {
+        use SseOpcode::*;
+        let opcode = match (lanes, signed) {
+            (V128Lanes::I16x8, true) => Pmovsxbw,
+            (V128Lanes::I16x8, false) => Pmovzxbw,
+            (V128Lanes::I32x4, true) => Pmovsxwd,
+            (V128Lanes::I32x4, false) => Pmovzxwd,
+            (V128Lanes::I64x2, true) => Pmovsxdq,
+            (V128Lanes::I64x2, false) => Pmovzxdq,
+            _ => unreachable!(),
+        };
+
+        if high {
+            // Move the high half into the low half first.
+            self.asm.xmm_vector_unary_rr_imm(Pshufd, src, dst, 0xee);
+            self.asm.xmm_vector_unary_rr(opcode, dst, dst);
+        } else {
+            self.asm.xmm_vector_unary_rr(opcode, src, dst);
+        }
+    }
+
+    /// 64-bit lane multiplication, composed from 32-bit multiplications:
+    /// `lo(a) * lo(b) + ((hi(a) * lo(b) + lo(a) * hi(b)) << 32)`.
+    fn v128_i64x2_mul(&mut self, context: &mut CodeGenContext, dst: Reg, rhs: Reg) {
+        use SseOpcode::*;
+        let scratch = regs::scratch_xmm();
+        let tmp = context.reg_for_class(RegClass::Float, self);
+
+        self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+        self.asm.xmm_vector_shift_ir(Psrlq, 32, scratch);
+        self.asm.xmm_vector_rr(Pmuludq, rhs, scratch);
+        self.asm.xmm_mov_rr(rhs, tmp, OperandSize::S128);
+        self.asm.xmm_vector_shift_ir(Psrlq, 32, tmp);
+        self.asm.xmm_vector_rr(Pmuludq, dst, tmp);
+        self.asm.xmm_vector_rr(Paddq, tmp, scratch);
+        self.asm.xmm_vector_shift_ir(Psllq, 32, scratch);
+        self.asm.xmm_vector_rr(Pmuludq, rhs, dst);
+        self.asm.xmm_vector_rr(Paddq, scratch, dst);
+
+        context.free_reg(tmp);
+    }
+
+    /// Floating point lane minimum. `minps` and `minpd` return the second
+    /// operand if any of the operands is NaN and don't order -0.0 and
+    /// 0.0; computing the minimum in both directions and combining the
+    /// results gives the semantics required by WebAssembly.
+    fn v128_float_min(&mut self, dst: Reg, rhs: Reg, lanes: V128Lanes) {
+        use SseOpcode::*;
+        let (min, or, cmp, shift, amount, andn) = match lanes {
+            V128Lanes::F32x4 => (Minps, Orps, Cmpps, Psrld, 10, Andnps),
+            V128Lanes::F64x2 => (Minpd, Orpd, Cmppd, Psrlq, 13, Andnpd),
+            _ => unreachable!(),
+        };
+        let scratch = regs::scratch_xmm();
+
+        self.asm.xmm_mov_rr(rhs, scratch, OperandSize::S128);
+        self.asm.xmm_vector_rr(min, dst, scratch);
+        self.asm.xmm_vector_rr(min, rhs, dst);
+        // Propagate -0.0 and NaNs.
+        self.asm.xmm_vector_rr(or, scratch, dst);
+        self.asm
+            .xmm_vector_rr_imm(cmp, dst, scratch, 3, OperandSize::S32);
+        self.asm.xmm_vector_rr(or, scratch, dst);
+        // Canonicalize the NaNs by clearing their payload.
+        self.asm.xmm_vector_shift_ir(shift, amount, scratch);
+        self.asm.xmm_vector_rr(andn, dst, scratch);
+        self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+    }
+
+    /// Floating point lane maximum; see [`Self::v128_float_min`].
+    fn v128_float_max(
+        &mut self,
+        context: &mut CodeGenContext,
+        dst: Reg,
+        rhs: Reg,
+        lanes: V128Lanes,
+    ) {
+        use SseOpcode::*;
+        let (max, xor, or, sub, cmp, shift, amount, andn) = match lanes {
+            V128Lanes::F32x4 => (Maxps, Xorps, Orps, Subps, Cmpps, Psrld, 10, Andnps),
+            V128Lanes::F64x2 => (Maxpd, Xorpd, Orpd, Subpd, Cmppd, Psrlq, 13, Andnpd),
+            _ => unreachable!(),
+        };
+        let scratch = regs::scratch_xmm();
+        let tmp = context.reg_for_class(RegClass::Float, self);
+
+        self.asm.xmm_mov_rr(rhs, tmp, OperandSize::S128);
+        self.asm.xmm_vector_rr(max, dst, tmp);
+        self.asm.xmm_vector_rr(max, rhs, dst);
+        // The lanes in which both results differ hold NaNs or signed
+        // zeroes.
+        self.asm.xmm_vector_rr(xor, dst, tmp);
+        self.asm.xmm_vector_rr(or, tmp, dst);
+        self.asm.xmm_mov_rr(dst, scratch, OperandSize::S128);
+        // Resolve the signed zeroes.
+        self.asm.xmm_vector_rr(sub, tmp, dst);
+        // Canonicalize the NaNs by clearing their payload.
+        self.asm
+            .xmm_vector_rr_imm(cmp, scratch, scratch, 3, OperandSize::S32);
+        self.asm.xmm_vector_shift_ir(shift, amount, scratch);
+        self.asm.xmm_vector_rr(andn, dst, scratch);
+        self.asm.xmm_mov_rr(scratch, dst, OperandSize::S128);
+
+        context.free_reg(tmp);
+    }
+
     fn handle_invalid_operand_combination<T>(src: impl Into<RegImm>, dst: impl Into<RegImm>) -> T {
         panic!(
             "Invalid operand combination; src={:?}, dst={:?}",
@@ -13,13 +13,15 @@ use crate::{
     isa::{Builder, TargetIsa},
     regset::RegBitSet,
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
 use cranelift_codegen::settings::{self, Flags};
 use cranelift_codegen::{isa::x64::settings as x64_settings, Final, MachBufferFinalized};
 use cranelift_codegen::{MachTextSectionBuilder, TextSectionBuilder};
 use target_lexicon::Triple;
 use wasmparser::{FuncValidator, FunctionBody, ValidatorResources};
-use wasmtime_environ::{ModuleTranslation, ModuleTypes, VMOffsets, WasmFuncType};
+use wasmtime_environ::{
+    DefinedFuncIndex, ModuleTranslation, ModuleTypes, Tunables, VMOffsets, WasmFuncType,
+};
 
 use self::regs::{ALL_FPR, ALL_GPR, MAX_FPR, MAX_GPR, NON_ALLOCATABLE_FPR, NON_ALLOCATABLE_GPR};
 
@@ -86,12 +88,32 @@ impl TargetIsa for X64 {
         self.isa_flags.iter().collect()
     }
 
+    fn check_simd_support(&self) -> Result<()> {
+        // The lowering of `v128` operators relies on instructions up to
+        // SSE4.2, e.g. `pshufb` from SSSE3, `pmulld` and `ptest` from SSE4.1
+        // and `pcmpgtq` from SSE4.2.
+        let required = [
+            ("sse3", self.isa_flags.has_sse3()),
+            ("ssse3", self.isa_flags.has_ssse3()),
+            ("sse41", self.isa_flags.has_sse41()),
+            ("sse42", self.isa_flags.has_sse42()),
+        ];
+        for (feature, enabled) in required {
+            if !enabled {
+                bail!("SIMD support in Winch on x64 requires the `{feature}` target feature");
+            }
+        }
+        Ok(())
+    }
+
     fn compile_function(
         &self,
+        index: DefinedFuncIndex,
         sig: &WasmFuncType,
         body: &FunctionBody,
         translation: &ModuleTranslation,
         types: &ModuleTypes,
+        tunables: &Tunables,
         builtins: &mut BuiltinFunctions,
         validator: &mut FuncValidator<ValidatorResources>,
     ) -> Result<MachBufferFinalized<Final>> {
@@ -108,6 +130,7 @@ impl TargetIsa for X64 {
         let abi_sig = abi::X64ABI::sig(sig, &CallingConvention::Default);
 
         let defined_locals = DefinedLocals::new(translation, &mut body, validator)?;
+        self.check_v128_types(sig, defined_locals.defined_locals.iter().map(|l| &l.ty))?;
         let frame = Frame::new::<abi::X64ABI>(&abi_sig, &defined_locals)?;
         let gpr = RegBitSet::int(
             ALL_GPR.into(),
@@ -121,7 +144,7 @@ impl TargetIsa for X64 {
         );
 
         let regalloc = RegAlloc::from(gpr, fpr);
-        let env = FuncEnv::new(&vmoffsets, translation, types);
+        let env = FuncEnv::new(&vmoffsets, translation, types, tunables, index, self);
         let codegen_context = CodeGenContext::new(regalloc, stack, frame, builtins, &vmoffsets);
         let mut codegen = CodeGen::new(&mut masm, codegen_context, env, abi_sig);
 
@@ -146,6 +169,8 @@ impl TargetIsa for X64 {
     ) -> Result<MachBufferFinalized<Final>> {
         use TrampolineKind::*;
 
+        self.check_v128_types(ty, [])?;
+
         let mut masm = X64Masm::new(
             self.pointer_bytes(),
             self.shared_flags.clone(),
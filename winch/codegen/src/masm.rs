@@ -1,6 +1,7 @@
 use crate::abi::{self, align_to, LocalSlot};
 use crate::codegen::{CodeGenContext, TableData};
 use crate::isa::reg::Reg;
+use anyhow::Result;
 use cranelift_codegen::{ir::LibCall, Final, MachBufferFinalized, MachLabel};
 use std::{fmt::Debug, ops::Range};
 use wasmtime_environ::PtrSize;
@@ -62,6 +63,7 @@ pub(crate) enum CmpKind {
 /// Kinds of shifts in WebAssembly.The [`masm`] implementation for each ISA is
 /// responsible for emitting the correct sequence of instructions when
 /// lowering to machine code.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum ShiftKind {
     /// Left shift.
     Shl,
@@ -75,9 +77,229 @@ pub(crate) enum ShiftKind {
     Rotr,
 }
 
+/// Kinds of integer extension in WebAssembly. Each variant names the
+/// destination type and the width of the source operand; the signedness
+/// determines whether the extension is performed with zeroes or with
+/// the sign bit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ExtendKind {
+    /// Sign extends i8 to i32.
+    I32Extend8S,
+    /// Zero extends i8 to i32.
+    I32Extend8U,
+    /// Sign extends i16 to i32.
+    I32Extend16S,
+    /// Zero extends i16 to i32.
+    I32Extend16U,
+    /// Sign extends i8 to i64.
+    I64Extend8S,
+    /// Zero extends i8 to i64.
+    I64Extend8U,
+    /// Sign extends i16 to i64.
+    I64Extend16S,
+    /// Zero extends i16 to i64.
+    I64Extend16U,
+    /// Sign extends i32 to i64.
+    I64ExtendI32S,
+    /// Zero extends i32 to i64.
+    I64ExtendI32U,
+}
+
+impl ExtendKind {
+    /// Returns true if the extension is signed.
+    pub fn signed(&self) -> bool {
+        use ExtendKind::*;
+        matches!(
+            self,
+            I32Extend8S | I32Extend16S | I64Extend8S | I64Extend16S | I64ExtendI32S
+        )
+    }
+}
+
+/// Kinds of float to integer truncation in WebAssembly.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum TruncKind {
+    /// Traps if the source value is NaN or if it can't be represented
+    /// in the destination type.
+    Checked,
+    /// Saturates the result to the bounds of the destination type; NaN
+    /// values are converted to zero.
+    Saturating,
+}
+
+/// Kinds of floating point comparison in WebAssembly.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum FloatCmpKind {
+    /// Equal.
+    Eq,
+    /// Not equal.
+    Ne,
+    /// Less than.
+    Lt,
+    /// Greater than.
+    Gt,
+    /// Less than or equal.
+    Le,
+    /// Greater than or equal.
+    Ge,
+}
+
+/// The lane interpretation of a `v128` value.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum V128Lanes {
+    /// Sixteen 8-bit integer lanes.
+    I8x16,
+    /// Eight 16-bit integer lanes.
+    I16x8,
+    /// Four 32-bit integer lanes.
+    I32x4,
+    /// Two 64-bit integer lanes.
+    I64x2,
+    /// Four single precision floating point lanes.
+    F32x4,
+    /// Two double precision floating point lanes.
+    F64x2,
+}
+
+impl V128Lanes {
+    /// The size of each lane.
+    pub fn lane_size(&self) -> OperandSize {
+        use V128Lanes::*;
+        match self {
+            I8x16 => OperandSize::S8,
+            I16x8 => OperandSize::S16,
+            I32x4 | F32x4 => OperandSize::S32,
+            I64x2 | F64x2 => OperandSize::S64,
+        }
+    }
+}
+
+/// Binary `v128` operations in WebAssembly. Operations that depend on the
+/// lane interpretation carry the lanes of their result; the narrowing and
+/// extended multiplication variants therefore name the wider or narrower
+/// lanes produced by the operation, not the lanes of their operands.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum V128BinaryOp {
+    /// Bitwise and.
+    And,
+    /// Bitwise and with the complement of the second operand.
+    AndNot,
+    /// Bitwise or.
+    Or,
+    /// Bitwise exclusive or.
+    Xor,
+    /// Wrapping integer or floating point addition.
+    Add(V128Lanes),
+    /// Signed saturating integer addition.
+    AddSatS(V128Lanes),
+    /// Unsigned saturating integer addition.
+    AddSatU(V128Lanes),
+    /// Wrapping integer or floating point subtraction.
+    Sub(V128Lanes),
+    /// Signed saturating integer subtraction.
+    SubSatS(V128Lanes),
+    /// Unsigned saturating integer subtraction.
+    SubSatU(V128Lanes),
+    /// Wrapping integer or floating point multiplication.
+    Mul(V128Lanes),
+    /// Floating point division.
+    Div(V128Lanes),
+    /// Signed integer minimum.
+    MinS(V128Lanes),
+    /// Unsigned integer minimum.
+    MinU(V128Lanes),
+    /// Signed integer maximum.
+    MaxS(V128Lanes),
+    /// Unsigned integer maximum.
+    MaxU(V128Lanes),
+    /// Unsigned rounding average.
+    AvgrU(V128Lanes),
+    /// Floating point minimum, propagating NaNs.
+    Min(V128Lanes),
+    /// Floating point maximum, propagating NaNs.
+    Max(V128Lanes),
+    /// Floating point pseudo-minimum: `rhs < lhs ? rhs : lhs`.
+    PMin(V128Lanes),
+    /// Floating point pseudo-maximum: `lhs < rhs ? rhs : lhs`.
+    PMax(V128Lanes),
+    /// Signed saturating narrowing of two vectors into the given lanes.
+    NarrowS(V128Lanes),
+    /// Unsigned saturating narrowing of two vectors into the given lanes.
+    NarrowU(V128Lanes),
+    /// Signed extended multiplication of the low half of the operands.
+    ExtMulLowS(V128Lanes),
+    /// Signed extended multiplication of the high half of the operands.
+    ExtMulHighS(V128Lanes),
+    /// Unsigned extended multiplication of the low half of the operands.
+    ExtMulLowU(V128Lanes),
+    /// Unsigned extended multiplication of the high half of the operands.
+    ExtMulHighU(V128Lanes),
+    /// `i16x8.q15mulr_sat_s`.
+    Q15MulrSatS,
+    /// `i32x4.dot_i16x8_s`.
+    DotI16x8S,
+    /// `i8x16.swizzle`.
+    Swizzle,
+}
+
+/// Unary `v128` operations in WebAssembly. Similar to [`V128BinaryOp`],
+/// the lanes carried by the extension variants are the lanes of the
+/// result.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum V128UnaryOp {
+    /// Bitwise not.
+    Not,
+    /// Integer or floating point absolute value.
+    Abs(V128Lanes),
+    /// Integer or floating point negation.
+    Neg(V128Lanes),
+    /// `i8x16.popcnt`.
+    Popcnt,
+    /// Floating point square root.
+    Sqrt(V128Lanes),
+    /// Floating point rounding.
+    Round(V128Lanes, RoundingMode),
+    /// Sign extension of the low half of the lanes.
+    ExtendLowS(V128Lanes),
+    /// Sign extension of the high half of the lanes.
+    ExtendHighS(V128Lanes),
+    /// Zero extension of the low half of the lanes.
+    ExtendLowU(V128Lanes),
+    /// Zero extension of the high half of the lanes.
+    ExtendHighU(V128Lanes),
+    /// Signed pairwise addition into the given lanes.
+    ExtAddPairwiseS(V128Lanes),
+    /// Unsigned pairwise addition into the given lanes.
+    ExtAddPairwiseU(V128Lanes),
+    /// `f32x4.convert_i32x4_s`.
+    F32x4ConvertI32x4S,
+    /// `f32x4.convert_i32x4_u`.
+    F32x4ConvertI32x4U,
+    /// `f64x2.convert_low_i32x4_s`.
+    F64x2ConvertLowI32x4S,
+    /// `f64x2.convert_low_i32x4_u`.
+    F64x2ConvertLowI32x4U,
+    /// `i32x4.trunc_sat_f32x4_s`.
+    I32x4TruncSatF32x4S,
+    /// `i32x4.trunc_sat_f32x4_u`.
+    I32x4TruncSatF32x4U,
+    /// `i32x4.trunc_sat_f64x2_s_zero`.
+    I32x4TruncSatF64x2SZero,
+    /// `i32x4.trunc_sat_f64x2_u_zero`.
+    I32x4TruncSatF64x2UZero,
+    /// `f32x4.demote_f64x2_zero`.
+    F32x4DemoteF64x2Zero,
+    /// `f64x2.promote_low_f32x4`.
+    F64x2PromoteLowF32x4,
+}
+
 /// Operand size, in bits.
 #[derive(Copy, Debug, Clone, Eq, PartialEq)]
 pub(crate) enum OperandSize {
+    /// 8 bits.
+    S8,
+    /// 16 bits.
+    S16,
     /// 32 bits.
     S32,
     /// 64 bits.
@@ -90,6 +312,8 @@ impl OperandSize {
     /// The number of bits in the operand.
     pub fn num_bits(&self) -> i32 {
         match self {
+            OperandSize::S8 => 8,
+            OperandSize::S16 => 16,
             OperandSize::S32 => 32,
             OperandSize::S64 => 64,
             OperandSize::S128 => 128,
@@ -99,6 +323,8 @@ impl OperandSize {
     /// The number of bytes in the operand.
     pub fn bytes(&self) -> u32 {
         match self {
+            Self::S8 => 1,
+            Self::S16 => 2,
             Self::S32 => 4,
             Self::S64 => 8,
             Self::S128 => 16,
@@ -108,6 +334,8 @@ impl OperandSize {
     /// The binary logarithm of the number of bits in the operand.
     pub fn log2(&self) -> u8 {
         match self {
+            OperandSize::S8 => 3,
+            OperandSize::S16 => 4,
             OperandSize::S32 => 5,
             OperandSize::S64 => 6,
             OperandSize::S128 => 7,
@@ -118,6 +346,8 @@ impl OperandSize {
     pub fn from_bytes(bytes: u8) -> Self {
         use OperandSize::*;
         match bytes {
+            1 => S8,
+            2 => S16,
             4 => S32,
             8 => S64,
             16 => S128,
@@ -146,6 +376,8 @@ pub(crate) enum Imm {
     F32(u32),
     /// F64 immediate.
     F64(u64),
+    /// V128 immediate.
+    V128(i128),
 }
 
 impl Imm {
@@ -173,6 +405,11 @@ impl Imm {
         Self::F64(bits)
     }
 
+    /// Create a new V128 immediate.
+    pub fn v128(bits: i128) -> Self {
+        Self::V128(bits)
+    }
+
     /// Convert the immediate to i32, if possible.
     pub fn to_i32(&self) -> Option<i32> {
         match self {
@@ -239,6 +476,11 @@ impl RegImm {
     pub fn f64(bits: u64) -> Self {
         RegImm::Imm(Imm::f64(bits))
     }
+
+    /// V128 immediate.
+    pub fn v128(bits: i128) -> Self {
+        RegImm::Imm(Imm::v128(bits))
+    }
 }
 
 impl From<Reg> for RegImm {
@@ -247,6 +489,7 @@ impl From<Reg> for RegImm {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum RoundingMode {
     Nearest,
     Up,
@@ -350,6 +593,28 @@ pub(crate) trait MacroAssembler {
     /// to the pointer size of the target.
     fn load_ptr(&mut self, src: Self::Address, dst: Reg);
 
+    /// Load the effective address of the given memory location into the
+    /// destination register.
+    fn load_addr(&mut self, src: Self::Address, dst: Reg, size: OperandSize);
+
+    /// Perform a WebAssembly load.
+    /// A WebAssembly load introduces several additional requirements compared
+    /// to a stack load: the load might trap if the address is out of bounds
+    /// and, for loads narrower than the destination type, the value must be
+    /// extended according to the given [`ExtendKind`].
+    fn wasm_load(
+        &mut self,
+        src: Self::Address,
+        dst: Reg,
+        size: OperandSize,
+        kind: Option<ExtendKind>,
+    );
+
+    /// Perform a WebAssembly store.
+    /// Similar to [`Self::wasm_load`], the store might trap if the address
+    /// is out of bounds.
+    fn wasm_store(&mut self, src: Reg, dst: Self::Address, size: OperandSize);
+
     /// Alias for `MacroAssembler::store` with the operand size corresponding
     /// to the pointer size of the target.
     fn store_ptr(&mut self, src: Reg, dst: Self::Address);
@@ -366,6 +631,10 @@ pub(crate) trait MacroAssembler {
     /// Perform add operation.
     fn add(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize);
 
+    /// Perform a checked unsigned integer addition, emitting the provided
+    /// trap if the addition overflows.
+    fn checked_uadd(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize, trap: TrapCode);
+
     /// Perform subtraction operation.
     fn sub(&mut self, dst: Reg, lhs: Reg, rhs: RegImm, size: OperandSize);
 
@@ -448,6 +717,73 @@ pub(crate) trait MacroAssembler {
     /// This function will potentially emit a series of instructions.
     fn cmp_with_set(&mut self, src: RegImm, dst: Reg, kind: CmpKind, size: OperandSize);
 
+    /// Take the low 32 bits of the 64-bit source register and put the
+    /// result in dst.
+    fn wrap(&mut self, src: Reg, dst: Reg);
+
+    /// Zero or sign extend the source register, according to the given
+    /// [`ExtendKind`], and put the result in dst.
+    fn extend(&mut self, src: Reg, dst: Reg, kind: ExtendKind);
+
+    /// Convert the signed integer in src to a floating point value and put
+    /// the result in dst.
+    fn signed_convert(&mut self, src: Reg, dst: Reg, src_size: OperandSize, dst_size: OperandSize);
+
+    /// Convert the unsigned integer in src to a floating point value and
+    /// put the result in dst. The temporary general purpose register
+    /// might be used when lowering the conversion to a sequence of
+    /// instructions.
+    fn unsigned_convert(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_gpr: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+    );
+
+    /// Truncate the floating point value in src to a signed integer and
+    /// put the result in dst. Checked truncations trap if the value is NaN
+    /// or if it's out of the range of the destination type.
+    fn signed_truncate(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        kind: TruncKind,
+    );
+
+    /// Truncate the floating point value in src to an unsigned integer
+    /// and put the result in dst. The temporary floating point register
+    /// might be used when lowering the truncation to a sequence of
+    /// instructions.
+    fn unsigned_truncate(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        tmp_fpr: Reg,
+        src_size: OperandSize,
+        dst_size: OperandSize,
+        kind: TruncKind,
+    );
+
+    /// Reinterpret the bits of the floating point value in src as an
+    /// integer and put the result in dst.
+    fn reinterpret_float_as_int(&mut self, src: Reg, dst: Reg, size: OperandSize);
+
+    /// Reinterpret the bits of the integer in src as a floating point
+    /// value and put the result in dst.
+    fn reinterpret_int_as_float(&mut self, src: Reg, dst: Reg, size: OperandSize);
+
+    /// Convert the 64-bit floating point value in src to a 32-bit
+    /// floating point value and put the result in dst.
+    fn demote(&mut self, src: Reg, dst: Reg);
+
+    /// Convert the 32-bit floating point value in src to a 64-bit
+    /// floating point value and put the result in dst.
+    fn promote(&mut self, src: Reg, dst: Reg);
+
     /// Count the number of leading zeroes in src and put the result in dst.
     /// In x64, this will emit multiple instructions if the `has_lzcnt` flag is
     /// false.
@@ -458,6 +794,89 @@ pub(crate) trait MacroAssembler {
     /// false.
     fn ctz(&mut self, src: Reg, dst: Reg, size: OperandSize);
 
+    /// Replicate the scalar in `src` into every lane of `dst`.
+    fn v128_splat(&mut self, src: Reg, dst: Reg, lanes: V128Lanes) -> Result<()>;
+
+    /// Extract the given lane of `src` into `dst`. Integer lanes narrower
+    /// than 32 bits are zero extended, unless `signed` is true, in which
+    /// case they are sign extended.
+    fn v128_extract_lane(
+        &mut self,
+        src: Reg,
+        dst: Reg,
+        lane: u8,
+        lanes: V128Lanes,
+        signed: bool,
+    ) -> Result<()>;
+
+    /// Replace the given lane of `dst` with the scalar in `src`.
+    fn v128_replace_lane(&mut self, src: Reg, dst: Reg, lane: u8, lanes: V128Lanes) -> Result<()>;
+
+    /// Select bytes from `lhs` and `rhs` according to the given byte
+    /// indices, in which indices 0 to 15 refer to `lhs` and 16 to 31
+    /// refer to `rhs`; put the result in `dst`.
+    fn v128_shuffle(&mut self, dst: Reg, lhs: Reg, rhs: Reg, indices: [u8; 16]) -> Result<()>;
+
+    /// Perform a binary `v128` operation, putting the result in `dst`.
+    fn v128_binop(
+        &mut self,
+        context: &mut CodeGenContext,
+        dst: Reg,
+        lhs: Reg,
+        rhs: Reg,
+        op: V128BinaryOp,
+    ) -> Result<()>;
+
+    /// Perform a unary `v128` operation, in place.
+    fn v128_unop(&mut self, context: &mut CodeGenContext, dst: Reg, op: V128UnaryOp) -> Result<()>;
+
+    /// Select the bits of `lhs` in which `mask` is set and the bits of
+    /// `rhs` in which `mask` is unset; put the result in `dst`.
+    fn v128_bitselect(&mut self, dst: Reg, lhs: Reg, rhs: Reg, mask: Reg) -> Result<()>;
+
+    /// Compare the integer lanes of `lhs` and `rhs`, setting each lane of
+    /// `dst` to all ones if the comparison holds or to zero otherwise.
+    fn v128_cmp(
+        &mut self,
+        dst: Reg,
+        lhs: Reg,
+        rhs: Reg,
+        kind: CmpKind,
+        lanes: V128Lanes,
+    ) -> Result<()>;
+
+    /// Compare the floating point lanes of `lhs` and `rhs`, setting each
+    /// lane of `dst` to all ones if the comparison holds or to zero
+    /// otherwise.
+    fn v128_float_cmp(
+        &mut self,
+        dst: Reg,
+        lhs: Reg,
+        rhs: Reg,
+        kind: FloatCmpKind,
+        lanes: V128Lanes,
+    ) -> Result<()>;
+
+    /// Shift each lane of a `v128` value by a scalar amount. Similar to
+    /// [`Self::shift`], the shift amount is expected at the top of the
+    /// value stack, followed by the vector value.
+    fn v128_shift(
+        &mut self,
+        context: &mut CodeGenContext,
+        kind: ShiftKind,
+        lanes: V128Lanes,
+    ) -> Result<()>;
+
+    /// Set `dst` to 1 if any bit of `src` is set, or to 0 otherwise.
+    fn v128_any_true(&mut self, src: Reg, dst: Reg) -> Result<()>;
+
+    /// Set `dst` to 1 if all the lanes of `src` are non-zero, or to 0
+    /// otherwise.
+    fn v128_all_true(&mut self, src: Reg, dst: Reg, lanes: V128Lanes) -> Result<()>;
+
+    /// Gather the most significant bit of each lane of `src` into `dst`.
+    fn v128_bitmask(&mut self, src: Reg, dst: Reg, lanes: V128Lanes) -> Result<()>;
+
     /// Push the register to the stack, returning the stack slot metadata.
     // NB
     // The stack alignment should not be assumed after any call to `push`,
@@ -547,6 +966,9 @@ pub(crate) trait MacroAssembler {
     /// Emit an unreachable code trap.
     fn unreachable(&mut self);
 
+    /// Emit an unconditional trap.
+    fn trap(&mut self, code: TrapCode);
+
     /// Traps if the condition code is met.
     fn trapif(&mut self, cc: CmpKind, code: TrapCode);
 
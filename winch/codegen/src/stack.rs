@@ -71,6 +71,8 @@ pub(crate) enum Val {
     F32(Ieee32),
     /// F64 Constant.
     F64(Ieee64),
+    /// V128 Constant.
+    V128(i128),
     /// A register value.
     Reg(TypedReg),
     /// A local slot.
@@ -124,6 +126,11 @@ impl Val {
         Self::F64(v)
     }
 
+    /// Create a new V128 constant value.
+    pub fn v128(v: i128) -> Self {
+        Self::V128(v)
+    }
+
     /// Create a new Reg value.
     pub fn reg(reg: Reg, ty: WasmType) -> Self {
         Self::Reg(TypedReg { reg, ty })
@@ -211,6 +218,7 @@ impl Val {
             Val::I64(_) => WasmType::I64,
             Val::F32(_) => WasmType::F32,
             Val::F64(_) => WasmType::F64,
+            Val::V128(_) => WasmType::V128,
             Val::Reg(r) => r.ty,
             Val::Memory(m) => m.ty,
             Val::Local(l) => l.ty,
@@ -219,7 +227,7 @@ impl Val {
 }
 
 /// The shadow stack used for compilation.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub(crate) struct Stack {
     inner: VecDeque<Val>,
 }
@@ -10,7 +10,7 @@
 // and VM context type should be derived from the ABI's pointer size. This is
 // going to be relevant once 32-bit architectures are supported.
 use crate::{
-    abi::{ABIArg, ABIParams, ABIResult, ABISig, ABI},
+    abi::{align_to, ABIArg, ABIParams, ABISig, ABI},
     isa::CallingConvention,
     masm::{CalleeKind, MacroAssembler, OperandSize, RegImm},
     reg::Reg,
@@ -111,8 +111,20 @@ where
 
         let val_ptr_offset = offsets[2];
 
+        // The return area, if any, is placed right after the stack
+        // arguments.
+        let ret_area_offset = align_to(
+            wasm_sig.stack_bytes,
+            <M::ABI as ABI>::call_stack_align().into(),
+        );
+        let arg_stack_space = if wasm_sig.result.has_stack_results() {
+            ret_area_offset + wasm_sig.result.ret_area_size()
+        } else {
+            wasm_sig.stack_bytes
+        };
+
         // Call the function that was passed into the trampoline.
-        let allocated_stack = self.masm.call(wasm_sig.stack_bytes, |masm| {
+        let allocated_stack = self.masm.call(arg_stack_space, |masm| {
             // Save the SP when entering Wasm.
             // TODO: Once Winch supports comparison operators,
             // check that the caller VM context is what we expect.
@@ -128,11 +140,12 @@ where
             // register for argument assignment.
             masm.mov(*val_ptr, self.scratch_reg.into(), OperandSize::S64);
             Self::assign_args_from_array(masm, &wasm_sig, self.scratch_reg, self.alloc_scratch_reg);
+            if let Some(retptr) = wasm_sig.retptr() {
+                Self::assign_retptr(masm, retptr, ret_area_offset, self.alloc_scratch_reg);
+            }
             CalleeKind::Direct(callee_index.as_u32())
         });
 
-        self.masm.free_stack(allocated_stack);
-
         // Move the val ptr back into the scratch register so we can
         // load the return values.
         self.masm.load(
@@ -141,16 +154,17 @@ where
             OperandSize::S64,
         );
 
-        // Move the return values into the value ptr.  We are only
-        // supporting a single return value at this time.
-        match wasm_sig.result {
-            ABIResult::Reg { ty, reg } => self.masm.store(
-                RegImm::reg(reg),
-                self.masm.address_at_reg(self.scratch_reg, 0),
-                ty.into(),
-            ),
-            ABIResult::Void => {}
-        }
+        // Move the return values into the value ptr, before reclaiming
+        // the stack space used by the return area.
+        Self::store_results_to_array(
+            self.masm,
+            &wasm_sig,
+            ret_area_offset,
+            self.scratch_reg,
+            self.alloc_scratch_reg,
+        );
+
+        self.masm.free_stack(allocated_stack);
 
         self.epilogue_with_callee_saved_restore(spill_size);
         Ok(())
@@ -293,6 +307,7 @@ where
                     (ABIArg::Stack { ty, offset }, ABIArg::Reg { .. }) => {
                         let spill_offset = caller_stack_offsets[offset_index];
                         let addr = masm.address_from_sp(spill_offset);
+                        let scratch = Self::scratch_for(ty, scratch);
                         masm.load(addr, scratch, (*ty).into());
 
                         let arg_addr = masm.address_at_sp(*offset);
@@ -316,6 +331,7 @@ where
                         },
                     ) => {
                         let addr = masm.address_at_reg(fp, arg_base_offset + caller_offset);
+                        let scratch = Self::scratch_for(ty, scratch);
                         masm.load(addr, scratch, (*ty).into());
 
                         let arg_addr = masm.address_at_sp(*callee_offset);
@@ -325,6 +341,16 @@ where
             )
     }
 
+    /// Returns the scratch register to use when moving a value of the
+    /// given type between stack slots: the given general purpose
+    /// scratch register, unless the type requires a vector register.
+    fn scratch_for(ty: &WasmType, scratch: Reg) -> Reg {
+        match ty {
+            WasmType::V128 => <M::ABI as ABI>::float_scratch_reg(),
+            _ => scratch,
+        }
+    }
+
     /// Get the type of the caller and callee VM contexts.
     fn callee_and_caller_vmctx_types() -> Vec<WasmType> {
         vec![WasmType::I64, WasmType::I64]
@@ -386,29 +412,92 @@ where
         // The max size a value can be when reading from the params
         // memory location.
         let value_size = mem::size_of::<u128>();
-        callee_sig.params.iter().enumerate().for_each(|(i, param)| {
-            let value_offset = (i * value_size) as u32;
-
-            match param {
-                ABIArg::Reg { reg, ty } => masm.load(
-                    masm.address_at_reg(values_reg, value_offset),
-                    *reg,
-                    (*ty).into(),
-                ),
-                ABIArg::Stack { offset, ty } => {
-                    masm.load(
+        callee_sig
+            .params_without_retptr()
+            .iter()
+            .enumerate()
+            .for_each(|(i, param)| {
+                let value_offset = (i * value_size) as u32;
+
+                match param {
+                    ABIArg::Reg { reg, ty } => masm.load(
                         masm.address_at_reg(values_reg, value_offset),
-                        scratch,
-                        (*ty).into(),
-                    );
-                    masm.store(
-                        RegImm::reg(scratch),
-                        masm.address_at_sp(*offset),
+                        *reg,
                         (*ty).into(),
-                    );
+                    ),
+                    ABIArg::Stack { offset, ty } => {
+                        let scratch = Self::scratch_for(ty, scratch);
+                        masm.load(
+                            masm.address_at_reg(values_reg, value_offset),
+                            scratch,
+                            (*ty).into(),
+                        );
+                        masm.store(
+                            RegImm::reg(scratch),
+                            masm.address_at_sp(*offset),
+                            (*ty).into(),
+                        );
+                    }
                 }
+            });
+    }
+
+    /// Assigns the address of the return area to the return area
+    /// pointer param of the callee.
+    fn assign_retptr(masm: &mut M, retptr: &ABIArg, ret_area_offset: u32, scratch: Reg) {
+        let ret_area = masm.address_at_sp(ret_area_offset);
+        match retptr {
+            ABIArg::Reg { reg, .. } => masm.load_addr(ret_area, *reg, OperandSize::S64),
+            ABIArg::Stack { offset, .. } => {
+                masm.load_addr(ret_area, scratch, OperandSize::S64);
+                masm.store(
+                    RegImm::reg(scratch),
+                    masm.address_at_sp(*offset),
+                    OperandSize::S64,
+                );
             }
-        });
+        }
+    }
+
+    /// Stores the results of the callee to the values array, using the
+    /// same layout as the params.
+    fn store_results_to_array(
+        masm: &mut M,
+        callee_sig: &ABISig,
+        ret_area_offset: u32,
+        values_reg: Reg,
+        scratch: Reg,
+    ) {
+        let value_size = mem::size_of::<u128>();
+        callee_sig
+            .result
+            .operands()
+            .iter()
+            .enumerate()
+            .for_each(|(i, result)| {
+                let value_offset = (i * value_size) as u32;
+
+                match result {
+                    ABIArg::Reg { reg, ty } => masm.store(
+                        RegImm::reg(*reg),
+                        masm.address_at_reg(values_reg, value_offset),
+                        (*ty).into(),
+                    ),
+                    ABIArg::Stack { offset, ty } => {
+                        let scratch = Self::scratch_for(ty, scratch);
+                        masm.load(
+                            masm.address_at_sp(ret_area_offset + offset),
+                            scratch,
+                            (*ty).into(),
+                        );
+                        masm.store(
+                            RegImm::reg(scratch),
+                            masm.address_at_reg(values_reg, value_offset),
+                            (*ty).into(),
+                        );
+                    }
+                }
+            });
     }
 
     fn save_last_wasm_entry_sp(
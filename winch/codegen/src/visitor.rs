@@ -6,16 +6,19 @@
 
 use crate::abi::ABI;
 use crate::codegen::{control_index, Callee, CodeGen, ControlStackFrame, FnCall};
+use crate::isa::reg::RegClass;
 use crate::masm::{
-    CmpKind, DivKind, MacroAssembler, OperandSize, RegImm, RemKind, RoundingMode, ShiftKind,
+    CmpKind, DivKind, ExtendKind, FloatCmpKind, MacroAssembler, OperandSize, RegImm, RemKind,
+    RoundingMode, ShiftKind, TruncKind, V128BinaryOp, V128Lanes, V128UnaryOp,
 };
 use crate::stack::{TypedReg, Val};
 use cranelift_codegen::ir::TrapCode;
+use cranelift_codegen::MachLabel;
 use smallvec::SmallVec;
 use wasmparser::BrTable;
-use wasmparser::{BlockType, Ieee32, Ieee64, VisitOperator};
+use wasmparser::{BlockType, Ieee32, Ieee64, MemArg, VisitOperator, V128};
 use wasmtime_environ::{
-    FuncIndex, GlobalIndex, TableIndex, TableStyle, TypeIndex, WasmHeapType, WasmType,
+    FuncIndex, GlobalIndex, MemoryIndex, TableIndex, TableStyle, TypeIndex, WasmHeapType, WasmType,
     FUNCREF_INIT_BIT,
 };
 
@@ -156,8 +159,308 @@ macro_rules! def_unsupported {
     (emit TableSet $($rest:tt)*) => {};
     (emit TableGrow $($rest:tt)*) => {};
     (emit TableSize $($rest:tt)*) => {};
+    (emit MemorySize $($rest:tt)*) => {};
+    (emit MemoryGrow $($rest:tt)*) => {};
     (emit TableFill $($rest:tt)*) => {};
     (emit ElemDrop $($rest:tt)*) => {};
+    (emit I32Load $($rest:tt)*) => {};
+    (emit I32Load8S $($rest:tt)*) => {};
+    (emit I32Load8U $($rest:tt)*) => {};
+    (emit I32Load16S $($rest:tt)*) => {};
+    (emit I32Load16U $($rest:tt)*) => {};
+    (emit I64Load $($rest:tt)*) => {};
+    (emit I64Load8S $($rest:tt)*) => {};
+    (emit I64Load8U $($rest:tt)*) => {};
+    (emit I64Load16S $($rest:tt)*) => {};
+    (emit I64Load16U $($rest:tt)*) => {};
+    (emit I64Load32S $($rest:tt)*) => {};
+    (emit I64Load32U $($rest:tt)*) => {};
+    (emit F32Load $($rest:tt)*) => {};
+    (emit F64Load $($rest:tt)*) => {};
+    (emit I32Store $($rest:tt)*) => {};
+    (emit I32Store8 $($rest:tt)*) => {};
+    (emit I32Store16 $($rest:tt)*) => {};
+    (emit I64Store $($rest:tt)*) => {};
+    (emit I64Store8 $($rest:tt)*) => {};
+    (emit I64Store16 $($rest:tt)*) => {};
+    (emit I64Store32 $($rest:tt)*) => {};
+    (emit F32Store $($rest:tt)*) => {};
+    (emit F64Store $($rest:tt)*) => {};
+    (emit I32WrapI64 $($rest:tt)*) => {};
+    (emit I64ExtendI32S $($rest:tt)*) => {};
+    (emit I64ExtendI32U $($rest:tt)*) => {};
+    (emit I32Extend8S $($rest:tt)*) => {};
+    (emit I32Extend16S $($rest:tt)*) => {};
+    (emit I64Extend8S $($rest:tt)*) => {};
+    (emit I64Extend16S $($rest:tt)*) => {};
+    (emit I64Extend32S $($rest:tt)*) => {};
+    (emit F32ConvertI32S $($rest:tt)*) => {};
+    (emit F32ConvertI32U $($rest:tt)*) => {};
+    (emit F32ConvertI64S $($rest:tt)*) => {};
+    (emit F32ConvertI64U $($rest:tt)*) => {};
+    (emit F64ConvertI32S $($rest:tt)*) => {};
+    (emit F64ConvertI32U $($rest:tt)*) => {};
+    (emit F64ConvertI64S $($rest:tt)*) => {};
+    (emit F64ConvertI64U $($rest:tt)*) => {};
+    (emit F32DemoteF64 $($rest:tt)*) => {};
+    (emit F64PromoteF32 $($rest:tt)*) => {};
+    (emit I32ReinterpretF32 $($rest:tt)*) => {};
+    (emit I64ReinterpretF64 $($rest:tt)*) => {};
+    (emit F32ReinterpretI32 $($rest:tt)*) => {};
+    (emit F64ReinterpretI64 $($rest:tt)*) => {};
+    (emit I32TruncF32S $($rest:tt)*) => {};
+    (emit I32TruncSatF32S $($rest:tt)*) => {};
+    (emit I32TruncF32U $($rest:tt)*) => {};
+    (emit I32TruncSatF32U $($rest:tt)*) => {};
+    (emit I32TruncF64S $($rest:tt)*) => {};
+    (emit I32TruncSatF64S $($rest:tt)*) => {};
+    (emit I32TruncF64U $($rest:tt)*) => {};
+    (emit I32TruncSatF64U $($rest:tt)*) => {};
+    (emit I64TruncF32S $($rest:tt)*) => {};
+    (emit I64TruncSatF32S $($rest:tt)*) => {};
+    (emit I64TruncF32U $($rest:tt)*) => {};
+    (emit I64TruncSatF32U $($rest:tt)*) => {};
+    (emit I64TruncF64S $($rest:tt)*) => {};
+    (emit I64TruncSatF64S $($rest:tt)*) => {};
+    (emit I64TruncF64U $($rest:tt)*) => {};
+    (emit I64TruncSatF64U $($rest:tt)*) => {};
+
+    (emit V128Load $($rest:tt)*) => {};
+    (emit V128Load8x8S $($rest:tt)*) => {};
+    (emit V128Load8x8U $($rest:tt)*) => {};
+    (emit V128Load16x4S $($rest:tt)*) => {};
+    (emit V128Load16x4U $($rest:tt)*) => {};
+    (emit V128Load32x2S $($rest:tt)*) => {};
+    (emit V128Load32x2U $($rest:tt)*) => {};
+    (emit V128Load8Splat $($rest:tt)*) => {};
+    (emit V128Load16Splat $($rest:tt)*) => {};
+    (emit V128Load32Splat $($rest:tt)*) => {};
+    (emit V128Load64Splat $($rest:tt)*) => {};
+    (emit V128Load32Zero $($rest:tt)*) => {};
+    (emit V128Load64Zero $($rest:tt)*) => {};
+    (emit V128Store $($rest:tt)*) => {};
+    (emit V128Load8Lane $($rest:tt)*) => {};
+    (emit V128Load16Lane $($rest:tt)*) => {};
+    (emit V128Load32Lane $($rest:tt)*) => {};
+    (emit V128Load64Lane $($rest:tt)*) => {};
+    (emit V128Store8Lane $($rest:tt)*) => {};
+    (emit V128Store16Lane $($rest:tt)*) => {};
+    (emit V128Store32Lane $($rest:tt)*) => {};
+    (emit V128Store64Lane $($rest:tt)*) => {};
+    (emit V128Const $($rest:tt)*) => {};
+    (emit I8x16Shuffle $($rest:tt)*) => {};
+    (emit I8x16ExtractLaneS $($rest:tt)*) => {};
+    (emit I8x16ExtractLaneU $($rest:tt)*) => {};
+    (emit I8x16ReplaceLane $($rest:tt)*) => {};
+    (emit I16x8ExtractLaneS $($rest:tt)*) => {};
+    (emit I16x8ExtractLaneU $($rest:tt)*) => {};
+    (emit I16x8ReplaceLane $($rest:tt)*) => {};
+    (emit I32x4ExtractLane $($rest:tt)*) => {};
+    (emit I32x4ReplaceLane $($rest:tt)*) => {};
+    (emit I64x2ExtractLane $($rest:tt)*) => {};
+    (emit I64x2ReplaceLane $($rest:tt)*) => {};
+    (emit F32x4ExtractLane $($rest:tt)*) => {};
+    (emit F32x4ReplaceLane $($rest:tt)*) => {};
+    (emit F64x2ExtractLane $($rest:tt)*) => {};
+    (emit F64x2ReplaceLane $($rest:tt)*) => {};
+    (emit I8x16Swizzle $($rest:tt)*) => {};
+    (emit I8x16Splat $($rest:tt)*) => {};
+    (emit I16x8Splat $($rest:tt)*) => {};
+    (emit I32x4Splat $($rest:tt)*) => {};
+    (emit I64x2Splat $($rest:tt)*) => {};
+    (emit F32x4Splat $($rest:tt)*) => {};
+    (emit F64x2Splat $($rest:tt)*) => {};
+    (emit I8x16Eq $($rest:tt)*) => {};
+    (emit I8x16Ne $($rest:tt)*) => {};
+    (emit I8x16LtS $($rest:tt)*) => {};
+    (emit I8x16LtU $($rest:tt)*) => {};
+    (emit I8x16GtS $($rest:tt)*) => {};
+    (emit I8x16GtU $($rest:tt)*) => {};
+    (emit I8x16LeS $($rest:tt)*) => {};
+    (emit I8x16LeU $($rest:tt)*) => {};
+    (emit I8x16GeS $($rest:tt)*) => {};
+    (emit I8x16GeU $($rest:tt)*) => {};
+    (emit I16x8Eq $($rest:tt)*) => {};
+    (emit I16x8Ne $($rest:tt)*) => {};
+    (emit I16x8LtS $($rest:tt)*) => {};
+    (emit I16x8LtU $($rest:tt)*) => {};
+    (emit I16x8GtS $($rest:tt)*) => {};
+    (emit I16x8GtU $($rest:tt)*) => {};
+    (emit I16x8LeS $($rest:tt)*) => {};
+    (emit I16x8LeU $($rest:tt)*) => {};
+    (emit I16x8GeS $($rest:tt)*) => {};
+    (emit I16x8GeU $($rest:tt)*) => {};
+    (emit I32x4Eq $($rest:tt)*) => {};
+    (emit I32x4Ne $($rest:tt)*) => {};
+    (emit I32x4LtS $($rest:tt)*) => {};
+    (emit I32x4LtU $($rest:tt)*) => {};
+    (emit I32x4GtS $($rest:tt)*) => {};
+    (emit I32x4GtU $($rest:tt)*) => {};
+    (emit I32x4LeS $($rest:tt)*) => {};
+    (emit I32x4LeU $($rest:tt)*) => {};
+    (emit I32x4GeS $($rest:tt)*) => {};
+    (emit I32x4GeU $($rest:tt)*) => {};
+    (emit I64x2Eq $($rest:tt)*) => {};
+    (emit I64x2Ne $($rest:tt)*) => {};
+    (emit I64x2LtS $($rest:tt)*) => {};
+    (emit I64x2GtS $($rest:tt)*) => {};
+    (emit I64x2LeS $($rest:tt)*) => {};
+    (emit I64x2GeS $($rest:tt)*) => {};
+    (emit F32x4Eq $($rest:tt)*) => {};
+    (emit F32x4Ne $($rest:tt)*) => {};
+    (emit F32x4Lt $($rest:tt)*) => {};
+    (emit F32x4Gt $($rest:tt)*) => {};
+    (emit F32x4Le $($rest:tt)*) => {};
+    (emit F32x4Ge $($rest:tt)*) => {};
+    (emit F64x2Eq $($rest:tt)*) => {};
+    (emit F64x2Ne $($rest:tt)*) => {};
+    (emit F64x2Lt $($rest:tt)*) => {};
+    (emit F64x2Gt $($rest:tt)*) => {};
+    (emit F64x2Le $($rest:tt)*) => {};
+    (emit F64x2Ge $($rest:tt)*) => {};
+    (emit V128Not $($rest:tt)*) => {};
+    (emit V128And $($rest:tt)*) => {};
+    (emit V128AndNot $($rest:tt)*) => {};
+    (emit V128Or $($rest:tt)*) => {};
+    (emit V128Xor $($rest:tt)*) => {};
+    (emit V128Bitselect $($rest:tt)*) => {};
+    (emit V128AnyTrue $($rest:tt)*) => {};
+    (emit I8x16Abs $($rest:tt)*) => {};
+    (emit I8x16Neg $($rest:tt)*) => {};
+    (emit I8x16AllTrue $($rest:tt)*) => {};
+    (emit I8x16Bitmask $($rest:tt)*) => {};
+    (emit I8x16Shl $($rest:tt)*) => {};
+    (emit I8x16ShrS $($rest:tt)*) => {};
+    (emit I8x16ShrU $($rest:tt)*) => {};
+    (emit I8x16Add $($rest:tt)*) => {};
+    (emit I8x16Sub $($rest:tt)*) => {};
+    (emit I8x16Popcnt $($rest:tt)*) => {};
+    (emit I8x16NarrowI16x8S $($rest:tt)*) => {};
+    (emit I8x16NarrowI16x8U $($rest:tt)*) => {};
+    (emit I8x16AddSatS $($rest:tt)*) => {};
+    (emit I8x16AddSatU $($rest:tt)*) => {};
+    (emit I8x16SubSatS $($rest:tt)*) => {};
+    (emit I8x16SubSatU $($rest:tt)*) => {};
+    (emit I8x16AvgrU $($rest:tt)*) => {};
+    (emit I16x8AddSatS $($rest:tt)*) => {};
+    (emit I16x8AddSatU $($rest:tt)*) => {};
+    (emit I16x8SubSatS $($rest:tt)*) => {};
+    (emit I16x8SubSatU $($rest:tt)*) => {};
+    (emit I16x8AvgrU $($rest:tt)*) => {};
+    (emit I8x16MinS $($rest:tt)*) => {};
+    (emit I8x16MinU $($rest:tt)*) => {};
+    (emit I8x16MaxS $($rest:tt)*) => {};
+    (emit I8x16MaxU $($rest:tt)*) => {};
+    (emit I16x8MinS $($rest:tt)*) => {};
+    (emit I16x8MinU $($rest:tt)*) => {};
+    (emit I16x8MaxS $($rest:tt)*) => {};
+    (emit I16x8MaxU $($rest:tt)*) => {};
+    (emit I32x4MinS $($rest:tt)*) => {};
+    (emit I32x4MinU $($rest:tt)*) => {};
+    (emit I32x4MaxS $($rest:tt)*) => {};
+    (emit I32x4MaxU $($rest:tt)*) => {};
+    (emit I16x8Abs $($rest:tt)*) => {};
+    (emit I16x8Neg $($rest:tt)*) => {};
+    (emit I16x8AllTrue $($rest:tt)*) => {};
+    (emit I16x8Bitmask $($rest:tt)*) => {};
+    (emit I16x8Shl $($rest:tt)*) => {};
+    (emit I16x8ShrS $($rest:tt)*) => {};
+    (emit I16x8ShrU $($rest:tt)*) => {};
+    (emit I16x8Add $($rest:tt)*) => {};
+    (emit I16x8Sub $($rest:tt)*) => {};
+    (emit I16x8Mul $($rest:tt)*) => {};
+    (emit I16x8Q15MulrSatS $($rest:tt)*) => {};
+    (emit I16x8NarrowI32x4S $($rest:tt)*) => {};
+    (emit I16x8NarrowI32x4U $($rest:tt)*) => {};
+    (emit I16x8ExtAddPairwiseI8x16S $($rest:tt)*) => {};
+    (emit I16x8ExtAddPairwiseI8x16U $($rest:tt)*) => {};
+    (emit I16x8ExtendLowI8x16S $($rest:tt)*) => {};
+    (emit I16x8ExtendHighI8x16S $($rest:tt)*) => {};
+    (emit I16x8ExtendLowI8x16U $($rest:tt)*) => {};
+    (emit I16x8ExtendHighI8x16U $($rest:tt)*) => {};
+    (emit I16x8ExtMulLowI8x16S $($rest:tt)*) => {};
+    (emit I16x8ExtMulHighI8x16S $($rest:tt)*) => {};
+    (emit I16x8ExtMulLowI8x16U $($rest:tt)*) => {};
+    (emit I16x8ExtMulHighI8x16U $($rest:tt)*) => {};
+    (emit I32x4Abs $($rest:tt)*) => {};
+    (emit I32x4Neg $($rest:tt)*) => {};
+    (emit I32x4AllTrue $($rest:tt)*) => {};
+    (emit I32x4Bitmask $($rest:tt)*) => {};
+    (emit I32x4Shl $($rest:tt)*) => {};
+    (emit I32x4ShrS $($rest:tt)*) => {};
+    (emit I32x4ShrU $($rest:tt)*) => {};
+    (emit I32x4Add $($rest:tt)*) => {};
+    (emit I32x4Sub $($rest:tt)*) => {};
+    (emit I32x4Mul $($rest:tt)*) => {};
+    (emit I32x4DotI16x8S $($rest:tt)*) => {};
+    (emit I32x4ExtAddPairwiseI16x8S $($rest:tt)*) => {};
+    (emit I32x4ExtAddPairwiseI16x8U $($rest:tt)*) => {};
+    (emit I32x4ExtendLowI16x8S $($rest:tt)*) => {};
+    (emit I32x4ExtendHighI16x8S $($rest:tt)*) => {};
+    (emit I32x4ExtendLowI16x8U $($rest:tt)*) => {};
+    (emit I32x4ExtendHighI16x8U $($rest:tt)*) => {};
+    (emit I32x4ExtMulLowI16x8S $($rest:tt)*) => {};
+    (emit I32x4ExtMulHighI16x8S $($rest:tt)*) => {};
+    (emit I32x4ExtMulLowI16x8U $($rest:tt)*) => {};
+    (emit I32x4ExtMulHighI16x8U $($rest:tt)*) => {};
+    (emit I64x2Abs $($rest:tt)*) => {};
+    (emit I64x2Neg $($rest:tt)*) => {};
+    (emit I64x2AllTrue $($rest:tt)*) => {};
+    (emit I64x2Bitmask $($rest:tt)*) => {};
+    (emit I64x2Shl $($rest:tt)*) => {};
+    (emit I64x2ShrS $($rest:tt)*) => {};
+    (emit I64x2ShrU $($rest:tt)*) => {};
+    (emit I64x2Add $($rest:tt)*) => {};
+    (emit I64x2Sub $($rest:tt)*) => {};
+    (emit I64x2Mul $($rest:tt)*) => {};
+    (emit I64x2ExtendLowI32x4S $($rest:tt)*) => {};
+    (emit I64x2ExtendHighI32x4S $($rest:tt)*) => {};
+    (emit I64x2ExtendLowI32x4U $($rest:tt)*) => {};
+    (emit I64x2ExtendHighI32x4U $($rest:tt)*) => {};
+    (emit I64x2ExtMulLowI32x4S $($rest:tt)*) => {};
+    (emit I64x2ExtMulHighI32x4S $($rest:tt)*) => {};
+    (emit I64x2ExtMulLowI32x4U $($rest:tt)*) => {};
+    (emit I64x2ExtMulHighI32x4U $($rest:tt)*) => {};
+    (emit F32x4Ceil $($rest:tt)*) => {};
+    (emit F32x4Floor $($rest:tt)*) => {};
+    (emit F32x4Trunc $($rest:tt)*) => {};
+    (emit F32x4Nearest $($rest:tt)*) => {};
+    (emit F32x4Abs $($rest:tt)*) => {};
+    (emit F32x4Neg $($rest:tt)*) => {};
+    (emit F32x4Sqrt $($rest:tt)*) => {};
+    (emit F32x4Add $($rest:tt)*) => {};
+    (emit F32x4Sub $($rest:tt)*) => {};
+    (emit F32x4Mul $($rest:tt)*) => {};
+    (emit F32x4Div $($rest:tt)*) => {};
+    (emit F32x4Min $($rest:tt)*) => {};
+    (emit F32x4Max $($rest:tt)*) => {};
+    (emit F32x4PMin $($rest:tt)*) => {};
+    (emit F32x4PMax $($rest:tt)*) => {};
+    (emit F64x2Ceil $($rest:tt)*) => {};
+    (emit F64x2Floor $($rest:tt)*) => {};
+    (emit F64x2Trunc $($rest:tt)*) => {};
+    (emit F64x2Nearest $($rest:tt)*) => {};
+    (emit F64x2Abs $($rest:tt)*) => {};
+    (emit F64x2Neg $($rest:tt)*) => {};
+    (emit F64x2Sqrt $($rest:tt)*) => {};
+    (emit F64x2Add $($rest:tt)*) => {};
+    (emit F64x2Sub $($rest:tt)*) => {};
+    (emit F64x2Mul $($rest:tt)*) => {};
+    (emit F64x2Div $($rest:tt)*) => {};
+    (emit F64x2Min $($rest:tt)*) => {};
+    (emit F64x2Max $($rest:tt)*) => {};
+    (emit F64x2PMin $($rest:tt)*) => {};
+    (emit F64x2PMax $($rest:tt)*) => {};
+    (emit I32x4TruncSatF32x4S $($rest:tt)*) => {};
+    (emit I32x4TruncSatF32x4U $($rest:tt)*) => {};
+    (emit F32x4ConvertI32x4S $($rest:tt)*) => {};
+    (emit F32x4ConvertI32x4U $($rest:tt)*) => {};
+    (emit I32x4TruncSatF64x2SZero $($rest:tt)*) => {};
+    (emit I32x4TruncSatF64x2UZero $($rest:tt)*) => {};
+    (emit F64x2ConvertLowI32x4S $($rest:tt)*) => {};
+    (emit F64x2ConvertLowI32x4U $($rest:tt)*) => {};
+    (emit F32x4DemoteF64x2Zero $($rest:tt)*) => {};
+    (emit F64x2PromoteLowF32x4 $($rest:tt)*) => {};
 
     (emit $unsupported:tt $($rest:tt)*) => {$($rest)*};
 }
@@ -745,7 +1048,7 @@ where
             if !is_outermost {
                 control.emit_end(self.masm, &mut self.context);
             } else {
-                self.context.pop_abi_results(control.result(), self.masm);
+                control.pop_results(&mut self.context, self.masm);
                 control.bind_exit_label(self.masm);
             }
         }
@@ -762,6 +1065,376 @@ where
         self.masm.popcnt(&mut self.context, S64);
     }
 
+    fn visit_i32_wrap_i64(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _size| {
+                masm.wrap(src, dst);
+            });
+    }
+
+    fn visit_i64_extend_i32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, _size| {
+                masm.extend(src, dst, ExtendKind::I64ExtendI32S);
+            });
+    }
+
+    fn visit_i64_extend_i32_u(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, _size| {
+                masm.extend(src, dst, ExtendKind::I64ExtendI32U);
+            });
+    }
+
+    fn visit_i32_extend8_s(&mut self) {
+        self.context
+            .unop(self.masm, OperandSize::S32, &mut |masm, reg, _size| {
+                masm.extend(reg, reg, ExtendKind::I32Extend8S);
+            });
+    }
+
+    fn visit_i32_extend16_s(&mut self) {
+        self.context
+            .unop(self.masm, OperandSize::S32, &mut |masm, reg, _size| {
+                masm.extend(reg, reg, ExtendKind::I32Extend16S);
+            });
+    }
+
+    fn visit_i64_extend8_s(&mut self) {
+        self.context
+            .unop(self.masm, OperandSize::S64, &mut |masm, reg, _size| {
+                masm.extend(reg, reg, ExtendKind::I64Extend8S);
+            });
+    }
+
+    fn visit_i64_extend16_s(&mut self) {
+        self.context
+            .unop(self.masm, OperandSize::S64, &mut |masm, reg, _size| {
+                masm.extend(reg, reg, ExtendKind::I64Extend16S);
+            });
+    }
+
+    fn visit_i64_extend32_s(&mut self) {
+        self.context
+            .unop(self.masm, OperandSize::S64, &mut |masm, reg, _size| {
+                masm.extend(reg, reg, ExtendKind::I64ExtendI32S);
+            });
+    }
+
+    fn visit_f32_convert_i32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F32, |masm, dst, src, dst_size| {
+                masm.signed_convert(src, dst, OperandSize::S32, dst_size);
+            });
+    }
+
+    fn visit_f32_convert_i32_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::F32,
+            RegClass::Int,
+            |masm, dst, src, tmp_gpr, dst_size| {
+                masm.unsigned_convert(src, dst, tmp_gpr, OperandSize::S32, dst_size);
+            },
+        );
+    }
+
+    fn visit_f32_convert_i64_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F32, |masm, dst, src, dst_size| {
+                masm.signed_convert(src, dst, OperandSize::S64, dst_size);
+            });
+    }
+
+    fn visit_f32_convert_i64_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::F32,
+            RegClass::Int,
+            |masm, dst, src, tmp_gpr, dst_size| {
+                masm.unsigned_convert(src, dst, tmp_gpr, OperandSize::S64, dst_size);
+            },
+        );
+    }
+
+    fn visit_f64_convert_i32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F64, |masm, dst, src, dst_size| {
+                masm.signed_convert(src, dst, OperandSize::S32, dst_size);
+            });
+    }
+
+    fn visit_f64_convert_i32_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::F64,
+            RegClass::Int,
+            |masm, dst, src, tmp_gpr, dst_size| {
+                masm.unsigned_convert(src, dst, tmp_gpr, OperandSize::S32, dst_size);
+            },
+        );
+    }
+
+    fn visit_f64_convert_i64_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F64, |masm, dst, src, dst_size| {
+                masm.signed_convert(src, dst, OperandSize::S64, dst_size);
+            });
+    }
+
+    fn visit_f64_convert_i64_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::F64,
+            RegClass::Int,
+            |masm, dst, src, tmp_gpr, dst_size| {
+                masm.unsigned_convert(src, dst, tmp_gpr, OperandSize::S64, dst_size);
+            },
+        );
+    }
+
+    fn visit_f32_demote_f64(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F32, |masm, dst, src, _size| {
+                masm.demote(src, dst);
+            });
+    }
+
+    fn visit_f64_promote_f32(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F64, |masm, dst, src, _size| {
+                masm.promote(src, dst);
+            });
+    }
+
+    fn visit_i32_reinterpret_f32(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, size| {
+                masm.reinterpret_float_as_int(src, dst, size);
+            });
+    }
+
+    fn visit_i64_reinterpret_f64(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, size| {
+                masm.reinterpret_float_as_int(src, dst, size);
+            });
+    }
+
+    fn visit_f32_reinterpret_i32(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F32, |masm, dst, src, size| {
+                masm.reinterpret_int_as_float(src, dst, size);
+            });
+    }
+
+    fn visit_f64_reinterpret_i64(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::F64, |masm, dst, src, size| {
+                masm.reinterpret_int_as_float(src, dst, size);
+            });
+    }
+
+    fn visit_i32_trunc_f32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S32, dst_size, TruncKind::Checked);
+            });
+    }
+
+    fn visit_i32_trunc_sat_f32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S32, dst_size, TruncKind::Saturating);
+            });
+    }
+
+    fn visit_i32_trunc_f32_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I32,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S32,
+                    dst_size,
+                    TruncKind::Checked,
+                );
+            },
+        );
+    }
+
+    fn visit_i32_trunc_sat_f32_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I32,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S32,
+                    dst_size,
+                    TruncKind::Saturating,
+                );
+            },
+        );
+    }
+
+    fn visit_i32_trunc_f64_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S64, dst_size, TruncKind::Checked);
+            });
+    }
+
+    fn visit_i32_trunc_sat_f64_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S64, dst_size, TruncKind::Saturating);
+            });
+    }
+
+    fn visit_i32_trunc_f64_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I32,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S64,
+                    dst_size,
+                    TruncKind::Checked,
+                );
+            },
+        );
+    }
+
+    fn visit_i32_trunc_sat_f64_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I32,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S64,
+                    dst_size,
+                    TruncKind::Saturating,
+                );
+            },
+        );
+    }
+
+    fn visit_i64_trunc_f32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S32, dst_size, TruncKind::Checked);
+            });
+    }
+
+    fn visit_i64_trunc_sat_f32_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S32, dst_size, TruncKind::Saturating);
+            });
+    }
+
+    fn visit_i64_trunc_f32_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I64,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S32,
+                    dst_size,
+                    TruncKind::Checked,
+                );
+            },
+        );
+    }
+
+    fn visit_i64_trunc_sat_f32_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I64,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S32,
+                    dst_size,
+                    TruncKind::Saturating,
+                );
+            },
+        );
+    }
+
+    fn visit_i64_trunc_f64_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S64, dst_size, TruncKind::Checked);
+            });
+    }
+
+    fn visit_i64_trunc_sat_f64_s(&mut self) {
+        self.context
+            .convert_op(self.masm, WasmType::I64, |masm, dst, src, dst_size| {
+                masm.signed_truncate(src, dst, OperandSize::S64, dst_size, TruncKind::Saturating);
+            });
+    }
+
+    fn visit_i64_trunc_f64_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I64,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S64,
+                    dst_size,
+                    TruncKind::Checked,
+                );
+            },
+        );
+    }
+
+    fn visit_i64_trunc_sat_f64_u(&mut self) {
+        self.context.convert_op_with_tmp_reg(
+            self.masm,
+            WasmType::I64,
+            RegClass::Float,
+            |masm, dst, src, tmp_fpr, dst_size| {
+                masm.unsigned_truncate(
+                    src,
+                    dst,
+                    tmp_fpr,
+                    OperandSize::S64,
+                    dst_size,
+                    TruncKind::Saturating,
+                );
+            },
+        );
+    }
+
     fn visit_local_get(&mut self, index: u32) {
         use WasmType::*;
         let context = &mut self.context;
@@ -770,7 +1443,7 @@ where
             .get_local(index)
             .unwrap_or_else(|| panic!("valid local at slot = {}", index));
         match slot.ty {
-            I32 | I64 | F32 | F64 => context.stack.push(Val::local(index, slot.ty)),
+            I32 | I64 | F32 | F64 | V128 => context.stack.push(Val::local(index, slot.ty)),
             Ref(rt) => match rt.heap_type {
                 WasmHeapType::Func => context.stack.push(Val::local(index, slot.ty)),
                 ht => unimplemented!("Support for WasmHeapType: {ht}"),
@@ -901,6 +1574,14 @@ where
         });
     }
 
+    fn visit_memory_size(&mut self, mem: u32, _: u8) {
+        self.emit_memory_size(MemoryIndex::from_u32(mem));
+    }
+
+    fn visit_memory_grow(&mut self, mem: u32, _: u8) {
+        self.emit_memory_grow(MemoryIndex::from_u32(mem));
+    }
+
     fn visit_table_size(&mut self, table: u32) {
         let table_index = TableIndex::from_u32(table);
         let table_data = self.env.resolve_table_data(table_index);
@@ -1016,6 +1697,14 @@ where
             self.masm,
             &mut self.context,
         ));
+
+        // Loop headers are the back-edge targets, so fuel and epochs are
+        // checked at this point to guarantee that long running loops can
+        // be interrupted.
+        if self.context.reachable {
+            self.maybe_emit_fuel_check();
+            self.maybe_emit_epoch_check();
+        }
     }
 
     fn visit_br(&mut self, depth: u32) {
@@ -1023,7 +1712,7 @@ where
         let frame = &mut self.control_frames[index];
         self.context
             .unconditional_jump(frame, self.masm, |masm, cx, frame| {
-                cx.pop_abi_results(&frame.as_target_result(), masm);
+                frame.pop_target_values(cx, masm);
             });
     }
 
@@ -1031,7 +1720,10 @@ where
         let index = control_index(depth, self.control_frames.len());
         let frame = &mut self.control_frames[index];
         frame.set_as_target();
-        let result = frame.as_target_result();
+        let result = match frame.target_values().as_reg_result().cloned() {
+            Some(result) => result,
+            None => return self.emit_br_if_out_of_line(index),
+        };
         let top = self.context.maybe_without1::<TypedReg, M, _>(
             result.result_reg(),
             self.masm,
@@ -1058,7 +1750,25 @@ where
         let labels: SmallVec<[_; 5]> = (0..len).map(|_| self.masm.get_label()).collect();
 
         let default_index = control_index(targets.default(), self.control_frames.len());
-        let default_result = self.control_frames[default_index].as_target_result();
+        let has_stack_values = targets
+            .targets()
+            .into_iter()
+            .chain(std::iter::once(Ok(targets.default())))
+            .any(|t| {
+                let index = control_index(t.unwrap(), self.control_frames.len());
+                self.control_frames[index]
+                    .target_values()
+                    .as_reg_result()
+                    .is_none()
+            });
+        if has_stack_values {
+            return self.emit_br_table_out_of_line(&targets, &labels);
+        }
+        let default_result = self.control_frames[default_index]
+            .target_values()
+            .as_reg_result()
+            .cloned()
+            .unwrap();
         let (index, tmp) = self.context.maybe_without1::<(TypedReg, _), M, _>(
             default_result.result_reg(),
             self.masm,
@@ -1100,7 +1810,7 @@ where
         let outermost = &mut self.control_frames[0];
         self.context
             .unconditional_jump(outermost, self.masm, |masm, cx, frame| {
-                cx.pop_abi_results(&frame.as_target_result(), masm);
+                frame.pop_target_values(cx, masm);
             });
     }
 
@@ -1164,24 +1874,1419 @@ where
         self.context.free_reg(cond);
     }
 
-    wasmparser::for_each_operator!(def_unsupported);
-}
+    fn visit_i32_load(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::I32, OperandSize::S32, None);
+    }
 
-impl<'a, 'b, 'c, M> CodeGen<'a, 'b, 'c, M>
-where
-    M: MacroAssembler,
-{
-    fn cmp_i32s(&mut self, kind: CmpKind) {
-        self.context.i32_binop(self.masm, |masm, dst, src, size| {
-            masm.cmp_with_set(src, dst, kind, size);
-        });
+    fn visit_i32_load8_s(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I32,
+            OperandSize::S8,
+            Some(ExtendKind::I32Extend8S),
+        );
     }
 
-    fn cmp_i64s(&mut self, kind: CmpKind) {
-        self.context
-            .i64_binop(self.masm, move |masm, dst, src, size| {
-                masm.cmp_with_set(src, dst, kind, size);
-            });
+    fn visit_i32_load8_u(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I32,
+            OperandSize::S8,
+            Some(ExtendKind::I32Extend8U),
+        );
+    }
+
+    fn visit_i32_load16_s(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I32,
+            OperandSize::S16,
+            Some(ExtendKind::I32Extend16S),
+        );
+    }
+
+    fn visit_i32_load16_u(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I32,
+            OperandSize::S16,
+            Some(ExtendKind::I32Extend16U),
+        );
+    }
+
+    fn visit_i64_load(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::I64, OperandSize::S64, None);
+    }
+
+    fn visit_i64_load8_s(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I64,
+            OperandSize::S8,
+            Some(ExtendKind::I64Extend8S),
+        );
+    }
+
+    fn visit_i64_load8_u(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I64,
+            OperandSize::S8,
+            Some(ExtendKind::I64Extend8U),
+        );
+    }
+
+    fn visit_i64_load16_s(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I64,
+            OperandSize::S16,
+            Some(ExtendKind::I64Extend16S),
+        );
+    }
+
+    fn visit_i64_load16_u(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I64,
+            OperandSize::S16,
+            Some(ExtendKind::I64Extend16U),
+        );
+    }
+
+    fn visit_i64_load32_s(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I64,
+            OperandSize::S32,
+            Some(ExtendKind::I64ExtendI32S),
+        );
+    }
+
+    fn visit_i64_load32_u(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(
+            &memarg,
+            WasmType::I64,
+            OperandSize::S32,
+            Some(ExtendKind::I64ExtendI32U),
+        );
+    }
+
+    fn visit_f32_load(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::F32, OperandSize::S32, None);
+    }
+
+    fn visit_f64_load(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::F64, OperandSize::S64, None);
+    }
+
+    fn visit_i32_store(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S32);
+    }
+
+    fn visit_i32_store8(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S8);
+    }
+
+    fn visit_i32_store16(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S16);
+    }
+
+    fn visit_i64_store(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S64);
+    }
+
+    fn visit_i64_store8(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S8);
+    }
+
+    fn visit_i64_store16(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S16);
+    }
+
+    fn visit_i64_store32(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S32);
+    }
+
+    fn visit_f32_store(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S32);
+    }
+
+    fn visit_f64_store(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S64);
+    }
+
+    fn visit_v128_load(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::V128, OperandSize::S128, None);
+    }
+
+    fn visit_v128_load8x8_s(&mut self, memarg: MemArg) {
+        self.emit_v128_load_extend(&memarg, V128UnaryOp::ExtendLowS(V128Lanes::I16x8));
+    }
+
+    fn visit_v128_load8x8_u(&mut self, memarg: MemArg) {
+        self.emit_v128_load_extend(&memarg, V128UnaryOp::ExtendLowU(V128Lanes::I16x8));
+    }
+
+    fn visit_v128_load16x4_s(&mut self, memarg: MemArg) {
+        self.emit_v128_load_extend(&memarg, V128UnaryOp::ExtendLowS(V128Lanes::I32x4));
+    }
+
+    fn visit_v128_load16x4_u(&mut self, memarg: MemArg) {
+        self.emit_v128_load_extend(&memarg, V128UnaryOp::ExtendLowU(V128Lanes::I32x4));
+    }
+
+    fn visit_v128_load32x2_s(&mut self, memarg: MemArg) {
+        self.emit_v128_load_extend(&memarg, V128UnaryOp::ExtendLowS(V128Lanes::I64x2));
+    }
+
+    fn visit_v128_load32x2_u(&mut self, memarg: MemArg) {
+        self.emit_v128_load_extend(&memarg, V128UnaryOp::ExtendLowU(V128Lanes::I64x2));
+    }
+
+    fn visit_v128_load8_splat(&mut self, memarg: MemArg) {
+        self.emit_v128_load_splat(&memarg, V128Lanes::I8x16);
+    }
+
+    fn visit_v128_load16_splat(&mut self, memarg: MemArg) {
+        self.emit_v128_load_splat(&memarg, V128Lanes::I16x8);
+    }
+
+    fn visit_v128_load32_splat(&mut self, memarg: MemArg) {
+        self.emit_v128_load_splat(&memarg, V128Lanes::I32x4);
+    }
+
+    fn visit_v128_load64_splat(&mut self, memarg: MemArg) {
+        self.emit_v128_load_splat(&memarg, V128Lanes::I64x2);
+    }
+
+    fn visit_v128_load32_zero(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::V128, OperandSize::S32, None);
+    }
+
+    fn visit_v128_load64_zero(&mut self, memarg: MemArg) {
+        self.emit_wasm_load(&memarg, WasmType::V128, OperandSize::S64, None);
+    }
+
+    fn visit_v128_store(&mut self, memarg: MemArg) {
+        self.emit_wasm_store(&memarg, OperandSize::S128);
+    }
+
+    fn visit_v128_load8_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_load_lane(&memarg, lane, V128Lanes::I8x16);
+    }
+
+    fn visit_v128_load16_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_load_lane(&memarg, lane, V128Lanes::I16x8);
+    }
+
+    fn visit_v128_load32_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_load_lane(&memarg, lane, V128Lanes::I32x4);
+    }
+
+    fn visit_v128_load64_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_load_lane(&memarg, lane, V128Lanes::I64x2);
+    }
+
+    fn visit_v128_store8_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_store_lane(&memarg, lane, V128Lanes::I8x16);
+    }
+
+    fn visit_v128_store16_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_store_lane(&memarg, lane, V128Lanes::I16x8);
+    }
+
+    fn visit_v128_store32_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_store_lane(&memarg, lane, V128Lanes::I32x4);
+    }
+
+    fn visit_v128_store64_lane(&mut self, memarg: MemArg, lane: u8) {
+        self.emit_v128_store_lane(&memarg, lane, V128Lanes::I64x2);
+    }
+
+    fn visit_v128_const(&mut self, value: V128) {
+        self.context.stack.push(Val::v128(value.i128()));
+    }
+
+    fn visit_i8x16_shuffle(&mut self, lanes: [u8; 16]) {
+        let mut result = Ok(());
+        self.context
+            .binop(self.masm, OperandSize::S128, |masm, dst, src, _| {
+                result = masm.v128_shuffle(dst, dst, src, lanes);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_extract_lane_s(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::I8x16, true);
+    }
+
+    fn visit_i8x16_extract_lane_u(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::I8x16, false);
+    }
+
+    fn visit_i8x16_replace_lane(&mut self, lane: u8) {
+        self.emit_v128_replace_lane(lane, V128Lanes::I8x16);
+    }
+
+    fn visit_i16x8_extract_lane_s(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::I16x8, true);
+    }
+
+    fn visit_i16x8_extract_lane_u(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::I16x8, false);
+    }
+
+    fn visit_i16x8_replace_lane(&mut self, lane: u8) {
+        self.emit_v128_replace_lane(lane, V128Lanes::I16x8);
+    }
+
+    fn visit_i32x4_extract_lane(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::I32x4, false);
+    }
+
+    fn visit_i32x4_replace_lane(&mut self, lane: u8) {
+        self.emit_v128_replace_lane(lane, V128Lanes::I32x4);
+    }
+
+    fn visit_i64x2_extract_lane(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::I64x2, false);
+    }
+
+    fn visit_i64x2_replace_lane(&mut self, lane: u8) {
+        self.emit_v128_replace_lane(lane, V128Lanes::I64x2);
+    }
+
+    fn visit_f32x4_extract_lane(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::F32x4, false);
+    }
+
+    fn visit_f32x4_replace_lane(&mut self, lane: u8) {
+        self.emit_v128_replace_lane(lane, V128Lanes::F32x4);
+    }
+
+    fn visit_f64x2_extract_lane(&mut self, lane: u8) {
+        self.emit_v128_extract_lane(lane, V128Lanes::F64x2, false);
+    }
+
+    fn visit_f64x2_replace_lane(&mut self, lane: u8) {
+        self.emit_v128_replace_lane(lane, V128Lanes::F64x2);
+    }
+
+    fn visit_i8x16_swizzle(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Swizzle);
+    }
+
+    fn visit_i8x16_splat(&mut self) {
+        self.emit_v128_splat(V128Lanes::I8x16);
+    }
+
+    fn visit_i16x8_splat(&mut self) {
+        self.emit_v128_splat(V128Lanes::I16x8);
+    }
+
+    fn visit_i32x4_splat(&mut self) {
+        self.emit_v128_splat(V128Lanes::I32x4);
+    }
+
+    fn visit_i64x2_splat(&mut self) {
+        self.emit_v128_splat(V128Lanes::I64x2);
+    }
+
+    fn visit_f32x4_splat(&mut self) {
+        self.emit_v128_splat(V128Lanes::F32x4);
+    }
+
+    fn visit_f64x2_splat(&mut self) {
+        self.emit_v128_splat(V128Lanes::F64x2);
+    }
+
+    fn visit_i8x16_eq(&mut self) {
+        self.cmp_v128s(CmpKind::Eq, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_ne(&mut self) {
+        self.cmp_v128s(CmpKind::Ne, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_lt_s(&mut self) {
+        self.cmp_v128s(CmpKind::LtS, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_lt_u(&mut self) {
+        self.cmp_v128s(CmpKind::LtU, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_gt_s(&mut self) {
+        self.cmp_v128s(CmpKind::GtS, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_gt_u(&mut self) {
+        self.cmp_v128s(CmpKind::GtU, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_le_s(&mut self) {
+        self.cmp_v128s(CmpKind::LeS, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_le_u(&mut self) {
+        self.cmp_v128s(CmpKind::LeU, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_ge_s(&mut self) {
+        self.cmp_v128s(CmpKind::GeS, V128Lanes::I8x16);
+    }
+
+    fn visit_i8x16_ge_u(&mut self) {
+        self.cmp_v128s(CmpKind::GeU, V128Lanes::I8x16);
+    }
+
+    fn visit_i16x8_eq(&mut self) {
+        self.cmp_v128s(CmpKind::Eq, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_ne(&mut self) {
+        self.cmp_v128s(CmpKind::Ne, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_lt_s(&mut self) {
+        self.cmp_v128s(CmpKind::LtS, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_lt_u(&mut self) {
+        self.cmp_v128s(CmpKind::LtU, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_gt_s(&mut self) {
+        self.cmp_v128s(CmpKind::GtS, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_gt_u(&mut self) {
+        self.cmp_v128s(CmpKind::GtU, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_le_s(&mut self) {
+        self.cmp_v128s(CmpKind::LeS, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_le_u(&mut self) {
+        self.cmp_v128s(CmpKind::LeU, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_ge_s(&mut self) {
+        self.cmp_v128s(CmpKind::GeS, V128Lanes::I16x8);
+    }
+
+    fn visit_i16x8_ge_u(&mut self) {
+        self.cmp_v128s(CmpKind::GeU, V128Lanes::I16x8);
+    }
+
+    fn visit_i32x4_eq(&mut self) {
+        self.cmp_v128s(CmpKind::Eq, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_ne(&mut self) {
+        self.cmp_v128s(CmpKind::Ne, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_lt_s(&mut self) {
+        self.cmp_v128s(CmpKind::LtS, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_lt_u(&mut self) {
+        self.cmp_v128s(CmpKind::LtU, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_gt_s(&mut self) {
+        self.cmp_v128s(CmpKind::GtS, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_gt_u(&mut self) {
+        self.cmp_v128s(CmpKind::GtU, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_le_s(&mut self) {
+        self.cmp_v128s(CmpKind::LeS, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_le_u(&mut self) {
+        self.cmp_v128s(CmpKind::LeU, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_ge_s(&mut self) {
+        self.cmp_v128s(CmpKind::GeS, V128Lanes::I32x4);
+    }
+
+    fn visit_i32x4_ge_u(&mut self) {
+        self.cmp_v128s(CmpKind::GeU, V128Lanes::I32x4);
+    }
+
+    fn visit_i64x2_eq(&mut self) {
+        self.cmp_v128s(CmpKind::Eq, V128Lanes::I64x2);
+    }
+
+    fn visit_i64x2_ne(&mut self) {
+        self.cmp_v128s(CmpKind::Ne, V128Lanes::I64x2);
+    }
+
+    fn visit_i64x2_lt_s(&mut self) {
+        self.cmp_v128s(CmpKind::LtS, V128Lanes::I64x2);
+    }
+
+    fn visit_i64x2_gt_s(&mut self) {
+        self.cmp_v128s(CmpKind::GtS, V128Lanes::I64x2);
+    }
+
+    fn visit_i64x2_le_s(&mut self) {
+        self.cmp_v128s(CmpKind::LeS, V128Lanes::I64x2);
+    }
+
+    fn visit_i64x2_ge_s(&mut self) {
+        self.cmp_v128s(CmpKind::GeS, V128Lanes::I64x2);
+    }
+
+    fn visit_f32x4_eq(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Eq, V128Lanes::F32x4);
+    }
+
+    fn visit_f32x4_ne(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Ne, V128Lanes::F32x4);
+    }
+
+    fn visit_f32x4_lt(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Lt, V128Lanes::F32x4);
+    }
+
+    fn visit_f32x4_gt(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Gt, V128Lanes::F32x4);
+    }
+
+    fn visit_f32x4_le(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Le, V128Lanes::F32x4);
+    }
+
+    fn visit_f32x4_ge(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Ge, V128Lanes::F32x4);
+    }
+
+    fn visit_f64x2_eq(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Eq, V128Lanes::F64x2);
+    }
+
+    fn visit_f64x2_ne(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Ne, V128Lanes::F64x2);
+    }
+
+    fn visit_f64x2_lt(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Lt, V128Lanes::F64x2);
+    }
+
+    fn visit_f64x2_gt(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Gt, V128Lanes::F64x2);
+    }
+
+    fn visit_f64x2_le(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Le, V128Lanes::F64x2);
+    }
+
+    fn visit_f64x2_ge(&mut self) {
+        self.float_cmp_v128s(FloatCmpKind::Ge, V128Lanes::F64x2);
+    }
+
+    fn visit_v128_not(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Not);
+    }
+
+    fn visit_v128_and(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::And);
+    }
+
+    fn visit_v128_andnot(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AndNot);
+    }
+
+    fn visit_v128_or(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Or);
+    }
+
+    fn visit_v128_xor(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Xor);
+    }
+
+    fn visit_v128_bitselect(&mut self) {
+        let mask = self.context.pop_to_reg(self.masm, None);
+        let rhs = self.context.pop_to_reg(self.masm, None);
+        let lhs = self.context.pop_to_reg(self.masm, None);
+        let result = self
+            .masm
+            .v128_bitselect(lhs.reg, lhs.reg, rhs.reg, mask.reg);
+        self.handle_result(result);
+        self.context.free_reg(mask);
+        self.context.free_reg(rhs);
+        self.context.stack.push(lhs.into());
+    }
+
+    fn visit_v128_any_true(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_any_true(src, dst);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_abs(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Abs(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_neg(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Neg(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_all_true(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_all_true(src, dst, V128Lanes::I8x16);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_bitmask(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_bitmask(src, dst, V128Lanes::I8x16);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_shl(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::Shl, V128Lanes::I8x16);
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_shr_s(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrS, V128Lanes::I8x16);
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_shr_u(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrU, V128Lanes::I8x16);
+        self.handle_result(result);
+    }
+
+    fn visit_i8x16_add(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Add(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_sub(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Sub(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_popcnt(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Popcnt);
+    }
+
+    fn visit_i8x16_narrow_i16x8_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::NarrowS(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_narrow_i16x8_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::NarrowU(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_add_sat_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AddSatS(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_add_sat_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AddSatU(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_sub_sat_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::SubSatS(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_sub_sat_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::SubSatU(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_avgr_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AvgrU(V128Lanes::I8x16));
+    }
+
+    fn visit_i16x8_add_sat_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AddSatS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_add_sat_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AddSatU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_sub_sat_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::SubSatS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_sub_sat_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::SubSatU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_avgr_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::AvgrU(V128Lanes::I16x8));
+    }
+
+    fn visit_i8x16_min_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MinS(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_min_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MinU(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_max_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MaxS(V128Lanes::I8x16));
+    }
+
+    fn visit_i8x16_max_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MaxU(V128Lanes::I8x16));
+    }
+
+    fn visit_i16x8_min_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MinS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_min_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MinU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_max_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MaxS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_max_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MaxU(V128Lanes::I16x8));
+    }
+
+    fn visit_i32x4_min_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MinS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_min_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MinU(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_max_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MaxS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_max_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::MaxU(V128Lanes::I32x4));
+    }
+
+    fn visit_i16x8_abs(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Abs(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_neg(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Neg(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_all_true(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_all_true(src, dst, V128Lanes::I16x8);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i16x8_bitmask(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_bitmask(src, dst, V128Lanes::I16x8);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i16x8_shl(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::Shl, V128Lanes::I16x8);
+        self.handle_result(result);
+    }
+
+    fn visit_i16x8_shr_s(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrS, V128Lanes::I16x8);
+        self.handle_result(result);
+    }
+
+    fn visit_i16x8_shr_u(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrU, V128Lanes::I16x8);
+        self.handle_result(result);
+    }
+
+    fn visit_i16x8_add(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Add(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_sub(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Sub(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_mul(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Mul(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_q15mulr_sat_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Q15MulrSatS);
+    }
+
+    fn visit_i16x8_narrow_i32x4_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::NarrowS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_narrow_i32x4_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::NarrowU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extadd_pairwise_i8x16_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtAddPairwiseS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extadd_pairwise_i8x16_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtAddPairwiseU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extend_low_i8x16_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendLowS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extend_high_i8x16_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendHighS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extend_low_i8x16_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendLowU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extend_high_i8x16_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendHighU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extmul_low_i8x16_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulLowS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extmul_high_i8x16_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulHighS(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extmul_low_i8x16_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulLowU(V128Lanes::I16x8));
+    }
+
+    fn visit_i16x8_extmul_high_i8x16_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulHighU(V128Lanes::I16x8));
+    }
+
+    fn visit_i32x4_abs(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Abs(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_neg(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Neg(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_all_true(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_all_true(src, dst, V128Lanes::I32x4);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i32x4_bitmask(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_bitmask(src, dst, V128Lanes::I32x4);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i32x4_shl(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::Shl, V128Lanes::I32x4);
+        self.handle_result(result);
+    }
+
+    fn visit_i32x4_shr_s(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrS, V128Lanes::I32x4);
+        self.handle_result(result);
+    }
+
+    fn visit_i32x4_shr_u(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrU, V128Lanes::I32x4);
+        self.handle_result(result);
+    }
+
+    fn visit_i32x4_add(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Add(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_sub(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Sub(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_mul(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Mul(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_dot_i16x8_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::DotI16x8S);
+    }
+
+    fn visit_i32x4_extadd_pairwise_i16x8_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtAddPairwiseS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extadd_pairwise_i16x8_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtAddPairwiseU(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extend_low_i16x8_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendLowS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extend_high_i16x8_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendHighS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extend_low_i16x8_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendLowU(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extend_high_i16x8_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendHighU(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extmul_low_i16x8_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulLowS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extmul_high_i16x8_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulHighS(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extmul_low_i16x8_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulLowU(V128Lanes::I32x4));
+    }
+
+    fn visit_i32x4_extmul_high_i16x8_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulHighU(V128Lanes::I32x4));
+    }
+
+    fn visit_i64x2_abs(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Abs(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_neg(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Neg(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_all_true(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_all_true(src, dst, V128Lanes::I64x2);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i64x2_bitmask(&mut self) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::I32, |masm, dst, src, _| {
+                result = masm.v128_bitmask(src, dst, V128Lanes::I64x2);
+            });
+        self.handle_result(result);
+    }
+
+    fn visit_i64x2_shl(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::Shl, V128Lanes::I64x2);
+        self.handle_result(result);
+    }
+
+    fn visit_i64x2_shr_s(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrS, V128Lanes::I64x2);
+        self.handle_result(result);
+    }
+
+    fn visit_i64x2_shr_u(&mut self) {
+        let result = self
+            .masm
+            .v128_shift(&mut self.context, ShiftKind::ShrU, V128Lanes::I64x2);
+        self.handle_result(result);
+    }
+
+    fn visit_i64x2_add(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Add(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_sub(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Sub(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_mul(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Mul(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extend_low_i32x4_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendLowS(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extend_high_i32x4_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendHighS(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extend_low_i32x4_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendLowU(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extend_high_i32x4_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::ExtendHighU(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extmul_low_i32x4_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulLowS(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extmul_high_i32x4_s(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulHighS(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extmul_low_i32x4_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulLowU(V128Lanes::I64x2));
+    }
+
+    fn visit_i64x2_extmul_high_i32x4_u(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::ExtMulHighU(V128Lanes::I64x2));
+    }
+
+    fn visit_f32x4_ceil(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F32x4, RoundingMode::Up));
+    }
+
+    fn visit_f32x4_floor(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F32x4, RoundingMode::Down));
+    }
+
+    fn visit_f32x4_trunc(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F32x4, RoundingMode::Zero));
+    }
+
+    fn visit_f32x4_nearest(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F32x4, RoundingMode::Nearest));
+    }
+
+    fn visit_f32x4_abs(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Abs(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_neg(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Neg(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_sqrt(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Sqrt(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_add(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Add(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_sub(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Sub(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_mul(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Mul(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_div(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Div(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_min(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Min(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_max(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Max(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_pmin(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::PMin(V128Lanes::F32x4));
+    }
+
+    fn visit_f32x4_pmax(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::PMax(V128Lanes::F32x4));
+    }
+
+    fn visit_f64x2_ceil(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F64x2, RoundingMode::Up));
+    }
+
+    fn visit_f64x2_floor(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F64x2, RoundingMode::Down));
+    }
+
+    fn visit_f64x2_trunc(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F64x2, RoundingMode::Zero));
+    }
+
+    fn visit_f64x2_nearest(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Round(V128Lanes::F64x2, RoundingMode::Nearest));
+    }
+
+    fn visit_f64x2_abs(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Abs(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_neg(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Neg(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_sqrt(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::Sqrt(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_add(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Add(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_sub(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Sub(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_mul(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Mul(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_div(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Div(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_min(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Min(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_max(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::Max(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_pmin(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::PMin(V128Lanes::F64x2));
+    }
+
+    fn visit_f64x2_pmax(&mut self) {
+        self.emit_v128_binop(V128BinaryOp::PMax(V128Lanes::F64x2));
+    }
+
+    fn visit_i32x4_trunc_sat_f32x4_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::I32x4TruncSatF32x4S);
+    }
+
+    fn visit_i32x4_trunc_sat_f32x4_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::I32x4TruncSatF32x4U);
+    }
+
+    fn visit_f32x4_convert_i32x4_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::F32x4ConvertI32x4S);
+    }
+
+    fn visit_f32x4_convert_i32x4_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::F32x4ConvertI32x4U);
+    }
+
+    fn visit_i32x4_trunc_sat_f64x2_s_zero(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::I32x4TruncSatF64x2SZero);
+    }
+
+    fn visit_i32x4_trunc_sat_f64x2_u_zero(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::I32x4TruncSatF64x2UZero);
+    }
+
+    fn visit_f64x2_convert_low_i32x4_s(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::F64x2ConvertLowI32x4S);
+    }
+
+    fn visit_f64x2_convert_low_i32x4_u(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::F64x2ConvertLowI32x4U);
+    }
+
+    fn visit_f32x4_demote_f64x2_zero(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::F32x4DemoteF64x2Zero);
+    }
+
+    fn visit_f64x2_promote_low_f32x4(&mut self) {
+        self.emit_v128_unop(V128UnaryOp::F64x2PromoteLowF32x4);
+    }
+
+    wasmparser::for_each_operator!(def_unsupported);
+}
+
+impl<'a, 'b, 'c, M> CodeGen<'a, 'b, 'c, M>
+where
+    M: MacroAssembler,
+{
+    fn cmp_i32s(&mut self, kind: CmpKind) {
+        self.context.i32_binop(self.masm, |masm, dst, src, size| {
+            masm.cmp_with_set(src, dst, kind, size);
+        });
+    }
+
+    fn cmp_i64s(&mut self, kind: CmpKind) {
+        self.context
+            .i64_binop(self.masm, move |masm, dst, src, size| {
+                masm.cmp_with_set(src, dst, kind, size);
+            });
+    }
+    fn cmp_v128s(&mut self, kind: CmpKind, lanes: V128Lanes) {
+        let mut result = Ok(());
+        self.context
+            .binop(self.masm, OperandSize::S128, |masm, dst, src, _| {
+                result = masm.v128_cmp(dst, dst, src, kind, lanes);
+            });
+        self.handle_result(result);
+    }
+
+    fn float_cmp_v128s(&mut self, kind: FloatCmpKind, lanes: V128Lanes) {
+        let mut result = Ok(());
+        self.context
+            .binop(self.masm, OperandSize::S128, |masm, dst, src, _| {
+                result = masm.v128_float_cmp(dst, dst, src, kind, lanes);
+            });
+        self.handle_result(result);
+    }
+
+    fn emit_v128_binop(&mut self, op: V128BinaryOp) {
+        let rhs = self.context.pop_to_reg(self.masm, None);
+        let lhs = self.context.pop_to_reg(self.masm, None);
+        let result = self
+            .masm
+            .v128_binop(&mut self.context, lhs.reg, lhs.reg, rhs.reg, op);
+        self.handle_result(result);
+        self.context.free_reg(rhs);
+        self.context.stack.push(lhs.into());
+    }
+
+    fn emit_v128_unop(&mut self, op: V128UnaryOp) {
+        let typed_reg = self.context.pop_to_reg(self.masm, None);
+        let result = self.masm.v128_unop(&mut self.context, typed_reg.reg, op);
+        self.handle_result(result);
+        self.context.stack.push(typed_reg.into());
+    }
+
+    fn emit_v128_splat(&mut self, lanes: V128Lanes) {
+        let mut result = Ok(());
+        self.context
+            .convert_op(self.masm, WasmType::V128, |masm, dst, src, _| {
+                result = masm.v128_splat(src, dst, lanes);
+            });
+        self.handle_result(result);
+    }
+
+    fn emit_v128_extract_lane(&mut self, lane: u8, lanes: V128Lanes, signed: bool) {
+        let ty = match lanes {
+            V128Lanes::I8x16 | V128Lanes::I16x8 | V128Lanes::I32x4 => WasmType::I32,
+            V128Lanes::I64x2 => WasmType::I64,
+            V128Lanes::F32x4 => WasmType::F32,
+            V128Lanes::F64x2 => WasmType::F64,
+        };
+        let mut result = Ok(());
+        self.context.convert_op(self.masm, ty, |masm, dst, src, _| {
+            result = masm.v128_extract_lane(src, dst, lane, lanes, signed);
+        });
+        self.handle_result(result);
+    }
+
+    fn emit_v128_replace_lane(&mut self, lane: u8, lanes: V128Lanes) {
+        let mut result = Ok(());
+        self.context
+            .binop(self.masm, OperandSize::S128, |masm, dst, src, _| {
+                result = masm.v128_replace_lane(src, dst, lane, lanes);
+            });
+        self.handle_result(result);
+    }
+
+    /// Loads 64 bits and extends their lanes through the given operation.
+    fn emit_v128_load_extend(&mut self, memarg: &MemArg, op: V128UnaryOp) {
+        self.emit_wasm_load(memarg, WasmType::V128, OperandSize::S64, None);
+        if self.context.reachable {
+            self.emit_v128_unop(op);
+        }
+    }
+
+    /// Loads a scalar and replicates it into every lane.
+    fn emit_v128_load_splat(&mut self, memarg: &MemArg, lanes: V128Lanes) {
+        let size = lanes.lane_size();
+        if let Some(addr) = self.emit_compute_heap_address(memarg, size) {
+            let src = self.masm.address_at_reg(addr, 0);
+            let scalar = self.context.any_gpr(self.masm);
+            self.masm
+                .wasm_load(src, scalar, size, Self::lane_zero_extend(lanes));
+            let dst = self.context.reg_for_type(WasmType::V128, self.masm);
+            let result = self.masm.v128_splat(scalar, dst, lanes);
+            self.handle_result(result);
+            self.context.free_reg(scalar);
+            self.context.free_reg(addr);
+            self.context
+                .stack
+                .push(TypedReg::new(WasmType::V128, dst).into());
+        }
+    }
+
+    /// Loads a scalar into the given lane of the vector at the top of the
+    /// stack.
+    fn emit_v128_load_lane(&mut self, memarg: &MemArg, lane: u8, lanes: V128Lanes) {
+        let size = lanes.lane_size();
+        let vector = self.context.pop_to_reg(self.masm, None);
+        if let Some(addr) = self.emit_compute_heap_address(memarg, size) {
+            let src = self.masm.address_at_reg(addr, 0);
+            let scalar = self.context.any_gpr(self.masm);
+            self.masm
+                .wasm_load(src, scalar, size, Self::lane_zero_extend(lanes));
+            let result = self.masm.v128_replace_lane(scalar, vector.reg, lane, lanes);
+            self.handle_result(result);
+            self.context.free_reg(scalar);
+            self.context.free_reg(addr);
+            self.context.stack.push(vector.into());
+        } else {
+            self.context.free_reg(vector);
+        }
+    }
+
+    /// Stores the given lane of the vector at the top of the stack.
+    fn emit_v128_store_lane(&mut self, memarg: &MemArg, lane: u8, lanes: V128Lanes) {
+        let size = lanes.lane_size();
+        let vector = self.context.pop_to_reg(self.masm, None);
+        if let Some(addr) = self.emit_compute_heap_address(memarg, size) {
+            let dst = self.masm.address_at_reg(addr, 0);
+            let scalar = self.context.any_gpr(self.masm);
+            let result = self
+                .masm
+                .v128_extract_lane(vector.reg, scalar, lane, lanes, false);
+            self.handle_result(result);
+            self.masm.wasm_store(scalar, dst, size);
+            self.context.free_reg(scalar);
+            self.context.free_reg(addr);
+        }
+        self.context.free_reg(vector);
+    }
+
+    /// Emits a `br_if` to a frame expecting values in stack slots.
+    ///
+    /// Placing the values in their expected location might require
+    /// emitting code that must only execute if the branch is taken, so the
+    /// branch is emitted out of line: the condition skips over an
+    /// unconditional jump to the target frame.
+    fn emit_br_if_out_of_line(&mut self, index: usize) {
+        let top = self.context.pop_to_reg(self.masm, None);
+        self.context.spill(self.masm);
+        let skip = self.masm.get_label();
+        self.masm.branch(
+            CmpKind::Eq,
+            top.reg.into(),
+            top.reg.into(),
+            skip,
+            OperandSize::S32,
+        );
+        self.context.free_reg(top);
+
+        let (stack, sp_offset) = (self.context.stack.clone(), self.masm.sp_offset());
+        self.context.unconditional_jump(
+            &mut self.control_frames[index],
+            self.masm,
+            |masm, cx, frame| {
+                frame.pop_target_values(cx, masm);
+            },
+        );
+
+        // Restore the state prior to the jump for the fallthrough sequence.
+        self.context.stack = stack;
+        self.masm.reset_stack_pointer(sp_offset);
+        self.context.reachable = true;
+        self.masm.bind(skip);
+    }
+
+    /// Emits a `br_table` in which at least one of the targets expects
+    /// values in stack slots.
+    ///
+    /// Each of the jump table labels emits its own unconditional jump to
+    /// the corresponding target, placing the values in the location
+    /// expected by each target.
+    fn emit_br_table_out_of_line(&mut self, targets: &BrTable<'a>, labels: &[MachLabel]) {
+        let index = self.context.pop_to_reg(self.masm, None);
+        let tmp = self.context.any_gpr(self.masm);
+        self.context.spill(self.masm);
+        self.masm.jmp_table(labels, index.into(), tmp);
+        self.context.free_reg(index);
+        self.context.free_reg(tmp);
+
+        let (stack, sp_offset) = (self.context.stack.clone(), self.masm.sp_offset());
+        for (t, l) in targets
+            .targets()
+            .into_iter()
+            .chain(std::iter::once(Ok(targets.default())))
+            .zip(labels.iter())
+        {
+            let control_index = control_index(t.unwrap(), self.control_frames.len());
+
+            self.masm.bind(*l);
+            self.context.stack = stack.clone();
+            self.masm.reset_stack_pointer(sp_offset);
+            self.context.reachable = true;
+            self.context.unconditional_jump(
+                &mut self.control_frames[control_index],
+                self.masm,
+                |masm, cx, frame| {
+                    frame.pop_target_values(cx, masm);
+                },
+            );
+        }
+    }
+
+    /// The extension needed to load a lane of the given kind into a general
+    /// purpose register.
+    fn lane_zero_extend(lanes: V128Lanes) -> Option<ExtendKind> {
+        match lanes {
+            V128Lanes::I8x16 => Some(ExtendKind::I32Extend8U),
+            V128Lanes::I16x8 => Some(ExtendKind::I32Extend16U),
+            _ => None,
+        }
     }
 }
 
@@ -1190,6 +3295,7 @@ impl From<WasmType> for OperandSize {
         match ty {
             WasmType::I32 | WasmType::F32 => OperandSize::S32,
             WasmType::I64 | WasmType::F64 => OperandSize::S64,
+            WasmType::V128 => OperandSize::S128,
             WasmType::Ref(rt) => {
                 match rt.heap_type {
                     // TODO: Harcoded size, assuming 64-bit support only. Once
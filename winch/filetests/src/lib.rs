@@ -115,7 +115,7 @@ mod test {
 
         let binding = body_inputs
             .into_iter()
-            .map(|func| compile(&isa, &types, &translation, func).join("\n"))
+            .map(|func| compile(&isa, &types, &translation, &tunables, func).join("\n"))
             .collect::<Vec<String>>()
             .join("\n\n");
         let actual = binding.as_str();
@@ -150,6 +150,7 @@ mod test {
         isa: &Box<dyn TargetIsa>,
         module_types: &ModuleTypes,
         translation: &ModuleTranslation,
+        tunables: &Tunables,
         f: (DefinedFuncIndex, FunctionBodyData<'_>),
     ) -> Vec<String> {
         let module = &translation.module;
@@ -165,10 +166,12 @@ mod test {
         let mut validator = validator.into_validator(Default::default());
         let buffer = isa
             .compile_function(
+                f.0,
                 &sig,
                 &body,
                 translation,
                 module_types,
+                tunables,
                 &mut builtins,
                 &mut validator,
             )
@@ -41,7 +41,7 @@ pub fn run(opt: &Options) -> Result<()> {
 
     body_inputs
         .into_iter()
-        .try_for_each(|func| compile(&isa, &translation, &types, func))?;
+        .try_for_each(|func| compile(&isa, &translation, &types, &tunables, func))?;
 
     Ok(())
 }
@@ -50,6 +50,7 @@ fn compile(
     isa: &Box<dyn TargetIsa>,
     translation: &ModuleTranslation,
     module_types: &ModuleTypes,
+    tunables: &Tunables,
     f: (DefinedFuncIndex, FunctionBodyData<'_>),
 ) -> Result<()> {
     let index = translation.module.func_index(f.0);
@@ -62,10 +63,12 @@ fn compile(
     let mut validator = validator.into_validator(Default::default());
     let buffer = isa
         .compile_function(
+            f.0,
             &sig,
             &body,
             translation,
             module_types,
+            tunables,
             &mut builtins,
             &mut validator,
         )